@@ -0,0 +1,19 @@
+// GENERATED CODE! DO NOT MODIFY BY HAND!
+
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ApprovalKind } from "./ApprovalKind";
+import type { ReviewDecision } from "./ReviewDecision";
+
+/**
+ * Recorded in the rollout when the user decides an approval request, so the
+ * time turns spend waiting on approvals can be measured afterwards.
+ */
+export type ApprovalDecisionEvent = { 
+/**
+ * Call id of the request that was decided.
+ */
+call_id: string, turn_id: string, kind: ApprovalKind, decision: ReviewDecision, 
+/**
+ * Milliseconds from the request to the decision.
+ */
+wait_ms: bigint, };
@@ -0,0 +1,24 @@
+// GENERATED CODE! DO NOT MODIFY BY HAND!
+
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One stored pattern that resembles a pattern query.
+ */
+export type PatternQueryMatch = { pattern_id: string, trigger: string, invariant: string, 
+/**
+ * The response to repeat, or to avoid when `anti_pattern` is set.
+ */
+best_response: string, 
+/**
+ * An event the pattern did not hold for, when one was recorded.
+ */
+counterexample?: string, anti_pattern: boolean, 
+/**
+ * Weighted total of the text, domain, and outcome scores (0-1).
+ */
+score: number, 
+/**
+ * The component scores behind `score`, as `text=0.91 domain=0.40 ...`.
+ */
+rationale: string, };
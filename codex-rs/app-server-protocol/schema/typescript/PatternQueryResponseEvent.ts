@@ -0,0 +1,21 @@
+// GENERATED CODE! DO NOT MODIFY BY HAND!
+
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PatternQueryMatch } from "./PatternQueryMatch";
+
+/**
+ * Response payload for `Op::QueryPatterns`.
+ */
+export type PatternQueryResponseEvent = { 
+/**
+ * Best match first.
+ */
+matches: Array<PatternQueryMatch>, 
+/**
+ * Patterns that matched the query at all, across every page.
+ */
+total_candidates: number, 
+/**
+ * Offset of the next page, absent when these are the last matches.
+ */
+next_offset?: number, };
@@ -0,0 +1,17 @@
+// GENERATED CODE! DO NOT MODIFY BY HAND!
+
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A [`RequestUserInputEvent`] that will no longer be answered, such as a
+ * prompt that timed out. Clients should dismiss it.
+ */
+export type RequestUserInputClosedEvent = { 
+/**
+ * Responses API call id of the closed request.
+ */
+call_id: string, 
+/**
+ * Turn ID that the closed request belongs to.
+ */
+turn_id: string, };
@@ -0,0 +1,13 @@
+// GENERATED CODE! DO NOT MODIFY BY HAND!
+
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A stored pattern that has gone unconfirmed for a while.
+ */
+export type StalePatternSummary = { pattern_id: string, trigger: string, best_response: string, 
+/**
+ * When the pattern was last re-confirmed, or created when it never was,
+ * in seconds since the epoch.
+ */
+last_confirmed_at: number, };
@@ -0,0 +1,17 @@
+// GENERATED CODE! DO NOT MODIFY BY HAND!
+
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { StalePatternSummary } from "./StalePatternSummary";
+
+/**
+ * Payload for `EventMsg::StalePatterns`.
+ */
+export type StalePatternsEvent = { 
+/**
+ * Days without confirmation after which a pattern is listed.
+ */
+stale_after_days: bigint, 
+/**
+ * Least recently confirmed first.
+ */
+patterns: Array<StalePatternSummary>, };
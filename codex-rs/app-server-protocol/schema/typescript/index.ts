@@ -17,6 +17,8 @@ export type { AgentStatus } from "./AgentStatus";
 export type { ApplyPatchApprovalParams } from "./ApplyPatchApprovalParams";
 export type { ApplyPatchApprovalRequestEvent } from "./ApplyPatchApprovalRequestEvent";
 export type { ApplyPatchApprovalResponse } from "./ApplyPatchApprovalResponse";
+export type { ApprovalDecisionEvent } from "./ApprovalDecisionEvent";
+export type { ApprovalKind } from "./ApprovalKind";
 export type { ArchiveConversationParams } from "./ArchiveConversationParams";
 export type { ArchiveConversationResponse } from "./ArchiveConversationResponse";
 export type { AskForApproval } from "./AskForApproval";
@@ -126,6 +128,8 @@ export type { NewConversationResponse } from "./NewConversationResponse";
 export type { ParsedCommand } from "./ParsedCommand";
 export type { PatchApplyBeginEvent } from "./PatchApplyBeginEvent";
 export type { PatchApplyEndEvent } from "./PatchApplyEndEvent";
+export type { PatternQueryMatch } from "./PatternQueryMatch";
+export type { PatternQueryResponseEvent } from "./PatternQueryResponseEvent";
 export type { Personality } from "./Personality";
 export type { PlanDeltaEvent } from "./PlanDeltaEvent";
 export type { PlanItem } from "./PlanItem";
@@ -147,6 +151,7 @@ export type { RemoteSkillSummary } from "./RemoteSkillSummary";
 export type { RemoveConversationListenerParams } from "./RemoveConversationListenerParams";
 export type { RemoveConversationSubscriptionResponse } from "./RemoveConversationSubscriptionResponse";
 export type { RequestId } from "./RequestId";
+export type { RequestUserInputClosedEvent } from "./RequestUserInputClosedEvent";
 export type { RequestUserInputEvent } from "./RequestUserInputEvent";
 export type { RequestUserInputQuestion } from "./RequestUserInputQuestion";
 export type { RequestUserInputQuestionOption } from "./RequestUserInputQuestionOption";
@@ -184,6 +189,8 @@ export type { SkillMetadata } from "./SkillMetadata";
 export type { SkillScope } from "./SkillScope";
 export type { SkillToolDependency } from "./SkillToolDependency";
 export type { SkillsListEntry } from "./SkillsListEntry";
+export type { StalePatternSummary } from "./StalePatternSummary";
+export type { StalePatternsEvent } from "./StalePatternsEvent";
 export type { StepStatus } from "./StepStatus";
 export type { StreamErrorEvent } from "./StreamErrorEvent";
 export type { SubAgentSource } from "./SubAgentSource";
@@ -0,0 +1,439 @@
+//! `codex capture timelines`: prior→posterior belief series for every
+//! hypothesis recorded by the capture tool.
+//!
+//! `codex capture import`: store existing postmortems as captures.
+//!
+//! `codex capture list` and `codex capture search`: browse stored captures.
+
+use anyhow::Context;
+use clap::Parser;
+use clap::ValueEnum;
+use codex_core::ARCHIVED_SESSIONS_SUBDIR;
+use codex_core::BeliefPoint;
+use codex_core::SESSIONS_SUBDIR;
+use codex_core::config::find_codex_home;
+use codex_core::covenant::load_covenant;
+use codex_core::probability_timelines;
+use codex_core::store_query::Filter;
+use codex_core::store_query::filter_records;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use codex_state::AuditAction;
+use codex_state::CovenantEvent;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::capture_import::covenant_event_from_markdown;
+use crate::capture_import::parse_markdown_capture;
+use crate::capture_list::CaptureSummary;
+use crate::capture_list::events_with_tags;
+use crate::capture_list::list_captures;
+use crate::capture_list::search_captures;
+use crate::compile_cmd::unix_timestamp;
+use crate::covenant_cmd::load_all_events;
+use crate::covenant_store::StoreArgs;
+use crate::covenant_store::open_covenant_store;
+use crate::stats::collect_rollout_paths;
+use crate::stats::read_rollout;
+use crate::stats::rollout_stem;
+
+/// Name of the tool whose outputs carry capture records.
+const CAPTURE_TOOL_NAME: &str = "capture";
+
+#[derive(Debug, Parser)]
+pub struct CaptureCli {
+    #[clap(flatten)]
+    pub store: StoreArgs,
+
+    #[command(subcommand)]
+    pub subcommand: CaptureSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum CaptureSubcommand {
+    /// Export per-hypothesis probability updates from recorded captures.
+    Timelines(TimelinesArgs),
+    /// Import a postmortem as a capture in the covenant store.
+    Import(ImportArgs),
+    /// List stored captures, newest first.
+    List(ListArgs),
+    /// Rank stored captures by how well their intent and outcomes match a query.
+    Search(SearchArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct TimelinesArgs {
+    /// Directory containing rollout files. Defaults to `$CODEX_HOME/sessions`.
+    #[arg(long, value_name = "DIR")]
+    pub sessions_dir: Option<PathBuf>,
+
+    /// Also include archived sessions from `$CODEX_HOME/archived_sessions`.
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value_t = TimelineFormat::Json)]
+    pub format: TimelineFormat,
+
+    /// File to write. Defaults to stdout.
+    #[arg(short = 'o', long = "out", value_name = "FILE")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct ImportArgs {
+    /// Postmortem to import.
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Format of the postmortem.
+    #[arg(long, value_enum, default_value_t = ImportFormat::Markdown)]
+    pub format: ImportFormat,
+}
+
+#[derive(Debug, Parser)]
+pub struct ListArgs {
+    /// Maximum number of captures to print.
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Only captures carrying this tag; repeat to require several.
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Only captures passing this filter, such as
+    /// `resolved = false AND created_at > 2024-06-01`.
+    #[arg(long = "where", value_name = "FILTER")]
+    pub filter: Option<String>,
+
+    /// Also read events archived by `codex covenant gc`.
+    #[arg(long, default_value_t = false)]
+    pub include_archived: bool,
+
+    /// Print the captures as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct SearchArgs {
+    /// Words to look for in capture intents and outcomes.
+    #[arg(value_name = "QUERY")]
+    pub query: String,
+
+    /// Maximum number of captures to print.
+    #[arg(long, default_value_t = 10)]
+    pub limit: usize,
+
+    /// Only captures carrying this tag; repeat to require several.
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Only captures passing this filter, such as
+    /// `resolved = false AND created_at > 2024-06-01`.
+    #[arg(long = "where", value_name = "FILTER")]
+    pub filter: Option<String>,
+
+    /// Also read events archived by `codex covenant gc`.
+    #[arg(long, default_value_t = false)]
+    pub include_archived: bool,
+
+    /// Print the captures as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// Intent, Hypotheses, Tests, Outcome, and Pattern headings.
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TimelineFormat {
+    /// One object per hypothesis with its ordered points.
+    Json,
+    /// One row per probability update.
+    Csv,
+}
+
+/// The belief history of one hypothesis in one capture call.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct CaptureTimeline {
+    pub(crate) session_id: String,
+    /// Tool call id of the capture that recorded the hypothesis.
+    pub(crate) capture_id: String,
+    pub(crate) hypothesis_id: String,
+    pub(crate) statement: String,
+    pub(crate) points: Vec<BeliefPoint>,
+}
+
+pub async fn run_capture(cli: CaptureCli) -> anyhow::Result<()> {
+    match cli.subcommand {
+        CaptureSubcommand::Timelines(args) => run_timelines(args),
+        CaptureSubcommand::Import(args) => run_import(args, &cli.store).await,
+        CaptureSubcommand::List(args) => {
+            let filter = parse_event_filter(args.filter.as_deref())?;
+            let store = cli.store.resolve(find_codex_home()?.as_path()).await?;
+            let events = filter_records(
+                events_with_tags(
+                    load_all_events(&store, args.include_archived).await?,
+                    &args.tags,
+                )?,
+                filter.as_ref(),
+            );
+            print_captures(&list_captures(&events, args.limit), args.json)
+        }
+        CaptureSubcommand::Search(args) => {
+            anyhow::ensure!(!args.query.trim().is_empty(), "the search query is empty");
+            let filter = parse_event_filter(args.filter.as_deref())?;
+            let store = cli.store.resolve(find_codex_home()?.as_path()).await?;
+            let events = filter_records(
+                events_with_tags(
+                    load_all_events(&store, args.include_archived).await?,
+                    &args.tags,
+                )?,
+                filter.as_ref(),
+            );
+            print_captures(
+                &search_captures(&events, args.query.as_str(), args.limit),
+                args.json,
+            )
+        }
+    }
+}
+
+fn parse_event_filter(filter: Option<&str>) -> anyhow::Result<Option<Filter>> {
+    filter
+        .map(|filter| Filter::parse::<CovenantEvent>(filter).context("invalid --where filter"))
+        .transpose()
+}
+
+/// One line per capture: date, score when searching, event id, intent, and
+/// the last outcome (or `open`).
+fn print_captures(captures: &[CaptureSummary], json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(captures)?);
+        return Ok(());
+    }
+    if captures.is_empty() {
+        println!("no captures found");
+    }
+    for capture in captures {
+        let score = capture
+            .score
+            .map(|score| format!("{score:.2}  "))
+            .unwrap_or_default();
+        let outcome = capture.outcome.as_deref().unwrap_or("open");
+        println!(
+            "{}  {score}{}  {} -> {outcome}",
+            capture.date, capture.event_id, capture.intent
+        );
+    }
+    Ok(())
+}
+
+async fn run_import(args: ImportArgs, store: &StoreArgs) -> anyhow::Result<()> {
+    let contents = std::fs::read_to_string(&args.file)
+        .with_context(|| format!("failed to read {}", args.file.display()))?;
+    let capture = match args.format {
+        ImportFormat::Markdown => parse_markdown_capture(contents.as_str()),
+    }
+    .with_context(|| format!("failed to import {}", args.file.display()))?;
+    capture
+        .flow
+        .validate()
+        .with_context(|| format!("invalid capture in {}", args.file.display()))?;
+
+    let created_at = unix_timestamp();
+    let event = covenant_event_from_markdown(&capture, Uuid::new_v4().to_string(), created_at);
+    let store = store.resolve(find_codex_home()?.as_path()).await?;
+    let runtime = open_covenant_store(&store).await?;
+    let covenant_version = load_covenant(std::env::current_dir()?.as_path())
+        .await
+        .map(|covenant| covenant.version)
+        .unwrap_or_else(|_| "missing".to_string());
+    let mut tx = runtime.begin_covenant_transaction().await?;
+    tx.insert_covenant_event(&event).await?;
+    tx.insert_audit_action(&AuditAction {
+        timestamp: created_at,
+        actor: "user".to_string(),
+        action_type: "capture.import".to_string(),
+        scope: "cli".to_string(),
+        covenant_version,
+        event_id: Some(event.id.clone()),
+        intent_id: event.intent.as_ref().map(|intent| intent.id.clone()),
+    })
+    .await?;
+    tx.commit().await?;
+
+    println!(
+        "imported {} as event {} ({} hypotheses, {} tests, {} outcomes)",
+        args.file.display(),
+        event.id,
+        event.hypotheses.len(),
+        event.tests.len(),
+        event.outcomes.len()
+    );
+    if !capture.unmapped_sections.is_empty() {
+        println!(
+            "unmapped sections: {}",
+            capture.unmapped_sections.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn run_timelines(args: TimelinesArgs) -> anyhow::Result<()> {
+    let mut dirs = Vec::new();
+    match args.sessions_dir {
+        Some(dir) => dirs.push(dir),
+        None => dirs.push(find_codex_home()?.join(SESSIONS_SUBDIR)),
+    }
+    if args.all {
+        dirs.push(find_codex_home()?.join(ARCHIVED_SESSIONS_SUBDIR));
+    }
+    let mut timelines = Vec::new();
+    for dir in &dirs {
+        for path in collect_rollout_paths(dir.as_path())? {
+            timelines.extend(load_timelines(path.as_path())?);
+        }
+    }
+
+    let rendered = match args.format {
+        TimelineFormat::Json => format!("{}\n", serde_json::to_string_pretty(&timelines)?),
+        TimelineFormat::Csv => render_csv(&timelines),
+    };
+    match args.out {
+        Some(path) => std::fs::write(&path, rendered)
+            .with_context(|| format!("failed to write {}", path.display()))?,
+        None => print!("{rendered}"),
+    }
+    Ok(())
+}
+
+/// Timelines for every capture call in one rollout file, in recorded order.
+/// Outputs that are not capture records (for example a cancelled capture)
+/// are skipped.
+pub(crate) fn load_timelines(path: &Path) -> anyhow::Result<Vec<CaptureTimeline>> {
+    let contents = read_rollout(path)?;
+    let mut session_id = rollout_stem(path);
+    let mut capture_calls = HashSet::new();
+    let mut timelines = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let rollout_line: RolloutLine = serde_json::from_str(line)
+            .with_context(|| format!("failed to parse {}:{}", path.display(), index + 1))?;
+        match rollout_line.item {
+            RolloutItem::SessionMeta(meta_line) => session_id = meta_line.meta.id.to_string(),
+            RolloutItem::ResponseItem(ResponseItem::FunctionCall { name, call_id, .. }) => {
+                if name == CAPTURE_TOOL_NAME {
+                    capture_calls.insert(call_id);
+                }
+            }
+            RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput { call_id, output }) => {
+                if !capture_calls.contains(&call_id) {
+                    continue;
+                }
+                let Some(text) = output.body.to_text() else {
+                    continue;
+                };
+                let Ok(hypotheses) = probability_timelines(text.as_str()) else {
+                    continue;
+                };
+                timelines.extend(hypotheses.into_iter().map(|hypothesis| CaptureTimeline {
+                    session_id: session_id.clone(),
+                    capture_id: call_id.clone(),
+                    hypothesis_id: hypothesis.hypothesis_id,
+                    statement: hypothesis.statement,
+                    points: hypothesis.points,
+                }));
+            }
+            RolloutItem::ResponseItem(_)
+            | RolloutItem::TurnContext(_)
+            | RolloutItem::EventMsg(_)
+            | RolloutItem::Compacted(_) => {}
+        }
+    }
+    Ok(timelines)
+}
+
+/// One row per point, ready for plotting tools that expect long-form data.
+pub(crate) fn render_csv(timelines: &[CaptureTimeline]) -> String {
+    let mut csv =
+        String::from("session_id,capture_id,hypothesis_id,step,evidence_test_id,prior,posterior\n");
+    for timeline in timelines {
+        for point in &timeline.points {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                csv_field(&timeline.session_id),
+                csv_field(&timeline.capture_id),
+                csv_field(&timeline.hypothesis_id),
+                point.step,
+                csv_field(&point.evidence_test_id),
+                point.prior,
+                point.posterior
+            ));
+        }
+    }
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn capture_outputs_become_csv_rows() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("rollout-test.jsonl");
+        let capture_output = serde_json::json!({
+            "hypotheses": [{
+                "id": "H1",
+                "statement": "bluetooth output not selected",
+                "probability_updates": [
+                    {"hypothesis_id": "H1", "prior": 0.5, "posterior": 0.8, "evidence_test_id": "T1"},
+                    {"hypothesis_id": "H1", "prior": 0.8, "posterior": 0.2, "evidence_test_id": "T2, retry"}
+                ]
+            }]
+        })
+        .to_string();
+        let lines = [
+            r#"{"timestamp":"2026-01-02T03:04:06.000Z","type":"response_item","payload":{"type":"function_call","name":"capture","arguments":"{}","call_id":"c1"}}"#.to_string(),
+            serde_json::json!({
+                "timestamp": "2026-01-02T03:04:07.000Z",
+                "type": "response_item",
+                "payload": {"type": "function_call_output", "call_id": "c1", "output": capture_output},
+            })
+            .to_string(),
+            r#"{"timestamp":"2026-01-02T03:04:08.000Z","type":"response_item","payload":{"type":"function_call","name":"shell","arguments":"{}","call_id":"c2"}}"#.to_string(),
+            r#"{"timestamp":"2026-01-02T03:04:09.000Z","type":"response_item","payload":{"type":"function_call_output","call_id":"c2","output":"{\"hypotheses\":[]}"}}"#.to_string(),
+        ];
+        std::fs::write(&path, lines.join("\n")).expect("write rollout");
+
+        let timelines = load_timelines(&path).expect("load timelines");
+
+        assert_eq!(
+            render_csv(&timelines),
+            "session_id,capture_id,hypothesis_id,step,evidence_test_id,prior,posterior\n\
+             rollout-test,c1,H1,0,T1,0.5,0.8\n\
+             rollout-test,c1,H1,1,\"T2, retry\",0.8,0.2\n"
+        );
+    }
+}
@@ -0,0 +1,426 @@
+//! `codex capture import`: build captures from existing markdown postmortems
+//! and store them as covenant events, as the capture tool does.
+
+use codex_core::patterns::Outcome as OutcomeKind;
+use codex_intent_patterns::CaptureFlow;
+use codex_intent_patterns::CapturePayload;
+use codex_intent_patterns::CaptureTest;
+use codex_intent_patterns::CompileOptions;
+use codex_intent_patterns::CompiledPattern;
+use codex_intent_patterns::Hypothesis;
+use codex_intent_patterns::IntentToken;
+use codex_intent_patterns::Outcome;
+use codex_intent_patterns::RecordId;
+use codex_protocol::test_status::TestStatus;
+use codex_state::CovenantEvent;
+use codex_state::CovenantEvidence;
+use codex_state::CovenantHypothesis;
+use codex_state::CovenantIntent;
+use codex_state::CovenantOutcome;
+use codex_state::CovenantTest;
+use std::collections::HashMap;
+
+/// A postmortem parsed into a capture flow.
+#[derive(Debug, Clone)]
+pub(crate) struct MarkdownCapture {
+    /// The leading `#` heading, when it is not one of the capture sections.
+    pub(crate) title: Option<String>,
+    pub(crate) flow: CaptureFlow,
+    /// Headings that do not map to a capture section, in document order.
+    pub(crate) unmapped_sections: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Section {
+    Intent,
+    Hypotheses,
+    Tests,
+    Outcome,
+    Pattern,
+}
+
+impl Section {
+    fn from_heading(heading: &str) -> Option<Self> {
+        match heading.trim_end_matches(':').trim().to_lowercase().as_str() {
+            "intent" => Some(Self::Intent),
+            "hypothesis" | "hypotheses" => Some(Self::Hypotheses),
+            "test" | "tests" => Some(Self::Tests),
+            "outcome" | "outcomes" => Some(Self::Outcome),
+            "pattern" | "patterns" => Some(Self::Pattern),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a postmortem whose `Intent`, `Hypotheses`, `Tests`, `Outcome`, and
+/// `Pattern` headings hold the capture. Each list item in a section is one
+/// entry; a section without list items has one entry per paragraph. Test `n`
+/// is linked to hypothesis `n` (or the last hypothesis), outcomes to the last
+/// test, and patterns to the last outcome.
+pub(crate) fn parse_markdown_capture(markdown: &str) -> anyhow::Result<MarkdownCapture> {
+    let mut title = None;
+    let mut unmapped_sections: Vec<String> = Vec::new();
+    let mut sections: HashMap<Section, Vec<&str>> = HashMap::new();
+    let mut current = None;
+    let mut seen_heading = false;
+    let mut in_fence = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some((level, heading)) = markdown_heading(trimmed) {
+            current = Section::from_heading(heading);
+            if current.is_none() {
+                if level == 1 && !seen_heading {
+                    title = Some(heading.to_string());
+                } else if !unmapped_sections.iter().any(|section| section == heading) {
+                    unmapped_sections.push(heading.to_string());
+                }
+            }
+            seen_heading = true;
+            continue;
+        }
+        if let Some(section) = current {
+            sections.entry(section).or_default().push(line);
+        }
+    }
+    let mut entries = |section| section_entries(&sections.remove(&section).unwrap_or_default());
+
+    let intent = entries(Section::Intent).join(" ");
+    anyhow::ensure!(!intent.is_empty(), "no Intent section found");
+    let hypotheses = entries(Section::Hypotheses);
+    let tests = entries(Section::Tests);
+    let outcomes = entries(Section::Outcome);
+    let patterns = entries(Section::Pattern);
+
+    let mut flow = CaptureFlow::new(IntentToken { text: intent });
+    let hypothesis_ids = hypotheses
+        .into_iter()
+        .map(|summary| flow.add_hypothesis(Hypothesis { summary }))
+        .collect::<Vec<_>>();
+    let mut test_ids = Vec::new();
+    for (index, description) in tests.into_iter().enumerate() {
+        let Some(hypothesis_id) = hypothesis_ids
+            .get(index)
+            .or_else(|| hypothesis_ids.last())
+            .copied()
+        else {
+            anyhow::bail!("the Tests section needs a Hypotheses section to link to");
+        };
+        test_ids.push(flow.add_test(hypothesis_id, CaptureTest { description }));
+    }
+    let mut last_outcome: Option<(RecordId, Outcome)> = None;
+    for summary in outcomes {
+        let Some(test_id) = test_ids.last().copied() else {
+            anyhow::bail!("the Outcome section needs a Tests section to link to");
+        };
+        let success = OutcomeKind::from_text(summary.as_str()) == OutcomeKind::Success;
+        let outcome = Outcome { summary, success };
+        let outcome_id = flow.add_outcome(test_id, outcome.clone());
+        last_outcome = Some((outcome_id, outcome));
+    }
+    let idf = flow.intent_idf();
+    for text in patterns {
+        let Some((outcome_id, outcome)) = &last_outcome else {
+            anyhow::bail!("the Pattern section needs an Outcome section to link to");
+        };
+        let pattern = CompiledPattern::compile_with(
+            &IntentToken { text },
+            outcome,
+            &idf,
+            &CompileOptions::default(),
+        );
+        flow.add_compiled_pattern(*outcome_id, pattern);
+    }
+
+    Ok(MarkdownCapture {
+        title,
+        flow,
+        unmapped_sections,
+    })
+}
+
+/// Convert an imported capture into a covenant event. Hypotheses share the
+/// probability equally, and test results are classified from their text.
+/// Pattern entries have no place in an event; `codex compile` derives patterns
+/// from the stored events instead.
+pub(crate) fn covenant_event_from_markdown(
+    capture: &MarkdownCapture,
+    event_id: String,
+    created_at: i64,
+) -> CovenantEvent {
+    let records = capture.flow.records();
+    let hypothesis_count = records
+        .iter()
+        .filter(|record| matches!(record.payload, CapturePayload::Hypothesis(_)))
+        .count();
+    let evidence_ref = |test_number: usize| format!("capture://{event_id}/T{test_number}");
+
+    let mut intent = None;
+    let mut hypotheses = Vec::new();
+    let mut tests = Vec::new();
+    let mut outcomes = Vec::new();
+    let mut hypothesis_keys: HashMap<RecordId, String> = HashMap::new();
+    let mut test_numbers: HashMap<RecordId, usize> = HashMap::new();
+    for record in records {
+        match &record.payload {
+            CapturePayload::IntentToken(token) => {
+                intent = Some(CovenantIntent {
+                    id: format!("{event_id}:intent"),
+                    goal: token.text.clone(),
+                    constraints: String::new(),
+                    success_signal: String::new(),
+                    confidence: 0.0,
+                });
+            }
+            CapturePayload::Hypothesis(hypothesis) => {
+                let id = format!("{event_id}:H{}", hypotheses.len() + 1);
+                hypothesis_keys.insert(record.id, id.clone());
+                hypotheses.push(CovenantHypothesis {
+                    id,
+                    model_type: hypothesis.summary.clone(),
+                    probability: 1.0 / hypothesis_count as f64,
+                    falsifiers: Vec::new(),
+                    domain_signature: "[]".to_string(),
+                });
+            }
+            CapturePayload::Test(test) => {
+                let number = tests.len() + 1;
+                test_numbers.insert(record.id, number);
+                tests.push(CovenantTest {
+                    id: format!("{event_id}:T{number}"),
+                    hypothesis_id: record
+                        .links
+                        .first()
+                        .and_then(|link| hypothesis_keys.get(link))
+                        .cloned()
+                        .unwrap_or_default(),
+                    description: test.description.clone(),
+                    result: TestStatus::from_text(test.description.as_str()),
+                    evidence_ref: evidence_ref(number),
+                });
+            }
+            CapturePayload::Outcome(outcome) => {
+                let evidence_refs = record
+                    .links
+                    .iter()
+                    .filter_map(|link| test_numbers.get(link))
+                    .map(|number| evidence_ref(*number))
+                    .collect::<Vec<_>>();
+                outcomes.push(CovenantOutcome {
+                    id: format!("{event_id}:O{}", outcomes.len() + 1),
+                    summary: outcome.summary.clone(),
+                    outcome: OutcomeKind::from_text(outcome.summary.as_str()),
+                    evidence: evidence_refs.iter().map(CovenantEvidence::test).collect(),
+                    evidence_refs,
+                });
+            }
+            CapturePayload::CompiledPattern(_) => {}
+        }
+    }
+
+    let description = capture
+        .title
+        .clone()
+        .or_else(|| intent.as_ref().map(|intent| intent.goal.clone()))
+        .unwrap_or_default();
+    CovenantEvent {
+        id: event_id,
+        created_at,
+        description,
+        domain_signature: "[]".to_string(),
+        intent,
+        hypotheses,
+        tests,
+        outcomes,
+        tags: Vec::new(),
+        session_id: None,
+        rollout_path: None,
+    }
+}
+
+/// The level and text of an ATX heading such as `## Tests`.
+fn markdown_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.len() - line.trim_start_matches('#').len();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let heading = rest.trim().trim_end_matches('#').trim_end();
+    (!heading.is_empty()).then_some((level, heading))
+}
+
+/// One entry per list item, or per paragraph when the section has no list.
+/// Wrapped lines continue the entry they follow.
+fn section_entries(lines: &[&str]) -> Vec<String> {
+    let has_items = lines.iter().any(|line| list_item(line.trim()).is_some());
+    let mut entries: Vec<String> = Vec::new();
+    let mut open = false;
+    for line in lines {
+        let text = line.trim();
+        if text.is_empty() {
+            // Blank lines end paragraphs; list items continue across them.
+            open &= has_items;
+            continue;
+        }
+        if let Some(item) = list_item(text) {
+            entries.push(item.to_string());
+            open = true;
+        } else if open && let Some(entry) = entries.last_mut() {
+            entry.push(' ');
+            entry.push_str(text);
+        } else if !has_items {
+            entries.push(text.to_string());
+            open = true;
+        }
+    }
+    entries.retain(|entry| !entry.is_empty());
+    entries
+}
+
+/// The text of a `-`, `*`, `+`, or numbered list item.
+fn list_item(text: &str) -> Option<&str> {
+    if let Some(item) = text
+        .strip_prefix(['-', '*', '+'])
+        .and_then(|rest| rest.strip_prefix(' '))
+    {
+        return Some(item.trim());
+    }
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    text[digits..]
+        .strip_prefix(['.', ')'])
+        .and_then(|rest| rest.strip_prefix(' '))
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const POSTMORTEM: &str = "\
+# Build host ran out of disk
+
+## Intent
+Keep nightly builds green.
+
+## Timeline
+- 02:00 builds start failing
+
+## Hypotheses
+- Logs filled the disk
+- Docker images piled up
+
+## Tests
+1. Checked log sizes: 40G of logs,
+   mostly from the test runner.
+2. Pruned docker images, builds still failing
+```sh
+# not a heading
+```
+
+## Outcome
+Fixed after rotating logs.
+
+## Pattern
+- Disk full on build host: rotate logs first.
+
+## Action items
+- Add a disk alert
+";
+
+    #[test]
+    fn postmortem_sections_become_a_covenant_event() {
+        let capture = parse_markdown_capture(POSTMORTEM).expect("parse postmortem");
+        capture.flow.validate().expect("valid flow");
+
+        assert_eq!(
+            (capture.title.clone(), capture.unmapped_sections.clone()),
+            (
+                Some("Build host ran out of disk".to_string()),
+                vec!["Timeline".to_string(), "Action items".to_string()]
+            )
+        );
+        assert_eq!(
+            covenant_event_from_markdown(&capture, "evt".to_string(), 7),
+            CovenantEvent {
+                id: "evt".to_string(),
+                created_at: 7,
+                description: "Build host ran out of disk".to_string(),
+                domain_signature: "[]".to_string(),
+                intent: Some(CovenantIntent {
+                    id: "evt:intent".to_string(),
+                    goal: "Keep nightly builds green.".to_string(),
+                    constraints: String::new(),
+                    success_signal: String::new(),
+                    confidence: 0.0,
+                }),
+                hypotheses: vec![
+                    CovenantHypothesis {
+                        id: "evt:H1".to_string(),
+                        model_type: "Logs filled the disk".to_string(),
+                        probability: 0.5,
+                        falsifiers: Vec::new(),
+                        domain_signature: "[]".to_string(),
+                    },
+                    CovenantHypothesis {
+                        id: "evt:H2".to_string(),
+                        model_type: "Docker images piled up".to_string(),
+                        probability: 0.5,
+                        falsifiers: Vec::new(),
+                        domain_signature: "[]".to_string(),
+                    },
+                ],
+                tests: vec![
+                    CovenantTest {
+                        id: "evt:T1".to_string(),
+                        hypothesis_id: "evt:H1".to_string(),
+                        description: "Checked log sizes: 40G of logs, mostly from the test runner."
+                            .to_string(),
+                        result: TestStatus::Inconclusive,
+                        evidence_ref: "capture://evt/T1".to_string(),
+                    },
+                    CovenantTest {
+                        id: "evt:T2".to_string(),
+                        hypothesis_id: "evt:H2".to_string(),
+                        description: "Pruned docker images, builds still failing".to_string(),
+                        result: TestStatus::Fail,
+                        evidence_ref: "capture://evt/T2".to_string(),
+                    },
+                ],
+                outcomes: vec![CovenantOutcome {
+                    id: "evt:O1".to_string(),
+                    summary: "Fixed after rotating logs.".to_string(),
+                    outcome: OutcomeKind::Success,
+                    evidence_refs: vec!["capture://evt/T2".to_string()],
+                    evidence: vec![CovenantEvidence::test("capture://evt/T2")],
+                }],
+                tags: Vec::new(),
+                session_id: None,
+                rollout_path: None,
+            }
+        );
+    }
+
+    #[test]
+    fn outcome_without_tests_is_rejected() {
+        let error = parse_markdown_capture("## Intent\nShip it\n\n## Outcome\nShipped\n")
+            .expect_err("outcome needs a test");
+
+        assert_eq!(
+            error.to_string(),
+            "the Outcome section needs a Tests section to link to"
+        );
+    }
+}
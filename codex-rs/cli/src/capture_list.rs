@@ -0,0 +1,275 @@
+//! `codex capture list` and `codex capture search`: stored captures, newest
+//! first or ranked against a query.
+
+use chrono::DateTime;
+use codex_core::pattern_match::MatchOptions;
+use codex_core::pattern_match::PatternDefinition;
+use codex_core::pattern_match::PatternMatchEvent;
+use codex_core::pattern_match::ScoringProfile;
+use codex_core::pattern_match::rank_patterns;
+use codex_state::CovenantEvent;
+use codex_state::normalize_event_tags;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Captures have no domain signature or tests to compare with a query, so
+/// search ranks on text alone.
+const SEARCH_SCORING: ScoringProfile = ScoringProfile {
+    text: 1.0,
+    domain: 0.0,
+    outcome: 0.0,
+    fuzzy: ScoringProfile::BALANCED.fuzzy,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct CaptureSummary {
+    pub(crate) event_id: String,
+    pub(crate) created_at: i64,
+    /// `YYYY-MM-DD`, in UTC.
+    pub(crate) date: String,
+    pub(crate) intent: String,
+    /// Summary of the last outcome, or `null` while the capture is open.
+    pub(crate) outcome: Option<String>,
+    /// Relevance to the search query, from 0 to 1. Omitted when listing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) score: Option<f64>,
+}
+
+impl CaptureSummary {
+    fn new(event: &CovenantEvent) -> Self {
+        Self {
+            event_id: event.id.clone(),
+            created_at: event.created_at,
+            date: DateTime::from_timestamp(event.created_at, 0)
+                .map(|time| time.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            intent: capture_intent(event).to_string(),
+            outcome: event.outcomes.last().map(|outcome| outcome.summary.clone()),
+            score: None,
+        }
+    }
+}
+
+/// The newest `limit` captures, newest first. `events` is oldest first, as
+/// the state store returns it.
+pub(crate) fn list_captures(events: &[CovenantEvent], limit: usize) -> Vec<CaptureSummary> {
+    events
+        .iter()
+        .rev()
+        .take(limit)
+        .map(CaptureSummary::new)
+        .collect()
+}
+
+/// The events carrying every one of `tags`. Tags are normalized the way
+/// `handshakeos-e log --tag` stores them, so `#Infra` finds `infra`.
+pub(crate) fn events_with_tags(
+    events: Vec<CovenantEvent>,
+    tags: &[String],
+) -> anyhow::Result<Vec<CovenantEvent>> {
+    let tags = normalize_event_tags(tags).map_err(anyhow::Error::msg)?;
+    Ok(events
+        .into_iter()
+        .filter(|event| event.has_tags(&tags))
+        .collect())
+}
+
+/// Rank captures against `query` the way `codex patterns-match` ranks
+/// patterns against an event: the intent stands in for the trigger and the
+/// outcome summaries for the invariant. Captures sharing no words (or, for
+/// short queries, no character trigrams) with the query are left out.
+pub(crate) fn search_captures(
+    events: &[CovenantEvent],
+    query: &str,
+    limit: usize,
+) -> Vec<CaptureSummary> {
+    let documents = events
+        .iter()
+        .map(|event| PatternDefinition {
+            id: event.id.clone(),
+            trigger: capture_intent(event).to_string(),
+            invariant: event
+                .outcomes
+                .iter()
+                .map(|outcome| outcome.summary.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            domain_signature: Vec::new(),
+            evidence_refs: Vec::new(),
+            namespace: None,
+            confidence: None,
+            anti_pattern: false,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+    let query = PatternMatchEvent {
+        trigger: query.to_string(),
+        invariant: String::new(),
+        domain_signature: Vec::new(),
+        tests: Vec::new(),
+        test_results: Vec::new(),
+    };
+    let options = MatchOptions {
+        top_k: Some(limit),
+        scoring: SEARCH_SCORING,
+        ..MatchOptions::default()
+    };
+    let events_by_id = events
+        .iter()
+        .map(|event| (event.id.as_str(), event))
+        .collect::<HashMap<_, _>>();
+    rank_patterns(&query, &documents, &options)
+        .into_iter()
+        .filter_map(|result| {
+            events_by_id
+                .get(result.pattern_id.as_str())
+                .map(|event| CaptureSummary {
+                    score: Some(result.total),
+                    ..CaptureSummary::new(event)
+                })
+        })
+        .collect()
+}
+
+/// The intent's goal, or the event description for captures recorded
+/// without an intent.
+fn capture_intent(event: &CovenantEvent) -> &str {
+    event
+        .intent
+        .as_ref()
+        .map_or(event.description.as_str(), |intent| intent.goal.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::patterns::Outcome;
+    use codex_state::CovenantIntent;
+    use codex_state::CovenantOutcome;
+    use pretty_assertions::assert_eq;
+
+    /// 2026-01-01T00:00:00Z.
+    const NEW_YEAR: i64 = 1_767_225_600;
+
+    fn capture(id: &str, day: i64, goal: Option<&str>, outcome: Option<&str>) -> CovenantEvent {
+        CovenantEvent {
+            id: id.to_string(),
+            created_at: NEW_YEAR + day * 24 * 60 * 60,
+            description: "certificate expired".to_string(),
+            domain_signature: "[]".to_string(),
+            intent: goal.map(|goal| CovenantIntent {
+                id: format!("{id}-intent"),
+                goal: goal.to_string(),
+                constraints: String::new(),
+                success_signal: String::new(),
+                confidence: 0.5,
+            }),
+            hypotheses: Vec::new(),
+            tests: Vec::new(),
+            outcomes: outcome
+                .map(|summary| CovenantOutcome {
+                    id: format!("{id}-outcome"),
+                    summary: summary.to_string(),
+                    outcome: Outcome::Success,
+                    evidence_refs: Vec::new(),
+                    evidence: Vec::new(),
+                })
+                .into_iter()
+                .collect(),
+            tags: Vec::new(),
+            session_id: None,
+            rollout_path: None,
+        }
+    }
+
+    fn events() -> Vec<CovenantEvent> {
+        vec![
+            capture(
+                "e1",
+                0,
+                Some("fix the flaky login test"),
+                Some("stubbed the auth clock"),
+            ),
+            capture(
+                "e2",
+                1,
+                Some("disk full on the build host"),
+                Some("pruned docker images"),
+            ),
+            capture("e3", 2, None, None),
+        ]
+    }
+
+    #[test]
+    fn list_shows_the_newest_captures_first() {
+        assert_eq!(
+            list_captures(&events(), 2),
+            vec![
+                CaptureSummary {
+                    event_id: "e3".to_string(),
+                    created_at: NEW_YEAR + 2 * 24 * 60 * 60,
+                    date: "2026-01-03".to_string(),
+                    intent: "certificate expired".to_string(),
+                    outcome: None,
+                    score: None,
+                },
+                CaptureSummary {
+                    event_id: "e2".to_string(),
+                    created_at: NEW_YEAR + 24 * 60 * 60,
+                    date: "2026-01-02".to_string(),
+                    intent: "disk full on the build host".to_string(),
+                    outcome: Some("pruned docker images".to_string()),
+                    score: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn search_ranks_intents_and_outcomes_against_the_query() {
+        let events = events();
+        let ids = |query: &str| {
+            search_captures(&events, query, 5)
+                .into_iter()
+                .map(|summary| summary.event_id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids("pruning docker images"), vec!["e2".to_string()]);
+        assert_eq!(ids("flaky login tests"), vec!["e1".to_string()]);
+        assert_eq!(ids("kernel panic on boot"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn tag_filters_keep_events_carrying_every_tag() {
+        let mut events = events();
+        events[0].tags = vec!["flaky-test".to_string(), "auth".to_string()];
+        events[1].tags = vec!["infra".to_string()];
+        let ids = |tags: &[&str]| {
+            events_with_tags(
+                events.clone(),
+                &tags.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            )
+            .map(|events| events.into_iter().map(|event| event.id).collect::<Vec<_>>())
+            .map_err(|err| err.to_string())
+        };
+
+        assert_eq!(
+            (
+                ids(&[]),
+                ids(&["#Flaky-Test"]),
+                ids(&["auth", "infra"]),
+                ids(&["flaky test"]),
+            ),
+            (
+                Ok(vec!["e1".to_string(), "e2".to_string(), "e3".to_string()]),
+                Ok(vec!["e1".to_string()]),
+                Ok(Vec::new()),
+                Err("'flaky test' is not a tag; tags use letters, digits, '-' and '_'".to_string()),
+            )
+        );
+    }
+}
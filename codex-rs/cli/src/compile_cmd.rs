@@ -0,0 +1,759 @@
+//! `codex compile`: mine resolved covenant events into the pattern log.
+
+use anyhow::Context;
+use clap::Parser;
+use codex_core::config::find_codex_home;
+use codex_core::covenant::load_covenant;
+use codex_core::domain_signature::signature_provider;
+use codex_core::pattern_match::load_compile_options;
+use codex_core::pattern_match::load_signature_config;
+use codex_core::patterns::CompileOptions;
+use codex_core::patterns::EventCluster;
+use codex_core::patterns::EventSource;
+use codex_core::patterns::GroupBy;
+use codex_core::patterns::Outcome;
+use codex_core::patterns::Pattern;
+use codex_core::patterns::ResolvedEvent;
+use codex_core::patterns::cluster_events;
+use codex_core::patterns::compile_pattern_batches;
+use codex_core::patterns::dedupe_resolved_events;
+use codex_state::AuditAction;
+use codex_state::CovenantEvent;
+use codex_state::CovenantStorePaths;
+use codex_state::JsonlAppender;
+use codex_state::JsonlAppenderOptions;
+use codex_state::StoreCipher;
+use codex_state::open_line;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+use uuid::Uuid;
+
+use crate::covenant_cmd::load_closed_event_sources;
+use crate::covenant_store::StoreArgs;
+use crate::covenant_store::open_covenant_store;
+
+/// Append-only log of compiled patterns under `CODEX_HOME`.
+pub const PATTERNS_LOG_FILENAME: &str = "patterns.jsonl";
+
+#[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct CompileCommand {
+    #[command(subcommand)]
+    pub subcommand: Option<CompileSubcommand>,
+
+    /// Pattern log to write. Defaults to `patterns.jsonl` next to the
+    /// covenant store's database (`$CODEX_HOME/patterns.jsonl` for `home`).
+    #[arg(long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Instead of compiling, rewrite the pattern log keeping only the latest
+    /// version of each pattern and move superseded entries to
+    /// `<name>.archive.jsonl`.
+    #[arg(long, default_value_t = false)]
+    pub compact: bool,
+
+    /// Also compile events archived by `codex covenant gc`.
+    #[arg(long, default_value_t = false, conflicts_with = "compact")]
+    pub include_archived: bool,
+
+    /// Fewest events a group needs to become a pattern. Overrides
+    /// `[compile] min_evidence` in `patterns.toml`.
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with = "compact",
+        value_parser = clap::value_parser!(usize).range(1..)
+    )]
+    pub min_evidence: Option<usize>,
+
+    /// What events must share to form one pattern: `trigger`,
+    /// `trigger+invariant`, or `signature`. Overrides `[compile] group_by` in
+    /// `patterns.toml`.
+    #[arg(long, value_name = "STRATEGY", conflicts_with = "compact")]
+    pub group_by: Option<GroupBy>,
+
+    #[clap(flatten)]
+    pub store: StoreArgs,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum CompileSubcommand {
+    /// Print the groups a compile would form, with their member events,
+    /// dominant outcome, and evidence, without writing any patterns.
+    Clusters(CompileClustersArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct CompileClustersArgs {
+    /// Also group events archived by `codex covenant gc`.
+    #[arg(long, default_value_t = false)]
+    pub include_archived: bool,
+
+    /// Fewest events a group needs to become a pattern. Overrides
+    /// `[compile] min_evidence` in `patterns.toml`.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(usize).range(1..))]
+    pub min_evidence: Option<usize>,
+
+    /// What events must share to form one pattern: `trigger`,
+    /// `trigger+invariant`, or `signature`. Overrides `[compile] group_by` in
+    /// `patterns.toml`.
+    #[arg(long, value_name = "STRATEGY")]
+    pub group_by: Option<GroupBy>,
+
+    /// Print the clusters as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+
+    #[clap(flatten)]
+    pub store: StoreArgs,
+}
+
+/// One line of the pattern log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct SuggestedPattern {
+    /// Incremented each time the same pattern key is compiled again.
+    pub(crate) version: u64,
+    pub(crate) compiled_at: i64,
+    #[serde(flatten)]
+    pub(crate) pattern: Pattern,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CompactionSummary {
+    pub(crate) kept: usize,
+    pub(crate) archived: usize,
+}
+
+pub async fn run_compile(cmd: CompileCommand) -> anyhow::Result<()> {
+    let codex_home = find_codex_home()?;
+    if let Some(CompileSubcommand::Clusters(args)) = cmd.subcommand {
+        return run_clusters(args, codex_home.as_path()).await;
+    }
+    let store = cmd.store.resolve(codex_home.as_path()).await?;
+    let output = cmd
+        .output
+        .unwrap_or_else(|| store.db_dir.join(PATTERNS_LOG_FILENAME));
+
+    let cipher = StoreCipher::load()?;
+    if cmd.compact {
+        let archive = archive_path(output.as_path());
+        let summary = compact_patterns_log(output.as_path(), archive.as_path(), cipher.as_ref())?;
+        println!(
+            "kept {} patterns, archived {} superseded entries to {}",
+            summary.kept,
+            summary.archived,
+            archive.display()
+        );
+        return Ok(());
+    }
+
+    let (events, duplicates) = load_compile_events(&store, cmd.include_archived).await?;
+    record_compile(&store, "patterns.compile", &duplicates).await?;
+    let provider = signature_provider(&load_signature_config(codex_home.as_path())?)?;
+    let options = compile_options(codex_home.as_path(), cmd.min_evidence, cmd.group_by)?;
+    let run_id = Uuid::new_v4().to_string();
+    // Batches are written as they are compiled, so a compile over a long
+    // history never holds all of its patterns at once.
+    let patterns = compile_pattern_batches(&events, &options).flatten().map(
+        |mut pattern| -> anyhow::Result<Pattern> {
+            pattern.fill_missing_signature(provider.as_ref())?;
+            pattern.provenance.compile_run_id = Some(run_id.clone());
+            Ok(pattern)
+        },
+    );
+    let appended = append_patterns(
+        output.as_path(),
+        patterns,
+        unix_timestamp(),
+        cipher.as_ref(),
+    )?;
+    println!(
+        "compiled {appended} patterns from {} resolved events into {} (run {run_id})",
+        events.len(),
+        output.display()
+    );
+    if !duplicates.is_empty() {
+        println!(
+            "skipped {} duplicate events: {}",
+            duplicates.len(),
+            duplicates.join(", ")
+        );
+    }
+    Ok(())
+}
+
+async fn run_clusters(args: CompileClustersArgs, codex_home: &Path) -> anyhow::Result<()> {
+    let store = args.store.resolve(codex_home).await?;
+    let (events, duplicates) = load_compile_events(&store, args.include_archived).await?;
+    let options = compile_options(codex_home, args.min_evidence, args.group_by)?;
+    let clusters = cluster_events(&events, &options);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&clusters)?);
+        return Ok(());
+    }
+    print!("{}", format_clusters(&clusters, &options));
+    if !duplicates.is_empty() {
+        println!(
+            "left out {} duplicate events: {}",
+            duplicates.len(),
+            duplicates.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// The resolved events of the store, with the ids of those dropped as
+/// copies of another event.
+pub(crate) async fn load_compile_events(
+    store: &CovenantStorePaths,
+    include_archived: bool,
+) -> anyhow::Result<(Vec<ResolvedEvent>, Vec<String>)> {
+    let events = load_closed_event_sources(store, include_archived)
+        .await?
+        .into_iter()
+        .map(|(event, source)| resolved_event_from_covenant(&event, Some(source)))
+        .collect::<Vec<_>>();
+    // The same resolution exported twice would otherwise count twice toward
+    // a pattern's support.
+    Ok(dedupe_resolved_events(events))
+}
+
+/// `[compile]` options from `patterns.toml`, with the command-line
+/// overrides applied.
+pub(crate) fn compile_options(
+    codex_home: &Path,
+    min_evidence: Option<usize>,
+    group_by: Option<GroupBy>,
+) -> anyhow::Result<CompileOptions> {
+    let mut options = load_compile_options(codex_home)?;
+    if let Some(min_evidence) = min_evidence {
+        options.min_evidence = min_evidence;
+    }
+    if let Some(group_by) = group_by {
+        options.group_by = group_by;
+    }
+    Ok(options)
+}
+
+/// One block per cluster: its key, whether it would compile, its dominant
+/// outcome and evidence, and its member events.
+fn format_clusters(clusters: &[EventCluster], options: &CompileOptions) -> String {
+    if clusters.is_empty() {
+        return "no resolved events to group\n".to_string();
+    }
+    let mut out = String::new();
+    for cluster in clusters {
+        let status = if cluster.compiles {
+            "compiles"
+        } else {
+            "too small"
+        };
+        out.push_str(&format!(
+            "{}  [{status}]\n  outcome: {}  evidence: {}  events: {}\n",
+            cluster.key,
+            cluster.outcome.as_str(),
+            cluster.evidence_count,
+            cluster.event_ids.len()
+        ));
+        for event_id in &cluster.event_ids {
+            out.push_str(&format!("    {event_id}\n"));
+        }
+    }
+    let compiling = clusters.iter().filter(|cluster| cluster.compiles).count();
+    out.push_str(&format!(
+        "{} clusters by {}, {compiling} with at least {} events\n",
+        clusters.len(),
+        options.group_by.as_str(),
+        options.min_evidence.max(1)
+    ));
+    out
+}
+
+/// Audit the compile as `action_type`, with one `<action_type>.duplicate`
+/// entry per event skipped as a copy of another.
+pub(crate) async fn record_compile(
+    store: &CovenantStorePaths,
+    action_type: &str,
+    duplicates: &[String],
+) -> anyhow::Result<()> {
+    let covenant_version = load_covenant(std::env::current_dir()?.as_path())
+        .await
+        .map(|covenant| covenant.version)
+        .unwrap_or_else(|_| "missing".to_string());
+    let action = |action_type: &str, event_id: Option<String>| AuditAction {
+        timestamp: unix_timestamp(),
+        actor: "user".to_string(),
+        action_type: action_type.to_string(),
+        scope: "cli".to_string(),
+        covenant_version: covenant_version.clone(),
+        event_id,
+        intent_id: None,
+    };
+    let runtime = open_covenant_store(store).await?;
+    let mut tx = runtime.begin_covenant_transaction().await?;
+    tx.insert_audit_action(&action(action_type, None)).await?;
+    let duplicate_type = format!("{action_type}.duplicate");
+    for event_id in duplicates {
+        tx.insert_audit_action(&action(duplicate_type.as_str(), Some(event_id.clone())))
+            .await?;
+    }
+    tx.commit().await
+}
+
+/// Flatten a closed covenant event, read from `source`, into compile input.
+///
+/// The invariant is the most probable hypothesis (falling back to the intent
+/// goal), and the response is the latest outcome summary.
+pub(crate) fn resolved_event_from_covenant(
+    event: &CovenantEvent,
+    source: Option<EventSource>,
+) -> ResolvedEvent {
+    let invariant = event
+        .hypotheses
+        .iter()
+        .max_by(|left, right| left.probability.total_cmp(&right.probability))
+        .map(|hypothesis| hypothesis.model_type.clone())
+        .or_else(|| event.intent.as_ref().map(|intent| intent.goal.clone()))
+        .unwrap_or_default();
+    let response = event
+        .outcomes
+        .last()
+        .map(|outcome| outcome.summary.clone())
+        .unwrap_or_default();
+    let mut evidence_refs = Vec::new();
+    for evidence_ref in event
+        .outcomes
+        .iter()
+        .flat_map(|outcome| outcome.evidence_refs.iter())
+    {
+        if !evidence_refs.contains(evidence_ref) {
+            evidence_refs.push(evidence_ref.clone());
+        }
+    }
+
+    ResolvedEvent {
+        event_id: event.id.clone(),
+        trigger: event.description.clone(),
+        invariant,
+        response,
+        outcome: resolved_outcome(event),
+        domain_signature: serde_json::from_str(event.domain_signature.as_str()).unwrap_or_default(),
+        evidence_refs,
+        resolved_at: event.created_at,
+        source,
+        tags: event.tags.clone(),
+    }
+}
+
+/// The latest outcome's classification, or the combined test results when the
+/// outcome itself carries no signal.
+pub(crate) fn resolved_outcome(event: &CovenantEvent) -> Outcome {
+    match event.outcomes.last().map(|outcome| outcome.outcome) {
+        Some(outcome @ (Outcome::Success | Outcome::Failure | Outcome::Mixed)) => outcome,
+        Some(Outcome::Unknown | Outcome::Other) | None => {
+            Outcome::combine(event.tests.iter().map(|test| test.result.outcome()))
+        }
+    }
+}
+
+/// Append `patterns` to the log, versioning each after its latest entry.
+pub(crate) fn append_patterns(
+    path: &Path,
+    patterns: impl IntoIterator<Item = anyhow::Result<Pattern>>,
+    compiled_at: i64,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<usize> {
+    let versions = latest_versions(&read_patterns_log(path, cipher)?);
+    append_versioned_patterns(path, &versions, patterns, compiled_at, cipher)
+}
+
+/// The highest version of each pattern key among `entries`.
+pub(crate) fn latest_versions(entries: &[(String, SuggestedPattern)]) -> HashMap<String, u64> {
+    let mut versions: HashMap<String, u64> = HashMap::new();
+    for (_, entry) in entries {
+        let version = versions.entry(entry.pattern.key.clone()).or_insert(0);
+        *version = (*version).max(entry.version);
+    }
+    versions
+}
+
+/// Append `patterns` to the log at `path`, versioning each after its entry in
+/// `versions`. With a cipher, each entry is sealed.
+pub(crate) fn append_versioned_patterns(
+    path: &Path,
+    versions: &HashMap<String, u64>,
+    patterns: impl IntoIterator<Item = anyhow::Result<Pattern>>,
+    compiled_at: i64,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<usize> {
+    let mut log =
+        JsonlAppender::open(path, JsonlAppenderOptions::default())?.with_cipher(cipher.cloned());
+    let mut appended = 0;
+    for pattern in patterns {
+        let pattern = pattern?;
+        let version = versions.get(&pattern.key).copied().unwrap_or(0) + 1;
+        let entry = SuggestedPattern {
+            version,
+            compiled_at,
+            pattern,
+        };
+        log.append(&entry)?;
+        appended += 1;
+    }
+    log.finish()?;
+    Ok(appended)
+}
+
+/// Rewrite the log so it holds only the latest version of each pattern key.
+///
+/// Superseded lines are appended to `archive` before the log is replaced, so
+/// the full history stays recoverable. With a cipher, every line is sealed
+/// afresh as it is moved or kept.
+pub(crate) fn compact_patterns_log(
+    path: &Path,
+    archive: &Path,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<CompactionSummary> {
+    let entries = read_patterns_log(path, cipher)?;
+    let mut latest: HashMap<&str, usize> = HashMap::new();
+    for (index, (_, entry)) in entries.iter().enumerate() {
+        let key = entry.pattern.key.as_str();
+        let newer = latest
+            .get(key)
+            .is_none_or(|&current| entries[current].1.version <= entry.version);
+        if newer {
+            latest.insert(key, index);
+        }
+    }
+
+    let mut kept = Vec::new();
+    let mut superseded = Vec::new();
+    for (index, (line, entry)) in entries.iter().enumerate() {
+        if latest.get(entry.pattern.key.as_str()) == Some(&index) {
+            kept.push(line.as_str());
+        } else {
+            superseded.push(line.as_str());
+        }
+    }
+    let summary = CompactionSummary {
+        kept: kept.len(),
+        archived: superseded.len(),
+    };
+    if superseded.is_empty() {
+        return Ok(summary);
+    }
+
+    let mut archive_log =
+        JsonlAppender::open(archive, JsonlAppenderOptions::default())?.with_cipher(cipher.cloned());
+    for line in superseded {
+        archive_log.append_line(line)?;
+    }
+    archive_log.finish()?;
+
+    let staging = path.with_extension("jsonl.tmp");
+    let mut contents = String::new();
+    for line in kept {
+        match cipher {
+            Some(cipher) => contents.push_str(&cipher.seal_line(line)?),
+            None => contents.push_str(line),
+        }
+        contents.push('\n');
+    }
+    // The staging file reaches the disk before it replaces the log, and the
+    // rename before the compaction reports success, so a crash leaves either
+    // the old log or the compacted one.
+    let mut staged = std::fs::File::create(&staging)
+        .with_context(|| format!("failed to create {}", staging.display()))?;
+    staged
+        .write_all(contents.as_bytes())
+        .and_then(|()| staged.sync_all())
+        .with_context(|| format!("failed to write {}", staging.display()))?;
+    drop(staged);
+    std::fs::rename(&staging, path)
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+    sync_parent_dir(path)?;
+    Ok(summary)
+}
+
+/// Flush the directory entry of `path`, making a rename into it durable.
+/// Directories cannot be opened for syncing on Windows, where the rename is
+/// left to the file system.
+fn sync_parent_dir(path: &Path) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        std::fs::File::open(parent)
+            .and_then(|dir| dir.sync_all())
+            .with_context(|| format!("failed to sync {}", parent.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Read the pattern log, returning each line, decrypted when it was sealed,
+/// alongside its parsed entry. Sealed lines need `cipher` to parse.
+pub(crate) fn read_patterns_log(
+    path: &Path,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<Vec<(String, SuggestedPattern)>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", path.display()));
+        }
+    };
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let (line, entry) = open_line(line, cipher)
+                .and_then(|line| {
+                    let entry = serde_json::from_str(&line)?;
+                    Ok((line.into_owned(), entry))
+                })
+                .with_context(|| format!("failed to parse {}:{}", path.display(), index + 1))?;
+            Ok((line, entry))
+        })
+        .collect()
+}
+
+pub(crate) fn archive_path(path: &Path) -> PathBuf {
+    path.with_extension("archive.jsonl")
+}
+
+pub(crate) fn unix_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::test_status::TestStatus;
+    use codex_state::CovenantHypothesis;
+    use codex_state::CovenantOutcome;
+    use codex_state::CovenantTest;
+    use pretty_assertions::assert_eq;
+
+    fn pattern(key: &str, best_response: &str) -> Pattern {
+        Pattern {
+            key: key.to_string(),
+            trigger: key.to_string(),
+            invariant: String::new(),
+            best_response: best_response.to_string(),
+            outcome: Outcome::Success,
+            counterexample: None,
+            support_count: 2,
+            confidence: 0.0,
+            domain_signature: Vec::new(),
+            evidence_refs: Vec::new(),
+            anti_pattern: false,
+            signature_provider: None,
+            provenance: Default::default(),
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn compact_keeps_latest_versions_and_archives_the_rest() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let log = dir.path().join(PATTERNS_LOG_FILENAME);
+        let archive = archive_path(log.as_path());
+        // Encryption is turned on after the first compile, so the log starts
+        // out with plaintext lines.
+        let cipher = StoreCipher::generate();
+        append_patterns(
+            log.as_path(),
+            [
+                Ok(pattern("disk full|", "reboot")),
+                Ok(pattern("oom|", "add swap")),
+            ],
+            10,
+            None,
+        )
+        .expect("first compile");
+        append_patterns(
+            log.as_path(),
+            [Ok(pattern("disk full|", "prune logs"))],
+            20,
+            Some(&cipher),
+        )
+        .expect("second compile");
+
+        let summary = compact_patterns_log(log.as_path(), archive.as_path(), Some(&cipher))
+            .expect("compact log");
+        assert_eq!(
+            summary,
+            CompactionSummary {
+                kept: 2,
+                archived: 1
+            }
+        );
+
+        let contents = std::fs::read_to_string(&log).expect("read raw log");
+        assert_eq!(
+            (
+                contents.contains("swap"),
+                read_patterns_log(log.as_path(), None)
+                    .map(|entries| entries.len())
+                    .map_err(|err| err.to_string()),
+            ),
+            (false, Err(format!("failed to parse {}:1", log.display())))
+        );
+
+        let kept = read_patterns_log(log.as_path(), Some(&cipher))
+            .expect("read log")
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kept,
+            vec![
+                SuggestedPattern {
+                    version: 1,
+                    compiled_at: 10,
+                    pattern: pattern("oom|", "add swap"),
+                },
+                SuggestedPattern {
+                    version: 2,
+                    compiled_at: 20,
+                    pattern: pattern("disk full|", "prune logs"),
+                },
+            ]
+        );
+        let archived = read_patterns_log(archive.as_path(), Some(&cipher))
+            .expect("read archive")
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            archived,
+            vec![SuggestedPattern {
+                version: 1,
+                compiled_at: 10,
+                pattern: pattern("disk full|", "reboot"),
+            }]
+        );
+    }
+
+    #[test]
+    fn covenant_events_flatten_into_resolved_events() {
+        let event = CovenantEvent {
+            id: "evt-1".to_string(),
+            created_at: 42,
+            description: "Disk full on build host".to_string(),
+            domain_signature: "[0.5,0.5]".to_string(),
+            intent: None,
+            hypotheses: vec![
+                CovenantHypothesis {
+                    id: "evt-1:H1".to_string(),
+                    model_type: "logs filled the disk".to_string(),
+                    probability: 0.8,
+                    falsifiers: Vec::new(),
+                    domain_signature: "[]".to_string(),
+                },
+                CovenantHypothesis {
+                    id: "evt-1:H2".to_string(),
+                    model_type: "docker layers".to_string(),
+                    probability: 0.2,
+                    falsifiers: Vec::new(),
+                    domain_signature: "[]".to_string(),
+                },
+            ],
+            tests: vec![CovenantTest {
+                id: "evt-1:R1:H1".to_string(),
+                hypothesis_id: "evt-1:H1".to_string(),
+                description: "check /var/log".to_string(),
+                result: TestStatus::Pass,
+                evidence_ref: "capture://evt-1/T1".to_string(),
+            }],
+            outcomes: vec![CovenantOutcome {
+                id: "evt-1:O1".to_string(),
+                summary: "pruned logs".to_string(),
+                outcome: Outcome::Other,
+                evidence_refs: vec!["capture://evt-1/T1".to_string()],
+                evidence: Vec::new(),
+            }],
+            tags: Vec::new(),
+            session_id: None,
+            rollout_path: None,
+        };
+
+        let source = EventSource {
+            path: "events-2026-01.jsonl.zst".to_string(),
+            line: Some(3),
+        };
+        assert_eq!(
+            resolved_event_from_covenant(&event, Some(source.clone())),
+            ResolvedEvent {
+                event_id: "evt-1".to_string(),
+                trigger: "Disk full on build host".to_string(),
+                invariant: "logs filled the disk".to_string(),
+                response: "pruned logs".to_string(),
+                outcome: Outcome::Success,
+                domain_signature: vec![0.5, 0.5],
+                evidence_refs: vec!["capture://evt-1/T1".to_string()],
+                resolved_at: 42,
+                source: Some(source),
+                tags: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn clusters_print_their_members_and_whether_they_compile() {
+        let clusters = vec![
+            EventCluster {
+                key: "certificate expired|*".to_string(),
+                event_ids: vec!["e3".to_string()],
+                outcome: Outcome::Success,
+                evidence_count: 0,
+                compiles: false,
+            },
+            EventCluster {
+                key: "disk full on {path}|*".to_string(),
+                event_ids: vec!["e1".to_string(), "e2".to_string()],
+                outcome: Outcome::Failure,
+                evidence_count: 3,
+                compiles: true,
+            },
+        ];
+        let options = CompileOptions {
+            min_evidence: 2,
+            group_by: GroupBy::Trigger,
+        };
+
+        assert_eq!(
+            format_clusters(&clusters, &options),
+            [
+                "certificate expired|*  [too small]",
+                "  outcome: success  evidence: 0  events: 1",
+                "    e3",
+                "disk full on {path}|*  [compiles]",
+                "  outcome: failure  evidence: 3  events: 2",
+                "    e1",
+                "    e2",
+                "2 clusters by trigger, 1 with at least 2 events",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,960 @@
+//! `codex covenant`: manage the `covenant.json` that scopes agent and user actions.
+
+use anyhow::Context;
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::SESSIONS_SUBDIR;
+use codex_core::config::Config;
+use codex_core::config::find_codex_home;
+use codex_core::covenant::COVENANT_FILENAME;
+use codex_core::covenant::COVENANT_VERSION;
+use codex_core::covenant::Covenant;
+use codex_core::covenant::CovenantAction;
+use codex_core::covenant::find_covenant;
+use codex_core::covenant::find_covenant_path;
+use codex_core::covenant::load_covenant;
+use codex_core::covenant::upgrade_covenant;
+use codex_core::patterns::EventSource;
+use codex_core::patterns::Outcome;
+use codex_state::AuditAction;
+use codex_state::CovenantEvent;
+use codex_state::CovenantHypothesis;
+use codex_state::CovenantRetention;
+use codex_state::CovenantStorePaths;
+use codex_state::StoreCipher;
+use codex_state::archive_covenant_events;
+use codex_state::archive_unattached_artifacts;
+use codex_state::directory_size;
+use codex_state::handshake_cli::CovenantAttachOptions;
+use codex_state::handshake_cli::CovenantBatchOptions;
+use codex_state::handshake_cli::run_covenant_attach;
+use codex_state::handshake_cli::run_covenant_batch;
+use codex_state::read_archived_covenant_event_entries;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::compile_cmd::resolved_outcome;
+use crate::compile_cmd::unix_timestamp;
+use crate::covenant_show::format_show;
+use crate::covenant_show::show_report;
+use crate::covenant_simulate::load_recorded_actions;
+use crate::covenant_simulate::print_simulation;
+use crate::covenant_simulate::read_covenant_file;
+use crate::covenant_simulate::simulate_covenant;
+use crate::covenant_store::StoreArgs;
+use crate::covenant_store::open_covenant_store;
+use crate::covenant_usage::UsagePeriod;
+use crate::covenant_usage::print_usage;
+use crate::covenant_usage::usage_report;
+use crate::covenant_wizard::event_log_scopes;
+use crate::covenant_wizard::prompt_event;
+use crate::stats::collect_rollout_paths;
+
+/// Number of equal-width confidence buckets in the calibration table.
+const CALIBRATION_BUCKETS: usize = 5;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+#[derive(Debug, Parser)]
+pub struct CovenantCli {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[clap(flatten)]
+    pub store: StoreArgs,
+
+    #[command(subcommand)]
+    pub subcommand: CovenantSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum CovenantSubcommand {
+    /// Write a starter covenant.json granting every capability to the default scopes.
+    Init(CovenantInitArgs),
+    /// Report how well hypothesis probabilities predicted resolved events.
+    Stats(CovenantStatsArgs),
+    /// Archive old resolved events out of the active covenant store.
+    Gc(CovenantGcArgs),
+    /// Replay recorded tool actions against a proposed covenant and report
+    /// which ones it would have denied.
+    Simulate(CovenantSimulateArgs),
+    /// Log a covenant event by answering prompts for its scope, summary,
+    /// intent, and first hypothesis.
+    Wizard(CovenantWizardArgs),
+    /// Print one event with the session it was captured in and the turns
+    /// of that session it covers.
+    Show(CovenantShowArgs),
+    /// Report how often each scope's capabilities were checked, allowed, and
+    /// denied, and which grants were never exercised.
+    Usage(CovenantUsageArgs),
+    /// Rewrite a covenant.json written in an older format version in the
+    /// current one.
+    Upgrade(CovenantUpgradeArgs),
+    /// Generate a key for encrypting the covenant store's files at rest and
+    /// save it in the OS keychain.
+    Keygen(CovenantKeygenArgs),
+    /// Apply a JSONL file of `handshakeos-e` commands in one transaction,
+    /// each checked and audited as if it were run on its own.
+    Batch(CovenantBatchArgs),
+    /// Store a file as evidence for an existing event, as `handshakeos-e
+    /// attach` does.
+    Attach(CovenantAttachArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct CovenantInitArgs {
+    /// Directory to write covenant.json into. Defaults to the current directory.
+    #[arg(long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    pub dir: Option<PathBuf>,
+
+    /// Overwrite an existing covenant.json.
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct CovenantStatsArgs {
+    /// Print the report as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+
+    /// Also read events archived by `codex covenant gc`.
+    #[arg(long, default_value_t = false)]
+    pub include_archived: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct CovenantGcArgs {
+    /// Archive resolved events created more than this many days ago.
+    /// Overrides `covenant.max_event_age_days`.
+    #[arg(long, value_name = "DAYS")]
+    pub max_age_days: Option<u64>,
+
+    /// Archive the oldest resolved events until the store fits in this many
+    /// megabytes. Overrides `covenant.max_store_mb`.
+    #[arg(long, value_name = "MB")]
+    pub max_store_mb: Option<u64>,
+
+    /// Report how many events would be archived without changing anything.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct CovenantSimulateArgs {
+    /// The proposed covenant.json.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub covenant: PathBuf,
+
+    /// Rollout files, or directories to search for them. Defaults to
+    /// `$CODEX_HOME/sessions`.
+    #[arg(long, value_name = "PATH", num_args = 1.., value_hint = clap::ValueHint::AnyPath)]
+    pub rollouts: Vec<PathBuf>,
+
+    /// Deny scopes the covenant does not declare, as `covenant.required` does.
+    #[arg(long, default_value_t = false)]
+    pub required: bool,
+
+    /// Print the report as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct CovenantShowArgs {
+    /// Id of the event to show.
+    pub event_id: String,
+
+    /// Print the event and its session as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct CovenantUsageArgs {
+    /// Only count checks from the last this many days. Defaults to every
+    /// recorded check.
+    #[arg(long, value_name = "DAYS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub days: Option<u64>,
+
+    /// Period to group checks by over time.
+    #[arg(long, value_enum, default_value_t = UsagePeriod::Week)]
+    pub by: UsagePeriod,
+
+    /// Print the report as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct CovenantUpgradeArgs {
+    /// The covenant.json to rewrite. Defaults to the one found from the
+    /// current directory upwards.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CovenantBatchArgs {
+    /// JSONL file with one command object per line.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub file: PathBuf,
+
+    /// Covenant scope used to authorize each command.
+    #[arg(long, default_value = "default")]
+    pub scope: String,
+
+    /// Actor identity written to the audit trail.
+    #[arg(long, default_value = "cli")]
+    pub actor: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct CovenantAttachArgs {
+    /// Event id, or a prefix of one that no other event shares.
+    #[arg(long, required_unless_present = "event_match")]
+    pub event_id: Option<String>,
+
+    /// Words that all appear in the description of exactly one event, such
+    /// as `"disk full"`, instead of `--event-id`.
+    #[arg(long, conflicts_with = "event_id")]
+    pub event_match: Option<String>,
+
+    /// File to store in the covenant store's artifacts, keyed by its SHA-256.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub file: PathBuf,
+
+    /// Covenant scope used to authorize the attachment.
+    #[arg(long, default_value = "default")]
+    pub scope: String,
+
+    /// Actor identity written to the audit trail.
+    #[arg(long, default_value = "cli")]
+    pub actor: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct CovenantKeygenArgs {
+    /// Print the key, for `CODEX_STORE_KEY`, instead of saving it in the
+    /// keychain.
+    #[arg(long, default_value_t = false)]
+    pub print: bool,
+
+    /// Replace a key already in the keychain. Files sealed with it can no
+    /// longer be read.
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct CovenantWizardArgs {
+    /// Scope offered first. Defaults to `cli`.
+    #[arg(long, default_value = "cli")]
+    pub scope: String,
+
+    /// Actor identity written to the audit trail.
+    #[arg(long, default_value = "user")]
+    pub actor: String,
+}
+
+/// Predicted probability against observed support for one confidence range.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct CalibrationBucket {
+    pub(crate) lower: f64,
+    pub(crate) upper: f64,
+    pub(crate) hypotheses: usize,
+    /// Mean stated probability, or `null` for an empty bucket.
+    pub(crate) mean_probability: Option<f64>,
+    /// Share of the bucket's hypotheses that the resolution supported.
+    pub(crate) supported_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct CalibrationReport {
+    /// Hypotheses whose resolution decided for or against them.
+    pub(crate) hypotheses: usize,
+    /// Mean squared error of the stated probabilities, or `null` when no
+    /// hypothesis was decided.
+    pub(crate) brier_score: Option<f64>,
+    pub(crate) buckets: Vec<CalibrationBucket>,
+}
+
+pub async fn run_covenant(cli: CovenantCli) -> anyhow::Result<()> {
+    match cli.subcommand {
+        CovenantSubcommand::Init(args) => {
+            let dir = match args.dir {
+                Some(dir) => dir,
+                None => std::env::current_dir()?,
+            };
+            let path = write_starter_covenant(dir.as_path(), args.force)?;
+            println!("wrote {}", path.display());
+        }
+        CovenantSubcommand::Stats(args) => {
+            let store = cli.store.resolve(find_codex_home()?.as_path()).await?;
+            let events = load_closed_events(&store, args.include_archived).await?;
+            let report = calibration_report(&events);
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_calibration(&report);
+            }
+        }
+        CovenantSubcommand::Gc(args) => run_gc(args, cli.config_overrides, cli.store).await?,
+        CovenantSubcommand::Simulate(args) => run_simulate(args)?,
+        CovenantSubcommand::Wizard(args) => {
+            let store = cli.store.resolve(find_codex_home()?.as_path()).await?;
+            run_wizard(args, &store).await?;
+        }
+        CovenantSubcommand::Show(args) => {
+            let store = cli.store.resolve(find_codex_home()?.as_path()).await?;
+            run_show(args, &store).await?;
+        }
+        CovenantSubcommand::Usage(args) => {
+            let store = cli.store.resolve(find_codex_home()?.as_path()).await?;
+            run_usage(args, &store).await?;
+        }
+        CovenantSubcommand::Upgrade(args) => run_upgrade(args).await?,
+        CovenantSubcommand::Keygen(args) => run_keygen(args)?,
+        CovenantSubcommand::Batch(args) => {
+            run_covenant_batch(CovenantBatchOptions {
+                file: args.file,
+                scope: args.scope,
+                actor: args.actor,
+                store: cli.store.store,
+            })
+            .await?;
+        }
+        CovenantSubcommand::Attach(args) => {
+            run_covenant_attach(CovenantAttachOptions {
+                event_id: args.event_id,
+                event_match: args.event_match,
+                file: args.file,
+                scope: args.scope,
+                actor: args.actor,
+                store: cli.store.store,
+            })
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn run_usage(args: CovenantUsageArgs, store: &CovenantStorePaths) -> anyhow::Result<()> {
+    let counts = if store.db_path().exists() {
+        let runtime = open_covenant_store(store).await?;
+        let since = args.days.map(|days| {
+            unix_timestamp().saturating_sub(
+                i64::try_from(days.saturating_mul(SECONDS_PER_DAY)).unwrap_or(i64::MAX),
+            )
+        });
+        runtime.list_covenant_check_counts(since).await?
+    } else {
+        Vec::new()
+    };
+    let covenant = find_covenant(std::env::current_dir()?.as_path()).await?;
+    let report = usage_report(&counts, covenant.as_ref(), args.by);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_usage(&report);
+    }
+    Ok(())
+}
+
+async fn run_show(args: CovenantShowArgs, store: &CovenantStorePaths) -> anyhow::Result<()> {
+    let runtime = open_covenant_store(store).await?;
+    let events = runtime.list_covenant_events().await?;
+    let Some(event) = events
+        .iter()
+        .find(|event| event.id == args.event_id)
+        .cloned()
+    else {
+        anyhow::bail!("no covenant event with id {}", args.event_id);
+    };
+    let report = show_report(event, &events);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{}", format_show(&report));
+    }
+    Ok(())
+}
+
+async fn run_wizard(args: CovenantWizardArgs, store: &CovenantStorePaths) -> anyhow::Result<()> {
+    let covenant = load_covenant(std::env::current_dir()?.as_path()).await?;
+    let scopes = event_log_scopes(&covenant, args.scope.as_str());
+    let created_at = unix_timestamp();
+    let Some((scope, event)) = prompt_event(
+        &scopes,
+        Uuid::new_v4().to_string(),
+        created_at,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stdout(),
+    )?
+    else {
+        println!("no event logged");
+        return Ok(());
+    };
+
+    // The scope was chosen from those granting `event.log`, so the audit
+    // entry records an allowed action, as `handshakeos-e log` would.
+    let runtime = open_covenant_store(store).await?;
+    let mut tx = runtime.begin_covenant_transaction().await?;
+    tx.insert_audit_action(&AuditAction {
+        timestamp: created_at,
+        actor: args.actor,
+        action_type: CovenantAction::EventLog.as_capability().to_string(),
+        scope,
+        covenant_version: covenant.version,
+        event_id: Some(event.id.clone()),
+        intent_id: event.intent.as_ref().map(|intent| intent.id.clone()),
+    })
+    .await?;
+    tx.insert_covenant_event(&event).await?;
+    tx.commit().await?;
+    println!("logged event {}", event.id);
+    Ok(())
+}
+
+fn run_simulate(args: CovenantSimulateArgs) -> anyhow::Result<()> {
+    let covenant = read_covenant_file(args.covenant.as_path())?;
+    let inputs = if args.rollouts.is_empty() {
+        vec![find_codex_home()?.join(SESSIONS_SUBDIR)]
+    } else {
+        args.rollouts
+    };
+    let mut rollouts = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            rollouts.extend(collect_rollout_paths(input.as_path())?);
+        } else {
+            rollouts.push(input);
+        }
+    }
+    let mut actions = Vec::new();
+    for rollout in &rollouts {
+        actions.extend(load_recorded_actions(rollout.as_path())?);
+    }
+    let report = simulate_covenant(&covenant, actions, args.required);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_simulation(&report, &rollouts);
+    }
+    Ok(())
+}
+
+fn write_starter_covenant(dir: &Path, force: bool) -> anyhow::Result<PathBuf> {
+    let path = dir.join(COVENANT_FILENAME);
+    anyhow::ensure!(
+        force || !path.exists(),
+        "{} already exists; pass --force to overwrite it",
+        path.display()
+    );
+    let covenant = Covenant::starter(&COVENANT_VERSION.to_string());
+    let contents = serde_json::to_string_pretty(&covenant)?;
+    std::fs::write(&path, format!("{contents}\n"))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+async fn run_upgrade(args: CovenantUpgradeArgs) -> anyhow::Result<()> {
+    let path = match args.file {
+        Some(path) => path,
+        None => {
+            let cwd = std::env::current_dir()?;
+            find_covenant_path(cwd.as_path())
+                .await
+                .ok_or_else(|| anyhow::anyhow!("covenant.json not found from {}", cwd.display()))?
+        }
+    };
+    match upgrade_covenant_file(path.as_path())? {
+        Some(from_version) => println!(
+            "upgraded {} from covenant version {from_version} to {COVENANT_VERSION}",
+            path.display()
+        ),
+        None => println!(
+            "{} is already at covenant version {COVENANT_VERSION}",
+            path.display()
+        ),
+    }
+    Ok(())
+}
+
+fn run_keygen(args: CovenantKeygenArgs) -> anyhow::Result<()> {
+    let cipher = StoreCipher::generate();
+    if args.print {
+        println!("{}", cipher.export_key());
+        return Ok(());
+    }
+    anyhow::ensure!(
+        args.force || StoreCipher::load_from_keychain()?.is_none(),
+        "the keychain already has a store key; pass --force to replace it"
+    );
+    cipher.save_to_keychain()?;
+    println!(
+        "saved a new store key in the keychain; pattern logs and event archives \
+         written from now on are encrypted"
+    );
+    Ok(())
+}
+
+/// Rewrite the covenant at `path` in the current format, returning the
+/// version it was upgraded from, or `None` when it was already current and
+/// the file was left alone.
+fn upgrade_covenant_file(path: &Path) -> anyhow::Result<Option<u32>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let upgrade = upgrade_covenant(&contents)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    if upgrade.is_current() {
+        return Ok(None);
+    }
+    let contents = serde_json::to_string_pretty(&upgrade.covenant)?;
+    std::fs::write(path, format!("{contents}\n"))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(Some(upgrade.from_version))
+}
+
+/// Closed events from the active store, optionally followed by archived
+/// ones. An event present in both is only returned from the active store.
+pub(crate) async fn load_closed_events(
+    store: &CovenantStorePaths,
+    include_archived: bool,
+) -> anyhow::Result<Vec<CovenantEvent>> {
+    load_events(store, include_archived, true).await
+}
+
+/// Like [`load_closed_events`], but with the file each event was read from.
+pub(crate) async fn load_closed_event_sources(
+    store: &CovenantStorePaths,
+    include_archived: bool,
+) -> anyhow::Result<Vec<(CovenantEvent, EventSource)>> {
+    load_event_sources(store, include_archived, true).await
+}
+
+/// Like [`load_closed_events`], but also returns events that are still open.
+pub(crate) async fn load_all_events(
+    store: &CovenantStorePaths,
+    include_archived: bool,
+) -> anyhow::Result<Vec<CovenantEvent>> {
+    load_events(store, include_archived, false).await
+}
+
+async fn load_events(
+    store: &CovenantStorePaths,
+    include_archived: bool,
+    closed_only: bool,
+) -> anyhow::Result<Vec<CovenantEvent>> {
+    Ok(load_event_sources(store, include_archived, closed_only)
+        .await?
+        .into_iter()
+        .map(|(event, _)| event)
+        .collect())
+}
+
+async fn load_event_sources(
+    store: &CovenantStorePaths,
+    include_archived: bool,
+    closed_only: bool,
+) -> anyhow::Result<Vec<(CovenantEvent, EventSource)>> {
+    let state_db = store.db_path();
+    let mut events = if state_db.exists() {
+        let runtime = open_covenant_store(store).await?;
+        let events = if closed_only {
+            runtime.list_closed_covenant_events().await?
+        } else {
+            runtime.list_covenant_events().await?
+        };
+        let source = EventSource {
+            path: state_db.display().to_string(),
+            line: None,
+        };
+        events
+            .into_iter()
+            .map(|event| (event, source.clone()))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    if include_archived {
+        let active = events
+            .iter()
+            .map(|(event, _)| event.id.clone())
+            .collect::<HashSet<_>>();
+        let cipher = StoreCipher::load()?;
+        let archived =
+            read_archived_covenant_event_entries(store.archive_dir().as_path(), cipher.as_ref())?;
+        events.extend(
+            archived
+                .into_iter()
+                .filter(|entry| !active.contains(&entry.event.id))
+                .map(|entry| {
+                    let source = EventSource {
+                        path: entry.path.display().to_string(),
+                        line: Some(entry.line),
+                    };
+                    (entry.event, source)
+                }),
+        );
+        events.sort_by(|(left, _), (right, _)| {
+            left.created_at
+                .cmp(&right.created_at)
+                .then_with(|| left.id.cmp(&right.id))
+        });
+    }
+    Ok(events)
+}
+
+async fn run_gc(
+    args: CovenantGcArgs,
+    config_overrides: CliConfigOverrides,
+    store: StoreArgs,
+) -> anyhow::Result<()> {
+    let overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(overrides).await?;
+    let retention = CovenantRetention {
+        max_event_age_secs: args
+            .max_age_days
+            .or(config.covenant_retention.max_event_age_days)
+            .map(|days| i64::try_from(days.saturating_mul(SECONDS_PER_DAY)).unwrap_or(i64::MAX)),
+        max_store_bytes: args
+            .max_store_mb
+            .or(config.covenant_retention.max_store_mb)
+            .map(|megabytes| megabytes.saturating_mul(BYTES_PER_MB)),
+    };
+    anyhow::ensure!(
+        retention != CovenantRetention::default(),
+        "no retention limit set; configure covenant.max_event_age_days or covenant.max_store_mb, \
+         or pass --max-age-days or --max-store-mb"
+    );
+
+    let store = store.resolve(config.codex_home.as_path()).await?;
+    if !store.db_path().exists() {
+        println!("no covenant store in {}", store.db_dir.display());
+        return Ok(());
+    }
+    let runtime = open_covenant_store(&store).await?;
+    let closed = runtime.list_closed_covenant_events().await?;
+    let created_at = closed
+        .iter()
+        .map(|event| event.created_at)
+        .collect::<Vec<_>>();
+    let count = retention.events_to_archive(
+        &created_at,
+        unix_timestamp(),
+        runtime.covenant_event_count().await?,
+        covenant_store_bytes(&store)?,
+    );
+    if args.dry_run {
+        println!("would archive {count} of {} resolved events", closed.len());
+        return Ok(());
+    }
+    if count == 0 {
+        println!("nothing to archive");
+        return Ok(());
+    }
+
+    let archive_dir = store.archive_dir();
+    let archived = &closed[..count];
+    // Write and sync the archive first: if the delete fails, readers skip
+    // archived copies of events that are still active, and a crash after the
+    // delete cannot lose events the archive had not yet flushed.
+    let cipher = StoreCipher::load()?;
+    let files = archive_covenant_events(archive_dir.as_path(), archived, cipher.as_ref())?;
+    let ids = archived
+        .iter()
+        .map(|event| event.id.clone())
+        .collect::<Vec<_>>();
+    runtime.delete_covenant_events(&ids).await?;
+    let moved_artifacts = match runtime.list_attached_artifact_hashes().await? {
+        Some(attached) => archive_unattached_artifacts(
+            store.artifacts_dir().as_path(),
+            archive_dir.as_path(),
+            &attached,
+        )?,
+        None => 0,
+    };
+    runtime.vacuum().await?;
+
+    println!(
+        "archived {count} resolved events into {} file(s) under {}",
+        files.len(),
+        archive_dir.display()
+    );
+    if moved_artifacts > 0 {
+        println!("moved {moved_artifacts} artifacts that no active event uses");
+    }
+    Ok(())
+}
+
+/// Bytes held by the active store: the state database with its write-ahead
+/// log, plus attached artifact files.
+fn covenant_store_bytes(store: &CovenantStorePaths) -> anyhow::Result<u64> {
+    let db_path = store.db_path();
+    let mut total = directory_size(store.artifacts_dir().as_path())?;
+    for suffix in ["", "-wal"] {
+        let mut path = db_path.clone().into_os_string();
+        path.push(suffix);
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            total += metadata.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Bucket every decided hypothesis by its stated probability and compare
+/// against how often the resolution supported it.
+pub(crate) fn calibration_report(events: &[CovenantEvent]) -> CalibrationReport {
+    let mut sums = [(0usize, 0.0, 0usize); CALIBRATION_BUCKETS];
+    let mut squared_error = 0.0;
+    let mut decided = 0;
+    for event in events {
+        for hypothesis in &event.hypotheses {
+            if !hypothesis.probability.is_finite() {
+                continue;
+            }
+            let Some(supported) = hypothesis_supported(event, hypothesis) else {
+                continue;
+            };
+            let probability = hypothesis.probability.clamp(0.0, 1.0);
+            let index =
+                ((probability * CALIBRATION_BUCKETS as f64) as usize).min(CALIBRATION_BUCKETS - 1);
+            let (count, probability_sum, supported_count) = &mut sums[index];
+            *count += 1;
+            *probability_sum += probability;
+            let observed = if supported {
+                *supported_count += 1;
+                1.0
+            } else {
+                0.0
+            };
+            squared_error += (probability - observed) * (probability - observed);
+            decided += 1;
+        }
+    }
+
+    CalibrationReport {
+        hypotheses: decided,
+        brier_score: (decided > 0).then_some(squared_error / decided as f64),
+        buckets: sums
+            .iter()
+            .enumerate()
+            .map(
+                |(index, (count, probability_sum, supported_count))| CalibrationBucket {
+                    lower: index as f64 / CALIBRATION_BUCKETS as f64,
+                    upper: (index + 1) as f64 / CALIBRATION_BUCKETS as f64,
+                    hypotheses: *count,
+                    mean_probability: (*count > 0).then_some(probability_sum / *count as f64),
+                    supported_rate: (*count > 0).then_some(*supported_count as f64 / *count as f64),
+                },
+            )
+            .collect(),
+    }
+}
+
+/// Whether the resolution supported a hypothesis. Tests linked to the
+/// hypothesis decide when they agree; otherwise the event's resolved outcome
+/// does. Mixed or unknown evidence leaves the hypothesis undecided.
+fn hypothesis_supported(event: &CovenantEvent, hypothesis: &CovenantHypothesis) -> Option<bool> {
+    let tested = Outcome::combine(
+        event
+            .tests
+            .iter()
+            .filter(|test| test.hypothesis_id == hypothesis.id)
+            .map(|test| test.result.outcome()),
+    );
+    let outcome = match tested {
+        Outcome::Success | Outcome::Failure | Outcome::Mixed => tested,
+        Outcome::Unknown | Outcome::Other => resolved_outcome(event),
+    };
+    match outcome {
+        Outcome::Success => Some(true),
+        Outcome::Failure => Some(false),
+        Outcome::Mixed | Outcome::Unknown | Outcome::Other => None,
+    }
+}
+
+fn print_calibration(report: &CalibrationReport) {
+    println!("hypotheses: {}", report.hypotheses);
+    match report.brier_score {
+        Some(score) => println!("brier score: {score:.3}"),
+        None => println!("brier score: -"),
+    }
+    println!();
+    println!(
+        "{:<9} {:>10} {:>9} {:>9}",
+        "BUCKET", "HYPOTHESES", "PREDICTED", "OBSERVED"
+    );
+    for bucket in &report.buckets {
+        println!(
+            "{:<9} {:>10} {:>9} {:>9}",
+            format!("{:.1}-{:.1}", bucket.lower, bucket.upper),
+            bucket.hypotheses,
+            format_rate(bucket.mean_probability),
+            format_rate(bucket.supported_rate)
+        );
+    }
+}
+
+fn format_rate(rate: Option<f64>) -> String {
+    rate.map_or_else(|| "-".to_string(), |rate| format!("{rate:.2}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::covenant::CovenantAction;
+    use codex_protocol::test_status::TestStatus;
+    use codex_state::CovenantOutcome;
+    use codex_state::CovenantTest;
+    use pretty_assertions::assert_eq;
+
+    fn hypothesis(id: &str, probability: f64) -> CovenantHypothesis {
+        CovenantHypothesis {
+            id: id.to_string(),
+            model_type: format!("hypothesis {id}"),
+            probability,
+            falsifiers: Vec::new(),
+            domain_signature: "[]".to_string(),
+        }
+    }
+
+    fn test(hypothesis_id: &str, result: TestStatus) -> CovenantTest {
+        CovenantTest {
+            id: format!("{hypothesis_id}:{result}"),
+            hypothesis_id: hypothesis_id.to_string(),
+            description: "check".to_string(),
+            result,
+            evidence_ref: "log".to_string(),
+        }
+    }
+
+    #[test]
+    fn calibration_buckets_tested_and_resolved_hypotheses() {
+        let event = CovenantEvent {
+            id: "evt".to_string(),
+            created_at: 1,
+            description: "audio missing".to_string(),
+            domain_signature: "[]".to_string(),
+            intent: None,
+            hypotheses: vec![
+                hypothesis("h-tested-pass", 0.9),
+                hypothesis("h-tested-fail", 0.7),
+                hypothesis("h-resolved", 0.1),
+                hypothesis("h-mixed", 0.5),
+            ],
+            tests: vec![
+                test("h-tested-pass", TestStatus::Pass),
+                test("h-tested-fail", TestStatus::Fail),
+                test("h-mixed", TestStatus::Pass),
+                test("h-mixed", TestStatus::Fail),
+            ],
+            outcomes: vec![CovenantOutcome {
+                id: "out".to_string(),
+                summary: "audio restored".to_string(),
+                outcome: Outcome::Success,
+                evidence_refs: Vec::new(),
+                evidence: Vec::new(),
+            }],
+            tags: Vec::new(),
+            session_id: None,
+            rollout_path: None,
+        };
+
+        let report = calibration_report(&[event]);
+
+        let bucket = |lower: f64,
+                      upper: f64,
+                      hypotheses: usize,
+                      predicted: Option<f64>,
+                      observed: Option<f64>| {
+            CalibrationBucket {
+                lower,
+                upper,
+                hypotheses,
+                mean_probability: predicted,
+                supported_rate: observed,
+            }
+        };
+        assert_eq!(
+            report,
+            CalibrationReport {
+                hypotheses: 3,
+                brier_score: Some((0.01 + 0.49 + 0.81) / 3.0),
+                buckets: vec![
+                    bucket(0.0, 0.2, 1, Some(0.1), Some(1.0)),
+                    bucket(0.2, 0.4, 0, None, None),
+                    bucket(0.4, 0.6, 0, None, None),
+                    bucket(0.6, 0.8, 1, Some(0.7), Some(0.0)),
+                    bucket(0.8, 1.0, 1, Some(0.9), Some(1.0)),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn init_scaffolds_a_loadable_covenant_and_refuses_to_overwrite() {
+        let dir = tempfile::tempdir().expect("tempdir");
+
+        let path = write_starter_covenant(dir.path(), false).expect("write covenant");
+        let covenant: Covenant =
+            serde_json::from_str(&std::fs::read_to_string(&path).expect("read covenant"))
+                .expect("parse covenant");
+        let scopes = covenant
+            .scopes
+            .iter()
+            .map(|scope| scope.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(scopes, vec!["cli", "exec", "vscode", "mcp"]);
+        assert_eq!(
+            covenant.allows("exec", CovenantAction::EventLog.as_capability()),
+            true
+        );
+
+        assert!(write_starter_covenant(dir.path(), false).is_err());
+        assert_eq!(
+            write_starter_covenant(dir.path(), true).expect("overwrite covenant"),
+            path
+        );
+        assert_eq!(
+            (
+                covenant.version,
+                upgrade_covenant_file(&path).expect("upgrade current covenant"),
+            ),
+            (COVENANT_VERSION.to_string(), None)
+        );
+    }
+
+    #[test]
+    fn upgrade_rewrites_older_covenants_in_place() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join(COVENANT_FILENAME);
+        std::fs::write(
+            &path,
+            r#"{"version": "1", "scopes": {"cli": ["event.log"]}}"#,
+        )
+        .expect("write covenant");
+
+        assert_eq!(
+            upgrade_covenant_file(&path).expect("upgrade covenant"),
+            Some(1)
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(
+                &std::fs::read_to_string(&path).expect("read covenant")
+            )
+            .expect("parse upgraded covenant"),
+            serde_json::json!({
+                "version": "2",
+                "scopes": [{"name": "cli", "capabilities": ["event.log"]}],
+            })
+        );
+    }
+}
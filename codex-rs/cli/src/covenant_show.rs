@@ -0,0 +1,253 @@
+//! `codex covenant show`: one covenant event, with the session it was
+//! captured in and the turns of that session it covers.
+
+use chrono::DateTime;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use codex_state::CovenantEvent;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::stats::read_rollout;
+
+/// Turns of a session an event covers, 1-based and inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) struct TurnRange {
+    pub(crate) first: usize,
+    pub(crate) last: usize,
+    /// Turns the rollout holds in total.
+    pub(crate) total: usize,
+}
+
+/// The session an event was captured in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct LinkedSession {
+    pub(crate) session_id: String,
+    pub(crate) rollout_path: Option<String>,
+    /// `null` when the rollout is gone or holds no turn started before the
+    /// event was captured.
+    pub(crate) turns: Option<TurnRange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ShowReport {
+    pub(crate) event: CovenantEvent,
+    pub(crate) session: Option<LinkedSession>,
+}
+
+/// Report `event`, linking it to its session. `events` are the other events
+/// of the store: the event covers the turns after the one the previous event
+/// of the same session was captured in, through the one it was captured in.
+pub(crate) fn show_report(event: CovenantEvent, events: &[CovenantEvent]) -> ShowReport {
+    let session = event.session_id.clone().map(|session_id| {
+        let previous = events
+            .iter()
+            .filter(|other| {
+                other.session_id.as_deref() == Some(session_id.as_str())
+                    && (other.created_at, other.id.as_str()) < (event.created_at, event.id.as_str())
+            })
+            .map(|other| other.created_at)
+            .max();
+        let turns = event.rollout_path.as_deref().and_then(|path| {
+            let starts = user_turn_starts(read_rollout(Path::new(path)).ok()?.as_str());
+            turn_range(&starts, previous, event.created_at)
+        });
+        LinkedSession {
+            session_id,
+            rollout_path: event.rollout_path.clone(),
+            turns,
+        }
+    });
+    ShowReport { event, session }
+}
+
+/// When each user turn of a rollout started, in Unix seconds. Lines that do
+/// not parse are skipped.
+pub(crate) fn user_turn_starts(contents: &str) -> Vec<i64> {
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RolloutLine>(line.trim()).ok())
+        .filter(|line| matches!(line.item, RolloutItem::EventMsg(EventMsg::UserMessage(_))))
+        .filter_map(|line| DateTime::parse_from_rfc3339(&line.timestamp).ok())
+        .map(|time| time.timestamp())
+        .collect()
+}
+
+/// The turns from the one after the turn in progress at `since` through the
+/// one in progress at `until`. A capture made in the same turn as the
+/// previous one covers just that turn.
+pub(crate) fn turn_range(starts: &[i64], since: Option<i64>, until: i64) -> Option<TurnRange> {
+    let started_by = |time: i64| starts.iter().filter(|start| **start <= time).count();
+    let last = started_by(until);
+    if last == 0 {
+        return None;
+    }
+    let first = since.map_or(1, |since| started_by(since) + 1).min(last);
+    Some(TurnRange {
+        first,
+        last,
+        total: starts.len(),
+    })
+}
+
+pub(crate) fn format_show(report: &ShowReport) -> String {
+    let event = &report.event;
+    let created = DateTime::from_timestamp(event.created_at, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_default();
+    let mut lines = vec![
+        format!("event:   {}", event.id),
+        format!("created: {created}"),
+        format!("status:  {}", event.status()),
+        format!("summary: {}", event.description),
+    ];
+    if let Some(intent) = &event.intent {
+        lines.push(format!("intent:  {}", intent.goal));
+    }
+    if !event.tags.is_empty() {
+        lines.push(format!("tags:    {}", event.tags.join(", ")));
+    }
+    match &report.session {
+        Some(session) => {
+            lines.push(format!("session: {}", session.session_id));
+            if let Some(path) = &session.rollout_path {
+                lines.push(format!("rollout: {path}"));
+            }
+            lines.push(match session.turns {
+                Some(TurnRange { first, last, total }) if first == last => {
+                    format!("turns:   {first} of {total}")
+                }
+                Some(TurnRange { first, last, total }) => {
+                    format!("turns:   {first}-{last} of {total}")
+                }
+                None => "turns:   unknown".to_string(),
+            });
+        }
+        None => lines.push("session: none".to_string()),
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::UserMessageEvent;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn event(id: &str, created_at: i64, rollout_path: &Path) -> CovenantEvent {
+        CovenantEvent {
+            id: id.to_string(),
+            created_at,
+            description: "Build host ran out of disk".to_string(),
+            domain_signature: "[]".to_string(),
+            intent: None,
+            hypotheses: Vec::new(),
+            tests: Vec::new(),
+            outcomes: Vec::new(),
+            tags: vec!["infra".to_string()],
+            session_id: Some("s1".to_string()),
+            rollout_path: Some(rollout_path.display().to_string()),
+        }
+    }
+
+    fn rollout_line(timestamp: i64, item: RolloutItem) -> String {
+        let timestamp = DateTime::from_timestamp(timestamp, 0)
+            .expect("timestamp")
+            .to_rfc3339();
+        serde_json::to_string(&RolloutLine { timestamp, item }).expect("serialize")
+    }
+
+    fn user_message(message: &str) -> RolloutItem {
+        RolloutItem::EventMsg(EventMsg::UserMessage(UserMessageEvent {
+            message: message.to_string(),
+            images: None,
+            local_images: Vec::new(),
+            text_elements: Vec::new(),
+        }))
+    }
+
+    #[test]
+    fn events_cover_the_turns_since_the_previous_capture_of_their_session() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let rollout = dir.path().join("rollout.jsonl");
+        let lines = [
+            rollout_line(110, user_message("the build host is out of disk")),
+            rollout_line(200, user_message("rotate the logs")),
+            rollout_line(300, user_message("prune the images")),
+            rollout_line(400, user_message("capture this")),
+            rollout_line(500, user_message("thanks")),
+        ];
+        std::fs::write(&rollout, lines.join("\n")).expect("write rollout");
+        let first = event("e1", 250, &rollout);
+        let second = event("e2", 420, &rollout);
+        let unlinked = CovenantEvent {
+            session_id: None,
+            rollout_path: None,
+            ..event("e3", 430, &rollout)
+        };
+        let events = vec![first.clone(), second.clone(), unlinked.clone()];
+
+        let report = show_report(second.clone(), &events);
+        assert_eq!(
+            report,
+            ShowReport {
+                event: second,
+                session: Some(LinkedSession {
+                    session_id: "s1".to_string(),
+                    rollout_path: Some(rollout.display().to_string()),
+                    turns: Some(TurnRange {
+                        first: 3,
+                        last: 4,
+                        total: 5,
+                    }),
+                }),
+            }
+        );
+        assert_eq!(
+            format_show(&report),
+            format!(
+                "event:   e2\n\
+                 created: 1970-01-01 00:07:00 UTC\n\
+                 status:  open\n\
+                 summary: Build host ran out of disk\n\
+                 tags:    infra\n\
+                 session: s1\n\
+                 rollout: {}\n\
+                 turns:   3-4 of 5",
+                rollout.display()
+            )
+        );
+        assert_eq!(
+            (
+                show_report(first, &events)
+                    .session
+                    .and_then(|session| session.turns),
+                show_report(unlinked, &events).session,
+                turn_range(&[100, 200], Some(250), 260),
+            ),
+            (
+                Some(TurnRange {
+                    first: 1,
+                    last: 2,
+                    total: 5,
+                }),
+                None,
+                Some(TurnRange {
+                    first: 2,
+                    last: 2,
+                    total: 2,
+                }),
+            )
+        );
+        assert_eq!(
+            serde_json::to_value(&report.session).expect("serialize"),
+            json!({
+                "session_id": "s1",
+                "rollout_path": rollout.display().to_string(),
+                "turns": { "first": 3, "last": 4, "total": 5 },
+            })
+        );
+    }
+}
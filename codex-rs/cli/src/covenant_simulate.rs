@@ -0,0 +1,262 @@
+//! `codex covenant simulate`: replay recorded tool actions against a proposed
+//! covenant to see what it would have denied.
+
+use anyhow::Context;
+use codex_core::covenant::Covenant;
+use codex_core::covenant::CovenantAction;
+use codex_core::covenant::parse_covenant;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use codex_protocol::protocol::SessionSource;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::stats::read_rollout;
+use crate::stats::rollout_stem;
+
+/// Tools whose calls the covenant gates, with the capability each needs.
+/// Mirrors the runtimes that declare a covenant action in `codex-core`.
+const GATED_TOOLS: &[(&str, CovenantAction)] = &[
+    ("shell", CovenantAction::ProposalExecCommand),
+    ("container.exec", CovenantAction::ProposalExecCommand),
+    ("local_shell", CovenantAction::ProposalExecCommand),
+    ("shell_command", CovenantAction::ProposalExecCommand),
+    ("apply_patch", CovenantAction::ProposalApplyPatch),
+    ("capture", CovenantAction::EventLog),
+];
+
+/// One gated tool call recorded in a rollout.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct RecordedAction {
+    pub(crate) session_id: String,
+    pub(crate) call_id: String,
+    pub(crate) tool: String,
+    /// The session source the covenant would have checked the call against.
+    pub(crate) scope: String,
+    pub(crate) capability: String,
+}
+
+/// Recorded actions for one scope and capability, and the calls among them
+/// the proposed covenant does not grant.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct SimulationGroup {
+    pub(crate) scope: String,
+    pub(crate) capability: String,
+    pub(crate) actions: usize,
+    pub(crate) denied: Vec<RecordedAction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct SimulationReport {
+    pub(crate) covenant_version: String,
+    pub(crate) required: bool,
+    pub(crate) actions: usize,
+    pub(crate) denied: usize,
+    /// Sorted by scope, then capability.
+    pub(crate) groups: Vec<SimulationGroup>,
+}
+
+pub(crate) fn read_covenant_file(path: &Path) -> anyhow::Result<Covenant> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    parse_covenant(&contents).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Gated tool calls in one rollout file, in recorded order. Calls before the
+/// session metadata line, or in rollouts without one, use the `unknown` scope.
+pub(crate) fn load_recorded_actions(path: &Path) -> anyhow::Result<Vec<RecordedAction>> {
+    let contents = read_rollout(path)?;
+    let mut session_id = rollout_stem(path);
+    let mut scope = SessionSource::Unknown.to_string();
+    let mut actions = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let rollout_line: RolloutLine = serde_json::from_str(line)
+            .with_context(|| format!("failed to parse {}:{}", path.display(), index + 1))?;
+        let (tool, call_id) = match rollout_line.item {
+            RolloutItem::SessionMeta(meta_line) => {
+                session_id = meta_line.meta.id.to_string();
+                scope = meta_line.meta.source.to_string();
+                continue;
+            }
+            RolloutItem::ResponseItem(ResponseItem::FunctionCall { name, call_id, .. })
+            | RolloutItem::ResponseItem(ResponseItem::CustomToolCall { name, call_id, .. }) => {
+                (name, call_id)
+            }
+            RolloutItem::ResponseItem(ResponseItem::LocalShellCall { call_id, .. }) => {
+                ("local_shell".to_string(), call_id.unwrap_or_default())
+            }
+            RolloutItem::ResponseItem(_)
+            | RolloutItem::TurnContext(_)
+            | RolloutItem::EventMsg(_)
+            | RolloutItem::Compacted(_) => continue,
+        };
+        let Some(action) = gated_action(tool.as_str()) else {
+            continue;
+        };
+        actions.push(RecordedAction {
+            session_id: session_id.clone(),
+            call_id,
+            tool,
+            scope: scope.clone(),
+            capability: action.as_capability().to_string(),
+        });
+    }
+    Ok(actions)
+}
+
+/// Check every recorded action against `covenant` the way a session would,
+/// with `covenant.required` set to `required`.
+pub(crate) fn simulate_covenant(
+    covenant: &Covenant,
+    actions: Vec<RecordedAction>,
+    required: bool,
+) -> SimulationReport {
+    let total = actions.len();
+    let mut groups: BTreeMap<(String, String), SimulationGroup> = BTreeMap::new();
+    for action in actions {
+        let group = groups
+            .entry((action.scope.clone(), action.capability.clone()))
+            .or_insert_with(|| SimulationGroup {
+                scope: action.scope.clone(),
+                capability: action.capability.clone(),
+                actions: 0,
+                denied: Vec::new(),
+            });
+        group.actions += 1;
+        if !covenant.grants(&action.scope, &action.capability, required) {
+            group.denied.push(action);
+        }
+    }
+    let groups = groups.into_values().collect::<Vec<_>>();
+    SimulationReport {
+        covenant_version: covenant.version.clone(),
+        required,
+        actions: total,
+        denied: groups.iter().map(|group| group.denied.len()).sum(),
+        groups,
+    }
+}
+
+pub(crate) fn print_simulation(report: &SimulationReport, rollouts: &[PathBuf]) {
+    println!(
+        "covenant {}: {} of {} recorded actions in {} rollouts would be denied",
+        report.covenant_version,
+        report.denied,
+        report.actions,
+        rollouts.len()
+    );
+    if report.groups.is_empty() {
+        return;
+    }
+    println!();
+    println!(
+        "{:<22} {:<28} {:>7} {:>7}",
+        "SCOPE", "CAPABILITY", "ACTIONS", "DENIED"
+    );
+    for group in &report.groups {
+        println!(
+            "{:<22} {:<28} {:>7} {:>7}",
+            group.scope,
+            group.capability,
+            group.actions,
+            group.denied.len()
+        );
+    }
+    for group in report
+        .groups
+        .iter()
+        .filter(|group| !group.denied.is_empty())
+    {
+        println!();
+        println!("denied {} in {}:", group.capability, group.scope);
+        for action in &group.denied {
+            println!(
+                "  {} {} ({})",
+                action.session_id, action.call_id, action.tool
+            );
+        }
+    }
+}
+
+fn gated_action(tool: &str) -> Option<CovenantAction> {
+    GATED_TOOLS
+        .iter()
+        .find(|(name, _)| *name == tool)
+        .map(|(_, action)| *action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::covenant::CovenantScope;
+    use codex_state::CovenantStorage;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn recorded_actions_are_grouped_by_scope_and_capability() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("rollout-test.jsonl");
+        let lines = [
+            r#"{"timestamp":"2026-01-02T03:04:05.000Z","type":"session_meta","payload":{"id":"67e55044-10b1-426f-9247-bb680e5fe0c8","timestamp":"2026-01-02T03:04:05.000Z","cwd":"/tmp","originator":"codex_exec","cli_version":"0.0.0","source":"exec","model_provider":null,"base_instructions":null}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:06.000Z","type":"response_item","payload":{"type":"function_call","name":"shell","arguments":"{}","call_id":"c1"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:07.000Z","type":"response_item","payload":{"type":"custom_tool_call","name":"apply_patch","input":"*** Begin Patch","call_id":"c2"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:08.000Z","type":"response_item","payload":{"type":"function_call","name":"read_file","arguments":"{}","call_id":"c3"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:09.000Z","type":"response_item","payload":{"type":"function_call","name":"shell","arguments":"{}","call_id":"c4"}}"#,
+        ];
+        std::fs::write(&path, lines.join("\n")).expect("write rollout");
+        let covenant = Covenant {
+            version: "2".to_string(),
+            scopes: vec![CovenantScope {
+                name: "exec".to_string(),
+                template: None,
+                capabilities: vec![
+                    CovenantAction::ProposalExecCommand
+                        .as_capability()
+                        .to_string(),
+                ],
+            }],
+            storage: CovenantStorage::Home,
+        };
+
+        let actions = load_recorded_actions(&path).expect("load actions");
+        let report = simulate_covenant(&covenant, actions, false);
+
+        let denied_patch = RecordedAction {
+            session_id: "67e55044-10b1-426f-9247-bb680e5fe0c8".to_string(),
+            call_id: "c2".to_string(),
+            tool: "apply_patch".to_string(),
+            scope: "exec".to_string(),
+            capability: "proposal.apply_patch".to_string(),
+        };
+        assert_eq!(
+            report,
+            SimulationReport {
+                covenant_version: "2".to_string(),
+                required: false,
+                actions: 3,
+                denied: 1,
+                groups: vec![
+                    SimulationGroup {
+                        scope: "exec".to_string(),
+                        capability: "proposal.apply_patch".to_string(),
+                        actions: 1,
+                        denied: vec![denied_patch],
+                    },
+                    SimulationGroup {
+                        scope: "exec".to_string(),
+                        capability: "proposal.exec_command".to_string(),
+                        actions: 2,
+                        denied: Vec::new(),
+                    },
+                ],
+            }
+        );
+    }
+}
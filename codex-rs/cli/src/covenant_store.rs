@@ -0,0 +1,73 @@
+//! `--store`: which covenant store the covenant, capture, compile, and
+//! patterns commands read and write.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use clap::Args;
+use codex_core::covenant::find_covenant;
+use codex_state::CovenantStorage;
+use codex_state::CovenantStorePaths;
+use codex_state::DEFAULT_PATTERN_SCOPE;
+use codex_state::StateRuntime;
+
+#[derive(Debug, Default, Clone, Args)]
+pub struct StoreArgs {
+    /// Covenant store to use: `home` for `$CODEX_HOME`, or `workspace` for
+    /// `<repo>/.codex/covenant/`. Defaults to the `storage` set in
+    /// covenant.json, then `home`.
+    #[arg(long = "store", value_enum, value_name = "STORE", global = true)]
+    pub store: Option<CovenantStorage>,
+}
+
+impl StoreArgs {
+    /// Resolve the store for a command run from the current directory. The
+    /// covenant is only consulted when `--store` was not passed.
+    pub(crate) async fn resolve(&self, codex_home: &Path) -> anyhow::Result<CovenantStorePaths> {
+        let cwd = std::env::current_dir()?;
+        let storage = match self.store {
+            Some(storage) => storage,
+            // An unreadable covenant is reported by the commands that enforce it.
+            None => find_covenant(cwd.as_path())
+                .await
+                .ok()
+                .flatten()
+                .map(|covenant| covenant.storage)
+                .unwrap_or_default(),
+        };
+        Ok(CovenantStorePaths::resolve(
+            storage,
+            codex_home,
+            cwd.as_path(),
+        ))
+    }
+}
+
+/// `--scope` and `--all-scopes`: which covenant scope's live patterns a
+/// patterns command works with.
+#[derive(Debug, Clone, Args)]
+pub struct PatternScopeArgs {
+    /// Covenant scope whose patterns to use.
+    #[arg(long, value_name = "SCOPE", default_value = DEFAULT_PATTERN_SCOPE)]
+    pub scope: String,
+
+    /// Use the patterns of every scope.
+    #[arg(long, default_value_t = false, conflicts_with = "scope")]
+    pub all_scopes: bool,
+}
+
+impl PatternScopeArgs {
+    /// The scope to load patterns from, or `None` for every scope.
+    pub(crate) fn selected(&self) -> Option<&str> {
+        (!self.all_scopes).then_some(self.scope.as_str())
+    }
+}
+
+/// Open (and migrate) the state database of `store`.
+pub(crate) async fn open_covenant_store(
+    store: &CovenantStorePaths,
+) -> anyhow::Result<Arc<StateRuntime>> {
+    // The provider is only used for thread metadata, which the covenant
+    // commands never read.
+    StateRuntime::init_covenant_store(store, "openai".to_string(), None).await
+}
@@ -0,0 +1,269 @@
+//! `codex covenant usage`: how often enforcement checked each capability, per
+//! scope and over time, and which granted capabilities were never exercised.
+
+use chrono::DateTime;
+use clap::ValueEnum;
+use codex_core::covenant::Covenant;
+use codex_state::CovenantCheckCount;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Days from the Monday that starts a week to the Thursday of 1970-01-01.
+const EPOCH_WEEKDAY_OFFSET: i64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum UsagePeriod {
+    /// One row per UTC day.
+    Day,
+    /// One row per week, starting on Monday.
+    Week,
+}
+
+impl UsagePeriod {
+    fn start(self, day: i64) -> i64 {
+        match self {
+            Self::Day => day,
+            Self::Week => {
+                let days = day.div_euclid(SECONDS_PER_DAY);
+                day - (days + EPOCH_WEEKDAY_OFFSET).rem_euclid(7) * SECONDS_PER_DAY
+            }
+        }
+    }
+}
+
+/// Checks of one capability in one scope.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct CapabilityUsage {
+    pub(crate) scope: String,
+    pub(crate) capability: String,
+    pub(crate) allowed: i64,
+    pub(crate) denied: i64,
+    /// Start of the latest UTC day with a check.
+    pub(crate) last_checked: i64,
+}
+
+/// Checks of one capability in one scope during one period.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct PeriodUsage {
+    /// Start of the period, in seconds since the epoch.
+    pub(crate) period: i64,
+    pub(crate) scope: String,
+    pub(crate) capability: String,
+    pub(crate) allowed: i64,
+    pub(crate) denied: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct GrantRef {
+    pub(crate) scope: String,
+    pub(crate) capability: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct UsageReport {
+    pub(crate) period: UsagePeriod,
+    /// Sorted by scope, then capability.
+    pub(crate) capabilities: Vec<CapabilityUsage>,
+    /// Oldest period first, then by scope and capability.
+    pub(crate) periods: Vec<PeriodUsage>,
+    /// Capabilities the covenant grants that were never checked in the
+    /// window, or empty when no covenant was found.
+    pub(crate) never_checked: Vec<GrantRef>,
+}
+
+pub(crate) fn usage_report(
+    counts: &[CovenantCheckCount],
+    covenant: Option<&Covenant>,
+    period: UsagePeriod,
+) -> UsageReport {
+    let mut capabilities = BTreeMap::<(String, String), CapabilityUsage>::new();
+    let mut periods = BTreeMap::<(i64, String, String), PeriodUsage>::new();
+    for count in counts {
+        let key = (count.scope.clone(), count.capability.clone());
+        let usage = capabilities.entry(key).or_insert_with(|| CapabilityUsage {
+            scope: count.scope.clone(),
+            capability: count.capability.clone(),
+            allowed: 0,
+            denied: 0,
+            last_checked: count.day,
+        });
+        usage.allowed += count.allowed;
+        usage.denied += count.denied;
+        usage.last_checked = usage.last_checked.max(count.day);
+
+        let start = period.start(count.day);
+        let usage = periods
+            .entry((start, count.scope.clone(), count.capability.clone()))
+            .or_insert_with(|| PeriodUsage {
+                period: start,
+                scope: count.scope.clone(),
+                capability: count.capability.clone(),
+                allowed: 0,
+                denied: 0,
+            });
+        usage.allowed += count.allowed;
+        usage.denied += count.denied;
+    }
+
+    let never_checked = covenant
+        .map(|covenant| {
+            covenant
+                .scopes
+                .iter()
+                .flat_map(|scope| {
+                    scope
+                        .capabilities
+                        .iter()
+                        .map(|capability| (scope.name.clone(), capability.clone()))
+                })
+                .filter(|key| !capabilities.contains_key(key))
+                .collect::<BTreeSet<_>>()
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(scope, capability)| GrantRef { scope, capability })
+        .collect();
+    UsageReport {
+        period,
+        capabilities: capabilities.into_values().collect(),
+        periods: periods.into_values().collect(),
+        never_checked,
+    }
+}
+
+pub(crate) fn print_usage(report: &UsageReport) {
+    if report.capabilities.is_empty() {
+        println!("no covenant checks recorded");
+    } else {
+        println!(
+            "{:<22} {:<28} {:>8} {:>8} {:>12}",
+            "SCOPE", "CAPABILITY", "ALLOWED", "DENIED", "LAST CHECKED"
+        );
+        for usage in &report.capabilities {
+            println!(
+                "{:<22} {:<28} {:>8} {:>8} {:>12}",
+                usage.scope,
+                usage.capability,
+                usage.allowed,
+                usage.denied,
+                format_day(usage.last_checked)
+            );
+        }
+        println!();
+        let label = match report.period {
+            UsagePeriod::Day => "DAY",
+            UsagePeriod::Week => "WEEK OF",
+        };
+        println!(
+            "{label:<12} {:<22} {:<28} {:>8} {:>8}",
+            "SCOPE", "CAPABILITY", "ALLOWED", "DENIED"
+        );
+        for usage in &report.periods {
+            println!(
+                "{:<12} {:<22} {:<28} {:>8} {:>8}",
+                format_day(usage.period),
+                usage.scope,
+                usage.capability,
+                usage.allowed,
+                usage.denied
+            );
+        }
+    }
+    if !report.never_checked.is_empty() {
+        println!();
+        println!("granted but never checked:");
+        for grant in &report.never_checked {
+            println!("  {} {}", grant.scope, grant.capability);
+        }
+    }
+}
+
+fn format_day(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|time| time.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::covenant::CovenantScope;
+    use codex_state::CovenantStorage;
+    use pretty_assertions::assert_eq;
+
+    fn count(day: i64, capability: &str, allowed: i64, denied: i64) -> CovenantCheckCount {
+        CovenantCheckCount {
+            day: day * SECONDS_PER_DAY,
+            scope: "cli".to_string(),
+            capability: capability.to_string(),
+            allowed,
+            denied,
+        }
+    }
+
+    #[test]
+    fn usage_totals_capabilities_and_groups_them_by_week() {
+        // Day 4 is Monday 1970-01-05, so days 0-3 fall in the week before.
+        let counts = [
+            count(2, "proposal.exec_command", 3, 1),
+            count(4, "proposal.exec_command", 2, 0),
+            count(5, "proposal.exec_command", 1, 0),
+            count(5, "event.log", 0, 2),
+        ];
+        let covenant = Covenant {
+            version: "1".to_string(),
+            scopes: vec![CovenantScope {
+                name: "cli".to_string(),
+                template: None,
+                capabilities: vec![
+                    "proposal.exec_command".to_string(),
+                    "proposal.apply_patch".to_string(),
+                ],
+            }],
+            storage: CovenantStorage::Home,
+        };
+        let period = |start: i64, capability: &str, allowed, denied| PeriodUsage {
+            period: start * SECONDS_PER_DAY,
+            scope: "cli".to_string(),
+            capability: capability.to_string(),
+            allowed,
+            denied,
+        };
+
+        assert_eq!(
+            usage_report(&counts, Some(&covenant), UsagePeriod::Week),
+            UsageReport {
+                period: UsagePeriod::Week,
+                capabilities: vec![
+                    CapabilityUsage {
+                        scope: "cli".to_string(),
+                        capability: "event.log".to_string(),
+                        allowed: 0,
+                        denied: 2,
+                        last_checked: 5 * SECONDS_PER_DAY,
+                    },
+                    CapabilityUsage {
+                        scope: "cli".to_string(),
+                        capability: "proposal.exec_command".to_string(),
+                        allowed: 6,
+                        denied: 1,
+                        last_checked: 5 * SECONDS_PER_DAY,
+                    },
+                ],
+                periods: vec![
+                    period(-3, "proposal.exec_command", 3, 1),
+                    period(4, "event.log", 0, 2),
+                    period(4, "proposal.exec_command", 3, 0),
+                ],
+                never_checked: vec![GrantRef {
+                    scope: "cli".to_string(),
+                    capability: "proposal.apply_patch".to_string(),
+                }],
+            }
+        );
+    }
+}
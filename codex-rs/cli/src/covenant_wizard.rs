@@ -0,0 +1,328 @@
+//! `codex covenant wizard`: log a covenant event by answering prompts instead
+//! of passing every field to `handshakeos-e log`.
+
+use codex_core::covenant::Covenant;
+use codex_core::covenant::CovenantAction;
+use codex_state::CovenantEvent;
+use codex_state::CovenantHypothesis;
+use codex_state::CovenantIntent;
+use std::io::BufRead;
+use std::io::Write;
+
+/// Confidence given to an intent when the answer is left blank.
+const DEFAULT_CONFIDENCE: f64 = 0.5;
+
+/// Scopes whose covenant entry grants `event.log`, with `preferred` first
+/// when it is one of them.
+pub(crate) fn event_log_scopes(covenant: &Covenant, preferred: &str) -> Vec<String> {
+    let capability = CovenantAction::EventLog.as_capability();
+    let mut scopes = covenant
+        .scopes
+        .iter()
+        .filter(|scope| covenant.allows(scope.name.as_str(), capability))
+        .map(|scope| scope.name.clone())
+        .collect::<Vec<_>>();
+    if let Some(index) = scopes.iter().position(|scope| scope == preferred) {
+        let scope = scopes.remove(index);
+        scopes.insert(0, scope);
+    }
+    scopes
+}
+
+/// Walk through the scope, summary, optional intent, and optional first
+/// hypothesis of a new event. `scopes` are offered in order and the first is
+/// the default. Returns the chosen scope and the event, or `None` when input
+/// ends or the event is not confirmed.
+pub(crate) fn prompt_event<R: BufRead, W: Write>(
+    scopes: &[String],
+    event_id: String,
+    created_at: i64,
+    input: &mut R,
+    output: &mut W,
+) -> anyhow::Result<Option<(String, CovenantEvent)>> {
+    let Some(default_scope) = scopes.first() else {
+        anyhow::bail!("no covenant scope grants event.log");
+    };
+    let scope = loop {
+        let Some(answer) = ask(
+            input,
+            output,
+            &format!("scope [{default_scope}] ({})", scopes.join(", ")),
+        )?
+        else {
+            return Ok(None);
+        };
+        if answer.is_empty() {
+            break default_scope.clone();
+        }
+        if scopes.contains(&answer) {
+            break answer;
+        }
+        writeln!(
+            output,
+            "scope '{answer}' cannot log events; choose one of: {}",
+            scopes.join(", ")
+        )?;
+    };
+
+    let description = loop {
+        let Some(answer) = ask(input, output, "summary")? else {
+            return Ok(None);
+        };
+        if !answer.is_empty() {
+            break answer;
+        }
+        writeln!(output, "a summary is required")?;
+    };
+
+    let Some(goal) = ask(input, output, "intent goal (blank to skip)")? else {
+        return Ok(None);
+    };
+    let intent = if goal.is_empty() {
+        None
+    } else {
+        let Some(constraints) = ask(input, output, "constraints")? else {
+            return Ok(None);
+        };
+        let Some(success_signal) = ask(input, output, "success signal")? else {
+            return Ok(None);
+        };
+        let Some(confidence) = ask_probability(
+            input,
+            output,
+            &format!("confidence (0-1 or 0-100%) [{DEFAULT_CONFIDENCE}]"),
+            Some(DEFAULT_CONFIDENCE),
+        )?
+        else {
+            return Ok(None);
+        };
+        Some(CovenantIntent {
+            id: format!("{event_id}:intent"),
+            goal,
+            constraints,
+            success_signal,
+            confidence,
+        })
+    };
+
+    let Some(statement) = ask(input, output, "hypothesis (blank to skip)")? else {
+        return Ok(None);
+    };
+    let mut hypotheses = Vec::new();
+    if !statement.is_empty() {
+        let Some(probability) =
+            ask_probability(input, output, "probability (0-1 or 0-100%)", None)?
+        else {
+            return Ok(None);
+        };
+        let Some(falsifiers) = ask(input, output, "falsifiers (comma separated)")? else {
+            return Ok(None);
+        };
+        hypotheses.push(CovenantHypothesis {
+            id: format!("{event_id}:H1"),
+            model_type: statement,
+            probability,
+            falsifiers: falsifiers
+                .split(',')
+                .map(str::trim)
+                .filter(|falsifier| !falsifier.is_empty())
+                .map(str::to_string)
+                .collect(),
+            domain_signature: "[]".to_string(),
+        });
+    }
+
+    writeln!(output, "scope:      {scope}")?;
+    writeln!(output, "summary:    {description}")?;
+    if let Some(intent) = &intent {
+        writeln!(
+            output,
+            "intent:     {} (confidence {:.2})",
+            intent.goal, intent.confidence
+        )?;
+    }
+    for hypothesis in &hypotheses {
+        writeln!(
+            output,
+            "hypothesis: {} (p={:.2})",
+            hypothesis.model_type, hypothesis.probability
+        )?;
+    }
+    let Some(confirm) = ask(input, output, "log this event? [Y/n]")? else {
+        return Ok(None);
+    };
+    if !matches!(confirm.to_lowercase().as_str(), "" | "y" | "yes") {
+        return Ok(None);
+    }
+
+    Ok(Some((
+        scope,
+        CovenantEvent {
+            id: event_id,
+            created_at,
+            description,
+            domain_signature: "[]".to_string(),
+            intent,
+            hypotheses,
+            tests: Vec::new(),
+            outcomes: Vec::new(),
+            tags: Vec::new(),
+            session_id: None,
+            rollout_path: None,
+        },
+    )))
+}
+
+/// Ask for a probability until the answer parses, falling back to `default`
+/// for a blank answer when there is one.
+fn ask_probability<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    question: &str,
+    default: Option<f64>,
+) -> anyhow::Result<Option<f64>> {
+    loop {
+        let Some(answer) = ask(input, output, question)? else {
+            return Ok(None);
+        };
+        if answer.is_empty()
+            && let Some(default) = default
+        {
+            return Ok(Some(default));
+        }
+        match parse_probability(&answer) {
+            Some(probability) => return Ok(Some(probability)),
+            None => writeln!(output, "expected a number from 0 to 1, or a percentage")?,
+        }
+    }
+}
+
+/// `0.7` and `70%` are both 0.7.
+fn parse_probability(answer: &str) -> Option<f64> {
+    let (number, scale) = match answer.strip_suffix('%') {
+        Some(percent) => (percent.trim(), 100.0),
+        None => (answer, 1.0),
+    };
+    let value = number.parse::<f64>().ok()? / scale;
+    (0.0..=1.0).contains(&value).then_some(value)
+}
+
+fn ask<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    question: &str,
+) -> anyhow::Result<Option<String>> {
+    write!(output, "{question}: ")?;
+    output.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::covenant::CovenantScope;
+    use codex_state::CovenantStorage;
+    use pretty_assertions::assert_eq;
+    use std::io::Cursor;
+
+    #[test]
+    fn wizard_builds_an_event_from_answers() {
+        let covenant = Covenant {
+            version: "1".to_string(),
+            scopes: vec![
+                CovenantScope {
+                    name: "exec".to_string(),
+                    template: None,
+                    capabilities: vec!["event.log".to_string()],
+                },
+                CovenantScope {
+                    name: "mcp".to_string(),
+                    template: None,
+                    capabilities: vec!["proposal.exec_command".to_string()],
+                },
+                CovenantScope {
+                    name: "cli".to_string(),
+                    template: None,
+                    capabilities: vec!["event.log".to_string()],
+                },
+            ],
+            storage: CovenantStorage::Home,
+        };
+        let scopes = event_log_scopes(&covenant, "cli");
+        let answers = [
+            "mcp",
+            "",
+            "",
+            "Deploy failed on staging",
+            "Ship the release",
+            "no downtime",
+            "health checks pass",
+            "80%",
+            "Migration locks the table",
+            "1.5",
+            "0.6",
+            "checks pass before migrating, lock is absent",
+            "y",
+        ];
+        let mut input = Cursor::new(answers.join("\n"));
+        let mut output = Vec::new();
+
+        let logged = prompt_event(&scopes, "evt".to_string(), 7, &mut input, &mut output)
+            .expect("prompt event");
+
+        assert_eq!(
+            (scopes.clone(), logged),
+            (
+                vec!["cli".to_string(), "exec".to_string()],
+                Some((
+                    "cli".to_string(),
+                    CovenantEvent {
+                        id: "evt".to_string(),
+                        created_at: 7,
+                        description: "Deploy failed on staging".to_string(),
+                        domain_signature: "[]".to_string(),
+                        intent: Some(CovenantIntent {
+                            id: "evt:intent".to_string(),
+                            goal: "Ship the release".to_string(),
+                            constraints: "no downtime".to_string(),
+                            success_signal: "health checks pass".to_string(),
+                            confidence: 0.8,
+                        }),
+                        hypotheses: vec![CovenantHypothesis {
+                            id: "evt:H1".to_string(),
+                            model_type: "Migration locks the table".to_string(),
+                            probability: 0.6,
+                            falsifiers: vec![
+                                "checks pass before migrating".to_string(),
+                                "lock is absent".to_string(),
+                            ],
+                            domain_signature: "[]".to_string(),
+                        }],
+                        tests: Vec::new(),
+                        outcomes: Vec::new(),
+                        tags: Vec::new(),
+                        session_id: None,
+                        rollout_path: None,
+                    },
+                )),
+            )
+        );
+
+        let mut declined = Cursor::new("exec\nFlaky test\n\n\nn\n");
+        assert_eq!(
+            prompt_event(
+                &scopes,
+                "evt".to_string(),
+                7,
+                &mut declined,
+                &mut Vec::new()
+            )
+            .expect("prompt event"),
+            None
+        );
+    }
+}
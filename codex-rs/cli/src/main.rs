@@ -33,16 +33,45 @@ use supports_color::Stream;
 
 #[cfg(target_os = "macos")]
 mod app_cmd;
+mod capture_cmd;
+mod capture_import;
+mod capture_list;
+mod compile_cmd;
+mod covenant_cmd;
+mod covenant_show;
+mod covenant_simulate;
+mod covenant_store;
+mod covenant_usage;
+mod covenant_wizard;
 #[cfg(target_os = "macos")]
 mod desktop_app;
 mod mcp_cmd;
+mod patterns_list;
+mod patterns_match;
+mod patterns_rebuild;
+mod patterns_review;
+mod patterns_serve;
+mod patterns_sync;
+mod schema_cmd;
+mod stats;
 #[cfg(not(windows))]
 mod wsl_paths;
-mod patterns_match;
 
+use crate::capture_cmd::CaptureCli;
+use crate::capture_cmd::run_capture;
+use crate::compile_cmd::CompileCommand;
+use crate::compile_cmd::run_compile;
+use crate::covenant_cmd::CovenantCli;
+use crate::covenant_cmd::run_covenant;
 use crate::mcp_cmd::McpCli;
 use crate::patterns_match::PatternsMatchCommand;
 use crate::patterns_match::run_patterns_match;
+use crate::patterns_review::PatternsCli;
+use crate::patterns_review::run_patterns;
+use crate::schema_cmd::SchemaCli;
+use crate::schema_cmd::run_schema;
+use crate::stats::StatsCommand;
+use crate::stats::run_stats;
 
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
@@ -50,6 +79,7 @@ use codex_core::config::edit::ConfigEditsBuilder;
 use codex_core::config::find_codex_home;
 use codex_core::features::Stage;
 use codex_core::features::is_known_feature_key;
+use codex_core::pattern_match::install_text_normalizer;
 use codex_core::terminal::TerminalName;
 
 /// Codex CLI
@@ -130,6 +160,24 @@ enum Subcommand {
     #[clap(name = "patterns-match")]
     PatternsMatch(PatternsMatchCommand),
 
+    /// Review compiled pattern suggestions and record feedback on matches.
+    Patterns(PatternsCli),
+
+    /// Summarize intent/outcome statistics across recorded sessions.
+    Stats(StatsCommand),
+
+    /// Compile resolved covenant events into the pattern log.
+    Compile(CompileCommand),
+
+    /// Manage the covenant.json that scopes agent and user actions.
+    Covenant(CovenantCli),
+
+    /// Export JSON Schemas for capture records, patterns, covenant events, and stats.
+    Schema(SchemaCli),
+
+    /// Export probability update timelines from recorded captures, or import postmortems.
+    Capture(CaptureCli),
+
     /// Resume a previous interactive session (picker by default; use --last to continue the most recent).
     Resume(ResumeCommand),
 
@@ -568,6 +616,14 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
     let toggle_overrides = feature_toggles.to_overrides()?;
     root_config_overrides.raw_overrides.extend(toggle_overrides);
 
+    // Tokenizer settings apply to every subcommand that compares text. A bad
+    // `[tokenizer]` table should not keep unrelated subcommands from running.
+    if let Ok(codex_home) = find_codex_home()
+        && let Err(err) = install_text_normalizer(codex_home.as_path())
+    {
+        eprintln!("WARNING: ignoring tokenizer settings: {err:#}");
+    }
+
     match subcommand {
         None => {
             prepend_config_flags(
@@ -768,7 +824,26 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             run_apply_command(apply_cli, None).await?;
         }
         Some(Subcommand::PatternsMatch(cmd)) => {
-            run_patterns_match(cmd)?;
+            run_patterns_match(cmd).await?;
+        }
+        Some(Subcommand::Patterns(cli)) => {
+            run_patterns(cli).await?;
+        }
+        Some(Subcommand::Stats(cmd)) => {
+            run_stats(cmd)?;
+        }
+        Some(Subcommand::Compile(cmd)) => {
+            run_compile(cmd).await?;
+        }
+        Some(Subcommand::Covenant(mut cli)) => {
+            prepend_config_flags(&mut cli.config_overrides, root_config_overrides.clone());
+            run_covenant(cli).await?;
+        }
+        Some(Subcommand::Schema(cli)) => {
+            run_schema(cli)?;
+        }
+        Some(Subcommand::Capture(cli)) => {
+            run_capture(cli).await?;
         }
         Some(Subcommand::ResponsesApiProxy(args)) => {
             tokio::task::spawn_blocking(move || codex_responses_api_proxy::run_main(args))
@@ -1392,6 +1467,43 @@ mod tests {
         assert!(parse_result.is_err());
     }
 
+    #[test]
+    fn patterns_match_takes_the_event_from_a_file_stdin_or_inline_json() {
+        let event_args = |args: &[&str]| {
+            let cli = MultitoolCli::try_parse_from(args).expect("parse should succeed");
+            let Some(Subcommand::PatternsMatch(cmd)) = cli.subcommand else {
+                panic!("expected patterns-match subcommand");
+            };
+            (cmd.event, cmd.event_json)
+        };
+
+        assert_eq!(
+            event_args(&["codex", "patterns-match", "--event", "-"]),
+            (Some(PathBuf::from("-")), None)
+        );
+        assert_eq!(
+            event_args(&[
+                "codex",
+                "patterns-match",
+                "--event-json",
+                r#"{"trigger":"t"}"#
+            ]),
+            (None, Some(r#"{"trigger":"t"}"#.to_string()))
+        );
+        assert!(MultitoolCli::try_parse_from(["codex", "patterns-match"]).is_err());
+        assert!(
+            MultitoolCli::try_parse_from([
+                "codex",
+                "patterns-match",
+                "--event",
+                "event.json",
+                "--event-json",
+                "{}",
+            ])
+            .is_err()
+        );
+    }
+
     #[test]
     fn features_enable_parses_feature_name() {
         let cli = MultitoolCli::try_parse_from(["codex", "features", "enable", "unified_exec"])
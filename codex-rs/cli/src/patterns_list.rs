@@ -0,0 +1,98 @@
+//! `codex patterns list`: the live patterns of the covenant store, oldest
+//! first, optionally narrowed by a filter expression.
+
+use anyhow::Context;
+use chrono::DateTime;
+use clap::Parser;
+use codex_core::config::find_codex_home;
+use codex_core::store_query::Filter;
+use codex_core::store_query::filter_records;
+use codex_state::CovenantPattern;
+
+use crate::covenant_store::PatternScopeArgs;
+use crate::covenant_store::StoreArgs;
+use crate::covenant_store::open_covenant_store;
+
+#[derive(Debug, Parser)]
+pub struct PatternsListCommand {
+    /// Only patterns passing this filter, such as
+    /// `namespace ~ acme AND anti_pattern = false`.
+    #[arg(long = "where", value_name = "FILTER")]
+    pub filter: Option<String>,
+
+    /// Print the patterns as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+
+    #[clap(flatten)]
+    pub scope: PatternScopeArgs,
+}
+
+pub async fn run_patterns_list(cmd: PatternsListCommand, store: &StoreArgs) -> anyhow::Result<()> {
+    let filter = cmd
+        .filter
+        .as_deref()
+        .map(|filter| Filter::parse::<CovenantPattern>(filter).context("invalid --where filter"))
+        .transpose()?;
+    let store = store.resolve(find_codex_home()?.as_path()).await?;
+    let runtime = open_covenant_store(&store).await?;
+    let patterns = runtime.list_covenant_patterns(cmd.scope.selected()).await?;
+    let patterns = filter_records(patterns, filter.as_ref());
+    if cmd.json {
+        println!("{}", serde_json::to_string_pretty(&patterns)?);
+        return Ok(());
+    }
+    if patterns.is_empty() {
+        println!("no patterns found");
+    }
+    for pattern in &patterns {
+        println!("{}", format_pattern(pattern));
+    }
+    Ok(())
+}
+
+/// One line per pattern: creation date, id, namespace, and the trigger with
+/// its best response. Anti-patterns are marked, since their response is the
+/// one to avoid.
+fn format_pattern(pattern: &CovenantPattern) -> String {
+    let date = DateTime::from_timestamp(pattern.created_at, 0)
+        .map(|time| time.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let marker = if pattern.anti_pattern { "avoid: " } else { "" };
+    format!(
+        "{date}  {}  [{}]  {} -> {marker}{}",
+        pattern.id, pattern.namespace, pattern.trigger, pattern.best_response
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn patterns_print_one_line_each() {
+        let pattern = CovenantPattern {
+            id: "p1".to_string(),
+            trigger: "Disk full on {path}".to_string(),
+            invariant: "Logs rotate".to_string(),
+            counterexample: String::new(),
+            best_response: "Delete the cache".to_string(),
+            domain_signature: String::new(),
+            evidence_refs: Vec::new(),
+            namespace: "github.com/acme/app".to_string(),
+            scope: "default".to_string(),
+            created_at: 1_717_200_000,
+            anti_pattern: true,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+            supports: 0,
+            contradictions: 0,
+        };
+
+        assert_eq!(
+            format_pattern(&pattern),
+            "2024-06-01  p1  [github.com/acme/app]  Disk full on {path} -> avoid: Delete the cache"
+        );
+    }
+}
@@ -1,43 +1,563 @@
 use anyhow::Context;
+use chrono::DateTime;
 use clap::Parser;
+use codex_core::config::find_codex_home;
+use codex_core::domain_signature::signature_provider;
+use codex_core::pattern_match::MatchOptions;
+use codex_core::pattern_match::MatchPage;
 use codex_core::pattern_match::PatternDefinition;
 use codex_core::pattern_match::PatternMatchEvent;
-use codex_core::pattern_match::rank_patterns;
+use codex_core::pattern_match::PatternMatchResult;
+use codex_core::pattern_match::ScoringProfile;
+use codex_core::pattern_match::fill_missing_signatures;
+use codex_core::pattern_match::load_scoring_profile;
+use codex_core::pattern_match::load_signature_config;
+use codex_core::pattern_match::patterns_in_namespace;
+use codex_core::pattern_match::patterns_with_tags;
+use codex_core::pattern_match::rank_pattern_page;
+use codex_core::pattern_usage::PATTERN_USAGE_FILENAME;
+use codex_core::pattern_usage::PatternRollingStats;
+use codex_core::pattern_usage::PatternUsageRecord;
+use codex_core::pattern_usage::append_usage;
+use codex_core::pattern_usage::read_usage;
+use codex_core::pattern_usage::rolling_stats;
+use codex_core::patterns::Outcome;
+use codex_core::patterns::PatternProvenance;
+use codex_state::CovenantStorePaths;
+use codex_state::StoreCipher;
+use codex_state::normalize_event_tags;
+use codex_state::project_pattern_namespace;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 
+use crate::compile_cmd::PATTERNS_LOG_FILENAME;
+use crate::compile_cmd::read_patterns_log;
+use crate::compile_cmd::unix_timestamp;
+use crate::covenant_store::PatternScopeArgs;
+use crate::covenant_store::StoreArgs;
+use crate::covenant_store::open_covenant_store;
+
 #[derive(Debug, Parser)]
 pub struct PatternsMatchCommand {
-    /// JSON file containing an array of stored patterns.
+    /// JSON file containing an array of stored patterns, or `-` to read it
+    /// from stdin. Defaults to the reviewed patterns in the state database.
     #[arg(long, value_name = "FILE")]
-    pub patterns: PathBuf,
+    pub patterns: Option<PathBuf>,
 
-    /// JSON file describing the event to match.
-    #[arg(long, value_name = "FILE")]
-    pub event: PathBuf,
+    /// JSON file describing the event to match, or `-` to read it from stdin.
+    #[arg(
+        long,
+        value_name = "FILE",
+        required_unless_present = "event_json",
+        conflicts_with = "event_json"
+    )]
+    pub event: Option<PathBuf>,
+
+    /// The event to match as inline JSON.
+    #[arg(long, value_name = "JSON")]
+    pub event_json: Option<String>,
 
     /// Maximum number of matches to print.
-    #[arg(long, default_value_t = 5)]
+    #[arg(long, visible_alias = "top-k", default_value_t = 5)]
     pub limit: usize,
+
+    /// Skip this many of the best matches, to print a later page.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub offset: usize,
+
+    /// Skip matches whose total score is below this threshold (0-1).
+    #[arg(long, value_name = "SCORE", default_value_t = 0.0)]
+    pub min_score: f64,
+
+    /// Skip matches whose domain signature does not overlap the event's.
+    #[arg(long, default_value_t = false)]
+    pub require_domain_overlap: bool,
+
+    /// Scoring preset to use instead of `CODEX_HOME/patterns.toml`
+    /// (balanced, lexical-heavy, domain-heavy).
+    #[arg(long, value_name = "NAME")]
+    pub scoring_profile: Option<String>,
+
+    /// Match against patterns from every project instead of only the current one.
+    #[arg(long, default_value_t = false)]
+    pub global: bool,
+
+    /// Scale down patterns whose confidence is below this value (0-1).
+    #[arg(long, value_name = "CONFIDENCE")]
+    pub min_confidence: Option<f64>,
+
+    /// Do not append the matches to `$CODEX_HOME/pattern_usage.jsonl`.
+    #[arg(long, default_value_t = false)]
+    pub no_record: bool,
+
+    /// Under each match, list the events the pattern was compiled from and
+    /// where each was read.
+    #[arg(long, default_value_t = false)]
+    pub explain: bool,
+
+    /// Print the matches as JSON, with the tokens and signature dimensions
+    /// behind each score.
+    #[arg(long, default_value_t = false, conflicts_with = "explain")]
+    pub json: bool,
+
+    /// Only match patterns compiled from events with this tag; repeat to
+    /// require several.
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    // Which scope's live patterns to match; ignored with `--patterns`.
+    #[clap(flatten)]
+    pub scope: PatternScopeArgs,
+
+    #[clap(flatten)]
+    pub store: StoreArgs,
+}
+
+pub async fn run_patterns_match(cmd: PatternsMatchCommand) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !(cmd.patterns.as_deref().is_some_and(is_stdin)
+            && cmd.event.as_deref().is_some_and(is_stdin)),
+        "--patterns and --event cannot both read from stdin"
+    );
+    let store = cmd.store.resolve(find_codex_home()?.as_path()).await?;
+    let patterns = match &cmd.patterns {
+        Some(path) => read_json(path)?,
+        None => load_live_patterns(&store, &cmd.scope).await?,
+    };
+    let mut event: PatternMatchEvent = match (&cmd.event, &cmd.event_json) {
+        (_, Some(json)) => {
+            serde_json::from_str(json).context("failed to parse JSON from --event-json")?
+        }
+        (Some(path), None) => read_json(path)?,
+        (None, None) => anyhow::bail!("pass --event or --event-json"),
+    };
+    let settings = MatchSettings {
+        namespace: if cmd.global {
+            None
+        } else {
+            Some(project_pattern_namespace(
+                std::env::current_dir()?.as_path(),
+            ))
+        },
+        tags: cmd.tags.clone(),
+        limit: cmd.limit,
+        offset: cmd.offset,
+        min_score: cmd.min_score,
+        require_domain_overlap: cmd.require_domain_overlap,
+        scoring_profile: cmd.scoring_profile.clone(),
+        min_confidence: cmd.min_confidence,
+    };
+    let codex_home = find_codex_home()?;
+    let cipher = StoreCipher::load()?;
+    let (patterns, page) = match_event(
+        codex_home.as_path(),
+        &mut event,
+        patterns,
+        &settings,
+        cipher.as_ref(),
+    )?;
+    if let Some(next_offset) = page.next_offset {
+        eprintln!(
+            "showing matches {}-{next_offset} of {}; pass --offset {next_offset} for more",
+            cmd.offset + 1,
+            page.total_candidates
+        );
+    }
+    let results = page.results;
+    let provenance = if cmd.explain {
+        pattern_provenance(
+            &patterns,
+            store.db_dir.join(PATTERNS_LOG_FILENAME).as_path(),
+            cipher.as_ref(),
+        )?
+    } else {
+        HashMap::new()
+    };
+    let explanation = |result: &PatternMatchResult| {
+        if cmd.explain {
+            format_provenance(provenance.get(&result.pattern_id))
+        } else {
+            String::new()
+        }
+    };
+    if cmd.no_record {
+        if cmd.json {
+            return print_json_matches(&results, None);
+        }
+        for result in &results {
+            println!(
+                "{} {}{}{}",
+                result.pattern_id,
+                result.rationale,
+                format_bindings(result),
+                explanation(result)
+            );
+        }
+        return Ok(());
+    }
+
+    let records = record_matches(codex_home.as_path(), &event, &results, cipher.as_ref())?;
+    if cmd.json {
+        print_json_matches(&results, Some(&records))?;
+    } else {
+        for (result, record) in results.iter().zip(&records) {
+            println!(
+                "{} {}{} match={}{}",
+                result.pattern_id,
+                result.rationale,
+                format_bindings(result),
+                record.match_id(),
+                explanation(result)
+            );
+        }
+    }
+    Ok(())
 }
 
-pub fn run_patterns_match(cmd: PatternsMatchCommand) -> anyhow::Result<()> {
-    let patterns: Vec<PatternDefinition> = read_json(&cmd.patterns)?;
-    let event: PatternMatchEvent = read_json(&cmd.event)?;
+/// How to match an event, shared by `codex patterns-match` and
+/// `codex patterns serve`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct MatchSettings {
+    /// Only match patterns visible from this namespace; `None` matches every
+    /// namespace.
+    pub(crate) namespace: Option<String>,
+    pub(crate) tags: Vec<String>,
+    pub(crate) limit: usize,
+    pub(crate) offset: usize,
+    pub(crate) min_score: f64,
+    pub(crate) require_domain_overlap: bool,
+    pub(crate) scoring_profile: Option<String>,
+    pub(crate) min_confidence: Option<f64>,
+}
 
-    let results = rank_patterns(&event, &patterns, cmd.limit);
-    for result in results {
-        println!("{} {}", result.pattern_id, result.rationale);
+impl Default for MatchSettings {
+    fn default() -> Self {
+        Self {
+            namespace: None,
+            tags: Vec::new(),
+            limit: 5,
+            offset: 0,
+            min_score: 0.0,
+            require_domain_overlap: false,
+            scoring_profile: None,
+            min_confidence: None,
+        }
     }
+}
+
+/// Rank `patterns` against `event` with the scoring profile, signature
+/// provider, and usage history of `codex_home`, whose usage log `cipher`
+/// opens. Returns the patterns that were considered along with the requested
+/// page of matches.
+pub(crate) fn match_event(
+    codex_home: &Path,
+    event: &mut PatternMatchEvent,
+    patterns: Vec<PatternDefinition>,
+    settings: &MatchSettings,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<(Vec<PatternDefinition>, MatchPage)> {
+    let mut patterns = match &settings.namespace {
+        Some(namespace) => patterns_in_namespace(&patterns, namespace.as_str()),
+        None => patterns,
+    };
+    if !settings.tags.is_empty() {
+        let tags = normalize_event_tags(&settings.tags).map_err(anyhow::Error::msg)?;
+        patterns = patterns_with_tags(&patterns, &tags);
+    }
+    let provider = signature_provider(&load_signature_config(codex_home)?)?;
+    fill_missing_signatures(event, &mut patterns, provider.as_ref())?;
+
+    let scoring = match settings.scoring_profile.as_deref() {
+        Some(name) => ScoringProfile::preset(name)
+            .with_context(|| format!("unknown scoring profile '{name}'"))?,
+        None => load_scoring_profile(codex_home)?,
+    };
+    let options = MatchOptions {
+        top_k: Some(settings.limit),
+        offset: settings.offset,
+        min_score: settings.min_score,
+        require_domain_overlap: settings.require_domain_overlap,
+        scoring,
+        min_confidence: settings.min_confidence,
+    };
+    let usage_path = codex_home.join(PATTERN_USAGE_FILENAME);
+    let usage = rolling_stats(&read_usage(usage_path.as_path(), cipher)?, unix_timestamp());
+    let page = rank_pattern_page(event, &patterns, &options, &usage);
+    Ok((patterns, page))
+}
+
+/// Append one usage record per match to `codex_home`'s usage log and return
+/// them, so their match ids can be handed out for feedback.
+pub(crate) fn record_matches(
+    codex_home: &Path,
+    event: &PatternMatchEvent,
+    results: &[PatternMatchResult],
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<Vec<PatternUsageRecord>> {
+    let recorded_at = unix_timestamp();
+    let records = results
+        .iter()
+        .map(|result| {
+            PatternUsageRecord::new_match(
+                result.pattern_id.as_str(),
+                result.total,
+                event.trigger.as_str(),
+                recorded_at,
+            )
+        })
+        .collect::<Vec<_>>();
+    append_usage(
+        codex_home.join(PATTERN_USAGE_FILENAME).as_path(),
+        &records,
+        cipher,
+    )?;
+    Ok(records)
+}
+
+#[derive(Debug, Parser)]
+pub struct PatternsFeedbackCommand {
+    /// Match id printed by `codex patterns-match`.
+    #[arg(long)]
+    pub match_id: String,
 
+    /// Whether the suggested response was used.
+    #[arg(long, action = clap::ArgAction::Set, value_name = "BOOL")]
+    pub accepted: bool,
+
+    /// How the event turned out (success, failure, mixed).
+    #[arg(long)]
+    pub outcome: Option<String>,
+}
+
+pub fn run_patterns_feedback(cmd: PatternsFeedbackCommand) -> anyhow::Result<()> {
+    let codex_home = find_codex_home()?;
+    let known = record_feedback(
+        codex_home.as_path(),
+        cmd.match_id.as_str(),
+        cmd.accepted,
+        cmd.outcome.as_deref(),
+        StoreCipher::load()?.as_ref(),
+    )?;
+    anyhow::ensure!(
+        known,
+        "no pattern match with id {} in {}",
+        cmd.match_id,
+        codex_home.join(PATTERN_USAGE_FILENAME).display()
+    );
+    println!("recorded feedback for match {}", cmd.match_id);
     Ok(())
 }
 
+/// Record feedback for a match previously appended to `codex_home`'s usage
+/// log. Returns `false`, recording nothing, when the match id is unknown.
+pub(crate) fn record_feedback(
+    codex_home: &Path,
+    match_id: &str,
+    accepted: bool,
+    outcome: Option<&str>,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<bool> {
+    let path = codex_home.join(PATTERN_USAGE_FILENAME);
+    let known = read_usage(path.as_path(), cipher)?.iter().any(|record| {
+        matches!(record, PatternUsageRecord::Match { .. }) && record.match_id() == match_id
+    });
+    if !known {
+        return Ok(false);
+    }
+    append_usage(
+        path.as_path(),
+        &[PatternUsageRecord::Feedback {
+            match_id: match_id.to_string(),
+            accepted,
+            outcome: outcome.map(Outcome::from_text),
+            recorded_at: unix_timestamp(),
+        }],
+        cipher,
+    )?;
+    Ok(true)
+}
+
+#[derive(Debug, Parser)]
+pub struct PatternsStatsCommand {
+    /// Print the statistics as a JSON array instead of a table.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+pub fn run_patterns_stats(cmd: PatternsStatsCommand) -> anyhow::Result<()> {
+    let path = find_codex_home()?.join(PATTERN_USAGE_FILENAME);
+    let cipher = StoreCipher::load()?;
+    let mut stats = rolling_stats(
+        &read_usage(path.as_path(), cipher.as_ref())?,
+        unix_timestamp(),
+    );
+    stats.sort_by(|left, right| right.cmp_activity(left));
+    if cmd.json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    if stats.is_empty() {
+        println!("no pattern matches recorded in {}", path.display());
+        return Ok(());
+    }
+    println!(
+        "{:<38} {:>4} {:>4} {:>9} {:<12}",
+        "PATTERN", "7D", "30D", "ACCEPTED", "LAST MATCHED"
+    );
+    for pattern in &stats {
+        println!("{}", format_rolling_stats(pattern));
+    }
+    Ok(())
+}
+
+fn format_rolling_stats(stats: &PatternRollingStats) -> String {
+    format!(
+        "{:<38} {:>4} {:>4} {:>9} {:<12}",
+        stats.pattern_id,
+        stats.matches_7d,
+        stats.matches_30d,
+        stats
+            .acceptance_rate
+            .map_or_else(|| "-".to_string(), |rate| format!("{:.1}%", rate * 100.0)),
+        stats
+            .last_matched_at
+            .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+            .map_or_else(
+                || "-".to_string(),
+                |time| time.format("%Y-%m-%d").to_string()
+            ),
+    )
+}
+
+/// A match as printed by `codex patterns-match --json` and returned by
+/// `codex patterns serve`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JsonMatch<'a> {
+    #[serde(flatten)]
+    result: &'a PatternMatchResult,
+    /// Id to pass to `codex patterns feedback`; absent with `--no-record`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    match_id: Option<&'a str>,
+}
+
+fn print_json_matches(
+    results: &[PatternMatchResult],
+    records: Option<&[PatternUsageRecord]>,
+) -> anyhow::Result<()> {
+    let matches = json_matches(results, records);
+    println!("{}", serde_json::to_string_pretty(&matches)?);
+    Ok(())
+}
+
+/// Pair each match with the match id of its usage record, when recorded.
+pub(crate) fn json_matches<'a>(
+    results: &'a [PatternMatchResult],
+    records: Option<&'a [PatternUsageRecord]>,
+) -> Vec<JsonMatch<'a>> {
+    results
+        .iter()
+        .enumerate()
+        .map(|(index, result)| JsonMatch {
+            result,
+            match_id: records
+                .and_then(|records| records.get(index))
+                .map(PatternUsageRecord::match_id),
+        })
+        .collect()
+}
+
+/// ` name=value` for each placeholder the event filled, in name order.
+fn format_bindings(result: &PatternMatchResult) -> String {
+    result
+        .bindings
+        .iter()
+        .map(|(name, value)| format!(" {name}={value}"))
+        .collect()
+}
+
+/// Provenance for each pattern by id: the pattern's own, or else that of the
+/// latest compiled version of the same key in the pattern log. Live patterns
+/// take their id from that key but do not store provenance themselves.
+fn pattern_provenance(
+    patterns: &[PatternDefinition],
+    log: &Path,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<HashMap<String, PatternProvenance>> {
+    let mut compiled: HashMap<String, (u64, PatternProvenance)> = HashMap::new();
+    for (_, entry) in read_patterns_log(log, cipher)? {
+        let newer = compiled
+            .get(&entry.pattern.key)
+            .is_none_or(|(version, _)| *version <= entry.version);
+        if newer {
+            compiled.insert(entry.pattern.key, (entry.version, entry.pattern.provenance));
+        }
+    }
+    Ok(patterns
+        .iter()
+        .filter_map(|pattern| {
+            let provenance = match &pattern.provenance {
+                Some(provenance) => provenance.clone(),
+                None => compiled.get(&pattern.id)?.1.clone(),
+            };
+            Some((pattern.id.clone(), provenance))
+        })
+        .collect())
+}
+
+/// Indented lines naming the compile run and each supporting event.
+fn format_provenance(provenance: Option<&PatternProvenance>) -> String {
+    let Some(provenance) = provenance.filter(|provenance| !provenance.events.is_empty()) else {
+        return "\n  no provenance recorded".to_string();
+    };
+    let mut lines = String::new();
+    if let Some(run_id) = &provenance.compile_run_id {
+        lines.push_str(&format!("\n  compile run {run_id}"));
+    }
+    for event in &provenance.events {
+        match &event.source {
+            Some(source) => lines.push_str(&format!("\n  {} from {source}", event.event_id)),
+            None => lines.push_str(&format!("\n  {}", event.event_id)),
+        }
+    }
+    lines
+}
+
+/// The live patterns of `store` in the scope `scope` selects.
+async fn load_live_patterns(
+    store: &CovenantStorePaths,
+    scope: &PatternScopeArgs,
+) -> anyhow::Result<Vec<PatternDefinition>> {
+    if !store.db_path().exists() {
+        return Ok(Vec::new());
+    }
+    let runtime = open_covenant_store(store).await?;
+    Ok(runtime
+        .list_covenant_patterns(scope.selected())
+        .await?
+        .into_iter()
+        .map(PatternDefinition::from)
+        .collect())
+}
+
+/// `-` stands for stdin wherever a JSON file is expected.
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
 fn read_json<T>(path: &Path) -> anyhow::Result<T>
 where
     T: serde::de::DeserializeOwned,
 {
+    if is_stdin(path) {
+        let contents = std::io::read_to_string(std::io::stdin()).context("failed to read stdin")?;
+        return serde_json::from_str(&contents).context("failed to parse JSON from stdin");
+    }
     let contents = fs::read_to_string(path)
         .with_context(|| format!("failed to read {path}", path = path.display()))?;
     serde_json::from_str(&contents)
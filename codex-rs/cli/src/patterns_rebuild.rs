@@ -0,0 +1,399 @@
+//! `codex patterns rebuild`: replay every closed covenant event, archived
+//! ones included, through the current compile logic into a fresh pattern log,
+//! and report how it differs from the existing one.
+
+use anyhow::Context;
+use clap::Parser;
+use codex_core::config::find_codex_home;
+use codex_core::domain_signature::signature_provider;
+use codex_core::pattern_match::load_signature_config;
+use codex_core::patterns::GroupBy;
+use codex_core::patterns::Pattern;
+use codex_core::patterns::compile_patterns;
+use codex_state::JsonlAppender;
+use codex_state::JsonlAppenderOptions;
+use codex_state::StoreCipher;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::compile_cmd::PATTERNS_LOG_FILENAME;
+use crate::compile_cmd::SuggestedPattern;
+use crate::compile_cmd::append_versioned_patterns;
+use crate::compile_cmd::archive_path;
+use crate::compile_cmd::compile_options;
+use crate::compile_cmd::latest_versions;
+use crate::compile_cmd::load_compile_events;
+use crate::compile_cmd::read_patterns_log;
+use crate::compile_cmd::record_compile;
+use crate::compile_cmd::unix_timestamp;
+use crate::covenant_store::StoreArgs;
+
+#[derive(Debug, Parser)]
+pub struct PatternsRebuildCommand {
+    /// Pattern log to compare against. Defaults to the one `codex compile`
+    /// writes for the covenant store.
+    #[arg(long, value_name = "FILE")]
+    pub patterns_log: Option<PathBuf>,
+
+    /// Where to write the rebuilt log. Defaults to `<name>.rebuild.jsonl`
+    /// next to the pattern log; an existing file is overwritten.
+    #[arg(long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Replace the pattern log with the rebuilt one, moving its entries to
+    /// `<name>.archive.jsonl`.
+    #[arg(long, default_value_t = false)]
+    pub replace: bool,
+
+    /// Fewest events a group needs to become a pattern. Overrides
+    /// `[compile] min_evidence` in `patterns.toml`.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(usize).range(1..))]
+    pub min_evidence: Option<usize>,
+
+    /// What events must share to form one pattern: `trigger`,
+    /// `trigger+invariant`, or `signature`. Overrides `[compile] group_by` in
+    /// `patterns.toml`.
+    #[arg(long, value_name = "STRATEGY")]
+    pub group_by: Option<GroupBy>,
+
+    /// Print the report as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+/// A field whose value the rebuild changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct FieldChange {
+    pub(crate) field: &'static str,
+    pub(crate) before: String,
+    pub(crate) after: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct ChangedPattern {
+    pub(crate) key: String,
+    pub(crate) changes: Vec<FieldChange>,
+}
+
+/// How the rebuilt patterns differ from the latest version of each pattern
+/// in the existing log, by key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct RebuildReport {
+    /// Keys only the rebuild produced.
+    pub(crate) added: Vec<String>,
+    /// Keys the rebuild no longer produces.
+    pub(crate) removed: Vec<String>,
+    pub(crate) changed: Vec<ChangedPattern>,
+    pub(crate) unchanged: usize,
+}
+
+pub async fn run_patterns_rebuild(
+    cmd: PatternsRebuildCommand,
+    store: &StoreArgs,
+) -> anyhow::Result<()> {
+    let codex_home = find_codex_home()?;
+    let store = store.resolve(codex_home.as_path()).await?;
+    let log_path = cmd
+        .patterns_log
+        .unwrap_or_else(|| store.db_dir.join(PATTERNS_LOG_FILENAME));
+    let output = cmd
+        .output
+        .unwrap_or_else(|| log_path.with_extension("rebuild.jsonl"));
+    let cipher = StoreCipher::load()?;
+    let existing = read_patterns_log(log_path.as_path(), cipher.as_ref())?;
+
+    let (events, duplicates) = load_compile_events(&store, true).await?;
+    let provider = signature_provider(&load_signature_config(codex_home.as_path())?)?;
+    let options = compile_options(codex_home.as_path(), cmd.min_evidence, cmd.group_by)?;
+    let run_id = Uuid::new_v4().to_string();
+    let mut rebuilt = compile_patterns(&events, &options);
+    for pattern in &mut rebuilt {
+        pattern.fill_missing_signature(provider.as_ref())?;
+        pattern.provenance.compile_run_id = Some(run_id.clone());
+    }
+    let report = diff_rebuild(&existing, &rebuilt);
+
+    match std::fs::remove_file(&output) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to replace {}", output.display()));
+        }
+    }
+    // Versions continue from the existing log, so `codex patterns review`
+    // offers every rebuilt pattern again once the log is replaced.
+    let written = append_versioned_patterns(
+        output.as_path(),
+        &latest_versions(&existing),
+        rebuilt.into_iter().map(Ok),
+        unix_timestamp(),
+        cipher.as_ref(),
+    )?;
+    if cmd.replace {
+        replace_log(
+            log_path.as_path(),
+            output.as_path(),
+            &existing,
+            cipher.as_ref(),
+        )?;
+    }
+    record_compile(&store, "patterns.rebuild", &duplicates).await?;
+
+    if cmd.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    let destination = if cmd.replace { &log_path } else { &output };
+    println!(
+        "rebuilt {written} patterns from {} resolved events into {} (run {run_id})",
+        events.len(),
+        destination.display()
+    );
+    print!("{}", format_report(&report));
+    Ok(())
+}
+
+/// Compare `rebuilt` with the latest version of each key in `existing`.
+pub(crate) fn diff_rebuild(
+    existing: &[(String, SuggestedPattern)],
+    rebuilt: &[Pattern],
+) -> RebuildReport {
+    let mut before: BTreeMap<&str, &SuggestedPattern> = BTreeMap::new();
+    for (_, entry) in existing {
+        let key = entry.pattern.key.as_str();
+        if before
+            .get(key)
+            .is_none_or(|current| current.version <= entry.version)
+        {
+            before.insert(key, entry);
+        }
+    }
+    let after = rebuilt
+        .iter()
+        .map(|pattern| (pattern.key.as_str(), pattern))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut report = RebuildReport {
+        removed: before
+            .keys()
+            .filter(|key| !after.contains_key(*key))
+            .map(ToString::to_string)
+            .collect(),
+        ..RebuildReport::default()
+    };
+    for (key, pattern) in after {
+        let Some(entry) = before.get(key) else {
+            report.added.push(key.to_string());
+            continue;
+        };
+        let changes = field_changes(&entry.pattern, pattern);
+        if changes.is_empty() {
+            report.unchanged += 1;
+        } else {
+            report.changed.push(ChangedPattern {
+                key: key.to_string(),
+                changes,
+            });
+        }
+    }
+    report
+}
+
+/// The compiled fields that differ between two versions of a pattern.
+fn field_changes(before: &Pattern, after: &Pattern) -> Vec<FieldChange> {
+    let fields = [
+        ("trigger", before.trigger.clone(), after.trigger.clone()),
+        (
+            "invariant",
+            before.invariant.clone(),
+            after.invariant.clone(),
+        ),
+        (
+            "best_response",
+            before.best_response.clone(),
+            after.best_response.clone(),
+        ),
+        (
+            "outcome",
+            before.outcome.as_str().to_string(),
+            after.outcome.as_str().to_string(),
+        ),
+        (
+            "anti_pattern",
+            before.anti_pattern.to_string(),
+            after.anti_pattern.to_string(),
+        ),
+        (
+            "support_count",
+            before.support_count.to_string(),
+            after.support_count.to_string(),
+        ),
+        (
+            "confidence",
+            format!("{:.3}", before.confidence),
+            format!("{:.3}", after.confidence),
+        ),
+        (
+            "evidence_refs",
+            before.evidence_refs.len().to_string(),
+            after.evidence_refs.len().to_string(),
+        ),
+    ];
+    fields
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| FieldChange {
+            field,
+            before,
+            after,
+        })
+        .collect()
+}
+
+/// Move the entries of the log at `path` to its archive and put the rebuilt
+/// log in its place.
+fn replace_log(
+    path: &Path,
+    rebuilt: &Path,
+    existing: &[(String, SuggestedPattern)],
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<()> {
+    if !existing.is_empty() {
+        let mut archive = JsonlAppender::open(
+            archive_path(path).as_path(),
+            JsonlAppenderOptions::default(),
+        )?
+        .with_cipher(cipher.cloned());
+        for (line, _) in existing {
+            archive.append_line(line)?;
+        }
+        archive.finish()?;
+    }
+    std::fs::rename(rebuilt, path).with_context(|| format!("failed to replace {}", path.display()))
+}
+
+/// `+` for added keys, `-` for removed ones, and `~` with one indented line
+/// per changed field, then the totals.
+pub(crate) fn format_report(report: &RebuildReport) -> String {
+    let mut out = String::new();
+    for key in &report.added {
+        out.push_str(&format!("+ {key}\n"));
+    }
+    for key in &report.removed {
+        out.push_str(&format!("- {key}\n"));
+    }
+    for pattern in &report.changed {
+        out.push_str(&format!("~ {}\n", pattern.key));
+        for change in &pattern.changes {
+            out.push_str(&format!(
+                "    {}: {} -> {}\n",
+                change.field, change.before, change.after
+            ));
+        }
+    }
+    out.push_str(&format!(
+        "{} added, {} removed, {} changed, {} unchanged\n",
+        report.added.len(),
+        report.removed.len(),
+        report.changed.len(),
+        report.unchanged
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::patterns::Outcome;
+    use pretty_assertions::assert_eq;
+
+    fn pattern(key: &str, support_count: usize) -> Pattern {
+        Pattern {
+            key: key.to_string(),
+            trigger: key.to_string(),
+            invariant: String::new(),
+            best_response: "prune logs".to_string(),
+            outcome: Outcome::Success,
+            counterexample: None,
+            support_count,
+            confidence: 0.5,
+            domain_signature: Vec::new(),
+            evidence_refs: Vec::new(),
+            anti_pattern: false,
+            signature_provider: None,
+            provenance: Default::default(),
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+        }
+    }
+
+    fn entry(version: u64, pattern: Pattern) -> (String, SuggestedPattern) {
+        (
+            String::new(),
+            SuggestedPattern {
+                version,
+                compiled_at: 1,
+                pattern,
+            },
+        )
+    }
+
+    #[test]
+    fn rebuild_reports_keys_added_removed_and_changed_since_the_latest_version() {
+        let existing = vec![
+            entry(1, pattern("disk full", 2)),
+            entry(2, pattern("disk full", 3)),
+            entry(1, pattern("oom", 2)),
+            entry(1, pattern("dns", 4)),
+        ];
+        let rebuilt = vec![
+            Pattern {
+                anti_pattern: true,
+                ..pattern("disk full", 5)
+            },
+            pattern("dns", 4),
+            pattern("certificate expired", 2),
+        ];
+
+        let report = diff_rebuild(&existing, &rebuilt);
+        assert_eq!(
+            report,
+            RebuildReport {
+                added: vec!["certificate expired".to_string()],
+                removed: vec!["oom".to_string()],
+                changed: vec![ChangedPattern {
+                    key: "disk full".to_string(),
+                    changes: vec![
+                        FieldChange {
+                            field: "anti_pattern",
+                            before: "false".to_string(),
+                            after: "true".to_string(),
+                        },
+                        FieldChange {
+                            field: "support_count",
+                            before: "3".to_string(),
+                            after: "5".to_string(),
+                        },
+                    ],
+                }],
+                unchanged: 1,
+            }
+        );
+        assert_eq!(
+            format_report(&report),
+            [
+                "+ certificate expired",
+                "- oom",
+                "~ disk full",
+                "    anti_pattern: false -> true",
+                "    support_count: 3 -> 5",
+                "1 added, 1 removed, 1 changed, 1 unchanged",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
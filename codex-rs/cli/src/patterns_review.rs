@@ -0,0 +1,384 @@
+//! `codex patterns review`: accept, edit, or reject compiled pattern suggestions
+//! before they reach the live pattern store.
+
+use clap::Parser;
+use codex_core::config::find_codex_home;
+use codex_core::covenant::load_covenant;
+use codex_core::patterns::Pattern;
+use codex_state::AuditAction;
+use codex_state::CovenantPattern;
+use codex_state::DEFAULT_PATTERN_SCOPE;
+use codex_state::GLOBAL_PATTERN_NAMESPACE;
+use codex_state::PatternReview;
+use codex_state::PatternReviewDecision;
+use codex_state::StoreCipher;
+use codex_state::project_pattern_namespace;
+use codex_state::to_canonical_json;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::compile_cmd::PATTERNS_LOG_FILENAME;
+use crate::compile_cmd::SuggestedPattern;
+use crate::compile_cmd::read_patterns_log;
+use crate::compile_cmd::unix_timestamp;
+use crate::covenant_store::StoreArgs;
+use crate::covenant_store::open_covenant_store;
+use crate::patterns_list::PatternsListCommand;
+use crate::patterns_list::run_patterns_list;
+use crate::patterns_match::PatternsFeedbackCommand;
+use crate::patterns_match::PatternsStatsCommand;
+use crate::patterns_match::run_patterns_feedback;
+use crate::patterns_match::run_patterns_stats;
+use crate::patterns_rebuild::PatternsRebuildCommand;
+use crate::patterns_rebuild::run_patterns_rebuild;
+use crate::patterns_serve::PatternsServeCommand;
+use crate::patterns_serve::run_patterns_serve;
+use crate::patterns_sync::PatternsSyncCommand;
+use crate::patterns_sync::run_patterns_sync;
+
+#[derive(Debug, Parser)]
+pub struct PatternsCli {
+    #[clap(flatten)]
+    pub store: StoreArgs,
+
+    #[command(subcommand)]
+    pub subcommand: PatternsSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum PatternsSubcommand {
+    /// List the live patterns of the store, optionally narrowed by a filter.
+    List(PatternsListCommand),
+    /// Walk through newly compiled patterns and accept, edit, or reject each.
+    Review(PatternsReviewCommand),
+    /// Record whether a matched pattern was used and how the event turned out.
+    Feedback(PatternsFeedbackCommand),
+    /// Show each pattern's matches over the last 7 and 30 days, acceptance
+    /// rate, and when it was last matched.
+    Stats(PatternsStatsCommand),
+    /// Merge the project's live patterns with a JSONL file in the repository.
+    Sync(PatternsSyncCommand),
+    /// Serve the pattern store over HTTP so other machines can list, match,
+    /// add, and give feedback on its patterns.
+    Serve(PatternsServeCommand),
+    /// Recompile every closed event, archived ones included, into a fresh
+    /// pattern log and report how it differs from the existing one.
+    Rebuild(PatternsRebuildCommand),
+}
+
+#[derive(Debug, Parser)]
+pub struct PatternsReviewCommand {
+    /// Pattern log to review. Defaults to the one `codex compile` writes for
+    /// the covenant store.
+    #[arg(long, value_name = "FILE")]
+    pub patterns_log: Option<PathBuf>,
+
+    /// Store accepted patterns globally instead of in the current project.
+    #[arg(long, default_value_t = false)]
+    pub global: bool,
+
+    /// Actor identity written to the audit trail.
+    #[arg(long, default_value = "user")]
+    pub actor: String,
+}
+
+/// What the reviewer chose for one suggestion.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ReviewChoice {
+    Accept(Pattern),
+    Edit(Pattern),
+    Reject,
+    Skip,
+    Quit,
+}
+
+pub async fn run_patterns(cli: PatternsCli) -> anyhow::Result<()> {
+    match cli.subcommand {
+        PatternsSubcommand::List(cmd) => run_patterns_list(cmd, &cli.store).await,
+        PatternsSubcommand::Review(cmd) => run_patterns_review(cmd, &cli.store).await,
+        PatternsSubcommand::Feedback(cmd) => run_patterns_feedback(cmd),
+        PatternsSubcommand::Stats(cmd) => run_patterns_stats(cmd),
+        PatternsSubcommand::Sync(cmd) => run_patterns_sync(cmd, &cli.store).await,
+        PatternsSubcommand::Serve(cmd) => run_patterns_serve(cmd, &cli.store).await,
+        PatternsSubcommand::Rebuild(cmd) => run_patterns_rebuild(cmd, &cli.store).await,
+    }
+}
+
+async fn run_patterns_review(cmd: PatternsReviewCommand, store: &StoreArgs) -> anyhow::Result<()> {
+    let store = store.resolve(find_codex_home()?.as_path()).await?;
+    let log_path = cmd
+        .patterns_log
+        .unwrap_or_else(|| store.db_dir.join(PATTERNS_LOG_FILENAME));
+    let cipher = StoreCipher::load()?;
+    let entries = read_patterns_log(log_path.as_path(), cipher.as_ref())?
+        .into_iter()
+        .map(|(_, entry)| entry);
+    let runtime = open_covenant_store(&store).await?;
+    let reviewed = runtime
+        .list_pattern_reviews()
+        .await?
+        .into_iter()
+        .map(|review| (review.pattern_key, review.version))
+        .collect::<HashSet<_>>();
+    let pending = pending_suggestions(entries, &reviewed);
+    if pending.is_empty() {
+        println!("no patterns awaiting review in {}", log_path.display());
+        return Ok(());
+    }
+
+    let cwd = std::env::current_dir()?;
+    let covenant_version = load_covenant(cwd.as_path())
+        .await
+        .map(|covenant| covenant.version)
+        .unwrap_or_else(|_| "missing".to_string());
+    let namespace = if cmd.global {
+        GLOBAL_PATTERN_NAMESPACE.to_string()
+    } else {
+        project_pattern_namespace(cwd.as_path())
+    };
+
+    let total = pending.len();
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for (index, suggestion) in pending.into_iter().enumerate() {
+        println!("\n[{}/{total}]", index + 1);
+        let choice = prompt_review(
+            &suggestion,
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout(),
+        )?;
+        let (decision, pattern) = match choice {
+            ReviewChoice::Accept(pattern) => (PatternReviewDecision::Accepted, Some(pattern)),
+            ReviewChoice::Edit(pattern) => (PatternReviewDecision::Edited, Some(pattern)),
+            ReviewChoice::Reject => (PatternReviewDecision::Rejected, None),
+            ReviewChoice::Skip => {
+                *counts.entry("skipped").or_insert(0) += 1;
+                continue;
+            }
+            ReviewChoice::Quit => break,
+        };
+
+        let reviewed_at = unix_timestamp();
+        let pattern_id = pattern.as_ref().map(|pattern| pattern.key.clone());
+        // The live pattern, the review, and its audit entry land together.
+        let mut tx = runtime.begin_covenant_transaction().await?;
+        if let Some(pattern) = pattern {
+            tx.upsert_covenant_pattern(&live_pattern(pattern, namespace.as_str(), reviewed_at)?)
+                .await?;
+        }
+        tx.insert_pattern_review(&PatternReview {
+            pattern_key: suggestion.pattern.key.clone(),
+            version: suggestion.version,
+            decision,
+            pattern_id,
+            reviewed_at,
+        })
+        .await?;
+        tx.insert_audit_action(&AuditAction {
+            timestamp: reviewed_at,
+            actor: cmd.actor.clone(),
+            action_type: format!("patterns.review.{}", decision.as_str()),
+            scope: "cli".to_string(),
+            covenant_version: covenant_version.clone(),
+            event_id: None,
+            intent_id: None,
+        })
+        .await?;
+        tx.commit().await?;
+        *counts.entry(decision.as_str()).or_insert(0) += 1;
+    }
+
+    let summary = counts
+        .iter()
+        .map(|(decision, count)| format!("{decision} {count}"))
+        .collect::<Vec<_>>();
+    if summary.is_empty() {
+        println!("no decisions recorded");
+    } else {
+        println!("{}", summary.join(", "));
+    }
+    Ok(())
+}
+
+/// The latest version of each pattern key that has not been reviewed yet,
+/// ordered by key.
+pub(crate) fn pending_suggestions(
+    entries: impl IntoIterator<Item = SuggestedPattern>,
+    reviewed: &HashSet<(String, u64)>,
+) -> Vec<SuggestedPattern> {
+    let mut latest: BTreeMap<String, SuggestedPattern> = BTreeMap::new();
+    for entry in entries {
+        let newer = latest
+            .get(&entry.pattern.key)
+            .is_none_or(|current| current.version <= entry.version);
+        if newer {
+            latest.insert(entry.pattern.key.clone(), entry);
+        }
+    }
+    latest
+        .into_values()
+        .filter(|entry| !reviewed.contains(&(entry.pattern.key.clone(), entry.version)))
+        .collect()
+}
+
+/// Show one suggestion and read the reviewer's choice. End of input quits.
+pub(crate) fn prompt_review<R: BufRead, W: Write>(
+    suggestion: &SuggestedPattern,
+    input: &mut R,
+    output: &mut W,
+) -> anyhow::Result<ReviewChoice> {
+    let pattern = &suggestion.pattern;
+    writeln!(
+        output,
+        "pattern:        {} (v{})",
+        pattern.key, suggestion.version
+    )?;
+    writeln!(output, "trigger:        {}", pattern.trigger)?;
+    writeln!(output, "invariant:      {}", pattern.invariant)?;
+    if pattern.anti_pattern {
+        writeln!(output, "avoid:          {}", pattern.best_response)?;
+    } else {
+        writeln!(output, "best response:  {}", pattern.best_response)?;
+    }
+    writeln!(
+        output,
+        "outcome:        {} ({} events, confidence {:.2})",
+        pattern.outcome, pattern.support_count, pattern.confidence
+    )?;
+    if let Some(counterexample) = &pattern.counterexample {
+        writeln!(output, "counterexample: {counterexample}")?;
+    }
+
+    loop {
+        write!(
+            output,
+            "accept [a], edit [e], reject [r], skip [s], quit [q]: "
+        )?;
+        output.flush()?;
+        let Some(answer) = read_answer(input)? else {
+            return Ok(ReviewChoice::Quit);
+        };
+        match answer.to_lowercase().as_str() {
+            "a" | "accept" => return Ok(ReviewChoice::Accept(pattern.clone())),
+            "e" | "edit" => {
+                let mut edited = pattern.clone();
+                for (label, field) in [
+                    ("trigger", &mut edited.trigger),
+                    ("invariant", &mut edited.invariant),
+                    ("best response", &mut edited.best_response),
+                ] {
+                    write!(output, "{label} [{field}]: ")?;
+                    output.flush()?;
+                    match read_answer(input)? {
+                        Some(value) if !value.is_empty() => *field = value,
+                        Some(_) => {}
+                        None => return Ok(ReviewChoice::Quit),
+                    }
+                }
+                return Ok(ReviewChoice::Edit(edited));
+            }
+            "r" | "reject" => return Ok(ReviewChoice::Reject),
+            "s" | "skip" => return Ok(ReviewChoice::Skip),
+            "q" | "quit" => return Ok(ReviewChoice::Quit),
+            _ => writeln!(output, "unrecognized choice '{answer}'")?,
+        }
+    }
+}
+
+fn read_answer<R: BufRead>(input: &mut R) -> anyhow::Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+/// Convert an accepted suggestion into a live pattern. The id is the pattern
+/// key, so accepting a later version of the same key replaces it.
+fn live_pattern(
+    pattern: Pattern,
+    namespace: &str,
+    created_at: i64,
+) -> anyhow::Result<CovenantPattern> {
+    Ok(CovenantPattern {
+        id: pattern.key,
+        trigger: pattern.trigger,
+        invariant: pattern.invariant,
+        counterexample: pattern.counterexample.unwrap_or_default(),
+        best_response: pattern.best_response,
+        domain_signature: to_canonical_json(&pattern.domain_signature)?,
+        evidence_refs: pattern.evidence_refs,
+        namespace: namespace.to_string(),
+        scope: DEFAULT_PATTERN_SCOPE.to_string(),
+        created_at,
+        anti_pattern: pattern.anti_pattern,
+        tags: pattern.tags,
+        counterexamples: pattern.counterexamples,
+        supports: 0,
+        contradictions: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::patterns::Outcome;
+    use pretty_assertions::assert_eq;
+    use std::io::Cursor;
+
+    fn suggestion(key: &str, version: u64) -> SuggestedPattern {
+        SuggestedPattern {
+            version,
+            compiled_at: 1_700_000_000,
+            pattern: Pattern {
+                key: key.to_string(),
+                trigger: "Disk full".to_string(),
+                invariant: "disk pressure".to_string(),
+                best_response: "prune logs".to_string(),
+                outcome: Outcome::Success,
+                counterexample: None,
+                support_count: 3,
+                confidence: 0.44,
+                domain_signature: vec![1.0],
+                evidence_refs: Vec::new(),
+                anti_pattern: false,
+                signature_provider: None,
+                provenance: Default::default(),
+                tags: Vec::new(),
+                counterexamples: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn only_the_latest_unreviewed_version_is_pending() {
+        let entries = vec![
+            suggestion("disk", 1),
+            suggestion("cert", 1),
+            suggestion("disk", 2),
+        ];
+        let reviewed = HashSet::from([("cert".to_string(), 1)]);
+
+        assert_eq!(
+            pending_suggestions(entries, &reviewed),
+            vec![suggestion("disk", 2)]
+        );
+    }
+
+    #[test]
+    fn edit_keeps_blank_fields_and_replaces_the_rest() {
+        let pending = suggestion("disk", 1);
+        let mut input = Cursor::new("maybe\ne\n\n\nrotate logs\n");
+        let mut output = Vec::new();
+
+        let choice = prompt_review(&pending, &mut input, &mut output).expect("prompt review");
+
+        let mut expected = pending.pattern.clone();
+        expected.best_response = "rotate logs".to_string();
+        assert_eq!(choice, ReviewChoice::Edit(expected));
+        let transcript = String::from_utf8(output).expect("utf8 output");
+        assert!(transcript.contains("unrecognized choice 'maybe'"));
+    }
+}
@@ -0,0 +1,807 @@
+//! `codex patterns serve`: share one pattern store over HTTP so several
+//! machines can list, match, add, and give feedback on the same patterns.
+
+use anyhow::Context;
+use clap::Parser;
+use codex_core::config::find_codex_home;
+use codex_core::covenant::load_covenant;
+use codex_core::pattern_match::PatternDefinition;
+use codex_core::pattern_match::PatternMatchEvent;
+use codex_core::pattern_match::ScoringProfile;
+use codex_state::AuditAction;
+use codex_state::CovenantPattern;
+use codex_state::DEFAULT_PATTERN_SCOPE;
+use codex_state::GLOBAL_PATTERN_NAMESPACE;
+use codex_state::StateRuntime;
+use codex_state::normalize_event_tags;
+use codex_state::project_pattern_namespace;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+use std::io::Read;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tiny_http::Header;
+use tiny_http::Method;
+use tiny_http::Request;
+use tiny_http::Response;
+use tiny_http::Server;
+
+use crate::compile_cmd::unix_timestamp;
+use crate::covenant_store::PatternScopeArgs;
+use crate::covenant_store::StoreArgs;
+use crate::covenant_store::open_covenant_store;
+use crate::patterns_match::MatchSettings;
+use crate::patterns_match::json_matches;
+use crate::patterns_match::match_event;
+use crate::patterns_match::record_feedback;
+use crate::patterns_match::record_matches;
+
+/// Environment variable holding the API token when `--token-file` is not set.
+pub(crate) const PATTERNS_TOKEN_ENV_VAR: &str = "CODEX_PATTERNS_TOKEN";
+
+/// Request bodies larger than this are rejected with 413.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Parser)]
+pub struct PatternsServeCommand {
+    /// Port to listen on.
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
+
+    /// Address to listen on. Use `0.0.0.0` to accept other machines.
+    #[arg(long, value_name = "ADDR", default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    pub host: IpAddr,
+
+    /// File holding the token clients must send as `Authorization: Bearer
+    /// <token>`. Defaults to `$CODEX_PATTERNS_TOKEN`.
+    #[arg(long, value_name = "FILE")]
+    pub token_file: Option<PathBuf>,
+
+    /// Actor identity written to the audit trail for added patterns.
+    #[arg(long, default_value = "patterns-serve")]
+    pub actor: String,
+
+    /// Namespace clients may add patterns to besides the global one. Defaults
+    /// to the namespace of the current directory's project.
+    #[arg(long, value_name = "NAMESPACE")]
+    pub namespace: Option<String>,
+
+    #[clap(flatten)]
+    pub scope: PatternScopeArgs,
+}
+
+/// Everything a request handler needs.
+pub(crate) struct ServeState {
+    pub(crate) codex_home: PathBuf,
+    pub(crate) runtime: Arc<StateRuntime>,
+    pub(crate) token: String,
+    pub(crate) actor: String,
+    pub(crate) covenant_version: String,
+    /// Covenant scope whose patterns are listed and matched, or `None` for
+    /// every scope.
+    pub(crate) scope: Option<String>,
+    /// The one namespace besides the global one that patterns may be added to.
+    pub(crate) namespace: String,
+    /// Scopes declared by the server's covenant, which added patterns must
+    /// belong to unless they use the default scope.
+    pub(crate) covenant_scopes: Vec<String>,
+}
+
+/// A request with its body read, detached from the connection.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ApiRequest {
+    pub(crate) method: Method,
+    /// The URL path without its query string.
+    pub(crate) path: String,
+    pub(crate) authorization: Option<String>,
+    pub(crate) body: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ApiResponse {
+    pub(crate) status: u16,
+    /// `None` for responses without a body.
+    pub(crate) body: Option<Value>,
+}
+
+impl ApiResponse {
+    fn json(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            body: Some(body),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(status, json!({ "error": message.into() }))
+    }
+}
+
+/// Body of `POST /v1/match`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MatchBody {
+    event: PatternMatchEvent,
+    #[serde(flatten)]
+    settings: MatchSettings,
+    /// Append the matches to the usage log and return their match ids.
+    #[serde(default = "default_record")]
+    record: bool,
+}
+
+fn default_record() -> bool {
+    true
+}
+
+/// Body of `POST /v1/patterns`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddPatternBody {
+    id: String,
+    trigger: String,
+    invariant: String,
+    best_response: String,
+    #[serde(default)]
+    counterexample: String,
+    /// Defaults to the global namespace.
+    #[serde(default)]
+    namespace: Option<String>,
+    /// Covenant scope to store the pattern in. Defaults to the scope the
+    /// server serves.
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    evidence_refs: Vec<String>,
+    #[serde(default)]
+    anti_pattern: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Body of `POST /v1/feedback`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeedbackBody {
+    match_id: String,
+    accepted: bool,
+    #[serde(default)]
+    outcome: Option<String>,
+}
+
+pub(crate) async fn run_patterns_serve(
+    cmd: PatternsServeCommand,
+    store_args: &StoreArgs,
+) -> anyhow::Result<()> {
+    let token = read_token(cmd.token_file.as_deref())?;
+    let codex_home = find_codex_home()?;
+    let store = store_args.resolve(codex_home.as_path()).await?;
+    let runtime = open_covenant_store(&store).await?;
+    let cwd = std::env::current_dir()?;
+    let covenant = load_covenant(cwd.as_path()).await.ok();
+    let covenant_version = covenant.as_ref().map_or_else(
+        || "missing".to_string(),
+        |covenant| covenant.version.clone(),
+    );
+    let covenant_scopes = covenant
+        .map(|covenant| {
+            covenant
+                .scopes
+                .into_iter()
+                .map(|scope| scope.name)
+                .collect()
+        })
+        .unwrap_or_default();
+    let state = Arc::new(ServeState {
+        codex_home,
+        runtime,
+        token,
+        actor: cmd.actor,
+        covenant_version,
+        scope: cmd.scope.selected().map(str::to_string),
+        namespace: cmd
+            .namespace
+            .unwrap_or_else(|| project_pattern_namespace(cwd.as_path())),
+        covenant_scopes,
+    });
+
+    let addr = SocketAddr::new(cmd.host, cmd.port);
+    let server = Arc::new(
+        Server::http(addr).map_err(|err| anyhow::anyhow!("failed to listen on {addr}: {err}"))?,
+    );
+    println!(
+        "serving patterns from {} on http://{addr}",
+        store.db_path().display()
+    );
+    loop {
+        // tiny_http blocks, so connections are accepted off the runtime. A
+        // connection that fails to arrive is logged and the server goes on.
+        let server = Arc::clone(&server);
+        let request = match tokio::task::spawn_blocking(move || server.recv()).await {
+            Ok(Ok(request)) => request,
+            Ok(Err(err)) => {
+                eprintln!("warning: failed to accept a connection: {err}");
+                continue;
+            }
+            Err(err) => {
+                eprintln!("warning: failed to accept a connection: {err}");
+                continue;
+            }
+        };
+        let state = Arc::clone(&state);
+        tokio::spawn(serve_request(state, request));
+    }
+}
+
+/// Answer one connection. The token is checked from the headers before the
+/// body is read, so unauthenticated clients cannot make the server read
+/// anything more. Reading and answering block, so both run off the runtime.
+async fn serve_request(state: Arc<ServeState>, request: Request) {
+    let authorization = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .map(|header| header.value.to_string());
+    let (request, response) = if authorized(authorization.as_deref(), state.token.as_str()) {
+        let read = tokio::task::spawn_blocking(move || {
+            let mut request = request;
+            let api_request = read_request(&mut request, authorization);
+            (request, api_request)
+        })
+        .await;
+        let (request, api_request) = match read {
+            Ok(read) => read,
+            Err(err) => {
+                eprintln!("warning: failed to read a request: {err}");
+                return;
+            }
+        };
+        let response = match api_request {
+            Ok(api_request) => handle_request(&state, api_request).await,
+            Err(response) => response,
+        };
+        (request, response)
+    } else {
+        (request, unauthorized())
+    };
+    let sent = tokio::task::spawn_blocking(move || respond(request, response)).await;
+    if let Ok(Err(err)) = sent {
+        eprintln!("warning: failed to send a response: {err}");
+    }
+}
+
+/// The token from `token_file`, or else from `$CODEX_PATTERNS_TOKEN`. It is
+/// never taken from the command line, where other users could see it.
+fn read_token(token_file: Option<&Path>) -> anyhow::Result<String> {
+    let token = match token_file {
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?,
+        None => std::env::var(PATTERNS_TOKEN_ENV_VAR).unwrap_or_default(),
+    };
+    let token = token.trim().to_string();
+    anyhow::ensure!(
+        !token.is_empty(),
+        "set {PATTERNS_TOKEN_ENV_VAR} or pass --token-file; the server does not run without a token"
+    );
+    Ok(token)
+}
+
+/// Read the body of an authorized request. A body over [`MAX_BODY_BYTES`]
+/// is refused rather than cut short.
+fn read_request(
+    request: &mut Request,
+    authorization: Option<String>,
+) -> Result<ApiRequest, ApiResponse> {
+    let too_large = || {
+        ApiResponse::error(
+            413,
+            format!("request body is larger than {MAX_BODY_BYTES} bytes"),
+        )
+    };
+    if request
+        .body_length()
+        .is_some_and(|length| length > MAX_BODY_BYTES)
+    {
+        return Err(too_large());
+    }
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES as u64 + 1)
+        .read_to_string(&mut body)
+        .map_err(|err| ApiResponse::error(400, format!("failed to read request: {err}")))?;
+    if body.len() > MAX_BODY_BYTES {
+        return Err(too_large());
+    }
+    Ok(ApiRequest {
+        method: request.method().clone(),
+        path: request
+            .url()
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        authorization,
+        body,
+    })
+}
+
+fn respond(request: Request, response: ApiResponse) -> std::io::Result<()> {
+    let Some(body) = response.body else {
+        return request.respond(Response::empty(response.status));
+    };
+    let mut reply = Response::from_string(body.to_string()).with_status_code(response.status);
+    if let Ok(header) = Header::from_bytes("Content-Type", "application/json") {
+        reply.add_header(header);
+    }
+    request.respond(reply)
+}
+
+/// Route one request. Every endpoint requires the bearer token.
+pub(crate) async fn handle_request(state: &ServeState, request: ApiRequest) -> ApiResponse {
+    if !authorized(request.authorization.as_deref(), state.token.as_str()) {
+        return unauthorized();
+    }
+    let result = match (&request.method, request.path.as_str()) {
+        (Method::Get, "/v1/patterns") => list_patterns(state).await,
+        (Method::Post, "/v1/patterns") => add_pattern(state, request.body.as_str()).await,
+        (Method::Post, "/v1/match") => match_patterns(state, request.body.as_str()).await,
+        (Method::Post, "/v1/feedback") => feedback(state, request.body.as_str()).await,
+        (method, path) => Ok(ApiResponse::error(
+            404,
+            format!("no route for {method} {path}"),
+        )),
+    };
+    result.unwrap_or_else(|err| ApiResponse::error(500, format!("{err:#}")))
+}
+
+fn unauthorized() -> ApiResponse {
+    ApiResponse::error(401, "missing or invalid bearer token")
+}
+
+/// Compare the `Authorization` header with the token in constant time.
+fn authorized(header: Option<&str>, token: &str) -> bool {
+    let Some(presented) = header.and_then(|header| header.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (left, right)| diff | (left ^ right))
+            == 0
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(body: &str) -> Result<T, ApiResponse> {
+    serde_json::from_str(body)
+        .map_err(|err| ApiResponse::error(400, format!("invalid body: {err}")))
+}
+
+async fn list_patterns(state: &ServeState) -> anyhow::Result<ApiResponse> {
+    let patterns = state
+        .runtime
+        .list_covenant_patterns(state.scope.as_deref())
+        .await?;
+    Ok(ApiResponse::json(200, serde_json::to_value(patterns)?))
+}
+
+async fn add_pattern(state: &ServeState, body: &str) -> anyhow::Result<ApiResponse> {
+    let body: AddPatternBody = match parse_body(body) {
+        Ok(body) => body,
+        Err(response) => return Ok(response),
+    };
+    for (field, value) in [
+        ("id", &body.id),
+        ("trigger", &body.trigger),
+        ("bestResponse", &body.best_response),
+    ] {
+        if value.trim().is_empty() {
+            return Ok(ApiResponse::error(
+                400,
+                format!("{field} must not be empty"),
+            ));
+        }
+    }
+    let tags = match normalize_event_tags(&body.tags) {
+        Ok(tags) => tags,
+        Err(err) => return Ok(ApiResponse::error(400, err)),
+    };
+    let namespace = body
+        .namespace
+        .unwrap_or_else(|| GLOBAL_PATTERN_NAMESPACE.to_string());
+    if namespace != GLOBAL_PATTERN_NAMESPACE && namespace != state.namespace {
+        return Ok(ApiResponse::error(
+            403,
+            format!("namespace '{namespace}' is not served here"),
+        ));
+    }
+    let scope = body
+        .scope
+        .or_else(|| state.scope.clone())
+        .unwrap_or_else(|| DEFAULT_PATTERN_SCOPE.to_string());
+    if scope != DEFAULT_PATTERN_SCOPE && !state.covenant_scopes.contains(&scope) {
+        return Ok(ApiResponse::error(
+            400,
+            format!("scope '{scope}' is not declared by the covenant"),
+        ));
+    }
+    if let Some(served) = state.scope.as_deref()
+        && scope != served
+    {
+        return Ok(ApiResponse::error(
+            403,
+            format!("scope '{scope}' is not served here"),
+        ));
+    }
+    let created_at = unix_timestamp();
+    let pattern = CovenantPattern {
+        id: body.id,
+        trigger: body.trigger,
+        invariant: body.invariant,
+        counterexample: body.counterexample,
+        best_response: body.best_response,
+        // Filled in from the trigger and invariant when matched.
+        domain_signature: "[]".to_string(),
+        evidence_refs: body.evidence_refs,
+        namespace,
+        scope,
+        created_at,
+        anti_pattern: body.anti_pattern,
+        tags,
+        counterexamples: Vec::new(),
+        supports: 0,
+        contradictions: 0,
+    };
+    // The pattern and its audit entry land together.
+    let mut tx = state.runtime.begin_covenant_transaction().await?;
+    tx.upsert_covenant_pattern(&pattern).await?;
+    tx.insert_audit_action(&AuditAction {
+        timestamp: created_at,
+        actor: state.actor.clone(),
+        action_type: "patterns.add".to_string(),
+        scope: "serve".to_string(),
+        covenant_version: state.covenant_version.clone(),
+        event_id: None,
+        intent_id: None,
+    })
+    .await?;
+    tx.commit().await?;
+    Ok(ApiResponse::json(201, serde_json::to_value(pattern)?))
+}
+
+async fn match_patterns(state: &ServeState, body: &str) -> anyhow::Result<ApiResponse> {
+    let MatchBody {
+        mut event,
+        settings,
+        record,
+    } = match parse_body(body) {
+        Ok(body) => body,
+        Err(response) => return Ok(response),
+    };
+    // Reject what the client got wrong before anything that could fail on
+    // the server's side.
+    if let Some(name) = settings.scoring_profile.as_deref()
+        && ScoringProfile::preset(name).is_none()
+    {
+        return Ok(ApiResponse::error(
+            400,
+            format!("unknown scoring profile '{name}'"),
+        ));
+    }
+    if let Err(err) = normalize_event_tags(&settings.tags) {
+        return Ok(ApiResponse::error(400, err));
+    }
+    let patterns = state
+        .runtime
+        .list_covenant_patterns(state.scope.as_deref())
+        .await?
+        .into_iter()
+        .map(PatternDefinition::from)
+        .collect();
+    // Matching reads the signature config and usage log, and recording
+    // appends to the log, all through blocking file I/O.
+    let codex_home = state.codex_home.clone();
+    let cipher = state.runtime.store_cipher()?.cloned();
+    let (page, records) = tokio::task::spawn_blocking(move || {
+        let cipher = cipher.as_ref();
+        let (_, page) = match_event(
+            codex_home.as_path(),
+            &mut event,
+            patterns,
+            &settings,
+            cipher,
+        )?;
+        let records = if record {
+            Some(record_matches(
+                codex_home.as_path(),
+                &event,
+                &page.results,
+                cipher,
+            )?)
+        } else {
+            None
+        };
+        anyhow::Ok((page, records))
+    })
+    .await??;
+    let matches = serde_json::to_value(json_matches(&page.results, records.as_deref()))?;
+    Ok(ApiResponse::json(
+        200,
+        json!({
+            "matches": matches,
+            "totalCandidates": page.total_candidates,
+            "nextOffset": page.next_offset,
+        }),
+    ))
+}
+
+async fn feedback(state: &ServeState, body: &str) -> anyhow::Result<ApiResponse> {
+    let body: FeedbackBody = match parse_body(body) {
+        Ok(body) => body,
+        Err(response) => return Ok(response),
+    };
+    let codex_home = state.codex_home.clone();
+    let cipher = state.runtime.store_cipher()?.cloned();
+    let match_id = body.match_id.clone();
+    let known = tokio::task::spawn_blocking(move || {
+        record_feedback(
+            codex_home.as_path(),
+            match_id.as_str(),
+            body.accepted,
+            body.outcome.as_deref(),
+            cipher.as_ref(),
+        )
+    })
+    .await??;
+    if !known {
+        return Ok(ApiResponse::error(
+            404,
+            format!("no pattern match with id {}", body.match_id),
+        ));
+    }
+    Ok(ApiResponse {
+        status: 204,
+        body: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_state::CovenantStorage;
+    use codex_state::CovenantStorePaths;
+    use pretty_assertions::assert_eq;
+
+    const TOKEN: &str = "secret-token";
+
+    async fn test_state(codex_home: &Path) -> ServeState {
+        let store = CovenantStorePaths::resolve(CovenantStorage::Home, codex_home, codex_home);
+        ServeState {
+            codex_home: codex_home.to_path_buf(),
+            runtime: open_covenant_store(&store).await.expect("open store"),
+            token: TOKEN.to_string(),
+            actor: "patterns-serve".to_string(),
+            covenant_version: "missing".to_string(),
+            scope: Some(DEFAULT_PATTERN_SCOPE.to_string()),
+            namespace: "project:served".to_string(),
+            covenant_scopes: vec!["ci".to_string()],
+        }
+    }
+
+    fn request(method: Method, path: &str, body: Value) -> ApiRequest {
+        ApiRequest {
+            method,
+            path: path.to_string(),
+            authorization: Some(format!("Bearer {TOKEN}")),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn only_the_exact_bearer_token_is_authorized() {
+        assert_eq!(
+            [
+                authorized(Some("Bearer secret-token"), TOKEN),
+                authorized(Some("Bearer secret-tokem"), TOKEN),
+                authorized(Some("Bearer secret"), TOKEN),
+                authorized(Some("secret-token"), TOKEN),
+                authorized(None, TOKEN),
+            ],
+            [true, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn bodies_over_the_limit_are_refused() {
+        let mut small: Request = tiny_http::TestRequest::new()
+            .with_method(Method::Post)
+            .with_path("/v1/match?explain=1")
+            .with_body("{}")
+            .into();
+        let oversized_body = "x".repeat(MAX_BODY_BYTES + 1);
+        let mut oversized: Request = tiny_http::TestRequest::new()
+            .with_method(Method::Post)
+            .with_path("/v1/patterns")
+            .with_body(oversized_body.as_str())
+            .into();
+
+        assert_eq!(
+            (
+                read_request(&mut small, Some(format!("Bearer {TOKEN}"))),
+                read_request(&mut oversized, Some(format!("Bearer {TOKEN}"))),
+            ),
+            (
+                Ok(request(Method::Post, "/v1/match", json!({}))),
+                Err(ApiResponse::error(
+                    413,
+                    format!("request body is larger than {MAX_BODY_BYTES} bytes")
+                )),
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn patterns_can_be_added_listed_matched_and_given_feedback() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let state = test_state(codex_home.path()).await;
+
+        let mut unauthorized = request(Method::Get, "/v1/patterns", Value::Null);
+        unauthorized.authorization = None;
+        assert_eq!(handle_request(&state, unauthorized).await.status, 401);
+
+        let added = handle_request(
+            &state,
+            request(
+                Method::Post,
+                "/v1/patterns",
+                json!({
+                    "id": "disk-full",
+                    "trigger": "disk full on build host",
+                    "invariant": "disk pressure",
+                    "bestResponse": "prune old build logs",
+                }),
+            ),
+        )
+        .await;
+        assert_eq!(added.status, 201);
+        // Patterns of other scopes are neither listed nor matched.
+        let other_scope = CovenantPattern {
+            id: "disk-full-ci".to_string(),
+            trigger: "disk full on build host".to_string(),
+            invariant: "disk pressure".to_string(),
+            counterexample: String::new(),
+            best_response: "wipe the build cache".to_string(),
+            domain_signature: "[]".to_string(),
+            evidence_refs: Vec::new(),
+            namespace: GLOBAL_PATTERN_NAMESPACE.to_string(),
+            scope: "ci".to_string(),
+            created_at: 0,
+            anti_pattern: false,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+            supports: 0,
+            contradictions: 0,
+        };
+        state
+            .runtime
+            .upsert_covenant_pattern(&other_scope)
+            .await
+            .expect("upsert pattern in another scope");
+
+        let listed = handle_request(&state, request(Method::Get, "/v1/patterns", Value::Null))
+            .await
+            .body
+            .expect("list body");
+        assert_eq!(
+            listed
+                .as_array()
+                .expect("pattern array")
+                .iter()
+                .map(|pattern| (pattern["id"].clone(), pattern["namespace"].clone()))
+                .collect::<Vec<_>>(),
+            vec![(json!("disk-full"), json!(GLOBAL_PATTERN_NAMESPACE))]
+        );
+
+        let matched = handle_request(
+            &state,
+            request(
+                Method::Post,
+                "/v1/match",
+                json!({
+                    "event": {"trigger": "disk full on build host", "invariant": "disk pressure"},
+                    "limit": 1,
+                }),
+            ),
+        )
+        .await
+        .body
+        .expect("match body");
+        assert_eq!(
+            (&matched["totalCandidates"], &matched["nextOffset"]),
+            (&json!(1), &Value::Null)
+        );
+        let matches = matched["matches"].as_array().expect("match array");
+        assert_eq!(
+            matches
+                .iter()
+                .map(|result| result["patternId"].clone())
+                .collect::<Vec<_>>(),
+            vec![json!("disk-full")]
+        );
+        let match_id = matches[0]["matchId"].as_str().expect("match id");
+
+        let feedback = |match_id: &str| {
+            request(
+                Method::Post,
+                "/v1/feedback",
+                json!({"matchId": match_id, "accepted": true, "outcome": "success"}),
+            )
+        };
+        assert_eq!(
+            (
+                handle_request(&state, feedback(match_id)).await,
+                handle_request(&state, feedback("unknown")).await.status,
+                handle_request(&state, request(Method::Delete, "/v1/patterns", Value::Null))
+                    .await
+                    .status,
+            ),
+            (
+                ApiResponse {
+                    status: 204,
+                    body: None,
+                },
+                404,
+                404,
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn patterns_outside_the_served_namespace_or_scope_are_refused() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let state = test_state(codex_home.path()).await;
+        let add = |extra: Value| {
+            let mut body = json!({
+                "id": "disk-full",
+                "trigger": "disk full on build host",
+                "invariant": "disk pressure",
+                "bestResponse": "prune old build logs",
+            });
+            if let (Some(body), Value::Object(extra)) = (body.as_object_mut(), extra) {
+                body.extend(extra);
+            }
+            request(Method::Post, "/v1/patterns", body)
+        };
+
+        assert_eq!(
+            (
+                handle_request(&state, add(json!({"namespace": "project:other"}))).await,
+                handle_request(&state, add(json!({"scope": "undeclared"}))).await,
+                handle_request(&state, add(json!({"scope": "ci"}))).await,
+            ),
+            (
+                ApiResponse::error(403, "namespace 'project:other' is not served here"),
+                ApiResponse::error(400, "scope 'undeclared' is not declared by the covenant"),
+                ApiResponse::error(403, "scope 'ci' is not served here"),
+            )
+        );
+        assert_eq!(
+            state
+                .runtime
+                .list_covenant_patterns(None)
+                .await
+                .expect("list patterns"),
+            Vec::new()
+        );
+        assert_eq!(
+            handle_request(&state, add(json!({"namespace": "project:served"})))
+                .await
+                .status,
+            201
+        );
+    }
+}
@@ -0,0 +1,457 @@
+//! `codex patterns sync`: keep the project's live patterns in a JSONL file
+//! inside the repository so they can be version-controlled and merged.
+
+use anyhow::Context;
+use clap::Parser;
+use clap::ValueEnum;
+use codex_core::config::find_codex_home;
+use codex_core::covenant::load_covenant;
+use codex_state::AuditAction;
+use codex_state::CovenantPattern;
+use codex_state::canonical_json;
+use codex_state::project_pattern_namespace;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::compile_cmd::unix_timestamp;
+use crate::covenant_store::StoreArgs;
+use crate::covenant_store::open_covenant_store;
+
+/// File written inside `--repo-path`.
+pub(crate) const SYNCED_PATTERNS_FILENAME: &str = "patterns.jsonl";
+
+/// File in the store's directory recording what each synced file held after
+/// the last sync, the base the next sync merges against. It stays on this
+/// machine.
+pub(crate) const SYNC_BASE_FILENAME: &str = "pattern_sync_base.json";
+
+/// Fields left out of the synced file: the namespace, which the repository
+/// already implies, and the feedback counters, which count reports made on one
+/// machine and would conflict on every sync.
+const UNSYNCED_FIELDS: [&str; 3] = ["namespace", "supports", "contradictions"];
+
+const CONFLICT_MARKERS: [&str; 3] = ["<<<<<<<", "=======", ">>>>>>>"];
+
+#[derive(Debug, Parser)]
+pub struct PatternsSyncCommand {
+    /// Directory in the repository that holds the synced patterns.
+    #[arg(long, value_name = "DIR", default_value = ".codex-patterns")]
+    pub repo_path: PathBuf,
+
+    /// Which copy wins when the repository and the store both changed a
+    /// pattern since the last sync.
+    #[arg(long, value_enum, value_name = "SIDE")]
+    pub prefer: Option<SyncSide>,
+
+    /// Actor identity written to the audit trail.
+    #[arg(long, default_value = "user")]
+    pub actor: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SyncSide {
+    Repo,
+    Store,
+}
+
+/// A pattern the repository and the store both changed, differently, since
+/// the last sync, or that they hold differently with no sync on record.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SyncConflict {
+    pub(crate) id: String,
+}
+
+/// The synced line of each pattern by id, as of the last sync.
+pub(crate) type SyncBase = BTreeMap<String, String>;
+
+/// What a sync writes to each side.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SyncPlan {
+    /// Patterns to write to the store, from the repository.
+    pub(crate) import: Vec<CovenantPattern>,
+    /// Every pattern the repository file holds afterwards, ordered by id.
+    pub(crate) file: Vec<CovenantPattern>,
+    /// Patterns only the store had.
+    pub(crate) exported: usize,
+    pub(crate) conflicts: Vec<SyncConflict>,
+}
+
+pub(crate) async fn run_patterns_sync(
+    cmd: PatternsSyncCommand,
+    store_args: &StoreArgs,
+) -> anyhow::Result<()> {
+    let cwd = std::env::current_dir()?;
+    let namespace = project_pattern_namespace(cwd.as_path());
+    let file_path = cwd.join(&cmd.repo_path).join(SYNCED_PATTERNS_FILENAME);
+    let repo = read_synced_patterns(file_path.as_path(), namespace.as_str())?;
+
+    let store_paths = store_args.resolve(find_codex_home()?.as_path()).await?;
+    let base_path = store_paths.db_dir.join(SYNC_BASE_FILENAME);
+    let base_key = file_path.display().to_string();
+    let mut bases = read_sync_bases(base_path.as_path())?;
+    let runtime = open_covenant_store(&store_paths).await?;
+    let store = runtime
+        .list_covenant_patterns(None)
+        .await?
+        .into_iter()
+        .filter(|pattern| pattern.namespace == namespace)
+        .collect::<Vec<_>>();
+
+    let base = bases.remove(&base_key).unwrap_or_default();
+    let plan = plan_sync(repo, store, &base, cmd.prefer)?;
+    if !plan.conflicts.is_empty() {
+        let conflicts = plan
+            .conflicts
+            .iter()
+            .map(|conflict| conflict.id.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        anyhow::bail!(
+            "{} and the pattern store both changed {conflicts} since the last sync; rerun with `--prefer repo` or `--prefer store`",
+            file_path.display()
+        );
+    }
+
+    if !plan.import.is_empty() {
+        let covenant_version = load_covenant(cwd.as_path())
+            .await
+            .map(|covenant| covenant.version)
+            .unwrap_or_else(|_| "missing".to_string());
+        // The imported patterns and their audit entry land together.
+        let mut tx = runtime.begin_covenant_transaction().await?;
+        for pattern in &plan.import {
+            tx.upsert_covenant_pattern(pattern).await?;
+        }
+        tx.insert_audit_action(&AuditAction {
+            timestamp: unix_timestamp(),
+            actor: cmd.actor.clone(),
+            action_type: "patterns.sync".to_string(),
+            scope: "cli".to_string(),
+            covenant_version,
+            event_id: None,
+            intent_id: None,
+        })
+        .await?;
+        tx.commit().await?;
+    }
+
+    let contents = synced_patterns_file(&plan.file)?;
+    if std::fs::read_to_string(&file_path).ok().as_deref() != Some(contents.as_str()) {
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&file_path, contents)
+            .with_context(|| format!("failed to write {}", file_path.display()))?;
+    }
+    bases.insert(
+        base_key,
+        plan.file
+            .iter()
+            .map(|pattern| Ok((pattern.id.clone(), synced_line(pattern)?)))
+            .collect::<anyhow::Result<_>>()?,
+    );
+    write_sync_bases(base_path.as_path(), &bases)?;
+    println!(
+        "{}: imported {}, exported {}, {} total",
+        file_path.display(),
+        plan.import.len(),
+        plan.exported,
+        plan.file.len()
+    );
+    Ok(())
+}
+
+/// Merge the repository's patterns with the store's by id, against `base`,
+/// what both held after the last sync. A pattern only one side has is copied
+/// to the other. When both have different copies, the side that still matches
+/// the base did not change it, so the other side's copy wins. When neither
+/// matches, both changed it and the copies conflict unless `prefer` picks a
+/// side. Copies are compared as they are synced, so counters never differ.
+pub(crate) fn plan_sync(
+    repo: Vec<CovenantPattern>,
+    store: Vec<CovenantPattern>,
+    base: &SyncBase,
+    prefer: Option<SyncSide>,
+) -> anyhow::Result<SyncPlan> {
+    let mut repo = repo
+        .into_iter()
+        .map(|pattern| (pattern.id.clone(), pattern))
+        .collect::<BTreeMap<_, _>>();
+    let mut plan = SyncPlan::default();
+    let mut file = BTreeMap::new();
+    for pattern in store {
+        let Some(theirs) = repo.remove(&pattern.id) else {
+            plan.exported += 1;
+            file.insert(pattern.id.clone(), pattern);
+            continue;
+        };
+        let ours = synced_line(&pattern)?;
+        let repo_line = synced_line(&theirs)?;
+        let base = base.get(&pattern.id);
+        let winner = if repo_line == ours || base == Some(&repo_line) {
+            SyncSide::Store
+        } else if base == Some(&ours) {
+            SyncSide::Repo
+        } else if let Some(prefer) = prefer {
+            prefer
+        } else {
+            plan.conflicts.push(SyncConflict {
+                id: pattern.id.clone(),
+            });
+            SyncSide::Store
+        };
+        match winner {
+            SyncSide::Repo => {
+                plan.import.push(theirs.clone());
+                file.insert(theirs.id.clone(), theirs);
+            }
+            SyncSide::Store => {
+                file.insert(pattern.id.clone(), pattern);
+            }
+        }
+    }
+    for (id, pattern) in repo {
+        plan.import.push(pattern.clone());
+        file.insert(id, pattern);
+    }
+    plan.file = file.into_values().collect();
+    Ok(plan)
+}
+
+/// Read the synced file, giving each pattern the current project's namespace.
+/// A missing file holds no patterns. Unresolved merge conflicts, and ids that
+/// appear twice with different contents, are errors.
+pub(crate) fn read_synced_patterns(
+    path: &Path,
+    namespace: &str,
+) -> anyhow::Result<Vec<CovenantPattern>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", path.display()));
+        }
+    };
+    let mut patterns: BTreeMap<String, CovenantPattern> = BTreeMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        if CONFLICT_MARKERS
+            .iter()
+            .any(|marker| line.starts_with(marker))
+        {
+            anyhow::bail!(
+                "{}:{line_number}: unresolved merge conflict",
+                path.display()
+            );
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut value: Value = serde_json::from_str(line)
+            .with_context(|| format!("{}:{line_number}: invalid JSON", path.display()))?;
+        if let Value::Object(fields) = &mut value {
+            for field in UNSYNCED_FIELDS {
+                fields.remove(field);
+            }
+            fields.insert(
+                "namespace".to_string(),
+                Value::String(namespace.to_string()),
+            );
+        }
+        let pattern: CovenantPattern = serde_json::from_value(value)
+            .with_context(|| format!("{}:{line_number}: invalid pattern", path.display()))?;
+        if let Some(existing) = patterns.get(&pattern.id)
+            && existing != &pattern
+        {
+            anyhow::bail!(
+                "{}:{line_number}: pattern {} appears more than once with different contents",
+                path.display(),
+                pattern.id
+            );
+        }
+        patterns.insert(pattern.id.clone(), pattern);
+    }
+    Ok(patterns.into_values().collect())
+}
+
+/// One [synced line](synced_line) per pattern in `patterns`' order.
+pub(crate) fn synced_patterns_file(patterns: &[CovenantPattern]) -> anyhow::Result<String> {
+    let mut contents = String::new();
+    for pattern in patterns {
+        contents.push_str(&synced_line(pattern)?);
+        contents.push('\n');
+    }
+    Ok(contents)
+}
+
+/// `pattern` as canonical JSON without the [`UNSYNCED_FIELDS`].
+fn synced_line(pattern: &CovenantPattern) -> anyhow::Result<String> {
+    let mut value = serde_json::to_value(pattern)?;
+    if let Value::Object(fields) = &mut value {
+        for field in UNSYNCED_FIELDS {
+            fields.remove(field);
+        }
+    }
+    Ok(canonical_json(&value))
+}
+
+/// The recorded base of every synced file, keyed by the file's path. A
+/// missing record means nothing has been synced yet.
+fn read_sync_bases(path: &Path) -> anyhow::Result<BTreeMap<String, SyncBase>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse {}", path.display())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(err) => Err(err).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+fn write_sync_bases(path: &Path, bases: &BTreeMap<String, SyncBase>) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(bases)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn pattern(id: &str, best_response: &str, created_at: i64) -> CovenantPattern {
+        CovenantPattern {
+            id: id.to_string(),
+            trigger: "disk full".to_string(),
+            invariant: "disk pressure".to_string(),
+            counterexample: String::new(),
+            best_response: best_response.to_string(),
+            domain_signature: "[1.0]".to_string(),
+            evidence_refs: Vec::new(),
+            namespace: "project:aaaa".to_string(),
+            scope: "default".to_string(),
+            created_at,
+            anti_pattern: false,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+            supports: 0,
+            contradictions: 0,
+        }
+    }
+
+    #[test]
+    fn sync_takes_the_side_that_changed_since_the_base() {
+        let repo = vec![
+            pattern("cert", "renew the certificate", 20),
+            pattern("disk", "prune old logs", 5),
+            pattern("oom", "raise the memory limit", 10),
+            pattern("swap", "add swap", 1),
+        ];
+        let store = vec![
+            // Counted on this machine; not a change to sync.
+            CovenantPattern {
+                supports: 3,
+                ..pattern("disk", "rotate logs", 5)
+            },
+            pattern("dns", "flush the resolver cache", 5),
+            pattern("oom", "restart the worker", 10),
+            pattern("swap", "grow the swap file", 1),
+        ];
+        let base = [
+            pattern("disk", "rotate logs", 5),
+            pattern("oom", "kill the worker", 10),
+            pattern("swap", "add swap", 1),
+        ]
+        .iter()
+        .map(|pattern| {
+            (
+                pattern.id.clone(),
+                synced_line(pattern).expect("synced line"),
+            )
+        })
+        .collect::<SyncBase>();
+
+        let plan = plan_sync(repo.clone(), store.clone(), &base, None).expect("plan sync");
+        assert_eq!(
+            (plan.import.clone(), plan.exported, plan.conflicts),
+            (
+                vec![
+                    pattern("disk", "prune old logs", 5),
+                    pattern("cert", "renew the certificate", 20),
+                ],
+                1,
+                vec![SyncConflict {
+                    id: "oom".to_string(),
+                }],
+            )
+        );
+        assert_eq!(
+            (
+                plan_sync(repo.clone(), store.clone(), &SyncBase::new(), None)
+                    .expect("plan sync without a base")
+                    .conflicts
+                    .into_iter()
+                    .map(|conflict| conflict.id)
+                    .collect::<Vec<_>>(),
+                plan_sync(repo, store, &base, Some(SyncSide::Repo))
+                    .expect("plan sync preferring the repo")
+                    .file,
+            ),
+            (
+                vec!["disk".to_string(), "oom".to_string(), "swap".to_string()],
+                vec![
+                    pattern("cert", "renew the certificate", 20),
+                    pattern("disk", "prune old logs", 5),
+                    pattern("dns", "flush the resolver cache", 5),
+                    pattern("oom", "raise the memory limit", 10),
+                    pattern("swap", "grow the swap file", 1),
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn synced_file_round_trips_and_rejects_conflict_markers() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join(SYNCED_PATTERNS_FILENAME);
+        let patterns = vec![pattern("cert", "renew", 1), pattern("disk", "prune", 2)];
+        let counted = patterns
+            .iter()
+            .cloned()
+            .map(|pattern| CovenantPattern {
+                supports: 2,
+                contradictions: 1,
+                ..pattern
+            })
+            .collect::<Vec<_>>();
+        std::fs::write(
+            &path,
+            synced_patterns_file(&counted).expect("format patterns"),
+        )
+        .expect("write patterns");
+
+        assert_eq!(
+            std::fs::read_to_string(&path)
+                .expect("read patterns")
+                .lines()
+                .next(),
+            Some(
+                r#"{"anti_pattern":false,"best_response":"renew","counterexample":"","created_at":1,"domain_signature":"[1.0]","evidence_refs":[],"id":"cert","invariant":"disk pressure","scope":"default","trigger":"disk full"}"#
+            )
+        );
+        assert_eq!(
+            read_synced_patterns(&path, "project:aaaa").expect("read synced patterns"),
+            patterns
+        );
+
+        std::fs::write(&path, "<<<<<<< HEAD\n=======\n>>>>>>> branch\n").expect("write conflict");
+        assert_eq!(
+            read_synced_patterns(&path, "project:aaaa")
+                .map_err(|err| err.to_string())
+                .map(|_| ()),
+            Err(format!("{}:1: unresolved merge conflict", path.display()))
+        );
+    }
+}
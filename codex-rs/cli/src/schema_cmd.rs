@@ -0,0 +1,97 @@
+//! `codex schema export`: JSON Schemas for the files and records Codex writes.
+
+use anyhow::Context;
+use clap::Parser;
+use clap::ValueEnum;
+use codex_state::CovenantEvent;
+use schemars::schema::RootSchema;
+use std::path::PathBuf;
+
+use crate::compile_cmd::SuggestedPattern;
+use crate::stats::StatsSummary;
+
+#[derive(Debug, Parser)]
+pub struct SchemaCli {
+    #[command(subcommand)]
+    pub subcommand: SchemaSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SchemaSubcommand {
+    /// Write the JSON Schema for one serialized format.
+    Export(ExportArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct ExportArgs {
+    /// Format to describe.
+    #[arg(long = "type", value_enum)]
+    pub kind: SchemaKind,
+
+    /// File to write. Defaults to stdout.
+    #[arg(short = 'o', long = "out", value_name = "FILE")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaKind {
+    /// Record returned by the capture tool.
+    Capture,
+    /// One line of `CODEX_HOME/patterns.jsonl`.
+    Pattern,
+    /// An event from the covenant store.
+    CovenantEvent,
+    /// Output of `codex stats --json`.
+    Stats,
+}
+
+pub fn run_schema(cli: SchemaCli) -> anyhow::Result<()> {
+    match cli.subcommand {
+        SchemaSubcommand::Export(args) => {
+            let schema = serde_json::to_string_pretty(&schema_for_kind(args.kind))?;
+            match args.out {
+                Some(path) => std::fs::write(&path, format!("{schema}\n"))
+                    .with_context(|| format!("failed to write {}", path.display()))?,
+                None => println!("{schema}"),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn schema_for_kind(kind: SchemaKind) -> RootSchema {
+    match kind {
+        SchemaKind::Capture => codex_core::capture_schema(),
+        SchemaKind::Pattern => schemars::schema_for!(SuggestedPattern),
+        SchemaKind::CovenantEvent => schemars::schema_for!(CovenantEvent),
+        SchemaKind::Stats => schemars::schema_for!(StatsSummary),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn every_kind_exports_its_root_type() {
+        let titles = SchemaKind::value_variants()
+            .iter()
+            .map(|kind| {
+                schema_for_kind(*kind)
+                    .schema
+                    .metadata
+                    .and_then(|metadata| metadata.title)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec![
+                Some("CaptureRecord".to_string()),
+                Some("SuggestedPattern".to_string()),
+                Some("CovenantEvent".to_string()),
+                Some("StatsSummary".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Approval friction: how often turns stopped for an exec or patch approval,
+//! how long the user took to decide, and how often they said no.
+
+use codex_protocol::protocol::ApprovalKind;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::session::SessionStats;
+
+/// One approval decided during a turn.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TurnApproval {
+    pub(crate) kind: ApprovalKind,
+    /// The request was denied, or aborted the turn.
+    pub(crate) denied: bool,
+    /// Milliseconds from the request to the decision.
+    pub(crate) wait_ms: u64,
+}
+
+/// Counts and rates for one group of approvals.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct ApprovalStats {
+    pub(crate) requests: usize,
+    pub(crate) denied: usize,
+    /// Requests per counted turn, or `null` when there were no turns.
+    pub(crate) per_turn: Option<f64>,
+    /// Mean seconds from request to decision, or `null` without requests.
+    pub(crate) mean_wait_secs: Option<f64>,
+    /// Share of requests that were denied or aborted, or `null` without
+    /// requests.
+    pub(crate) denial_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct ApprovalReport {
+    pub(crate) all: ApprovalStats,
+    pub(crate) exec: ApprovalStats,
+    pub(crate) patch: ApprovalStats,
+}
+
+impl ApprovalReport {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.all.requests == 0
+    }
+}
+
+pub(crate) fn approval_report(sessions: &[SessionStats]) -> ApprovalReport {
+    let turns = sessions.iter().flat_map(|session| &session.turns);
+    let approvals = turns
+        .clone()
+        .flat_map(|turn| &turn.approvals)
+        .collect::<Vec<_>>();
+    let turns = turns.count();
+    let stats = |kind: Option<ApprovalKind>| {
+        approval_stats(
+            approvals
+                .iter()
+                .copied()
+                .filter(|approval| kind.is_none_or(|kind| approval.kind == kind)),
+            turns,
+        )
+    };
+    ApprovalReport {
+        all: stats(None),
+        exec: stats(Some(ApprovalKind::Exec)),
+        patch: stats(Some(ApprovalKind::Patch)),
+    }
+}
+
+fn approval_stats<'a>(
+    approvals: impl Iterator<Item = &'a TurnApproval>,
+    turns: usize,
+) -> ApprovalStats {
+    let mut requests = 0;
+    let mut denied = 0;
+    let mut wait_ms = 0u64;
+    for approval in approvals {
+        requests += 1;
+        denied += usize::from(approval.denied);
+        wait_ms = wait_ms.saturating_add(approval.wait_ms);
+    }
+    let has_requests = requests > 0;
+    ApprovalStats {
+        requests,
+        denied,
+        per_turn: (turns > 0).then(|| requests as f64 / turns as f64),
+        mean_wait_secs: has_requests.then(|| wait_ms as f64 / 1000.0 / requests as f64),
+        denial_rate: has_requests.then(|| denied as f64 / requests as f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::session::TurnStats;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn turn(approvals: &[(ApprovalKind, bool, u64)]) -> TurnStats {
+        TurnStats {
+            approvals: approvals
+                .iter()
+                .map(|(kind, denied, wait_ms)| TurnApproval {
+                    kind: *kind,
+                    denied: *denied,
+                    wait_ms: *wait_ms,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn approvals_are_counted_per_turn_with_wait_and_denial_rate() {
+        let session = SessionStats {
+            session_id: "session-1".to_string(),
+            started_at: None,
+            model: None,
+            path: PathBuf::from("rollout.jsonl"),
+            turns: vec![
+                turn(&[
+                    (ApprovalKind::Exec, false, 1_000),
+                    (ApprovalKind::Exec, true, 3_000),
+                ]),
+                turn(&[(ApprovalKind::Patch, false, 500)]),
+                turn(&[]),
+                turn(&[]),
+            ],
+        };
+
+        assert_eq!(
+            approval_report(&[session]),
+            ApprovalReport {
+                all: ApprovalStats {
+                    requests: 3,
+                    denied: 1,
+                    per_turn: Some(0.75),
+                    mean_wait_secs: Some(1.5),
+                    denial_rate: Some(1.0 / 3.0),
+                },
+                exec: ApprovalStats {
+                    requests: 2,
+                    denied: 1,
+                    per_turn: Some(0.5),
+                    mean_wait_secs: Some(2.0),
+                    denial_rate: Some(0.5),
+                },
+                patch: ApprovalStats {
+                    requests: 1,
+                    denied: 0,
+                    per_turn: Some(0.25),
+                    mean_wait_secs: Some(0.5),
+                    denial_rate: Some(0.0),
+                },
+            }
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! `codex stats compare`: aggregate deltas between two groups of sessions.
+
+use anyhow::Context;
+use serde::Serialize;
+use std::path::Path;
+use std::path::PathBuf;
+
+use super::session::SessionStats;
+use super::session::StatsAggregate;
+
+/// Each side needs at least this many turns (or scored turns, for fidelity)
+/// before a delta is tested for significance.
+const MIN_SAMPLES: usize = 10;
+
+/// Two-sided critical values of the standard normal distribution.
+const Z_P01: f64 = 2.576;
+const Z_P05: f64 = 1.960;
+
+/// Aggregates for both sides of a comparison and the change between them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct StatsComparison {
+    pub(crate) before: ComparisonSide,
+    pub(crate) after: ComparisonSide,
+    pub(crate) hit_rate: MetricDelta,
+    pub(crate) fidelity: MetricDelta,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ComparisonSide {
+    pub(crate) sessions: usize,
+    pub(crate) turns: usize,
+    pub(crate) hit_rate: Option<f64>,
+    pub(crate) fidelity: Option<f64>,
+}
+
+/// Change in one metric from `before` to `after`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct MetricDelta {
+    /// `after - before`, or `null` when either side has no value.
+    pub(crate) delta: Option<f64>,
+    /// Test statistic behind `significance`, when one could be computed.
+    pub(crate) z_score: Option<f64>,
+    pub(crate) significance: Significance,
+}
+
+/// How unlikely a delta is to be noise, from a two-sided z-test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Significance {
+    /// p < 0.01.
+    Strong,
+    /// p < 0.05.
+    Likely,
+    NotSignificant,
+    TooFewSamples,
+}
+
+impl Significance {
+    fn from_z_score(z_score: f64) -> Self {
+        let z_score = z_score.abs();
+        if z_score >= Z_P01 {
+            Self::Strong
+        } else if z_score >= Z_P05 {
+            Self::Likely
+        } else {
+            Self::NotSignificant
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Strong => "significant (p < 0.01)",
+            Self::Likely => "significant (p < 0.05)",
+            Self::NotSignificant => "not significant",
+            Self::TooFewSamples => "too few samples",
+        }
+    }
+}
+
+impl ComparisonSide {
+    fn new(aggregate: &StatsAggregate) -> Self {
+        Self {
+            sessions: aggregate.sessions,
+            turns: aggregate.turns,
+            hit_rate: aggregate.hit_rate(),
+            fidelity: aggregate.fidelity(),
+        }
+    }
+}
+
+pub(crate) fn compare_aggregates(
+    before: &StatsAggregate,
+    after: &StatsAggregate,
+) -> StatsComparison {
+    StatsComparison {
+        before: ComparisonSide::new(before),
+        after: ComparisonSide::new(after),
+        hit_rate: hit_rate_delta(before, after),
+        fidelity: fidelity_delta(before, after),
+    }
+}
+
+/// Sum the sessions into one aggregate.
+pub(crate) fn aggregate_sessions<'a>(
+    sessions: impl IntoIterator<Item = &'a SessionStats>,
+) -> StatsAggregate {
+    sessions
+        .into_iter()
+        .map(StatsAggregate::from_session)
+        .fold(StatsAggregate::default(), StatsAggregate::merge)
+}
+
+/// Whether a session started within `period` (`YYYY`, `YYYY-MM`, or
+/// `YYYY-MM-DD`). Sessions without a start date match no period.
+pub(crate) fn session_in_period(session: &SessionStats, period: &str) -> bool {
+    session.date().is_some_and(|date| date.starts_with(period))
+}
+
+/// Clap value parser for `--before` and `--after`.
+pub(crate) fn parse_period(value: &str) -> Result<String, String> {
+    let parts = value.split('-').collect::<Vec<_>>();
+    let expected_lengths: &[usize] = &[4, 2, 2];
+    let valid = parts.len() <= expected_lengths.len()
+        && parts
+            .iter()
+            .zip(expected_lengths)
+            .all(|(part, len)| part.len() == *len && part.chars().all(|ch| ch.is_ascii_digit()));
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(format!(
+            "'{value}' is not a period; use YYYY, YYYY-MM, or YYYY-MM-DD"
+        ))
+    }
+}
+
+/// Rollout paths listed in `path`, one per line. Blank lines and lines
+/// starting with `#` are skipped.
+pub(crate) fn read_rollout_list(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Two-proportion z-test on hits over turns.
+fn hit_rate_delta(before: &StatsAggregate, after: &StatsAggregate) -> MetricDelta {
+    let delta = before
+        .hit_rate()
+        .zip(after.hit_rate())
+        .map(|(before, after)| after - before);
+    if before.turns < MIN_SAMPLES || after.turns < MIN_SAMPLES {
+        return too_few_samples(delta);
+    }
+    let pooled = (before.hits + after.hits) as f64 / (before.turns + after.turns) as f64;
+    let standard_error =
+        (pooled * (1.0 - pooled) * (1.0 / before.turns as f64 + 1.0 / after.turns as f64)).sqrt();
+    tested(delta, standard_error)
+}
+
+/// Welch's z-test on the per-turn fidelity scores.
+fn fidelity_delta(before: &StatsAggregate, after: &StatsAggregate) -> MetricDelta {
+    let delta = before
+        .fidelity()
+        .zip(after.fidelity())
+        .map(|(before, after)| after - before);
+    if before.fidelity_samples < MIN_SAMPLES || after.fidelity_samples < MIN_SAMPLES {
+        return too_few_samples(delta);
+    }
+    let standard_error = (fidelity_variance(before) / before.fidelity_samples as f64
+        + fidelity_variance(after) / after.fidelity_samples as f64)
+        .sqrt();
+    tested(delta, standard_error)
+}
+
+/// Sample variance of the scored turns. Needs at least two samples.
+fn fidelity_variance(aggregate: &StatsAggregate) -> f64 {
+    let samples = aggregate.fidelity_samples as f64;
+    let mean = aggregate.fidelity_sum / samples;
+    ((aggregate.fidelity_squares_sum - samples * mean * mean) / (samples - 1.0)).max(0.0)
+}
+
+fn tested(delta: Option<f64>, standard_error: f64) -> MetricDelta {
+    let Some(delta) = delta else {
+        return too_few_samples(None);
+    };
+    // Without any spread, only an exact tie is noise.
+    let (z_score, significance) = if standard_error > 0.0 {
+        let z_score = delta / standard_error;
+        (Some(z_score), Significance::from_z_score(z_score))
+    } else if delta == 0.0 {
+        (None, Significance::NotSignificant)
+    } else {
+        (None, Significance::Strong)
+    };
+    MetricDelta {
+        delta: Some(delta),
+        z_score,
+        significance,
+    }
+}
+
+fn too_few_samples(delta: Option<f64>) -> MetricDelta {
+    MetricDelta {
+        delta,
+        z_score: None,
+        significance: Significance::TooFewSamples,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn aggregate(turns: usize, hits: usize, fidelity: &[f64]) -> StatsAggregate {
+        StatsAggregate {
+            sessions: 1,
+            turns,
+            hits,
+            fidelity_sum: fidelity.iter().sum(),
+            fidelity_squares_sum: fidelity.iter().map(|score| score * score).sum(),
+            fidelity_samples: fidelity.len(),
+        }
+    }
+
+    #[test]
+    fn deltas_are_tested_once_both_sides_have_enough_samples() {
+        let before = aggregate(100, 50, &[0.5, 0.6, 0.4]);
+        let after = aggregate(100, 70, &[0.9, 0.8, 0.7]);
+
+        let comparison = compare_aggregates(&before, &after);
+        let rounded = |value: Option<f64>| value.map(|value| (value * 100.0).round() / 100.0);
+
+        assert_eq!(
+            (
+                rounded(comparison.hit_rate.delta),
+                rounded(comparison.hit_rate.z_score),
+                comparison.hit_rate.significance,
+            ),
+            (Some(0.2), Some(2.89), Significance::Strong)
+        );
+        assert_eq!(
+            (
+                rounded(comparison.fidelity.delta),
+                comparison.fidelity.significance,
+            ),
+            (Some(0.3), Significance::TooFewSamples)
+        );
+        let small_change = compare_aggregates(&aggregate(40, 20, &[]), &aggregate(40, 22, &[]));
+        assert_eq!(
+            (
+                rounded(small_change.hit_rate.delta),
+                rounded(small_change.hit_rate.z_score),
+                small_change.hit_rate.significance,
+                small_change.fidelity,
+            ),
+            (
+                Some(0.05),
+                Some(0.45),
+                Significance::NotSignificant,
+                MetricDelta {
+                    delta: None,
+                    z_score: None,
+                    significance: Significance::TooFewSamples,
+                },
+            )
+        );
+    }
+
+    #[test]
+    fn periods_are_years_months_or_days() {
+        assert_eq!(
+            [
+                "2024",
+                "2024-05",
+                "2024-05-03",
+                "2024-5",
+                "May",
+                "2024-05-03-01"
+            ]
+            .into_iter()
+            .map(|value| parse_period(value).is_ok())
+            .collect::<Vec<_>>(),
+            vec![true, true, true, false, false, false]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! `codex stats drift`: how far each user message strays from the intent the
+//! session started with.
+
+use codex_utils_text_normalize::TextNormalizer;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+use super::session::SessionStats;
+
+/// Turns whose drift is above this are flagged unless `--threshold` is given.
+pub(crate) const DEFAULT_DRIFT_THRESHOLD: f64 = 0.8;
+
+/// Messages are fingerprinted by their single content words, so rephrasing
+/// the same request does not register as drift.
+const FINGERPRINT_NGRAM: usize = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct SessionDrift {
+    pub(crate) session_id: String,
+    pub(crate) started_at: Option<String>,
+    /// One point per turn, in order.
+    pub(crate) points: Vec<DriftPoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct DriftPoint {
+    /// 1-based turn number.
+    pub(crate) turn: usize,
+    /// Jaccard distance between this turn's user message and the first
+    /// intent, or `null` when either has no content words.
+    pub(crate) drift: Option<f64>,
+    pub(crate) flagged: bool,
+}
+
+impl SessionDrift {
+    pub(crate) fn flagged_turns(&self) -> usize {
+        self.points.iter().filter(|point| point.flagged).count()
+    }
+
+    pub(crate) fn max_drift(&self) -> Option<f64> {
+        self.points
+            .iter()
+            .filter_map(|point| point.drift)
+            .reduce(f64::max)
+    }
+}
+
+/// Measure each turn against the first user message with content words.
+pub(crate) fn session_drift(session: &SessionStats, threshold: f64) -> SessionDrift {
+    let normalizer = TextNormalizer::configured();
+    let fingerprints = session
+        .turns
+        .iter()
+        .map(|turn| normalizer.fingerprint(&turn.user_message, FINGERPRINT_NGRAM))
+        .collect::<Vec<_>>();
+    let intent = fingerprints
+        .iter()
+        .find(|fingerprint| !fingerprint.is_empty());
+    let points = fingerprints
+        .iter()
+        .enumerate()
+        .map(|(index, fingerprint)| {
+            let drift = intent
+                .filter(|_| !fingerprint.is_empty())
+                .map(|intent| 1.0 - jaccard_similarity(intent, fingerprint));
+            DriftPoint {
+                turn: index + 1,
+                drift,
+                flagged: drift.is_some_and(|drift| drift > threshold),
+            }
+        })
+        .collect();
+    SessionDrift {
+        session_id: session.session_id.clone(),
+        started_at: session.started_at.clone(),
+        points,
+    }
+}
+
+/// Clap value parser for `--threshold`.
+pub(crate) fn parse_threshold(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(threshold) if (0.0..=1.0).contains(&threshold) => Ok(threshold),
+        _ => Err(format!("'{value}' is not a number between 0 and 1")),
+    }
+}
+
+pub(crate) fn jaccard_similarity(left: &BTreeSet<u64>, right: &BTreeSet<u64>) -> f64 {
+    let intersection = left.intersection(right).count();
+    let union = left.len() + right.len() - intersection;
+    if union == 0 {
+        0.0
+    } else {
+        intersection as f64 / union as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::session::TurnStats;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn session(messages: &[&str]) -> SessionStats {
+        SessionStats {
+            session_id: "session-1".to_string(),
+            started_at: None,
+            model: None,
+            path: PathBuf::from("rollout.jsonl"),
+            turns: messages
+                .iter()
+                .map(|message| TurnStats {
+                    user_message: message.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn drift_is_measured_against_the_first_intent() {
+        let drift = session_drift(
+            &session(&[
+                "?",
+                "fix the flaky login test",
+                "the login test is still flaky",
+                "also bump the release version",
+            ]),
+            0.8,
+        );
+        let rounded = drift
+            .points
+            .iter()
+            .map(|point| {
+                (
+                    point.drift.map(|drift| (drift * 100.0).round() / 100.0),
+                    point.flagged,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            rounded,
+            vec![
+                (None, false),
+                (Some(0.0), false),
+                (Some(0.4), false),
+                (Some(1.0), true),
+            ]
+        );
+        assert_eq!((drift.flagged_turns(), drift.max_drift()), (1, Some(1.0)));
+    }
+}
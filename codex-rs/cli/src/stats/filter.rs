@@ -0,0 +1,152 @@
+//! `--filter` and `--tag`: limit the stats to one class of requests by what
+//! the user asked.
+
+use regex_lite::Regex;
+
+use super::session::SessionStats;
+use super::session::TurnStats;
+
+/// Which turns count toward the summary. A turn must match the pattern, when
+/// there is one, and carry every tag.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TurnFilter {
+    pub(crate) pattern: Option<Regex>,
+    /// Tags without their leading `#`.
+    pub(crate) tags: Vec<String>,
+}
+
+impl TurnFilter {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pattern.is_none() && self.tags.is_empty()
+    }
+
+    pub(crate) fn matches(&self, turn: &TurnStats) -> bool {
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(&turn.user_message))
+            && self.tags.iter().all(|tag| has_tag(&turn.user_message, tag))
+    }
+}
+
+/// Keep only the turns `filter` matches, dropping sessions left without any.
+pub(crate) fn filter_sessions(
+    sessions: Vec<SessionStats>,
+    filter: &TurnFilter,
+) -> Vec<SessionStats> {
+    if filter.is_empty() {
+        return sessions;
+    }
+    sessions
+        .into_iter()
+        .filter_map(|mut session| {
+            session.turns.retain(|turn| filter.matches(turn));
+            (!session.turns.is_empty()).then_some(session)
+        })
+        .collect()
+}
+
+pub(crate) fn parse_filter(value: &str) -> Result<Regex, String> {
+    Regex::new(value).map_err(|err| format!("invalid regular expression: {err}"))
+}
+
+/// Accepts `infra` or `#infra`.
+pub(crate) fn parse_tag(value: &str) -> Result<String, String> {
+    let tag = value.strip_prefix('#').unwrap_or(value);
+    if tag.is_empty() || !tag.chars().all(is_tag_char) {
+        return Err(format!(
+            "'{value}' is not a tag; tags use letters, digits, '-' and '_'"
+        ));
+    }
+    Ok(tag.to_string())
+}
+
+/// Whether `message` contains `#tag` as a whole word, ignoring ASCII case.
+fn has_tag(message: &str, tag: &str) -> bool {
+    message.match_indices('#').any(|(index, _)| {
+        let starts_word = message[..index]
+            .chars()
+            .next_back()
+            .is_none_or(|ch| !is_tag_char(ch));
+        let rest = &message[index + 1..];
+        let end = rest.find(|ch: char| !is_tag_char(ch)).unwrap_or(rest.len());
+        starts_word && rest[..end].eq_ignore_ascii_case(tag)
+    })
+}
+
+fn is_tag_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '-' || ch == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn session(id: &str, messages: &[&str]) -> SessionStats {
+        SessionStats {
+            session_id: id.to_string(),
+            started_at: None,
+            model: None,
+            path: PathBuf::from("rollout.jsonl"),
+            turns: messages
+                .iter()
+                .map(|message| TurnStats {
+                    user_message: (*message).to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn only_matching_turns_and_their_sessions_are_kept() {
+        let sessions = vec![
+            session(
+                "infra",
+                &[
+                    "#infra refactor the deploy script",
+                    "refactor the #infrastructure module",
+                    "Refactor the DNS config (#INFRA)",
+                ],
+            ),
+            session("docs", &["refactor the README #docs"]),
+        ];
+        let filter = TurnFilter {
+            pattern: Some(parse_filter("(?i)refactor").expect("parse filter")),
+            tags: vec![parse_tag("#infra").expect("parse tag")],
+        };
+
+        let kept = filter_sessions(sessions, &filter)
+            .into_iter()
+            .map(|session| {
+                (
+                    session.session_id,
+                    session
+                        .turns
+                        .into_iter()
+                        .map(|turn| turn.user_message)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kept,
+            vec![(
+                "infra".to_string(),
+                vec![
+                    "#infra refactor the deploy script".to_string(),
+                    "Refactor the DNS config (#INFRA)".to_string(),
+                ]
+            )]
+        );
+        assert_eq!(
+            (parse_tag("#"), parse_filter("(").is_err()),
+            (
+                Err("'#' is not a tag; tags use letters, digits, '-' and '_'".to_string()),
+                true
+            )
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! `codex stats label`: human success/failure judgments of sampled turns,
+//! kept in `CODEX_HOME/labels.jsonl`, and `codex stats --calibrate`, which
+//! checks the hit and fidelity heuristics against them.
+
+use anyhow::Context;
+use codex_state::JsonlAppender;
+use codex_state::JsonlAppenderOptions;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+
+use super::pairs::IntentOutcomePair;
+use super::session::SessionStats;
+
+/// File under `CODEX_HOME` that holds turn labels.
+pub(crate) const LABELS_FILENAME: &str = "labels.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TurnLabel {
+    Success,
+    Failure,
+}
+
+/// One judgment, appended to the labels file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct LabelRecord {
+    pub(crate) session_id: String,
+    /// 1-based turn number within the session.
+    pub(crate) turn: usize,
+    pub(crate) label: TurnLabel,
+    pub(crate) labeled_at: i64,
+}
+
+/// What the labeler answered for one turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LabelChoice {
+    Label(TurnLabel),
+    Skip,
+    Quit,
+}
+
+/// Read the labels file, keeping the latest label of each turn. A missing
+/// file has no labels.
+pub(crate) fn read_labels(path: &Path) -> anyhow::Result<HashMap<(String, usize), TurnLabel>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", path.display()));
+        }
+    };
+    let mut labels = HashMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: LabelRecord = serde_json::from_str(line)
+            .with_context(|| format!("failed to parse {}:{}", path.display(), index + 1))?;
+        labels.insert((record.session_id, record.turn), record.label);
+    }
+    Ok(labels)
+}
+
+pub(crate) fn append_label(path: &Path, record: &LabelRecord) -> anyhow::Result<()> {
+    let mut log = JsonlAppender::open(path, JsonlAppenderOptions::default())?;
+    log.append(record)?;
+    log.finish()
+}
+
+/// Up to `size` of the unlabeled `pairs`, spread evenly from the first to
+/// the last so one long session does not fill the sample.
+pub(crate) fn sample_unlabeled<'a>(
+    pairs: &'a [IntentOutcomePair],
+    labels: &HashMap<(String, usize), TurnLabel>,
+    size: usize,
+) -> Vec<&'a IntentOutcomePair> {
+    let unlabeled = pairs
+        .iter()
+        .filter(|pair| !labels.contains_key(&(pair.context.session_id.clone(), pair.context.turn)))
+        .collect::<Vec<_>>();
+    if unlabeled.len() <= size {
+        return unlabeled;
+    }
+    (0..size)
+        .map(|index| unlabeled[index * unlabeled.len() / size])
+        .collect()
+}
+
+/// Show one turn and read the labeler's judgment. End of input quits.
+pub(crate) fn prompt_label<R: BufRead, W: Write>(
+    pair: &IntentOutcomePair,
+    input: &mut R,
+    output: &mut W,
+) -> anyhow::Result<LabelChoice> {
+    writeln!(
+        output,
+        "session: {} turn {}",
+        pair.context.session_id, pair.context.turn
+    )?;
+    writeln!(output, "user:    {}", pair.intent)?;
+    writeln!(output, "agent:   {}", pair.outcome)?;
+    loop {
+        write!(output, "success [y], failure [n], skip [s], quit [q]: ")?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(LabelChoice::Quit);
+        }
+        let answer = line.trim();
+        match answer.to_lowercase().as_str() {
+            "y" | "success" => return Ok(LabelChoice::Label(TurnLabel::Success)),
+            "n" | "failure" => return Ok(LabelChoice::Label(TurnLabel::Failure)),
+            "s" | "skip" => return Ok(LabelChoice::Skip),
+            "q" | "quit" => return Ok(LabelChoice::Quit),
+            _ => writeln!(output, "unrecognized choice '{answer}'")?,
+        }
+    }
+}
+
+/// How well the heuristics agree with the labels.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct LabelCalibration {
+    /// Labeled turns found in the loaded sessions.
+    pub(crate) labeled: usize,
+    pub(crate) successes: usize,
+    /// Share of labeled turns where "hit" matches "success", or `null`
+    /// without labeled turns.
+    pub(crate) hit_agreement: Option<f64>,
+    /// The fidelity at or above which calling a turn a success agrees with
+    /// the most labels, or `null` without scored labeled turns.
+    pub(crate) fidelity_threshold: Option<f64>,
+    /// Share of scored labeled turns that agree at `fidelity_threshold`.
+    pub(crate) threshold_agreement: Option<f64>,
+}
+
+pub(crate) fn calibrate(
+    sessions: &[SessionStats],
+    labels: &HashMap<(String, usize), TurnLabel>,
+) -> LabelCalibration {
+    let mut labeled = 0;
+    let mut successes = 0;
+    let mut hit_agreements = 0;
+    let mut scored = Vec::new();
+    for session in sessions {
+        for (index, turn) in session.turns.iter().enumerate() {
+            let Some(label) = labels.get(&(session.session_id.clone(), index + 1)) else {
+                continue;
+            };
+            let success = *label == TurnLabel::Success;
+            labeled += 1;
+            successes += usize::from(success);
+            hit_agreements += usize::from(turn.is_hit() == success);
+            if let Some(fidelity) = turn.fidelity() {
+                scored.push((fidelity, success));
+            }
+        }
+    }
+    let agreement = |threshold: f64| {
+        scored
+            .iter()
+            .filter(|(fidelity, success)| (*fidelity >= threshold) == *success)
+            .count()
+    };
+    // Candidate thresholds are the scores themselves, lowest first, so ties
+    // keep the most lenient one.
+    let mut thresholds = scored
+        .iter()
+        .map(|(fidelity, _)| *fidelity)
+        .collect::<Vec<_>>();
+    thresholds.sort_by(f64::total_cmp);
+    thresholds.dedup();
+    let mut best: Option<(f64, usize)> = None;
+    for threshold in thresholds {
+        let agreed = agreement(threshold);
+        if best.is_none_or(|(_, most)| agreed > most) {
+            best = Some((threshold, agreed));
+        }
+    }
+    LabelCalibration {
+        labeled,
+        successes,
+        hit_agreement: (labeled > 0).then(|| hit_agreements as f64 / labeled as f64),
+        fidelity_threshold: best.map(|(threshold, _)| threshold),
+        threshold_agreement: best.map(|(_, agreed)| agreed as f64 / scored.len() as f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::pairs::intent_outcome_pairs;
+    use crate::stats::session::TurnStats;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn turn(user_message: &str, reply: &str, last_tool_failed: bool) -> TurnStats {
+        TurnStats {
+            user_message: user_message.to_string(),
+            agent_messages: vec![reply.to_string()],
+            last_tool_failed,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn labels_sample_unlabeled_turns_and_calibrate_the_heuristics() {
+        let sessions = vec![SessionStats {
+            session_id: "s1".to_string(),
+            started_at: None,
+            model: None,
+            path: PathBuf::from("rollout.jsonl"),
+            turns: vec![
+                turn("fix the parser tests", "The parser tests fix is in.", false),
+                turn("update the docs", "Updated the docs.", true),
+                turn("rename the config flag", "Done.", false),
+                turn("bump the version", "Bumped the version.", false),
+            ],
+        }];
+        let labels = HashMap::from([
+            (("s1".to_string(), 1), TurnLabel::Success),
+            (("s1".to_string(), 2), TurnLabel::Failure),
+            (("s1".to_string(), 3), TurnLabel::Failure),
+        ]);
+
+        let pairs = intent_outcome_pairs(&sessions);
+        let sampled = sample_unlabeled(&pairs, &labels, 5)
+            .into_iter()
+            .map(|pair| pair.context.turn)
+            .collect::<Vec<_>>();
+        assert_eq!(sampled, vec![4]);
+
+        assert_eq!(
+            calibrate(&sessions, &labels),
+            LabelCalibration {
+                labeled: 3,
+                successes: 1,
+                hit_agreement: Some(2.0 / 3.0),
+                fidelity_threshold: Some(1.0),
+                threshold_agreement: Some(1.0),
+            }
+        );
+    }
+
+    #[test]
+    fn prompts_accept_a_judgment_and_quit_at_end_of_input() {
+        let pairs = intent_outcome_pairs(&[SessionStats {
+            session_id: "s1".to_string(),
+            started_at: None,
+            model: None,
+            path: PathBuf::from("rollout.jsonl"),
+            turns: vec![turn("fix the build", "Fixed it.", false)],
+        }]);
+        let mut output = Vec::new();
+
+        assert_eq!(
+            (
+                prompt_label(&pairs[0], &mut "maybe\nn\n".as_bytes(), &mut output).expect("prompt"),
+                prompt_label(&pairs[0], &mut "".as_bytes(), &mut Vec::new()).expect("prompt"),
+            ),
+            (LabelChoice::Label(TurnLabel::Failure), LabelChoice::Quit)
+        );
+        assert_eq!(
+            String::from_utf8(output).expect("utf-8"),
+            "session: s1 turn 1\n\
+             user:    fix the build\n\
+             agent:   Fixed it.\n\
+             success [y], failure [n], skip [s], quit [q]: unrecognized choice 'maybe'\n\
+             success [y], failure [n], skip [s], quit [q]: "
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Repeated-failure loops: runs of turns where the user kept sending nearly
+//! the same message because the previous turn failed, a sign the agent was
+//! stuck.
+
+use codex_utils_text_normalize::TextNormalizer;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::drift::jaccard_similarity;
+use super::session::SessionStats;
+
+/// Fewest consecutive turns that count as a loop.
+pub(crate) const LOOP_MIN_TURNS: usize = 3;
+
+/// Fingerprint similarity at or above which a message repeats the previous
+/// one.
+pub(crate) const LOOP_SIMILARITY: f64 = 0.6;
+
+const FINGERPRINT_NGRAM: usize = 1;
+
+/// One loop: `turns` turns starting at 1-based turn `first_turn`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct LoopSpan {
+    pub(crate) first_turn: usize,
+    pub(crate) turns: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct SessionLoops {
+    pub(crate) session_id: String,
+    pub(crate) loops: Vec<LoopSpan>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct LoopReport {
+    pub(crate) loops: usize,
+    /// Mean turns per loop, or `null` when there were none.
+    pub(crate) mean_turns: Option<f64>,
+    /// Sessions with at least one loop, most looped turns first.
+    pub(crate) sessions: Vec<SessionLoops>,
+}
+
+impl LoopReport {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.loops == 0
+    }
+}
+
+pub(crate) fn loop_report(sessions: &[SessionStats]) -> LoopReport {
+    let mut looped = sessions
+        .iter()
+        .map(|session| SessionLoops {
+            session_id: session.session_id.clone(),
+            loops: session_loops(session),
+        })
+        .filter(|session| !session.loops.is_empty())
+        .collect::<Vec<_>>();
+    let looped_turns =
+        |session: &SessionLoops| session.loops.iter().map(|span| span.turns).sum::<usize>();
+    looped.sort_by(|left, right| {
+        looped_turns(right)
+            .cmp(&looped_turns(left))
+            .then_with(|| left.session_id.cmp(&right.session_id))
+    });
+    let loops = looped.iter().map(|session| session.loops.len()).sum();
+    let turns = looped.iter().map(looped_turns).sum::<usize>();
+    LoopReport {
+        loops,
+        mean_turns: (loops > 0).then(|| turns as f64 / loops as f64),
+        sessions: looped,
+    }
+}
+
+/// A turn continues a run when the turn before it failed and its user
+/// message is at least [`LOOP_SIMILARITY`] similar to that turn's. Runs of
+/// [`LOOP_MIN_TURNS`] or more are loops.
+fn session_loops(session: &SessionStats) -> Vec<LoopSpan> {
+    let normalizer = TextNormalizer::configured();
+    let fingerprints = session
+        .turns
+        .iter()
+        .map(|turn| normalizer.fingerprint(&turn.user_message, FINGERPRINT_NGRAM))
+        .collect::<Vec<_>>();
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for index in 1..=session.turns.len() {
+        let continues = index < session.turns.len()
+            && !session.turns[index - 1].is_hit()
+            && !fingerprints[index].is_empty()
+            && jaccard_similarity(&fingerprints[index - 1], &fingerprints[index])
+                >= LOOP_SIMILARITY;
+        if continues {
+            continue;
+        }
+        if index - start >= LOOP_MIN_TURNS {
+            spans.push(LoopSpan {
+                first_turn: start + 1,
+                turns: index - start,
+            });
+        }
+        start = index;
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::session::TurnStats;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn session(id: &str, turns: &[(&str, bool)]) -> SessionStats {
+        SessionStats {
+            session_id: id.to_string(),
+            started_at: None,
+            model: None,
+            path: PathBuf::from("rollout.jsonl"),
+            turns: turns
+                .iter()
+                .map(|(message, hit)| TurnStats {
+                    user_message: message.to_string(),
+                    agent_messages: if *hit {
+                        vec!["Done.".to_string()]
+                    } else {
+                        Vec::new()
+                    },
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn repeated_messages_after_failures_are_loops() {
+        let sessions = [
+            session(
+                "login",
+                &[
+                    ("fix the login test", false),
+                    ("fix the login test", false),
+                    ("fix the login test", true),
+                    ("bump the release version", false),
+                    ("bump the release version", true),
+                    ("bump the release version", false),
+                ],
+            ),
+            session(
+                "deploy",
+                &[
+                    ("deploy the staging app", false),
+                    ("deploy the staging app", false),
+                    ("deploy the staging app", false),
+                    ("deploy the staging app", false),
+                ],
+            ),
+            session(
+                "steady",
+                &[
+                    ("deploy the staging app", true),
+                    ("deploy the staging app", true),
+                    ("deploy the staging app", true),
+                ],
+            ),
+        ];
+
+        assert_eq!(
+            loop_report(&sessions),
+            LoopReport {
+                loops: 2,
+                mean_turns: Some(3.5),
+                sessions: vec![
+                    SessionLoops {
+                        session_id: "deploy".to_string(),
+                        loops: vec![LoopSpan {
+                            first_turn: 1,
+                            turns: 4,
+                        }],
+                    },
+                    SessionLoops {
+                        session_id: "login".to_string(),
+                        loops: vec![LoopSpan {
+                            first_turn: 1,
+                            turns: 3,
+                        }],
+                    },
+                ],
+            }
+        );
+    }
+}
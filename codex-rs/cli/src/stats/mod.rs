@@ -0,0 +1,1022 @@
+//! `codex stats`: intent/outcome analytics over recorded rollout sessions.
+
+mod approvals;
+mod compare;
+mod drift;
+mod filter;
+mod labels;
+mod loops;
+mod pairs;
+mod recovery;
+mod report;
+mod session;
+
+use anyhow::Context;
+use clap::Parser;
+use clap::ValueEnum;
+use codex_core::ARCHIVED_SESSIONS_SUBDIR;
+use codex_core::SESSIONS_SUBDIR;
+use codex_core::config::find_codex_home;
+use codex_core::pattern_usage::PATTERN_USAGE_FILENAME;
+use codex_core::pattern_usage::PatternEffectiveness;
+use codex_core::pattern_usage::read_usage;
+use codex_core::pattern_usage::summarize_usage;
+use codex_state::StoreCipher;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::compile_cmd::unix_timestamp;
+
+use self::approvals::ApprovalReport;
+use self::approvals::approval_report;
+use self::compare::StatsComparison;
+use self::compare::aggregate_sessions;
+use self::compare::compare_aggregates;
+use self::compare::parse_period;
+use self::compare::read_rollout_list;
+use self::compare::session_in_period;
+use self::drift::DEFAULT_DRIFT_THRESHOLD;
+use self::drift::SessionDrift;
+use self::drift::parse_threshold;
+use self::drift::session_drift;
+use self::filter::TurnFilter;
+use self::filter::filter_sessions;
+use self::filter::parse_filter;
+use self::filter::parse_tag;
+use self::labels::LABELS_FILENAME;
+use self::labels::LabelCalibration;
+use self::labels::LabelChoice;
+use self::labels::LabelRecord;
+use self::labels::append_label;
+use self::labels::calibrate;
+use self::labels::prompt_label;
+use self::labels::read_labels;
+use self::labels::sample_unlabeled;
+use self::loops::LoopReport;
+use self::loops::loop_report;
+use self::pairs::intent_outcome_pairs;
+use self::pairs::render_pairs_jsonl;
+use self::recovery::RecoveryReport;
+use self::recovery::recovery_report;
+use self::report::render_html;
+use self::session::CorruptRollout;
+use self::session::FidelityScore;
+use self::session::SessionStats;
+pub(crate) use self::session::collect_rollout_paths;
+use self::session::load_sessions;
+use self::session::load_sessions_with;
+pub(crate) use self::session::read_rollout;
+pub(crate) use self::session::rollout_stem;
+use self::session::score_fidelity;
+
+#[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct StatsCommand {
+    #[command(subcommand)]
+    pub subcommand: Option<StatsSubcommand>,
+
+    /// List sessions from lowest to highest intent-outcome fidelity.
+    #[arg(long, default_value_t = false)]
+    pub rank_sessions: bool,
+
+    /// Maximum number of sessions to list with `--rank-sessions`.
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Directory containing rollout files. Defaults to `$CODEX_HOME/sessions`.
+    #[arg(long, value_name = "DIR")]
+    pub sessions_dir: Option<PathBuf>,
+
+    /// Codex home to read sessions and pattern usage from. Repeat to merge
+    /// several homes; sessions found in more than one are counted once.
+    #[arg(long = "codex-home", value_name = "DIR")]
+    pub codex_homes: Vec<PathBuf>,
+
+    /// More places to read sessions from: a Codex home, or any directory of
+    /// rollout files.
+    #[arg(value_name = "ROOT")]
+    pub roots: Vec<PathBuf>,
+
+    /// Also include archived sessions from `$CODEX_HOME/archived_sessions`.
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+
+    /// Number of rollout files to analyze in parallel. Defaults to the number
+    /// of available CPUs.
+    #[arg(long, value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Skip rollout lines that do not parse and report them per file instead
+    /// of failing. On by default with `--all`; `--lenient=false` turns it off.
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        action = clap::ArgAction::Set
+    )]
+    pub lenient: Option<bool>,
+
+    /// Only count turns whose user message matches this regular expression.
+    #[arg(long, value_name = "REGEX", value_parser = parse_filter)]
+    pub filter: Option<regex_lite::Regex>,
+
+    /// Only count turns whose user message carries this tag, as in `#infra`.
+    /// Repeat to require several tags.
+    #[arg(long = "tag", value_name = "TAG", value_parser = parse_tag)]
+    pub tags: Vec<String>,
+
+    /// Check the hit and fidelity heuristics against the turns labeled with
+    /// `codex stats label`, and suggest a fidelity threshold.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["rank_sessions", "format", "out"])]
+    pub calibrate: bool,
+
+    /// Print the summary as JSON. Same as `--format json`.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["rank_sessions", "format"])]
+    pub json: bool,
+
+    /// Output format for the summary.
+    #[arg(long, value_enum, default_value_t = StatsFormat::Text, conflicts_with = "rank_sessions")]
+    pub format: StatsFormat,
+
+    /// File to write the JSON summary or HTML report to. Defaults to stdout.
+    #[arg(
+        short = 'o',
+        long = "out",
+        value_name = "FILE",
+        conflicts_with = "rank_sessions"
+    )]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StatsFormat {
+    /// Plain-text tables.
+    Text,
+    /// The summary as one JSON object.
+    Json,
+    /// A standalone page with summary cards, a daily trend, and every session.
+    Html,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum StatsSubcommand {
+    /// Score one intent/outcome pair the way turn fidelity is scored.
+    Score(StatsScoreArgs),
+
+    /// Compare hit rate and fidelity between two groups of sessions.
+    Compare(StatsCompareArgs),
+
+    /// Report how far each session's user messages drift from its first intent.
+    Drift(StatsDriftArgs),
+
+    /// Write each turn's intent, final reply, and similarity as JSON Lines.
+    ExportPairs(StatsExportPairsArgs),
+
+    /// Judge sampled turns as successes or failures, for `--calibrate`.
+    Label(StatsLabelArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct StatsScoreArgs {
+    /// What the user asked for.
+    #[arg(long)]
+    pub intent: String,
+
+    /// What the agent replied or did.
+    #[arg(long)]
+    pub outcome: String,
+
+    /// Print the tokens and score as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct StatsCompareArgs {
+    /// Baseline sessions: those started in this period (YYYY, YYYY-MM, or
+    /// YYYY-MM-DD).
+    #[arg(
+        long,
+        value_name = "PERIOD",
+        value_parser = parse_period,
+        required_unless_present = "baseline",
+        conflicts_with = "baseline"
+    )]
+    pub before: Option<String>,
+
+    /// Sessions to compare against the baseline: those started in this period.
+    #[arg(
+        long,
+        value_name = "PERIOD",
+        value_parser = parse_period,
+        required_unless_present = "candidate",
+        conflicts_with = "candidate"
+    )]
+    pub after: Option<String>,
+
+    /// File listing the baseline rollout files, one path per line.
+    #[arg(long, value_name = "FILE")]
+    pub baseline: Option<PathBuf>,
+
+    /// File listing the rollout files to compare, one path per line.
+    #[arg(long, value_name = "FILE")]
+    pub candidate: Option<PathBuf>,
+
+    /// Directory containing rollout files. Defaults to `$CODEX_HOME/sessions`.
+    #[arg(long, value_name = "DIR")]
+    pub sessions_dir: Option<PathBuf>,
+
+    /// Also include archived sessions from `$CODEX_HOME/archived_sessions`.
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+
+    /// Number of rollout files to analyze in parallel.
+    #[arg(long, value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Print the comparison as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct StatsDriftArgs {
+    /// Only report the session with this id, turn by turn.
+    #[arg(long, value_name = "SESSION_ID")]
+    pub session: Option<String>,
+
+    /// Flag turns whose drift from the first intent is above this (0-1).
+    #[arg(long, default_value_t = DEFAULT_DRIFT_THRESHOLD, value_parser = parse_threshold)]
+    pub threshold: f64,
+
+    /// Directory containing rollout files. Defaults to `$CODEX_HOME/sessions`.
+    #[arg(long, value_name = "DIR")]
+    pub sessions_dir: Option<PathBuf>,
+
+    /// Also include archived sessions from `$CODEX_HOME/archived_sessions`.
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+
+    /// Number of rollout files to analyze in parallel.
+    #[arg(long, value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Print every session's drift curve as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct StatsExportPairsArgs {
+    /// File to write the pairs to, one JSON object per line.
+    #[arg(short = 'o', long = "out", value_name = "FILE")]
+    pub out: PathBuf,
+
+    /// Directory containing rollout files. Defaults to `$CODEX_HOME/sessions`.
+    #[arg(long, value_name = "DIR")]
+    pub sessions_dir: Option<PathBuf>,
+
+    /// Also include archived sessions from `$CODEX_HOME/archived_sessions`.
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+
+    /// Number of rollout files to analyze in parallel.
+    #[arg(long, value_name = "N")]
+    pub jobs: Option<usize>,
+}
+
+#[derive(Debug, Parser)]
+pub struct StatsLabelArgs {
+    /// Most turns to ask about, spread across the unlabeled turns.
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    pub sample: usize,
+
+    /// Directory containing rollout files. Defaults to `$CODEX_HOME/sessions`.
+    #[arg(long, value_name = "DIR")]
+    pub sessions_dir: Option<PathBuf>,
+
+    /// Also include archived sessions from `$CODEX_HOME/archived_sessions`.
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+
+    /// Number of rollout files to analyze in parallel.
+    #[arg(long, value_name = "N")]
+    pub jobs: Option<usize>,
+}
+
+/// The summary printed by `codex stats --json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct StatsSummary {
+    pub(crate) sessions: usize,
+    pub(crate) turns: usize,
+    /// Share of turns that were hits, or `null` when there were no turns.
+    pub(crate) hit_rate: Option<f64>,
+    /// Mean intent-outcome fidelity, or `null` when no turn was scored.
+    pub(crate) fidelity: Option<f64>,
+    /// Per-pattern usage from `$CODEX_HOME/pattern_usage.jsonl`.
+    #[serde(default)]
+    pub(crate) pattern_effectiveness: Vec<PatternEffectiveness>,
+    /// Recovery latency after errors, stream errors, and aborted turns.
+    #[serde(default)]
+    pub(crate) recovery: RecoveryReport,
+    /// Exec and patch approval requests, wait times, and denials.
+    #[serde(default)]
+    pub(crate) approvals: ApprovalReport,
+    /// Runs of near-identical user messages after failed turns.
+    #[serde(default)]
+    pub(crate) loops: LoopReport,
+    /// Rollout files with lines skipped by `--lenient`.
+    #[serde(default)]
+    pub(crate) corrupt_rollouts: Vec<CorruptRollout>,
+}
+
+impl StatsSummary {
+    fn new(sessions: &[SessionStats], pattern_effectiveness: Vec<PatternEffectiveness>) -> Self {
+        let aggregate = aggregate_sessions(sessions);
+        Self {
+            sessions: aggregate.sessions,
+            turns: aggregate.turns,
+            hit_rate: aggregate.hit_rate(),
+            fidelity: aggregate.fidelity(),
+            pattern_effectiveness,
+            recovery: recovery_report(sessions),
+            approvals: approval_report(sessions),
+            loops: loop_report(sessions),
+            corrupt_rollouts: Vec::new(),
+        }
+    }
+}
+
+pub fn run_stats(cmd: StatsCommand) -> anyhow::Result<()> {
+    match cmd.subcommand {
+        Some(StatsSubcommand::Score(args)) => {
+            let score = score_fidelity(args.intent.as_str(), args.outcome.as_str());
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&score)?);
+            } else {
+                print_fidelity_score(&score);
+            }
+            return Ok(());
+        }
+        Some(StatsSubcommand::Compare(args)) => return run_compare(args),
+        Some(StatsSubcommand::Drift(args)) => return run_drift(args),
+        Some(StatsSubcommand::ExportPairs(args)) => return run_export_pairs(args),
+        Some(StatsSubcommand::Label(args)) => return run_label(args),
+        None => {}
+    }
+
+    let homes = cmd
+        .codex_homes
+        .iter()
+        .chain(cmd.roots.iter().filter(|root| is_codex_home(root)))
+        .cloned()
+        .collect::<Vec<_>>();
+    let paths = if homes.is_empty() && cmd.roots.is_empty() {
+        session_rollout_paths(cmd.sessions_dir, cmd.all)?
+    } else {
+        let mut dirs = cmd.sessions_dir.into_iter().collect::<Vec<_>>();
+        for home in &homes {
+            dirs.push(home.join(SESSIONS_SUBDIR));
+            if cmd.all {
+                dirs.push(home.join(ARCHIVED_SESSIONS_SUBDIR));
+            }
+        }
+        dirs.extend(
+            cmd.roots
+                .iter()
+                .filter(|root| !is_codex_home(root))
+                .cloned(),
+        );
+        let mut paths = Vec::new();
+        for dir in &dirs {
+            paths.extend(collect_rollout_paths(dir.as_path())?);
+        }
+        paths
+    };
+    let (sessions, corrupt_rollouts) = load_sessions_with(
+        &paths,
+        default_jobs(cmd.jobs),
+        cmd.lenient.unwrap_or(cmd.all),
+    )?;
+    let sessions = filter_sessions(
+        dedupe_sessions(sessions),
+        &TurnFilter {
+            pattern: cmd.filter,
+            tags: cmd.tags,
+        },
+    );
+
+    if cmd.rank_sessions {
+        print_session_ranking(&sessions, cmd.limit);
+        print_corrupt_rollouts(&corrupt_rollouts);
+        return Ok(());
+    }
+
+    // Pattern usage and labels are optional: without a resolvable home there
+    // is no log.
+    let usage_homes = if homes.is_empty() {
+        find_codex_home().into_iter().collect()
+    } else {
+        homes
+    };
+    if cmd.calibrate {
+        let mut labels = HashMap::new();
+        for home in &usage_homes {
+            labels.extend(read_labels(home.join(LABELS_FILENAME).as_path())?);
+        }
+        let calibration = calibrate(&sessions, &labels);
+        if cmd.json {
+            println!("{}", serde_json::to_string_pretty(&calibration)?);
+        } else {
+            print_calibration(&calibration);
+        }
+        return Ok(());
+    }
+    let cipher = StoreCipher::load()?;
+    let mut usage = Vec::new();
+    for home in &usage_homes {
+        usage.extend(read_usage(
+            home.join(PATTERN_USAGE_FILENAME).as_path(),
+            cipher.as_ref(),
+        )?);
+    }
+    let summary = StatsSummary {
+        corrupt_rollouts,
+        ..StatsSummary::new(&sessions, summarize_usage(&usage))
+    };
+    let format = if cmd.json {
+        StatsFormat::Json
+    } else {
+        cmd.format
+    };
+    let rendered = match format {
+        StatsFormat::Text => {
+            if cmd.out.is_some() {
+                anyhow::bail!("--out needs --format json or --format html");
+            }
+            print_summary(&summary);
+            return Ok(());
+        }
+        StatsFormat::Json => format!("{}\n", serde_json::to_string_pretty(&summary)?),
+        StatsFormat::Html => render_html(&summary, &sessions)?,
+    };
+    match cmd.out {
+        Some(path) => std::fs::write(&path, rendered)
+            .with_context(|| format!("failed to write {}", path.display()))?,
+        None => print!("{rendered}"),
+    }
+    Ok(())
+}
+
+fn run_compare(args: StatsCompareArgs) -> anyhow::Result<()> {
+    let jobs = default_jobs(args.jobs);
+    // Period sides share one scan of the sessions directory.
+    let dated = if args.before.is_some() || args.after.is_some() {
+        load_sessions(&session_rollout_paths(args.sessions_dir, args.all)?, jobs)?
+    } else {
+        Vec::new()
+    };
+    let side = |period: Option<String>, list: Option<PathBuf>| -> anyhow::Result<_> {
+        match (period, list) {
+            (Some(period), _) => {
+                Ok(aggregate_sessions(dated.iter().filter(|session| {
+                    session_in_period(session, period.as_str())
+                })))
+            }
+            (None, Some(list)) => Ok(aggregate_sessions(&load_sessions(
+                &read_rollout_list(list.as_path())?,
+                jobs,
+            )?)),
+            (None, None) => Err(anyhow::anyhow!(
+                "each side needs a period or a rollout list"
+            )),
+        }
+    };
+    let before = side(args.before, args.baseline)?;
+    let after = side(args.after, args.candidate)?;
+
+    let comparison = compare_aggregates(&before, &after);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&comparison)?);
+    } else {
+        print_comparison(&comparison);
+    }
+    Ok(())
+}
+
+fn run_drift(args: StatsDriftArgs) -> anyhow::Result<()> {
+    let paths = session_rollout_paths(args.sessions_dir, args.all)?;
+    let sessions = load_sessions(&paths, default_jobs(args.jobs))?;
+    let drifts = sessions
+        .iter()
+        .filter(|session| {
+            args.session
+                .as_deref()
+                .is_none_or(|id| session.session_id == id)
+        })
+        .map(|session| session_drift(session, args.threshold))
+        .collect::<Vec<_>>();
+    if let Some(id) = args.session.as_deref()
+        && drifts.is_empty()
+    {
+        anyhow::bail!("no session with id {id}");
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&drifts)?);
+    } else if args.session.is_some() {
+        drifts.iter().for_each(print_drift_curve);
+    } else {
+        print_drifted_sessions(&drifts);
+    }
+    Ok(())
+}
+
+fn run_export_pairs(args: StatsExportPairsArgs) -> anyhow::Result<()> {
+    let paths = session_rollout_paths(args.sessions_dir, args.all)?;
+    let sessions = load_sessions(&paths, default_jobs(args.jobs))?;
+    let pairs = intent_outcome_pairs(&sessions);
+    std::fs::write(&args.out, render_pairs_jsonl(&pairs)?)
+        .with_context(|| format!("failed to write {}", args.out.display()))?;
+    println!("wrote {} pairs to {}", pairs.len(), args.out.display());
+    Ok(())
+}
+
+fn run_label(args: StatsLabelArgs) -> anyhow::Result<()> {
+    let paths = session_rollout_paths(args.sessions_dir, args.all)?;
+    let sessions = load_sessions(&paths, default_jobs(args.jobs))?;
+    let labels_path = find_codex_home()?.join(LABELS_FILENAME);
+    let labels = read_labels(labels_path.as_path())?;
+    let pairs = intent_outcome_pairs(&sessions);
+    let sample = sample_unlabeled(&pairs, &labels, args.sample);
+    if sample.is_empty() {
+        println!("no unlabeled turns with a reply");
+        return Ok(());
+    }
+
+    let total = sample.len();
+    let mut labeled = 0;
+    for (index, pair) in sample.into_iter().enumerate() {
+        println!("\n[{}/{total}]", index + 1);
+        let label = match prompt_label(pair, &mut std::io::stdin().lock(), &mut std::io::stdout())?
+        {
+            LabelChoice::Label(label) => label,
+            LabelChoice::Skip => continue,
+            LabelChoice::Quit => break,
+        };
+        append_label(
+            labels_path.as_path(),
+            &LabelRecord {
+                session_id: pair.context.session_id.clone(),
+                turn: pair.context.turn,
+                label,
+                labeled_at: unix_timestamp(),
+            },
+        )?;
+        labeled += 1;
+    }
+    println!("labeled {labeled} turns in {}", labels_path.display());
+    Ok(())
+}
+
+/// Rollout files under the sessions directory, plus the archived sessions
+/// when `all` is set.
+fn session_rollout_paths(sessions_dir: Option<PathBuf>, all: bool) -> anyhow::Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    match sessions_dir {
+        Some(dir) => dirs.push(dir),
+        None => dirs.push(find_codex_home()?.join(SESSIONS_SUBDIR)),
+    }
+    if all {
+        dirs.push(find_codex_home()?.join(ARCHIVED_SESSIONS_SUBDIR));
+    }
+    let mut paths = Vec::new();
+    for dir in &dirs {
+        paths.extend(collect_rollout_paths(dir.as_path())?);
+    }
+    Ok(paths)
+}
+
+/// A root with a `sessions` directory is read as a Codex home.
+fn is_codex_home(root: &Path) -> bool {
+    root.join(SESSIONS_SUBDIR).is_dir()
+}
+
+/// Keep one copy of each session id, preferring the copy with the most
+/// turns, since a synced copy may have been taken before the session ended.
+fn dedupe_sessions(sessions: Vec<SessionStats>) -> Vec<SessionStats> {
+    let mut by_id: HashMap<String, usize> = HashMap::new();
+    let mut kept: Vec<SessionStats> = Vec::with_capacity(sessions.len());
+    for session in sessions {
+        match by_id.get(&session.session_id) {
+            Some(&index) => {
+                if session.turns.len() > kept[index].turns.len() {
+                    kept[index] = session;
+                }
+            }
+            None => {
+                by_id.insert(session.session_id.clone(), kept.len());
+                kept.push(session);
+            }
+        }
+    }
+    kept
+}
+
+fn default_jobs(jobs: Option<usize>) -> usize {
+    jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(std::num::NonZero::get)
+            .unwrap_or(1)
+    })
+}
+
+fn print_comparison(comparison: &StatsComparison) {
+    let (before, after) = (&comparison.before, &comparison.after);
+    println!(
+        "{:<10} {:>10} {:>10} {:>10}  SIGNIFICANCE",
+        "", "BEFORE", "AFTER", "DELTA"
+    );
+    println!(
+        "{:<10} {:>10} {:>10} {:>+10}",
+        "sessions",
+        before.sessions,
+        after.sessions,
+        after.sessions as i64 - before.sessions as i64
+    );
+    println!(
+        "{:<10} {:>10} {:>10} {:>+10}",
+        "turns",
+        before.turns,
+        after.turns,
+        after.turns as i64 - before.turns as i64
+    );
+    println!(
+        "{:<10} {:>10} {:>10} {:>10}  {}",
+        "hit rate",
+        format_percent(before.hit_rate),
+        format_percent(after.hit_rate),
+        comparison.hit_rate.delta.map_or_else(
+            || "-".to_string(),
+            |delta| format!("{:+.1}pp", delta * 100.0)
+        ),
+        comparison.hit_rate.significance.label()
+    );
+    println!(
+        "{:<10} {:>10} {:>10} {:>10}  {}",
+        "fidelity",
+        format_score(before.fidelity),
+        format_score(after.fidelity),
+        comparison
+            .fidelity
+            .delta
+            .map_or_else(|| "-".to_string(), |delta| format!("{delta:+.2}")),
+        comparison.fidelity.significance.label()
+    );
+}
+
+fn print_drift_curve(drift: &SessionDrift) {
+    println!(
+        "{} ({}): {} of {} turns flagged",
+        drift.session_id,
+        drift.started_at.as_deref().unwrap_or("-"),
+        drift.flagged_turns(),
+        drift.points.len()
+    );
+    println!("{:>5} {:>6}", "TURN", "DRIFT");
+    for point in &drift.points {
+        println!(
+            "{:>5} {:>6}{}",
+            point.turn,
+            format_score(point.drift),
+            if point.flagged { "  flagged" } else { "" }
+        );
+    }
+}
+
+/// One line per session with at least one flagged turn, most flagged first.
+fn print_drifted_sessions(drifts: &[SessionDrift]) {
+    let mut drifted = drifts
+        .iter()
+        .filter(|drift| drift.flagged_turns() > 0)
+        .collect::<Vec<_>>();
+    drifted.sort_by(|left, right| {
+        right
+            .flagged_turns()
+            .cmp(&left.flagged_turns())
+            .then_with(|| left.session_id.cmp(&right.session_id))
+    });
+    if drifted.is_empty() {
+        println!("no session drifted past the threshold");
+        return;
+    }
+    println!(
+        "{:<38} {:>7} {:>5} {:>9}",
+        "SESSION", "FLAGGED", "TURNS", "MAX DRIFT"
+    );
+    for drift in drifted {
+        println!(
+            "{:<38} {:>7} {:>5} {:>9}",
+            drift.session_id,
+            drift.flagged_turns(),
+            drift.points.len(),
+            format_score(drift.max_drift())
+        );
+    }
+}
+
+fn print_summary(summary: &StatsSummary) {
+    println!("sessions: {}", summary.sessions);
+    println!("turns: {}", summary.turns);
+    println!("hit rate: {}", format_percent(summary.hit_rate));
+    println!("fidelity: {}", format_score(summary.fidelity));
+    print_corrupt_rollouts(&summary.corrupt_rollouts);
+    if !summary.recovery.is_empty() {
+        print_recovery(&summary.recovery);
+    }
+    if !summary.approvals.is_empty() {
+        print_approvals(&summary.approvals);
+    }
+    if !summary.loops.is_empty() {
+        print_loops(&summary.loops);
+    }
+    if summary.pattern_effectiveness.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("pattern effectiveness:");
+    println!(
+        "{:<38} {:>7} {:>6} {:>9} {:>9}",
+        "PATTERN", "MATCHES", "SCORE", "ACCEPTED", "SUCCESS"
+    );
+    for pattern in &summary.pattern_effectiveness {
+        println!(
+            "{:<38} {:>7} {:>6} {:>9} {:>9}",
+            pattern.pattern_id,
+            pattern.matches,
+            format_score(Some(pattern.mean_score)),
+            format_percent(pattern.acceptance_rate()),
+            format_percent(pattern.success_rate()),
+        );
+    }
+}
+
+fn print_corrupt_rollouts(corrupt: &[CorruptRollout]) {
+    if corrupt.is_empty() {
+        return;
+    }
+    println!();
+    println!("skipped unparsable lines:");
+    for rollout in corrupt {
+        let lines = rollout
+            .lines
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        println!("  {}: lines {}", rollout.path.display(), lines.join(", "));
+    }
+}
+
+fn print_recovery(report: &RecoveryReport) {
+    println!();
+    println!("recovery:");
+    println!(
+        "{:<14} {:<20} {:<18} {:>6} {:>9} {:>7} {:>7} {:>7}",
+        "KIND", "MODEL", "SANDBOX", "ERRORS", "RECOVERED", "P50", "P90", "P99"
+    );
+    let rows = report
+        .by_kind
+        .iter()
+        .map(|stats| (stats, "all", "all"))
+        .chain(report.by_context.iter().map(|row| {
+            (
+                &row.stats,
+                row.model.as_deref().unwrap_or("-"),
+                row.sandbox.as_deref().unwrap_or("-"),
+            )
+        }));
+    for (stats, model, sandbox) in rows {
+        println!(
+            "{:<14} {:<20} {:<18} {:>6} {:>9} {:>7} {:>7} {:>7}",
+            stats.kind.label(),
+            model,
+            sandbox,
+            stats.errors,
+            stats.recovered,
+            format_seconds(stats.p50_secs),
+            format_seconds(stats.p90_secs),
+            format_seconds(stats.p99_secs),
+        );
+    }
+}
+
+fn print_approvals(report: &ApprovalReport) {
+    println!();
+    println!("approvals:");
+    println!(
+        "{:<6} {:>8} {:>9} {:>9} {:>7}",
+        "KIND", "REQUESTS", "PER TURN", "MEAN WAIT", "DENIED"
+    );
+    let rows = [
+        ("all", &report.all),
+        ("exec", &report.exec),
+        ("patch", &report.patch),
+    ];
+    for (kind, stats) in rows {
+        println!(
+            "{:<6} {:>8} {:>9} {:>9} {:>7}",
+            kind,
+            stats.requests,
+            format_score(stats.per_turn),
+            format_seconds(stats.mean_wait_secs),
+            format_percent(stats.denial_rate),
+        );
+    }
+}
+
+fn print_loops(report: &LoopReport) {
+    println!();
+    println!(
+        "failure loops: {} (mean {} turns)",
+        report.loops,
+        report
+            .mean_turns
+            .map_or_else(|| "-".to_string(), |turns| format!("{turns:.1}"))
+    );
+    println!("{:<38} {:>5} {:>7}", "SESSION", "LOOPS", "LONGEST");
+    for session in &report.sessions {
+        println!(
+            "{:<38} {:>5} {:>7}",
+            session.session_id,
+            session.loops.len(),
+            session
+                .loops
+                .iter()
+                .map(|span| span.turns)
+                .max()
+                .unwrap_or_default()
+        );
+    }
+}
+
+fn print_fidelity_score(score: &FidelityScore) {
+    let join = |tokens: &BTreeSet<String>| {
+        tokens
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    println!("intent tokens:  {}", join(&score.intent_tokens));
+    println!("outcome tokens: {}", join(&score.outcome_tokens));
+    println!("overlap:        {}", join(&score.overlap));
+    println!(
+        "score:          {} ({}/{})",
+        format_score(score.score),
+        score.overlap.len(),
+        score.intent_tokens.len()
+    );
+}
+
+fn print_calibration(calibration: &LabelCalibration) {
+    if calibration.labeled == 0 {
+        println!("no labeled turns; label some with `codex stats label`");
+        return;
+    }
+    println!(
+        "labeled turns: {} ({} success, {} failure)",
+        calibration.labeled,
+        calibration.successes,
+        calibration.labeled - calibration.successes
+    );
+    println!(
+        "hit agreement: {}",
+        format_percent(calibration.hit_agreement)
+    );
+    println!(
+        "fidelity threshold: {} (agreement {})",
+        format_score(calibration.fidelity_threshold),
+        format_percent(calibration.threshold_agreement)
+    );
+}
+
+fn print_session_ranking(sessions: &[SessionStats], limit: usize) {
+    let ranked = rank_sessions(sessions);
+    println!(
+        "{:<38} {:<10} {:<20} {:>5} {:>8} {:>8}",
+        "SESSION", "DATE", "MODEL", "TURNS", "HIT", "FIDELITY"
+    );
+    for session in ranked.into_iter().take(limit) {
+        println!(
+            "{:<38} {:<10} {:<20} {:>5} {:>8} {:>8}",
+            session.session_id,
+            session.date().unwrap_or("-"),
+            session.model.as_deref().unwrap_or("-"),
+            session.turns.len(),
+            format_percent(session.hit_rate()),
+            format_score(session.fidelity()),
+        );
+    }
+}
+
+/// Order sessions worst first: lowest fidelity, then lowest hit rate.
+/// Sessions without any turns are omitted.
+fn rank_sessions(sessions: &[SessionStats]) -> Vec<&SessionStats> {
+    let mut ranked = sessions
+        .iter()
+        .filter(|session| !session.turns.is_empty())
+        .collect::<Vec<_>>();
+    ranked.sort_by(|left, right| {
+        compare_scores(left.fidelity(), right.fidelity())
+            .then_with(|| compare_scores(left.hit_rate(), right.hit_rate()))
+            .then_with(|| left.session_id.cmp(&right.session_id))
+    });
+    ranked
+}
+
+fn compare_scores(left: Option<f64>, right: Option<f64>) -> Ordering {
+    let left = left.unwrap_or(f64::INFINITY);
+    let right = right.unwrap_or(f64::INFINITY);
+    left.partial_cmp(&right).unwrap_or(Ordering::Equal)
+}
+
+fn format_percent(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| format!("{:.1}%", value * 100.0))
+}
+
+fn format_score(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| format!("{value:.2}"))
+}
+
+fn format_seconds(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| format!("{value:.1}s"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::session::TurnStats;
+    use pretty_assertions::assert_eq;
+
+    fn session(id: &str, turns: Vec<TurnStats>) -> SessionStats {
+        SessionStats {
+            session_id: id.to_string(),
+            started_at: Some("2026-01-02T03:04:05.000Z".to_string()),
+            model: Some("gpt-test".to_string()),
+            path: Path::new("rollout.jsonl").to_path_buf(),
+            turns,
+        }
+    }
+
+    fn turn(user_message: &str, reply: &str) -> TurnStats {
+        TurnStats {
+            user_message: user_message.to_string(),
+            agent_messages: vec![reply.to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merged_homes_count_each_session_once() {
+        let partial = session("shared", vec![turn("update readme", "Updated the readme")]);
+        let complete = session(
+            "shared",
+            vec![
+                turn("update readme", "Updated the readme"),
+                turn("add a changelog", "Added CHANGELOG.md"),
+            ],
+        );
+        let other = session("other", vec![turn("fix the build", "Fixed it")]);
+
+        assert_eq!(
+            dedupe_sessions(vec![partial, other.clone(), complete.clone()]),
+            vec![complete, other]
+        );
+    }
+
+    #[test]
+    fn sessions_rank_from_worst_to_best_fidelity() {
+        let sessions = vec![
+            session(
+                "faithful",
+                vec![turn("update readme", "Updated the readme")],
+            ),
+            session("drifted", vec![turn("update readme", "Refactored parser")]),
+            session("empty", Vec::new()),
+        ];
+
+        let ranked = rank_sessions(&sessions)
+            .into_iter()
+            .map(|session| session.session_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ranked, vec!["drifted", "faithful"]);
+    }
+}
@@ -0,0 +1,158 @@
+//! `codex stats export-pairs`: each turn's intent and final reply as one JSON
+//! line, for building evaluation sets or fine-tuning data from real sessions.
+
+use serde::Serialize;
+
+use super::recovery::ErrorKind;
+use super::session::SessionStats;
+use super::session::score_fidelity;
+
+/// One user intent and the agent's final reply to it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct IntentOutcomePair {
+    /// The user message that started the turn.
+    pub(crate) intent: String,
+    /// The last agent message of the turn.
+    pub(crate) outcome: String,
+    /// Share of the intent's content words the outcome addressed, or `null`
+    /// when the intent has none.
+    pub(crate) similarity: Option<f64>,
+    pub(crate) context: PairContext,
+}
+
+/// Where a pair came from and how its turn went.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct PairContext {
+    pub(crate) session_id: String,
+    /// 1-based turn number within the session.
+    pub(crate) turn: usize,
+    pub(crate) started_at: Option<String>,
+    pub(crate) model: Option<String>,
+    pub(crate) sandbox: Option<String>,
+    pub(crate) tool_calls: usize,
+    pub(crate) failed_tool_calls: usize,
+    pub(crate) aborted: bool,
+    /// Whether the turn counted as a hit.
+    pub(crate) hit: bool,
+    /// Kinds of the errors recorded during the turn, in order.
+    pub(crate) errors: Vec<ErrorKind>,
+}
+
+/// One pair per turn that has both a user message and an agent reply, in
+/// session and turn order.
+pub(crate) fn intent_outcome_pairs(sessions: &[SessionStats]) -> Vec<IntentOutcomePair> {
+    sessions
+        .iter()
+        .flat_map(|session| {
+            session
+                .turns
+                .iter()
+                .enumerate()
+                .filter_map(move |(index, turn)| {
+                    let outcome = turn.agent_messages.last()?;
+                    if turn.user_message.trim().is_empty() {
+                        return None;
+                    }
+                    Some(IntentOutcomePair {
+                        intent: turn.user_message.clone(),
+                        outcome: outcome.clone(),
+                        similarity: score_fidelity(&turn.user_message, outcome).score,
+                        context: PairContext {
+                            session_id: session.session_id.clone(),
+                            turn: index + 1,
+                            started_at: session.started_at.clone(),
+                            model: turn.model.clone().or_else(|| session.model.clone()),
+                            sandbox: turn.sandbox.clone(),
+                            tool_calls: turn.tool_calls,
+                            failed_tool_calls: turn.failed_tool_calls,
+                            aborted: turn.aborted,
+                            hit: turn.is_hit(),
+                            errors: turn.errors.iter().map(|error| error.kind).collect(),
+                        },
+                    })
+                })
+        })
+        .collect()
+}
+
+/// The pairs as JSON Lines, one object per line.
+pub(crate) fn render_pairs_jsonl(pairs: &[IntentOutcomePair]) -> anyhow::Result<String> {
+    let mut jsonl = String::new();
+    for pair in pairs {
+        jsonl.push_str(&serde_json::to_string(pair)?);
+        jsonl.push('\n');
+    }
+    Ok(jsonl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::recovery::TurnError;
+    use crate::stats::session::TurnStats;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    #[test]
+    fn pairs_keep_the_final_reply_of_turns_that_got_one() {
+        let sessions = vec![SessionStats {
+            session_id: "s1".to_string(),
+            started_at: Some("2026-01-02T09:00:00.000Z".to_string()),
+            model: Some("gpt-5".to_string()),
+            path: PathBuf::from("rollout.jsonl"),
+            turns: vec![
+                TurnStats {
+                    user_message: "fix the parser test".to_string(),
+                    agent_messages: vec![
+                        "Looking at it".to_string(),
+                        "Fixed the parser test".to_string(),
+                    ],
+                    tool_calls: 2,
+                    failed_tool_calls: 1,
+                    sandbox: Some("workspace-write".to_string()),
+                    errors: vec![TurnError {
+                        kind: ErrorKind::StreamError,
+                        at: None,
+                        recovery_secs: None,
+                    }],
+                    ..Default::default()
+                },
+                TurnStats {
+                    user_message: "and the docs".to_string(),
+                    aborted: true,
+                    ..Default::default()
+                },
+            ],
+        }];
+
+        let pairs = intent_outcome_pairs(&sessions);
+        assert_eq!(
+            pairs,
+            vec![IntentOutcomePair {
+                intent: "fix the parser test".to_string(),
+                outcome: "Fixed the parser test".to_string(),
+                similarity: Some(1.0),
+                context: PairContext {
+                    session_id: "s1".to_string(),
+                    turn: 1,
+                    started_at: Some("2026-01-02T09:00:00.000Z".to_string()),
+                    model: Some("gpt-5".to_string()),
+                    sandbox: Some("workspace-write".to_string()),
+                    tool_calls: 2,
+                    failed_tool_calls: 1,
+                    aborted: false,
+                    hit: true,
+                    errors: vec![ErrorKind::StreamError],
+                },
+            }]
+        );
+        assert_eq!(
+            render_pairs_jsonl(&pairs)
+                .expect("render pairs")
+                .lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("pair json"))
+                .collect::<Vec<_>>(),
+            vec![serde_json::to_value(&pairs[0]).expect("serialize pair")]
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Recovery latency: how long the agent took to reply again after an error,
+//! a stream error, or an aborted turn.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::session::SessionStats;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ErrorKind {
+    Error,
+    StreamError,
+    TurnAborted,
+}
+
+impl ErrorKind {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::StreamError => "stream_error",
+            Self::TurnAborted => "turn_aborted",
+        }
+    }
+}
+
+/// One error recorded during a turn.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TurnError {
+    pub(crate) kind: ErrorKind,
+    /// Unix time of the error in seconds, when the rollout line had a
+    /// readable timestamp.
+    pub(crate) at: Option<f64>,
+    /// Seconds until the next agent message, or `None` when the agent never
+    /// replied again.
+    pub(crate) recovery_secs: Option<f64>,
+}
+
+/// Counts and latency percentiles for one group of errors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct RecoveryStats {
+    pub(crate) kind: ErrorKind,
+    pub(crate) errors: usize,
+    /// Errors followed by an agent message.
+    pub(crate) recovered: usize,
+    /// Recovery latency percentiles in seconds, or `null` when nothing
+    /// recovered.
+    pub(crate) p50_secs: Option<f64>,
+    pub(crate) p90_secs: Option<f64>,
+    pub(crate) p99_secs: Option<f64>,
+}
+
+/// [`RecoveryStats`] for the errors of one kind that happened in turns run
+/// with the same model and sandbox policy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct ContextRecovery {
+    pub(crate) model: Option<String>,
+    pub(crate) sandbox: Option<String>,
+    #[serde(flatten)]
+    pub(crate) stats: RecoveryStats,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct RecoveryReport {
+    /// One row per error kind.
+    pub(crate) by_kind: Vec<RecoveryStats>,
+    /// One row per error kind, model, and sandbox policy.
+    pub(crate) by_context: Vec<ContextRecovery>,
+}
+
+impl RecoveryReport {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.by_kind.is_empty()
+    }
+}
+
+pub(crate) fn recovery_report(sessions: &[SessionStats]) -> RecoveryReport {
+    let mut by_kind: BTreeMap<ErrorKind, Vec<Option<f64>>> = BTreeMap::new();
+    type ContextKey = (ErrorKind, Option<String>, Option<String>);
+    let mut by_context: BTreeMap<ContextKey, Vec<Option<f64>>> = BTreeMap::new();
+    for turn in sessions.iter().flat_map(|session| &session.turns) {
+        for error in &turn.errors {
+            by_kind
+                .entry(error.kind)
+                .or_default()
+                .push(error.recovery_secs);
+            by_context
+                .entry((error.kind, turn.model.clone(), turn.sandbox.clone()))
+                .or_default()
+                .push(error.recovery_secs);
+        }
+    }
+    RecoveryReport {
+        by_kind: by_kind
+            .into_iter()
+            .map(|(kind, samples)| recovery_stats(kind, samples))
+            .collect(),
+        by_context: by_context
+            .into_iter()
+            .map(|((kind, model, sandbox), samples)| ContextRecovery {
+                model,
+                sandbox,
+                stats: recovery_stats(kind, samples),
+            })
+            .collect(),
+    }
+}
+
+fn recovery_stats(kind: ErrorKind, samples: Vec<Option<f64>>) -> RecoveryStats {
+    let errors = samples.len();
+    let mut latencies = samples.into_iter().flatten().collect::<Vec<_>>();
+    latencies.sort_by(f64::total_cmp);
+    RecoveryStats {
+        kind,
+        errors,
+        recovered: latencies.len(),
+        p50_secs: percentile(&latencies, 0.50),
+        p90_secs: percentile(&latencies, 0.90),
+        p99_secs: percentile(&latencies, 0.99),
+    }
+}
+
+/// Nearest-rank percentile of sorted `values`.
+fn percentile(values: &[f64], quantile: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let rank = (quantile * values.len() as f64).ceil() as usize;
+    values.get(rank.clamp(1, values.len()) - 1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::session::TurnStats;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn turn(model: &str, sandbox: &str, errors: &[(ErrorKind, Option<f64>)]) -> TurnStats {
+        TurnStats {
+            model: Some(model.to_string()),
+            sandbox: Some(sandbox.to_string()),
+            errors: errors
+                .iter()
+                .map(|(kind, recovery_secs)| TurnError {
+                    kind: *kind,
+                    at: Some(0.0),
+                    recovery_secs: *recovery_secs,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn recovery_is_reported_per_kind_and_turn_context() {
+        let session = SessionStats {
+            session_id: "session-1".to_string(),
+            started_at: None,
+            model: Some("gpt-a".to_string()),
+            path: PathBuf::from("rollout.jsonl"),
+            turns: vec![
+                turn(
+                    "gpt-a",
+                    "read-only",
+                    &[
+                        (ErrorKind::StreamError, Some(2.0)),
+                        (ErrorKind::StreamError, Some(1.0)),
+                    ],
+                ),
+                turn(
+                    "gpt-b",
+                    "workspace-write",
+                    &[
+                        (ErrorKind::StreamError, Some(10.0)),
+                        (ErrorKind::Error, None),
+                    ],
+                ),
+            ],
+        };
+
+        let report = recovery_report(&[session]);
+
+        assert_eq!(
+            report.by_kind,
+            vec![
+                RecoveryStats {
+                    kind: ErrorKind::Error,
+                    errors: 1,
+                    recovered: 0,
+                    p50_secs: None,
+                    p90_secs: None,
+                    p99_secs: None,
+                },
+                RecoveryStats {
+                    kind: ErrorKind::StreamError,
+                    errors: 3,
+                    recovered: 3,
+                    p50_secs: Some(2.0),
+                    p90_secs: Some(10.0),
+                    p99_secs: Some(10.0),
+                },
+            ]
+        );
+        assert_eq!(
+            report
+                .by_context
+                .iter()
+                .map(|row| (
+                    row.stats.kind,
+                    row.model.as_deref(),
+                    row.sandbox.as_deref(),
+                    row.stats.errors,
+                    row.stats.p50_secs,
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    ErrorKind::Error,
+                    Some("gpt-b"),
+                    Some("workspace-write"),
+                    1,
+                    None
+                ),
+                (
+                    ErrorKind::StreamError,
+                    Some("gpt-a"),
+                    Some("read-only"),
+                    2,
+                    Some(1.0)
+                ),
+                (
+                    ErrorKind::StreamError,
+                    Some("gpt-b"),
+                    Some("workspace-write"),
+                    1,
+                    Some(10.0)
+                ),
+            ]
+        );
+    }
+}
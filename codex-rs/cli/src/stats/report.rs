@@ -0,0 +1,252 @@
+//! `codex stats --format html`: the summary as one self-contained page, for
+//! sharing with people who do not run the CLI.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use super::StatsSummary;
+use super::compare::aggregate_sessions;
+use super::format_percent;
+use super::format_score;
+use super::session::SessionStats;
+
+/// Hit rate and fidelity of the sessions started on one day.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct TrendPoint {
+    /// `YYYY-MM-DD`.
+    pub(crate) date: String,
+    pub(crate) sessions: usize,
+    pub(crate) turns: usize,
+    pub(crate) hit_rate: Option<f64>,
+    pub(crate) fidelity: Option<f64>,
+}
+
+/// One point per day with sessions, oldest first. Sessions without a start
+/// date are left out.
+pub(crate) fn trend_points(sessions: &[SessionStats]) -> Vec<TrendPoint> {
+    let mut by_date = BTreeMap::<&str, Vec<&SessionStats>>::new();
+    for session in sessions {
+        if let Some(date) = session.date() {
+            by_date.entry(date).or_default().push(session);
+        }
+    }
+    by_date
+        .into_iter()
+        .map(|(date, sessions)| {
+            let aggregate = aggregate_sessions(sessions);
+            TrendPoint {
+                date: date.to_string(),
+                sessions: aggregate.sessions,
+                turns: aggregate.turns,
+                hit_rate: aggregate.hit_rate(),
+                fidelity: aggregate.fidelity(),
+            }
+        })
+        .collect()
+}
+
+const STYLE: &str = "\
+body{font-family:system-ui,sans-serif;margin:2rem;color:#1f2328}\
+h1{font-size:1.4rem}h2{font-size:1.1rem;margin-top:2rem}\
+.cards{display:flex;flex-wrap:wrap;gap:1rem}\
+.card{border:1px solid #d0d7de;border-radius:6px;padding:.75rem 1rem;min-width:8rem}\
+.card .value{font-size:1.5rem;font-weight:600}.card .label{color:#59636e}\
+table{border-collapse:collapse}th,td{padding:.25rem .75rem;text-align:left}\
+th{border-bottom:1px solid #d0d7de}td.num,th.num{text-align:right}\
+svg{display:block;margin-top:.5rem}";
+
+/// Draws each `data-metric` sparkline from the inlined trend.
+const SCRIPT: &str = "\
+const trend=JSON.parse(document.getElementById('trend').textContent);\
+for(const svg of document.querySelectorAll('svg[data-metric]')){\
+const values=trend.map(p=>p[svg.dataset.metric]).filter(v=>v!==null);\
+if(values.length<2)continue;\
+const w=svg.width.baseVal.value,h=svg.height.baseVal.value,step=w/(values.length-1);\
+const line=document.createElementNS('http://www.w3.org/2000/svg','polyline');\
+line.setAttribute('points',values.map((v,i)=>`${i*step},${h-v*h}`).join(' '));\
+line.setAttribute('fill','none');line.setAttribute('stroke','#0969da');\
+svg.appendChild(line);}";
+
+/// Render a standalone HTML report: summary cards, a hit rate and fidelity
+/// sparkline per day, and one row per session, newest first.
+pub(crate) fn render_html(
+    summary: &StatsSummary,
+    sessions: &[SessionStats],
+) -> anyhow::Result<String> {
+    let trend = inline_json(&trend_points(sessions))?;
+    let errors = summary
+        .recovery
+        .by_kind
+        .iter()
+        .map(|stats| stats.errors)
+        .sum::<usize>();
+    let recovered = summary
+        .recovery
+        .by_kind
+        .iter()
+        .map(|stats| stats.recovered)
+        .sum::<usize>();
+    let cards = [
+        ("Sessions", summary.sessions.to_string()),
+        ("Turns", summary.turns.to_string()),
+        ("Hit rate", format_percent(summary.hit_rate)),
+        ("Fidelity", format_score(summary.fidelity)),
+        ("Errors recovered", format!("{recovered}/{errors}")),
+        ("Failure loops", summary.loops.loops.to_string()),
+    ];
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Codex stats</title>\n");
+    writeln!(html, "<style>{STYLE}</style>")?;
+    html.push_str("</head>\n<body>\n<h1>Codex stats</h1>\n<div class=\"cards\">\n");
+    for (label, value) in cards {
+        writeln!(
+            html,
+            "<div class=\"card\"><div class=\"value\">{}</div><div class=\"label\">{label}</div></div>",
+            escape_html(&value)
+        )?;
+    }
+    html.push_str("</div>\n<h2>Trend</h2>\n");
+    for (metric, label) in [("hit_rate", "Hit rate"), ("fidelity", "Fidelity")] {
+        writeln!(
+            html,
+            "<div>{label}<svg data-metric=\"{metric}\" width=\"240\" height=\"40\"></svg></div>"
+        )?;
+    }
+
+    html.push_str("<h2>Sessions</h2>\n<table>\n<tr><th>Session</th><th>Date</th><th>Model</th>");
+    html.push_str("<th class=\"num\">Turns</th><th class=\"num\">Hit</th><th class=\"num\">Fidelity</th></tr>\n");
+    let mut rows = sessions.iter().collect::<Vec<_>>();
+    rows.sort_by(|left, right| {
+        right
+            .started_at
+            .cmp(&left.started_at)
+            .then_with(|| left.session_id.cmp(&right.session_id))
+    });
+    for session in rows {
+        writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            escape_html(&session.session_id),
+            escape_html(session.date().unwrap_or("-")),
+            escape_html(session.model.as_deref().unwrap_or("-")),
+            session.turns.len(),
+            format_percent(session.hit_rate()),
+            format_score(session.fidelity()),
+        )?;
+    }
+    html.push_str("</table>\n");
+
+    if !summary.pattern_effectiveness.is_empty() {
+        html.push_str("<h2>Pattern effectiveness</h2>\n<table>\n<tr><th>Pattern</th>");
+        html.push_str("<th class=\"num\">Matches</th><th class=\"num\">Score</th><th class=\"num\">Accepted</th><th class=\"num\">Success</th></tr>\n");
+        for pattern in &summary.pattern_effectiveness {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                escape_html(&pattern.pattern_id),
+                pattern.matches,
+                format_score(Some(pattern.mean_score)),
+                format_percent(pattern.acceptance_rate()),
+                format_percent(pattern.success_rate()),
+            )?;
+        }
+        html.push_str("</table>\n");
+    }
+
+    writeln!(
+        html,
+        "<script type=\"application/json\" id=\"trend\">{trend}</script>"
+    )?;
+    writeln!(html, "<script>{SCRIPT}</script>")?;
+    html.push_str("</body>\n</html>\n");
+    Ok(html)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// JSON safe to embed in a `<script>` element: `<` is escaped, so no string
+/// in the data can close the element.
+fn inline_json(value: &impl Serialize) -> anyhow::Result<String> {
+    Ok(serde_json::to_string(value)?.replace('<', "\\u003c"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::session::TurnStats;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn session(id: &str, started_at: Option<&str>, reply: &str) -> SessionStats {
+        SessionStats {
+            session_id: id.to_string(),
+            started_at: started_at.map(str::to_string),
+            model: Some("gpt-<test>".to_string()),
+            path: PathBuf::from("rollout.jsonl"),
+            turns: vec![TurnStats {
+                user_message: "update readme".to_string(),
+                agent_messages: vec![reply.to_string()],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn report_inlines_daily_trend_and_escapes_session_text() {
+        let sessions = vec![
+            session("b", Some("2026-01-03T09:00:00.000Z"), "Refactored parser"),
+            session("a", Some("2026-01-02T09:00:00.000Z"), "Updated the readme"),
+            session("c", Some("2026-01-03T10:00:00.000Z"), "Updated the readme"),
+            session("undated", None, "Updated the readme"),
+        ];
+        let trend = trend_points(&sessions);
+        assert_eq!(
+            trend
+                .iter()
+                .map(|point| (point.date.as_str(), point.sessions, point.turns))
+                .collect::<Vec<_>>(),
+            vec![("2026-01-02", 1, 1), ("2026-01-03", 2, 2)]
+        );
+
+        let summary = StatsSummary::new(&sessions, Vec::new());
+        let html = render_html(&summary, &sessions).expect("render report");
+        let inlined = html
+            .split("<script type=\"application/json\" id=\"trend\">")
+            .nth(1)
+            .and_then(|rest| rest.split("</script>").next())
+            .expect("inlined trend");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(inlined).expect("trend json"),
+            serde_json::to_value(&trend).expect("serialize trend")
+        );
+        let rows = html
+            .lines()
+            .filter_map(|line| line.strip_prefix("<tr><td>"))
+            .map(|row| row.split("</td>").next().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec!["c", "b", "a", "undated"]);
+        assert_eq!(
+            (
+                html.contains("gpt-&lt;test&gt;"),
+                html.contains("gpt-<test>")
+            ),
+            (true, false)
+        );
+    }
+}
@@ -0,0 +1,801 @@
+use super::approvals::TurnApproval;
+use super::recovery::ErrorKind;
+use super::recovery::TurnError;
+use anyhow::Context;
+use chrono::DateTime;
+use codex_core::turn_intents::TurnContextSnapshot;
+use codex_core::turn_intents::TurnIntent;
+use codex_core::turn_intents::extract_turn_intents;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use codex_utils_text_normalize::token_set;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// Per-session summary reconstructed from a rollout file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SessionStats {
+    pub(crate) session_id: String,
+    pub(crate) started_at: Option<String>,
+    pub(crate) model: Option<String>,
+    pub(crate) path: PathBuf,
+    pub(crate) turns: Vec<TurnStats>,
+}
+
+/// A rollout file with lines that were skipped because they did not parse.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct CorruptRollout {
+    pub(crate) path: PathBuf,
+    /// 1-based numbers of the skipped lines.
+    pub(crate) lines: Vec<usize>,
+}
+
+/// One user request and everything the agent did in response to it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct TurnStats {
+    pub(crate) user_message: String,
+    pub(crate) agent_messages: Vec<String>,
+    pub(crate) tool_calls: usize,
+    pub(crate) failed_tool_calls: usize,
+    pub(crate) last_tool_failed: bool,
+    pub(crate) aborted: bool,
+    /// Model and sandbox policy from the turn's context, or inherited from
+    /// the previous turn when the rollout did not record one.
+    pub(crate) model: Option<String>,
+    pub(crate) sandbox: Option<String>,
+    pub(crate) errors: Vec<TurnError>,
+    pub(crate) approvals: Vec<TurnApproval>,
+}
+
+/// How much of an intent an outcome addressed, with the tokens compared.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct FidelityScore {
+    pub(crate) intent_tokens: BTreeSet<String>,
+    pub(crate) outcome_tokens: BTreeSet<String>,
+    /// Intent tokens that also appear in the outcome.
+    pub(crate) overlap: BTreeSet<String>,
+    /// Share of intent tokens in the overlap, or `null` when the intent has
+    /// no content tokens.
+    pub(crate) score: Option<f64>,
+}
+
+/// Compare an intent with an outcome the way turn fidelity does.
+pub(crate) fn score_fidelity(intent: &str, outcome: &str) -> FidelityScore {
+    let intent_tokens = token_set(intent).into_iter().collect::<BTreeSet<_>>();
+    let outcome_tokens = token_set(outcome).into_iter().collect::<BTreeSet<_>>();
+    let overlap = intent_tokens
+        .intersection(&outcome_tokens)
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    let score =
+        (!intent_tokens.is_empty()).then_some(overlap.len() as f64 / intent_tokens.len() as f64);
+    FidelityScore {
+        intent_tokens,
+        outcome_tokens,
+        overlap,
+        score,
+    }
+}
+
+/// Summary counters that merge across sessions.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct StatsAggregate {
+    pub(crate) sessions: usize,
+    pub(crate) turns: usize,
+    pub(crate) hits: usize,
+    pub(crate) fidelity_sum: f64,
+    pub(crate) fidelity_squares_sum: f64,
+    pub(crate) fidelity_samples: usize,
+}
+
+impl StatsAggregate {
+    pub(crate) fn from_session(session: &SessionStats) -> Self {
+        let fidelity_scores = session
+            .turns
+            .iter()
+            .filter_map(TurnStats::fidelity)
+            .collect::<Vec<_>>();
+        Self {
+            sessions: 1,
+            turns: session.turns.len(),
+            hits: session.turns.iter().filter(|turn| turn.is_hit()).count(),
+            fidelity_sum: fidelity_scores.iter().sum(),
+            fidelity_squares_sum: fidelity_scores.iter().map(|score| score * score).sum(),
+            fidelity_samples: fidelity_scores.len(),
+        }
+    }
+
+    pub(crate) fn merge(self, other: Self) -> Self {
+        Self {
+            sessions: self.sessions + other.sessions,
+            turns: self.turns + other.turns,
+            hits: self.hits + other.hits,
+            fidelity_sum: self.fidelity_sum + other.fidelity_sum,
+            fidelity_squares_sum: self.fidelity_squares_sum + other.fidelity_squares_sum,
+            fidelity_samples: self.fidelity_samples + other.fidelity_samples,
+        }
+    }
+
+    pub(crate) fn hit_rate(&self) -> Option<f64> {
+        (self.turns > 0).then_some(self.hits as f64 / self.turns as f64)
+    }
+
+    /// Mean fidelity across every scored turn.
+    pub(crate) fn fidelity(&self) -> Option<f64> {
+        (self.fidelity_samples > 0).then_some(self.fidelity_sum / self.fidelity_samples as f64)
+    }
+}
+
+impl TurnStats {
+    /// A turn is a hit when it was not aborted, produced a reply, and its last
+    /// tool call (if any) succeeded.
+    pub(crate) fn is_hit(&self) -> bool {
+        !self.aborted && !self.agent_messages.is_empty() && !self.last_tool_failed
+    }
+
+    /// Share of the user's content tokens that the agent's replies addressed.
+    ///
+    /// Returns `None` when the user message has no content tokens to compare.
+    pub(crate) fn fidelity(&self) -> Option<f64> {
+        if !self.is_hit() {
+            return (!token_set(&self.user_message).is_empty()).then_some(0.0);
+        }
+        score_fidelity(&self.user_message, &self.agent_messages.join("\n")).score
+    }
+
+    /// Give every error still waiting for a reply its recovery latency.
+    fn resolve_errors(&mut self, now: Option<f64>) {
+        for error in &mut self.errors {
+            if error.recovery_secs.is_none()
+                && let (Some(at), Some(now)) = (error.at, now)
+            {
+                error.recovery_secs = Some((now - at).max(0.0));
+            }
+        }
+    }
+
+    fn record_tool_output(&mut self, output: &str) {
+        let failed = tool_output_exit_code(output).is_some_and(|code| code != 0);
+        if failed {
+            self.failed_tool_calls += 1;
+        }
+        self.last_tool_failed = failed;
+    }
+}
+
+impl SessionStats {
+    fn new(path: &Path) -> Self {
+        let session_id = rollout_stem(path);
+        Self {
+            session_id,
+            started_at: None,
+            model: None,
+            path: path.to_path_buf(),
+            turns: Vec::new(),
+        }
+    }
+
+    /// Fraction of turns that were hits, or `None` for sessions without turns.
+    pub(crate) fn hit_rate(&self) -> Option<f64> {
+        if self.turns.is_empty() {
+            return None;
+        }
+        let hits = self.turns.iter().filter(|turn| turn.is_hit()).count() as f64;
+        Some(hits / self.turns.len() as f64)
+    }
+
+    /// Mean per-turn fidelity across turns that have a comparable intent.
+    pub(crate) fn fidelity(&self) -> Option<f64> {
+        let scores = self
+            .turns
+            .iter()
+            .filter_map(TurnStats::fidelity)
+            .collect::<Vec<_>>();
+        if scores.is_empty() {
+            return None;
+        }
+        Some(scores.iter().sum::<f64>() / scores.len() as f64)
+    }
+
+    /// Session start date (`YYYY-MM-DD`) when recorded.
+    pub(crate) fn date(&self) -> Option<&str> {
+        self.started_at
+            .as_deref()
+            .and_then(|timestamp| timestamp.split('T').next())
+    }
+
+    /// Fold one rollout line into the session. `timestamp` is the line's time
+    /// in Unix seconds, and `intents` yields the next turn's intent.
+    fn apply(
+        &mut self,
+        item: RolloutItem,
+        timestamp: Option<f64>,
+        intents: &mut impl Iterator<Item = TurnIntent>,
+    ) {
+        match item {
+            RolloutItem::SessionMeta(meta_line) => {
+                if self.started_at.is_none() {
+                    self.session_id = meta_line.meta.id.to_string();
+                    self.started_at = Some(meta_line.meta.timestamp);
+                }
+            }
+            RolloutItem::TurnContext(turn_context) => {
+                if self.model.is_none() {
+                    self.model = Some(turn_context.model);
+                }
+            }
+            RolloutItem::EventMsg(EventMsg::UserMessage(event)) => {
+                // Intents are extracted from the same lines, one per user
+                // message, so the next one belongs to this message.
+                let (user_message, context) = match intents.next() {
+                    Some(intent) => (intent.message, intent.context),
+                    None => (event.message, None),
+                };
+                self.turns.push(TurnStats {
+                    user_message,
+                    model: context.as_ref().map(|context| context.model.clone()),
+                    sandbox: context.as_ref().and_then(TurnContextSnapshot::sandbox_mode),
+                    ..Default::default()
+                });
+            }
+            RolloutItem::EventMsg(EventMsg::AgentMessage(event)) => {
+                // A reply ends the wait for errors in this turn and, when the
+                // turn had no reply of its own yet, in the turns before it.
+                for turn in self.turns.iter_mut().rev() {
+                    turn.resolve_errors(timestamp);
+                    if !turn.agent_messages.is_empty() {
+                        break;
+                    }
+                }
+                if let Some(turn) = self.turns.last_mut() {
+                    turn.agent_messages.push(event.message);
+                }
+            }
+            RolloutItem::EventMsg(EventMsg::TurnAborted(_)) => {
+                if let Some(turn) = self.turns.last_mut() {
+                    turn.aborted = true;
+                }
+                self.record_error(ErrorKind::TurnAborted, timestamp);
+            }
+            RolloutItem::EventMsg(EventMsg::Error(_)) => {
+                self.record_error(ErrorKind::Error, timestamp);
+            }
+            RolloutItem::EventMsg(EventMsg::StreamError(_)) => {
+                self.record_error(ErrorKind::StreamError, timestamp);
+            }
+            RolloutItem::EventMsg(EventMsg::ApprovalDecision(event)) => {
+                if let Some(turn) = self.turns.last_mut() {
+                    turn.approvals.push(TurnApproval {
+                        kind: event.kind,
+                        denied: matches!(
+                            event.decision,
+                            ReviewDecision::Denied | ReviewDecision::Abort
+                        ),
+                        wait_ms: event.wait_ms,
+                    });
+                }
+            }
+            RolloutItem::ResponseItem(item) => {
+                if let Some(turn) = self.turns.last_mut() {
+                    apply_response_item(turn, item);
+                }
+            }
+            RolloutItem::EventMsg(_) | RolloutItem::Compacted(_) => {}
+        }
+    }
+
+    fn record_error(&mut self, kind: ErrorKind, at: Option<f64>) {
+        if let Some(turn) = self.turns.last_mut() {
+            turn.errors.push(TurnError {
+                kind,
+                at,
+                recovery_secs: None,
+            });
+        }
+    }
+}
+
+fn apply_response_item(turn: &mut TurnStats, item: ResponseItem) {
+    match item {
+        ResponseItem::LocalShellCall { .. }
+        | ResponseItem::FunctionCall { .. }
+        | ResponseItem::CustomToolCall { .. } => {
+            turn.tool_calls += 1;
+        }
+        ResponseItem::FunctionCallOutput { output, .. } => {
+            turn.record_tool_output(output.body.to_text().unwrap_or_default().as_str());
+        }
+        ResponseItem::CustomToolCallOutput { output, .. } => {
+            turn.record_tool_output(output.as_str());
+        }
+        ResponseItem::Message { .. }
+        | ResponseItem::Reasoning { .. }
+        | ResponseItem::WebSearchCall { .. }
+        | ResponseItem::GhostSnapshot { .. }
+        | ResponseItem::Compaction { .. }
+        | ResponseItem::Other => {}
+    }
+}
+
+/// Parse a rollout file into per-turn statistics. When `lenient` is set,
+/// lines that do not parse are skipped instead of failing, and their 1-based
+/// numbers are returned with the session.
+fn load_session(path: &Path, lenient: bool) -> anyhow::Result<(SessionStats, Vec<usize>)> {
+    let contents = read_rollout(path)?;
+    let mut lines = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<RolloutLine>(line) {
+            Ok(rollout_line) => lines.push(rollout_line),
+            Err(_) if lenient => skipped.push(index + 1),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to parse {}:{}", path.display(), index + 1));
+            }
+        }
+    }
+
+    let mut intents = extract_turn_intents(&lines).into_iter();
+    let mut session = SessionStats::new(path);
+    for rollout_line in lines {
+        let timestamp = DateTime::parse_from_rfc3339(&rollout_line.timestamp)
+            .ok()
+            .map(|time| time.timestamp_millis() as f64 / 1000.0);
+        session.apply(rollout_line.item, timestamp, &mut intents);
+    }
+    Ok((session, skipped))
+}
+
+/// Parse rollout files on up to `jobs` threads.
+///
+/// Results are returned in the order of `paths` regardless of how the work was
+/// scheduled, so aggregates computed from them are deterministic.
+pub(crate) fn load_sessions(paths: &[PathBuf], jobs: usize) -> anyhow::Result<Vec<SessionStats>> {
+    load_sessions_with(paths, jobs, false).map(|(sessions, _)| sessions)
+}
+
+/// Like [`load_sessions`], but when `lenient` is set, lines that do not parse
+/// are skipped and reported per file instead of failing the whole load.
+pub(crate) fn load_sessions_with(
+    paths: &[PathBuf],
+    jobs: usize,
+    lenient: bool,
+) -> anyhow::Result<(Vec<SessionStats>, Vec<CorruptRollout>)> {
+    let jobs = jobs.clamp(1, paths.len().max(1));
+    let next = AtomicUsize::new(0);
+    let mut loaded = std::thread::scope(|scope| {
+        let workers = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut loaded = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = paths.get(index) else {
+                            break;
+                        };
+                        loaded.push((index, load_session(path, lenient)));
+                    }
+                    loaded
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .map(|worker| {
+                worker
+                    .join()
+                    .map_err(|_| anyhow::anyhow!("rollout analysis thread panicked"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    loaded.sort_by_key(|(index, _)| *index);
+    let mut sessions = Vec::with_capacity(loaded.len());
+    let mut corrupt = Vec::new();
+    for (index, result) in loaded {
+        let (session, lines) = result?;
+        if !lines.is_empty() {
+            corrupt.push(CorruptRollout {
+                path: paths[index].clone(),
+                lines,
+            });
+        }
+        sessions.push(session);
+    }
+    Ok((sessions, corrupt))
+}
+
+/// How a rollout file is compressed, going by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RolloutCompression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// Rollout extensions, longest first so `.jsonl` does not shadow the rest.
+const ROLLOUT_EXTENSIONS: [(&str, RolloutCompression); 3] = [
+    (".jsonl.zst", RolloutCompression::Zstd),
+    (".jsonl.gz", RolloutCompression::Gzip),
+    (".jsonl", RolloutCompression::None),
+];
+
+fn rollout_extension(path: &Path) -> Option<(&'static str, RolloutCompression)> {
+    let name = path.file_name()?.to_str()?;
+    ROLLOUT_EXTENSIONS
+        .into_iter()
+        .find(|(extension, _)| name.ends_with(extension))
+}
+
+/// Read a rollout file, decompressing `.jsonl.gz` and `.jsonl.zst` archives.
+pub(crate) fn read_rollout(path: &Path) -> anyhow::Result<String> {
+    let compression =
+        rollout_extension(path).map_or(RolloutCompression::None, |(_, compression)| compression);
+    let read = || -> std::io::Result<String> {
+        let mut file = std::fs::File::open(path)?;
+        let mut contents = String::new();
+        match compression {
+            RolloutCompression::None => file.read_to_string(&mut contents),
+            RolloutCompression::Gzip => {
+                flate2::read::MultiGzDecoder::new(file).read_to_string(&mut contents)
+            }
+            RolloutCompression::Zstd => zstd::Decoder::new(file)?.read_to_string(&mut contents),
+        }?;
+        Ok(contents)
+    };
+    read().with_context(|| format!("failed to read {}", path.display()))
+}
+
+/// The file name without its rollout extension, compressed or not:
+/// `rollout-x` for `rollout-x.jsonl.gz`.
+pub(crate) fn rollout_stem(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match rollout_extension(path) {
+        Some((extension, _)) => name.strip_suffix(extension).unwrap_or(&name).to_string(),
+        None => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    }
+}
+
+/// Recursively collect `rollout-*.jsonl` files under `dir`, including
+/// `.jsonl.gz` and `.jsonl.zst` archives, sorted by path.
+pub(crate) fn collect_rollout_paths(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = match std::fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", current.display()));
+            }
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if is_rollout_file(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn is_rollout_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("rollout-"))
+        && rollout_extension(path).is_some()
+}
+
+/// Extract the exit code from shell-style tool output, if present.
+fn tool_output_exit_code(output: &str) -> Option<i64> {
+    if let Ok(value) = serde_json::from_str::<Value>(output)
+        && let Some(code) = value.pointer("/metadata/exit_code").and_then(Value::as_i64)
+    {
+        return Some(code);
+    }
+    output.lines().find_map(|line| {
+        let line = line.trim();
+        line.strip_prefix("Exit code: ")
+            .or_else(|| line.strip_prefix("Process exited with code "))
+            .and_then(|code| code.trim().parse().ok())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::ApprovalKind;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn exit_codes_are_read_from_structured_and_freeform_output() {
+        assert_eq!(
+            tool_output_exit_code(
+                r#"{"output":"boom","metadata":{"exit_code":2,"duration_seconds":0.1}}"#
+            ),
+            Some(2)
+        );
+        assert_eq!(
+            tool_output_exit_code("Exit code: 0\nWall time: 0.1 seconds\nOutput:\nok"),
+            Some(0)
+        );
+        assert_eq!(
+            tool_output_exit_code("Process exited with code 1\nOutput:\nfail"),
+            Some(1)
+        );
+        assert_eq!(tool_output_exit_code("patch applied"), None);
+    }
+
+    #[test]
+    fn turn_fidelity_measures_intent_coverage() {
+        let mut turn = TurnStats {
+            user_message: "rename the parser module".to_string(),
+            agent_messages: vec!["Renamed the parser module.".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(turn.is_hit(), true);
+        assert_eq!(turn.fidelity(), Some(1.0));
+
+        turn.record_tool_output("Exit code: 1\nOutput:\nerror");
+        assert_eq!(turn.is_hit(), false);
+        assert_eq!(turn.fidelity(), Some(0.0));
+    }
+
+    #[test]
+    fn score_reports_the_tokens_behind_the_fidelity() {
+        let tokens = |tokens: &[&str]| {
+            tokens
+                .iter()
+                .map(ToString::to_string)
+                .collect::<BTreeSet<_>>()
+        };
+
+        assert_eq!(
+            score_fidelity("fix the failing parser tests", "Fixed two parser bugs."),
+            FidelityScore {
+                intent_tokens: tokens(&["fail", "fix", "parser", "test"]),
+                outcome_tokens: tokens(&["bug", "fix", "parser", "two"]),
+                overlap: tokens(&["fix", "parser"]),
+                score: Some(0.5),
+            }
+        );
+    }
+
+    #[test]
+    fn parallel_loading_preserves_path_order() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let paths = (0..8)
+            .map(|index| {
+                let path = dir.path().join(format!("rollout-{index}.jsonl"));
+                let line = format!(
+                    r#"{{"timestamp":"2026-01-02T03:04:05.000Z","type":"event_msg","payload":{{"type":"user_message","message":"request {index}"}}}}"#
+                );
+                std::fs::write(&path, line).expect("write rollout");
+                path
+            })
+            .collect::<Vec<_>>();
+
+        let sequential = load_sessions(&paths, 1).expect("sequential load");
+        let parallel = load_sessions(&paths, 4).expect("parallel load");
+        assert_eq!(parallel, sequential);
+        assert_eq!(
+            parallel
+                .iter()
+                .map(|session| session.session_id.as_str())
+                .collect::<Vec<_>>(),
+            (0..8)
+                .map(|index| format!("rollout-{index}"))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn compressed_rollouts_are_collected_and_read() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let line = r#"{"timestamp":"2026-01-02T03:04:05.000Z","type":"event_msg","payload":{"type":"user_message","message":"fix the build"}}"#;
+        std::fs::write(dir.path().join("rollout-plain.jsonl"), line).expect("write rollout");
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(line.as_bytes()).expect("gzip rollout");
+        std::fs::write(
+            dir.path().join("rollout-gzip.jsonl.gz"),
+            gzip.finish().expect("finish gzip"),
+        )
+        .expect("write rollout");
+        std::fs::write(
+            dir.path().join("rollout-zstd.jsonl.zst"),
+            zstd::encode_all(line.as_bytes(), 3).expect("zstd rollout"),
+        )
+        .expect("write rollout");
+        std::fs::write(dir.path().join("rollout-other.json.gz"), "").expect("write other");
+
+        let paths = collect_rollout_paths(dir.path()).expect("collect rollouts");
+        let sessions = load_sessions(&paths, 1).expect("load sessions");
+        assert_eq!(
+            sessions
+                .iter()
+                .map(|session| (session.session_id.as_str(), session.turns.len()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("rollout-gzip", 1),
+                ("rollout-plain", 1),
+                ("rollout-zstd", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn lenient_loading_skips_and_reports_unparsable_lines() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("rollout-corrupt.jsonl");
+        let lines = [
+            r#"{"timestamp":"2026-01-02T03:04:05.000Z","type":"event_msg","payload":{"type":"user_message","message":"fix the build"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:06.000Z","type":"event_msg","payl"#,
+            r#"{"timestamp":"2026-01-02T03:04:08.000Z","type":"event_msg","payload":{"type":"agent_message","message":"Fixed."}}"#,
+            "not json",
+        ];
+        std::fs::write(&path, lines.join("\n")).expect("write rollout");
+        let paths = vec![path.clone()];
+
+        let strict = load_sessions_with(&paths, 1, false).expect_err("strict load should fail");
+        let (sessions, corrupt) = load_sessions_with(&paths, 1, true).expect("lenient load");
+        assert_eq!(
+            (
+                strict.to_string(),
+                sessions
+                    .iter()
+                    .map(|session| session.turns.len())
+                    .collect::<Vec<_>>(),
+                corrupt,
+            ),
+            (
+                format!("failed to parse {}:2", path.display()),
+                vec![1],
+                vec![CorruptRollout {
+                    path,
+                    lines: vec![2, 4],
+                }],
+            )
+        );
+    }
+
+    #[test]
+    fn load_session_splits_turns_on_user_messages() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("rollout-test.jsonl");
+        let lines = [
+            r#"{"timestamp":"2026-01-02T03:04:05.000Z","type":"turn_context","payload":{"cwd":"/tmp","approval_policy":"never","sandbox_policy":{"type":"danger-full-access"},"model":"gpt-test","summary":"auto"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:05.000Z","type":"event_msg","payload":{"type":"user_message","message":"fix the build"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:06.000Z","type":"response_item","payload":{"type":"function_call","name":"shell","arguments":"{}","call_id":"c1"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:07.000Z","type":"response_item","payload":{"type":"function_call_output","call_id":"c1","output":"Exit code: 1\nOutput:\nerror"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:08.000Z","type":"event_msg","payload":{"type":"agent_message","message":"The build still fails."}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:09.000Z","type":"event_msg","payload":{"type":"user_message","message":"thanks"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:10.000Z","type":"event_msg","payload":{"type":"agent_message","message":"You're welcome."}}"#,
+        ];
+        std::fs::write(&path, lines.join("\n")).expect("write rollout");
+
+        let (session, _) = load_session(&path, false).expect("load session");
+        assert_eq!(session.model, Some("gpt-test".to_string()));
+        assert_eq!(session.turns.len(), 2);
+        assert_eq!(
+            session.turns[0],
+            TurnStats {
+                user_message: "fix the build".to_string(),
+                agent_messages: vec!["The build still fails.".to_string()],
+                tool_calls: 1,
+                failed_tool_calls: 1,
+                last_tool_failed: true,
+                aborted: false,
+                model: Some("gpt-test".to_string()),
+                sandbox: Some("danger-full-access".to_string()),
+                errors: Vec::new(),
+                approvals: Vec::new(),
+            }
+        );
+        assert_eq!(session.hit_rate(), Some(0.5));
+    }
+
+    #[test]
+    fn errors_are_attributed_to_their_turn_with_recovery_latency() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("rollout-test.jsonl");
+        let lines = [
+            r#"{"timestamp":"2026-01-02T03:04:05.000Z","type":"event_msg","payload":{"type":"user_message","message":"fix the build"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:05.000Z","type":"turn_context","payload":{"cwd":"/tmp","approval_policy":"never","sandbox_policy":{"type":"read-only"},"model":"gpt-a","summary":"auto"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:06.000Z","type":"event_msg","payload":{"type":"stream_error","message":"reconnecting"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:08.500Z","type":"event_msg","payload":{"type":"agent_message","message":"Fixed."}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:09.000Z","type":"event_msg","payload":{"type":"user_message","message":"now the tests"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:10.000Z","type":"event_msg","payload":{"type":"error","message":"boom"}}"#,
+        ];
+        std::fs::write(&path, lines.join("\n")).expect("write rollout");
+
+        let (session, _) = load_session(&path, false).expect("load session");
+        assert_eq!(
+            session
+                .turns
+                .iter()
+                .map(|turn| (
+                    turn.model.as_deref(),
+                    turn.sandbox.as_deref(),
+                    turn.errors.clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    Some("gpt-a"),
+                    Some("read-only"),
+                    vec![TurnError {
+                        kind: ErrorKind::StreamError,
+                        at: Some(1_767_323_046.0),
+                        recovery_secs: Some(2.5),
+                    }],
+                ),
+                (
+                    Some("gpt-a"),
+                    Some("read-only"),
+                    vec![TurnError {
+                        kind: ErrorKind::Error,
+                        at: Some(1_767_323_050.0),
+                        recovery_secs: None,
+                    }],
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn approval_decisions_are_attributed_to_their_turn() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("rollout-test.jsonl");
+        let lines = [
+            r#"{"timestamp":"2026-01-02T03:04:05.000Z","type":"event_msg","payload":{"type":"user_message","message":"fix the build"}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:06.000Z","type":"event_msg","payload":{"type":"approval_decision","call_id":"c1","turn_id":"1","kind":"exec","decision":"approved","wait_ms":1200}}"#,
+            r#"{"timestamp":"2026-01-02T03:04:07.000Z","type":"event_msg","payload":{"type":"approval_decision","call_id":"c2","turn_id":"1","kind":"patch","decision":"abort","wait_ms":300}}"#,
+        ];
+        std::fs::write(&path, lines.join("\n")).expect("write rollout");
+
+        let (session, _) = load_session(&path, false).expect("load session");
+        assert_eq!(
+            session.turns[0].approvals,
+            vec![
+                TurnApproval {
+                    kind: ApprovalKind::Exec,
+                    denied: false,
+                    wait_ms: 1200,
+                },
+                TurnApproval {
+                    kind: ApprovalKind::Patch,
+                    denied: true,
+                    wait_ms: 300,
+                },
+            ]
+        );
+    }
+}
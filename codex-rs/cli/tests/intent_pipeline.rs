@@ -0,0 +1,158 @@
+//! End-to-end run of the intent→pattern pipeline through the `codex` binary:
+//! postmortems are imported as captures into the covenant store, compiled
+//! into the pattern log, accepted in review, and matched against new events.
+//!
+//! The compiled patterns are compared with `compiled_patterns.jsonl`, so a
+//! change to shared tokenization, templating, or scoring that alters what the
+//! pipeline produces shows up here first.
+
+use std::path::Path;
+
+use anyhow::Result;
+use codex_utils_cargo_bin::find_resource;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use tempfile::TempDir;
+
+/// Fields of a pattern log line that do not depend on event ids, paths, or
+/// when the pipeline ran.
+const STABLE_PATTERN_FIELDS: [&str; 9] = [
+    "key",
+    "version",
+    "trigger",
+    "invariant",
+    "best_response",
+    "outcome",
+    "support_count",
+    "confidence",
+    "anti_pattern",
+];
+
+const DISK_KEY: &str = "disk full on {path}|log rotation stopped";
+const CERTIFICATE_KEY: &str = "certificate expired on api|renewal job failed";
+
+fn codex_command(codex_home: &Path, project: &Path) -> Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::new(codex_utils_cargo_bin::cargo_bin("codex")?);
+    cmd.env("CODEX_HOME", codex_home).current_dir(project);
+    Ok(cmd)
+}
+
+fn stable_fields(line: &str) -> Result<Value> {
+    let entry: Value = serde_json::from_str(line)?;
+    Ok(Value::Object(
+        STABLE_PATTERN_FIELDS
+            .iter()
+            .map(|field| {
+                (
+                    (*field).to_string(),
+                    entry.get(*field).cloned().unwrap_or(Value::Null),
+                )
+            })
+            .collect(),
+    ))
+}
+
+fn read_jsonl(path: &Path) -> Result<Vec<Value>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(stable_fields)
+        .collect()
+}
+
+/// Match `event` against the live patterns and return each printed line.
+fn ranked_matches(codex_home: &Path, project: &Path, event: &str) -> Result<Vec<String>> {
+    let output = codex_command(codex_home, project)?
+        .args([
+            "patterns-match",
+            "--no-record",
+            "--min-score",
+            "0.4",
+            "--event-json",
+            event,
+        ])
+        .output()?;
+    assert!(
+        output.status.success(),
+        "patterns-match failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+#[test]
+fn captures_compile_into_patterns_that_rank_for_new_events() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    let project = TempDir::new()?;
+    let fixtures = find_resource!("tests/fixtures/intent_pipeline")?;
+
+    let mut captures = Vec::new();
+    for entry in std::fs::read_dir(fixtures.join("captures"))? {
+        captures.push(entry?.path());
+    }
+    captures.sort();
+    for capture in &captures {
+        codex_command(codex_home.path(), project.path())?
+            .args(["capture", "import"])
+            .arg(capture)
+            .assert()
+            .success();
+    }
+
+    codex_command(codex_home.path(), project.path())?
+        .arg("compile")
+        .assert()
+        .success();
+    assert_eq!(
+        read_jsonl(codex_home.path().join("patterns.jsonl").as_path())?,
+        read_jsonl(fixtures.join("compiled_patterns.jsonl").as_path())?
+    );
+
+    // Suggestions are reviewed in key order; accept both.
+    codex_command(codex_home.path(), project.path())?
+        .args(["patterns", "review"])
+        .write_stdin("a\na\n")
+        .assert()
+        .success();
+
+    let disk = ranked_matches(
+        codex_home.path(),
+        project.path(),
+        r#"{"trigger":"Disk full on /srv/cache","invariant":"log rotation stopped"}"#,
+    )?;
+    let certificate = ranked_matches(
+        codex_home.path(),
+        project.path(),
+        r#"{"trigger":"Certificate expired on api","invariant":"renewal job failed"}"#,
+    )?;
+    // Pattern ids are keys, which contain spaces, so each line is identified
+    // by the key it starts with.
+    let summary = |lines: &[String]| {
+        lines
+            .iter()
+            .map(|line| {
+                let key = [DISK_KEY, CERTIFICATE_KEY]
+                    .into_iter()
+                    .find(|key| line.starts_with(&format!("{key} ")))
+                    .unwrap_or(line.as_str());
+                (
+                    key.to_string(),
+                    line.contains("warning: anti-pattern"),
+                    line.ends_with(" path=/srv/cache"),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        (summary(&disk), summary(&certificate)),
+        (
+            vec![(DISK_KEY.to_string(), false, true)],
+            vec![(CERTIFICATE_KEY.to_string(), true, false)],
+        )
+    );
+
+    Ok(())
+}
@@ -0,0 +1,439 @@
+//! Prompt catalogs: translated headers and questions for the `capture` tool.
+//!
+//! `[capture] locale` in `config.toml` names a catalog in
+//! `CODEX_HOME/capture_locales/<locale>.toml`. The catalog has one table per
+//! capture section, keyed like capture templates, holding the text of that
+//! section's headers and questions. Text may use the placeholders of the
+//! built-in English text it replaces, such as `{min}` and `{max}`. Anything a
+//! catalog leaves out stays in English, and wording from a capture template
+//! takes precedence over both. A `[review]` table translates the summary shown
+//! before the record is saved.
+
+use crate::capture_numbers::NumberFormat;
+use crate::capture_template::CaptureSection;
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub(crate) const CAPTURE_LOCALES_DIR: &str = "capture_locales";
+
+/// Catalog table for the review step, which belongs to no capture section.
+const REVIEW_TABLE: &str = "review";
+
+/// The built-in English text of the review step. `summary` lists the whole
+/// record, and each section offered for redoing is described by `redo`.
+const REVIEW_PROMPTS: &[(&str, &str)] = &[
+    ("header", "Review capture"),
+    (
+        "summary",
+        "Intent: {goal} (confidence {confidence})\nEvent: {event}\n{hypotheses} hypotheses, {tests} tests, {test_results} test results, {outcomes} outcomes, {patterns} patterns\nOutcomes: {outcome_list}",
+    ),
+    (
+        "question",
+        "Save this capture, or choose a section to answer again.",
+    ),
+    ("save", "Save"),
+    ("save_description", "Finish the capture with these answers."),
+    ("redo", "Answer the {section} questions again."),
+];
+
+/// The built-in English text of every prompt in `section`. `header` titles
+/// each entry; sections that repeat also have `count_header` and `count` for
+/// the question asking how many entries to record.
+fn english_prompts(section: CaptureSection) -> &'static [(&'static str, &'static str)] {
+    match section {
+        CaptureSection::Intent => &[
+            ("header", "Intent token"),
+            ("goal", "What is the goal?"),
+            ("constraints", "What constraints must be respected?"),
+            ("success_signal", "What signals success?"),
+            ("confidence", "What is your confidence (0-1 or 0-100%)?"),
+        ],
+        CaptureSection::Event => &[
+            ("header", "Event details"),
+            ("details", "Describe the event details."),
+            (
+                "draft",
+                "Drafted from this session. Choose it to confirm, or type your own answer.",
+            ),
+        ],
+        CaptureSection::Hypotheses => &[
+            ("count_header", "Hypotheses"),
+            ("count", "How many hypotheses? ({min}-{max})"),
+            ("header", "Hypothesis"),
+            ("statement", "Hypothesis statement"),
+            ("probability", "Prior probability (0-1 or 0-100%)"),
+            (
+                "falsifiers",
+                "Falsifier(s) (comma/semicolon/newline separated)",
+            ),
+            (
+                "domain_signature",
+                "Domain-signature mixture vector (domain:weight, ...)",
+            ),
+        ],
+        CaptureSection::HypothesisRelations => &[
+            ("header", "Hypothesis relations"),
+            (
+                "depends_on",
+                "Which hypotheses must also hold for {id} to hold? Answer \"none\" or list ids. Available: {hypotheses}",
+            ),
+            (
+                "mutually_exclusive_with",
+                "Which hypotheses cannot hold together with {id}? Answer \"none\" or list ids.",
+            ),
+        ],
+        CaptureSection::Tests => &[
+            ("count_header", "Tests"),
+            ("count", "How many tests? ({min}-{max})"),
+            ("header", "Test"),
+            ("description", "Test description"),
+            ("procedure", "Test procedure / steps"),
+            (
+                "command",
+                "Shell command that runs this test, if any (or \"none\")",
+            ),
+            (
+                "draft",
+                "Drafted from this session. Choose it to confirm, or type your own answer.",
+            ),
+            (
+                "draft_description",
+                "`{command}` (exited with {exit_code} in this session)",
+            ),
+            ("draft_procedure", "Run `{command}`."),
+        ],
+        CaptureSection::HypothesisLinks => &[
+            ("header", "Hypothesis tests"),
+            (
+                "tests",
+                "Link tests for {id} ({statement})? Available: {tests}",
+            ),
+        ],
+        CaptureSection::TestResults => &[
+            ("count_header", "Test results"),
+            (
+                "count",
+                "How many test results are you recording? ({min}-{max})",
+            ),
+            ("header", "Test result"),
+            (
+                "run",
+                "Run `{command}` for {id} ({description}) and record its result from the exit status?",
+            ),
+            ("run_accept", "Run"),
+            (
+                "run_accept_description",
+                "Run the command in the sandbox and record pass or fail from its exit code.",
+            ),
+            ("run_decline", "Skip"),
+            (
+                "run_decline_description",
+                "Do not run it; record this test's result by hand.",
+            ),
+            ("test_id", "Test id (choose one): {tests}"),
+            ("result", "Result (pass/fail/inconclusive/skipped/error)"),
+            ("notes", "Notes / observations"),
+            (
+                "updates",
+                "Update hypothesis probabilities as H1=0.7,H2=0.2 (available: {hypotheses})",
+            ),
+            (
+                "artifacts",
+                "Files supporting this result, such as logs or screenshots (paths, or \"none\")",
+            ),
+        ],
+        CaptureSection::Outcomes => &[
+            ("count_header", "Outcomes"),
+            (
+                "count",
+                "How many outcomes are you recording? ({min}-{max})",
+            ),
+            ("header", "Outcome"),
+            ("summary", "Outcome summary"),
+            (
+                "draft",
+                "Drafted from this session. Choose it to confirm, or type your own answer.",
+            ),
+            ("evidence", "Evidence test ids (available: {tests})"),
+            (
+                "artifacts",
+                "Files supporting this outcome (paths, or \"none\")",
+            ),
+        ],
+        CaptureSection::Patterns => &[
+            ("count_header", "Patterns"),
+            (
+                "count",
+                "How many patterns are you recording? ({min}-{max})",
+            ),
+            ("header", "Pattern"),
+            ("trigger", "Trigger"),
+            ("invariant", "Invariant"),
+            ("counterexample", "Counterexample"),
+            ("best_response", "Best response"),
+            (
+                "domain_signature",
+                "Domain-signature mixture vector (domain:weight, ...)",
+            ),
+            ("evidence", "Evidence test ids (available: {tests})"),
+        ],
+    }
+}
+
+/// The English prompts of a catalog table: a section key or `review`.
+fn english_table(table: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    if table == REVIEW_TABLE {
+        return Some(REVIEW_PROMPTS);
+    }
+    CaptureSection::ALL
+        .into_iter()
+        .find(|section| section.key() == table)
+        .map(english_prompts)
+}
+
+fn english_prompt(table: &str, key: &str) -> Option<&'static str> {
+    english_table(table)?
+        .iter()
+        .find(|(id, _)| *id == key)
+        .map(|(_, text)| *text)
+}
+
+/// Prompt text for one locale, keyed by section and prompt key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct PromptCatalog {
+    sections: BTreeMap<String, BTreeMap<String, String>>,
+    numbers: NumberFormat,
+}
+
+impl PromptCatalog {
+    /// The catalog, reading numbers in answers as `numbers` says.
+    pub(crate) fn with_numbers(self, numbers: NumberFormat) -> Self {
+        Self { numbers, ..self }
+    }
+
+    /// How answers spell numbers.
+    pub(crate) fn numbers(&self) -> &NumberFormat {
+        &self.numbers
+    }
+
+    /// The catalog's text for `key` in `section`, or the English text, with
+    /// each `{name}` in `args` filled in.
+    pub(crate) fn text(&self, section: CaptureSection, key: &str, args: &[(&str, &str)]) -> String {
+        self.lookup(section.key(), key, args)
+    }
+
+    /// Text of the review step, like [`PromptCatalog::text`].
+    pub(crate) fn review_text(&self, key: &str, args: &[(&str, &str)]) -> String {
+        self.lookup(REVIEW_TABLE, key, args)
+    }
+
+    /// Short name of `section`: the plural count header of a repeating
+    /// section, or else its header.
+    pub(crate) fn section_label(&self, section: CaptureSection) -> String {
+        let key = if english_prompt(section.key(), "count_header").is_some() {
+            "count_header"
+        } else {
+            "header"
+        };
+        self.text(section, key, &[])
+    }
+
+    fn lookup(&self, table: &str, key: &str, args: &[(&str, &str)]) -> String {
+        let text = self
+            .sections
+            .get(table)
+            .and_then(|prompts| prompts.get(key))
+            .map(String::as_str)
+            .or_else(|| english_prompt(table, key))
+            .unwrap_or(key);
+        args.iter().fold(text.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (key, prompts) in &self.sections {
+            if english_table(key).is_none() {
+                return Err(format!("unknown section '{key}'"));
+            }
+            for (id, text) in prompts {
+                let Some(english) = english_prompt(key, id) else {
+                    return Err(format!("section '{key}' has no prompt '{id}'"));
+                };
+                let allowed = placeholders(english);
+                if let Some(unknown) = placeholders(text)
+                    .into_iter()
+                    .find(|name| !allowed.contains(name))
+                {
+                    if allowed.is_empty() {
+                        return Err(format!(
+                            "{key}.{id}: unknown placeholder {{{unknown}}}; this prompt takes none"
+                        ));
+                    }
+                    return Err(format!(
+                        "{key}.{id}: unknown placeholder {{{unknown}}}; expected one of: {}",
+                        allowed
+                            .iter()
+                            .map(|name| format!("{{{name}}}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Names of the `{name}` placeholders in `text`.
+fn placeholders(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        let name = &rest[..end];
+        if !name.is_empty() && name.chars().all(|ch| ch.is_ascii_lowercase() || ch == '_') {
+            names.push(name);
+            rest = &rest[end + 1..];
+        }
+    }
+    names
+}
+
+/// Load the catalog for `locale` from `CODEX_HOME/capture_locales`. Without a
+/// locale, or for `en` when no `en.toml` exists, the English prompts are used.
+pub(crate) fn load_prompt_catalog(
+    codex_home: &Path,
+    locale: Option<&str>,
+) -> anyhow::Result<PromptCatalog> {
+    let Some(locale) = locale.map(str::trim).filter(|locale| !locale.is_empty()) else {
+        return Ok(PromptCatalog::default());
+    };
+    if !locale
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+    {
+        anyhow::bail!("capture locales may only use letters, digits, '-' and '_'");
+    }
+    let path = codex_home
+        .join(CAPTURE_LOCALES_DIR)
+        .join(format!("{locale}.toml"));
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            if locale.eq_ignore_ascii_case("en") {
+                return Ok(PromptCatalog::default());
+            }
+            anyhow::bail!(
+                "unknown capture locale '{locale}'; expected {}",
+                path.display()
+            );
+        }
+        Err(err) => return Err(anyhow::anyhow!("failed to read {}: {err}", path.display())),
+    };
+    parse_prompt_catalog(&contents).with_context(|| format!("invalid {}", path.display()))
+}
+
+fn parse_prompt_catalog(contents: &str) -> anyhow::Result<PromptCatalog> {
+    let catalog = PromptCatalog {
+        sections: toml::from_str(contents)?,
+        numbers: NumberFormat::default(),
+    };
+    catalog.validate().map_err(anyhow::Error::msg)?;
+    Ok(catalog)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const FRENCH: &str = r#"
+[intent]
+header = "Jeton d'intention"
+goal = "Quel est l'objectif ?"
+
+[hypotheses]
+count = "Combien d'hypothèses ? ({min} à {max})"
+"#;
+
+    #[test]
+    fn catalogs_translate_prompts_and_fall_back_to_english() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let dir = codex_home.path().join(CAPTURE_LOCALES_DIR);
+        std::fs::create_dir_all(&dir).expect("create locales dir");
+        std::fs::write(dir.join("fr.toml"), FRENCH).expect("write catalog");
+
+        let french = load_prompt_catalog(codex_home.path(), Some("fr")).expect("load fr");
+        let english = load_prompt_catalog(codex_home.path(), Some("en")).expect("load en");
+        let range = [("min", "3"), ("max", "7")];
+
+        assert_eq!(
+            (
+                french.text(CaptureSection::Intent, "header", &[]),
+                french.text(CaptureSection::Intent, "goal", &[]),
+                french.text(CaptureSection::Intent, "constraints", &[]),
+                french.text(CaptureSection::Hypotheses, "count", &range),
+                english.text(CaptureSection::Hypotheses, "count", &range),
+            ),
+            (
+                "Jeton d'intention".to_string(),
+                "Quel est l'objectif ?".to_string(),
+                "What constraints must be respected?".to_string(),
+                "Combien d'hypothèses ? (3 à 7)".to_string(),
+                "How many hypotheses? (3-7)".to_string(),
+            )
+        );
+        assert_eq!(english, PromptCatalog::default());
+    }
+
+    #[test]
+    fn invalid_catalogs_are_rejected() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let errors = [
+            "[postmortem]\nheader = \"Post-mortem\"",
+            "[tests]\nsteps = \"Étapes\"",
+            "[tests]\ncount = \"Combien de tests ? ({count})\"",
+        ]
+        .into_iter()
+        .map(|contents| {
+            parse_prompt_catalog(contents)
+                .err()
+                .map(|err| err.to_string())
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            (
+                errors,
+                load_prompt_catalog(codex_home.path(), Some("de"))
+                    .err()
+                    .map(|err| err.to_string()),
+                load_prompt_catalog(codex_home.path(), Some("../fr")).is_err(),
+            ),
+            (
+                vec![
+                    Some("unknown section 'postmortem'".to_string()),
+                    Some("section 'tests' has no prompt 'steps'".to_string()),
+                    Some(
+                        "tests.count: unknown placeholder {count}; expected one of: {min}, {max}"
+                            .to_string()
+                    ),
+                ],
+                Some(format!(
+                    "unknown capture locale 'de'; expected {}",
+                    codex_home
+                        .path()
+                        .join(CAPTURE_LOCALES_DIR)
+                        .join("de.toml")
+                        .display()
+                )),
+                true,
+            )
+        );
+    }
+}
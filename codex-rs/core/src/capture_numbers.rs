@@ -0,0 +1,254 @@
+//! Numbers typed into the `capture` tool: confidences, probabilities, and
+//! domain-signature weights.
+//!
+//! Decimals use `[capture] decimal_separator`, which defaults to the one the
+//! capture locale writes, so `0,7` is 0.7 under `locale = "fr"`. By default
+//! the other separator is accepted too when a number has no other reading,
+//! and a bare probability above 1 and at most 100 is read as a percentage.
+//! `[capture] strict_numbers = true` accepts only the configured separator
+//! and reads a percentage only from a number ending in `%`.
+
+use std::fmt;
+
+use crate::config::types::DecimalSeparator;
+
+/// Languages, by ISO 639 code, that write decimals with a comma.
+const DECIMAL_COMMA_LANGUAGES: &[&str] = &[
+    "af", "bg", "ca", "cs", "da", "de", "el", "es", "et", "eu", "fi", "fr", "gl", "hr", "hu", "id",
+    "is", "it", "lt", "lv", "nb", "nl", "nn", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sq", "sr",
+    "sv", "tr", "uk", "vi",
+];
+
+/// The decimal separator of `locale`'s language, such as `,` for `pt-BR`.
+/// Without a locale, or for a language not known to use a comma, it is `.`.
+pub(crate) fn locale_decimal_separator(locale: Option<&str>) -> DecimalSeparator {
+    let language = locale
+        .map(str::trim)
+        .and_then(|locale| locale.split(['-', '_']).next())
+        .map(str::to_ascii_lowercase);
+    match language {
+        Some(language) if DECIMAL_COMMA_LANGUAGES.contains(&language.as_str()) => {
+            DecimalSeparator::Comma
+        }
+        Some(_) | None => DecimalSeparator::Point,
+    }
+}
+
+impl DecimalSeparator {
+    fn as_char(self) -> char {
+        match self {
+            Self::Point => '.',
+            Self::Comma => ',',
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Self::Point => Self::Comma,
+            Self::Comma => Self::Point,
+        }
+    }
+}
+
+/// How capture answers spell numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct NumberFormat {
+    pub(crate) decimal_separator: DecimalSeparator,
+    pub(crate) strict: bool,
+}
+
+/// Why an answer is not a valid number.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum NumberError {
+    /// Not a finite number written with an accepted decimal separator.
+    NotANumber { text: String, example: String },
+    /// Above 1 without `%`, which strict parsing needs for a percentage.
+    PercentWithoutSign { text: String },
+    /// A probability outside 0 to 1.
+    OutOfRange { text: String, value: f64 },
+}
+
+impl fmt::Display for NumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotANumber { text, example } => {
+                write!(f, "'{text}' is not a number; write it like {example}")
+            }
+            Self::PercentWithoutSign { text } => write!(
+                f,
+                "'{text}' is above 1; write {text}% for a percentage, or a value from 0 to 1"
+            ),
+            Self::OutOfRange { text, value } => {
+                write!(f, "'{text}' is {value}, which is not between 0 and 1")
+            }
+        }
+    }
+}
+
+impl NumberFormat {
+    /// A decimal number, such as a domain-signature weight.
+    pub(crate) fn parse_number(&self, text: &str) -> Result<f64, NumberError> {
+        let text = text.trim();
+        let separator = self.decimal_separator.as_char();
+        let other = self.decimal_separator.other().as_char();
+        let normalized = if text.contains(other) {
+            // The other separator stands in for ours only when it appears
+            // once and ours does not, so `1.000,5` is never guessed at.
+            let lenient =
+                !self.strict && !text.contains(separator) && text.matches(other).count() == 1;
+            if !lenient {
+                return Err(self.not_a_number(text));
+            }
+            text.replace(other, ".")
+        } else {
+            text.replace(separator, ".")
+        };
+        normalized
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| self.not_a_number(text))
+    }
+
+    /// A probability from 0 to 1, or a percentage ending in `%`. Lenient
+    /// parsing also reads a bare number above 1 and at most 100 as one.
+    pub(crate) fn parse_probability(&self, text: &str) -> Result<f64, NumberError> {
+        let text = text.trim();
+        let (number, percent) = match text.strip_suffix('%') {
+            Some(number) => (number, true),
+            None => (text, false),
+        };
+        let mut value = self.parse_number(number)?;
+        if percent {
+            value /= 100.0;
+        } else if value > 1.0 && value <= 100.0 {
+            if self.strict {
+                return Err(NumberError::PercentWithoutSign {
+                    text: text.to_string(),
+                });
+            }
+            value /= 100.0;
+        }
+        if !(0.0..=1.0).contains(&value) {
+            return Err(NumberError::OutOfRange {
+                text: text.to_string(),
+                value,
+            });
+        }
+        Ok(value)
+    }
+
+    /// Split a list of entries holding numbers. Commas separate entries
+    /// unless they are the decimal separator; semicolons and newlines always
+    /// do.
+    pub(crate) fn split_list(&self, text: &str) -> Vec<String> {
+        let comma_separates = self.decimal_separator != DecimalSeparator::Comma;
+        text.split(|ch| (ch == ',' && comma_separates) || ch == ';' || ch == '\n')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn not_a_number(&self, text: &str) -> NumberError {
+        let separator = self.decimal_separator.as_char();
+        NumberError::NotANumber {
+            text: text.to_string(),
+            example: format!("0{separator}7 or 70%"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn format(decimal_separator: DecimalSeparator, strict: bool) -> NumberFormat {
+        NumberFormat {
+            decimal_separator,
+            strict,
+        }
+    }
+
+    #[test]
+    fn probabilities_follow_the_separator_and_strictness() {
+        let lenient_comma = format(DecimalSeparator::Comma, false);
+        let strict_comma = format(DecimalSeparator::Comma, true);
+        let strict_point = format(DecimalSeparator::Point, true);
+        let parse = |format: NumberFormat, text: &str| format.parse_probability(text);
+
+        assert_eq!(
+            [
+                parse(NumberFormat::default(), "0.7"),
+                parse(NumberFormat::default(), "0,7"),
+                parse(NumberFormat::default(), "70"),
+                parse(lenient_comma, "0,25"),
+                parse(lenient_comma, "0.25"),
+                parse(strict_comma, "12,5 %"),
+                parse(strict_point, "1"),
+            ],
+            [
+                Ok(0.7),
+                Ok(0.7),
+                Ok(0.7),
+                Ok(0.25),
+                Ok(0.25),
+                Ok(0.125),
+                Ok(1.0)
+            ]
+        );
+        assert_eq!(
+            [
+                parse(strict_comma, "0.7"),
+                parse(strict_point, "70"),
+                parse(NumberFormat::default(), "1.000,5"),
+                parse(NumberFormat::default(), "140"),
+                parse(NumberFormat::default(), "NaN"),
+            ]
+            .map(|result| result.map_err(|err| err.to_string())),
+            [
+                Err("'0.7' is not a number; write it like 0,7 or 70%".to_string()),
+                Err(
+                    "'70' is above 1; write 70% for a percentage, or a value from 0 to 1"
+                        .to_string()
+                ),
+                Err("'1.000,5' is not a number; write it like 0.7 or 70%".to_string()),
+                Err("'140' is 140, which is not between 0 and 1".to_string()),
+                Err("'NaN' is not a number; write it like 0.7 or 70%".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn locales_pick_the_separator_and_lists_keep_decimal_commas() {
+        assert_eq!(
+            [
+                locale_decimal_separator(None),
+                locale_decimal_separator(Some("en-GB")),
+                locale_decimal_separator(Some(" pt-BR ")),
+                locale_decimal_separator(Some("de_AT")),
+            ],
+            [
+                DecimalSeparator::Point,
+                DecimalSeparator::Point,
+                DecimalSeparator::Comma,
+                DecimalSeparator::Comma,
+            ]
+        );
+        assert_eq!(
+            (
+                NumberFormat::default().split_list("H1=0.8, H2=0.2"),
+                format(DecimalSeparator::Comma, false).split_list("H1=0,8; H2=0,2\nH3=1"),
+            ),
+            (
+                vec!["H1=0.8".to_string(), "H2=0.2".to_string()],
+                vec![
+                    "H1=0,8".to_string(),
+                    "H2=0,2".to_string(),
+                    "H3=1".to_string(),
+                ],
+            )
+        );
+    }
+}
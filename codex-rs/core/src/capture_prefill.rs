@@ -0,0 +1,348 @@
+//! Draft answers for the `capture` tool, taken from the session so far.
+//!
+//! Much of what a capture records is already in the transcript: the user's
+//! latest request describes the event, the shell commands the agent ran are
+//! candidate tests, and its latest reply sums up the outcome. The handler
+//! offers each draft as an answer to choose, so the user confirms it instead
+//! of typing it again, or replaces it with their own.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use codex_protocol::items::AgentMessageContent;
+use codex_protocol::items::TurnItem;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::ShellCommandToolCallParams;
+use codex_protocol::models::ShellToolCallParams;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::capture_locale::PromptCatalog;
+use crate::capture_template::CaptureSection;
+use crate::event_mapping::parse_turn_item;
+use crate::parse_command::extract_shell_command;
+use crate::parse_command::shlex_join;
+
+/// Arguments of the `exec_command` tool that name the command it ran.
+#[derive(Deserialize)]
+struct ExecCommandArgs {
+    cmd: String,
+}
+
+/// A shell command the session ran, with the exit code its output reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CommandRun {
+    command: String,
+    exit_code: Option<i64>,
+}
+
+/// Draft answers derived from a session's history.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct CaptureDrafts {
+    /// The latest message from the user.
+    event: Option<String>,
+    /// Distinct shell commands, most recently run first.
+    commands: Vec<CommandRun>,
+    /// The latest message from the agent.
+    summary: Option<String>,
+}
+
+impl CaptureDrafts {
+    pub(crate) fn from_history(items: &[ResponseItem]) -> Self {
+        let mut drafts = Self::default();
+        let mut runs: Vec<(String, String)> = Vec::new();
+        let mut exit_codes: HashMap<&str, i64> = HashMap::new();
+        for item in items {
+            match item {
+                ResponseItem::FunctionCall {
+                    name,
+                    arguments,
+                    call_id,
+                    ..
+                } => {
+                    if let Some(command) = function_call_command(name, arguments) {
+                        runs.push((call_id.clone(), command));
+                    }
+                }
+                ResponseItem::LocalShellCall {
+                    call_id, action, ..
+                } => {
+                    let LocalShellAction::Exec(exec) = action;
+                    runs.push((
+                        call_id.clone().unwrap_or_default(),
+                        display_command(&exec.command),
+                    ));
+                }
+                ResponseItem::FunctionCallOutput { call_id, output } => {
+                    if let Some(code) = output.body.to_text().as_deref().and_then(exit_code) {
+                        exit_codes.insert(call_id.as_str(), code);
+                    }
+                }
+                ResponseItem::CustomToolCallOutput { call_id, output } => {
+                    if let Some(code) = exit_code(output) {
+                        exit_codes.insert(call_id.as_str(), code);
+                    }
+                }
+                ResponseItem::Message { .. } => match parse_turn_item(item) {
+                    Some(TurnItem::UserMessage(user)) => {
+                        drafts.event = single_line(&user.message());
+                    }
+                    Some(TurnItem::AgentMessage(agent)) => {
+                        let text = agent
+                            .content
+                            .iter()
+                            .map(|content| match content {
+                                AgentMessageContent::Text { text } => text.as_str(),
+                            })
+                            .collect::<String>();
+                        if let Some(text) = single_line(&text) {
+                            drafts.summary = Some(text);
+                        }
+                    }
+                    Some(
+                        TurnItem::Plan(_)
+                        | TurnItem::Reasoning(_)
+                        | TurnItem::WebSearch(_)
+                        | TurnItem::ContextCompaction(_),
+                    )
+                    | None => {}
+                },
+                ResponseItem::Reasoning { .. }
+                | ResponseItem::CustomToolCall { .. }
+                | ResponseItem::WebSearchCall { .. }
+                | ResponseItem::GhostSnapshot { .. }
+                | ResponseItem::Compaction { .. }
+                | ResponseItem::Other => {}
+            }
+        }
+        for (call_id, command) in runs.into_iter().rev() {
+            if drafts.commands.iter().any(|run| run.command == command) {
+                continue;
+            }
+            drafts.commands.push(CommandRun {
+                exit_code: exit_codes.get(call_id.as_str()).copied(),
+                command,
+            });
+        }
+        drafts
+    }
+
+    /// Drafts for the questions of entry `index` of `section`, keyed by
+    /// question id. Questions without a draft are left out.
+    pub(crate) fn answers(
+        &self,
+        prompts: &PromptCatalog,
+        section: CaptureSection,
+        index: usize,
+    ) -> BTreeMap<&'static str, String> {
+        let mut answers = BTreeMap::new();
+        match section {
+            CaptureSection::Event => {
+                if let Some(event) = self.event.clone() {
+                    answers.insert("details", event);
+                }
+            }
+            CaptureSection::Tests => {
+                if let Some(run) = self.commands.get(index) {
+                    let command = [("command", run.command.as_str())];
+                    let description = match run.exit_code {
+                        Some(code) => prompts.text(
+                            section,
+                            "draft_description",
+                            &[
+                                ("command", run.command.as_str()),
+                                ("exit_code", code.to_string().as_str()),
+                            ],
+                        ),
+                        None => format!("`{}`", run.command),
+                    };
+                    answers.insert("description", description);
+                    answers.insert(
+                        "procedure",
+                        prompts.text(section, "draft_procedure", &command),
+                    );
+                    answers.insert("command", run.command.clone());
+                }
+            }
+            CaptureSection::Outcomes => {
+                if index == 0
+                    && let Some(summary) = self.summary.clone()
+                {
+                    answers.insert("summary", summary);
+                }
+            }
+            CaptureSection::Intent
+            | CaptureSection::Hypotheses
+            | CaptureSection::HypothesisRelations
+            | CaptureSection::HypothesisLinks
+            | CaptureSection::TestResults
+            | CaptureSection::Patterns => {}
+        }
+        answers
+    }
+}
+
+/// The command run by a call to one of the shell tools.
+fn function_call_command(name: &str, arguments: &str) -> Option<String> {
+    match name {
+        "shell" | "container.exec" => serde_json::from_str::<ShellToolCallParams>(arguments)
+            .ok()
+            .map(|params| display_command(&params.command)),
+        "shell_command" => serde_json::from_str::<ShellCommandToolCallParams>(arguments)
+            .ok()
+            .map(|params| params.command),
+        "exec_command" => serde_json::from_str::<ExecCommandArgs>(arguments)
+            .ok()
+            .map(|args| args.cmd),
+        _ => None,
+    }
+}
+
+/// The script of a `bash -lc` style invocation, or the joined argv.
+fn display_command(command: &[String]) -> String {
+    extract_shell_command(command)
+        .map_or_else(|| shlex_join(command), |(_, script)| script.to_string())
+}
+
+/// The exit code reported by shell-style tool output, if present.
+fn exit_code(output: &str) -> Option<i64> {
+    if let Ok(value) = serde_json::from_str::<Value>(output)
+        && let Some(code) = value.pointer("/metadata/exit_code").and_then(Value::as_i64)
+    {
+        return Some(code);
+    }
+    output.lines().find_map(|line| {
+        let line = line.trim();
+        line.strip_prefix("Exit code: ")
+            .or_else(|| line.strip_prefix("Process exited with code "))
+            .and_then(|code| code.trim().parse().ok())
+    })
+}
+
+/// `text` with its whitespace collapsed, so it fits on one answer line.
+fn single_line(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::ContentItem;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn message(role: &str, text: &str) -> ResponseItem {
+        let text = text.to_string();
+        ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![if role == "user" {
+                ContentItem::InputText { text }
+            } else {
+                ContentItem::OutputText { text }
+            }],
+            end_turn: None,
+            phase: None,
+        }
+    }
+
+    fn shell_call(call_id: &str, command: &str, output: &str) -> [ResponseItem; 2] {
+        [
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell_command".to_string(),
+                arguments: serde_json::json!({ "command": command }).to_string(),
+                call_id: call_id.to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: call_id.to_string(),
+                output: FunctionCallOutputPayload::from_text(output.to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn drafts_come_from_the_latest_messages_and_distinct_commands() {
+        let mut items = vec![
+            message("user", "First request"),
+            message("user", "The deploy failed\nafter the migration"),
+        ];
+        items.extend(shell_call(
+            "c1",
+            "cargo test",
+            "Exit code: 1\nOutput:\nfailed",
+        ));
+        items.extend(shell_call(
+            "c2",
+            "git status",
+            "Exit code: 0\nOutput:\nclean",
+        ));
+        items.extend(shell_call("c3", "cargo test", "Exit code: 0\nOutput:\nok"));
+        items.push(ResponseItem::FunctionCall {
+            id: None,
+            name: "exec_command".to_string(),
+            arguments: r#"{"cmd":"ls"}"#.to_string(),
+            call_id: "c4".to_string(),
+        });
+        items.push(message("assistant", "Fixed the migration order."));
+
+        let drafts = CaptureDrafts::from_history(&items);
+        let prompts = PromptCatalog::default();
+
+        assert_eq!(
+            drafts,
+            CaptureDrafts {
+                event: Some("The deploy failed after the migration".to_string()),
+                commands: vec![
+                    CommandRun {
+                        command: "ls".to_string(),
+                        exit_code: None,
+                    },
+                    CommandRun {
+                        command: "cargo test".to_string(),
+                        exit_code: Some(0),
+                    },
+                    CommandRun {
+                        command: "git status".to_string(),
+                        exit_code: Some(0),
+                    },
+                ],
+                summary: Some("Fixed the migration order.".to_string()),
+            }
+        );
+        assert_eq!(
+            (
+                drafts.answers(&prompts, CaptureSection::Event, 0),
+                drafts.answers(&prompts, CaptureSection::Tests, 0),
+                drafts.answers(&prompts, CaptureSection::Tests, 1),
+                drafts.answers(&prompts, CaptureSection::Tests, 3),
+                drafts.answers(&prompts, CaptureSection::Outcomes, 0),
+                drafts.answers(&prompts, CaptureSection::Outcomes, 1),
+            ),
+            (
+                BTreeMap::from([(
+                    "details",
+                    "The deploy failed after the migration".to_string()
+                )]),
+                BTreeMap::from([
+                    ("description", "`ls`".to_string()),
+                    ("procedure", "Run `ls`.".to_string()),
+                    ("command", "ls".to_string()),
+                ]),
+                BTreeMap::from([
+                    (
+                        "description",
+                        "`cargo test` (exited with 0 in this session)".to_string()
+                    ),
+                    ("procedure", "Run `cargo test`.".to_string()),
+                    ("command", "cargo test".to_string()),
+                ]),
+                BTreeMap::new(),
+                BTreeMap::from([("summary", "Fixed the migration order.".to_string())]),
+                BTreeMap::new(),
+            )
+        );
+    }
+}
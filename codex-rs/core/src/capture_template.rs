@@ -0,0 +1,405 @@
+//! Capture templates: per-team wording and answer rules for the `capture`
+//! tool.
+//!
+//! A template lives in `CODEX_HOME/capture_templates/<name>.toml`. Each section
+//! of the capture flow may reword its questions, add length or choice rules to
+//! the answers, and, for sections that repeat, change how many entries are
+//! asked for. Anything a template leaves out keeps the built-in behavior.
+
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub(crate) const CAPTURE_TEMPLATES_DIR: &str = "capture_templates";
+
+/// One step of the capture flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CaptureSection {
+    Intent,
+    Event,
+    Hypotheses,
+    HypothesisRelations,
+    Tests,
+    HypothesisLinks,
+    TestResults,
+    Outcomes,
+    Patterns,
+}
+
+impl CaptureSection {
+    pub(crate) const ALL: [Self; 9] = [
+        Self::Intent,
+        Self::Event,
+        Self::Hypotheses,
+        Self::HypothesisRelations,
+        Self::Tests,
+        Self::HypothesisLinks,
+        Self::TestResults,
+        Self::Outcomes,
+        Self::Patterns,
+    ];
+
+    /// Table name of the section in a template file.
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Self::Intent => "intent",
+            Self::Event => "event",
+            Self::Hypotheses => "hypotheses",
+            Self::HypothesisRelations => "hypothesis_relations",
+            Self::Tests => "tests",
+            Self::HypothesisLinks => "hypothesis_links",
+            Self::TestResults => "test_results",
+            Self::Outcomes => "outcomes",
+            Self::Patterns => "patterns",
+        }
+    }
+
+    pub(crate) fn question_ids(self) -> &'static [&'static str] {
+        match self {
+            Self::Intent => &["goal", "constraints", "success_signal", "confidence"],
+            Self::Event => &["details"],
+            Self::Hypotheses => &["statement", "probability", "falsifiers", "domain_signature"],
+            Self::HypothesisRelations => &["depends_on", "mutually_exclusive_with"],
+            Self::Tests => &["description", "procedure", "command"],
+            Self::HypothesisLinks => &["tests"],
+            Self::TestResults => &["test_id", "result", "notes", "updates", "artifacts"],
+            Self::Outcomes => &["summary", "evidence", "artifacts"],
+            Self::Patterns => &[
+                "trigger",
+                "invariant",
+                "counterexample",
+                "best_response",
+                "domain_signature",
+                "evidence",
+            ],
+        }
+    }
+
+    /// Built-in `(min, max)` entry count, for sections that repeat.
+    fn default_count(self) -> Option<(usize, usize)> {
+        match self {
+            Self::Hypotheses => Some((3, 7)),
+            Self::Tests | Self::TestResults => Some((1, 10)),
+            Self::Outcomes | Self::Patterns => Some((1, 5)),
+            Self::Intent | Self::Event | Self::HypothesisRelations | Self::HypothesisLinks => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct CaptureTemplate {
+    intent: SectionTemplate,
+    event: SectionTemplate,
+    hypotheses: SectionTemplate,
+    hypothesis_relations: SectionTemplate,
+    tests: SectionTemplate,
+    hypothesis_links: SectionTemplate,
+    test_results: SectionTemplate,
+    outcomes: SectionTemplate,
+    patterns: SectionTemplate,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct SectionTemplate {
+    /// Fewest entries to ask for, for sections that repeat.
+    min: Option<usize>,
+    /// Most entries to ask for, for sections that repeat.
+    max: Option<usize>,
+    /// Overrides keyed by question id.
+    questions: BTreeMap<String, QuestionTemplate>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct QuestionTemplate {
+    /// Replaces the built-in wording of the question.
+    question: Option<String>,
+    /// Shortest accepted answer, in characters.
+    min_length: Option<usize>,
+    /// Longest accepted answer, in characters.
+    max_length: Option<usize>,
+    /// Accepted answers, compared case-insensitively. Empty accepts anything.
+    one_of: Vec<String>,
+}
+
+impl CaptureTemplate {
+    pub(crate) fn section(&self, section: CaptureSection) -> &SectionTemplate {
+        match section {
+            CaptureSection::Intent => &self.intent,
+            CaptureSection::Event => &self.event,
+            CaptureSection::Hypotheses => &self.hypotheses,
+            CaptureSection::HypothesisRelations => &self.hypothesis_relations,
+            CaptureSection::Tests => &self.tests,
+            CaptureSection::HypothesisLinks => &self.hypothesis_links,
+            CaptureSection::TestResults => &self.test_results,
+            CaptureSection::Outcomes => &self.outcomes,
+            CaptureSection::Patterns => &self.patterns,
+        }
+    }
+
+    /// `(min, max)` number of entries to ask for in a repeating section.
+    pub(crate) fn count_range(&self, section: CaptureSection) -> (usize, usize) {
+        let (default_min, default_max) = section.default_count().unwrap_or((1, 1));
+        let template = self.section(section);
+        (
+            template.min.unwrap_or(default_min),
+            template.max.unwrap_or(default_max),
+        )
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for section in CaptureSection::ALL {
+            let key = section.key();
+            let template = self.section(section);
+            for (id, question) in &template.questions {
+                if !section.question_ids().contains(&id.as_str()) {
+                    return Err(format!("section '{key}' has no question '{id}'"));
+                }
+                if let (Some(min), Some(max)) = (question.min_length, question.max_length)
+                    && min > max
+                {
+                    return Err(format!(
+                        "{key}.{id}: min_length {min} is greater than max_length {max}"
+                    ));
+                }
+            }
+            if section.default_count().is_none() {
+                if template.min.is_some() || template.max.is_some() {
+                    return Err(format!(
+                        "section '{key}' does not repeat; remove min and max"
+                    ));
+                }
+                continue;
+            }
+            let (min, max) = self.count_range(section);
+            if min == 0 {
+                return Err(format!("section '{key}' needs a min of at least 1"));
+            }
+            if min > max {
+                return Err(format!(
+                    "section '{key}': min {min} is greater than max {max}"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SectionTemplate {
+    /// The template's wording for question `id`, or `default`.
+    pub(crate) fn question(&self, id: &str, default: &str) -> String {
+        self.questions
+            .get(id)
+            .and_then(|question| question.question.clone())
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    /// Check every answer against the rules of its question.
+    pub(crate) fn check(&self, answers: &BTreeMap<String, String>) -> Result<(), String> {
+        for (id, question) in &self.questions {
+            if let Some(answer) = answers.get(id) {
+                question.check(id, answer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the answer to question `id` against its rules, if it has any.
+    pub(crate) fn check_answer(&self, id: &str, answer: &str) -> Result<(), String> {
+        self.questions
+            .get(id)
+            .map_or(Ok(()), |question| question.check(id, answer))
+    }
+}
+
+impl QuestionTemplate {
+    fn check(&self, id: &str, answer: &str) -> Result<(), String> {
+        let answer = answer.trim();
+        let length = answer.chars().count();
+        if let Some(min) = self.min_length
+            && length < min
+        {
+            return Err(format!("{id} must be at least {min} characters"));
+        }
+        if let Some(max) = self.max_length
+            && length > max
+        {
+            return Err(format!("{id} must be at most {max} characters"));
+        }
+        if !self.one_of.is_empty()
+            && !self
+                .one_of
+                .iter()
+                .any(|choice| choice.trim().eq_ignore_ascii_case(answer))
+        {
+            return Err(format!("{id} must be one of: {}", self.one_of.join(", ")));
+        }
+        Ok(())
+    }
+}
+
+/// Load template `name` from `CODEX_HOME/capture_templates`, or the built-in
+/// template when no name is given.
+pub(crate) fn load_capture_template(
+    codex_home: &Path,
+    name: Option<&str>,
+) -> anyhow::Result<CaptureTemplate> {
+    let Some(name) = name.map(str::trim).filter(|name| !name.is_empty()) else {
+        return Ok(CaptureTemplate::default());
+    };
+    if !name
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+    {
+        anyhow::bail!("capture template names may only use letters, digits, '-' and '_'");
+    }
+    let path = codex_home
+        .join(CAPTURE_TEMPLATES_DIR)
+        .join(format!("{name}.toml"));
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            anyhow::bail!(
+                "unknown capture template '{name}'; expected {}",
+                path.display()
+            );
+        }
+        Err(err) => return Err(anyhow::anyhow!("failed to read {}: {err}", path.display())),
+    };
+    parse_capture_template(&contents).with_context(|| format!("invalid {}", path.display()))
+}
+
+fn parse_capture_template(contents: &str) -> anyhow::Result<CaptureTemplate> {
+    let template: CaptureTemplate = toml::from_str(contents)?;
+    template.validate().map_err(anyhow::Error::msg)?;
+    Ok(template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const INCIDENT: &str = r#"
+[hypotheses]
+min = 2
+max = 4
+
+[hypotheses.questions.statement]
+question = "What could have caused the incident?"
+min_length = 10
+
+[test_results.questions.result]
+one_of = ["pass", "fail"]
+"#;
+
+    fn answers(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(id, answer)| ((*id).to_string(), (*answer).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn templates_override_wording_counts_and_answer_rules() {
+        let template = parse_capture_template(INCIDENT).expect("parse template");
+        let hypotheses = template.section(CaptureSection::Hypotheses);
+        let results = template.section(CaptureSection::TestResults);
+
+        assert_eq!(
+            (
+                template.count_range(CaptureSection::Hypotheses),
+                template.count_range(CaptureSection::Tests),
+                hypotheses.question("statement", "Hypothesis statement"),
+                hypotheses.question("probability", "Prior probability"),
+            ),
+            (
+                (2, 4),
+                (1, 10),
+                "What could have caused the incident?".to_string(),
+                "Prior probability".to_string(),
+            )
+        );
+        assert_eq!(
+            [
+                hypotheses.check(&answers(&[("statement", "disk full")])),
+                hypotheses.check(&answers(&[("statement", "disk full on db-1")])),
+                results.check(&answers(&[("result", " FAIL ")])),
+                results.check(&answers(&[("result", "inconclusive")])),
+            ],
+            [
+                Err("statement must be at least 10 characters".to_string()),
+                Ok(()),
+                Ok(()),
+                Err("result must be one of: pass, fail".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        let errors = [
+            "[tests.questions.steps]\nquestion = \"Steps\"",
+            "[intent]\nmin = 1",
+            "[outcomes]\nmin = 3\nmax = 2",
+            "[tests]\nmin = 0",
+            "[hypotheses]\nminimum = 2",
+        ]
+        .into_iter()
+        .map(|contents| {
+            parse_capture_template(contents).err().map(|err| {
+                err.to_string()
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string()
+            })
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            errors[..4],
+            [
+                Some("section 'tests' has no question 'steps'".to_string()),
+                Some("section 'intent' does not repeat; remove min and max".to_string()),
+                Some("section 'outcomes': min 3 is greater than max 2".to_string()),
+                Some("section 'tests' needs a min of at least 1".to_string()),
+            ]
+        );
+        assert!(errors[4].is_some(), "unknown keys should be rejected");
+    }
+
+    #[test]
+    fn templates_load_by_name_from_codex_home() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let dir = codex_home.path().join(CAPTURE_TEMPLATES_DIR);
+        std::fs::create_dir_all(&dir).expect("create templates dir");
+        std::fs::write(dir.join("incident.toml"), INCIDENT).expect("write template");
+
+        let incident =
+            load_capture_template(codex_home.path(), Some("incident")).expect("load incident");
+        let missing = load_capture_template(codex_home.path(), Some("postmortem"))
+            .expect_err("missing template");
+
+        assert_eq!(
+            (
+                incident.count_range(CaptureSection::Hypotheses),
+                load_capture_template(codex_home.path(), None).expect("load default"),
+                missing.to_string(),
+                load_capture_template(codex_home.path(), Some("../incident")).is_err(),
+            ),
+            (
+                (2, 4),
+                CaptureTemplate::default(),
+                format!(
+                    "unknown capture template 'postmortem'; expected {}",
+                    dir.join("postmortem.toml").display()
+                ),
+                true,
+            )
+        );
+    }
+}
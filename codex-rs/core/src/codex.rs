@@ -4,6 +4,7 @@ use std::fmt::Debug;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
+use std::time::Instant;
 
 use crate::AuthManager;
 use crate::CodexAuth;
@@ -20,7 +21,10 @@ use crate::compact::should_use_remote_compact_task;
 use crate::compact_remote::run_inline_remote_auto_compact_task;
 use crate::connectors;
 use crate::covenant::CovenantAction;
-use crate::covenant::load_covenant;
+use crate::covenant::CovenantDecision;
+use crate::covenant::CovenantEnforcement;
+use crate::covenant::decide_covenant;
+use crate::covenant_store::FsStore;
 use crate::exec_policy::ExecPolicyManager;
 use crate::features::FEATURES;
 use crate::features::Feature;
@@ -57,6 +61,8 @@ use codex_protocol::mcp::CallToolResult;
 use codex_protocol::models::BaseInstructions;
 use codex_protocol::models::format_allow_prefixes;
 use codex_protocol::openai_models::ModelInfo;
+use codex_protocol::protocol::ApprovalDecisionEvent;
+use codex_protocol::protocol::ApprovalKind;
 use codex_protocol::protocol::FileChange;
 use codex_protocol::protocol::HasLegacyEvent;
 use codex_protocol::protocol::ItemCompletedEvent;
@@ -74,6 +80,9 @@ use codex_protocol::request_user_input::RequestUserInputResponse;
 use codex_rmcp_client::ElicitationResponse;
 use codex_rmcp_client::OAuthCredentialsStoreMode;
 use codex_state::AuditAction;
+use codex_state::CovenantCheck;
+use codex_state::CovenantGrant;
+use codex_state::PatternNamespaces;
 use futures::future::BoxFuture;
 use futures::prelude::*;
 use futures::stream::FuturesOrdered;
@@ -144,6 +153,8 @@ use crate::mentions::build_connector_slug_counts;
 use crate::mentions::build_skill_name_counts;
 use crate::mentions::collect_explicit_app_paths;
 use crate::mentions::collect_tool_mentions_from_messages;
+use crate::pattern_expiry;
+use crate::pattern_suggestions;
 use crate::project_doc::get_user_instructions;
 use crate::proposed_plan_parser::ProposedPlanParser;
 use crate::proposed_plan_parser::ProposedPlanSegment;
@@ -164,6 +175,7 @@ use crate::protocol::PlanDeltaEvent;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::ReasoningContentDeltaEvent;
 use crate::protocol::ReasoningRawContentDeltaEvent;
+use crate::protocol::RequestUserInputClosedEvent;
 use crate::protocol::RequestUserInputEvent;
 use crate::protocol::ReviewDecision;
 use crate::protocol::SandboxPolicy;
@@ -198,6 +210,7 @@ use crate::skills::injection::app_id_from_path;
 use crate::skills::injection::tool_kind_for_path;
 use crate::skills::resolve_skill_dependencies_for_turn;
 use crate::state::ActiveTurn;
+use crate::state::PendingApproval;
 use crate::state::SessionServices;
 use crate::state::SessionState;
 use crate::state_db;
@@ -298,6 +311,12 @@ impl Codex {
             .await
             .map_err(|err| CodexErr::Fatal(format!("failed to load rules: {err}")))?;
 
+        // Pattern suggestions and turn intents tokenize with these settings.
+        if let Err(err) = crate::pattern_match::install_text_normalizer(config.codex_home.as_path())
+        {
+            warn!("ignoring tokenizer settings: {err:#}");
+        }
+
         let config = Arc::new(config);
         let _ = models_manager
             .list_models(
@@ -771,6 +790,48 @@ impl Session {
         });
     }
 
+    /// List the stored patterns that have gone stale so clients can offer to
+    /// re-confirm or archive them.
+    fn start_stale_pattern_check(
+        self: &Arc<Self>,
+        stale_after_days: u64,
+        cwd: PathBuf,
+        scope: String,
+    ) {
+        if stale_after_days == 0 {
+            return;
+        }
+        let weak_sess = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let Some(sess) = weak_sess.upgrade() else {
+                return;
+            };
+            let Some(state_db) = sess.services.covenant_db.as_deref() else {
+                return;
+            };
+            match pattern_expiry::stale_patterns_event(
+                state_db,
+                &sess.services.pattern_namespaces,
+                cwd.as_path(),
+                scope.as_str(),
+                stale_after_days,
+                chrono::Utc::now().timestamp(),
+            )
+            .await
+            {
+                Ok(Some(event)) => {
+                    let event = Event {
+                        id: sess.next_internal_sub_id(),
+                        msg: EventMsg::StalePatterns(event),
+                    };
+                    sess.send_event_raw(event).await;
+                }
+                Ok(None) => {}
+                Err(err) => warn!("failed to check for stale patterns: {err:#}"),
+            }
+        });
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn make_turn_context(
         auth_manager: Option<Arc<AuthManager>>,
@@ -797,7 +858,8 @@ impl Session {
             model_info: &model_info,
             features: &per_turn_config.features,
             web_search_mode: per_turn_config.web_search_mode,
-        });
+        })
+        .with_capture_tool_enabled(per_turn_config.capture_tool_enabled);
 
         let cwd = session_configuration.cwd.clone();
         TurnContext {
@@ -936,6 +998,14 @@ impl Session {
             error!("failed to initialize rollout recorder: {e:#}");
             e
         })?;
+        let covenant_db = state_db::covenant_store_db(&config, state_db_ctx.clone()).await;
+        let workspace_store = match (&covenant_db, &state_db_ctx) {
+            (Some(covenant_db), Some(state_db)) => !Arc::ptr_eq(covenant_db, state_db),
+            (Some(_), None) | (None, _) => false,
+        };
+        let pattern_suggestions_db = covenant_db
+            .clone()
+            .filter(|_| !workspace_store || config.active_project.is_trusted());
         let rollout_path = rollout_recorder
             .as_ref()
             .map(|rec| rec.rollout_path.clone());
@@ -1056,6 +1126,10 @@ impl Session {
             file_watcher,
             agent_control,
             state_db: state_db_ctx.clone(),
+            covenant_store: Arc::new(FsStore::new(covenant_db.clone())),
+            covenant_db,
+            pattern_suggestions_db,
+            pattern_namespaces: PatternNamespaces::default(),
             model_client: ModelClient::new(
                 Some(Arc::clone(&auth_manager)),
                 conversation_id,
@@ -1109,6 +1183,11 @@ impl Session {
 
         // Start the watcher after SessionConfigured so it cannot emit earlier events.
         sess.start_file_watcher_listener();
+        sess.start_stale_pattern_check(
+            config.pattern_suggestions.stale_after_days,
+            session_configuration.cwd.clone(),
+            session_configuration.session_source.to_string(),
+        );
 
         // Construct sandbox_state before initialize() so it can be sent to each
         // MCP server immediately after it becomes ready (avoiding blocking).
@@ -1148,6 +1227,20 @@ impl Session {
         self.services.state_db.clone()
     }
 
+    /// The store covenant events and patterns are read from and written to.
+    pub(crate) fn covenant_db(&self) -> Option<state_db::StateDbHandle> {
+        self.services.covenant_db.clone()
+    }
+
+    /// The rollout file this session is recorded to, unless persistence is
+    /// disabled.
+    pub(crate) async fn rollout_path(&self) -> Option<PathBuf> {
+        let guard = self.services.rollout.lock().await;
+        guard
+            .as_ref()
+            .map(|recorder| recorder.rollout_path().to_path_buf())
+    }
+
     /// Ensure all rollout writes are durably flushed.
     pub(crate) async fn flush_rollout(&self) {
         let recorder = {
@@ -1725,6 +1818,25 @@ impl Session {
         ))
     }
 
+    /// Whether the covenant grants `action` in the turn's scope, without
+    /// auditing it. The check is counted like any other.
+    pub(crate) async fn covenant_allows(
+        &self,
+        turn_context: &TurnContext,
+        action: CovenantAction,
+    ) -> anyhow::Result<bool> {
+        let capability = action.as_capability();
+        let (covenant_version, decision) = self.covenant_decision(turn_context, capability).await;
+        self.record_covenant_check(
+            turn_context,
+            capability,
+            &covenant_version,
+            matches!(decision, Ok(true)),
+        )
+        .await;
+        decision
+    }
+
     pub(crate) async fn audit_covenant_action(
         &self,
         turn_context: &TurnContext,
@@ -1733,35 +1845,268 @@ impl Session {
         event_id: Option<&str>,
         intent_id: Option<&str>,
     ) -> anyhow::Result<bool> {
+        let capability = action.as_capability();
+        let (covenant_version, decision) = self.covenant_decision(turn_context, capability).await;
+        let allowed = matches!(decision, Ok(true));
+        self.record_covenant_check(turn_context, capability, &covenant_version, allowed)
+            .await;
+        self.insert_covenant_audit(
+            turn_context,
+            capability,
+            covenant_version,
+            actor,
+            event_id,
+            intent_id,
+        )
+        .await?;
+        decision
+    }
+
+    /// Whether covenant enforcement applies to the turn: `covenant.required`
+    /// is set, or a `covenant.json` is found from the turn's directory, even
+    /// one that cannot be loaded.
+    pub(crate) async fn covenant_in_force(&self, turn_context: &TurnContext) -> bool {
+        turn_context.config.covenant_required
+            || !matches!(
+                self.services
+                    .covenant_store
+                    .find_covenant(turn_context.cwd.as_path())
+                    .await,
+                Ok(None)
+            )
+    }
+
+    /// Gate an agent proposal on the covenant before any approval flow runs.
+    /// Proposals the covenant does not grant are escalated to the user when
+    /// `can_escalate` is set and refused otherwise. Ungranted decisions are
+    /// audited as `<capability>.escalated` or `<capability>.refused`. Unlike
+    /// [`Self::audit_covenant_action`], a failed audit write does not block the
+    /// proposal, since every command and patch passes through this gate.
+    pub(crate) async fn enforce_covenant_proposal(
+        &self,
+        turn_context: &TurnContext,
+        action: CovenantAction,
+        call_id: &str,
+        can_escalate: bool,
+    ) -> anyhow::Result<CovenantEnforcement> {
+        let capability = action.as_capability();
+        let (covenant_version, decision) = self.covenant_decision(turn_context, capability).await;
+        let enforcement = match &decision {
+            Ok(granted) => CovenantEnforcement::decide(*granted, can_escalate),
+            Err(_) => CovenantEnforcement::Refused,
+        };
+        self.record_covenant_check(
+            turn_context,
+            capability,
+            &covenant_version,
+            enforcement == CovenantEnforcement::Allowed,
+        )
+        .await;
+        let action_type = match enforcement {
+            CovenantEnforcement::Allowed => capability.to_string(),
+            CovenantEnforcement::Escalated | CovenantEnforcement::Refused => {
+                format!("{capability}.{}", enforcement.as_str())
+            }
+        };
+        if let Err(err) = self
+            .insert_covenant_audit(
+                turn_context,
+                action_type.as_str(),
+                covenant_version,
+                "agent",
+                Some(call_id),
+                Some(turn_context.sub_id.as_str()),
+            )
+            .await
+        {
+            warn!("failed to audit {action_type}: {err}");
+        }
+        decision?;
+        Ok(enforcement)
+    }
+
+    /// Count a check of `capability` towards `codex covenant usage`. Checks
+    /// made without a covenant or `covenant.required` enforce nothing and are
+    /// not counted. A failed write is logged rather than failing the action.
+    async fn record_covenant_check(
+        &self,
+        turn_context: &TurnContext,
+        capability: &str,
+        covenant_version: &str,
+        allowed: bool,
+    ) {
+        if covenant_version == "missing" && !turn_context.config.covenant_required {
+            return;
+        }
+        let check = CovenantCheck {
+            timestamp: chrono::Utc::now().timestamp(),
+            scope: turn_context.session_source.to_string(),
+            capability: capability.to_string(),
+            allowed,
+        };
+        if let Err(err) = self
+            .services
+            .covenant_store
+            .record_covenant_check(&check)
+            .await
+        {
+            warn!("failed to count covenant check of {capability}: {err}");
+        }
+    }
+
+    /// The loaded covenant's version and whether it grants `capability` in the
+    /// turn's scope.
+    async fn covenant_decision(
+        &self,
+        turn_context: &TurnContext,
+        capability: &str,
+    ) -> (String, anyhow::Result<bool>) {
         let scope = turn_context.session_source.to_string();
+        let found = self
+            .services
+            .covenant_store
+            .find_covenant(turn_context.cwd.as_path())
+            .await;
+        let (covenant_version, decision) = decide_covenant(
+            found,
+            turn_context.cwd.as_path(),
+            scope.as_str(),
+            capability,
+            turn_context.config.covenant_required,
+        );
+        // A live grant from `covenant_request` covers what the covenant does not.
+        let decision = match decision {
+            Ok(CovenantDecision::Granted) => Ok(true),
+            Ok(CovenantDecision::Denied) => Ok(self
+                .covenant_grant_expiry(turn_context, capability)
+                .await
+                .is_some()),
+            Err(err) => Err(err),
+        };
+        (covenant_version, decision)
+    }
+
+    /// When this session's grant of `capability` expires, if one is live.
+    /// Grants that cannot be read are treated as absent.
+    pub(crate) async fn covenant_grant_expiry(
+        &self,
+        turn_context: &TurnContext,
+        capability: &str,
+    ) -> Option<i64> {
+        self.services
+            .covenant_store
+            .covenant_grant_expiry(
+                self.conversation_id.to_string().as_str(),
+                turn_context.session_source.to_string().as_str(),
+                capability,
+                chrono::Utc::now().timestamp(),
+            )
+            .await
+            .unwrap_or_else(|err| {
+                warn!("failed to read covenant grants for {capability}: {err}");
+                None
+            })
+    }
+
+    /// Record the user's answer to a `covenant_request`. A grant until
+    /// `expires_at` is stored for this session and audited as
+    /// `<capability>.granted`; a refusal is audited as `<capability>.denied`.
+    pub(crate) async fn record_covenant_request(
+        &self,
+        turn_context: &TurnContext,
+        action: CovenantAction,
+        call_id: &str,
+        expires_at: Option<i64>,
+    ) -> anyhow::Result<()> {
         let capability = action.as_capability();
-        let (covenant_version, allowed) = match load_covenant(turn_context.cwd.as_path()).await {
-            Ok(covenant) => (
-                covenant.version.clone(),
-                covenant.allows(scope.as_str(), capability),
-            ),
-            Err(err) => {
-                warn!(
-                    "failed to load covenant.json from {}: {err}",
-                    turn_context.cwd.display()
-                );
-                ("missing".to_string(), false)
+        let action_type = match expires_at {
+            Some(expires_at) => {
+                self.services
+                    .covenant_store
+                    .insert_covenant_grant(&CovenantGrant {
+                        thread_id: self.conversation_id.to_string(),
+                        scope: turn_context.session_source.to_string(),
+                        capability: capability.to_string(),
+                        granted_at: chrono::Utc::now().timestamp(),
+                        expires_at,
+                    })
+                    .await?;
+                format!("{capability}.granted")
             }
+            None => format!("{capability}.denied"),
         };
+        let (covenant_version, _) = self.covenant_decision(turn_context, capability).await;
+        self.insert_covenant_audit(
+            turn_context,
+            action_type.as_str(),
+            covenant_version,
+            "user",
+            Some(call_id),
+            Some(turn_context.sub_id.as_str()),
+        )
+        .await
+    }
+
+    async fn insert_covenant_audit(
+        &self,
+        turn_context: &TurnContext,
+        action_type: &str,
+        covenant_version: String,
+        actor: &str,
+        event_id: Option<&str>,
+        intent_id: Option<&str>,
+    ) -> anyhow::Result<()> {
         let audit_action = AuditAction {
             timestamp: chrono::Utc::now().timestamp(),
             actor: actor.to_string(),
-            action_type: capability.to_string(),
-            scope,
-            covenant_version: covenant_version.clone(),
+            action_type: action_type.to_string(),
+            scope: turn_context.session_source.to_string(),
+            covenant_version,
             event_id: event_id.map(ToString::to_string),
             intent_id: intent_id.map(ToString::to_string),
         };
-        let Some(state_db) = self.services.state_db.as_ref() else {
-            return Err(anyhow::anyhow!("state db unavailable for audit logging"));
+        self.services
+            .covenant_store
+            .insert_audit_action(&audit_action)
+            .await
+    }
+
+    /// With `[pattern_suggestions] after_failed_turns = true`, show the stored
+    /// pattern that best matches a turn that ended in `failure` as a warning.
+    pub(crate) async fn warn_with_pattern_after_failure(
+        &self,
+        turn_context: &TurnContext,
+        failure: &str,
+    ) {
+        let config = &turn_context.config.pattern_suggestions;
+        if !config.after_failed_turns {
+            return;
+        }
+        let Some(state_db) = self.services.covenant_db.as_deref() else {
+            return;
+        };
+        let history = self.clone_history().await;
+        let Some(message) = pattern_suggestions::last_user_message(history.raw_items()) else {
+            return;
         };
-        state_db.insert_audit_action(&audit_action).await?;
-        Ok(allowed)
+        match pattern_suggestions::failed_turn_warning(
+            state_db,
+            &self.services.pattern_namespaces,
+            turn_context.cwd.as_path(),
+            turn_context.session_source.to_string().as_str(),
+            message.as_str(),
+            failure,
+            config,
+        )
+        .await
+        {
+            Ok(Some(message)) => {
+                self.send_event(turn_context, EventMsg::Warning(WarningEvent { message }))
+                    .await;
+            }
+            Ok(None) => {}
+            Err(err) => warn!("failed to match patterns for the failed turn: {err:#}"),
+        }
     }
 
     pub(crate) async fn record_execpolicy_amendment_message(
@@ -1809,42 +2154,22 @@ impl Session {
         reason: Option<String>,
         proposed_execpolicy_amendment: Option<ExecPolicyAmendment>,
     ) -> ReviewDecision {
-        let capability = CovenantAction::ProposalExecCommand.as_capability();
-        let allowed = match self
-            .audit_covenant_action(
-                turn_context,
-                CovenantAction::ProposalExecCommand,
-                "agent",
-                Some(call_id.as_str()),
-                Some(turn_context.sub_id.as_str()),
-            )
-            .await
-        {
-            Ok(allowed) => allowed,
-            Err(err) => {
-                let message = format!("covenant audit failed for {capability}: {err}");
-                self.send_event(turn_context, EventMsg::Warning(WarningEvent { message }))
-                    .await;
-                return ReviewDecision::Denied;
-            }
-        };
-        if !allowed {
-            let message = format!("covenant scope disallows {capability}");
-            self.send_event(turn_context, EventMsg::Warning(WarningEvent { message }))
-                .await;
-            return ReviewDecision::Denied;
-        }
-
         let sub_id = turn_context.sub_id.clone();
         // Add the tx_approve callback to the map before sending the request.
         let (tx_approve, rx_approve) = oneshot::channel();
         let event_id = sub_id.clone();
+        let approval = PendingApproval {
+            tx: tx_approve,
+            call_id: call_id.clone(),
+            kind: ApprovalKind::Exec,
+            requested_at: Instant::now(),
+        };
         let prev_entry = {
             let mut active = self.active_turn.lock().await;
             match active.as_mut() {
                 Some(at) => {
                     let mut ts = at.turn_state.lock().await;
-                    ts.insert_pending_approval(sub_id, tx_approve)
+                    ts.insert_pending_approval(sub_id, approval)
                 }
                 None => None,
             }
@@ -1875,46 +2200,22 @@ impl Session {
         reason: Option<String>,
         grant_root: Option<PathBuf>,
     ) -> oneshot::Receiver<ReviewDecision> {
-        let capability = CovenantAction::ProposalApplyPatch.as_capability();
-        let allowed = match self
-            .audit_covenant_action(
-                turn_context,
-                CovenantAction::ProposalApplyPatch,
-                "agent",
-                Some(call_id.as_str()),
-                Some(turn_context.sub_id.as_str()),
-            )
-            .await
-        {
-            Ok(allowed) => allowed,
-            Err(err) => {
-                let message = format!("covenant audit failed for {capability}: {err}");
-                self.send_event(turn_context, EventMsg::Warning(WarningEvent { message }))
-                    .await;
-                let (tx_approve, rx_approve) = oneshot::channel();
-                let _ = tx_approve.send(ReviewDecision::Denied);
-                return rx_approve;
-            }
-        };
-        if !allowed {
-            let message = format!("covenant scope disallows {capability}");
-            self.send_event(turn_context, EventMsg::Warning(WarningEvent { message }))
-                .await;
-            let (tx_approve, rx_approve) = oneshot::channel();
-            let _ = tx_approve.send(ReviewDecision::Denied);
-            return rx_approve;
-        }
-
         let sub_id = turn_context.sub_id.clone();
         // Add the tx_approve callback to the map before sending the request.
         let (tx_approve, rx_approve) = oneshot::channel();
         let event_id = sub_id.clone();
+        let approval = PendingApproval {
+            tx: tx_approve,
+            call_id: call_id.clone(),
+            kind: ApprovalKind::Patch,
+            requested_at: Instant::now(),
+        };
         let prev_entry = {
             let mut active = self.active_turn.lock().await;
             match active.as_mut() {
                 Some(at) => {
                     let mut ts = at.turn_state.lock().await;
-                    ts.insert_pending_approval(sub_id, tx_approve)
+                    ts.insert_pending_approval(sub_id, approval)
                 }
                 None => None,
             }
@@ -1966,6 +2267,29 @@ impl Session {
         rx_response.await.ok()
     }
 
+    /// Stop waiting for the pending user input of `turn_context` and tell the
+    /// client to close the request made for `call_id`; a response that
+    /// arrives afterwards is ignored.
+    pub(crate) async fn cancel_user_input(&self, turn_context: &TurnContext, call_id: &str) {
+        let removed = {
+            let mut active = self.active_turn.lock().await;
+            match active.as_mut() {
+                Some(at) => {
+                    let mut ts = at.turn_state.lock().await;
+                    ts.remove_pending_user_input(&turn_context.sub_id)
+                }
+                None => None,
+            }
+        };
+        if removed.is_some() {
+            let event = EventMsg::RequestUserInputClosed(RequestUserInputClosedEvent {
+                call_id: call_id.to_string(),
+                turn_id: turn_context.sub_id.clone(),
+            });
+            self.send_event(turn_context, event).await;
+        }
+    }
+
     pub async fn notify_user_input_response(
         &self,
         sub_id: &str,
@@ -2023,14 +2347,24 @@ impl Session {
                 None => None,
             }
         };
-        match entry {
-            Some(tx_approve) => {
-                tx_approve.send(decision).ok();
-            }
-            None => {
-                warn!("No pending approval found for sub_id: {sub_id}");
-            }
-        }
+        let Some(approval) = entry else {
+            warn!("No pending approval found for sub_id: {sub_id}");
+            return;
+        };
+        let wait_ms =
+            u64::try_from(approval.requested_at.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let event = ApprovalDecisionEvent {
+            call_id: approval.call_id,
+            turn_id: sub_id.to_string(),
+            kind: approval.kind,
+            decision: decision.clone(),
+            wait_ms,
+        };
+        approval.tx.send(decision).ok();
+        // Only recorded, so `codex stats` can measure approval friction;
+        // clients already know what they decided.
+        self.persist_rollout_items(&[RolloutItem::EventMsg(EventMsg::ApprovalDecision(event))])
+            .await;
     }
 
     pub async fn resolve_elicitation(
@@ -2813,6 +3147,15 @@ async fn submission_loop(sess: Arc<Session>, config: Arc<Config>, rx_sub: Receiv
                 )
                 .await;
             }
+            Op::QueryPatterns { request } => {
+                handlers::query_patterns(&sess, &config, sub.id.clone(), request).await;
+            }
+            Op::ReviewStalePatterns {
+                pattern_ids,
+                action,
+            } => {
+                handlers::review_stale_patterns(&sess, sub.id.clone(), pattern_ids, action).await;
+            }
             Op::Undo => {
                 handlers::undo(&sess, sub.id.clone()).await;
             }
@@ -2887,11 +3230,13 @@ mod handlers {
     use codex_protocol::protocol::ListSkillsResponseEvent;
     use codex_protocol::protocol::McpServerRefreshConfig;
     use codex_protocol::protocol::Op;
+    use codex_protocol::protocol::PatternQueryRequest;
     use codex_protocol::protocol::RemoteSkillDownloadedEvent;
     use codex_protocol::protocol::RemoteSkillSummary;
     use codex_protocol::protocol::ReviewDecision;
     use codex_protocol::protocol::ReviewRequest;
     use codex_protocol::protocol::SkillsListEntry;
+    use codex_protocol::protocol::StalePatternAction;
     use codex_protocol::protocol::ThreadNameUpdatedEvent;
     use codex_protocol::protocol::ThreadRolledBackEvent;
     use codex_protocol::protocol::TurnAbortReason;
@@ -3419,6 +3764,77 @@ mod handlers {
         }
     }
 
+    pub async fn query_patterns(
+        sess: &Session,
+        config: &Arc<Config>,
+        sub_id: String,
+        request: PatternQueryRequest,
+    ) {
+        let response = match sess.services.covenant_db.as_deref() {
+            Some(state_db) => {
+                let (cwd, scope) = {
+                    let state = sess.state.lock().await;
+                    (
+                        state.session_configuration.cwd.clone(),
+                        state.session_configuration.session_source.to_string(),
+                    )
+                };
+                crate::pattern_query::query_patterns(
+                    state_db,
+                    &sess.services.pattern_namespaces,
+                    cwd.as_path(),
+                    scope.as_str(),
+                    config.codex_home.as_path(),
+                    &request,
+                )
+                .await
+            }
+            None => Err(anyhow::anyhow!("the state database is not available")),
+        };
+
+        let msg = match response {
+            Ok(response) => EventMsg::PatternQueryResponse(response),
+            Err(err) => EventMsg::Error(ErrorEvent {
+                message: format!("failed to query patterns: {err:#}"),
+                codex_error_info: Some(CodexErrorInfo::Other),
+            }),
+        };
+        sess.send_event_raw(Event { id: sub_id, msg }).await;
+    }
+
+    pub async fn review_stale_patterns(
+        sess: &Session,
+        sub_id: String,
+        pattern_ids: Vec<String>,
+        action: StalePatternAction,
+    ) {
+        let Some(state_db) = sess.services.covenant_db.as_deref() else {
+            let msg = EventMsg::Error(ErrorEvent {
+                message: "failed to review stale patterns: the state database is not available"
+                    .to_string(),
+                codex_error_info: Some(CodexErrorInfo::Other),
+            });
+            sess.send_event_raw(Event { id: sub_id, msg }).await;
+            return;
+        };
+        let now = chrono::Utc::now().timestamp();
+        let result = match action {
+            StalePatternAction::Confirm => {
+                state_db.confirm_covenant_patterns(&pattern_ids, now).await
+            }
+            StalePatternAction::Archive => {
+                state_db.archive_covenant_patterns(&pattern_ids, now).await
+            }
+        };
+        if let Err(err) = result {
+            let msg = EventMsg::Error(ErrorEvent {
+                message: format!("failed to review stale patterns: {err:#}"),
+                codex_error_info: Some(CodexErrorInfo::Other),
+            });
+            sess.send_event_raw(Event { id: sub_id, msg }).await;
+        }
+    }
+
     pub async fn undo(sess: &Arc<Session>, sub_id: String) {
         let turn_context = sess.new_default_turn_with_sub_id(sub_id).await;
         sess.spawn_task(turn_context, Vec::new(), UndoTask::new())
@@ -3656,7 +4072,8 @@ async fn spawn_review_thread(
         model_info: &review_model_info,
         features: &review_features,
         web_search_mode: Some(review_web_search_mode),
-    });
+    })
+    .with_capture_tool_enabled(config.capture_tool_enabled);
 
     let review_prompt = resolved.prompt.clone();
     let provider = parent_turn_context.provider.clone();
@@ -3908,6 +4325,27 @@ pub(crate) async fn run_turn(
             .await;
     }
 
+    if config.pattern_suggestions.enabled
+        && let Some(state_db) = sess.services.pattern_suggestions_db.as_deref()
+    {
+        match pattern_suggestions::known_patterns_item(
+            state_db,
+            &sess.services.pattern_namespaces,
+            turn_context.cwd.as_path(),
+            turn_context.session_source.to_string().as_str(),
+            &input,
+            &config.pattern_suggestions,
+        )
+        .await
+        {
+            Ok(Some(item)) => {
+                sess.record_conversation_items(&turn_context, &[item]).await;
+            }
+            Ok(None) => {}
+            Err(err) => warn!("failed to load known patterns: {err:#}"),
+        }
+    }
+
     sess.maybe_start_ghost_snapshot(Arc::clone(&turn_context), cancellation_token.child_token())
         .await;
     let mut last_agent_message: Option<String> = None;
@@ -4050,6 +4488,8 @@ pub(crate) async fn run_turn(
                 info!("Turn error: {e:#}");
                 let event = EventMsg::Error(e.to_error_event(None));
                 sess.send_event(&turn_context, event).await;
+                sess.warn_with_pattern_after_failure(&turn_context, e.to_string().as_str())
+                    .await;
                 // let the user continue the conversation
                 break;
             }
@@ -5970,6 +6410,10 @@ mod tests {
             file_watcher,
             agent_control,
             state_db: None,
+            covenant_store: Arc::new(FsStore::new(None)),
+            covenant_db: None,
+            pattern_suggestions_db: None,
+            pattern_namespaces: PatternNamespaces::default(),
             model_client: ModelClient::new(
                 Some(auth_manager.clone()),
                 conversation_id,
@@ -6100,6 +6544,10 @@ mod tests {
             file_watcher,
             agent_control,
             state_db: None,
+            covenant_store: Arc::new(FsStore::new(None)),
+            covenant_db: None,
+            pattern_suggestions_db: None,
+            pattern_namespaces: PatternNamespaces::default(),
             model_client: ModelClient::new(
                 Some(Arc::clone(&auth_manager)),
                 conversation_id,
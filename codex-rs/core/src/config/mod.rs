@@ -1,7 +1,10 @@
 use crate::auth::AuthCredentialsStoreMode;
+use crate::capture_numbers::locale_decimal_separator;
 use crate::config::edit::ConfigEdit;
 use crate::config::edit::ConfigEditsBuilder;
+use crate::config::types::CovenantRetentionConfig;
 use crate::config::types::DEFAULT_OTEL_ENVIRONMENT;
+use crate::config::types::DecimalSeparator;
 use crate::config::types::History;
 use crate::config::types::McpServerConfig;
 use crate::config::types::McpServerDisabledReason;
@@ -69,6 +72,7 @@ use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 #[cfg(test)]
 use tempfile::tempdir;
 
@@ -96,6 +100,7 @@ pub use codex_git::GhostSnapshotConfig;
 /// the context window.
 pub(crate) const PROJECT_DOC_MAX_BYTES: usize = 32 * 1024; // 32 KiB
 pub(crate) const DEFAULT_AGENT_MAX_THREADS: Option<usize> = Some(6);
+pub(crate) const DEFAULT_CAPTURE_MAX_BAYES_FACTOR: f64 = 100.0;
 
 pub const CONFIG_TOML_FILE: &str = "config.toml";
 
@@ -373,6 +378,33 @@ pub struct Config {
     /// Defaults to `true`.
     pub feedback_enabled: bool,
 
+    /// Whether the `capture` tool is offered (`tools.capture.enabled`).
+    pub capture_tool_enabled: bool,
+
+    /// Locale of the capture tool's prompts. `None` uses the built-in
+    /// English prompts.
+    pub capture_locale: Option<String>,
+
+    /// Bayes factor above which (or below whose inverse) a capture's
+    /// probability update is flagged as implausibly large.
+    pub capture_max_bayes_factor: f64,
+
+    /// How long the capture tool waits for each group of questions. `None`
+    /// waits indefinitely.
+    pub capture_prompt_timeout: Option<Duration>,
+
+    /// When `true`, covenant enforcement is deny-by-default: `covenant.json`
+    /// must load and only scopes it declares are granted anything.
+    /// Defaults to `false`.
+    pub covenant_required: bool,
+
+    /// Limits `codex covenant gc` applies when archiving resolved events.
+    pub covenant_retention: crate::config::types::CovenantRetentionConfig,
+
+    /// Which stored patterns, if any, are added to a turn's context when they
+    /// match the user message. Disabled by default.
+    pub pattern_suggestions: crate::config::types::PatternSuggestionsConfig,
+
     /// OTEL configuration (exporter type, endpoint, headers, etc.).
     pub otel: crate::config::types::OtelConfig,
 }
@@ -1024,6 +1056,15 @@ pub struct ConfigToml {
     /// Defaults to `true`.
     pub feedback: Option<crate::config::types::FeedbackConfigToml>,
 
+    /// Capture tool settings.
+    pub capture: Option<crate::config::types::CaptureConfigToml>,
+
+    /// Covenant enforcement settings.
+    pub covenant: Option<crate::config::types::CovenantConfigToml>,
+
+    /// Known-pattern injection settings.
+    pub pattern_suggestions: Option<crate::config::types::PatternSuggestionsConfigToml>,
+
     /// OTEL configuration.
     pub otel: Option<crate::config::types::OtelConfigToml>,
 
@@ -1095,6 +1136,10 @@ pub struct ToolsToml {
     /// Enable the `view_image` tool that lets the agent attach local images.
     #[serde(default)]
     pub view_image: Option<bool>,
+
+    /// Settings for the interactive `capture` tool.
+    #[serde(default)]
+    pub capture: Option<crate::config::types::CaptureToolToml>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, JsonSchema)]
@@ -1740,6 +1785,47 @@ impl Config {
                 .as_ref()
                 .and_then(|feedback| feedback.enabled)
                 .unwrap_or(true),
+            capture_tool_enabled: cfg
+                .tools
+                .as_ref()
+                .and_then(|tools| tools.capture.as_ref())
+                .and_then(|capture| capture.enabled)
+                .unwrap_or(true),
+            capture_locale: cfg
+                .capture
+                .as_ref()
+                .and_then(|capture| capture.locale.clone())
+                .map(|locale| locale.trim().to_string())
+                .filter(|locale| !locale.is_empty()),
+            capture_max_bayes_factor: cfg
+                .capture
+                .as_ref()
+                .and_then(|capture| capture.max_bayes_factor)
+                .filter(|factor| *factor > 1.0)
+                .unwrap_or(DEFAULT_CAPTURE_MAX_BAYES_FACTOR),
+            capture_prompt_timeout: cfg
+                .capture
+                .as_ref()
+                .and_then(|capture| capture.prompt_timeout_secs)
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+            covenant_required: cfg
+                .covenant
+                .as_ref()
+                .and_then(|covenant| covenant.required)
+                .unwrap_or(false),
+            covenant_retention: cfg.covenant.as_ref().map_or_else(
+                CovenantRetentionConfig::default,
+                |covenant| CovenantRetentionConfig {
+                    max_event_age_days: covenant.max_event_age_days,
+                    max_store_mb: covenant.max_store_mb,
+                },
+            ),
+            pattern_suggestions: cfg
+                .pattern_suggestions
+                .clone()
+                .map(Into::into)
+                .unwrap_or_default(),
             tui_notifications: cfg
                 .tui
                 .as_ref()
@@ -1894,6 +1980,7 @@ mod tests {
     use crate::config::types::McpServerTransportConfig;
     use crate::config::types::NotificationMethod;
     use crate::config::types::Notifications;
+    use crate::config::types::PatternSuggestionsConfig;
     use crate::config_loader::RequirementSource;
     use crate::features::Feature;
 
@@ -2413,6 +2500,185 @@ trust_level = "trusted"
         Ok(())
     }
 
+    #[test]
+    fn capture_locale_reads_the_capture_table() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cfg = toml::from_str::<ConfigToml>(
+            "[capture]\nlocale = \" pt-BR \"\nmax_bayes_factor = 20.0\nprompt_timeout_secs = 300\n",
+        )
+        .expect("TOML deserialization should succeed");
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+        let default_config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(
+            (
+                config.capture_locale,
+                config.capture_max_bayes_factor,
+                config.capture_prompt_timeout,
+                default_config.capture_locale,
+                default_config.capture_max_bayes_factor,
+                default_config.capture_prompt_timeout,
+            ),
+            (
+                Some("pt-BR".to_string()),
+                20.0,
+                Some(Duration::from_secs(300)),
+                None,
+                DEFAULT_CAPTURE_MAX_BAYES_FACTOR,
+                None,
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn capture_numbers_follow_the_locale_unless_configured() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let load = |toml: &str| {
+            let cfg =
+                toml::from_str::<ConfigToml>(toml).expect("TOML deserialization should succeed");
+            Config::load_from_base_config_with_overrides(
+                cfg,
+                ConfigOverrides::default(),
+                codex_home.path().to_path_buf(),
+            )
+            .map(|config| {
+                (
+                    config.capture_decimal_separator,
+                    config.capture_strict_numbers,
+                )
+            })
+        };
+
+        assert_eq!(
+            (
+                load("")?,
+                load("[capture]\nlocale = \"pt-BR\"\n")?,
+                load("[capture]\nlocale = \"de\"\ndecimal_separator = \".\"\n")?,
+                load("[capture]\ndecimal_separator = \",\"\nstrict_numbers = true\n")?,
+            ),
+            (
+                (DecimalSeparator::Point, false),
+                (DecimalSeparator::Comma, false),
+                (DecimalSeparator::Point, false),
+                (DecimalSeparator::Comma, true),
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn capture_tool_is_enabled_unless_tools_capture_turns_it_off() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let load = |toml: &str| {
+            let cfg =
+                toml::from_str::<ConfigToml>(toml).expect("TOML deserialization should succeed");
+            Config::load_from_base_config_with_overrides(
+                cfg,
+                ConfigOverrides::default(),
+                codex_home.path().to_path_buf(),
+            )
+            .map(|config| config.capture_tool_enabled)
+        };
+
+        assert_eq!(
+            (
+                load("")?,
+                load("[tools]\nweb_search = true\n")?,
+                load("[tools.capture]\nenabled = false\n")?,
+            ),
+            (true, true, false)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn covenant_required_is_opt_in() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let default_config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+        let cfg = toml::from_str::<ConfigToml>("[covenant]\nrequired = true\n")
+            .expect("TOML deserialization should succeed");
+        let required_config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(
+            (
+                default_config.covenant_required,
+                required_config.covenant_required
+            ),
+            (false, true)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn covenant_retention_reads_the_covenant_table() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cfg = toml::from_str::<ConfigToml>("[covenant]\nmax_event_age_days = 90\n")
+            .expect("TOML deserialization should succeed");
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(
+            config.covenant_retention,
+            CovenantRetentionConfig {
+                max_event_age_days: Some(90),
+                max_store_mb: None,
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn pattern_suggestions_fill_unset_fields_with_defaults() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cfg = toml::from_str::<ConfigToml>(
+            "[pattern_suggestions]\nenabled = true\nmax_patterns = 5\nafter_failed_turns = true\n",
+        )
+        .expect("TOML deserialization should succeed");
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(
+            config.pattern_suggestions,
+            PatternSuggestionsConfig {
+                enabled: true,
+                max_patterns: 5,
+                min_score: 0.5,
+                after_failed_turns: true,
+                stale_after_days: 90,
+            }
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn web_search_mode_defaults_to_none_if_unset() {
         let cfg = ConfigToml::default();
@@ -3937,6 +4203,15 @@ model_verbosity = "high"
                 experimental_mode: None,
                 analytics_enabled: Some(true),
                 feedback_enabled: true,
+                capture_tool_enabled: true,
+                capture_locale: None,
+                capture_max_bayes_factor: DEFAULT_CAPTURE_MAX_BAYES_FACTOR,
+                capture_prompt_timeout: None,
+                capture_decimal_separator: DecimalSeparator::Point,
+                capture_strict_numbers: false,
+                covenant_required: false,
+                covenant_retention: CovenantRetentionConfig::default(),
+                pattern_suggestions: PatternSuggestionsConfig::default(),
                 tui_alternate_screen: AltScreenMode::Auto,
                 tui_status_line: None,
                 otel: OtelConfig::default(),
@@ -4025,6 +4300,15 @@ model_verbosity = "high"
             experimental_mode: None,
             analytics_enabled: Some(true),
             feedback_enabled: true,
+            capture_tool_enabled: true,
+            capture_locale: None,
+            capture_max_bayes_factor: DEFAULT_CAPTURE_MAX_BAYES_FACTOR,
+            capture_prompt_timeout: None,
+            capture_decimal_separator: DecimalSeparator::Point,
+            capture_strict_numbers: false,
+            covenant_required: false,
+            covenant_retention: CovenantRetentionConfig::default(),
+            pattern_suggestions: PatternSuggestionsConfig::default(),
             tui_alternate_screen: AltScreenMode::Auto,
             tui_status_line: None,
             otel: OtelConfig::default(),
@@ -4128,6 +4412,15 @@ model_verbosity = "high"
             experimental_mode: None,
             analytics_enabled: Some(false),
             feedback_enabled: true,
+            capture_tool_enabled: true,
+            capture_locale: None,
+            capture_max_bayes_factor: DEFAULT_CAPTURE_MAX_BAYES_FACTOR,
+            capture_prompt_timeout: None,
+            capture_decimal_separator: DecimalSeparator::Point,
+            capture_strict_numbers: false,
+            covenant_required: false,
+            covenant_retention: CovenantRetentionConfig::default(),
+            pattern_suggestions: PatternSuggestionsConfig::default(),
             tui_alternate_screen: AltScreenMode::Auto,
             tui_status_line: None,
             otel: OtelConfig::default(),
@@ -4217,6 +4510,15 @@ model_verbosity = "high"
             experimental_mode: None,
             analytics_enabled: Some(true),
             feedback_enabled: true,
+            capture_tool_enabled: true,
+            capture_locale: None,
+            capture_max_bayes_factor: DEFAULT_CAPTURE_MAX_BAYES_FACTOR,
+            capture_prompt_timeout: None,
+            capture_decimal_separator: DecimalSeparator::Point,
+            capture_strict_numbers: false,
+            covenant_required: false,
+            covenant_retention: CovenantRetentionConfig::default(),
+            pattern_suggestions: PatternSuggestionsConfig::default(),
             tui_alternate_screen: AltScreenMode::Auto,
             tui_status_line: None,
             otel: OtelConfig::default(),
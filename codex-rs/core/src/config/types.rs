@@ -332,6 +332,130 @@ pub struct FeedbackConfigToml {
     pub enabled: Option<bool>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct CaptureConfigToml {
+    /// Locale of the capture tool's prompts, such as `fr` or `pt-BR`. Prompts
+    /// are read from `CODEX_HOME/capture_locales/<locale>.toml`; anything the
+    /// catalog leaves out stays in English.
+    pub locale: Option<String>,
+    /// Largest Bayes factor (posterior odds over prior odds) a probability
+    /// update may imply before the capture output warns about it. Updates
+    /// shrinking the odds by more than the same factor are flagged too.
+    /// Defaults to `100`.
+    pub max_bayes_factor: Option<f64>,
+    /// Seconds to wait for each group of capture questions before saving the
+    /// record answered so far as incomplete. Unset or `0` waits indefinitely.
+    pub prompt_timeout_secs: Option<u64>,
+    /// Decimal separator of the numbers typed into the capture tool. Defaults
+    /// to the one `locale` writes decimals with.
+    pub decimal_separator: Option<DecimalSeparator>,
+    /// Accept only `decimal_separator` in numbers and read a number as a
+    /// percentage only when it ends in `%`. Defaults to `false`.
+    pub strict_numbers: Option<bool>,
+}
+
+/// How the capture tool reads the decimals of probabilities and weights.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
+pub enum DecimalSeparator {
+    /// `0.7`.
+    #[default]
+    #[serde(rename = ".")]
+    Point,
+    /// `0,7`.
+    #[serde(rename = ",")]
+    Comma,
+}
+
+/// `[tools.capture]`: whether the `capture` tool is offered at all.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct CaptureToolToml {
+    /// Offer the `capture` tool in sessions with collaboration modes.
+    /// Defaults to `true`.
+    pub enabled: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct CovenantConfigToml {
+    /// When `true`, a missing or unreadable `covenant.json` blocks every
+    /// covenant-checked action, and scopes the covenant does not declare are
+    /// denied.
+    pub required: Option<bool>,
+    /// `codex covenant gc` archives resolved events created more than this
+    /// many days ago.
+    pub max_event_age_days: Option<u64>,
+    /// `codex covenant gc` archives the oldest resolved events until the
+    /// covenant store fits in this many megabytes.
+    pub max_store_mb: Option<u64>,
+}
+
+/// Retention limits for the covenant store. Unset limits never archive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CovenantRetentionConfig {
+    pub max_event_age_days: Option<u64>,
+    pub max_store_mb: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct PatternSuggestionsConfigToml {
+    /// When `true`, stored patterns matching the user message are added to
+    /// the turn's context. Defaults to `false`.
+    pub enabled: Option<bool>,
+    /// Most patterns injected into one turn. Defaults to `3`.
+    pub max_patterns: Option<usize>,
+    /// Lowest match score a pattern needs to be injected. Defaults to `0.5`.
+    pub min_score: Option<f64>,
+    /// When `true`, a turn that ends in an error or is interrupted is matched
+    /// against stored patterns, and the best match's response is shown as a
+    /// warning. Independent of `enabled`. Defaults to `false`.
+    pub after_failed_turns: Option<bool>,
+    /// Stored patterns that have not been created or re-confirmed in this many
+    /// days are listed when a session starts, to re-confirm or archive. `0`
+    /// turns the notice off. Defaults to `90`.
+    pub stale_after_days: Option<u64>,
+}
+
+/// Effective known-pattern injection settings after defaults are applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternSuggestionsConfig {
+    pub enabled: bool,
+    pub max_patterns: usize,
+    pub min_score: f64,
+    pub after_failed_turns: bool,
+    /// `0` when stale patterns are not listed at session start.
+    pub stale_after_days: u64,
+}
+
+impl Default for PatternSuggestionsConfig {
+    fn default() -> Self {
+        PatternSuggestionsConfig {
+            enabled: false,
+            max_patterns: 3,
+            min_score: 0.5,
+            after_failed_turns: false,
+            stale_after_days: 90,
+        }
+    }
+}
+
+impl From<PatternSuggestionsConfigToml> for PatternSuggestionsConfig {
+    fn from(toml: PatternSuggestionsConfigToml) -> Self {
+        let defaults = PatternSuggestionsConfig::default();
+        PatternSuggestionsConfig {
+            enabled: toml.enabled.unwrap_or(defaults.enabled),
+            max_patterns: toml.max_patterns.unwrap_or(defaults.max_patterns),
+            min_score: toml.min_score.unwrap_or(defaults.min_score),
+            after_failed_turns: toml
+                .after_failed_turns
+                .unwrap_or(defaults.after_failed_turns),
+            stale_after_days: toml.stale_after_days.unwrap_or(defaults.stale_after_days),
+        }
+    }
+}
+
 // ===== OTEL configuration =====
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
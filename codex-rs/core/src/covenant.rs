@@ -1,16 +1,54 @@
+use codex_state::CovenantStorage;
+use codex_state::expand_capability_template;
+use codex_state::unknown_template_message;
 use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
 use std::path::Path;
 use std::path::PathBuf;
+use tracing::info;
+use tracing::warn;
 
-#[derive(Debug, Deserialize)]
+/// File name searched for from the working directory upwards.
+pub const COVENANT_FILENAME: &str = "covenant.json";
+
+/// Oldest `covenant.json` format version this build reads. Older layouts are
+/// upgraded as they load, and `codex covenant upgrade` rewrites them on disk.
+pub const MIN_COVENANT_VERSION: u32 = 1;
+
+/// Format version this build writes, and the newest it understands.
+pub const COVENANT_VERSION: u32 = 2;
+
+/// Scopes granted every capability by [`Covenant::starter`], matching the
+/// session sources Codex reports.
+const STARTER_SCOPES: &[&str] = &["cli", "exec", "vscode", "mcp"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Covenant {
     pub version: String,
+    /// Where covenant events, patterns, and audit entries for this project are
+    /// stored.
+    #[serde(default, skip_serializing_if = "CovenantStorage::is_home")]
+    pub storage: CovenantStorage,
     pub scopes: Vec<CovenantScope>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CovenantScope {
     pub name: String,
+    /// Named capability list from [`codex_state::CAPABILITY_TEMPLATES`],
+    /// expanded into `capabilities` when the covenant is loaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+/// One scope's template, and the capabilities it expanded to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateExpansion {
+    pub scope: String,
+    pub template: String,
     pub capabilities: Vec<String>,
 }
 
@@ -21,9 +59,19 @@ pub enum CovenantAction {
     InterventionExecApproval,
     InterventionPatchApproval,
     InterventionUserShell,
+    EventLog,
 }
 
 impl CovenantAction {
+    pub const ALL: [CovenantAction; 6] = [
+        CovenantAction::ProposalExecCommand,
+        CovenantAction::ProposalApplyPatch,
+        CovenantAction::InterventionExecApproval,
+        CovenantAction::InterventionPatchApproval,
+        CovenantAction::InterventionUserShell,
+        CovenantAction::EventLog,
+    ];
+
     pub fn as_capability(self) -> &'static str {
         match self {
             CovenantAction::ProposalExecCommand => "proposal.exec_command",
@@ -31,11 +79,154 @@ impl CovenantAction {
             CovenantAction::InterventionExecApproval => "intervention.exec_approval",
             CovenantAction::InterventionPatchApproval => "intervention.patch_approval",
             CovenantAction::InterventionUserShell => "intervention.user_shell",
+            CovenantAction::EventLog => "event.log",
+        }
+    }
+
+    pub fn from_capability(capability: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.as_capability() == capability)
+    }
+}
+
+/// What the covenant says about one capability, before session grants are
+/// consulted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CovenantDecision {
+    Granted,
+    Denied,
+}
+
+/// Decide `capability` in `scope` from the covenant found from `cwd`,
+/// returning the covenant version to audit alongside the decision.
+///
+/// Without `required`, a missing `covenant.json` enforces nothing, scopes the
+/// covenant does not declare are unrestricted, and an unreadable one denies
+/// everything. With `required`, enforcement is deny-by-default and a missing
+/// or unreadable `covenant.json` is an error, so the action fails after it
+/// has been audited.
+pub fn decide_covenant(
+    found: anyhow::Result<Option<Covenant>>,
+    cwd: &Path,
+    scope: &str,
+    capability: &str,
+    required: bool,
+) -> (String, anyhow::Result<CovenantDecision>) {
+    match found {
+        Ok(Some(covenant)) => {
+            let decision = if covenant.grants(scope, capability, required) {
+                CovenantDecision::Granted
+            } else {
+                CovenantDecision::Denied
+            };
+            (covenant.version, Ok(decision))
+        }
+        Ok(None) if required => (
+            "missing".to_string(),
+            Err(anyhow::anyhow!(
+                "covenant.required is set but no covenant.json was found from {}",
+                cwd.display()
+            )),
+        ),
+        Ok(None) => ("missing".to_string(), Ok(CovenantDecision::Granted)),
+        Err(err) if required => (
+            "invalid".to_string(),
+            Err(err.context("covenant.required is set but covenant.json could not be loaded")),
+        ),
+        Err(err) => {
+            warn!("failed to load covenant.json from {}: {err}", cwd.display());
+            ("invalid".to_string(), Ok(CovenantDecision::Denied))
+        }
+    }
+}
+
+/// What happens to an agent proposal after the covenant is consulted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CovenantEnforcement {
+    /// The covenant grants the capability.
+    Allowed,
+    /// Not granted, so the user is asked to approve it explicitly.
+    Escalated,
+    /// Not granted and there is nobody to ask.
+    Refused,
+}
+
+impl CovenantEnforcement {
+    pub fn decide(granted: bool, can_escalate: bool) -> Self {
+        match (granted, can_escalate) {
+            (true, _) => Self::Allowed,
+            (false, true) => Self::Escalated,
+            (false, false) => Self::Refused,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Allowed => "allowed",
+            Self::Escalated => "escalated",
+            Self::Refused => "refused",
         }
     }
 }
 
 impl Covenant {
+    /// A covenant granting every capability to the interactive, exec, IDE, and
+    /// MCP scopes, meant as a starting point to trim down.
+    pub fn starter(version: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            storage: CovenantStorage::Home,
+            scopes: STARTER_SCOPES
+                .iter()
+                .map(|scope| CovenantScope {
+                    name: (*scope).to_string(),
+                    template: None,
+                    capabilities: CovenantAction::ALL
+                        .iter()
+                        .map(|action| action.as_capability().to_string())
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Expand each scope's template into its capabilities, keeping any
+    /// capabilities the scope also lists. Fails on an unknown template.
+    pub fn expand_templates(&mut self) -> anyhow::Result<Vec<TemplateExpansion>> {
+        let mut expansions = Vec::new();
+        for scope in &mut self.scopes {
+            let Some(template) = &scope.template else {
+                continue;
+            };
+            scope.capabilities = expand_capability_template(template, &scope.capabilities)
+                .ok_or_else(|| anyhow::anyhow!(unknown_template_message(&scope.name, template)))?;
+            expansions.push(TemplateExpansion {
+                scope: scope.name.clone(),
+                template: template.clone(),
+                capabilities: scope.capabilities.clone(),
+            });
+        }
+        Ok(expansions)
+    }
+
+    pub fn declares_scope(&self, scope: &str) -> bool {
+        self.scopes
+            .iter()
+            .any(|scope_entry| scope_entry.name == scope)
+    }
+
+    /// Whether `capability` is granted in `scope`. When `required` is false,
+    /// scopes the covenant does not declare are left unrestricted; when it is
+    /// true, only explicit grants are allowed.
+    pub fn grants(&self, scope: &str, capability: &str, required: bool) -> bool {
+        if !required && !self.declares_scope(scope) {
+            return true;
+        }
+        self.allows(scope, capability)
+    }
+
+    /// Whether `scope` explicitly lists `capability`.
     pub fn allows(&self, scope: &str, capability: &str) -> bool {
         self.scopes.iter().any(|scope_entry| {
             scope_entry.name == scope
@@ -47,19 +238,135 @@ impl Covenant {
     }
 }
 
+/// A covenant read in the current format, and the format version it was
+/// written in.
+#[derive(Debug, Clone)]
+pub struct CovenantUpgrade {
+    pub from_version: u32,
+    pub covenant: Covenant,
+}
+
+impl CovenantUpgrade {
+    pub fn is_current(&self) -> bool {
+        self.from_version == COVENANT_VERSION
+    }
+}
+
+/// Read `covenant.json` contents written in any supported format version and
+/// rewrite them in the current one. Templates are left unexpanded, so the
+/// result can be written back to disk. Versions outside
+/// `MIN_COVENANT_VERSION..=COVENANT_VERSION` are refused.
+pub fn upgrade_covenant(contents: &str) -> anyhow::Result<CovenantUpgrade> {
+    let mut value: Value = serde_json::from_str(contents)?;
+    let from_version = covenant_format_version(&value)?;
+    if from_version < 2 {
+        upgrade_scope_map(&mut value);
+    }
+    if let Some(object) = value.as_object_mut() {
+        object.insert(
+            "version".to_string(),
+            Value::String(COVENANT_VERSION.to_string()),
+        );
+    }
+    Ok(CovenantUpgrade {
+        from_version,
+        covenant: serde_json::from_value(value)?,
+    })
+}
+
+fn covenant_format_version(value: &Value) -> anyhow::Result<u32> {
+    let version = match value.get("version") {
+        Some(Value::String(version)) => version.trim().to_string(),
+        Some(Value::Number(version)) => version.to_string(),
+        Some(_) | None => anyhow::bail!(
+            "covenant has no version; set \"version\": \"{COVENANT_VERSION}\" at the top level"
+        ),
+    };
+    let Ok(parsed) = version.parse::<u32>() else {
+        anyhow::bail!(
+            "covenant version '{version}' is not a format version; versions {MIN_COVENANT_VERSION} through {COVENANT_VERSION} are supported, so set \"version\": \"{COVENANT_VERSION}\""
+        );
+    };
+    if parsed > COVENANT_VERSION {
+        anyhow::bail!(
+            "covenant version {parsed} is newer than this Codex understands (versions {MIN_COVENANT_VERSION} through {COVENANT_VERSION}); upgrade Codex to use this covenant"
+        );
+    }
+    if parsed < MIN_COVENANT_VERSION {
+        anyhow::bail!(
+            "covenant version {parsed} is no longer supported (versions {MIN_COVENANT_VERSION} through {COVENANT_VERSION}); rewrite it starting from `codex covenant init`"
+        );
+    }
+    Ok(parsed)
+}
+
+/// Version 1 could also map each scope name to its capability list, before
+/// scopes became objects that can name a template.
+fn upgrade_scope_map(value: &mut Value) {
+    let Some(Value::Object(scopes)) = value.get_mut("scopes") else {
+        return;
+    };
+    let scopes = std::mem::take(scopes)
+        .into_iter()
+        .map(|(name, capabilities)| {
+            serde_json::json!({
+                "name": name,
+                "capabilities": capabilities,
+            })
+        })
+        .collect();
+    value["scopes"] = Value::Array(scopes);
+}
+
 pub async fn load_covenant(cwd: &Path) -> anyhow::Result<Covenant> {
-    let covenant_path = find_covenant_path(cwd)
-        .await
-        .ok_or_else(|| anyhow::anyhow!("covenant.json not found from {}", cwd.display()))?;
+    find_covenant(cwd)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("covenant.json not found from {}", cwd.display()))
+}
+
+/// Like [`load_covenant`], but a missing `covenant.json` is `Ok(None)` rather
+/// than an error. Unreadable or invalid files are still errors.
+pub async fn find_covenant(cwd: &Path) -> anyhow::Result<Option<Covenant>> {
+    let Some(covenant_path) = find_covenant_path(cwd).await else {
+        return Ok(None);
+    };
     let contents = tokio::fs::read_to_string(&covenant_path).await?;
-    let covenant = serde_json::from_str(&contents)?;
+    let covenant = parse_covenant(&contents)
+        .map_err(|err| anyhow::anyhow!("failed to parse {}: {err}", covenant_path.display()))?;
+    Ok(Some(covenant))
+}
+
+/// Parse `covenant.json` contents and expand scope templates, logging each
+/// expansion so the capabilities a template granted can be audited.
+///
+/// Covenants in an older format version are upgraded first; one newer than
+/// [`COVENANT_VERSION`] is an error.
+pub fn parse_covenant(contents: &str) -> anyhow::Result<Covenant> {
+    let upgrade = upgrade_covenant(contents)?;
+    if !upgrade.is_current() {
+        info!(
+            "covenant uses format version {}; run `codex covenant upgrade` to rewrite it in version {COVENANT_VERSION}",
+            upgrade.from_version
+        );
+    }
+    let mut covenant = upgrade.covenant;
+    for expansion in covenant.expand_templates()? {
+        info!(
+            "covenant {} scope '{}' template '{}' expands to {}",
+            covenant.version,
+            expansion.scope,
+            expansion.template,
+            expansion.capabilities.join(", ")
+        );
+    }
     Ok(covenant)
 }
 
-async fn find_covenant_path(cwd: &Path) -> Option<PathBuf> {
+/// The nearest `covenant.json` in `cwd` or one of its parents.
+pub async fn find_covenant_path(cwd: &Path) -> Option<PathBuf> {
     let mut current = Some(cwd);
     while let Some(path) = current {
-        let candidate = path.join("covenant.json");
+        let candidate = path.join(COVENANT_FILENAME);
         if tokio::fs::try_exists(&candidate).await.unwrap_or(false) {
             return Some(candidate);
         }
@@ -72,8 +379,16 @@ async fn find_covenant_path(cwd: &Path) -> Option<PathBuf> {
 mod tests {
     use super::Covenant;
     use super::CovenantAction;
+    use super::CovenantDecision;
+    use super::CovenantEnforcement;
     use super::CovenantScope;
+    use super::TemplateExpansion;
+    use super::decide_covenant;
+    use super::parse_covenant;
+    use super::upgrade_covenant;
+    use codex_state::CovenantStorage;
     use pretty_assertions::assert_eq;
+    use std::path::Path;
 
     #[test]
     fn covenant_enforcement_blocks_out_of_scope_actions() {
@@ -81,12 +396,14 @@ mod tests {
             version: "2026-02-01".to_string(),
             scopes: vec![CovenantScope {
                 name: "proposal".to_string(),
+                template: None,
                 capabilities: vec![
                     CovenantAction::ProposalExecCommand
                         .as_capability()
                         .to_string(),
                 ],
             }],
+            storage: CovenantStorage::Home,
         };
 
         assert_eq!(
@@ -111,4 +428,182 @@ mod tests {
             false
         );
     }
+
+    #[test]
+    fn missing_covenants_and_undeclared_scopes_are_only_denied_when_required() {
+        let cwd = Path::new("/repo");
+        let capability = CovenantAction::ProposalExecCommand.as_capability();
+        let decide = |found, scope: &str, required| {
+            let (version, decision) = decide_covenant(found, cwd, scope, capability, required);
+            (version, decision.map_err(|err| err.to_string()))
+        };
+        let starter = || Ok(Some(Covenant::starter("2026-10-14")));
+
+        assert_eq!(
+            [
+                decide(starter(), "cli", false),
+                decide(starter(), "subagent_review", false),
+                decide(starter(), "subagent_review", true),
+                decide(Ok(None), "cli", false),
+                decide(Err(anyhow::anyhow!("bad json")), "cli", false),
+                decide(Ok(None), "cli", true),
+                decide(Err(anyhow::anyhow!("bad json")), "cli", true),
+            ],
+            [
+                ("2026-10-14".to_string(), Ok(CovenantDecision::Granted)),
+                ("2026-10-14".to_string(), Ok(CovenantDecision::Granted)),
+                ("2026-10-14".to_string(), Ok(CovenantDecision::Denied)),
+                ("missing".to_string(), Ok(CovenantDecision::Granted)),
+                ("invalid".to_string(), Ok(CovenantDecision::Denied)),
+                (
+                    "missing".to_string(),
+                    Err(
+                        "covenant.required is set but no covenant.json was found from /repo"
+                            .to_string()
+                    )
+                ),
+                (
+                    "invalid".to_string(),
+                    Err(
+                        "covenant.required is set but covenant.json could not be loaded"
+                            .to_string()
+                    )
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn scope_templates_expand_at_load() {
+        let mut covenant: Covenant = serde_json::from_str(
+            r#"{"version": "1", "scopes": [
+                {"name": "cli", "template": "read-only", "capabilities": ["proposal.apply_patch"]},
+                {"name": "exec", "capabilities": ["event.log"]}
+            ]}"#,
+        )
+        .expect("parse covenant");
+
+        assert_eq!(
+            covenant.expand_templates().expect("expand templates"),
+            vec![TemplateExpansion {
+                scope: "cli".to_string(),
+                template: "read-only".to_string(),
+                capabilities: vec!["event.log".to_string(), "proposal.apply_patch".to_string()],
+            }]
+        );
+        assert_eq!(
+            (
+                covenant.allows("cli", "event.log"),
+                covenant.allows("cli", "proposal.apply_patch"),
+                covenant.allows("cli", "proposal.exec_command"),
+            ),
+            (true, true, false)
+        );
+        assert_eq!(
+            parse_covenant(
+                r#"{"version": "1", "scopes": [{"name": "cli", "template": "admin"}]}"#
+            )
+            .map(|_| ())
+            .map_err(|err| err.to_string()),
+            Err("covenant scope 'cli' uses unknown template 'admin' (known: read-only, standard-dev, full-intervention)".to_string())
+        );
+    }
+
+    #[test]
+    fn older_layouts_upgrade_and_newer_versions_are_refused() {
+        let upgrade = upgrade_covenant(
+            r#"{"version": "1", "scopes": {"cli": ["event.log"], "exec": ["proposal.exec_command"]}}"#,
+        )
+        .expect("upgrade version 1 covenant");
+        assert_eq!(
+            (
+                upgrade.from_version,
+                upgrade.is_current(),
+                serde_json::to_value(&upgrade.covenant).expect("serialize covenant"),
+            ),
+            (
+                1,
+                false,
+                serde_json::json!({
+                    "version": "2",
+                    "scopes": [
+                        {"name": "cli", "capabilities": ["event.log"]},
+                        {"name": "exec", "capabilities": ["proposal.exec_command"]},
+                    ],
+                }),
+            )
+        );
+
+        assert_eq!(
+            [
+                r#"{"version": 2, "scopes": [{"name": "cli", "template": "read-only"}]}"#,
+                r#"{"version": "3", "scopes": []}"#,
+                r#"{"version": "2026-02-01", "scopes": []}"#,
+                r#"{"scopes": []}"#,
+            ]
+            .map(|contents| parse_covenant(contents)
+                .map(|covenant| covenant.allows("cli", "event.log"))
+                .map_err(|err| err.to_string())),
+            [
+                Ok(true),
+                Err("covenant version 3 is newer than this Codex understands (versions 1 through 2); upgrade Codex to use this covenant".to_string()),
+                Err("covenant version '2026-02-01' is not a format version; versions 1 through 2 are supported, so set \"version\": \"2\"".to_string()),
+                Err("covenant has no version; set \"version\": \"2\" at the top level".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn storage_defaults_to_home_and_is_only_written_when_set() {
+        let workspace = parse_covenant(r#"{"version": "2", "storage": "workspace", "scopes": []}"#)
+            .expect("parse workspace covenant");
+        let home = parse_covenant(r#"{"version": "2", "scopes": []}"#).expect("parse covenant");
+
+        assert_eq!(
+            (
+                workspace.storage,
+                serde_json::to_value(&workspace).expect("serialize workspace covenant"),
+                home.storage,
+                serde_json::to_value(&home).expect("serialize covenant"),
+            ),
+            (
+                CovenantStorage::Workspace,
+                serde_json::json!({"version": "2", "storage": "workspace", "scopes": []}),
+                CovenantStorage::Home,
+                serde_json::json!({"version": "2", "scopes": []}),
+            )
+        );
+    }
+
+    #[test]
+    fn capabilities_round_trip_through_actions() {
+        let round_tripped = CovenantAction::ALL
+            .iter()
+            .filter(|action| {
+                CovenantAction::from_capability(action.as_capability())
+                    .is_some_and(|parsed| parsed.as_capability() == action.as_capability())
+            })
+            .count();
+
+        assert_eq!(
+            (
+                round_tripped,
+                CovenantAction::from_capability("event.unknown").is_none()
+            ),
+            (CovenantAction::ALL.len(), true)
+        );
+    }
+
+    #[test]
+    fn ungranted_proposals_escalate_only_when_someone_can_approve() {
+        assert_eq!(
+            [(true, false), (false, true), (false, false)]
+                .map(|(granted, can_escalate)| CovenantEnforcement::decide(granted, can_escalate)),
+            [
+                CovenantEnforcement::Allowed,
+                CovenantEnforcement::Escalated,
+                CovenantEnforcement::Refused,
+            ]
+        );
+    }
 }
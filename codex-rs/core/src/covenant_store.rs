@@ -0,0 +1,262 @@
+//! Where covenant enforcement reads the covenant and records what it decided.
+//!
+//! Sessions go through a [`CovenantStore`] rather than the filesystem and the
+//! state database directly. [`FsStore`] is what Codex runs with; [`MemStore`]
+//! keeps everything in memory, so tests and other tools can enforce and audit
+//! covenant actions without a `covenant.json` on disk or a `CODEX_HOME`.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use codex_state::AuditAction;
+use codex_state::CovenantCheck;
+use codex_state::CovenantGrant;
+
+use crate::covenant::Covenant;
+use crate::covenant::find_covenant;
+use crate::state_db::StateDbHandle;
+
+#[async_trait]
+pub trait CovenantStore: Send + Sync {
+    /// The covenant governing `cwd`, or `None` when there is none.
+    async fn find_covenant(&self, cwd: &Path) -> anyhow::Result<Option<Covenant>>;
+
+    async fn insert_audit_action(&self, action: &AuditAction) -> anyhow::Result<()>;
+
+    /// Count an enforced check towards the capability's usage totals.
+    async fn record_covenant_check(&self, check: &CovenantCheck) -> anyhow::Result<()>;
+
+    async fn insert_covenant_grant(&self, grant: &CovenantGrant) -> anyhow::Result<()>;
+
+    /// When the latest grant of `capability` to the thread in `scope` expires,
+    /// or `None` when no grant is active at `now`.
+    async fn covenant_grant_expiry(
+        &self,
+        thread_id: &str,
+        scope: &str,
+        capability: &str,
+        now: i64,
+    ) -> anyhow::Result<Option<i64>>;
+}
+
+/// The `covenant.json` found from the working directory up, with audit
+/// entries and grants in the state database.
+pub struct FsStore {
+    state_db: Option<StateDbHandle>,
+}
+
+impl FsStore {
+    /// Without a state database, covenants are still read but nothing can be
+    /// recorded.
+    pub fn new(state_db: Option<StateDbHandle>) -> Self {
+        Self { state_db }
+    }
+
+    fn state_db(&self, purpose: &str) -> anyhow::Result<&StateDbHandle> {
+        self.state_db
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("state db unavailable for {purpose}"))
+    }
+}
+
+#[async_trait]
+impl CovenantStore for FsStore {
+    async fn find_covenant(&self, cwd: &Path) -> anyhow::Result<Option<Covenant>> {
+        find_covenant(cwd).await
+    }
+
+    async fn insert_audit_action(&self, action: &AuditAction) -> anyhow::Result<()> {
+        self.state_db("audit logging")?
+            .insert_audit_action(action)
+            .await
+    }
+
+    async fn record_covenant_check(&self, check: &CovenantCheck) -> anyhow::Result<()> {
+        self.state_db("covenant usage counts")?
+            .record_covenant_check(check)
+            .await
+    }
+
+    async fn insert_covenant_grant(&self, grant: &CovenantGrant) -> anyhow::Result<()> {
+        self.state_db("covenant grants")?
+            .insert_covenant_grant(grant)
+            .await
+    }
+
+    async fn covenant_grant_expiry(
+        &self,
+        thread_id: &str,
+        scope: &str,
+        capability: &str,
+        now: i64,
+    ) -> anyhow::Result<Option<i64>> {
+        match self.state_db.as_ref() {
+            Some(state_db) => {
+                state_db
+                    .covenant_grant_expiry(thread_id, scope, capability, now)
+                    .await
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// A covenant, audit log, and grants held in memory. The same covenant
+/// applies to every working directory.
+#[derive(Default)]
+pub struct MemStore {
+    covenant: Mutex<Option<Covenant>>,
+    audit_actions: Mutex<Vec<AuditAction>>,
+    checks: Mutex<Vec<CovenantCheck>>,
+    grants: Mutex<Vec<CovenantGrant>>,
+}
+
+impl MemStore {
+    pub fn new(covenant: Option<Covenant>) -> Self {
+        Self {
+            covenant: Mutex::new(covenant),
+            ..Default::default()
+        }
+    }
+
+    pub fn set_covenant(&self, covenant: Option<Covenant>) {
+        *lock(&self.covenant) = covenant;
+    }
+
+    /// Every audit entry recorded so far, oldest first.
+    pub fn audit_actions(&self) -> Vec<AuditAction> {
+        lock(&self.audit_actions).clone()
+    }
+
+    /// Every enforced check recorded so far, oldest first.
+    pub fn checks(&self) -> Vec<CovenantCheck> {
+        lock(&self.checks).clone()
+    }
+
+    pub fn grants(&self) -> Vec<CovenantGrant> {
+        lock(&self.grants).clone()
+    }
+}
+
+#[async_trait]
+impl CovenantStore for MemStore {
+    async fn find_covenant(&self, _cwd: &Path) -> anyhow::Result<Option<Covenant>> {
+        Ok(lock(&self.covenant).clone())
+    }
+
+    async fn insert_audit_action(&self, action: &AuditAction) -> anyhow::Result<()> {
+        lock(&self.audit_actions).push(action.clone());
+        Ok(())
+    }
+
+    async fn record_covenant_check(&self, check: &CovenantCheck) -> anyhow::Result<()> {
+        lock(&self.checks).push(check.clone());
+        Ok(())
+    }
+
+    async fn insert_covenant_grant(&self, grant: &CovenantGrant) -> anyhow::Result<()> {
+        lock(&self.grants).push(grant.clone());
+        Ok(())
+    }
+
+    async fn covenant_grant_expiry(
+        &self,
+        thread_id: &str,
+        scope: &str,
+        capability: &str,
+        now: i64,
+    ) -> anyhow::Result<Option<i64>> {
+        Ok(lock(&self.grants)
+            .iter()
+            .filter(|grant| {
+                grant.thread_id == thread_id
+                    && grant.scope == scope
+                    && grant.capability == capability
+                    && grant.expires_at > now
+            })
+            .map(|grant| grant.expires_at)
+            .max())
+    }
+}
+
+/// A poisoned lock only means another holder panicked; the data is still
+/// usable for recording.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::covenant::CovenantScope;
+    use codex_state::CovenantStorage;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn mem_store_serves_its_covenant_and_live_grants() {
+        let store = MemStore::new(Some(Covenant {
+            version: "1".to_string(),
+            scopes: vec![CovenantScope {
+                name: "cli".to_string(),
+                template: None,
+                capabilities: vec!["event.log".to_string()],
+            }],
+            storage: CovenantStorage::Home,
+        }));
+        let grant = |expires_at| CovenantGrant {
+            thread_id: "thread".to_string(),
+            scope: "cli".to_string(),
+            capability: "proposal.exec_command".to_string(),
+            granted_at: 0,
+            expires_at,
+        };
+        store
+            .insert_covenant_grant(&grant(100))
+            .await
+            .expect("insert grant");
+        store
+            .insert_covenant_grant(&grant(200))
+            .await
+            .expect("insert grant");
+        let audit = AuditAction {
+            timestamp: 50,
+            actor: "agent".to_string(),
+            action_type: "proposal.exec_command".to_string(),
+            scope: "cli".to_string(),
+            covenant_version: "1".to_string(),
+            event_id: None,
+            intent_id: None,
+        };
+        store
+            .insert_audit_action(&audit)
+            .await
+            .expect("insert audit");
+
+        let covenant = store
+            .find_covenant(Path::new("/nowhere"))
+            .await
+            .expect("find covenant")
+            .map(|covenant| covenant.version);
+        let expiry =
+            |now| store.covenant_grant_expiry("thread", "cli", "proposal.exec_command", now);
+        assert_eq!(
+            (
+                covenant,
+                expiry(50).await.expect("expiry"),
+                expiry(150).await.expect("expiry"),
+                expiry(200).await.expect("expiry"),
+                store.audit_actions(),
+            ),
+            (
+                Some("1".to_string()),
+                Some(200),
+                Some(200),
+                None,
+                vec![audit]
+            )
+        );
+    }
+}
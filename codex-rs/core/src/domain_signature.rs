@@ -0,0 +1,182 @@
+//! Domain signatures generated from pattern and event text.
+//!
+//! Events and patterns that arrive without a domain signature get one from
+//! their trigger and invariant, so compile and matching compare vectors built
+//! the same way. The default provider hashes content words into a fixed number
+//! of buckets; `[signatures] command` in `patterns.toml` hands the text to an
+//! external embedding program instead.
+
+use crate::trigger_template::templatize_trigger;
+use anyhow::Context;
+use codex_utils_text_normalize::TextNormalizer;
+use serde::Deserialize;
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+/// Buckets used by the hashed provider unless `dimensions` is set.
+pub const DEFAULT_SIGNATURE_DIMENSIONS: usize = 64;
+
+/// The `[signatures]` table of `patterns.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignatureConfig {
+    /// Bucket count for the hashed provider.
+    pub dimensions: Option<usize>,
+    /// Program and arguments that read text on stdin and print a JSON array
+    /// of numbers.
+    pub command: Option<Vec<String>>,
+}
+
+/// Turns text into a domain signature.
+pub trait SignatureProvider {
+    /// Recorded with generated signatures; vectors from providers with
+    /// different names are not comparable.
+    fn name(&self) -> String;
+
+    fn signature(&self, text: &str) -> anyhow::Result<Vec<f64>>;
+}
+
+/// Content words hashed into `dimensions` buckets, L2-normalized. Stable
+/// across runs and platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashedSignatureProvider {
+    dimensions: usize,
+}
+
+impl HashedSignatureProvider {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+}
+
+impl Default for HashedSignatureProvider {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIGNATURE_DIMENSIONS)
+    }
+}
+
+impl SignatureProvider for HashedSignatureProvider {
+    fn name(&self) -> String {
+        format!("hashed-{}", self.dimensions)
+    }
+
+    fn signature(&self, text: &str) -> anyhow::Result<Vec<f64>> {
+        let mut buckets = vec![0.0; self.dimensions];
+        for hash in TextNormalizer::configured().fingerprint(text, 1) {
+            buckets[(hash % self.dimensions as u64) as usize] += 1.0;
+        }
+        let norm = buckets
+            .iter()
+            .map(|value| value * value)
+            .sum::<f64>()
+            .sqrt();
+        if norm > 0.0 {
+            for value in &mut buckets {
+                *value /= norm;
+            }
+        }
+        Ok(buckets)
+    }
+}
+
+/// Runs an external program once per text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSignatureProvider {
+    command: Vec<String>,
+}
+
+impl CommandSignatureProvider {
+    pub fn new(command: Vec<String>) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            command.first().is_some_and(|program| !program.is_empty()),
+            "signatures.command must name a program"
+        );
+        Ok(Self { command })
+    }
+}
+
+impl SignatureProvider for CommandSignatureProvider {
+    fn name(&self) -> String {
+        format!("command:{}", self.command.join(" "))
+    }
+
+    fn signature(&self, text: &str) -> anyhow::Result<Vec<f64>> {
+        let program = &self.command[0];
+        let mut child = Command::new(program)
+            .args(&self.command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run signature command {program}"))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        anyhow::ensure!(
+            output.status.success(),
+            "signature command {program} failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        serde_json::from_slice(&output.stdout).with_context(|| {
+            format!("signature command {program} did not print a JSON array of numbers")
+        })
+    }
+}
+
+/// The provider configured by `config`: the command when one is set,
+/// otherwise the hashed default.
+pub fn signature_provider(config: &SignatureConfig) -> anyhow::Result<Box<dyn SignatureProvider>> {
+    match &config.command {
+        Some(command) => Ok(Box::new(CommandSignatureProvider::new(command.clone())?)),
+        None => Ok(Box::new(HashedSignatureProvider::new(
+            config.dimensions.unwrap_or(DEFAULT_SIGNATURE_DIMENSIONS),
+        ))),
+    }
+}
+
+/// Signature for a trigger/invariant pair. Variable spans of the trigger are
+/// replaced with placeholders first, so an event's signature lines up with
+/// that of the templated pattern it would compile into.
+pub fn generate_signature(
+    provider: &dyn SignatureProvider,
+    trigger: &str,
+    invariant: &str,
+) -> anyhow::Result<Vec<f64>> {
+    let trigger = templatize_trigger(trigger).unwrap_or_else(|| trigger.to_string());
+    provider.signature(&format!("{trigger} {invariant}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hashed_signatures_are_normalized_and_ignore_variable_spans() {
+        let provider = HashedSignatureProvider::new(16);
+        let pattern = generate_signature(&provider, "Disk full on {path}", "disk pressure")
+            .expect("pattern signature");
+        let event = generate_signature(&provider, "disk full on /var/log", "Disk pressure")
+            .expect("event signature");
+        let norm = pattern
+            .iter()
+            .map(|value| value * value)
+            .sum::<f64>()
+            .sqrt();
+
+        assert_eq!(
+            (pattern.len(), (norm * 1e9).round() / 1e9, provider.name()),
+            (16, 1.0, "hashed-16".to_string())
+        );
+        assert_eq!(pattern, event);
+        assert_eq!(
+            provider.signature("?").expect("empty signature"),
+            vec![0.0; 16]
+        );
+    }
+}
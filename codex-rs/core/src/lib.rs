@@ -10,6 +10,10 @@ pub mod api_bridge;
 mod apply_patch;
 pub mod auth;
 pub mod bash;
+mod capture_locale;
+mod capture_numbers;
+mod capture_prefill;
+mod capture_template;
 mod client;
 mod client_common;
 pub mod codex;
@@ -25,8 +29,10 @@ pub mod config;
 pub mod config_loader;
 pub mod connectors;
 mod context_manager;
-mod covenant;
+pub mod covenant;
+pub mod covenant_store;
 pub mod custom_prompts;
+pub mod domain_signature;
 pub mod env;
 mod environment_context;
 pub mod error;
@@ -50,9 +56,14 @@ mod mcp_tool_call;
 mod mentions;
 mod message_history;
 mod model_provider_info;
-pub mod pattern_match;
 pub mod parse_command;
 pub mod path_utils;
+mod pattern_expiry;
+pub mod pattern_match;
+mod pattern_query;
+mod pattern_suggestions;
+pub mod pattern_usage;
+pub mod patterns;
 pub mod personality_migration;
 pub mod powershell;
 mod proposed_plan_parser;
@@ -62,6 +73,7 @@ mod stream_events_utils;
 mod tagged_block_parser;
 mod text_encoding;
 pub mod token_data;
+pub mod trigger_template;
 mod truncate;
 mod unified_exec;
 pub mod windows_sandbox;
@@ -101,9 +113,11 @@ pub mod shell_snapshot;
 pub mod skills;
 pub mod spawn;
 pub mod state_db;
+pub mod store_query;
 pub mod terminal;
 mod tools;
 pub mod turn_diff_tracker;
+pub mod turn_intents;
 mod turn_metadata;
 pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
 pub use rollout::INTERACTIVE_SESSION_SOURCES;
@@ -141,6 +155,10 @@ pub use exec_policy::check_execpolicy_for_warnings;
 pub use exec_policy::load_exec_policy;
 pub use file_watcher::FileWatcherEvent;
 pub use safety::get_platform_sandbox;
+pub use tools::handlers::BeliefPoint;
+pub use tools::handlers::HypothesisTimeline;
+pub use tools::handlers::capture_schema;
+pub use tools::handlers::probability_timelines;
 pub use tools::spec::parse_tool_input_schema;
 pub use turn_metadata::build_turn_metadata_header;
 // Re-export the protocol types from the standalone `codex-protocol` crate so existing
@@ -0,0 +1,132 @@
+//! Stale pattern notices.
+//!
+//! When a session starts, stored patterns visible from its project that have
+//! gone `[pattern_suggestions] stale_after_days` without being created or
+//! re-confirmed are listed in `EventMsg::StalePatterns`, so the user can
+//! re-confirm the ones that still hold and archive the rest.
+
+use codex_protocol::protocol::StalePatternSummary;
+use codex_protocol::protocol::StalePatternsEvent;
+use codex_state::PatternNamespaces;
+use codex_state::StalePattern;
+use codex_state::StateRuntime;
+use codex_state::pattern_namespace_visible;
+use std::path::Path;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// The notice for the patterns of the covenant `scope` visible from `cwd`
+/// that were last confirmed
+/// more than `stale_after_days` before `now`, or `None` when there are none
+/// or the notice is turned off.
+pub(crate) async fn stale_patterns_event(
+    state_db: &StateRuntime,
+    namespaces: &PatternNamespaces,
+    cwd: &Path,
+    scope: &str,
+    stale_after_days: u64,
+    now: i64,
+) -> anyhow::Result<Option<StalePatternsEvent>> {
+    if stale_after_days == 0 {
+        return Ok(None);
+    }
+    let max_age = i64::try_from(stale_after_days)
+        .unwrap_or(i64::MAX)
+        .saturating_mul(SECONDS_PER_DAY);
+    let stale = state_db
+        .list_stale_covenant_patterns(now.saturating_sub(max_age), Some(scope))
+        .await?;
+    if stale.is_empty() {
+        return Ok(None);
+    }
+    let namespace = namespaces.resolve(cwd).await?;
+    Ok(stale_patterns_notice(stale, &namespace, stale_after_days))
+}
+
+/// The notice for the `stale` patterns visible from `namespace`.
+pub(crate) fn stale_patterns_notice(
+    stale: Vec<StalePattern>,
+    namespace: &str,
+    stale_after_days: u64,
+) -> Option<StalePatternsEvent> {
+    let patterns = stale
+        .into_iter()
+        .filter(|stale| pattern_namespace_visible(&stale.pattern.namespace, namespace))
+        .map(|stale| StalePatternSummary {
+            pattern_id: stale.pattern.id,
+            trigger: stale.pattern.trigger,
+            best_response: stale.pattern.best_response,
+            last_confirmed_at: stale.last_confirmed_at,
+        })
+        .collect::<Vec<_>>();
+    (!patterns.is_empty()).then_some(StalePatternsEvent {
+        stale_after_days,
+        patterns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_state::CovenantPattern;
+    use pretty_assertions::assert_eq;
+
+    fn stale(id: &str, namespace: &str, last_confirmed_at: i64) -> StalePattern {
+        StalePattern {
+            pattern: CovenantPattern {
+                id: id.to_string(),
+                trigger: format!("{id} trigger"),
+                invariant: "disk pressure".to_string(),
+                counterexample: String::new(),
+                best_response: "prune old logs".to_string(),
+                domain_signature: "[]".to_string(),
+                evidence_refs: Vec::new(),
+                namespace: namespace.to_string(),
+                scope: "default".to_string(),
+                created_at: 1,
+                anti_pattern: false,
+                tags: Vec::new(),
+                counterexamples: Vec::new(),
+                supports: 0,
+                contradictions: 0,
+            },
+            last_confirmed_at,
+        }
+    }
+
+    #[test]
+    fn notices_list_only_patterns_visible_from_the_project() {
+        let patterns = vec![
+            stale("disk", "global", 10),
+            stale("other-project", "project:bbbb", 20),
+            stale("cert", "project:aaaa", 30),
+        ];
+
+        assert_eq!(
+            (
+                stale_patterns_notice(patterns.clone(), "project:aaaa", 90),
+                stale_patterns_notice(patterns[1..2].to_vec(), "project:aaaa", 90),
+            ),
+            (
+                Some(StalePatternsEvent {
+                    stale_after_days: 90,
+                    patterns: vec![
+                        StalePatternSummary {
+                            pattern_id: "disk".to_string(),
+                            trigger: "disk trigger".to_string(),
+                            best_response: "prune old logs".to_string(),
+                            last_confirmed_at: 10,
+                        },
+                        StalePatternSummary {
+                            pattern_id: "cert".to_string(),
+                            trigger: "cert trigger".to_string(),
+                            best_response: "prune old logs".to_string(),
+                            last_confirmed_at: 30,
+                        },
+                    ],
+                }),
+                None,
+            )
+        );
+    }
+}
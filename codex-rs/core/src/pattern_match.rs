@@ -1,12 +1,219 @@
+use crate::domain_signature::SignatureConfig;
+use crate::domain_signature::SignatureProvider;
+use crate::domain_signature::generate_signature;
+use crate::pattern_usage::PatternRollingStats;
+use crate::patterns::CompileOptions;
+use crate::patterns::GroupBy;
+use crate::patterns::Outcome;
+use crate::patterns::PatternCounterexample;
+use crate::patterns::PatternProvenance;
+use crate::trigger_template::has_placeholders;
+use crate::trigger_template::match_trigger_template;
+use crate::trigger_template::templatize_trigger;
+pub use codex_protocol::test_status::TestStatus;
+use codex_state::CovenantPattern;
+use codex_state::pattern_namespace_visible;
+use codex_utils_text_normalize::TextNormalizer;
+use codex_utils_text_normalize::char_trigram_similarity;
+use codex_utils_text_normalize::set_configured_normalizer;
+use codex_utils_text_normalize::token_set;
+use codex_utils_text_normalize::tokenize;
 use serde::Deserialize;
 use serde::Serialize;
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
 
-const TEXT_WEIGHT: f64 = 0.4;
-const DOMAIN_WEIGHT: f64 = 0.5;
-const OUTCOME_WEIGHT: f64 = 0.1;
+/// File under `CODEX_HOME` that configures pattern ranking.
+pub const PATTERNS_CONFIG_FILENAME: &str = "patterns.toml";
+
+/// Triggers with fewer tokens than this also get a character-level text score.
+const SHORT_TRIGGER_TOKENS: usize = 3;
+
+/// Relative weights applied to each component score when ranking patterns.
+///
+/// A `[weights]` table must set all three weights, so that preset values are
+/// never mixed into explicit ones; only `fuzzy` may be left out.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ScoringProfile {
+    pub text: f64,
+    pub domain: f64,
+    pub outcome: f64,
+    /// Share of the text score taken from character trigram similarity of the
+    /// triggers when either trigger is shorter than three tokens.
+    #[serde(default = "default_fuzzy")]
+    pub fuzzy: f64,
+}
+
+fn default_fuzzy() -> f64 {
+    ScoringProfile::BALANCED.fuzzy
+}
+
+impl ScoringProfile {
+    pub const BALANCED: Self = Self {
+        text: 0.4,
+        domain: 0.5,
+        outcome: 0.1,
+        fuzzy: 0.5,
+    };
+    pub const LEXICAL_HEAVY: Self = Self {
+        text: 0.7,
+        domain: 0.2,
+        outcome: 0.1,
+        fuzzy: 0.5,
+    };
+    pub const DOMAIN_HEAVY: Self = Self {
+        text: 0.2,
+        domain: 0.7,
+        outcome: 0.1,
+        fuzzy: 0.5,
+    };
+
+    /// Look up a named preset such as `lexical-heavy` or `domain-heavy`.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "balanced" | "default" => Some(Self::BALANCED),
+            "lexical-heavy" => Some(Self::LEXICAL_HEAVY),
+            "domain-heavy" => Some(Self::DOMAIN_HEAVY),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ScoringProfile {
+    fn default() -> Self {
+        Self::BALANCED
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PatternsConfigToml {
+    profile: Option<String>,
+    weights: Option<ScoringProfile>,
+    signatures: Option<SignatureConfig>,
+    compile: Option<CompileConfigToml>,
+    tokenizer: Option<TokenizerConfigToml>,
+}
+
+/// The `[compile]` table of `patterns.toml`.
+#[derive(Debug, Default, Deserialize)]
+struct CompileConfigToml {
+    min_evidence: Option<usize>,
+    group_by: Option<GroupBy>,
+}
+
+/// The `[tokenizer]` table of `patterns.toml`.
+#[derive(Debug, Default, Deserialize)]
+struct TokenizerConfigToml {
+    stem: Option<bool>,
+    english_stopwords: Option<bool>,
+    #[serde(default)]
+    add_stopwords: Vec<String>,
+    #[serde(default)]
+    remove_stopwords: Vec<String>,
+}
+
+/// Load the scoring profile from `CODEX_HOME/patterns.toml`.
+///
+/// A missing file yields the balanced preset. When both `profile` and
+/// `[weights]` are set, the explicit weights win.
+pub fn load_scoring_profile(codex_home: &Path) -> anyhow::Result<ScoringProfile> {
+    match read_patterns_config(codex_home)? {
+        Some(contents) => parse_scoring_profile(&contents),
+        None => Ok(ScoringProfile::default()),
+    }
+}
+
+/// Load the `[signatures]` table from `CODEX_HOME/patterns.toml`. A missing
+/// file or table selects the hashed provider.
+pub fn load_signature_config(codex_home: &Path) -> anyhow::Result<SignatureConfig> {
+    let Some(contents) = read_patterns_config(codex_home)? else {
+        return Ok(SignatureConfig::default());
+    };
+    let config: PatternsConfigToml = toml::from_str(&contents)?;
+    Ok(config.signatures.unwrap_or_default())
+}
+
+/// Load `codex compile` options from the `[compile]` table of
+/// `CODEX_HOME/patterns.toml`. Unset keys keep their defaults.
+pub fn load_compile_options(codex_home: &Path) -> anyhow::Result<CompileOptions> {
+    match read_patterns_config(codex_home)? {
+        Some(contents) => parse_compile_options(&contents),
+        None => Ok(CompileOptions::default()),
+    }
+}
+
+fn parse_compile_options(contents: &str) -> anyhow::Result<CompileOptions> {
+    let config: PatternsConfigToml = toml::from_str(contents)?;
+    let compile = config.compile.unwrap_or_default();
+    let defaults = CompileOptions::default();
+    let min_evidence = compile.min_evidence.unwrap_or(defaults.min_evidence);
+    if min_evidence == 0 {
+        anyhow::bail!("[compile] min_evidence must be at least 1");
+    }
+    Ok(CompileOptions {
+        min_evidence,
+        group_by: compile.group_by.unwrap_or(defaults.group_by),
+    })
+}
+
+/// Load the shared tokenizer settings from the `[tokenizer]` table of
+/// `CODEX_HOME/patterns.toml`. A missing file or table keeps the defaults.
+pub fn load_text_normalizer(codex_home: &Path) -> anyhow::Result<TextNormalizer> {
+    match read_patterns_config(codex_home)? {
+        Some(contents) => parse_text_normalizer(&contents),
+        None => Ok(TextNormalizer::default()),
+    }
+}
+
+/// Install the `[tokenizer]` settings from `CODEX_HOME/patterns.toml` for
+/// every tokenizer user in this process, so pattern ranking, compiling,
+/// capture, and stats all split text the same way. Does nothing once
+/// settings are installed.
+pub fn install_text_normalizer(codex_home: &Path) -> anyhow::Result<()> {
+    // A second install keeps the first; tokens already compared must not
+    // change meaning mid-process.
+    let _ = set_configured_normalizer(load_text_normalizer(codex_home)?);
+    Ok(())
+}
+
+fn parse_text_normalizer(contents: &str) -> anyhow::Result<TextNormalizer> {
+    let config: PatternsConfigToml = toml::from_str(contents)?;
+    let tokenizer = config.tokenizer.unwrap_or_default();
+    let defaults = TextNormalizer::default();
+    Ok(TextNormalizer {
+        stem: tokenizer.stem.unwrap_or(defaults.stem),
+        english_stopwords: tokenizer
+            .english_stopwords
+            .unwrap_or(defaults.english_stopwords),
+        extra_stopwords: tokenizer.add_stopwords,
+        kept_stopwords: tokenizer.remove_stopwords,
+        ..defaults
+    })
+}
+
+fn read_patterns_config(codex_home: &Path) -> anyhow::Result<Option<String>> {
+    let path = codex_home.join(PATTERNS_CONFIG_FILENAME);
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(anyhow::anyhow!("failed to read {}: {err}", path.display())),
+    }
+}
+
+fn parse_scoring_profile(contents: &str) -> anyhow::Result<ScoringProfile> {
+    let config: PatternsConfigToml = toml::from_str(contents)?;
+    if let Some(weights) = config.weights {
+        return Ok(weights);
+    }
+    match config.profile {
+        Some(name) => ScoringProfile::preset(name.as_str())
+            .ok_or_else(|| anyhow::anyhow!("unknown scoring profile '{name}'")),
+        None => Ok(ScoringProfile::default()),
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -18,6 +225,64 @@ pub struct PatternDefinition {
     pub domain_signature: Vec<f64>,
     #[serde(default)]
     pub evidence_refs: Vec<String>,
+    /// Project namespace the pattern was stored under. `None` is treated as global.
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Lower bound of the Wilson interval for the pattern's dominant outcome.
+    #[serde(default)]
+    pub confidence: Option<f64>,
+    /// The pattern records a response that led to failure.
+    #[serde(default)]
+    pub anti_pattern: bool,
+    /// How the tests behind the pattern turned out. Events whose recorded
+    /// results agree with these get a higher outcome affinity.
+    #[serde(default)]
+    pub expected_outcomes: Vec<PatternTestResult>,
+    /// The events the pattern was compiled from, when the pattern carries
+    /// them.
+    #[serde(default)]
+    pub provenance: Option<PatternProvenance>,
+    /// Tags of the events the pattern was compiled from.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Events whose outcome contradicted the pattern's, most informative
+    /// first.
+    #[serde(default)]
+    pub counterexamples: Vec<PatternCounterexample>,
+}
+
+impl PatternDefinition {
+    /// A pattern to repeat that has failed before: at least one of its
+    /// counterexamples ended in failure. Anti-patterns are warnings already.
+    pub fn is_risky(&self) -> bool {
+        !self.anti_pattern
+            && self
+                .counterexamples
+                .iter()
+                .any(|counterexample| counterexample.outcome == Outcome::Failure)
+    }
+}
+
+impl From<CovenantPattern> for PatternDefinition {
+    /// A live pattern from the state store. An unparseable domain signature is
+    /// treated as empty.
+    fn from(pattern: CovenantPattern) -> Self {
+        Self {
+            domain_signature: serde_json::from_str(pattern.domain_signature.as_str())
+                .unwrap_or_default(),
+            id: pattern.id,
+            trigger: pattern.trigger,
+            invariant: pattern.invariant,
+            evidence_refs: pattern.evidence_refs,
+            namespace: Some(pattern.namespace),
+            confidence: None,
+            anti_pattern: pattern.anti_pattern,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: pattern.tags,
+            counterexamples: pattern.counterexamples,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +294,55 @@ pub struct PatternMatchEvent {
     pub domain_signature: Vec<f64>,
     #[serde(default)]
     pub tests: Vec<String>,
+    /// Results of the tests run for the event.
+    #[serde(default)]
+    pub test_results: Vec<PatternTestResult>,
+}
+
+/// A test, by id, and how it turned out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternTestResult {
+    pub id: String,
+    pub status: TestStatus,
+}
+
+/// Generate domain signatures for the event and for every pattern that has
+/// none. Hand-written signatures are kept as they are.
+pub fn fill_missing_signatures(
+    event: &mut PatternMatchEvent,
+    patterns: &mut [PatternDefinition],
+    provider: &dyn SignatureProvider,
+) -> anyhow::Result<()> {
+    if event.domain_signature.is_empty() {
+        event.domain_signature = generate_signature(provider, &event.trigger, &event.invariant)?;
+    }
+    for pattern in patterns {
+        if pattern.domain_signature.is_empty() {
+            pattern.domain_signature =
+                generate_signature(provider, &pattern.trigger, &pattern.invariant)?;
+        }
+    }
+    Ok(())
+}
+
+/// Controls which ranked matches [`rank_patterns`] returns.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MatchOptions {
+    /// Maximum number of matches to return. `None` returns every match.
+    pub top_k: Option<usize>,
+    /// Matches to skip, best first, before `top_k` are taken. Combined with
+    /// [`MatchPage::next_offset`] this pages through the matches.
+    pub offset: usize,
+    /// Drop matches whose total score is below this threshold. Matches with a
+    /// total of zero are always dropped.
+    pub min_score: f64,
+    /// Drop matches whose domain signature does not overlap the event's.
+    pub require_domain_overlap: bool,
+    /// Weights used to combine the component scores into a total.
+    pub scoring: ScoringProfile,
+    /// Patterns whose confidence is below this value have their total scaled
+    /// down proportionally. Patterns without a confidence are unaffected.
+    pub min_confidence: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -40,39 +354,201 @@ pub struct PatternMatchResult {
     pub outcome_affinity: f64,
     pub total: f64,
     pub rationale: String,
+    /// The match is a warning: the pattern's response is one to avoid.
+    pub anti_pattern: bool,
+    /// Values the event gave the placeholders of a templated trigger, such as
+    /// `path` for "Disk full on {path}". Empty when the pattern's trigger has
+    /// no placeholders or the event's trigger does not fit the template.
+    pub bindings: BTreeMap<String, String>,
+    /// Which words and signature dimensions the scores came from.
+    pub details: MatchDetails,
+}
+
+/// One page of ranked matches.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchPage {
+    /// The matches from [`MatchOptions::offset`] on, best first, at most
+    /// [`MatchOptions::top_k`] of them.
+    pub results: Vec<PatternMatchResult>,
+    /// Matches that passed every threshold, across all pages.
+    pub total_candidates: usize,
+    /// Offset of the next page, or `None` when this page is the last.
+    pub next_offset: Option<usize>,
+}
+
+/// The parts of the event and the pattern behind a match's scores, for
+/// highlighting them. Tokens are normalized the way they are scored (lowercased,
+/// stemmed, stopwords dropped), and each list keeps the order in which its
+/// tokens first appear in the trigger and invariant.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchDetails {
+    /// Tokens found in both, in event order.
+    pub shared_tokens: Vec<String>,
+    pub event_only_tokens: Vec<String>,
+    pub pattern_only_tokens: Vec<String>,
+    /// Each signature dimension's share of `domain_score`, which they sum to.
+    /// Dimensions past the shorter signature are left out, and the list is
+    /// empty when either signature is missing or all zeros.
+    pub domain_contributions: Vec<f64>,
+}
+
+impl MatchDetails {
+    fn new(
+        event_tokens: &[String],
+        pattern_tokens: &[String],
+        event_signature: &[f64],
+        pattern_signature: &[f64],
+    ) -> Self {
+        let event_set = event_tokens.iter().collect::<HashSet<_>>();
+        let pattern_set = pattern_tokens.iter().collect::<HashSet<_>>();
+        let mut shared_tokens = Vec::new();
+        let mut event_only_tokens = Vec::new();
+        for token in distinct(event_tokens) {
+            if pattern_set.contains(token) {
+                shared_tokens.push(token.clone());
+            } else {
+                event_only_tokens.push(token.clone());
+            }
+        }
+        let pattern_only_tokens = distinct(pattern_tokens)
+            .filter(|token| !event_set.contains(token))
+            .cloned()
+            .collect();
+        Self {
+            shared_tokens,
+            event_only_tokens,
+            pattern_only_tokens,
+            domain_contributions: domain_contributions(event_signature, pattern_signature),
+        }
+    }
+}
+
+/// `tokens` without repeats, in order of first appearance.
+fn distinct(tokens: &[String]) -> impl Iterator<Item = &String> {
+    let mut seen = HashSet::new();
+    tokens.iter().filter(move |token| seen.insert(*token))
 }
 
 pub fn rank_patterns(
     event: &PatternMatchEvent,
     patterns: &[PatternDefinition],
-    limit: usize,
+    options: &MatchOptions,
+) -> Vec<PatternMatchResult> {
+    rank_candidates(event, patterns, options, &HashMap::new()).results
+}
+
+/// Like [`rank_patterns`], but matches with equal totals are ordered by the
+/// patterns' recent usage (see [`PatternRollingStats::cmp_activity`]) before
+/// falling back to their ids. Patterns missing from `usage` rank as unused.
+pub fn rank_patterns_with_usage(
+    event: &PatternMatchEvent,
+    patterns: &[PatternDefinition],
+    options: &MatchOptions,
+    usage: &[PatternRollingStats],
 ) -> Vec<PatternMatchResult> {
+    rank_pattern_page(event, patterns, options, usage).results
+}
+
+/// [`rank_patterns_with_usage`], along with how many matches there are in
+/// all and where the next page starts.
+pub fn rank_pattern_page(
+    event: &PatternMatchEvent,
+    patterns: &[PatternDefinition],
+    options: &MatchOptions,
+    usage: &[PatternRollingStats],
+) -> MatchPage {
+    let usage = usage
+        .iter()
+        .map(|stats| (stats.pattern_id.as_str(), stats))
+        .collect();
+    rank_candidates(event, patterns, options, &usage)
+}
+
+fn rank_candidates(
+    event: &PatternMatchEvent,
+    patterns: &[PatternDefinition],
+    options: &MatchOptions,
+    usage: &HashMap<&str, &PatternRollingStats>,
+) -> MatchPage {
     let event_text = format!(
         "{trigger} {invariant}",
         trigger = event.trigger,
         invariant = event.invariant
     );
-    let event_tf = term_frequencies(&tokenize(&event_text));
+    let event_tokens = tokenize(&event_text);
+    let event_tf = term_frequencies(&event_tokens);
+    let event_trigger_short = tokenize(&event.trigger).len() < SHORT_TRIGGER_TOKENS;
+    // Templated pattern triggers are scored against the event's trigger with
+    // its variable spans replaced the same way, so "/var/log" and "/tmp" do
+    // not count against the match.
+    let templated_trigger =
+        templatize_trigger(&event.trigger).unwrap_or_else(|| event.trigger.clone());
+    let templated_tokens = tokenize(&format!(
+        "{templated_trigger} {invariant}",
+        invariant = event.invariant
+    ));
+    let templated_tf = term_frequencies(&templated_tokens);
+    let scoring = options.scoring;
 
     let mut results: Vec<PatternMatchResult> = patterns
         .iter()
         .map(|pattern| {
             let pattern_text =
                 format!("{trigger} {invariant}", trigger = pattern.trigger, invariant = pattern.invariant);
-            let text_score = cosine_similarity_tf(&event_tf, &term_frequencies(&tokenize(&pattern_text)));
+            let templated = has_placeholders(&pattern.trigger);
+            let (event_trigger, event_tokens, event_tf) = if templated {
+                (&templated_trigger, &templated_tokens, &templated_tf)
+            } else {
+                (&event.trigger, &event_tokens, &event_tf)
+            };
+            let bindings = if templated {
+                match_trigger_template(&pattern.trigger, &event.trigger).unwrap_or_default()
+            } else {
+                BTreeMap::new()
+            };
+            let pattern_tokens = tokenize(&pattern_text);
+            let mut text_score = cosine_similarity_tf(event_tf, &term_frequencies(&pattern_tokens));
+            if event_trigger_short || tokenize(&pattern.trigger).len() < SHORT_TRIGGER_TOKENS {
+                let fuzzy = scoring.fuzzy.clamp(0.0, 1.0);
+                text_score = text_score * (1.0 - fuzzy)
+                    + char_trigram_similarity(event_trigger, &pattern.trigger) * fuzzy;
+            }
             let domain_score = cosine_similarity_vec(&event.domain_signature, &pattern.domain_signature);
-            let outcome_affinity = outcome_affinity(&event.tests, &pattern.evidence_refs);
-            let total = (text_score * TEXT_WEIGHT
-                + domain_score * DOMAIN_WEIGHT
-                + outcome_affinity * OUTCOME_WEIGHT)
-                .clamp(0.0, 1.0);
-            let rationale = format!(
+            let outcome_affinity = outcome_affinity(event, pattern);
+            let confidence_factor = match (options.min_confidence, pattern.confidence) {
+                (Some(min_confidence), Some(confidence))
+                    if min_confidence > 0.0 && confidence < min_confidence =>
+                {
+                    confidence.max(0.0) / min_confidence
+                }
+                _ => 1.0,
+            };
+            let total = (text_score * scoring.text
+                + domain_score * scoring.domain
+                + outcome_affinity * scoring.outcome)
+                .clamp(0.0, 1.0)
+                * confidence_factor;
+            let mut rationale = format!(
                 "text={text_score:.2} domain={domain_score:.2} outcome_affinity={outcome_affinity:.2} total={total:.2}",
                 text_score = text_score,
                 domain_score = domain_score,
                 outcome_affinity = outcome_affinity,
                 total = total
             );
+            if let Some(confidence) = pattern.confidence {
+                rationale.push_str(&format!(" confidence={confidence:.2}"));
+            }
+            if pattern.anti_pattern {
+                rationale = format!("warning: anti-pattern, its response led to failure; {rationale}");
+            }
+            if pattern.is_risky() {
+                rationale.push_str(&format!(
+                    " risky: counterexamples {}",
+                    format_counterexamples(&pattern.counterexamples)
+                ));
+            }
             PatternMatchResult {
                 pattern_id: pattern.id.clone(),
                 text_score,
@@ -80,29 +556,77 @@ pub fn rank_patterns(
                 outcome_affinity,
                 total,
                 rationale,
+                anti_pattern: pattern.anti_pattern,
+                bindings,
+                details: MatchDetails::new(
+                    event_tokens,
+                    &pattern_tokens,
+                    &event.domain_signature,
+                    &pattern.domain_signature,
+                ),
             }
         })
+        .filter(|result| {
+            result.total > 0.0
+                && result.total >= options.min_score
+                && (!options.require_domain_overlap || result.domain_score > 0.0)
+        })
         .collect();
 
+    let unused = PatternRollingStats::default();
+    let activity = |id: &str| usage.get(id).copied().unwrap_or(&unused);
     results.sort_by(|left, right| {
         right
             .total
             .partial_cmp(&left.total)
             .unwrap_or(Ordering::Equal)
+            .then_with(|| activity(&right.pattern_id).cmp_activity(activity(&left.pattern_id)))
             .then_with(|| left.pattern_id.cmp(&right.pattern_id))
     });
 
-    if results.len() > limit {
-        results.truncate(limit);
+    let total_candidates = results.len();
+    let mut results = results.split_off(options.offset.min(total_candidates));
+    if let Some(top_k) = options.top_k {
+        results.truncate(top_k);
     }
+    let end = options.offset + results.len();
+    MatchPage {
+        next_offset: (end < total_candidates).then_some(end),
+        results,
+        total_candidates,
+    }
+}
 
-    results
+/// Select the patterns visible from `namespace`: patterns stored in that project
+/// namespace plus global (or unscoped) patterns.
+pub fn patterns_in_namespace(
+    patterns: &[PatternDefinition],
+    namespace: &str,
+) -> Vec<PatternDefinition> {
+    patterns
+        .iter()
+        .filter(|pattern| {
+            pattern
+                .namespace
+                .as_deref()
+                .is_none_or(|pattern_namespace| {
+                    pattern_namespace_visible(pattern_namespace, namespace)
+                })
+        })
+        .cloned()
+        .collect()
 }
 
-fn tokenize(text: &str) -> Vec<String> {
-    text.split(|ch: char| !ch.is_alphanumeric())
-        .filter(|token| !token.is_empty())
-        .map(|token| token.to_ascii_lowercase())
+/// Select the patterns carrying every one of `tags`, which should already be
+/// normalized with [`codex_state::normalize_event_tags`].
+pub fn patterns_with_tags(
+    patterns: &[PatternDefinition],
+    tags: &[String],
+) -> Vec<PatternDefinition> {
+    patterns
+        .iter()
+        .filter(|pattern| tags.iter().all(|tag| pattern.tags.contains(tag)))
+        .cloned()
         .collect()
 }
 
@@ -165,7 +689,77 @@ fn cosine_similarity_vec(left: &[f64], right: &[f64]) -> f64 {
     }
 }
 
-fn outcome_affinity(tests: &[String], evidence_refs: &[String]) -> f64 {
+/// Each dimension's term of [`cosine_similarity_vec`], so the terms sum to the
+/// similarity.
+fn domain_contributions(left: &[f64], right: &[f64]) -> Vec<f64> {
+    let len = left.len().min(right.len());
+    let norm = |values: &[f64]| values.iter().map(|value| value * value).sum::<f64>().sqrt();
+    let (left, right) = (&left[..len], &right[..len]);
+    let scale = norm(left) * norm(right);
+    if scale == 0.0 {
+        return Vec::new();
+    }
+    left.iter()
+        .zip(right)
+        .map(|(left, right)| left * right / scale)
+        .collect()
+}
+
+/// Pass/fail agreement between the event's test results and the pattern's
+/// expected outcomes. Without a decided test in common, falls back to how
+/// closely the event's test descriptions match the pattern's evidence.
+/// `e3 (failure, 2026-01-02, evidence://e3), ...`: each counterexample with
+/// its outcome, the day it was resolved, and its first evidence reference.
+fn format_counterexamples(counterexamples: &[PatternCounterexample]) -> String {
+    counterexamples
+        .iter()
+        .map(|counterexample| {
+            let resolved = chrono::DateTime::from_timestamp(counterexample.resolved_at, 0)
+                .map_or_else(
+                    || counterexample.resolved_at.to_string(),
+                    |time| time.format("%Y-%m-%d").to_string(),
+                );
+            match counterexample.evidence_refs.first() {
+                Some(evidence) => format!(
+                    "{} ({}, {resolved}, {evidence})",
+                    counterexample.event_id, counterexample.outcome
+                ),
+                None => format!(
+                    "{} ({}, {resolved})",
+                    counterexample.event_id, counterexample.outcome
+                ),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn outcome_affinity(event: &PatternMatchEvent, pattern: &PatternDefinition) -> f64 {
+    result_agreement(&event.test_results, &pattern.expected_outcomes)
+        .unwrap_or_else(|| test_text_affinity(&event.tests, &pattern.evidence_refs))
+}
+
+/// Share of the tests with a pass or fail status on both sides whose statuses
+/// agree, or `None` when there are no such tests. Ids compare case-insensitively.
+fn result_agreement(results: &[PatternTestResult], expected: &[PatternTestResult]) -> Option<f64> {
+    let mut compared = 0;
+    let mut agreed = 0;
+    for result in results.iter().filter(|result| result.status.is_decisive()) {
+        let Some(expected) = expected.iter().find(|expected| {
+            expected.status.is_decisive()
+                && expected.id.trim().eq_ignore_ascii_case(result.id.trim())
+        }) else {
+            continue;
+        };
+        compared += 1;
+        if expected.status == result.status {
+            agreed += 1;
+        }
+    }
+    (compared > 0).then(|| f64::from(agreed) / f64::from(compared))
+}
+
+fn test_text_affinity(tests: &[String], evidence_refs: &[String]) -> f64 {
     if tests.is_empty() || evidence_refs.is_empty() {
         return 0.0;
     }
@@ -183,10 +777,6 @@ fn outcome_affinity(tests: &[String], evidence_refs: &[String]) -> f64 {
     best
 }
 
-fn token_set(text: &str) -> HashSet<String> {
-    tokenize(text).into_iter().collect()
-}
-
 fn jaccard_similarity(left: &HashSet<String>, right: &HashSet<String>) -> f64 {
     if left.is_empty() || right.is_empty() {
         return 0.0;
@@ -213,6 +803,7 @@ mod tests {
             invariant: "missing import".to_string(),
             domain_signature: vec![1.0, 0.0, 0.0],
             tests: vec!["test_parser failed".to_string()],
+            test_results: Vec::new(),
         };
 
         let patterns = vec![
@@ -222,6 +813,13 @@ mod tests {
                 invariant: "missing import".to_string(),
                 domain_signature: vec![0.9, 0.1, 0.0],
                 evidence_refs: vec!["test_parser failed".to_string()],
+                namespace: None,
+                confidence: None,
+                anti_pattern: false,
+                expected_outcomes: Vec::new(),
+                provenance: None,
+                tags: Vec::new(),
+                counterexamples: Vec::new(),
             },
             PatternDefinition {
                 id: "pattern-b".to_string(),
@@ -229,10 +827,21 @@ mod tests {
                 invariant: "panic".to_string(),
                 domain_signature: vec![0.0, 1.0, 0.0],
                 evidence_refs: vec!["test_runtime failed".to_string()],
+                namespace: None,
+                confidence: None,
+                anti_pattern: false,
+                expected_outcomes: Vec::new(),
+                provenance: None,
+                tags: Vec::new(),
+                counterexamples: Vec::new(),
             },
         ];
 
-        let results = rank_patterns(&event, &patterns, 2);
+        let options = MatchOptions {
+            top_k: Some(2),
+            ..Default::default()
+        };
+        let results = rank_patterns(&event, &patterns, &options);
         let ids: Vec<&str> = results
             .iter()
             .map(|result| result.pattern_id.as_str())
@@ -240,6 +849,106 @@ mod tests {
         assert_eq!(ids, vec!["pattern-a", "pattern-b"]);
     }
 
+    #[test]
+    fn pages_report_total_candidates_and_next_offset() {
+        let event = PatternMatchEvent {
+            trigger: "disk full".to_string(),
+            invariant: "disk pressure".to_string(),
+            domain_signature: Vec::new(),
+            tests: Vec::new(),
+            test_results: Vec::new(),
+        };
+        let patterns = ["disk-a", "disk-b", "disk-c", "disk-d", "disk-e"]
+            .into_iter()
+            .map(|id| PatternDefinition {
+                id: id.to_string(),
+                trigger: "disk full".to_string(),
+                invariant: "disk pressure".to_string(),
+                domain_signature: Vec::new(),
+                evidence_refs: Vec::new(),
+                namespace: None,
+                confidence: None,
+                anti_pattern: false,
+                expected_outcomes: Vec::new(),
+                provenance: None,
+                tags: Vec::new(),
+                counterexamples: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        let page = |offset: usize| {
+            let page = rank_pattern_page(
+                &event,
+                &patterns,
+                &MatchOptions {
+                    top_k: Some(2),
+                    offset,
+                    ..Default::default()
+                },
+                &[],
+            );
+            (
+                page.results
+                    .into_iter()
+                    .map(|result| result.pattern_id)
+                    .collect::<Vec<_>>(),
+                page.total_candidates,
+                page.next_offset,
+            )
+        };
+
+        assert_eq!(
+            vec![page(0), page(2), page(4), page(9)],
+            vec![
+                (vec!["disk-a".to_string(), "disk-b".to_string()], 5, Some(2)),
+                (vec!["disk-c".to_string(), "disk-d".to_string()], 5, Some(4)),
+                (vec!["disk-e".to_string()], 5, None),
+                (Vec::new(), 5, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn match_details_split_tokens_and_domain_contributions() {
+        let event = PatternMatchEvent {
+            trigger: "Disk full on host".to_string(),
+            invariant: "cron disk".to_string(),
+            domain_signature: vec![3.0, 4.0],
+            tests: Vec::new(),
+            test_results: Vec::new(),
+        };
+        let pattern = PatternDefinition {
+            id: "disk".to_string(),
+            trigger: "disk full".to_string(),
+            invariant: "quota".to_string(),
+            domain_signature: vec![1.0, 0.0, 1.0],
+            evidence_refs: Vec::new(),
+            namespace: None,
+            confidence: None,
+            anti_pattern: false,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+        };
+
+        let results = rank_patterns(&event, &[pattern], &MatchOptions::default());
+        assert_eq!(
+            results
+                .into_iter()
+                .map(|result| (result.domain_score, result.details))
+                .collect::<Vec<_>>(),
+            vec![(
+                0.6,
+                MatchDetails {
+                    shared_tokens: vec!["disk".to_string(), "full".to_string()],
+                    event_only_tokens: vec!["host".to_string(), "cron".to_string()],
+                    pattern_only_tokens: vec!["quota".to_string()],
+                    domain_contributions: vec![0.6, 0.0],
+                },
+            )]
+        );
+    }
+
     #[test]
     fn ranking_returns_rationale_and_descending_totals() {
         let event = PatternMatchEvent {
@@ -247,6 +956,7 @@ mod tests {
             invariant: "session token expired".to_string(),
             domain_signature: vec![0.8, 0.2],
             tests: vec!["auth timeout integration test".to_string()],
+            test_results: Vec::new(),
         };
 
         let patterns = vec![
@@ -256,6 +966,13 @@ mod tests {
                 invariant: "session token expired".to_string(),
                 domain_signature: vec![0.9, 0.1],
                 evidence_refs: vec!["auth timeout integration test".to_string()],
+                namespace: None,
+                confidence: None,
+                anti_pattern: false,
+                expected_outcomes: Vec::new(),
+                provenance: None,
+                tags: Vec::new(),
+                counterexamples: Vec::new(),
             },
             PatternDefinition {
                 id: "weak-match".to_string(),
@@ -263,10 +980,21 @@ mod tests {
                 invariant: "css mismatch".to_string(),
                 domain_signature: vec![0.0, 1.0],
                 evidence_refs: vec!["ui snapshot".to_string()],
+                namespace: None,
+                confidence: None,
+                anti_pattern: false,
+                expected_outcomes: Vec::new(),
+                provenance: None,
+                tags: Vec::new(),
+                counterexamples: Vec::new(),
             },
         ];
 
-        let results = rank_patterns(&event, &patterns, 2);
+        let options = MatchOptions {
+            top_k: Some(2),
+            ..Default::default()
+        };
+        let results = rank_patterns(&event, &patterns, &options);
         assert_eq!(results.len(), 2);
         assert_eq!(results[0].pattern_id, "strong-match".to_string());
         assert_eq!(results[1].pattern_id, "weak-match".to_string());
@@ -293,6 +1021,717 @@ mod tests {
         );
     }
 
+    #[test]
+    fn match_options_drop_low_scores_and_missing_domain_overlap() {
+        let event = PatternMatchEvent {
+            trigger: "auth timeout".to_string(),
+            invariant: "session token expired".to_string(),
+            domain_signature: vec![1.0, 0.0],
+            tests: Vec::new(),
+            test_results: Vec::new(),
+        };
+        let pattern = |id: &str, trigger: &str, domain_signature: Vec<f64>| PatternDefinition {
+            id: id.to_string(),
+            trigger: trigger.to_string(),
+            invariant: "session token expired".to_string(),
+            domain_signature,
+            evidence_refs: Vec::new(),
+            namespace: None,
+            confidence: None,
+            anti_pattern: false,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+        };
+        let patterns = vec![
+            pattern("same-domain", "auth timeout", vec![1.0, 0.0]),
+            pattern("other-domain", "auth timeout", vec![0.0, 1.0]),
+            PatternDefinition {
+                invariant: "css mismatch".to_string(),
+                ..pattern("unrelated", "render glitch", vec![0.0, 1.0])
+            },
+        ];
+
+        let all = rank_patterns(&event, &patterns, &MatchOptions::default());
+        let ids: Vec<&str> = all
+            .iter()
+            .map(|result| result.pattern_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["same-domain", "other-domain"]);
+
+        let overlapping = rank_patterns(
+            &event,
+            &patterns,
+            &MatchOptions {
+                require_domain_overlap: true,
+                ..Default::default()
+            },
+        );
+        let ids: Vec<&str> = overlapping
+            .iter()
+            .map(|result| result.pattern_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["same-domain"]);
+
+        let thresholded = rank_patterns(
+            &event,
+            &patterns,
+            &MatchOptions {
+                min_score: 0.5,
+                ..Default::default()
+            },
+        );
+        let ids: Vec<&str> = thresholded
+            .iter()
+            .map(|result| result.pattern_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["same-domain"]);
+    }
+
+    #[test]
+    fn equal_scores_are_ordered_by_recent_usage() {
+        let event = PatternMatchEvent {
+            trigger: "auth timeout".to_string(),
+            invariant: "session token expired".to_string(),
+            domain_signature: Vec::new(),
+            tests: Vec::new(),
+            test_results: Vec::new(),
+        };
+        let pattern = |id: &str| PatternDefinition {
+            id: id.to_string(),
+            trigger: "auth timeout".to_string(),
+            invariant: "session token expired".to_string(),
+            domain_signature: Vec::new(),
+            evidence_refs: Vec::new(),
+            namespace: None,
+            confidence: None,
+            anti_pattern: false,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+        };
+        let patterns = vec![pattern("a-unused"), pattern("b-older"), pattern("c-busy")];
+        let usage = vec![
+            PatternRollingStats {
+                pattern_id: "b-older".to_string(),
+                matches_30d: 2,
+                last_matched_at: Some(10),
+                ..Default::default()
+            },
+            PatternRollingStats {
+                pattern_id: "c-busy".to_string(),
+                matches_30d: 2,
+                last_matched_at: Some(20),
+                ..Default::default()
+            },
+        ];
+
+        let ids = |results: Vec<PatternMatchResult>| {
+            results
+                .into_iter()
+                .map(|result| result.pattern_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            (
+                ids(rank_patterns(&event, &patterns, &MatchOptions::default())),
+                ids(rank_patterns_with_usage(
+                    &event,
+                    &patterns,
+                    &MatchOptions::default(),
+                    &usage
+                )),
+            ),
+            (
+                vec![
+                    "a-unused".to_string(),
+                    "b-older".to_string(),
+                    "c-busy".to_string()
+                ],
+                vec![
+                    "c-busy".to_string(),
+                    "b-older".to_string(),
+                    "a-unused".to_string()
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn scoring_profile_parses_presets_and_explicit_weights() {
+        assert_eq!(
+            parse_scoring_profile("").expect("empty config"),
+            ScoringProfile::BALANCED
+        );
+        assert_eq!(
+            parse_scoring_profile("profile = \"domain-heavy\"").expect("preset config"),
+            ScoringProfile::DOMAIN_HEAVY
+        );
+        assert_eq!(
+            parse_scoring_profile(
+                "profile = \"domain-heavy\"\n[weights]\ntext = 0.5\ndomain = 0.3\noutcome = 0.2\n"
+            )
+            .expect("weights config"),
+            ScoringProfile {
+                text: 0.5,
+                domain: 0.3,
+                outcome: 0.2,
+                fuzzy: 0.5,
+            }
+        );
+        assert_eq!(
+            parse_scoring_profile("profile = \"mystery\"").is_err(),
+            true
+        );
+        assert_eq!(
+            parse_scoring_profile("[weights]\ntext = 0.9\n")
+                .map_err(|err| err.to_string().contains("missing field `domain`")),
+            Err(true)
+        );
+    }
+
+    #[test]
+    fn compile_options_parse_from_the_compile_table() {
+        assert_eq!(
+            (
+                parse_compile_options("profile = \"balanced\"").expect("empty table"),
+                parse_compile_options("[compile]\nmin_evidence = 3\ngroup_by = \"signature\"\n")
+                    .expect("compile table"),
+                parse_compile_options("[compile]\nmin_evidence = 0\n").is_err(),
+                parse_compile_options("[compile]\ngroup_by = \"invariant\"\n").is_err(),
+            ),
+            (
+                CompileOptions::default(),
+                CompileOptions {
+                    min_evidence: 3,
+                    group_by: GroupBy::Signature,
+                },
+                true,
+                true,
+            )
+        );
+    }
+
+    #[test]
+    fn tokenizer_settings_parse_from_the_tokenizer_table() {
+        let normalizer = parse_text_normalizer(
+            "[tokenizer]\nstem = false\nadd_stopwords = [\"make\"]\nremove_stopwords = [\"how\"]\n",
+        )
+        .expect("tokenizer table");
+
+        assert_eq!(
+            (
+                parse_text_normalizer("profile = \"balanced\"").expect("empty table"),
+                normalizer.tokens("How to make the failing builds"),
+                parse_text_normalizer("[tokenizer]\nstem = \"no\"\n").is_err(),
+            ),
+            (
+                TextNormalizer::default(),
+                vec![
+                    "how".to_string(),
+                    "failing".to_string(),
+                    "builds".to_string()
+                ],
+                true,
+            )
+        );
+    }
+
+    #[test]
+    fn missing_signatures_are_generated_from_the_configured_provider() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        assert_eq!(
+            load_signature_config(codex_home.path()).expect("default config"),
+            SignatureConfig::default()
+        );
+        std::fs::write(
+            codex_home.path().join(PATTERNS_CONFIG_FILENAME),
+            "profile = \"balanced\"\n[signatures]\ndimensions = 8\n",
+        )
+        .expect("write patterns.toml");
+        let config = load_signature_config(codex_home.path()).expect("signature config");
+        let provider = crate::domain_signature::signature_provider(&config).expect("provider");
+
+        let mut event = PatternMatchEvent {
+            trigger: "disk full on /tmp".to_string(),
+            invariant: String::new(),
+            domain_signature: Vec::new(),
+            tests: Vec::new(),
+            test_results: Vec::new(),
+        };
+        let mut patterns = vec![
+            text_pattern("generated", "disk full on {path}", Vec::new()),
+            text_pattern("manual", "disk full", vec![1.0]),
+        ];
+        fill_missing_signatures(&mut event, &mut patterns, provider.as_ref())
+            .expect("fill signatures");
+
+        assert_eq!(
+            (
+                provider.name(),
+                event.domain_signature.len(),
+                patterns[0].domain_signature == event.domain_signature,
+                patterns[1].domain_signature.clone(),
+            ),
+            ("hashed-8".to_string(), 8, true, vec![1.0])
+        );
+    }
+
+    #[test]
+    fn lexical_profile_prefers_text_matches() {
+        let event = PatternMatchEvent {
+            trigger: "auth timeout".to_string(),
+            invariant: "session token expired".to_string(),
+            domain_signature: vec![1.0, 0.0],
+            tests: Vec::new(),
+            test_results: Vec::new(),
+        };
+        let patterns = vec![
+            PatternDefinition {
+                id: "same-text".to_string(),
+                trigger: "auth timeout".to_string(),
+                invariant: "session token expired".to_string(),
+                domain_signature: vec![0.0, 1.0],
+                evidence_refs: Vec::new(),
+                namespace: None,
+                confidence: None,
+                anti_pattern: false,
+                expected_outcomes: Vec::new(),
+                provenance: None,
+                tags: Vec::new(),
+                counterexamples: Vec::new(),
+            },
+            PatternDefinition {
+                id: "same-domain".to_string(),
+                trigger: "render glitch".to_string(),
+                invariant: "css mismatch".to_string(),
+                domain_signature: vec![1.0, 0.0],
+                evidence_refs: Vec::new(),
+                namespace: None,
+                confidence: None,
+                anti_pattern: false,
+                expected_outcomes: Vec::new(),
+                provenance: None,
+                tags: Vec::new(),
+                counterexamples: Vec::new(),
+            },
+        ];
+
+        let ranked_ids = |scoring: ScoringProfile| {
+            rank_patterns(
+                &event,
+                &patterns,
+                &MatchOptions {
+                    scoring,
+                    ..Default::default()
+                },
+            )
+            .into_iter()
+            .map(|result| result.pattern_id)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ranked_ids(ScoringProfile::LEXICAL_HEAVY),
+            vec!["same-text".to_string(), "same-domain".to_string()]
+        );
+        assert_eq!(
+            ranked_ids(ScoringProfile::DOMAIN_HEAVY),
+            vec!["same-domain".to_string(), "same-text".to_string()]
+        );
+    }
+
+    #[test]
+    fn namespace_filter_keeps_project_and_global_patterns() {
+        let pattern = |id: &str, namespace: Option<&str>| PatternDefinition {
+            id: id.to_string(),
+            trigger: "compile error".to_string(),
+            invariant: "missing import".to_string(),
+            domain_signature: Vec::new(),
+            evidence_refs: Vec::new(),
+            namespace: namespace.map(str::to_string),
+            confidence: None,
+            anti_pattern: false,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+        };
+        let patterns = vec![
+            pattern("unscoped", None),
+            pattern("global", Some("global")),
+            pattern("this-project", Some("project:aaaa")),
+            pattern("other-project", Some("project:bbbb")),
+        ];
+
+        let visible = patterns_in_namespace(&patterns, "project:aaaa");
+        let ids: Vec<&str> = visible.iter().map(|pattern| pattern.id.as_str()).collect();
+        assert_eq!(ids, vec!["unscoped", "global", "this-project"]);
+    }
+
+    #[test]
+    fn tag_filter_keeps_patterns_carrying_every_tag() {
+        let pattern = |id: &str, tags: &[&str]| PatternDefinition {
+            id: id.to_string(),
+            trigger: "compile error".to_string(),
+            invariant: "missing import".to_string(),
+            domain_signature: Vec::new(),
+            evidence_refs: Vec::new(),
+            namespace: None,
+            confidence: None,
+            anti_pattern: false,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: tags.iter().map(ToString::to_string).collect(),
+            counterexamples: Vec::new(),
+        };
+        let patterns = vec![
+            pattern("untagged", &[]),
+            pattern("infra", &["infra"]),
+            pattern("flaky-infra", &["infra", "flaky-test"]),
+        ];
+        let ids = |tags: &[&str]| {
+            patterns_with_tags(
+                &patterns,
+                &tags.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            )
+            .into_iter()
+            .map(|pattern| pattern.id)
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            (
+                ids(&[]).len(),
+                ids(&["infra"]),
+                ids(&["flaky-test", "infra"])
+            ),
+            (
+                3,
+                vec!["infra".to_string(), "flaky-infra".to_string()],
+                vec!["flaky-infra".to_string()],
+            )
+        );
+    }
+
+    #[test]
+    fn min_confidence_discounts_unreliable_patterns() {
+        let event = PatternMatchEvent {
+            trigger: "auth timeout".to_string(),
+            invariant: "session token expired".to_string(),
+            domain_signature: vec![1.0, 0.0],
+            tests: Vec::new(),
+            test_results: Vec::new(),
+        };
+        let pattern = |id: &str, confidence: Option<f64>| PatternDefinition {
+            id: id.to_string(),
+            trigger: "auth timeout".to_string(),
+            invariant: "session token expired".to_string(),
+            domain_signature: vec![1.0, 0.0],
+            evidence_refs: Vec::new(),
+            namespace: None,
+            confidence,
+            anti_pattern: false,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+        };
+        let patterns = vec![
+            pattern("reliable", Some(0.8)),
+            pattern("shaky", Some(0.2)),
+            pattern("unrated", None),
+        ];
+        let totals = |options: &MatchOptions| {
+            rank_patterns(&event, &patterns, options)
+                .into_iter()
+                .map(|result| (result.pattern_id, (result.total * 100.0).round() / 100.0))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            totals(&MatchOptions::default()),
+            vec![
+                ("reliable".to_string(), 0.9),
+                ("shaky".to_string(), 0.9),
+                ("unrated".to_string(), 0.9),
+            ]
+        );
+        assert_eq!(
+            totals(&MatchOptions {
+                min_confidence: Some(0.5),
+                ..Default::default()
+            }),
+            vec![
+                ("reliable".to_string(), 0.9),
+                ("unrated".to_string(), 0.9),
+                ("shaky".to_string(), 0.36),
+            ]
+        );
+    }
+
+    #[test]
+    fn anti_patterns_rank_as_warnings() {
+        let event = PatternMatchEvent {
+            trigger: "disk full".to_string(),
+            invariant: String::new(),
+            domain_signature: Vec::new(),
+            tests: Vec::new(),
+            test_results: Vec::new(),
+        };
+        let pattern = |id: &str, anti_pattern: bool| PatternDefinition {
+            id: id.to_string(),
+            trigger: "disk full".to_string(),
+            invariant: String::new(),
+            domain_signature: Vec::new(),
+            evidence_refs: Vec::new(),
+            namespace: None,
+            confidence: None,
+            anti_pattern,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+        };
+
+        let results = rank_patterns(
+            &event,
+            &[pattern("prune", false), pattern("reboot", true)],
+            &MatchOptions::default(),
+        );
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| (
+                    result.pattern_id.as_str(),
+                    result.anti_pattern,
+                    result.rationale.starts_with("warning: anti-pattern")
+                ))
+                .collect::<Vec<_>>(),
+            vec![("prune", false, false), ("reboot", true, true)]
+        );
+    }
+
+    #[test]
+    fn risky_patterns_list_their_counterexamples_in_the_rationale() {
+        let event = PatternMatchEvent {
+            trigger: "disk full".to_string(),
+            invariant: String::new(),
+            domain_signature: Vec::new(),
+            tests: Vec::new(),
+            test_results: Vec::new(),
+        };
+        let counterexample =
+            |event_id: &str, outcome: Outcome, evidence: &[&str]| PatternCounterexample {
+                event_id: event_id.to_string(),
+                outcome,
+                resolved_at: 1_767_225_600,
+                evidence_refs: evidence.iter().map(ToString::to_string).collect(),
+            };
+        let pattern = |id: &str, counterexamples: Vec<PatternCounterexample>| PatternDefinition {
+            id: id.to_string(),
+            trigger: "disk full".to_string(),
+            invariant: String::new(),
+            domain_signature: Vec::new(),
+            evidence_refs: Vec::new(),
+            namespace: None,
+            confidence: None,
+            anti_pattern: false,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: Vec::new(),
+            counterexamples,
+        };
+
+        let results = rank_patterns(
+            &event,
+            &[
+                pattern(
+                    "prune",
+                    vec![
+                        counterexample("e3", Outcome::Failure, &["evidence://e3"]),
+                        counterexample("e5", Outcome::Mixed, &[]),
+                    ],
+                ),
+                pattern("reboot", vec![counterexample("e7", Outcome::Mixed, &[])]),
+            ],
+            &MatchOptions::default(),
+        );
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| (
+                    result.pattern_id.as_str(),
+                    result
+                        .rationale
+                        .split_once(" risky: ")
+                        .map(|(_, risk)| risk),
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "prune",
+                    Some(
+                        "counterexamples e3 (failure, 2026-01-01, evidence://e3), e5 (mixed, 2026-01-01)"
+                    ),
+                ),
+                ("reboot", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn short_triggers_fall_back_to_character_similarity() {
+        let event = PatternMatchEvent {
+            trigger: "OOM".to_string(),
+            invariant: String::new(),
+            domain_signature: Vec::new(),
+            tests: Vec::new(),
+            test_results: Vec::new(),
+        };
+        let patterns = vec![PatternDefinition {
+            id: "oom-killed".to_string(),
+            trigger: "OOMKilled".to_string(),
+            invariant: String::new(),
+            domain_signature: Vec::new(),
+            evidence_refs: Vec::new(),
+            namespace: None,
+            confidence: None,
+            anti_pattern: false,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+        }];
+        let text_scores = |fuzzy: f64| {
+            let options = MatchOptions {
+                scoring: ScoringProfile {
+                    fuzzy,
+                    ..ScoringProfile::LEXICAL_HEAVY
+                },
+                ..Default::default()
+            };
+            rank_patterns(&event, &patterns, &options)
+                .into_iter()
+                .map(|result| {
+                    (
+                        result.pattern_id,
+                        (result.text_score * 100.0).round() / 100.0,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(text_scores(0.0), Vec::new());
+        assert_eq!(text_scores(0.5), vec![("oom-killed".to_string(), 0.17)]);
+        assert_eq!(text_scores(1.0), vec![("oom-killed".to_string(), 0.33)]);
+    }
+
+    fn text_pattern(id: &str, trigger: &str, domain_signature: Vec<f64>) -> PatternDefinition {
+        PatternDefinition {
+            id: id.to_string(),
+            trigger: trigger.to_string(),
+            invariant: String::new(),
+            domain_signature,
+            evidence_refs: Vec::new(),
+            namespace: None,
+            confidence: None,
+            anti_pattern: false,
+            expected_outcomes: Vec::new(),
+            provenance: None,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn templated_triggers_score_against_the_template_and_bind_values() {
+        let event = PatternMatchEvent {
+            trigger: "Disk full on /var/log".to_string(),
+            invariant: String::new(),
+            domain_signature: Vec::new(),
+            tests: Vec::new(),
+            test_results: Vec::new(),
+        };
+        let patterns = vec![
+            text_pattern("literal", "Disk full on /tmp", Vec::new()),
+            text_pattern("template", "Disk full on {path}", Vec::new()),
+        ];
+
+        let results = rank_patterns(&event, &patterns, &MatchOptions::default());
+
+        assert_eq!(
+            results
+                .into_iter()
+                .map(|result| (
+                    result.pattern_id,
+                    (result.text_score * 100.0).round() / 100.0,
+                    result.bindings
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "template".to_string(),
+                    1.0,
+                    BTreeMap::from([("path".to_string(), "/var/log".to_string())])
+                ),
+                ("literal".to_string(), 0.58, BTreeMap::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn outcome_affinity_compares_recorded_results_with_expected_outcomes() {
+        let result = |id: &str, status: TestStatus| PatternTestResult {
+            id: id.to_string(),
+            status,
+        };
+        let event = PatternMatchEvent {
+            trigger: "disk full".to_string(),
+            invariant: "disk pressure".to_string(),
+            domain_signature: Vec::new(),
+            tests: vec!["df shows the volume full".to_string()],
+            test_results: serde_json::from_str(
+                r#"[{"id":"df-check","status":"fail"},{"id":"log-rotation","status":"pass"},{"id":"inode-count","status":"inconclusive"}]"#,
+            )
+            .expect("parse test results"),
+        };
+        let pattern = |expected_outcomes: Vec<PatternTestResult>| PatternDefinition {
+            evidence_refs: vec!["df shows the volume full".to_string()],
+            expected_outcomes,
+            ..text_pattern("disk", "disk full", Vec::new())
+        };
+
+        let affinities = [
+            pattern(vec![
+                result("DF-CHECK", TestStatus::Fail),
+                result("log-rotation", TestStatus::Fail),
+                result("inode-count", TestStatus::Pass),
+            ]),
+            pattern(vec![result("df-check", TestStatus::Fail)]),
+            pattern(vec![result("inode-count", TestStatus::Fail)]),
+            pattern(Vec::new()),
+        ]
+        .iter()
+        .map(|pattern| outcome_affinity(&event, pattern))
+        .collect::<Vec<_>>();
+
+        assert_eq!(affinities, vec![0.5, 1.0, 1.0, 1.0]);
+        assert_eq!(
+            outcome_affinity(
+                &PatternMatchEvent {
+                    tests: Vec::new(),
+                    ..event.clone()
+                },
+                &pattern(vec![result("log-rotation", TestStatus::Fail)]),
+            ),
+            0.0
+        );
+    }
+
     #[test]
     fn empty_domain_signature_scores_zero() {
         let score = cosine_similarity_vec(&[], &[1.0, 0.5]);
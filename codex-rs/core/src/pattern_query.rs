@@ -0,0 +1,240 @@
+//! Pattern queries from clients.
+//!
+//! `Op::QueryPatterns` ranks the live patterns against a described situation
+//! the way `codex patterns-match` does, so client UIs can show similar past
+//! incidents next to a session. Patterns are limited to those visible from the
+//! session's project unless the request asks for every project.
+
+use crate::domain_signature::SignatureProvider;
+use crate::domain_signature::signature_provider;
+use crate::pattern_match::MatchOptions;
+use crate::pattern_match::PatternDefinition;
+use crate::pattern_match::PatternMatchEvent;
+use crate::pattern_match::ScoringProfile;
+use crate::pattern_match::fill_missing_signatures;
+use crate::pattern_match::load_scoring_profile;
+use crate::pattern_match::load_signature_config;
+use crate::pattern_match::patterns_in_namespace;
+use crate::pattern_match::rank_pattern_page;
+use codex_protocol::protocol::PatternQueryMatch;
+use codex_protocol::protocol::PatternQueryRequest;
+use codex_protocol::protocol::PatternQueryResponseEvent;
+use codex_state::CovenantPattern;
+use codex_state::PatternNamespaces;
+use codex_state::StateRuntime;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Matches returned when the request sets no limit.
+const DEFAULT_QUERY_LIMIT: usize = 5;
+
+/// Rank the live patterns against `request`, best match first.
+pub(crate) async fn query_patterns(
+    state_db: &StateRuntime,
+    namespaces: &PatternNamespaces,
+    cwd: &Path,
+    scope: &str,
+    codex_home: &Path,
+    request: &PatternQueryRequest,
+) -> anyhow::Result<PatternQueryResponseEvent> {
+    let no_matches = PatternQueryResponseEvent {
+        matches: Vec::new(),
+        total_candidates: 0,
+        next_offset: None,
+    };
+    if request.trigger.trim().is_empty() && request.invariant.trim().is_empty() {
+        return Ok(no_matches);
+    }
+    let patterns = state_db.list_covenant_patterns(Some(scope)).await?;
+    if patterns.is_empty() {
+        return Ok(no_matches);
+    }
+    let namespace = if request.global {
+        None
+    } else {
+        Some(namespaces.resolve(cwd).await?)
+    };
+    let scoring = load_scoring_profile(codex_home)?;
+    let provider = signature_provider(&load_signature_config(codex_home)?)?;
+    pattern_query_matches(
+        request,
+        namespace.as_deref(),
+        patterns,
+        scoring,
+        provider.as_ref(),
+    )
+}
+
+/// The page of matches for `request` among `patterns`, limited to those
+/// visible from `namespace` when one is given.
+pub(crate) fn pattern_query_matches(
+    request: &PatternQueryRequest,
+    namespace: Option<&str>,
+    patterns: Vec<CovenantPattern>,
+    scoring: ScoringProfile,
+    provider: &dyn SignatureProvider,
+) -> anyhow::Result<PatternQueryResponseEvent> {
+    let definitions = patterns
+        .iter()
+        .cloned()
+        .map(PatternDefinition::from)
+        .collect::<Vec<_>>();
+    let mut definitions = match namespace {
+        Some(namespace) => patterns_in_namespace(&definitions, namespace),
+        None => definitions,
+    };
+    let mut event = PatternMatchEvent {
+        trigger: request.trigger.clone(),
+        invariant: request.invariant.clone(),
+        domain_signature: Vec::new(),
+        tests: Vec::new(),
+        test_results: Vec::new(),
+    };
+    fill_missing_signatures(&mut event, &mut definitions, provider)?;
+    let options = MatchOptions {
+        top_k: Some(request.limit.unwrap_or(DEFAULT_QUERY_LIMIT)),
+        offset: request.offset.unwrap_or_default(),
+        min_score: request.min_score.unwrap_or_default(),
+        scoring,
+        ..MatchOptions::default()
+    };
+
+    let by_id = patterns
+        .iter()
+        .map(|pattern| (pattern.id.as_str(), pattern))
+        .collect::<HashMap<_, _>>();
+    let page = rank_pattern_page(&event, &definitions, &options, &[]);
+    let matches = page
+        .results
+        .into_iter()
+        .filter_map(|result| {
+            let pattern = by_id.get(result.pattern_id.as_str())?;
+            Some(PatternQueryMatch {
+                pattern_id: result.pattern_id,
+                trigger: pattern.trigger.clone(),
+                invariant: pattern.invariant.clone(),
+                best_response: pattern.best_response.clone(),
+                counterexample: (!pattern.counterexample.trim().is_empty())
+                    .then(|| pattern.counterexample.clone()),
+                anti_pattern: result.anti_pattern,
+                score: result.total,
+                rationale: result.rationale,
+            })
+        })
+        .collect();
+    Ok(PatternQueryResponseEvent {
+        matches,
+        total_candidates: page.total_candidates,
+        next_offset: page.next_offset,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain_signature::SignatureConfig;
+    use pretty_assertions::assert_eq;
+
+    fn pattern(id: &str, trigger: &str, namespace: &str) -> CovenantPattern {
+        CovenantPattern {
+            id: id.to_string(),
+            trigger: trigger.to_string(),
+            invariant: "disk pressure".to_string(),
+            counterexample: String::new(),
+            best_response: "prune old logs".to_string(),
+            domain_signature: "[]".to_string(),
+            evidence_refs: Vec::new(),
+            namespace: namespace.to_string(),
+            scope: "default".to_string(),
+            created_at: 1,
+            anti_pattern: false,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+            supports: 0,
+            contradictions: 0,
+        }
+    }
+
+    #[test]
+    fn queries_return_visible_matches_with_their_responses() {
+        let patterns = vec![
+            pattern("disk", "disk full on build host", "global"),
+            pattern("other-project", "disk full on build host", "project:bbbb"),
+            CovenantPattern {
+                invariant: "renewal job failed".to_string(),
+                ..pattern("cert", "certificate expired", "global")
+            },
+            CovenantPattern {
+                best_response: "reboot the host".to_string(),
+                counterexample: "reboot did not free space".to_string(),
+                anti_pattern: true,
+                ..pattern("reboot", "disk full on build host", "global")
+            },
+        ];
+        let provider = signature_provider(&SignatureConfig::default()).expect("provider");
+        let request = PatternQueryRequest {
+            trigger: "disk full on build host".to_string(),
+            invariant: "disk pressure".to_string(),
+            min_score: Some(0.3),
+            ..PatternQueryRequest::default()
+        };
+        let summary = |namespace: Option<&str>| {
+            let mut matches = pattern_query_matches(
+                &request,
+                namespace,
+                patterns.clone(),
+                ScoringProfile::default(),
+                provider.as_ref(),
+            )
+            .expect("query patterns")
+            .matches;
+            assert!(matches.iter().all(|found| {
+                found.score >= 0.3
+                    && found
+                        .rationale
+                        .contains(&format!("total={:.2}", found.score))
+            }));
+            matches.sort_by(|left, right| left.pattern_id.cmp(&right.pattern_id));
+            matches
+                .into_iter()
+                .map(|found| {
+                    (
+                        found.pattern_id,
+                        found.best_response,
+                        found.counterexample,
+                        found.anti_pattern,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let disk = (
+            "disk".to_string(),
+            "prune old logs".to_string(),
+            None,
+            false,
+        );
+        let reboot = (
+            "reboot".to_string(),
+            "reboot the host".to_string(),
+            Some("reboot did not free space".to_string()),
+            true,
+        );
+        assert_eq!(
+            (summary(Some("project:aaaa")), summary(None)),
+            (
+                vec![disk.clone(), reboot.clone()],
+                vec![
+                    disk,
+                    (
+                        "other-project".to_string(),
+                        "prune old logs".to_string(),
+                        None,
+                        false,
+                    ),
+                    reboot,
+                ],
+            )
+        );
+    }
+}
@@ -0,0 +1,487 @@
+//! Usage log for matched patterns.
+//!
+//! Every match reported by `codex patterns-match` is appended to
+//! `CODEX_HOME/pattern_usage.jsonl` under a fresh match id. Feedback records
+//! later mark whether the suggested response was used and how the event
+//! turned out, so pattern effectiveness can be measured across sessions.
+//! Uses reported by the `pattern_feedback` tool, which need no match, are
+//! logged under their own match id. With a store key, each line is sealed
+//! like the pattern logs, since triggers quote the events they came from.
+
+use anyhow::Context;
+use codex_protocol::outcome::Outcome;
+use codex_state::JsonlAppender;
+use codex_state::JsonlAppenderOptions;
+use codex_state::StoreCipher;
+use codex_state::open_line;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
+
+/// File under `CODEX_HOME` holding the pattern usage log.
+pub const PATTERN_USAGE_FILENAME: &str = "pattern_usage.jsonl";
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PatternUsageRecord {
+    /// A pattern was suggested for an event.
+    Match {
+        match_id: String,
+        pattern_id: String,
+        score: f64,
+        event_trigger: String,
+        recorded_at: i64,
+    },
+    /// Whether a suggestion was used, and how the event turned out.
+    Feedback {
+        match_id: String,
+        accepted: bool,
+        #[serde(default)]
+        outcome: Option<Outcome>,
+        recorded_at: i64,
+    },
+    /// A pattern's best response was applied without a recorded match, and
+    /// this is how it turned out.
+    Applied {
+        match_id: String,
+        pattern_id: String,
+        outcome: Outcome,
+        recorded_at: i64,
+    },
+}
+
+impl PatternUsageRecord {
+    /// A match record with a newly generated match id.
+    pub fn new_match(pattern_id: &str, score: f64, event_trigger: &str, recorded_at: i64) -> Self {
+        Self::Match {
+            match_id: Uuid::new_v4().to_string(),
+            pattern_id: pattern_id.to_string(),
+            score,
+            event_trigger: event_trigger.to_string(),
+            recorded_at,
+        }
+    }
+
+    /// A record of an applied best response with a newly generated match id.
+    pub fn new_applied(pattern_id: &str, outcome: Outcome, recorded_at: i64) -> Self {
+        Self::Applied {
+            match_id: Uuid::new_v4().to_string(),
+            pattern_id: pattern_id.to_string(),
+            outcome,
+            recorded_at,
+        }
+    }
+
+    pub fn match_id(&self) -> &str {
+        match self {
+            Self::Match { match_id, .. }
+            | Self::Feedback { match_id, .. }
+            | Self::Applied { match_id, .. } => match_id,
+        }
+    }
+}
+
+/// Whether `outcome`, reported after applying a pattern's best response,
+/// supports the pattern (`Some(true)`) or contradicts it (`Some(false)`).
+/// An anti-pattern is supported when its response made things worse again.
+/// Mixed and unknown outcomes count either way.
+pub fn outcome_supports(anti_pattern: bool, outcome: Outcome) -> Option<bool> {
+    match outcome {
+        Outcome::Success => Some(!anti_pattern),
+        Outcome::Failure => Some(anti_pattern),
+        Outcome::Mixed | Outcome::Unknown | Outcome::Other => None,
+    }
+}
+
+/// Per-pattern summary of the usage log.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PatternEffectiveness {
+    pub pattern_id: String,
+    pub matches: usize,
+    pub mean_score: f64,
+    /// Matches whose suggestion was used.
+    pub accepted: usize,
+    /// Matches with feedback saying the suggestion was not used.
+    pub rejected: usize,
+    /// Uses reported without a match.
+    pub reported: usize,
+    /// Accepted matches and reported uses that ended in success.
+    pub successes: usize,
+    /// Accepted matches and reported uses that ended in failure.
+    pub failures: usize,
+}
+
+impl PatternEffectiveness {
+    /// Share of matches with feedback whose suggestion was used.
+    pub fn acceptance_rate(&self) -> Option<f64> {
+        let reviewed = self.accepted + self.rejected;
+        (reviewed > 0).then_some(self.accepted as f64 / reviewed as f64)
+    }
+
+    /// Share of accepted matches and reported uses with a success or failure
+    /// outcome that ended in success.
+    pub fn success_rate(&self) -> Option<f64> {
+        let decided = self.successes + self.failures;
+        (decided > 0).then_some(self.successes as f64 / decided as f64)
+    }
+}
+
+/// Recent activity of one pattern, as of a point in time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PatternRollingStats {
+    pub pattern_id: String,
+    /// Matches recorded in the last 7 days.
+    pub matches_7d: usize,
+    /// Matches recorded in the last 30 days.
+    pub matches_30d: usize,
+    /// Share of the last 30 days' matches with feedback whose suggestion was
+    /// used, or `None` when none of them has feedback.
+    pub acceptance_rate: Option<f64>,
+    /// When the pattern was last matched, in seconds since the epoch.
+    pub last_matched_at: Option<i64>,
+}
+
+impl PatternRollingStats {
+    /// Ordering used to break ties between equally scored matches: more
+    /// matches in the last 30 days first, then the more recently matched.
+    pub fn cmp_activity(&self, other: &Self) -> std::cmp::Ordering {
+        self.matches_30d
+            .cmp(&other.matches_30d)
+            .then_with(|| self.last_matched_at.cmp(&other.last_matched_at))
+    }
+}
+
+pub fn append_usage(
+    path: &Path,
+    records: &[PatternUsageRecord],
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    let mut log =
+        JsonlAppender::open(path, JsonlAppenderOptions::default())?.with_cipher(cipher.cloned());
+    for record in records {
+        log.append(record)?;
+    }
+    log.finish()
+}
+
+/// Read the usage log, opening sealed lines with `cipher`. A missing file is
+/// an empty log.
+pub fn read_usage(
+    path: &Path,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<Vec<PatternUsageRecord>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", path.display()));
+        }
+    };
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let line = open_line(line, cipher)
+                .with_context(|| format!("failed to read {}:{}", path.display(), index + 1))?;
+            serde_json::from_str(&line)
+                .with_context(|| format!("failed to parse {}:{}", path.display(), index + 1))
+        })
+        .collect()
+}
+
+/// Summarize the log per pattern, ordered by pattern id. When a match has
+/// several feedback records the latest one wins; feedback for unknown match
+/// ids is ignored. A pattern only reported on has a mean score of zero.
+pub fn summarize_usage(records: &[PatternUsageRecord]) -> Vec<PatternEffectiveness> {
+    let mut feedback: HashMap<&str, (bool, Option<Outcome>)> = HashMap::new();
+    for record in records {
+        if let PatternUsageRecord::Feedback {
+            match_id,
+            accepted,
+            outcome,
+            ..
+        } = record
+        {
+            feedback.insert(match_id.as_str(), (*accepted, *outcome));
+        }
+    }
+
+    let mut summaries: BTreeMap<&str, (PatternEffectiveness, f64)> = BTreeMap::new();
+    for record in records {
+        let pattern_id = match record {
+            PatternUsageRecord::Match { pattern_id, .. }
+            | PatternUsageRecord::Applied { pattern_id, .. } => pattern_id,
+            PatternUsageRecord::Feedback { .. } => continue,
+        };
+        let (summary, score_sum) = summaries.entry(pattern_id.as_str()).or_insert_with(|| {
+            (
+                PatternEffectiveness {
+                    pattern_id: pattern_id.clone(),
+                    ..Default::default()
+                },
+                0.0,
+            )
+        });
+        let outcome = match record {
+            PatternUsageRecord::Match {
+                match_id, score, ..
+            } => {
+                summary.matches += 1;
+                *score_sum += score;
+                match feedback.get(match_id.as_str()) {
+                    Some((true, outcome)) => {
+                        summary.accepted += 1;
+                        *outcome
+                    }
+                    Some((false, _)) => {
+                        summary.rejected += 1;
+                        None
+                    }
+                    None => None,
+                }
+            }
+            PatternUsageRecord::Applied { outcome, .. } => {
+                summary.reported += 1;
+                Some(*outcome)
+            }
+            PatternUsageRecord::Feedback { .. } => None,
+        };
+        match outcome {
+            Some(Outcome::Success) => summary.successes += 1,
+            Some(Outcome::Failure) => summary.failures += 1,
+            Some(Outcome::Mixed | Outcome::Unknown | Outcome::Other) | None => {}
+        }
+    }
+
+    summaries
+        .into_values()
+        .map(|(mut summary, score_sum)| {
+            if summary.matches > 0 {
+                summary.mean_score = score_sum / summary.matches as f64;
+            }
+            summary
+        })
+        .collect()
+}
+
+/// Rolling statistics per pattern as of `now`, ordered by pattern id. Every
+/// pattern matched at least once is listed, even when its matches are older
+/// than the windows. Feedback is resolved as in [`summarize_usage`].
+pub fn rolling_stats(records: &[PatternUsageRecord], now: i64) -> Vec<PatternRollingStats> {
+    let mut feedback: HashMap<&str, bool> = HashMap::new();
+    for record in records {
+        if let PatternUsageRecord::Feedback {
+            match_id, accepted, ..
+        } = record
+        {
+            feedback.insert(match_id.as_str(), *accepted);
+        }
+    }
+
+    let week_start = now.saturating_sub(7 * SECONDS_PER_DAY);
+    let month_start = now.saturating_sub(30 * SECONDS_PER_DAY);
+    let mut stats: BTreeMap<&str, (PatternRollingStats, usize, usize)> = BTreeMap::new();
+    for record in records {
+        let PatternUsageRecord::Match {
+            match_id,
+            pattern_id,
+            recorded_at,
+            ..
+        } = record
+        else {
+            continue;
+        };
+        let (entry, accepted, reviewed) = stats.entry(pattern_id.as_str()).or_insert_with(|| {
+            (
+                PatternRollingStats {
+                    pattern_id: pattern_id.clone(),
+                    ..Default::default()
+                },
+                0,
+                0,
+            )
+        });
+        entry.last_matched_at = entry.last_matched_at.max(Some(*recorded_at));
+        if *recorded_at <= now && *recorded_at > month_start {
+            entry.matches_30d += 1;
+            if *recorded_at > week_start {
+                entry.matches_7d += 1;
+            }
+            if let Some(was_accepted) = feedback.get(match_id.as_str()) {
+                *reviewed += 1;
+                if *was_accepted {
+                    *accepted += 1;
+                }
+            }
+        }
+    }
+
+    stats
+        .into_values()
+        .map(|(mut entry, accepted, reviewed)| {
+            entry.acceptance_rate = (reviewed > 0).then_some(accepted as f64 / reviewed as f64);
+            entry
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn matched(match_id: &str, pattern_id: &str, score: f64) -> PatternUsageRecord {
+        PatternUsageRecord::Match {
+            match_id: match_id.to_string(),
+            pattern_id: pattern_id.to_string(),
+            score,
+            event_trigger: "disk full".to_string(),
+            recorded_at: 1,
+        }
+    }
+
+    fn feedback(match_id: &str, accepted: bool, outcome: Option<Outcome>) -> PatternUsageRecord {
+        PatternUsageRecord::Feedback {
+            match_id: match_id.to_string(),
+            accepted,
+            outcome,
+            recorded_at: 2,
+        }
+    }
+
+    #[test]
+    fn summary_counts_latest_feedback_per_match_and_reported_uses() {
+        let records = vec![
+            matched("m1", "prune-logs", 0.8),
+            matched("m2", "prune-logs", 0.6),
+            matched("m3", "prune-logs", 0.4),
+            matched("m4", "reboot", 0.5),
+            feedback("m1", true, Some(Outcome::Failure)),
+            feedback("m1", true, Some(Outcome::Success)),
+            feedback("m2", true, Some(Outcome::Failure)),
+            feedback("m4", false, None),
+            feedback("unknown", true, Some(Outcome::Success)),
+            PatternUsageRecord::new_applied("reboot", Outcome::Success, 3),
+            PatternUsageRecord::new_applied("flush-dns", Outcome::Mixed, 3),
+        ];
+
+        assert_eq!(
+            summarize_usage(&records),
+            vec![
+                PatternEffectiveness {
+                    pattern_id: "flush-dns".to_string(),
+                    reported: 1,
+                    ..Default::default()
+                },
+                PatternEffectiveness {
+                    pattern_id: "prune-logs".to_string(),
+                    matches: 3,
+                    mean_score: (0.8 + 0.6 + 0.4) / 3.0,
+                    accepted: 2,
+                    rejected: 0,
+                    reported: 0,
+                    successes: 1,
+                    failures: 1,
+                },
+                PatternEffectiveness {
+                    pattern_id: "reboot".to_string(),
+                    matches: 1,
+                    mean_score: 0.5,
+                    accepted: 0,
+                    rejected: 1,
+                    reported: 1,
+                    successes: 1,
+                    failures: 0,
+                },
+            ]
+        );
+        assert_eq!(
+            [
+                outcome_supports(false, Outcome::Success),
+                outcome_supports(false, Outcome::Failure),
+                outcome_supports(true, Outcome::Failure),
+                outcome_supports(true, Outcome::Mixed),
+            ],
+            [Some(true), Some(false), Some(true), None]
+        );
+    }
+
+    #[test]
+    fn rolling_stats_count_matches_inside_each_window() {
+        let now = 100 * SECONDS_PER_DAY;
+        let at = |match_id: &str, pattern_id: &str, days_ago: i64| PatternUsageRecord::Match {
+            match_id: match_id.to_string(),
+            pattern_id: pattern_id.to_string(),
+            score: 0.5,
+            event_trigger: "disk full".to_string(),
+            recorded_at: now - days_ago * SECONDS_PER_DAY,
+        };
+        let records = vec![
+            at("m1", "prune-logs", 1),
+            at("m2", "prune-logs", 10),
+            at("m3", "prune-logs", 40),
+            at("m4", "reboot", 45),
+            feedback("m1", true, None),
+            feedback("m2", false, None),
+            feedback("m3", true, None),
+        ];
+
+        assert_eq!(
+            rolling_stats(&records, now),
+            vec![
+                PatternRollingStats {
+                    pattern_id: "prune-logs".to_string(),
+                    matches_7d: 1,
+                    matches_30d: 2,
+                    acceptance_rate: Some(0.5),
+                    last_matched_at: Some(now - SECONDS_PER_DAY),
+                },
+                PatternRollingStats {
+                    pattern_id: "reboot".to_string(),
+                    matches_7d: 0,
+                    matches_30d: 0,
+                    acceptance_rate: None,
+                    last_matched_at: Some(now - 45 * SECONDS_PER_DAY),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn records_round_trip_through_the_log() {
+        let path = std::env::temp_dir()
+            .join(format!("pattern-usage-{}", Uuid::new_v4()))
+            .join(PATTERN_USAGE_FILENAME);
+        let records = vec![
+            matched("m1", "prune-logs", 0.8),
+            feedback("m1", true, Some(Outcome::Success)),
+        ];
+
+        let cipher = StoreCipher::generate();
+        append_usage(&path, &records[..1], None).expect("append plaintext usage");
+        append_usage(&path, &records[1..], Some(&cipher)).expect("append sealed usage");
+
+        assert_eq!(
+            (
+                read_usage(&path, Some(&cipher)).expect("read usage"),
+                std::fs::read_to_string(&path)
+                    .expect("read log")
+                    .contains("\"m1\""),
+            ),
+            (records, true)
+        );
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::remove_dir_all(parent);
+        }
+    }
+}
@@ -0,0 +1,988 @@
+//! Pattern mining over resolved covenant events.
+//!
+//! Events that share a normalized trigger and invariant are grouped (see
+//! [`GroupBy`] for the alternatives), and each group with enough supporting
+//! evidence is compiled into a [`Pattern`] that records the dominant outcome,
+//! the best known response, and counterexamples when the group's outcomes
+//! disagree.
+//!
+//! Triggers are grouped after their numbers, paths, and quoted strings are
+//! replaced with placeholders (see [`crate::trigger_template`]), so the
+//! compiled trigger is a template whenever the events had variable spans.
+
+use crate::domain_signature::SignatureProvider;
+use crate::domain_signature::generate_signature;
+use crate::trigger_template::templatize_trigger;
+pub use codex_protocol::outcome::Outcome;
+pub use codex_state::PatternCounterexample;
+use codex_state::canonical_json;
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use sha2::Digest;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::collections::btree_map;
+
+/// Minimum number of events a group needs before it becomes a pattern,
+/// unless [`CompileOptions::min_evidence`] says otherwise.
+pub const MIN_EVIDENCE_COUNT: usize = 2;
+
+/// Signature components are rounded to this many decimals before events are
+/// grouped by signature, so near-identical signatures share a group.
+const SIGNATURE_GROUP_DECIMALS: i32 = 1;
+
+/// What events must share to be compiled into the same pattern.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum GroupBy {
+    /// The normalized trigger template, whatever the invariant.
+    #[serde(rename = "trigger")]
+    Trigger,
+    /// The normalized trigger template and invariant.
+    #[default]
+    #[serde(rename = "trigger+invariant")]
+    TriggerInvariant,
+    /// The domain signature, rounded. Events without one fall back to
+    /// `trigger+invariant`.
+    #[serde(rename = "signature")]
+    Signature,
+}
+
+impl GroupBy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Trigger => "trigger",
+            Self::TriggerInvariant => "trigger+invariant",
+            Self::Signature => "signature",
+        }
+    }
+}
+
+impl std::str::FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "trigger" => Ok(Self::Trigger),
+            "trigger+invariant" => Ok(Self::TriggerInvariant),
+            "signature" => Ok(Self::Signature),
+            _ => Err(format!(
+                "unknown grouping '{value}' (expected trigger, trigger+invariant, or signature)"
+            )),
+        }
+    }
+}
+
+/// How aggressively [`compile_patterns`] mines events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompileOptions {
+    /// Fewest events a group needs to become a pattern.
+    pub min_evidence: usize,
+    pub group_by: GroupBy,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            min_evidence: MIN_EVIDENCE_COUNT,
+            group_by: GroupBy::default(),
+        }
+    }
+}
+
+/// Events grouped by one parallel task before the chunks are merged.
+const GROUP_CHUNK_SIZE: usize = 16_384;
+
+/// Groups compiled per batch of [`compile_pattern_batches`].
+pub const COMPILE_BATCH_SIZE: usize = 1_024;
+
+/// Most counterexamples kept per pattern.
+pub const MAX_COUNTEREXAMPLES: usize = 3;
+
+/// z-score for the 95% Wilson interval used as pattern confidence.
+pub const CONFIDENCE_Z: f64 = 1.96;
+
+/// A closed event, flattened into the fields compile needs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ResolvedEvent {
+    pub event_id: String,
+    pub trigger: String,
+    pub invariant: String,
+    pub response: String,
+    pub outcome: Outcome,
+    #[serde(default)]
+    pub domain_signature: Vec<f64>,
+    #[serde(default)]
+    pub evidence_refs: Vec<String>,
+    pub resolved_at: i64,
+    /// Where the event was read from, when known.
+    #[serde(default)]
+    pub source: Option<EventSource>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A file, and optionally the line within it, that an event was read from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EventSource {
+    pub path: String,
+    /// 1-based line, for line-oriented files such as event archives. `None`
+    /// for events read from the state database.
+    #[serde(default)]
+    pub line: Option<usize>,
+}
+
+impl std::fmt::Display for EventSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{line}", self.path),
+            None => f.write_str(&self.path),
+        }
+    }
+}
+
+/// The raw evidence a pattern was compiled from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PatternProvenance {
+    /// Id of the `codex compile` run that produced this version of the
+    /// pattern.
+    #[serde(default)]
+    pub compile_run_id: Option<String>,
+    /// Every event in the pattern's group, in compile input order.
+    #[serde(default)]
+    pub events: Vec<EventProvenance>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct EventProvenance {
+    pub event_id: String,
+    #[serde(default)]
+    pub source: Option<EventSource>,
+}
+
+/// A reusable trigger/response pair mined from resolved events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Pattern {
+    /// Normalized grouping key; stable across compiles of the same events.
+    pub key: String,
+    /// The latest event's trigger, with variable spans replaced by
+    /// placeholders such as `{path}`.
+    pub trigger: String,
+    pub invariant: String,
+    pub best_response: String,
+    pub outcome: Outcome,
+    /// Id of the first of `counterexamples`.
+    #[serde(default)]
+    pub counterexample: Option<String>,
+    /// Up to [`MAX_COUNTEREXAMPLES`] events whose outcome contradicted
+    /// `outcome`, most informative first (see [`mine_counterexamples`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub counterexamples: Vec<PatternCounterexample>,
+    pub support_count: usize,
+    /// Lower bound of the Wilson interval for the share of supporting events
+    /// that reached `outcome`.
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(default)]
+    pub domain_signature: Vec<f64>,
+    #[serde(default)]
+    pub evidence_refs: Vec<String>,
+    /// The group mostly failed, so `best_response` is the response to avoid
+    /// rather than the one to repeat.
+    #[serde(default)]
+    pub anti_pattern: bool,
+    /// Name of the provider that generated `domain_signature`, or `None` when
+    /// the signature came from the events.
+    #[serde(default)]
+    pub signature_provider: Option<String>,
+    #[serde(default)]
+    pub provenance: PatternProvenance,
+    /// Every tag carried by the events in the group.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Pattern {
+    /// Generate a domain signature from the trigger and invariant when the
+    /// events supplied none.
+    pub fn fill_missing_signature(
+        &mut self,
+        provider: &dyn SignatureProvider,
+    ) -> anyhow::Result<()> {
+        if self.domain_signature.is_empty() {
+            self.domain_signature = generate_signature(provider, &self.trigger, &self.invariant)?;
+            self.signature_provider = Some(provider.name());
+        }
+        Ok(())
+    }
+}
+
+impl ResolvedEvent {
+    /// `sha256:<hex>` of the event's canonical (JCS) JSON, leaving out its id and
+    /// source, so the same resolution exported twice under new ids hashes the
+    /// same.
+    pub fn content_hash(&self) -> String {
+        let mut json = serde_json::to_value(self).unwrap_or(JsonValue::Null);
+        if let JsonValue::Object(map) = &mut json {
+            map.remove("event_id");
+            map.remove("source");
+        }
+        let serialized = canonical_json(&json);
+        let hex = Sha256::digest(serialized)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        format!("sha256:{hex}")
+    }
+}
+
+/// Keep the first of each set of events with the same
+/// [`ResolvedEvent::content_hash`]. Returns the kept events and the ids of
+/// the duplicates that were dropped.
+pub fn dedupe_resolved_events(events: Vec<ResolvedEvent>) -> (Vec<ResolvedEvent>, Vec<String>) {
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+    let unique = events
+        .into_iter()
+        .filter(|event| {
+            let unique = seen.insert(event.content_hash());
+            if !unique {
+                duplicates.push(event.event_id.clone());
+            }
+            unique
+        })
+        .collect();
+    (unique, duplicates)
+}
+
+/// Grouping key for a trigger/invariant pair: lowercase, whitespace-collapsed.
+pub fn pattern_key(trigger: &str, invariant: &str) -> String {
+    format!("{}|{}", normalize(trigger), normalize(invariant))
+}
+
+/// A group of events that [`compile_patterns`] would compile into one
+/// pattern, reported without compiling it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct EventCluster {
+    /// The key the pattern would get.
+    pub key: String,
+    /// Ids of the member events, in input order.
+    pub event_ids: Vec<String>,
+    pub outcome: Outcome,
+    /// Distinct evidence references across the members.
+    pub evidence_count: usize,
+    /// Whether the cluster has the events [`CompileOptions::min_evidence`]
+    /// asks for, and so would become a pattern.
+    pub compiles: bool,
+}
+
+/// Compile resolved events into patterns, ordered by key.
+pub fn compile_patterns(events: &[ResolvedEvent], options: &CompileOptions) -> Vec<Pattern> {
+    compile_pattern_batches(events, options).flatten().collect()
+}
+
+/// Compile resolved events into patterns, ordered by key, in batches of up
+/// to [`COMPILE_BATCH_SIZE`] groups.
+///
+/// Events are grouped in parallel chunks that are then merged, and each
+/// batch is compiled in parallel when it is pulled, so a caller that writes
+/// every batch before pulling the next holds at most one batch of patterns.
+pub fn compile_pattern_batches<'a>(
+    events: &'a [ResolvedEvent],
+    options: &CompileOptions,
+) -> PatternBatches<'a> {
+    PatternBatches {
+        groups: group_events(events, options.group_by).into_iter(),
+        min_evidence: options.min_evidence.max(1),
+    }
+}
+
+/// Iterator over the batches of [`compile_pattern_batches`].
+pub struct PatternBatches<'a> {
+    groups: btree_map::IntoIter<String, Vec<&'a ResolvedEvent>>,
+    min_evidence: usize,
+}
+
+impl Iterator for PatternBatches<'_> {
+    type Item = Vec<Pattern>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let min_evidence = self.min_evidence;
+        let batch = self
+            .groups
+            .by_ref()
+            .filter(|(_, group)| group.len() >= min_evidence)
+            .take(COMPILE_BATCH_SIZE)
+            .collect::<Vec<_>>();
+        if batch.is_empty() {
+            return None;
+        }
+        Some(
+            batch
+                .into_par_iter()
+                .filter_map(|(key, group)| compile_group(key, &group))
+                .collect(),
+        )
+    }
+}
+
+/// The groups [`compile_patterns`] forms from `events`, ordered by key,
+/// including those too small to compile.
+pub fn cluster_events(events: &[ResolvedEvent], options: &CompileOptions) -> Vec<EventCluster> {
+    group_events(events, options.group_by)
+        .into_iter()
+        .map(|(key, group)| {
+            let evidence_refs = group
+                .iter()
+                .flat_map(|event| event.evidence_refs.iter())
+                .collect::<HashSet<_>>();
+            EventCluster {
+                key,
+                event_ids: group.iter().map(|event| event.event_id.clone()).collect(),
+                outcome: dominant_outcome(&group),
+                evidence_count: evidence_refs.len(),
+                compiles: group.len() >= options.min_evidence.max(1),
+            }
+        })
+        .collect()
+}
+
+type EventGroups<'a> = BTreeMap<String, Vec<&'a ResolvedEvent>>;
+
+/// Group `events` by key, [`GROUP_CHUNK_SIZE`] events per parallel task.
+/// Each group keeps its events in input order.
+fn group_events(events: &[ResolvedEvent], group_by: GroupBy) -> EventGroups<'_> {
+    events
+        .par_chunks(GROUP_CHUNK_SIZE)
+        .map(|chunk| {
+            let mut groups = EventGroups::new();
+            for event in chunk {
+                groups
+                    .entry(group_key(event, group_by))
+                    .or_default()
+                    .push(event);
+            }
+            groups
+        })
+        .reduce(EventGroups::new, merge_groups)
+}
+
+/// Append the groups of a chunk to those of the chunks before it. Rayon
+/// reduces neighbouring chunks in order, so this keeps input order.
+fn merge_groups<'a>(mut earlier: EventGroups<'a>, later: EventGroups<'a>) -> EventGroups<'a> {
+    for (key, mut group) in later {
+        earlier.entry(key).or_default().append(&mut group);
+    }
+    earlier
+}
+
+/// The pattern key of the group `event` belongs to under `group_by`.
+fn group_key(event: &ResolvedEvent, group_by: GroupBy) -> String {
+    let trigger = compiled_trigger(&event.trigger);
+    match group_by {
+        GroupBy::Trigger => format!("{}|*", normalize(&trigger)),
+        GroupBy::Signature if !event.domain_signature.is_empty() => {
+            let scale = 10f64.powi(SIGNATURE_GROUP_DECIMALS);
+            let rounded = event
+                .domain_signature
+                .iter()
+                // `+ 0.0` folds -0.0 into 0.0.
+                .map(|weight| format!("{}", (weight * scale).round() / scale + 0.0))
+                .collect::<Vec<_>>()
+                .join(",");
+            let digest = Sha256::digest(rounded.as_bytes());
+            let hex = digest
+                .iter()
+                .take(8)
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>();
+            format!("signature:{hex}")
+        }
+        GroupBy::TriggerInvariant | GroupBy::Signature => pattern_key(&trigger, &event.invariant),
+    }
+}
+
+fn compile_group(key: String, group: &[&ResolvedEvent]) -> Option<Pattern> {
+    let latest = group.iter().max_by_key(|event| event.resolved_at)?;
+    let outcome = dominant_outcome(group);
+    let best_response = best_response(group, outcome).unwrap_or_default();
+    let counterexamples = mine_counterexamples(group, outcome)
+        .into_iter()
+        .take(MAX_COUNTEREXAMPLES)
+        .map(|event| PatternCounterexample {
+            event_id: event.event_id.clone(),
+            outcome: event.outcome,
+            resolved_at: event.resolved_at,
+            evidence_refs: event.evidence_refs.clone(),
+        })
+        .collect::<Vec<_>>();
+    let counterexample = counterexamples
+        .first()
+        .map(|counterexample| counterexample.event_id.clone());
+    let mut evidence_refs = Vec::new();
+    let mut tags = Vec::new();
+    for event in group {
+        for evidence_ref in &event.evidence_refs {
+            if !evidence_refs.contains(evidence_ref) {
+                evidence_refs.push(evidence_ref.clone());
+            }
+        }
+        for tag in &event.tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+    }
+
+    let agreeing = group
+        .iter()
+        .filter(|event| event.outcome == outcome)
+        .count();
+    let anti_pattern = outcome == Outcome::Failure && !best_response.trim().is_empty();
+
+    Some(Pattern {
+        key,
+        trigger: compiled_trigger(&latest.trigger),
+        invariant: latest.invariant.clone(),
+        best_response,
+        outcome,
+        counterexample,
+        counterexamples,
+        support_count: group.len(),
+        confidence: wilson_lower_bound(agreeing, group.len()),
+        domain_signature: mean_signature(group),
+        evidence_refs,
+        anti_pattern,
+        signature_provider: None,
+        provenance: PatternProvenance {
+            compile_run_id: None,
+            events: group
+                .iter()
+                .map(|event| EventProvenance {
+                    event_id: event.event_id.clone(),
+                    source: event.source.clone(),
+                })
+                .collect(),
+        },
+        tags,
+    })
+}
+
+fn compiled_trigger(trigger: &str) -> String {
+    templatize_trigger(trigger).unwrap_or_else(|| trigger.to_string())
+}
+
+/// The most common outcome in the group. Ties between success and failure are
+/// reported as [`Outcome::Mixed`].
+fn dominant_outcome(group: &[&ResolvedEvent]) -> Outcome {
+    let mut counts: BTreeMap<Outcome, usize> = BTreeMap::new();
+    for event in group {
+        *counts.entry(event.outcome).or_insert(0) += 1;
+    }
+    let success = counts.get(&Outcome::Success).copied().unwrap_or(0);
+    let failure = counts.get(&Outcome::Failure).copied().unwrap_or(0);
+    if success > 0 && success == failure {
+        return Outcome::Mixed;
+    }
+    counts
+        .into_iter()
+        .max_by(|(left_outcome, left), (right_outcome, right)| {
+            left.cmp(right)
+                .then_with(|| right_outcome.cmp(left_outcome))
+        })
+        .map_or(Outcome::Unknown, |(outcome, _)| outcome)
+}
+
+/// The most frequent response among events that reached `outcome`, falling
+/// back to the most recent response in the group.
+fn best_response(group: &[&ResolvedEvent], outcome: Outcome) -> Option<String> {
+    let mut counts: BTreeMap<&str, (usize, i64)> = BTreeMap::new();
+    for event in group.iter().filter(|event| event.outcome == outcome) {
+        let entry = counts.entry(event.response.as_str()).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 = entry.1.max(event.resolved_at);
+    }
+    if let Some((response, _)) = counts.into_iter().max_by_key(|(_, ranking)| *ranking) {
+        return Some(response.to_string());
+    }
+    group
+        .iter()
+        .max_by_key(|event| event.resolved_at)
+        .map(|event| event.response.clone())
+}
+
+/// Lower bound of the 95% Wilson score interval for `successes` out of
+/// `total` trials. Small samples get low bounds even when they all agree.
+pub fn wilson_lower_bound(successes: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    let n = total as f64;
+    let p = successes.min(total) as f64 / n;
+    let z2 = CONFIDENCE_Z * CONFIDENCE_Z;
+    let center = p + z2 / (2.0 * n);
+    let margin = CONFIDENCE_Z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    ((center - margin) / (1.0 + z2 / n)).max(0.0)
+}
+
+/// The most informative event whose outcome contradicts the group's
+/// dominant outcome; see [`mine_counterexamples`].
+pub fn select_counterexample<'a>(
+    group: &[&'a ResolvedEvent],
+    dominant: Outcome,
+) -> Option<&'a ResolvedEvent> {
+    mine_counterexamples(group, dominant).into_iter().next()
+}
+
+/// Every event whose outcome contradicts the group's dominant outcome, most
+/// informative first: the largest outcome divergence (a failure in a group of
+/// successes, or the reverse, before a mixed or unknown result), then events
+/// with evidence, then the most recent. Ties keep the group's order.
+pub fn mine_counterexamples<'a>(
+    group: &[&'a ResolvedEvent],
+    dominant: Outcome,
+) -> Vec<&'a ResolvedEvent> {
+    let mut counterexamples = group
+        .iter()
+        .copied()
+        .filter(|event| event.outcome != dominant)
+        .collect::<Vec<_>>();
+    counterexamples.sort_by(|left, right| {
+        outcome_divergence(dominant, right.outcome)
+            .cmp(&outcome_divergence(dominant, left.outcome))
+            .then_with(|| (!right.evidence_refs.is_empty()).cmp(&!left.evidence_refs.is_empty()))
+            .then_with(|| right.resolved_at.cmp(&left.resolved_at))
+    });
+    counterexamples
+}
+
+/// How far `outcome` is from `dominant`: 2 for success against failure, 1 for
+/// any other difference, 0 for none.
+fn outcome_divergence(dominant: Outcome, outcome: Outcome) -> u8 {
+    match (dominant, outcome) {
+        (Outcome::Success, Outcome::Failure) | (Outcome::Failure, Outcome::Success) => 2,
+        _ if dominant == outcome => 0,
+        _ => 1,
+    }
+}
+
+fn mean_signature(group: &[&ResolvedEvent]) -> Vec<f64> {
+    let len = group
+        .iter()
+        .map(|event| event.domain_signature.len())
+        .max()
+        .unwrap_or(0);
+    let mut sums = vec![0.0; len];
+    for event in group {
+        for (sum, value) in sums.iter_mut().zip(&event.domain_signature) {
+            *sum += value;
+        }
+    }
+    let count = group.len() as f64;
+    sums.into_iter().map(|sum| sum / count).collect()
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn event(id: &str, trigger: &str, response: &str, outcome: Outcome) -> ResolvedEvent {
+        ResolvedEvent {
+            event_id: id.to_string(),
+            trigger: trigger.to_string(),
+            invariant: "disk pressure".to_string(),
+            response: response.to_string(),
+            outcome,
+            domain_signature: vec![1.0, 0.0],
+            evidence_refs: vec![format!("evidence://{id}")],
+            resolved_at: id.len() as i64,
+            source: Some(EventSource {
+                path: "events.jsonl".to_string(),
+                line: Some(id.len()),
+            }),
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn counterexamples_rank_divergence_then_evidence_then_recency() {
+        let success = event("s1", "Disk full", "prune logs", Outcome::Success);
+        let mixed = ResolvedEvent {
+            resolved_at: 100,
+            ..event("m1", "Disk full", "prune logs", Outcome::Mixed)
+        };
+        let old_failure = event("f1", "Disk full", "reboot", Outcome::Failure);
+        let bare_failure = ResolvedEvent {
+            resolved_at: 50,
+            evidence_refs: Vec::new(),
+            ..event("f2", "Disk full", "reboot", Outcome::Failure)
+        };
+        let new_failure = ResolvedEvent {
+            resolved_at: 40,
+            ..event("f3", "Disk full", "reboot", Outcome::Failure)
+        };
+        let group = [&success, &mixed, &bare_failure, &old_failure, &new_failure];
+
+        assert_eq!(
+            (
+                mine_counterexamples(&group, Outcome::Success)
+                    .iter()
+                    .map(|event| event.event_id.as_str())
+                    .collect::<Vec<_>>(),
+                select_counterexample(&group, Outcome::Success)
+                    .map(|event| event.event_id.as_str()),
+            ),
+            (vec!["f3", "f1", "f2", "m1"], Some("f3"))
+        );
+    }
+
+    #[test]
+    fn compile_groups_normalized_triggers_and_picks_counterexample() {
+        let events = vec![
+            event("e1", "Disk full", "prune logs", Outcome::Success),
+            event("e22", "disk   FULL", "prune logs", Outcome::Success),
+            event("e333", "Disk full", "reboot", Outcome::Failure),
+            event("solo", "Certificate expired", "renew", Outcome::Success),
+        ];
+
+        assert_eq!(
+            compile_patterns(&events, &CompileOptions::default()),
+            vec![Pattern {
+                key: "disk full|disk pressure".to_string(),
+                trigger: "Disk full".to_string(),
+                invariant: "disk pressure".to_string(),
+                best_response: "prune logs".to_string(),
+                outcome: Outcome::Success,
+                counterexample: Some("e333".to_string()),
+                counterexamples: vec![PatternCounterexample {
+                    event_id: "e333".to_string(),
+                    outcome: Outcome::Failure,
+                    resolved_at: 4,
+                    evidence_refs: vec!["evidence://e333".to_string()],
+                }],
+                support_count: 3,
+                confidence: wilson_lower_bound(2, 3),
+                domain_signature: vec![1.0, 0.0],
+                evidence_refs: vec![
+                    "evidence://e1".to_string(),
+                    "evidence://e22".to_string(),
+                    "evidence://e333".to_string(),
+                ],
+                anti_pattern: false,
+                signature_provider: None,
+                provenance: PatternProvenance {
+                    compile_run_id: None,
+                    events: [("e1", 2), ("e22", 3), ("e333", 4)]
+                        .into_iter()
+                        .map(|(event_id, line)| EventProvenance {
+                            event_id: event_id.to_string(),
+                            source: Some(EventSource {
+                                path: "events.jsonl".to_string(),
+                                line: Some(line),
+                            }),
+                        })
+                        .collect(),
+                },
+                tags: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn grouping_and_minimum_evidence_are_configurable() {
+        let mut events = vec![
+            event("e1", "Disk full", "prune logs", Outcome::Success),
+            ResolvedEvent {
+                invariant: "inode exhaustion".to_string(),
+                domain_signature: vec![0.98, 0.04],
+                ..event("e22", "disk full", "prune logs", Outcome::Success)
+            },
+            ResolvedEvent {
+                domain_signature: Vec::new(),
+                ..event("e333", "Certificate expired", "renew", Outcome::Success)
+            },
+        ];
+        let keys = |events: &[ResolvedEvent], min_evidence, group_by| {
+            compile_patterns(
+                events,
+                &CompileOptions {
+                    min_evidence,
+                    group_by,
+                },
+            )
+            .into_iter()
+            .map(|pattern| (pattern.key, pattern.support_count))
+            .collect::<Vec<_>>()
+        };
+
+        let by_trigger_invariant = keys(&events, 1, GroupBy::TriggerInvariant);
+        let by_trigger = keys(&events, 2, GroupBy::Trigger);
+        events.push(event(
+            "e4444",
+            "Certificate expired",
+            "renew",
+            Outcome::Success,
+        ));
+        let by_signature = keys(&events, 2, GroupBy::Signature);
+        assert_eq!(
+            (by_trigger_invariant, by_trigger, by_signature.len()),
+            (
+                vec![
+                    ("certificate expired|disk pressure".to_string(), 1),
+                    ("disk full|disk pressure".to_string(), 1),
+                    ("disk full|inode exhaustion".to_string(), 1),
+                ],
+                vec![("disk full|*".to_string(), 2)],
+                1,
+            )
+        );
+        assert_eq!(
+            by_signature[0].1, 3,
+            "e1, e22, and e4444 share a rounded signature"
+        );
+    }
+
+    #[test]
+    fn clusters_report_every_group_with_its_outcome_and_evidence() {
+        let events = vec![
+            event("e1", "Disk full on /var", "prune logs", Outcome::Success),
+            ResolvedEvent {
+                evidence_refs: vec!["evidence://e1".to_string(), "evidence://df".to_string()],
+                ..event("e22", "disk full on /tmp", "prune logs", Outcome::Success)
+            },
+            event("e333", "Disk full on /home", "reboot", Outcome::Failure),
+            event("e4444", "Certificate expired", "renew", Outcome::Success),
+        ];
+        let options = CompileOptions {
+            min_evidence: 2,
+            group_by: GroupBy::TriggerInvariant,
+        };
+
+        let clusters = cluster_events(&events, &options);
+        assert_eq!(
+            clusters,
+            vec![
+                EventCluster {
+                    key: "certificate expired|disk pressure".to_string(),
+                    event_ids: vec!["e4444".to_string()],
+                    outcome: Outcome::Success,
+                    evidence_count: 1,
+                    compiles: false,
+                },
+                EventCluster {
+                    key: "disk full on {path}|disk pressure".to_string(),
+                    event_ids: vec!["e1".to_string(), "e22".to_string(), "e333".to_string()],
+                    outcome: Outcome::Success,
+                    evidence_count: 3,
+                    compiles: true,
+                },
+            ]
+        );
+        assert_eq!(
+            compile_patterns(&events, &options)
+                .into_iter()
+                .map(|pattern| pattern.key)
+                .collect::<Vec<_>>(),
+            vec![clusters[1].key.clone()]
+        );
+    }
+
+    #[test]
+    fn batches_compile_the_same_patterns_in_the_same_order() {
+        let events = (0..3 * GROUP_CHUNK_SIZE)
+            .map(|id| {
+                let outcome = if id % 3 == 0 {
+                    Outcome::Failure
+                } else {
+                    Outcome::Success
+                };
+                ResolvedEvent {
+                    resolved_at: id as i64,
+                    ..event(
+                        &format!("e{id}"),
+                        &format!("service{} failed", id % (COMPILE_BATCH_SIZE + 7)),
+                        "restart",
+                        outcome,
+                    )
+                }
+            })
+            .collect::<Vec<_>>();
+        let options = CompileOptions::default();
+
+        let batches = compile_pattern_batches(&events, &options).collect::<Vec<_>>();
+        let mut sequential: BTreeMap<String, Vec<&ResolvedEvent>> = BTreeMap::new();
+        for event in &events {
+            sequential
+                .entry(group_key(event, options.group_by))
+                .or_default()
+                .push(event);
+        }
+        assert_eq!(
+            batches.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![COMPILE_BATCH_SIZE, 7]
+        );
+        assert_eq!(
+            batches.into_iter().flatten().collect::<Vec<_>>(),
+            sequential
+                .into_iter()
+                .filter_map(|(key, group)| compile_group(key, &group))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[ignore = "benchmark; run with --ignored --nocapture"]
+    #[allow(clippy::print_stderr)]
+    fn parallel_and_sequential_compile_agree_on_1m_events() {
+        let events = (0..1_000_000)
+            .map(|id| ResolvedEvent {
+                resolved_at: id,
+                ..event(
+                    &format!("e{id}"),
+                    &format!("service{} failed in region{}", id % 5_000, id % 7),
+                    "restart",
+                    Outcome::Success,
+                )
+            })
+            .collect::<Vec<_>>();
+        let options = CompileOptions::default();
+
+        let started = std::time::Instant::now();
+        let mut compiled = 0;
+        for batch in compile_pattern_batches(&events, &options) {
+            compiled += batch.len();
+        }
+        let parallel_elapsed = started.elapsed();
+        let started = std::time::Instant::now();
+        let mut groups: BTreeMap<String, Vec<&ResolvedEvent>> = BTreeMap::new();
+        for event in &events {
+            groups
+                .entry(group_key(event, options.group_by))
+                .or_default()
+                .push(event);
+        }
+        let sequential = groups
+            .into_iter()
+            .filter_map(|(key, group)| compile_group(key, &group))
+            .count();
+        let sequential_elapsed = started.elapsed();
+
+        assert_eq!(compiled, sequential);
+        eprintln!(
+            "parallel compile on {} threads took {parallel_elapsed:?}, sequential took {sequential_elapsed:?}",
+            rayon::current_num_threads()
+        );
+    }
+
+    #[test]
+    fn mostly_failing_groups_compile_to_anti_patterns() {
+        let events = vec![
+            event("e1", "Disk full", "reboot", Outcome::Failure),
+            event("e22", "Disk full", "reboot", Outcome::Failure),
+            event("e333", "Disk full", "prune logs", Outcome::Success),
+        ];
+
+        let patterns = compile_patterns(&events, &CompileOptions::default());
+
+        assert_eq!(
+            patterns
+                .iter()
+                .map(|pattern| (
+                    pattern.outcome,
+                    pattern.best_response.as_str(),
+                    pattern.anti_pattern
+                ))
+                .collect::<Vec<_>>(),
+            vec![(Outcome::Failure, "reboot", true)]
+        );
+    }
+
+    #[test]
+    fn triggers_differing_only_in_variables_compile_to_one_template() {
+        let events = vec![
+            event(
+                "e1",
+                "Disk full on /var/log",
+                "prune logs",
+                Outcome::Success,
+            ),
+            event("e22", "Disk full on /tmp", "prune logs", Outcome::Success),
+            event("e333", "Disk full", "reboot", Outcome::Failure),
+        ];
+
+        assert_eq!(
+            compile_patterns(&events, &CompileOptions::default())
+                .iter()
+                .map(|pattern| (
+                    pattern.key.as_str(),
+                    pattern.trigger.as_str(),
+                    pattern.support_count
+                ))
+                .collect::<Vec<_>>(),
+            vec![(
+                "disk full on {path}|disk pressure",
+                "Disk full on {path}",
+                2
+            )]
+        );
+    }
+
+    #[test]
+    fn events_repeated_under_new_ids_are_deduplicated() {
+        let original = event("evt-1", "Disk full", "rotate logs", Outcome::Success);
+        let reexported = ResolvedEvent {
+            event_id: "evt-9".to_string(),
+            ..original.clone()
+        };
+        let recurrence = ResolvedEvent {
+            event_id: "evt-2".to_string(),
+            resolved_at: 99,
+            ..original.clone()
+        };
+
+        let (unique, duplicates) = dedupe_resolved_events(vec![
+            original.clone(),
+            recurrence.clone(),
+            reexported.clone(),
+            original.clone(),
+        ]);
+
+        assert_eq!(
+            (unique, duplicates),
+            (
+                vec![original, recurrence],
+                vec!["evt-9".to_string(), "evt-1".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn wilson_bound_grows_with_support() {
+        let bounds = [(0, 0), (3, 3), (30, 30), (15, 30)]
+            .into_iter()
+            .map(|(successes, total)| {
+                (wilson_lower_bound(successes, total) * 1000.0).round() / 1000.0
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(bounds, vec![0.0, 0.438, 0.886, 0.332]);
+    }
+
+    #[test]
+    fn tied_success_and_failure_is_mixed() {
+        let first = event("e1", "Disk full", "prune logs", Outcome::Success);
+        let second = event("e2", "Disk full", "reboot", Outcome::Failure);
+
+        assert_eq!(dominant_outcome(&[&first, &second]), Outcome::Mixed);
+    }
+}
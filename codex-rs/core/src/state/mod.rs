@@ -5,5 +5,6 @@ mod turn;
 pub(crate) use service::SessionServices;
 pub(crate) use session::SessionState;
 pub(crate) use turn::ActiveTurn;
+pub(crate) use turn::PendingApproval;
 pub(crate) use turn::RunningTask;
 pub(crate) use turn::TaskKind;
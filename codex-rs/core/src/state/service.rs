@@ -5,6 +5,7 @@ use crate::RolloutRecorder;
 use crate::agent::AgentControl;
 use crate::analytics_client::AnalyticsEventsClient;
 use crate::client::ModelClient;
+use crate::covenant_store::CovenantStore;
 use crate::exec_policy::ExecPolicyManager;
 use crate::file_watcher::FileWatcher;
 use crate::hooks::Hooks;
@@ -15,6 +16,7 @@ use crate::state_db::StateDbHandle;
 use crate::tools::sandboxing::ApprovalStore;
 use crate::unified_exec::UnifiedExecProcessManager;
 use codex_otel::OtelManager;
+use codex_state::PatternNamespaces;
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
@@ -37,6 +39,18 @@ pub(crate) struct SessionServices {
     pub(crate) file_watcher: Arc<FileWatcher>,
     pub(crate) agent_control: AgentControl,
     pub(crate) state_db: Option<StateDbHandle>,
+    /// Where covenant checks read the covenant and record their decisions.
+    pub(crate) covenant_store: Arc<dyn CovenantStore>,
+    /// Covenant events and patterns: `state_db`, or the workspace store the
+    /// project's covenant asks for.
+    pub(crate) covenant_db: Option<StateDbHandle>,
+    /// Where known-pattern suggestions for the model come from:
+    /// `covenant_db`, unless it is a workspace store in an untrusted project.
+    /// A workspace store lives in the repository, so anyone who can commit to
+    /// it could otherwise write into the model's context.
+    pub(crate) pattern_suggestions_db: Option<StateDbHandle>,
+    /// Pattern namespaces resolved for this session's working directories.
+    pub(crate) pattern_namespaces: PatternNamespaces,
     /// Session-scoped model client shared across turns.
     pub(crate) model_client: ModelClient,
 }
@@ -12,6 +12,8 @@ use codex_protocol::ThreadId;
 use codex_protocol::dynamic_tools::DynamicToolSpec;
 use codex_protocol::protocol::RolloutItem;
 use codex_protocol::protocol::SessionSource;
+use codex_state::CovenantStorage;
+use codex_state::CovenantStorePaths;
 use codex_state::DB_METRIC_COMPARE_ERROR;
 pub use codex_state::LogEntry;
 use codex_state::STATE_DB_VERSION;
@@ -70,6 +72,39 @@ pub(crate) async fn init_if_enabled(
     require_backfill_complete(runtime, config.codex_home.as_path()).await
 }
 
+/// The store covenant events, patterns, and audit entries go to for sessions
+/// in `config.cwd`: `state_db` itself, unless the `covenant.json` governing the
+/// working directory sets `"storage": "workspace"`.
+pub(crate) async fn covenant_store_db(
+    config: &Config,
+    state_db: Option<StateDbHandle>,
+) -> Option<StateDbHandle> {
+    let storage = match crate::covenant::find_covenant(config.cwd.as_path()).await {
+        Ok(Some(covenant)) => covenant.storage,
+        Ok(None) | Err(_) => CovenantStorage::Home,
+    };
+    if storage == CovenantStorage::Home || state_db.is_none() {
+        return state_db;
+    }
+    let paths = CovenantStorePaths::resolve(storage, &config.codex_home, &config.cwd);
+    match codex_state::StateRuntime::init_covenant_store(
+        &paths,
+        config.model_provider_id.clone(),
+        None,
+    )
+    .await
+    {
+        Ok(runtime) => Some(runtime),
+        Err(err) => {
+            warn!(
+                "failed to open workspace covenant store at {}: {err}",
+                paths.db_dir.display()
+            );
+            None
+        }
+    }
+}
+
 /// Get the DB if the feature is enabled and the DB exists.
 pub async fn get_state_db(config: &Config, otel: Option<&OtelManager>) -> Option<StateDbHandle> {
     let state_path = codex_state::state_db_path(config.codex_home.as_path());
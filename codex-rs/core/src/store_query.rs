@@ -0,0 +1,679 @@
+//! Filter expressions over covenant events and patterns.
+//!
+//! A filter compares fields of a record with values and combines the
+//! comparisons with `AND`, `OR`, `NOT`, and parentheses:
+//!
+//! ```text
+//! tag = "infra" AND resolved = false AND created_at > 2024-06-01
+//! ```
+//!
+//! The operators are `=`, `!=`, `<`, `<=`, `>`, `>=`, and `~`, which holds
+//! when the field contains the value, ignoring case. Values are read the way
+//! the field they are compared with needs them: text, quoted with `"` or `'`
+//! when it has spaces; a number; `true` or `false`; or a time, written as a
+//! date (midnight UTC), an RFC 3339 timestamp, or Unix seconds. A list field
+//! such as `tag` equals a value when one of its items does. `AND` binds
+//! tighter than `OR`, and keywords are case-insensitive.
+//!
+//! Fields, operators, and values are checked against the record type when
+//! the filter is parsed, so a typo fails before anything is read.
+
+use anyhow::Context;
+use chrono::DateTime;
+use chrono::NaiveDate;
+use codex_state::CovenantEvent;
+use codex_state::CovenantPattern;
+
+/// What kind of value a field holds, which decides the values and operators
+/// it can be compared with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Number,
+    Bool,
+    /// Unix seconds.
+    Time,
+    /// Text items, such as tags.
+    List,
+}
+
+impl FieldKind {
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Number => "a number",
+            Self::Bool => "true or false",
+            Self::Time => "a date such as 2024-06-01",
+            Self::List => "text",
+        }
+    }
+
+    fn allows(self, op: CompareOp) -> bool {
+        match self {
+            Self::Text => true,
+            Self::Number | Self::Time => op != CompareOp::Contains,
+            Self::Bool => matches!(op, CompareOp::Eq | CompareOp::Ne),
+            Self::List => matches!(op, CompareOp::Eq | CompareOp::Ne | CompareOp::Contains),
+        }
+    }
+}
+
+/// The value of one field of a record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    Time(i64),
+    List(Vec<String>),
+}
+
+/// A record type filters can be written against.
+pub trait Queryable {
+    /// The fields a filter can name, with their kinds.
+    const FIELDS: &'static [(&'static str, FieldKind)];
+
+    /// The value of `field`, one of [`Queryable::FIELDS`], or `None` when the
+    /// record has none. A comparison with a missing value never holds.
+    fn field(&self, field: &str) -> Option<FieldValue>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+impl CompareOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Contains => "~",
+        }
+    }
+
+    fn holds(self, ordering: std::cmp::Ordering) -> bool {
+        match self {
+            Self::Eq => ordering.is_eq(),
+            Self::Ne => ordering.is_ne(),
+            Self::Lt => ordering.is_lt(),
+            Self::Le => ordering.is_le(),
+            Self::Gt => ordering.is_gt(),
+            Self::Ge => ordering.is_ge(),
+            Self::Contains => false,
+        }
+    }
+}
+
+/// A value in a filter, read for the kind of field it is compared with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    Time(i64),
+}
+
+/// A parsed filter expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Compare {
+        field: String,
+        op: CompareOp,
+        value: Literal,
+    },
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    /// Parse `text` as a filter over records of type `T`.
+    pub fn parse<T: Queryable>(text: &str) -> anyhow::Result<Self> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            next: 0,
+            end: text.chars().count() + 1,
+            fields: T::FIELDS,
+        };
+        let filter = parser.or()?;
+        if let Some(token) = parser.peek() {
+            anyhow::bail!(
+                "unexpected `{}` at column {}",
+                token.kind.text(),
+                token.column
+            );
+        }
+        Ok(filter)
+    }
+
+    /// Whether `record` passes the filter.
+    pub fn matches<T: Queryable>(&self, record: &T) -> bool {
+        match self {
+            Self::Compare { field, op, value } => record
+                .field(field)
+                .is_some_and(|actual| compare(&actual, *op, value)),
+            Self::And(left, right) => left.matches(record) && right.matches(record),
+            Self::Or(left, right) => left.matches(record) || right.matches(record),
+            Self::Not(inner) => !inner.matches(record),
+        }
+    }
+}
+
+/// Keep the records of `records` passing `filter`, or all of them without one.
+pub fn filter_records<T: Queryable>(records: Vec<T>, filter: Option<&Filter>) -> Vec<T> {
+    match filter {
+        Some(filter) => records
+            .into_iter()
+            .filter(|record| filter.matches(record))
+            .collect(),
+        None => records,
+    }
+}
+
+fn compare(actual: &FieldValue, op: CompareOp, value: &Literal) -> bool {
+    match (actual, value) {
+        (FieldValue::Text(actual), Literal::Text(value)) => match op {
+            CompareOp::Contains => contains_ignoring_case(actual, value),
+            CompareOp::Eq
+            | CompareOp::Ne
+            | CompareOp::Lt
+            | CompareOp::Le
+            | CompareOp::Gt
+            | CompareOp::Ge => op.holds(actual.as_str().cmp(value.as_str())),
+        },
+        (FieldValue::List(items), Literal::Text(value)) => match op {
+            CompareOp::Contains => items.iter().any(|item| contains_ignoring_case(item, value)),
+            CompareOp::Ne => !items.contains(value),
+            CompareOp::Eq | CompareOp::Lt | CompareOp::Le | CompareOp::Gt | CompareOp::Ge => {
+                items.contains(value)
+            }
+        },
+        (FieldValue::Number(actual), Literal::Number(value)) => actual
+            .partial_cmp(value)
+            .is_some_and(|ordering| op.holds(ordering)),
+        (FieldValue::Bool(actual), Literal::Bool(value)) => op.holds(actual.cmp(value)),
+        (FieldValue::Time(actual), Literal::Time(value)) => op.holds(actual.cmp(value)),
+        _ => false,
+    }
+}
+
+fn contains_ignoring_case(text: &str, needle: &str) -> bool {
+    text.to_lowercase().contains(&needle.to_lowercase())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Open,
+    Close,
+    Op(CompareOp),
+    Word(String),
+    Quoted(String),
+}
+
+impl TokenKind {
+    fn text(&self) -> String {
+        match self {
+            Self::Open => "(".to_string(),
+            Self::Close => ")".to_string(),
+            Self::Op(op) => op.symbol().to_string(),
+            Self::Word(word) => word.clone(),
+            Self::Quoted(text) => format!("\"{text}\""),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Self::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    /// 1-based column of the token's first character.
+    column: usize,
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '(' | ')' | '"' | '\'' | '=' | '!' | '<' | '>' | '~')
+}
+
+fn tokenize(text: &str) -> anyhow::Result<Vec<Token>> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while let Some(&c) = chars.get(index) {
+        let column = index + 1;
+        let next = chars.get(index + 1).copied();
+        let (kind, len) = match c {
+            c if c.is_whitespace() => {
+                index += 1;
+                continue;
+            }
+            '(' => (TokenKind::Open, 1),
+            ')' => (TokenKind::Close, 1),
+            '=' => (TokenKind::Op(CompareOp::Eq), 1),
+            '~' => (TokenKind::Op(CompareOp::Contains), 1),
+            '!' if next == Some('=') => (TokenKind::Op(CompareOp::Ne), 2),
+            '<' if next == Some('=') => (TokenKind::Op(CompareOp::Le), 2),
+            '>' if next == Some('=') => (TokenKind::Op(CompareOp::Ge), 2),
+            '<' => (TokenKind::Op(CompareOp::Lt), 1),
+            '>' => (TokenKind::Op(CompareOp::Gt), 1),
+            '!' => anyhow::bail!("expected `!=` at column {column}"),
+            '"' | '\'' => {
+                let Some(len) = chars[index + 1..].iter().position(|&end| end == c) else {
+                    anyhow::bail!("unterminated string starting at column {column}");
+                };
+                let text = chars[index + 1..index + 1 + len].iter().collect();
+                (TokenKind::Quoted(text), len + 2)
+            }
+            _ => {
+                let len = chars[index..]
+                    .iter()
+                    .take_while(|&&c| is_word_char(c))
+                    .count();
+                let word = chars[index..index + len].iter().collect();
+                (TokenKind::Word(word), len)
+            }
+        };
+        tokens.push(Token { kind, column });
+        index += len;
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    next: usize,
+    /// Column just past the end of the text, for errors at the end.
+    end: usize,
+    fields: &'static [(&'static str, FieldKind)],
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.next);
+        self.next += 1;
+        token
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self
+            .peek()
+            .is_some_and(|token| token.kind.is_keyword(keyword));
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> anyhow::Result<Filter> {
+        let mut filter = self.and()?;
+        while self.eat_keyword("OR") {
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> anyhow::Result<Filter> {
+        let mut filter = self.unary()?;
+        while self.eat_keyword("AND") {
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+        Ok(filter)
+    }
+
+    fn unary(&mut self) -> anyhow::Result<Filter> {
+        if self.eat_keyword("NOT") {
+            return Ok(Filter::Not(Box::new(self.unary()?)));
+        }
+        if self
+            .peek()
+            .is_some_and(|token| token.kind == TokenKind::Open)
+        {
+            self.next += 1;
+            let filter = self.or()?;
+            return match self.advance() {
+                Some(Token {
+                    kind: TokenKind::Close,
+                    ..
+                }) => Ok(filter),
+                Some(token) => anyhow::bail!(
+                    "expected `)` at column {}, found `{}`",
+                    token.column,
+                    token.kind.text()
+                ),
+                None => anyhow::bail!("expected `)` at column {}", self.end),
+            };
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> anyhow::Result<Filter> {
+        let end = self.end;
+        let fields = self.fields;
+        let (field, kind) = match self.advance() {
+            Some(Token {
+                kind: TokenKind::Word(word),
+                column,
+            }) => {
+                let Some((name, kind)) = fields.iter().find(|(name, _)| *name == word.as_str())
+                else {
+                    anyhow::bail!(
+                        "unknown field `{word}` at column {column}; expected one of {}",
+                        fields
+                            .iter()
+                            .map(|(name, _)| *name)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                };
+                ((*name).to_string(), *kind)
+            }
+            Some(token) => anyhow::bail!(
+                "expected a field name at column {}, found `{}`",
+                token.column,
+                token.kind.text()
+            ),
+            None => anyhow::bail!("expected a field name at column {end}"),
+        };
+        let op = match self.advance() {
+            Some(Token {
+                kind: TokenKind::Op(op),
+                column,
+            }) => {
+                if !kind.allows(*op) {
+                    anyhow::bail!(
+                        "`{field}` cannot be compared with `{}` at column {column}",
+                        op.symbol()
+                    );
+                }
+                *op
+            }
+            Some(token) => anyhow::bail!(
+                "expected a comparison after `{field}` at column {}, found `{}`",
+                token.column,
+                token.kind.text()
+            ),
+            None => anyhow::bail!("expected a comparison after `{field}` at column {end}"),
+        };
+        let value = match self.advance() {
+            Some(Token {
+                kind: TokenKind::Word(text) | TokenKind::Quoted(text),
+                column,
+            }) => read_literal(kind, text).with_context(|| {
+                format!("`{field}` takes {} at column {column}", kind.describe())
+            })?,
+            Some(token) => anyhow::bail!(
+                "expected a value for `{field}` at column {}, found `{}`",
+                token.column,
+                token.kind.text()
+            ),
+            None => anyhow::bail!("expected a value for `{field}` at column {end}"),
+        };
+        Ok(Filter::Compare { field, op, value })
+    }
+}
+
+fn read_literal(kind: FieldKind, text: &str) -> anyhow::Result<Literal> {
+    match kind {
+        FieldKind::Text | FieldKind::List => Ok(Literal::Text(text.to_string())),
+        FieldKind::Number => Ok(Literal::Number(text.parse()?)),
+        FieldKind::Bool => match text.to_ascii_lowercase().as_str() {
+            "true" => Ok(Literal::Bool(true)),
+            "false" => Ok(Literal::Bool(false)),
+            _ => anyhow::bail!("`{text}` is not true or false"),
+        },
+        FieldKind::Time => read_time(text).map(Literal::Time),
+    }
+}
+
+fn read_time(text: &str) -> anyhow::Result<i64> {
+    if let Ok(seconds) = text.parse::<i64>() {
+        return Ok(seconds);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        && let Some(midnight) = date.and_hms_opt(0, 0, 0)
+    {
+        return Ok(midnight.and_utc().timestamp());
+    }
+    Ok(DateTime::parse_from_rfc3339(text)?.timestamp())
+}
+
+impl Queryable for CovenantEvent {
+    const FIELDS: &'static [(&'static str, FieldKind)] = &[
+        ("id", FieldKind::Text),
+        ("created_at", FieldKind::Time),
+        ("description", FieldKind::Text),
+        ("intent", FieldKind::Text),
+        ("confidence", FieldKind::Number),
+        ("resolved", FieldKind::Bool),
+        ("outcome", FieldKind::Text),
+        ("tag", FieldKind::List),
+    ];
+
+    fn field(&self, field: &str) -> Option<FieldValue> {
+        match field {
+            "id" => Some(FieldValue::Text(self.id.clone())),
+            "created_at" => Some(FieldValue::Time(self.created_at)),
+            "description" => Some(FieldValue::Text(self.description.clone())),
+            "intent" => self
+                .intent
+                .as_ref()
+                .map(|intent| FieldValue::Text(intent.goal.clone())),
+            "confidence" => self
+                .intent
+                .as_ref()
+                .map(|intent| FieldValue::Number(intent.confidence)),
+            "resolved" => Some(FieldValue::Bool(!self.outcomes.is_empty())),
+            "outcome" => self
+                .outcomes
+                .last()
+                .map(|outcome| FieldValue::Text(outcome.outcome.as_str().to_string())),
+            "tag" => Some(FieldValue::List(self.tags.clone())),
+            _ => None,
+        }
+    }
+}
+
+impl Queryable for CovenantPattern {
+    const FIELDS: &'static [(&'static str, FieldKind)] = &[
+        ("id", FieldKind::Text),
+        ("created_at", FieldKind::Time),
+        ("namespace", FieldKind::Text),
+        ("scope", FieldKind::Text),
+        ("trigger", FieldKind::Text),
+        ("invariant", FieldKind::Text),
+        ("best_response", FieldKind::Text),
+        ("anti_pattern", FieldKind::Bool),
+        ("tag", FieldKind::List),
+        ("supports", FieldKind::Number),
+        ("contradictions", FieldKind::Number),
+    ];
+
+    fn field(&self, field: &str) -> Option<FieldValue> {
+        match field {
+            "id" => Some(FieldValue::Text(self.id.clone())),
+            "created_at" => Some(FieldValue::Time(self.created_at)),
+            "namespace" => Some(FieldValue::Text(self.namespace.clone())),
+            "scope" => Some(FieldValue::Text(self.scope.clone())),
+            "trigger" => Some(FieldValue::Text(self.trigger.clone())),
+            "invariant" => Some(FieldValue::Text(self.invariant.clone())),
+            "best_response" => Some(FieldValue::Text(self.best_response.clone())),
+            "anti_pattern" => Some(FieldValue::Bool(self.anti_pattern)),
+            "tag" => Some(FieldValue::List(self.tags.clone())),
+            "supports" => Some(FieldValue::Number(self.supports as f64)),
+            "contradictions" => Some(FieldValue::Number(self.contradictions as f64)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::outcome::Outcome;
+    use codex_state::CovenantIntent;
+    use codex_state::CovenantOutcome;
+    use pretty_assertions::assert_eq;
+
+    fn event(id: &str, created_at: i64, tags: &[&str], outcome: Option<Outcome>) -> CovenantEvent {
+        CovenantEvent {
+            id: id.to_string(),
+            created_at,
+            description: format!("{id} description"),
+            domain_signature: String::new(),
+            intent: Some(CovenantIntent {
+                id: format!("{id}-intent"),
+                goal: "Restore the build cache".to_string(),
+                constraints: String::new(),
+                success_signal: String::new(),
+                confidence: 0.6,
+            }),
+            hypotheses: Vec::new(),
+            tests: Vec::new(),
+            outcomes: outcome
+                .into_iter()
+                .map(|outcome| CovenantOutcome {
+                    id: format!("{id}-outcome"),
+                    summary: String::new(),
+                    outcome,
+                    evidence_refs: Vec::new(),
+                    evidence: Vec::new(),
+                })
+                .collect(),
+            tags: tags.iter().map(ToString::to_string).collect(),
+            session_id: None,
+            rollout_path: None,
+        }
+    }
+
+    fn matching(filter: &str, events: &[CovenantEvent]) -> Vec<String> {
+        let filter = Filter::parse::<CovenantEvent>(filter).expect("parse filter");
+        filter_records(events.to_vec(), Some(&filter))
+            .into_iter()
+            .map(|event| event.id)
+            .collect()
+    }
+
+    #[test]
+    fn filters_combine_comparisons_with_precedence() {
+        let june = 1_717_200_000; // 2024-06-01T00:00:00Z
+        let events = [
+            event("e1", june - 86_400, &["infra"], None),
+            event("e2", june + 3_600, &["infra", "flaky"], None),
+            event("e3", june + 7_200, &["infra"], Some(Outcome::Success)),
+            event("e4", june + 7_200, &["ui"], Some(Outcome::Failure)),
+        ];
+
+        assert_eq!(
+            (
+                matching(
+                    r#"tag = "infra" AND resolved = false AND created_at > 2024-06-01"#,
+                    &events
+                ),
+                matching(
+                    "tag = ui or outcome = success and NOT tag != infra",
+                    &events
+                ),
+                matching(
+                    "(tag = ui OR outcome = success) AND confidence >= 0.5",
+                    &events
+                ),
+                matching(
+                    "intent ~ 'BUILD cache' AND created_at <= 1717203600",
+                    &events
+                ),
+                matching("tag ~ FLA OR id = e1", &events),
+            ),
+            (
+                vec!["e2".to_string()],
+                vec!["e3".to_string(), "e4".to_string()],
+                vec!["e3".to_string(), "e4".to_string()],
+                vec!["e1".to_string(), "e2".to_string()],
+                vec!["e1".to_string(), "e2".to_string()],
+            )
+        );
+    }
+
+    #[test]
+    fn filters_are_checked_against_the_record_fields() {
+        let errors = [
+            "scope = infra",
+            "resolved > true",
+            "created_at > yesterday",
+            "tag = infra AND",
+            "(tag = infra",
+            "tag = 'infra",
+            "tag infra",
+        ]
+        .map(|filter| {
+            Filter::parse::<CovenantEvent>(filter)
+                .map(|_| ())
+                .map_err(|err| format!("{err:#}"))
+        });
+
+        assert_eq!(
+            errors.to_vec(),
+            vec![
+                Err("unknown field `scope` at column 1; expected one of id, created_at, description, intent, confidence, resolved, outcome, tag".to_string()),
+                Err("`resolved` cannot be compared with `>` at column 10".to_string()),
+                Err("`created_at` takes a date such as 2024-06-01 at column 14: input contains invalid characters".to_string()),
+                Err("expected a field name at column 16".to_string()),
+                Err("expected `)` at column 13".to_string()),
+                Err("unterminated string starting at column 7".to_string()),
+                Err("expected a comparison after `tag` at column 5, found `infra`".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn pattern_fields_can_be_filtered() {
+        let pattern = CovenantPattern {
+            id: "p1".to_string(),
+            trigger: "Disk full on {path}".to_string(),
+            invariant: "Logs rotate".to_string(),
+            counterexample: String::new(),
+            best_response: "Prune old logs".to_string(),
+            domain_signature: String::new(),
+            evidence_refs: Vec::new(),
+            namespace: "github.com/acme/app".to_string(),
+            scope: "default".to_string(),
+            created_at: 1_717_200_000,
+            anti_pattern: false,
+            tags: vec!["infra".to_string()],
+            counterexamples: Vec::new(),
+            supports: 0,
+            contradictions: 0,
+        };
+        let filter = Filter::parse::<CovenantPattern>(
+            "namespace ~ acme AND anti_pattern = false AND trigger ~ 'disk full'",
+        )
+        .expect("parse filter");
+
+        assert_eq!(
+            (
+                filter.matches(&pattern),
+                Filter::parse::<CovenantPattern>("tag != infra")
+                    .expect("parse filter")
+                    .matches(&pattern),
+            ),
+            (true, false)
+        );
+    }
+}
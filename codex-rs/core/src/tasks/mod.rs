@@ -270,6 +270,8 @@ impl Session {
             .await;
 
         if reason == TurnAbortReason::Interrupted {
+            self.warn_with_pattern_after_failure(task.turn_context.as_ref(), "turn interrupted")
+                .await;
             let marker = ResponseItem::Message {
                 id: None,
                 role: "user".to_string(),
@@ -1,28 +1,92 @@
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
 
 use async_trait::async_trait;
+use chrono::Utc;
 use codex_protocol::models::FunctionCallOutputBody;
+use codex_protocol::outcome::Outcome as OutcomeKind;
 use codex_protocol::request_user_input::RequestUserInputArgs;
 use codex_protocol::request_user_input::RequestUserInputQuestion;
+use codex_protocol::request_user_input::RequestUserInputQuestionOption;
 use codex_protocol::request_user_input::RequestUserInputResponse;
+use codex_protocol::test_status::TestStatus;
+use codex_state::CovenantEvent;
+use codex_state::CovenantEvidence;
+use codex_state::CovenantHypothesis;
+use codex_state::CovenantIntent;
+use codex_state::CovenantOutcome;
+use codex_state::CovenantTest;
+use codex_state::Redactor;
+use codex_state::to_canonical_json;
+use schemars::JsonSchema;
+use schemars::schema::RootSchema;
+use serde::Deserialize;
 use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tracing::warn;
+use uuid::Uuid;
 
+use crate::capture_locale::PromptCatalog;
+use crate::capture_locale::load_prompt_catalog;
+use crate::capture_numbers::NumberFormat;
+use crate::capture_prefill::CaptureDrafts;
+use crate::capture_template::CaptureSection;
+use crate::capture_template::CaptureTemplate;
+use crate::capture_template::load_capture_template;
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::covenant::CovenantAction;
+use crate::error::CodexErr;
+use crate::error::SandboxErr;
+use crate::exec::ExecParams;
+use crate::exec::ExecToolCallOutput;
+use crate::exec::process_exec_tool_call;
+use crate::exec_env::create_env;
+use crate::features::Feature;
 use crate::function_tool::FunctionCallError;
+use crate::protocol::EventMsg;
+use crate::protocol::WarningEvent;
+use crate::sandboxing::SandboxPermissions;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolOutput;
 use crate::tools::context::ToolPayload;
+use crate::tools::format_exec_output_str;
+use crate::tools::handlers::parse_arguments;
 use crate::tools::handlers::request_user_input_unavailable_message;
 use crate::tools::registry::ToolHandler;
 use crate::tools::registry::ToolKind;
+use crate::truncate::TruncationPolicy;
 
 const MAX_PROMPT_ATTEMPTS: usize = 5;
 
+const REVIEW_QUESTION_ID: &str = "review";
+
+const RUN_QUESTION_ID: &str = "run";
+
+/// How long a test command may run before its result is recorded as an error.
+const TEST_COMMAND_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+
+/// Tag of the covenant events stored from captures that timed out.
+const INCOMPLETE_TAG: &str = "incomplete";
+
 pub struct CaptureHandler;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Deserialize)]
+struct CaptureArgs {
+    /// Name of a template in `CODEX_HOME/capture_templates`.
+    #[serde(default)]
+    template: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, JsonSchema)]
 struct CaptureRecord {
+    status: CaptureStatus,
+    /// Section whose questions went unanswered when the capture timed out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timed_out_section: Option<String>,
     intent: IntentToken,
     event: EventDetails,
     hypotheses: Vec<Hypothesis>,
@@ -30,9 +94,23 @@ struct CaptureRecord {
     test_results: Vec<TestResult>,
     outcomes: Vec<Outcome>,
     patterns: Vec<Pattern>,
+    /// Probability updates whose Bayes factor is past the configured cap.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum CaptureStatus {
+    #[default]
+    Complete,
+    /// A group of questions went unanswered for `[capture]
+    /// prompt_timeout_secs`, so the record holds only the sections answered
+    /// before it.
+    Incomplete,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize, JsonSchema)]
 struct IntentToken {
     goal: String,
     constraints: String,
@@ -40,12 +118,12 @@ struct IntentToken {
     confidence: f64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize, JsonSchema)]
 struct EventDetails {
     details: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct Hypothesis {
     id: String,
     statement: String,
@@ -54,44 +132,72 @@ struct Hypothesis {
     domain_signature: Vec<DomainSignatureWeight>,
     test_ids: Vec<String>,
     probability_updates: Vec<ProbabilityUpdate>,
+    /// Hypotheses that must also hold for this one to hold.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<String>,
+    /// Hypotheses that cannot hold together with this one. Kept symmetric.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mutually_exclusive_with: Vec<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct DomainSignatureWeight {
     domain: String,
     weight: f64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct TestCase {
     id: String,
     description: String,
     procedure: String,
+    /// Shell command that runs the test, which the capture offers to run to
+    /// fill in its result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct TestResult {
     test_id: String,
-    result: String,
+    result: TestStatus,
     notes: String,
     probability_updates: Vec<ProbabilityUpdate>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    artifacts: Vec<ArtifactRef>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+/// A file offered as evidence, such as a log snippet or screenshot.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+struct ArtifactRef {
+    /// Relative to the turn's working directory when the file is under it.
+    path: String,
+    /// Hex SHA-256 of the file's contents when it was recorded.
+    sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 struct ProbabilityUpdate {
     hypothesis_id: String,
     prior: f64,
     posterior: f64,
     evidence_test_id: String,
+    /// Posterior odds over prior odds, or `None` when the prior or posterior
+    /// is 0 or 1 and the odds are undefined.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bayes_factor: Option<f64>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct Outcome {
     summary: String,
+    kind: OutcomeKind,
     evidence_test_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    artifacts: Vec<ArtifactRef>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 struct Pattern {
     trigger: String,
     invariant: String,
@@ -116,7 +222,7 @@ impl ToolHandler for CaptureHandler {
             ..
         } = invocation;
 
-        let ToolPayload::Function { .. } = payload else {
+        let ToolPayload::Function { arguments } = payload else {
             return Err(FunctionCallError::RespondToModel(
                 "capture handler received unsupported payload".to_string(),
             ));
@@ -126,43 +232,103 @@ impl ToolHandler for CaptureHandler {
         if let Some(message) = request_user_input_unavailable_message(mode) {
             return Err(FunctionCallError::RespondToModel(message));
         }
+        // A capture ends in an `event.log`, so scopes the covenant keeps from
+        // logging events do not get the interactive flow at all.
+        let capability = CovenantAction::EventLog.as_capability();
+        match session
+            .covenant_allows(turn.as_ref(), CovenantAction::EventLog)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => {
+                return Err(respond(format!(
+                    "capture is unavailable: the covenant does not grant {capability} to scope `{}`",
+                    turn.session_source
+                )));
+            }
+            Err(err) => return Err(respond(format!("capture is unavailable: {err:#}"))),
+        }
 
-        let intent = prompt_intent_token(session.as_ref(), turn.as_ref(), &call_id).await?;
-        let event = prompt_event_details(session.as_ref(), turn.as_ref(), &call_id).await?;
-        let mut hypotheses = prompt_hypotheses(session.as_ref(), turn.as_ref(), &call_id).await?;
-        let tests = prompt_tests(session.as_ref(), turn.as_ref(), &call_id).await?;
-        prompt_hypothesis_links(
-            session.as_ref(),
-            turn.as_ref(),
-            &call_id,
-            &tests,
-            &mut hypotheses,
+        let args: CaptureArgs = if arguments.trim().is_empty() {
+            CaptureArgs::default()
+        } else {
+            parse_arguments(&arguments)?
+        };
+        let template = load_capture_template(
+            session.codex_home().await.as_path(),
+            args.template.as_deref(),
         )
-        .await?;
-        let test_results = prompt_test_results(
+        .map_err(|err| respond(format!("{err:#}")))?;
+        let template = &template;
+        let prompts = load_prompt_catalog(
+            session.codex_home().await.as_path(),
+            turn.config.capture_locale.as_deref(),
+        )
+        .map_err(|err| respond(format!("{err:#}")))?
+        .with_numbers(NumberFormat {
+            decimal_separator: turn.config.capture_decimal_separator,
+            strict: turn.config.capture_strict_numbers,
+        });
+        let prompts = &prompts;
+
+        let mut record = CaptureRecord::default();
+        prompt_capture_sections(
             session.as_ref(),
             turn.as_ref(),
             &call_id,
-            &tests,
-            &mut hypotheses,
+            template,
+            prompts,
+            &mut record,
+            &CaptureSection::ALL,
         )
         .await?;
-        let outcomes = prompt_outcomes(session.as_ref(), turn.as_ref(), &call_id, &tests).await?;
-        let patterns = prompt_patterns(session.as_ref(), turn.as_ref(), &call_id, &tests).await?;
+        while record.status == CaptureStatus::Complete {
+            let section = match prompt_capture_review(
+                session.as_ref(),
+                turn.as_ref(),
+                &call_id,
+                prompts,
+                &record,
+            )
+            .await
+            {
+                Ok(ReviewChoice::Redo(section)) => section,
+                Ok(ReviewChoice::Save) => break,
+                // A review left unanswered saves the record as it is.
+                Err(PromptError::TimedOut) => break,
+                Err(PromptError::Failed(err)) => return Err(err),
+            };
+            prompt_capture_sections(
+                session.as_ref(),
+                turn.as_ref(),
+                &call_id,
+                template,
+                prompts,
+                &mut record,
+                redo_sections(section),
+            )
+            .await?;
+        }
 
-        let record = CaptureRecord {
-            intent,
-            event,
-            hypotheses,
-            tests,
-            test_results,
-            outcomes,
-            patterns,
-        };
+        record.warnings =
+            bayes_factor_warnings(&record.test_results, turn.config.capture_max_bayes_factor);
 
-        let content = serde_json::to_string_pretty(&record).map_err(|err| {
-            FunctionCallError::Fatal(format!("failed to serialize capture payload: {err}"))
-        })?;
+        if let Err(err) = persist_capture_record(session.as_ref(), turn.as_ref(), &record).await {
+            warn!("failed to persist capture record to the covenant store: {err}");
+        }
+
+        let redactor = Redactor::load(session.codex_home().await.as_path()).unwrap_or_else(|err| {
+            warn!("failed to load redaction config, using defaults: {err}");
+            Redactor::default()
+        });
+        let content = serde_json::to_value(&record)
+            .and_then(|mut value| {
+                redactor.redact_json(&mut value);
+                serde_json::to_string_pretty(&value)
+            })
+            .map_err(|err| {
+                FunctionCallError::Fatal(format!("failed to serialize capture payload: {err}"))
+            })?;
 
         Ok(ToolOutput::Function {
             body: FunctionCallOutputBody::Text(content),
@@ -171,34 +337,524 @@ impl ToolHandler for CaptureHandler {
     }
 }
 
+/// JSON Schema for the record returned by the capture tool.
+pub fn capture_schema() -> RootSchema {
+    schemars::schema_for!(CaptureRecord)
+}
+
+/// Prior→posterior history of one hypothesis within a capture.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HypothesisTimeline {
+    pub hypothesis_id: String,
+    pub statement: String,
+    /// Updates in the order the evidence was recorded.
+    pub points: Vec<BeliefPoint>,
+}
+
+/// One probability update, keyed by the test that produced the evidence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BeliefPoint {
+    /// Zero-based position of the update in the hypothesis' history.
+    pub step: usize,
+    pub evidence_test_id: String,
+    pub prior: f64,
+    pub posterior: f64,
+}
+
+/// The subset of a capture record needed to rebuild belief timelines. Older
+/// records may lack fields, so everything but the hypothesis id is optional.
+#[derive(Deserialize)]
+struct TimelineRecord {
+    #[serde(default)]
+    hypotheses: Vec<TimelineHypothesis>,
+}
+
+#[derive(Deserialize)]
+struct TimelineHypothesis {
+    id: String,
+    #[serde(default)]
+    statement: String,
+    #[serde(default)]
+    probability_updates: Vec<ProbabilityUpdate>,
+}
+
+/// Parse a capture tool output into per-hypothesis belief timelines.
+/// Hypotheses that were never updated are omitted.
+pub fn probability_timelines(output: &str) -> serde_json::Result<Vec<HypothesisTimeline>> {
+    let record: TimelineRecord = serde_json::from_str(output)?;
+    Ok(record
+        .hypotheses
+        .into_iter()
+        .filter(|hypothesis| !hypothesis.probability_updates.is_empty())
+        .map(|hypothesis| HypothesisTimeline {
+            hypothesis_id: hypothesis.id,
+            statement: hypothesis.statement,
+            points: hypothesis
+                .probability_updates
+                .into_iter()
+                .enumerate()
+                .map(|(step, update)| BeliefPoint {
+                    step,
+                    evidence_test_id: update.evidence_test_id,
+                    prior: update.prior,
+                    posterior: update.posterior,
+                })
+                .collect(),
+        })
+        .collect())
+}
+
 pub(crate) fn capture_tool_description() -> String {
-    "Capture intent, hypotheses, tests, outcomes, and patterns in a structured trace. Prompts the user for each step and returns a JSON record."
+    "Capture intent, hypotheses, tests, outcomes, and patterns in a structured trace. Prompts the user for each step, stores the trace as a covenant event, and returns a JSON record. Pass `template` when the user names a capture template."
         .to_string()
 }
 
+/// Persist a finished capture into the covenant store so it feeds the pattern
+/// compile pipeline. The write is audited as an `event.log` action and skipped
+/// when the covenant does not grant that capability.
+async fn persist_capture_record(
+    session: &Session,
+    turn: &TurnContext,
+    record: &CaptureRecord,
+) -> anyhow::Result<()> {
+    let Some(state_db) = session.covenant_db() else {
+        return Ok(());
+    };
+    let mut event =
+        covenant_event_from_capture(record, Uuid::new_v4().to_string(), Utc::now().timestamp())?;
+    event.session_id = Some(session.conversation_id.to_string());
+    event.rollout_path = session
+        .rollout_path()
+        .await
+        .map(|path| path.display().to_string());
+    let allowed = session
+        .audit_covenant_action(
+            turn,
+            CovenantAction::EventLog,
+            "agent",
+            Some(event.id.as_str()),
+            Some(turn.sub_id.as_str()),
+        )
+        .await?;
+    anyhow::ensure!(
+        allowed,
+        "covenant scope disallows {}",
+        CovenantAction::EventLog.as_capability()
+    );
+    state_db.insert_covenant_event(&event).await
+}
+
+fn covenant_event_from_capture(
+    record: &CaptureRecord,
+    event_id: String,
+    created_at: i64,
+) -> serde_json::Result<CovenantEvent> {
+    let evidence_ref = |test_id: &str| format!("capture://{event_id}/{test_id}");
+
+    let hypotheses = record
+        .hypotheses
+        .iter()
+        .map(|hypothesis| {
+            Ok(CovenantHypothesis {
+                id: format!("{event_id}:{}", hypothesis.id),
+                model_type: hypothesis.statement.clone(),
+                probability: hypothesis.probability,
+                falsifiers: hypothesis.falsifiers.clone(),
+                domain_signature: to_canonical_json(&hypothesis.domain_signature)?,
+            })
+        })
+        .collect::<serde_json::Result<Vec<_>>>()?;
+
+    let mut tests = Vec::new();
+    for (index, result) in record.test_results.iter().enumerate() {
+        let description = record
+            .tests
+            .iter()
+            .find(|test| test.id == result.test_id)
+            .map(|test| test.description.clone())
+            .unwrap_or_default();
+        let linked = record
+            .hypotheses
+            .iter()
+            .filter(|hypothesis| hypothesis.test_ids.contains(&result.test_id))
+            .map(|hypothesis| hypothesis.id.as_str());
+        let updated = result
+            .probability_updates
+            .iter()
+            .map(|update| update.hypothesis_id.as_str());
+        let mut hypothesis_ids: Vec<&str> = Vec::new();
+        for hypothesis_id in linked.chain(updated) {
+            if !hypothesis_ids.contains(&hypothesis_id) {
+                hypothesis_ids.push(hypothesis_id);
+            }
+        }
+        for hypothesis_id in hypothesis_ids {
+            tests.push(CovenantTest {
+                id: format!("{event_id}:R{}:{hypothesis_id}", index + 1),
+                hypothesis_id: format!("{event_id}:{hypothesis_id}"),
+                description: description.clone(),
+                result: result.result,
+                evidence_ref: evidence_ref(&result.test_id),
+            });
+        }
+    }
+
+    let outcomes = record
+        .outcomes
+        .iter()
+        .enumerate()
+        .map(|(index, outcome)| {
+            let evidence_refs = outcome
+                .evidence_test_ids
+                .iter()
+                .map(|test_id| evidence_ref(test_id))
+                .collect::<Vec<_>>();
+            CovenantOutcome {
+                id: format!("{event_id}:O{}", index + 1),
+                summary: outcome.summary.clone(),
+                outcome: outcome.kind,
+                evidence: evidence_refs.iter().map(CovenantEvidence::test).collect(),
+                evidence_refs,
+            }
+        })
+        .collect();
+
+    let intent = CovenantIntent {
+        id: format!("{event_id}:intent"),
+        goal: record.intent.goal.clone(),
+        constraints: record.intent.constraints.clone(),
+        success_signal: record.intent.success_signal.clone(),
+        confidence: record.intent.confidence,
+    };
+
+    Ok(CovenantEvent {
+        description: record.event.details.clone(),
+        domain_signature: to_canonical_json(&event_domain_signature(&record.hypotheses))?,
+        created_at,
+        intent: Some(intent),
+        hypotheses,
+        tests,
+        outcomes,
+        id: event_id,
+        tags: match record.status {
+            CaptureStatus::Complete => Vec::new(),
+            CaptureStatus::Incomplete => vec![INCOMPLETE_TAG.to_string()],
+        },
+        session_id: None,
+        rollout_path: None,
+    })
+}
+
+/// Mix hypothesis domain signatures into a single event signature, weighting
+/// each hypothesis by its final probability.
+fn event_domain_signature(hypotheses: &[Hypothesis]) -> Vec<DomainSignatureWeight> {
+    let mut weights: BTreeMap<String, f64> = BTreeMap::new();
+    for hypothesis in hypotheses {
+        for entry in &hypothesis.domain_signature {
+            *weights.entry(entry.domain.clone()).or_insert(0.0) +=
+                entry.weight * hypothesis.probability;
+        }
+    }
+    weights
+        .into_iter()
+        .map(|(domain, weight)| DomainSignatureWeight { domain, weight })
+        .collect()
+}
+
+/// Prompt for `sections` in capture order, replacing what `record` holds for
+/// them. Outcome kinds are classified again afterwards, since they can follow
+/// the test results. When a prompt times out, the remaining sections are
+/// skipped and `record` is marked incomplete.
+async fn prompt_capture_sections(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+    record: &mut CaptureRecord,
+    sections: &[CaptureSection],
+) -> Result<(), FunctionCallError> {
+    for section in CaptureSection::ALL
+        .into_iter()
+        .filter(|section| sections.contains(section))
+    {
+        match prompt_capture_section(session, turn, call_id, template, prompts, record, section)
+            .await
+        {
+            Ok(()) => {}
+            Err(PromptError::TimedOut) => {
+                record.status = CaptureStatus::Incomplete;
+                record.timed_out_section = Some(section.key().to_string());
+                break;
+            }
+            Err(PromptError::Failed(err)) => return Err(err),
+        }
+    }
+    for outcome in &mut record.outcomes {
+        outcome.kind = classify_outcome(
+            &outcome.summary,
+            &outcome.evidence_test_ids,
+            &record.test_results,
+        );
+    }
+    Ok(())
+}
+
+/// Prompt for `section`, replacing what `record` holds for it.
+async fn prompt_capture_section(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+    record: &mut CaptureRecord,
+    section: CaptureSection,
+) -> Result<(), PromptError> {
+    match section {
+        CaptureSection::Intent => {
+            record.intent = prompt_intent_token(session, turn, call_id, template, prompts).await?;
+        }
+        CaptureSection::Event => {
+            record.event = prompt_event_details(session, turn, call_id, template, prompts).await?;
+        }
+        CaptureSection::Hypotheses => {
+            record.hypotheses =
+                prompt_hypotheses(session, turn, call_id, template, prompts).await?;
+        }
+        CaptureSection::HypothesisRelations => {
+            for hypothesis in &mut record.hypotheses {
+                hypothesis.depends_on.clear();
+                hypothesis.mutually_exclusive_with.clear();
+            }
+            prompt_hypothesis_relations(
+                session,
+                turn,
+                call_id,
+                template,
+                prompts,
+                &mut record.hypotheses,
+            )
+            .await?;
+        }
+        CaptureSection::Tests => {
+            record.tests = prompt_tests(session, turn, call_id, template, prompts).await?;
+        }
+        CaptureSection::HypothesisLinks => {
+            prompt_hypothesis_links(
+                session,
+                turn,
+                call_id,
+                template,
+                prompts,
+                &record.tests,
+                &mut record.hypotheses,
+            )
+            .await?;
+        }
+        CaptureSection::TestResults => {
+            reset_probability_updates(&mut record.hypotheses);
+            record.test_results = prompt_test_results(
+                session,
+                turn,
+                call_id,
+                template,
+                prompts,
+                &record.tests,
+                &mut record.hypotheses,
+            )
+            .await?;
+        }
+        CaptureSection::Outcomes => {
+            record.outcomes = prompt_outcomes(
+                session,
+                turn,
+                call_id,
+                template,
+                prompts,
+                &record.tests,
+                &record.test_results,
+            )
+            .await?;
+        }
+        CaptureSection::Patterns => {
+            record.patterns =
+                prompt_patterns(session, turn, call_id, template, prompts, &record.tests).await?;
+        }
+    }
+    Ok(())
+}
+
+/// The sections to answer again when the user redoes `section`: the section
+/// itself and those whose answers refer to its entries.
+fn redo_sections(section: CaptureSection) -> &'static [CaptureSection] {
+    match section {
+        CaptureSection::Intent => &[CaptureSection::Intent],
+        CaptureSection::Event => &[CaptureSection::Event],
+        CaptureSection::Hypotheses => &[
+            CaptureSection::Hypotheses,
+            CaptureSection::HypothesisRelations,
+            CaptureSection::HypothesisLinks,
+            CaptureSection::TestResults,
+        ],
+        CaptureSection::HypothesisRelations => &[CaptureSection::HypothesisRelations],
+        CaptureSection::Tests => &[
+            CaptureSection::Tests,
+            CaptureSection::HypothesisLinks,
+            CaptureSection::TestResults,
+            CaptureSection::Outcomes,
+            CaptureSection::Patterns,
+        ],
+        CaptureSection::HypothesisLinks => &[CaptureSection::HypothesisLinks],
+        CaptureSection::TestResults => &[CaptureSection::TestResults],
+        CaptureSection::Outcomes => &[CaptureSection::Outcomes],
+        CaptureSection::Patterns => &[CaptureSection::Patterns],
+    }
+}
+
+/// Undo the probability updates of earlier test results, restoring each
+/// hypothesis to its prior.
+fn reset_probability_updates(hypotheses: &mut [Hypothesis]) {
+    for hypothesis in hypotheses {
+        if let Some(first) = hypothesis.probability_updates.first() {
+            hypothesis.probability = first.prior;
+        }
+        hypothesis.probability_updates.clear();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReviewChoice {
+    Save,
+    Redo(CaptureSection),
+}
+
+/// Show a summary of the finished record and ask whether to save it or
+/// answer a section again. An answer that names neither is asked again, and
+/// after [`MAX_PROMPT_ATTEMPTS`] the record is saved.
+async fn prompt_capture_review(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    prompts: &PromptCatalog,
+    record: &CaptureRecord,
+) -> Result<ReviewChoice, PromptError> {
+    let mut options = vec![RequestUserInputQuestionOption {
+        label: prompts.review_text("save", &[]),
+        description: prompts.review_text("save_description", &[]),
+    }];
+    options.extend(CaptureSection::ALL.into_iter().map(|section| {
+        let label = prompts.section_label(section);
+        RequestUserInputQuestionOption {
+            description: prompts.review_text("redo", &[("section", label.as_str())]),
+            label,
+        }
+    }));
+    let args = RequestUserInputArgs {
+        questions: vec![RequestUserInputQuestion {
+            id: REVIEW_QUESTION_ID.to_string(),
+            header: prompts.review_text("header", &[]),
+            question: format!(
+                "{}\n\n{}",
+                capture_summary(prompts, record),
+                prompts.review_text("question", &[])
+            ),
+            is_other: false,
+            is_secret: false,
+            options: Some(options),
+        }],
+    };
+    for _ in 0..MAX_PROMPT_ATTEMPTS {
+        let response = request_user_input(
+            session,
+            turn,
+            &format!("capture-{call_id}-review"),
+            args.clone(),
+        )
+        .await?;
+        if let Some(choice) = extract_answer(&response, REVIEW_QUESTION_ID)
+            .and_then(|answer| review_choice(prompts, &answer))
+        {
+            return Ok(choice);
+        }
+    }
+    Ok(ReviewChoice::Save)
+}
+
+/// Match an answer to the save option or a section, by label or section key.
+fn review_choice(prompts: &PromptCatalog, answer: &str) -> Option<ReviewChoice> {
+    let answer = answer.trim();
+    if answer.eq_ignore_ascii_case(&prompts.review_text("save", &[])) {
+        return Some(ReviewChoice::Save);
+    }
+    CaptureSection::ALL
+        .into_iter()
+        .find(|section| {
+            answer.eq_ignore_ascii_case(&prompts.section_label(*section))
+                || answer.eq_ignore_ascii_case(section.key())
+        })
+        .map(ReviewChoice::Redo)
+}
+
+/// The intent, the number of entries in each section, and each outcome with
+/// its kind.
+fn capture_summary(prompts: &PromptCatalog, record: &CaptureRecord) -> String {
+    let outcome_list = record
+        .outcomes
+        .iter()
+        .map(|outcome| format!("{}: {}", outcome.kind, outcome.summary))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let confidence = format!("{:.2}", record.intent.confidence);
+    let counts = [
+        record.hypotheses.len(),
+        record.tests.len(),
+        record.test_results.len(),
+        record.outcomes.len(),
+        record.patterns.len(),
+    ]
+    .map(|count| count.to_string());
+    prompts.review_text(
+        "summary",
+        &[
+            ("goal", record.intent.goal.as_str()),
+            ("confidence", confidence.as_str()),
+            ("event", record.event.details.as_str()),
+            ("hypotheses", counts[0].as_str()),
+            ("tests", counts[1].as_str()),
+            ("test_results", counts[2].as_str()),
+            ("outcomes", counts[3].as_str()),
+            ("patterns", counts[4].as_str()),
+            ("outcome_list", outcome_list.as_str()),
+        ],
+    )
+}
+
 async fn prompt_intent_token(
     session: &Session,
     turn: &TurnContext,
     call_id: &str,
-) -> Result<IntentToken, FunctionCallError> {
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+) -> Result<IntentToken, PromptError> {
     let mut attempts = 0;
     loop {
         attempts += 1;
-        let answers = prompt_questions(
+        let answers = prompt_section(
             session,
             turn,
             call_id,
-            "Intent token",
-            vec![
-                ("goal", "What is the goal?"),
-                ("constraints", "What constraints must be respected?"),
-                ("success_signal", "What signals success?"),
-                ("confidence", "What is your confidence (0-1 or 0-100%)?"),
-            ],
+            template,
+            prompts,
+            CaptureSection::Intent,
+            &[],
         )
         .await?;
 
-        let confidence = parse_probability(answers.get("confidence").map(String::as_str))?;
+        let confidence = parse_probability(
+            prompts.numbers(),
+            "confidence",
+            answers.get("confidence").map(String::as_str),
+        )?;
         if confidence.is_none() && attempts < MAX_PROMPT_ATTEMPTS {
             continue;
         }
@@ -218,13 +874,20 @@ async fn prompt_event_details(
     session: &Session,
     turn: &TurnContext,
     call_id: &str,
-) -> Result<EventDetails, FunctionCallError> {
-    let answers = prompt_questions(
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+) -> Result<EventDetails, PromptError> {
+    let section = CaptureSection::Event;
+    let drafts = session_drafts(session).await;
+    let answers = prompt_drafted_section(
         session,
         turn,
         call_id,
-        "Event details",
-        vec![("details", "Describe the event details.")],
+        template,
+        prompts,
+        section,
+        &[],
+        &drafts.answers(prompts, section, 0),
     )
     .await?;
     Ok(EventDetails {
@@ -236,48 +899,46 @@ async fn prompt_hypotheses(
     session: &Session,
     turn: &TurnContext,
     call_id: &str,
-) -> Result<Vec<Hypothesis>, FunctionCallError> {
-    let count = prompt_number_in_range(
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+) -> Result<Vec<Hypothesis>, PromptError> {
+    let count = prompt_section_count(
         session,
         turn,
         call_id,
-        "Hypotheses",
-        "How many hypotheses? (3-7)",
-        3,
-        7,
+        template,
+        prompts,
+        CaptureSection::Hypotheses,
     )
     .await?;
 
     let mut hypotheses = Vec::with_capacity(count);
     for index in 0..count {
         let id = format!("H{}", index + 1);
-        let answers = prompt_questions(
+        let answers = prompt_section(
             session,
             turn,
             call_id,
-            "Hypothesis",
-            vec![
-                ("statement", "Hypothesis statement"),
-                ("probability", "Prior probability (0-1 or 0-100%)"),
-                (
-                    "falsifiers",
-                    "Falsifier(s) (comma/semicolon/newline separated)",
-                ),
-                (
-                    "domain_signature",
-                    "Domain-signature mixture vector (domain:weight, ...)",
-                ),
-            ],
+            template,
+            prompts,
+            CaptureSection::Hypotheses,
+            &[],
         )
         .await?;
 
-        let probability = parse_probability(answers.get("probability").map(String::as_str))?
-            .ok_or_else(|| respond("probability must be a number between 0 and 1"))?;
+        let probability = parse_probability(
+            prompts.numbers(),
+            "probability",
+            answers.get("probability").map(String::as_str),
+        )?
+        .ok_or_else(|| respond("probability must be a number between 0 and 1"))?;
         let falsifiers = split_list(require_field(&answers, "falsifiers")?.as_str())
             .into_iter()
             .collect();
-        let domain_signature =
-            parse_domain_signature(require_field(&answers, "domain_signature")?.as_str())?;
+        let domain_signature = parse_domain_signature(
+            prompts.numbers(),
+            require_field(&answers, "domain_signature")?.as_str(),
+        )?;
 
         hypotheses.push(Hypothesis {
             id,
@@ -287,39 +948,195 @@ async fn prompt_hypotheses(
             domain_signature,
             test_ids: Vec::new(),
             probability_updates: Vec::new(),
+            depends_on: Vec::new(),
+            mutually_exclusive_with: Vec::new(),
         });
     }
     Ok(hypotheses)
 }
 
+/// Ask which other hypotheses each one depends on or excludes. Skipped when
+/// there is only one hypothesis.
+async fn prompt_hypothesis_relations(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+    hypotheses: &mut [Hypothesis],
+) -> Result<(), PromptError> {
+    if hypotheses.len() < 2 {
+        return Ok(());
+    }
+    let catalog = hypotheses
+        .iter()
+        .map(|hypothesis| format!("{}: {}", hypothesis.id, hypothesis.statement))
+        .collect::<Vec<_>>()
+        .join(" | ");
+    let ids = hypotheses
+        .iter()
+        .map(|hypothesis| hypothesis.id.clone())
+        .collect::<Vec<_>>();
+
+    let mut relations = Vec::with_capacity(hypotheses.len());
+    for hypothesis in hypotheses.iter() {
+        let answers = prompt_section(
+            session,
+            turn,
+            call_id,
+            template,
+            prompts,
+            CaptureSection::HypothesisRelations,
+            &[
+                ("id", hypothesis.id.as_str()),
+                ("hypotheses", catalog.as_str()),
+            ],
+        )
+        .await?;
+        let depends_on = parse_hypothesis_ids(
+            answers.get("depends_on").map_or("", String::as_str),
+            &hypothesis.id,
+            &ids,
+        )?;
+        let excludes = parse_hypothesis_ids(
+            answers
+                .get("mutually_exclusive_with")
+                .map_or("", String::as_str),
+            &hypothesis.id,
+            &ids,
+        )?;
+        relations.push((depends_on, excludes));
+    }
+
+    for (hypothesis, (depends_on, _)) in hypotheses.iter_mut().zip(&relations) {
+        hypothesis.depends_on = depends_on.clone();
+    }
+    for (id, (_, excludes)) in ids.iter().zip(&relations) {
+        for excluded in excludes {
+            add_exclusion(hypotheses, id, excluded);
+            add_exclusion(hypotheses, excluded, id);
+        }
+    }
+    validate_hypothesis_relations(hypotheses).map_err(respond)?;
+    Ok(())
+}
+
+fn add_exclusion(hypotheses: &mut [Hypothesis], id: &str, excluded: &str) {
+    if let Some(hypothesis) = hypotheses.iter_mut().find(|hypothesis| hypothesis.id == id)
+        && !hypothesis
+            .mutually_exclusive_with
+            .iter()
+            .any(|existing| existing == excluded)
+    {
+        hypothesis
+            .mutually_exclusive_with
+            .push(excluded.to_string());
+    }
+}
+
+/// Hypothesis ids from a list answer. `none` (or `-`) is an empty list; a
+/// hypothesis may not name itself.
+fn parse_hypothesis_ids(
+    text: &str,
+    own_id: &str,
+    ids: &[String],
+) -> Result<Vec<String>, FunctionCallError> {
+    let trimmed = text.trim();
+    if trimmed.is_empty() || trimmed == "-" || trimmed.eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    let mut parsed: Vec<String> = Vec::new();
+    for id in split_list(trimmed) {
+        if id == own_id {
+            return Err(respond(format!("{own_id} cannot be related to itself")));
+        }
+        if !ids.contains(&id) {
+            return Err(respond(format!("unknown hypothesis id '{id}'")));
+        }
+        if !parsed.contains(&id) {
+            parsed.push(id);
+        }
+    }
+    Ok(parsed)
+}
+
+/// Check that no hypothesis requires, directly or through other
+/// dependencies, two hypotheses that exclude each other (itself included).
+fn validate_hypothesis_relations(hypotheses: &[Hypothesis]) -> Result<(), String> {
+    let by_id = hypotheses
+        .iter()
+        .map(|hypothesis| (hypothesis.id.as_str(), hypothesis))
+        .collect::<BTreeMap<_, _>>();
+    for hypothesis in hypotheses {
+        let mut required = BTreeSet::from([hypothesis.id.as_str()]);
+        let mut pending = vec![hypothesis];
+        while let Some(current) = pending.pop() {
+            for dependency in &current.depends_on {
+                if required.insert(dependency.as_str())
+                    && let Some(next) = by_id.get(dependency.as_str())
+                {
+                    pending.push(next);
+                }
+            }
+        }
+        for id in &required {
+            let Some(member) = by_id.get(id) else {
+                continue;
+            };
+            if let Some(excluded) = member
+                .mutually_exclusive_with
+                .iter()
+                .find(|excluded| required.contains(excluded.as_str()))
+            {
+                let own_id = hypothesis.id.as_str();
+                return Err(if *id == own_id || excluded == own_id {
+                    let other = if *id == own_id {
+                        excluded.as_str()
+                    } else {
+                        *id
+                    };
+                    format!("{own_id} depends on {other}, which it excludes")
+                } else {
+                    format!(
+                        "{own_id} requires both {id} and {excluded}, which are mutually exclusive"
+                    )
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
 async fn prompt_tests(
     session: &Session,
     turn: &TurnContext,
     call_id: &str,
-) -> Result<Vec<TestCase>, FunctionCallError> {
-    let count = prompt_number_in_range(
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+) -> Result<Vec<TestCase>, PromptError> {
+    let count = prompt_section_count(
         session,
         turn,
         call_id,
-        "Tests",
-        "How many tests? (1-10)",
-        1,
-        10,
+        template,
+        prompts,
+        CaptureSection::Tests,
     )
     .await?;
 
+    let drafts = session_drafts(session).await;
     let mut tests = Vec::with_capacity(count);
     for index in 0..count {
         let id = format!("T{}", index + 1);
-        let answers = prompt_questions(
+        let answers = prompt_drafted_section(
             session,
             turn,
             call_id,
-            "Test",
-            vec![
-                ("description", "Test description"),
-                ("procedure", "Test procedure / steps"),
-            ],
+            template,
+            prompts,
+            CaptureSection::Tests,
+            &[],
+            &drafts.answers(prompts, CaptureSection::Tests, index),
         )
         .await?;
 
@@ -327,6 +1144,7 @@ async fn prompt_tests(
             id,
             description: require_field(&answers, "description")?,
             procedure: require_field(&answers, "procedure")?,
+            command: parse_test_command(answers.get("command").map_or("", String::as_str)),
         });
     }
 
@@ -337,9 +1155,11 @@ async fn prompt_hypothesis_links(
     session: &Session,
     turn: &TurnContext,
     call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
     tests: &[TestCase],
     hypotheses: &mut [Hypothesis],
-) -> Result<(), FunctionCallError> {
+) -> Result<(), PromptError> {
     let test_catalog = tests
         .iter()
         .map(|test| format!("{}: {}", test.id, test.description))
@@ -347,16 +1167,18 @@ async fn prompt_hypothesis_links(
         .join(" | ");
 
     for hypothesis in hypotheses {
-        let question = format!(
-            "Link tests for {} ({})? Available: {}",
-            hypothesis.id, hypothesis.statement, test_catalog
-        );
-        let answers = prompt_questions(
+        let answers = prompt_section(
             session,
             turn,
             call_id,
-            "Hypothesis tests",
-            vec![("tests", &question)],
+            template,
+            prompts,
+            CaptureSection::HypothesisLinks,
+            &[
+                ("id", hypothesis.id.as_str()),
+                ("statement", hypothesis.statement.as_str()),
+                ("tests", test_catalog.as_str()),
+            ],
         )
         .await?;
         let ids = split_list(require_field(&answers, "tests")?.as_str());
@@ -370,17 +1192,21 @@ async fn prompt_test_results(
     session: &Session,
     turn: &TurnContext,
     call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
     tests: &[TestCase],
     hypotheses: &mut [Hypothesis],
-) -> Result<Vec<TestResult>, FunctionCallError> {
-    let count = prompt_number_in_range(
+) -> Result<Vec<TestResult>, PromptError> {
+    let mut results =
+        run_test_commands(session, turn, call_id, template, prompts, tests, hypotheses).await?;
+    let count = prompt_remaining_count(
         session,
         turn,
         call_id,
-        "Test results",
-        "How many test results are you recording? (1-10)",
-        1,
-        10,
+        template,
+        prompts,
+        CaptureSection::TestResults,
+        results.len(),
     )
     .await?;
 
@@ -395,24 +1221,20 @@ async fn prompt_test_results(
         .map(|hypothesis| format!("{}: {}", hypothesis.id, hypothesis.statement))
         .collect::<Vec<_>>()
         .join(" | ");
+    let roots = artifact_roots(turn);
 
-    let mut results = Vec::with_capacity(count);
+    results.reserve(count);
     for _ in 0..count {
-        let answers = prompt_questions(
+        let answers = prompt_section(
             session,
             turn,
             call_id,
-            "Test result",
-            vec![
-                ("test_id", &format!("Test id (choose one): {test_catalog}")),
-                ("result", "Result (pass/fail/inconclusive)"),
-                ("notes", "Notes / observations"),
-                (
-                    "updates",
-                    &format!(
-                        "Update hypothesis probabilities as H1=0.7,H2=0.2 (available: {hypothesis_catalog})"
-                    ),
-                ),
+            template,
+            prompts,
+            CaptureSection::TestResults,
+            &[
+                ("tests", test_catalog.as_str()),
+                ("hypotheses", hypothesis_catalog.as_str()),
             ],
         )
         .await?;
@@ -423,31 +1245,257 @@ async fn prompt_test_results(
             require_field(&answers, "updates")?.as_str(),
             &test_id,
             hypotheses,
+            prompts.numbers(),
         )?;
         results.push(TestResult {
             test_id,
-            result: require_field(&answers, "result")?,
+            result: TestStatus::parse(require_field(&answers, "result")?.as_str())
+                .map_err(|err| respond(err.to_string()))?,
             notes: require_field(&answers, "notes")?,
             probability_updates: updates,
+            artifacts: parse_artifacts(
+                answers.get("artifacts").map_or("", String::as_str),
+                &turn.cwd,
+                &roots,
+            )?,
+        });
+    }
+    Ok(results)
+}
+
+/// Offer to run each test that has a command. A test the user runs gets its
+/// result from the command's exit status and output, and only the probability
+/// updates are asked for.
+async fn run_test_commands(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+    tests: &[TestCase],
+    hypotheses: &mut [Hypothesis],
+) -> Result<Vec<TestResult>, PromptError> {
+    let section = CaptureSection::TestResults;
+    let header = prompts.text(section, "header", &[]);
+    let accept = prompts.text(section, "run_accept", &[]);
+    let hypothesis_catalog = hypotheses
+        .iter()
+        .map(|hypothesis| format!("{}: {}", hypothesis.id, hypothesis.statement))
+        .collect::<Vec<_>>()
+        .join(" | ");
+    let updates_question = template.section(section).question(
+        "updates",
+        &prompts.text(
+            section,
+            "updates",
+            &[("hypotheses", hypothesis_catalog.as_str())],
+        ),
+    );
+
+    let mut results = Vec::new();
+    for test in tests {
+        let Some(command) = test.command.as_deref() else {
+            continue;
+        };
+        let args = RequestUserInputArgs {
+            questions: vec![RequestUserInputQuestion {
+                id: RUN_QUESTION_ID.to_string(),
+                header: header.clone(),
+                question: prompts.text(
+                    section,
+                    "run",
+                    &[
+                        ("id", test.id.as_str()),
+                        ("description", test.description.as_str()),
+                        ("command", command),
+                    ],
+                ),
+                is_other: false,
+                is_secret: false,
+                options: Some(vec![
+                    RequestUserInputQuestionOption {
+                        label: accept.clone(),
+                        description: prompts.text(section, "run_accept_description", &[]),
+                    },
+                    RequestUserInputQuestionOption {
+                        label: prompts.text(section, "run_decline", &[]),
+                        description: prompts.text(section, "run_decline_description", &[]),
+                    },
+                ]),
+            }],
+        };
+        let response = request_user_input(
+            session,
+            turn,
+            &format!("capture-{call_id}-run-{}", test.id),
+            args,
+        )
+        .await?;
+        if !extract_answer(&response, RUN_QUESTION_ID)
+            .is_some_and(|answer| answer.eq_ignore_ascii_case(&accept))
+        {
+            continue;
+        }
+        let Some((result, notes)) = run_test_command(session, turn, test, command).await else {
+            continue;
+        };
+
+        let rules = template.section(section);
+        let answers = prompt_questions(
+            session,
+            turn,
+            call_id,
+            &header,
+            vec![("updates", updates_question.as_str())],
+            &BTreeMap::new(),
+            |id, answer| rules.check_answer(id, answer),
+        )
+        .await?;
+        rules.check(&answers).map_err(respond)?;
+        let probability_updates = parse_probability_updates(
+            require_field(&answers, "updates")?.as_str(),
+            &test.id,
+            hypotheses,
+            prompts.numbers(),
+        )?;
+        results.push(TestResult {
+            test_id: test.id.clone(),
+            result,
+            notes,
+            probability_updates,
+            artifacts: Vec::new(),
         });
     }
     Ok(results)
 }
 
+/// Run `command` for `test` in the turn's sandbox and working directory, and
+/// read the test's status and notes from how it exited. Running it is an
+/// `intervention.user_shell` action, since the user chose to; when the
+/// covenant does not grant it, a warning is shown and `None` is returned so
+/// the result is recorded by hand.
+async fn run_test_command(
+    session: &Session,
+    turn: &TurnContext,
+    test: &TestCase,
+    command: &str,
+) -> Option<(TestStatus, String)> {
+    let action = CovenantAction::InterventionUserShell;
+    let denied = match session
+        .audit_covenant_action(
+            turn,
+            action,
+            "user",
+            Some(test.id.as_str()),
+            Some(turn.sub_id.as_str()),
+        )
+        .await
+    {
+        Ok(true) => None,
+        Ok(false) => Some(format!(
+            "covenant scope disallows {}",
+            action.as_capability()
+        )),
+        Err(err) => Some(format!(
+            "covenant audit failed for {}: {err}",
+            action.as_capability()
+        )),
+    };
+    if let Some(reason) = denied {
+        let message = format!("not running the command for {}: {reason}", test.id);
+        session
+            .send_event(turn, EventMsg::Warning(WarningEvent { message }))
+            .await;
+        return None;
+    }
+
+    let params = ExecParams {
+        command: session.user_shell().derive_exec_args(command, true),
+        cwd: turn.cwd.clone(),
+        expiration: TEST_COMMAND_TIMEOUT_MS.into(),
+        env: create_env(
+            &turn.shell_environment_policy,
+            Some(session.conversation_id),
+        ),
+        sandbox_permissions: SandboxPermissions::UseDefault,
+        windows_sandbox_level: turn.windows_sandbox_level,
+        justification: None,
+        arg0: None,
+    };
+    let output = process_exec_tool_call(
+        params,
+        &turn.sandbox_policy,
+        &turn.cwd,
+        &turn.codex_linux_sandbox_exe,
+        turn.features.enabled(Feature::UseLinuxSandboxBwrap),
+        None,
+    )
+    .await;
+    Some(executed_test_result(
+        command,
+        output,
+        turn.truncation_policy,
+    ))
+}
+
+/// A passing result for a command that exited with 0 and a failing one for
+/// any other exit code. Commands that timed out, were blocked by the sandbox,
+/// or could not start are errors. The notes hold the exit status and output.
+fn executed_test_result(
+    command: &str,
+    output: Result<ExecToolCallOutput, CodexErr>,
+    truncation_policy: TruncationPolicy,
+) -> (TestStatus, String) {
+    match output {
+        Ok(output) => (
+            if output.exit_code == 0 {
+                TestStatus::Pass
+            } else {
+                TestStatus::Fail
+            },
+            format!(
+                "`{command}` exited with code {}.\n{}",
+                output.exit_code,
+                format_exec_output_str(&output, truncation_policy)
+            ),
+        ),
+        Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => (
+            TestStatus::Error,
+            format!(
+                "`{command}` timed out.\n{}",
+                format_exec_output_str(&output, truncation_policy)
+            ),
+        ),
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output })) => (
+            TestStatus::Error,
+            format!(
+                "the sandbox blocked `{command}`.\n{}",
+                format_exec_output_str(&output, truncation_policy)
+            ),
+        ),
+        Err(err) => (
+            TestStatus::Error,
+            format!("`{command}` could not run: {err}"),
+        ),
+    }
+}
+
 async fn prompt_outcomes(
     session: &Session,
     turn: &TurnContext,
     call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
     tests: &[TestCase],
-) -> Result<Vec<Outcome>, FunctionCallError> {
-    let count = prompt_number_in_range(
+    test_results: &[TestResult],
+) -> Result<Vec<Outcome>, PromptError> {
+    let count = prompt_section_count(
         session,
         turn,
         call_id,
-        "Outcomes",
-        "How many outcomes are you recording? (1-5)",
-        1,
-        5,
+        template,
+        prompts,
+        CaptureSection::Outcomes,
     )
     .await?;
     let test_catalog = tests
@@ -455,21 +1503,20 @@ async fn prompt_outcomes(
         .map(|test| format!("{}: {}", test.id, test.description))
         .collect::<Vec<_>>()
         .join(" | ");
+    let roots = artifact_roots(turn);
+    let drafts = session_drafts(session).await;
 
     let mut outcomes = Vec::with_capacity(count);
-    for _ in 0..count {
-        let answers = prompt_questions(
+    for index in 0..count {
+        let answers = prompt_drafted_section(
             session,
             turn,
             call_id,
-            "Outcome",
-            vec![
-                ("summary", "Outcome summary"),
-                (
-                    "evidence",
-                    &format!("Evidence test ids (available: {test_catalog})"),
-                ),
-            ],
+            template,
+            prompts,
+            CaptureSection::Outcomes,
+            &[("tests", test_catalog.as_str())],
+            &drafts.answers(prompts, CaptureSection::Outcomes, index),
         )
         .await?;
 
@@ -477,28 +1524,54 @@ async fn prompt_outcomes(
             &split_list(require_field(&answers, "evidence")?.as_str()),
             tests,
         )?;
+        let summary = require_field(&answers, "summary")?;
         outcomes.push(Outcome {
-            summary: require_field(&answers, "summary")?,
+            kind: classify_outcome(&summary, &evidence_ids, test_results),
+            summary,
             evidence_test_ids: evidence_ids,
+            artifacts: parse_artifacts(
+                answers.get("artifacts").map_or("", String::as_str),
+                &turn.cwd,
+                &roots,
+            )?,
         });
     }
     Ok(outcomes)
 }
 
+/// Classify an outcome from its summary, falling back to the results of the
+/// tests cited as evidence when the summary carries no signal.
+fn classify_outcome(
+    summary: &str,
+    evidence_test_ids: &[String],
+    test_results: &[TestResult],
+) -> OutcomeKind {
+    match OutcomeKind::from_text(summary) {
+        OutcomeKind::Unknown | OutcomeKind::Other => OutcomeKind::combine(
+            test_results
+                .iter()
+                .filter(|result| evidence_test_ids.contains(&result.test_id))
+                .map(|result| result.result.outcome()),
+        ),
+        kind @ (OutcomeKind::Success | OutcomeKind::Failure | OutcomeKind::Mixed) => kind,
+    }
+}
+
 async fn prompt_patterns(
     session: &Session,
     turn: &TurnContext,
     call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
     tests: &[TestCase],
-) -> Result<Vec<Pattern>, FunctionCallError> {
-    let count = prompt_number_in_range(
+) -> Result<Vec<Pattern>, PromptError> {
+    let count = prompt_section_count(
         session,
         turn,
         call_id,
-        "Patterns",
-        "How many patterns are you recording? (1-5)",
-        1,
-        5,
+        template,
+        prompts,
+        CaptureSection::Patterns,
     )
     .await?;
     let test_catalog = tests
@@ -508,30 +1581,21 @@ async fn prompt_patterns(
         .join(" | ");
     let mut patterns = Vec::with_capacity(count);
     for _ in 0..count {
-        let answers = prompt_questions(
+        let answers = prompt_section(
             session,
             turn,
             call_id,
-            "Pattern",
-            vec![
-                ("trigger", "Trigger"),
-                ("invariant", "Invariant"),
-                ("counterexample", "Counterexample"),
-                ("best_response", "Best response"),
-                (
-                    "domain_signature",
-                    "Domain-signature mixture vector (domain:weight, ...)",
-                ),
-                (
-                    "evidence",
-                    &format!("Evidence test ids (available: {test_catalog})"),
-                ),
-            ],
+            template,
+            prompts,
+            CaptureSection::Patterns,
+            &[("tests", test_catalog.as_str())],
         )
         .await?;
 
-        let domain_signature =
-            parse_domain_signature(require_field(&answers, "domain_signature")?.as_str())?;
+        let domain_signature = parse_domain_signature(
+            prompts.numbers(),
+            require_field(&answers, "domain_signature")?.as_str(),
+        )?;
         let evidence_ids = validate_test_ids(
             &split_list(require_field(&answers, "evidence")?.as_str()),
             tests,
@@ -548,59 +1612,187 @@ async fn prompt_patterns(
     Ok(patterns)
 }
 
+/// Ask the questions of `section`, worded by the template or else by the
+/// prompt catalog with `args` filled in, then check the answers against the
+/// template's rules.
+async fn prompt_section(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+    section: CaptureSection,
+    args: &[(&str, &str)],
+) -> Result<BTreeMap<String, String>, PromptError> {
+    prompt_drafted_section(
+        session,
+        turn,
+        call_id,
+        template,
+        prompts,
+        section,
+        args,
+        &BTreeMap::new(),
+    )
+    .await
+}
+
+/// Draft answers from the session's history so far.
+async fn session_drafts(session: &Session) -> CaptureDrafts {
+    CaptureDrafts::from_history(session.clone_history().await.raw_items())
+}
+
+/// Like [`prompt_section`], offering each answer in `drafts` as a choice the
+/// user can confirm or replace with their own.
+#[allow(clippy::too_many_arguments)]
+async fn prompt_drafted_section(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+    section: CaptureSection,
+    args: &[(&str, &str)],
+    drafts: &BTreeMap<&'static str, String>,
+) -> Result<BTreeMap<String, String>, PromptError> {
+    let draft_description = prompts.text(section, "draft", &[]);
+    let drafts = drafts
+        .iter()
+        .map(|(id, draft)| {
+            (
+                *id,
+                RequestUserInputQuestionOption {
+                    label: draft.clone(),
+                    description: draft_description.clone(),
+                },
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    let template = template.section(section);
+    let questions = section
+        .question_ids()
+        .iter()
+        .map(|id| (*id, template.question(id, &prompts.text(section, id, args))))
+        .collect::<Vec<_>>();
+    let answers = prompt_questions(
+        session,
+        turn,
+        call_id,
+        &prompts.text(section, "header", args),
+        questions
+            .iter()
+            .map(|(id, question)| (*id, question.as_str()))
+            .collect(),
+        &drafts,
+        |id, answer| template.check_answer(id, answer),
+    )
+    .await?;
+    template.check(&answers).map_err(respond)?;
+    Ok(answers)
+}
+
+/// Ask `questions` until each has an answer that passes `check`, offering the
+/// draft in `drafts` for a question as a choice besides typing an answer. A
+/// retry asks only the questions still missing an answer or whose answer was
+/// rejected, prefixed with the reason; accepted answers are kept. On the last
+/// attempt rejected answers are returned as given, so the caller reports the
+/// error.
 async fn prompt_questions(
     session: &Session,
     turn: &TurnContext,
     call_id: &str,
     header: &str,
     questions: Vec<(&str, &str)>,
-) -> Result<BTreeMap<String, String>, FunctionCallError> {
-    let mut attempts = 0;
-    loop {
-        attempts += 1;
+    drafts: &BTreeMap<&str, RequestUserInputQuestionOption>,
+    check: impl Fn(&str, &str) -> Result<(), String>,
+) -> Result<BTreeMap<String, String>, PromptError> {
+    let mut answers: BTreeMap<String, String> = BTreeMap::new();
+    let mut problems: BTreeMap<String, String> = BTreeMap::new();
+    for attempt in 1..=MAX_PROMPT_ATTEMPTS {
+        let pending = questions
+            .iter()
+            .filter(|(id, _)| !answers.contains_key(*id))
+            .copied()
+            .collect::<Vec<_>>();
         let args = RequestUserInputArgs {
-            questions: questions
+            questions: pending
                 .iter()
                 .map(|(id, question)| RequestUserInputQuestion {
                     id: (*id).to_string(),
                     header: header.to_string(),
-                    question: (*question).to_string(),
-                    is_other: false,
+                    question: match problems.get(*id) {
+                        Some(problem) => format!("{problem}. {question}"),
+                        None => (*question).to_string(),
+                    },
+                    is_other: drafts.contains_key(*id),
                     is_secret: false,
-                    options: None,
+                    options: drafts.get(*id).map(|draft| vec![draft.clone()]),
                 })
                 .collect(),
         };
         let response =
             request_user_input(session, turn, &format!("capture-{call_id}-{header}"), args).await?;
-        let mut answers = BTreeMap::new();
-        for (id, _) in questions.iter() {
-            if let Some(value) = extract_answer(&response, id) {
-                if !value.is_empty() {
-                    answers.insert((*id).to_string(), value);
-                }
-            }
+        problems = record_answers(
+            &mut answers,
+            &response,
+            &pending,
+            &check,
+            attempt == MAX_PROMPT_ATTEMPTS,
+        );
+        if answers.len() == questions.len() {
+            break;
         }
-        if answers.len() == questions.len() || attempts >= MAX_PROMPT_ATTEMPTS {
-            return Ok(answers);
+    }
+    Ok(answers)
+}
+
+/// Add the answers to `pending` that pass `check` to `answers`, and return why
+/// each rejected one was refused. On the `last` attempt rejected answers are
+/// added too. Empty answers are left out either way.
+fn record_answers(
+    answers: &mut BTreeMap<String, String>,
+    response: &RequestUserInputResponse,
+    pending: &[(&str, &str)],
+    check: impl Fn(&str, &str) -> Result<(), String>,
+    last: bool,
+) -> BTreeMap<String, String> {
+    let mut problems = BTreeMap::new();
+    for (id, _) in pending {
+        let Some(value) = extract_answer(response, id).filter(|value| !value.is_empty()) else {
+            continue;
+        };
+        match check(id, &value) {
+            Err(problem) if !last => {
+                problems.insert((*id).to_string(), problem);
+            }
+            Ok(()) | Err(_) => {
+                answers.insert((*id).to_string(), value);
+            }
         }
     }
+    problems
 }
 
+/// Ask the user, giving up with [`PromptError::TimedOut`] after `[capture]
+/// prompt_timeout_secs` and closing the request on the client.
 async fn request_user_input(
     session: &Session,
     turn: &TurnContext,
     call_id: &str,
     args: RequestUserInputArgs,
-) -> Result<RequestUserInputResponse, FunctionCallError> {
-    session
-        .request_user_input(turn, call_id.to_string(), args)
-        .await
-        .ok_or_else(|| {
-            FunctionCallError::RespondToModel(
-                "capture was cancelled before receiving a response".to_string(),
-            )
-        })
+) -> Result<RequestUserInputResponse, PromptError> {
+    let request = session.request_user_input(turn, call_id.to_string(), args);
+    let response = match turn.config.capture_prompt_timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, request).await {
+            Ok(response) => response,
+            Err(_) => {
+                session.cancel_user_input(turn, call_id).await;
+                return Err(PromptError::TimedOut);
+            }
+        },
+        None => request.await,
+    };
+    response.ok_or_else(|| respond("capture was cancelled before receiving a response").into())
 }
 
 fn extract_answer(response: &RequestUserInputResponse, id: &str) -> Option<String> {
@@ -631,23 +1823,67 @@ fn require_field(
         .ok_or_else(|| respond(format!("{key} is required")))
 }
 
-fn parse_probability(value: Option<&str>) -> Result<Option<f64>, FunctionCallError> {
+/// Parse the answer for `field` as a probability, per `numbers`.
+fn parse_probability(
+    numbers: &NumberFormat,
+    field: &str,
+    value: Option<&str>,
+) -> Result<Option<f64>, FunctionCallError> {
     let Some(value) = value else {
         return Ok(None);
     };
-    let trimmed = value.trim().trim_end_matches('%');
-    let mut parsed = trimmed
-        .parse::<f64>()
-        .map_err(|err| respond(format!("failed to parse probability '{value}': {err}")))?;
-    if parsed > 1.0 && parsed <= 100.0 {
-        parsed /= 100.0;
-    }
-    if !(0.0..=1.0).contains(&parsed) {
-        return Err(respond(format!(
-            "probability must be between 0 and 1, got {parsed}"
-        )));
+    numbers
+        .parse_probability(value)
+        .map(Some)
+        .map_err(|err| respond(format!("invalid {field}: {err}")))
+}
+
+/// Ask how many entries of a repeating `section` to record, within the
+/// template's count range.
+async fn prompt_section_count(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+    section: CaptureSection,
+) -> Result<usize, PromptError> {
+    prompt_remaining_count(session, turn, call_id, template, prompts, section, 0).await
+}
+
+/// Like [`prompt_section_count`], after `recorded` entries of the section
+/// were already filled in, so the section's range counts them too. Nothing
+/// is asked once the section is full.
+async fn prompt_remaining_count(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    template: &CaptureTemplate,
+    prompts: &PromptCatalog,
+    section: CaptureSection,
+    recorded: usize,
+) -> Result<usize, PromptError> {
+    let (min, max) = template.count_range(section);
+    let (min, max) = (min.saturating_sub(recorded), max.saturating_sub(recorded));
+    if max == 0 {
+        return Ok(0);
     }
-    Ok(Some(parsed))
+    let (min_text, max_text) = (min.to_string(), max.to_string());
+    let question = prompts.text(
+        section,
+        "count",
+        &[("min", min_text.as_str()), ("max", max_text.as_str())],
+    );
+    prompt_number_in_range(
+        session,
+        turn,
+        call_id,
+        &prompts.text(section, "count_header", &[]),
+        &question,
+        min,
+        max,
+    )
+    .await
 }
 
 async fn prompt_number_in_range(
@@ -658,29 +1894,33 @@ async fn prompt_number_in_range(
     question: &str,
     min: usize,
     max: usize,
-) -> Result<usize, FunctionCallError> {
-    let mut attempts = 0;
-    loop {
-        attempts += 1;
-        let answers =
-            prompt_questions(session, turn, call_id, header, vec![("count", question)]).await?;
-        let Some(count_text) = answers.get("count") else {
-            if attempts >= MAX_PROMPT_ATTEMPTS {
-                return Err(respond(format!("count must be between {min} and {max}")));
-            }
-            continue;
-        };
-        let count = count_text
-            .trim()
-            .parse::<usize>()
-            .map_err(|err| respond(format!("failed to parse count '{count_text}': {err}")))?;
-        if (min..=max).contains(&count) {
-            return Ok(count);
-        }
-        if attempts >= MAX_PROMPT_ATTEMPTS {
-            return Err(respond(format!("count must be between {min} and {max}")));
-        }
+) -> Result<usize, PromptError> {
+    let answers = prompt_questions(
+        session,
+        turn,
+        call_id,
+        header,
+        vec![("count", question)],
+        &BTreeMap::new(),
+        |_, answer| parse_count(answer, min, max).map(|_| ()),
+    )
+    .await?;
+    let Some(count_text) = answers.get("count") else {
+        return Err(respond(format!("count must be between {min} and {max}")).into());
+    };
+    let count = parse_count(count_text, min, max).map_err(respond)?;
+    Ok(count)
+}
+
+fn parse_count(text: &str, min: usize, max: usize) -> Result<usize, String> {
+    let count = text
+        .trim()
+        .parse::<usize>()
+        .map_err(|err| format!("failed to parse count '{text}': {err}"))?;
+    if !(min..=max).contains(&count) {
+        return Err(format!("count must be between {min} and {max}"));
     }
+    Ok(count)
 }
 
 fn split_list(text: &str) -> Vec<String> {
@@ -691,18 +1931,20 @@ fn split_list(text: &str) -> Vec<String> {
         .collect()
 }
 
-fn parse_domain_signature(text: &str) -> Result<Vec<DomainSignatureWeight>, FunctionCallError> {
+fn parse_domain_signature(
+    numbers: &NumberFormat,
+    text: &str,
+) -> Result<Vec<DomainSignatureWeight>, FunctionCallError> {
     let mut entries = Vec::new();
-    for pair in split_list(text) {
+    for pair in numbers.split_list(text) {
         let (domain, weight) = pair.split_once(':').ok_or_else(|| {
             respond(format!(
                 "domain-signature entry must be domain:weight, got '{pair}'"
             ))
         })?;
-        let weight = weight
-            .trim()
-            .parse::<f64>()
-            .map_err(|err| respond(format!("invalid weight '{weight}': {err}")))?;
+        let weight = numbers
+            .parse_number(weight)
+            .map_err(|err| respond(format!("invalid weight: {err}")))?;
         entries.push(DomainSignatureWeight {
             domain: domain.trim().to_string(),
             weight,
@@ -716,6 +1958,71 @@ fn parse_domain_signature(text: &str) -> Result<Vec<DomainSignatureWeight>, Func
     Ok(entries)
 }
 
+/// Directories artifacts may be recorded from: the turn's working directory
+/// and the sandbox's writable roots.
+fn artifact_roots(turn: &TurnContext) -> Vec<PathBuf> {
+    let mut roots = vec![turn.cwd.clone()];
+    roots.extend(
+        turn.sandbox_policy
+            .get_writable_roots_with_cwd(&turn.cwd)
+            .into_iter()
+            .map(|root| root.root.to_path_buf()),
+    );
+    roots
+}
+
+/// Parse artifact paths, resolved against `cwd`, and hash each file. Every
+/// path must name an existing file under one of `roots`, symlinks resolved.
+fn parse_artifacts(
+    text: &str,
+    cwd: &Path,
+    roots: &[PathBuf],
+) -> Result<Vec<ArtifactRef>, FunctionCallError> {
+    let trimmed = text.trim();
+    if trimmed.is_empty() || trimmed == "-" || trimmed.eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    let cwd = dunce::canonicalize(cwd).unwrap_or_else(|_| cwd.to_path_buf());
+    let roots = roots
+        .iter()
+        .filter_map(|root| dunce::canonicalize(root).ok())
+        .collect::<Vec<_>>();
+    let mut artifacts: Vec<ArtifactRef> = Vec::new();
+    for entry in split_list(trimmed) {
+        let resolved = dunce::canonicalize(cwd.join(&entry))
+            .map_err(|_| respond(format!("artifact '{entry}' does not exist")))?;
+        if !roots.iter().any(|root| resolved.starts_with(root)) {
+            return Err(respond(format!(
+                "artifact '{entry}' is outside the workspace"
+            )));
+        }
+        if !resolved.is_file() {
+            return Err(respond(format!("artifact '{entry}' is not a file")));
+        }
+        let contents = std::fs::read(&resolved)
+            .map_err(|err| respond(format!("failed to read artifact '{entry}': {err}")))?;
+        let sha256 = Sha256::digest(&contents)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        let path = resolved.strip_prefix(&cwd).map_or_else(
+            |_| resolved.display().to_string(),
+            |relative| relative.display().to_string(),
+        );
+        if !artifacts.iter().any(|artifact| artifact.path == path) {
+            artifacts.push(ArtifactRef { path, sha256 });
+        }
+    }
+    Ok(artifacts)
+}
+
+/// A test's command, or `None` when the answer is empty, `-`, or `none`.
+fn parse_test_command(text: &str) -> Option<String> {
+    let trimmed = text.trim();
+    (!trimmed.is_empty() && trimmed != "-" && !trimmed.eq_ignore_ascii_case("none"))
+        .then(|| trimmed.to_string())
+}
+
 fn validate_test_id(test_id: &str, tests: &[TestCase]) -> Result<String, FunctionCallError> {
     let test_id = test_id.trim();
     if tests.iter().any(|test| test.id == test_id) {
@@ -739,16 +2046,17 @@ fn parse_probability_updates(
     text: &str,
     test_id: &str,
     hypotheses: &mut [Hypothesis],
+    numbers: &NumberFormat,
 ) -> Result<Vec<ProbabilityUpdate>, FunctionCallError> {
     let mut updates = Vec::new();
-    for entry in split_list(text) {
+    for entry in numbers.split_list(text) {
         let (hypothesis_id, value) = entry.split_once('=').ok_or_else(|| {
             respond(format!(
                 "updates must be in hypothesis=probability format, got '{entry}'"
             ))
         })?;
         let hypothesis_id = hypothesis_id.trim();
-        let posterior = parse_probability(Some(value.trim()))?
+        let posterior = parse_probability(numbers, "posterior", Some(value))?
             .ok_or_else(|| respond("posterior probability is required".to_string()))?;
         let hypothesis = hypotheses
             .iter_mut()
@@ -759,6 +2067,7 @@ fn parse_probability_updates(
             prior: hypothesis.probability,
             posterior,
             evidence_test_id: test_id.to_string(),
+            bayes_factor: bayes_factor(hypothesis.probability, posterior),
         };
         hypothesis.probability = posterior;
         hypothesis.probability_updates.push(update.clone());
@@ -772,6 +2081,612 @@ fn parse_probability_updates(
     Ok(updates)
 }
 
+/// Posterior odds over prior odds. Odds are undefined at 0 and 1.
+fn bayes_factor(prior: f64, posterior: f64) -> Option<f64> {
+    let odds = |probability: f64| {
+        (probability > 0.0 && probability < 1.0).then(|| probability / (1.0 - probability))
+    };
+    Some(odds(posterior)? / odds(prior)?)
+}
+
+/// One warning per update that multiplies or divides the odds by more than
+/// `max_factor`, or that moves a hypothesis to or from certainty, which no
+/// finite evidence can do.
+fn bayes_factor_warnings(results: &[TestResult], max_factor: f64) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for update in results
+        .iter()
+        .flat_map(|result| &result.probability_updates)
+    {
+        let ProbabilityUpdate {
+            hypothesis_id,
+            prior,
+            posterior,
+            evidence_test_id,
+            bayes_factor,
+        } = update;
+        let change = format!("{prior:.2} -> {posterior:.2}");
+        match bayes_factor {
+            Some(factor) if *factor > max_factor || *factor * max_factor < 1.0 => {
+                let factor = if *factor >= 1.0 {
+                    format!("{factor:.1}")
+                } else {
+                    format!("1/{:.1}", 1.0 / factor)
+                };
+                warnings.push(format!(
+                    "{hypothesis_id} update from {evidence_test_id} ({change}) implies a Bayes factor of {factor}, past the cap of {max_factor}"
+                ));
+            }
+            None if prior != posterior => warnings.push(format!(
+                "{hypothesis_id} update from {evidence_test_id} ({change}) moves to or from certainty, which no finite evidence justifies"
+            )),
+            Some(_) | None => {}
+        }
+    }
+    warnings
+}
+
 fn respond(message: impl Into<String>) -> FunctionCallError {
     FunctionCallError::RespondToModel(message.into())
 }
+
+/// Why a capture prompt got no answer.
+#[derive(Debug)]
+enum PromptError {
+    /// The prompt went unanswered for `[capture] prompt_timeout_secs`. The
+    /// capture stops asking and saves what it has.
+    TimedOut,
+    /// Anything else, reported to the model.
+    Failed(FunctionCallError),
+}
+
+impl From<FunctionCallError> for PromptError {
+    fn from(err: FunctionCallError) -> Self {
+        Self::Failed(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::StreamOutput;
+    use codex_protocol::request_user_input::RequestUserInputAnswer;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn retries_keep_accepted_answers_and_re_ask_the_rest() {
+        let response = |answers: &[(&str, &str)]| RequestUserInputResponse {
+            answers: answers
+                .iter()
+                .map(|(id, answer)| {
+                    (
+                        (*id).to_string(),
+                        RequestUserInputAnswer {
+                            answers: vec![(*answer).to_string()],
+                        },
+                    )
+                })
+                .collect(),
+        };
+        let check = |id: &str, answer: &str| match id {
+            "goal" if answer.len() < 5 => Err("goal must be at least 5 characters".to_string()),
+            _ => Ok(()),
+        };
+        let questions = vec![
+            ("goal", "What is the goal?"),
+            ("constraints", "Any constraints?"),
+            ("success_signal", "How will you know?"),
+        ];
+        let mut answers = BTreeMap::new();
+
+        let first = record_answers(
+            &mut answers,
+            &response(&[("goal", "fix"), ("constraints", "no reboot")]),
+            &questions,
+            check,
+            false,
+        );
+        let second = record_answers(
+            &mut answers,
+            &response(&[("goal", "bad"), ("success_signal", " ")]),
+            &questions[..1],
+            check,
+            true,
+        );
+
+        assert_eq!(
+            (first, second, answers),
+            (
+                BTreeMap::from([(
+                    "goal".to_string(),
+                    "goal must be at least 5 characters".to_string()
+                )]),
+                BTreeMap::new(),
+                BTreeMap::from([
+                    ("constraints".to_string(), "no reboot".to_string()),
+                    ("goal".to_string(), "bad".to_string()),
+                ]),
+            )
+        );
+    }
+
+    #[test]
+    fn outcome_kind_falls_back_to_evidence_results() {
+        let test_results = vec![TestResult {
+            test_id: "T1".to_string(),
+            result: TestStatus::Pass,
+            notes: String::new(),
+            probability_updates: Vec::new(),
+            artifacts: Vec::new(),
+        }];
+        let evidence = vec!["T1".to_string()];
+
+        assert_eq!(
+            classify_outcome("audio routed to bluetooth", &evidence, &test_results),
+            OutcomeKind::Success
+        );
+        assert_eq!(
+            classify_outcome("still broken", &evidence, &test_results),
+            OutcomeKind::Failure
+        );
+        assert_eq!(
+            classify_outcome("audio routed to bluetooth", &[], &test_results),
+            OutcomeKind::Unknown
+        );
+    }
+
+    #[test]
+    fn artifacts_must_be_files_inside_the_workspace() {
+        let workspace = tempfile::tempdir().expect("workspace");
+        let outside = tempfile::tempdir().expect("outside");
+        std::fs::create_dir(workspace.path().join("logs")).expect("create logs");
+        std::fs::write(
+            workspace.path().join("logs/panic.txt"),
+            "thread main panicked at src/lib.rs:42\n",
+        )
+        .expect("write artifact");
+        std::fs::write(outside.path().join("secret.txt"), "elsewhere").expect("write outside");
+        let roots = vec![workspace.path().to_path_buf()];
+        let parse = |text: &str| {
+            parse_artifacts(text, workspace.path(), &roots).map_err(|err| err.to_string())
+        };
+        let outside_path = outside.path().join("secret.txt").display().to_string();
+
+        assert_eq!(
+            (
+                parse("none"),
+                parse("logs/panic.txt; ./logs/panic.txt"),
+                parse("logs/missing.txt"),
+                parse(outside_path.as_str()),
+                parse("logs"),
+            ),
+            (
+                Ok(Vec::new()),
+                Ok(vec![ArtifactRef {
+                    path: "logs/panic.txt".to_string(),
+                    sha256: "bdadf0f15dc36bb780c97a28f8cc425b3a73a593b42c7f4894e4aadddd0f59eb"
+                        .to_string(),
+                }]),
+                Err("artifact 'logs/missing.txt' does not exist".to_string()),
+                Err(format!(
+                    "artifact '{outside_path}' is outside the workspace"
+                )),
+                Err("artifact 'logs' is not a file".to_string()),
+            )
+        );
+    }
+
+    #[test]
+    fn test_commands_fill_in_results_from_their_exit_status() {
+        let output = |exit_code: i32, text: &str| ExecToolCallOutput {
+            exit_code,
+            aggregated_output: StreamOutput::new(text.to_string()),
+            ..Default::default()
+        };
+        let policy = TruncationPolicy::Bytes(10_000);
+        let passed = output(0, "ok");
+        let failed = output(1, "1 test failed");
+
+        assert_eq!(
+            (
+                ["cargo test", " none ", "-", ""].map(parse_test_command),
+                executed_test_result("cargo test", Ok(passed.clone()), policy),
+                executed_test_result("cargo test", Ok(failed.clone()), policy),
+                executed_test_result(
+                    "cargo test",
+                    Err(CodexErr::Sandbox(SandboxErr::Timeout {
+                        output: Box::new(failed.clone()),
+                    })),
+                    policy,
+                )
+                .0,
+            ),
+            (
+                [Some("cargo test".to_string()), None, None, None],
+                (
+                    TestStatus::Pass,
+                    format!(
+                        "`cargo test` exited with code 0.\n{}",
+                        format_exec_output_str(&passed, policy)
+                    ),
+                ),
+                (
+                    TestStatus::Fail,
+                    format!(
+                        "`cargo test` exited with code 1.\n{}",
+                        format_exec_output_str(&failed, policy)
+                    ),
+                ),
+                TestStatus::Error,
+            )
+        );
+    }
+
+    #[test]
+    fn review_summarizes_the_record_and_reads_the_choice() {
+        let prompts = PromptCatalog::default();
+        let record = CaptureRecord {
+            intent: IntentToken {
+                goal: "Restore audio".to_string(),
+                constraints: String::new(),
+                success_signal: String::new(),
+                confidence: 0.8,
+            },
+            event: EventDetails {
+                details: "Speakers went silent after the update".to_string(),
+            },
+            tests: vec![TestCase {
+                id: "T1".to_string(),
+                description: "Play a test tone".to_string(),
+                procedure: String::new(),
+                command: None,
+            }],
+            outcomes: vec![Outcome {
+                summary: "fixed by rerouting".to_string(),
+                kind: OutcomeKind::Success,
+                evidence_test_ids: vec!["T1".to_string()],
+                artifacts: Vec::new(),
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            (
+                capture_summary(&prompts, &record),
+                review_choice(&prompts, "save"),
+                review_choice(&prompts, "Test results"),
+                review_choice(&prompts, "hypothesis_links"),
+                review_choice(&prompts, "later"),
+                redo_sections(CaptureSection::Tests),
+            ),
+            (
+                "Intent: Restore audio (confidence 0.80)\n\
+                 Event: Speakers went silent after the update\n\
+                 0 hypotheses, 1 tests, 0 test results, 1 outcomes, 0 patterns\n\
+                 Outcomes: success: fixed by rerouting"
+                    .to_string(),
+                Some(ReviewChoice::Save),
+                Some(ReviewChoice::Redo(CaptureSection::TestResults)),
+                Some(ReviewChoice::Redo(CaptureSection::HypothesisLinks)),
+                None,
+                &[
+                    CaptureSection::Tests,
+                    CaptureSection::HypothesisLinks,
+                    CaptureSection::TestResults,
+                    CaptureSection::Outcomes,
+                    CaptureSection::Patterns,
+                ][..],
+            )
+        );
+    }
+
+    #[test]
+    fn updates_past_the_bayes_factor_cap_are_flagged() {
+        let mut hypotheses = vec![
+            related("H1", &[], &[]),
+            related("H2", &[], &[]),
+            related("H3", &[], &[]),
+        ];
+        for (hypothesis, prior) in hypotheses.iter_mut().zip([0.5, 0.5, 0.1]) {
+            hypothesis.probability = prior;
+        }
+        let updates = parse_probability_updates(
+            "H1=0.8, H2=0.995, H3=1",
+            "T1",
+            &mut hypotheses,
+            &NumberFormat::default(),
+        )
+        .expect("parse updates");
+        let results = vec![TestResult {
+            test_id: "T1".to_string(),
+            result: TestStatus::Pass,
+            notes: String::new(),
+            probability_updates: updates,
+            artifacts: Vec::new(),
+        }];
+
+        assert_eq!(
+            (
+                results[0]
+                    .probability_updates
+                    .iter()
+                    .map(|update| update.bayes_factor.map(|factor| factor.round()))
+                    .collect::<Vec<_>>(),
+                bayes_factor_warnings(&results, 100.0),
+            ),
+            (
+                vec![Some(4.0), Some(199.0), None],
+                vec![
+                    "H2 update from T1 (0.50 -> 0.99) implies a Bayes factor of 199.0, past the cap of 100"
+                        .to_string(),
+                    "H3 update from T1 (0.10 -> 1.00) moves to or from certainty, which no finite evidence justifies"
+                        .to_string(),
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn redoing_test_results_restores_priors() {
+        let mut hypotheses = vec![related("H1", &[], &[])];
+        hypotheses[0].probability = 0.9;
+        hypotheses[0].probability_updates = vec![
+            ProbabilityUpdate {
+                hypothesis_id: "H1".to_string(),
+                prior: 0.4,
+                posterior: 0.7,
+                evidence_test_id: "T1".to_string(),
+                bayes_factor: None,
+            },
+            ProbabilityUpdate {
+                hypothesis_id: "H1".to_string(),
+                prior: 0.7,
+                posterior: 0.9,
+                evidence_test_id: "T2".to_string(),
+                bayes_factor: None,
+            },
+        ];
+
+        reset_probability_updates(&mut hypotheses);
+
+        assert_eq!(
+            (
+                hypotheses[0].probability,
+                hypotheses[0].probability_updates.len()
+            ),
+            (0.4, 0)
+        );
+    }
+
+    fn related(id: &str, depends_on: &[&str], excludes: &[&str]) -> Hypothesis {
+        Hypothesis {
+            id: id.to_string(),
+            statement: format!("hypothesis {id}"),
+            probability: 0.5,
+            falsifiers: Vec::new(),
+            domain_signature: Vec::new(),
+            test_ids: Vec::new(),
+            probability_updates: Vec::new(),
+            depends_on: depends_on.iter().map(ToString::to_string).collect(),
+            mutually_exclusive_with: excludes.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn hypothesis_relations_reject_required_exclusions() {
+        let ids = ["H1", "H2", "H3"].map(str::to_string);
+        assert_eq!(
+            (
+                parse_hypothesis_ids("none", "H1", &ids).ok(),
+                parse_hypothesis_ids("H2, H3; H2", "H1", &ids).ok(),
+                parse_hypothesis_ids("H1", "H1", &ids).is_err(),
+                parse_hypothesis_ids("H9", "H1", &ids).is_err(),
+            ),
+            (
+                Some(Vec::new()),
+                Some(vec!["H2".to_string(), "H3".to_string()]),
+                true,
+                true,
+            )
+        );
+
+        let consistent = [
+            related("H1", &["H2"], &[]),
+            related("H2", &[], &["H3"]),
+            related("H3", &[], &["H2"]),
+        ];
+        let transitive = [
+            related("H1", &["H2", "H3"], &[]),
+            related("H2", &[], &["H3"]),
+            related("H3", &["H2"], &["H2"]),
+        ];
+        let direct = [related("H1", &["H2"], &["H2"]), related("H2", &[], &["H1"])];
+
+        assert_eq!(
+            [
+                validate_hypothesis_relations(&consistent),
+                validate_hypothesis_relations(&transitive),
+                validate_hypothesis_relations(&direct),
+            ],
+            [
+                Ok(()),
+                Err("H1 requires both H2 and H3, which are mutually exclusive".to_string()),
+                Err("H1 depends on H2, which it excludes".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn timelines_follow_recorded_update_order() {
+        let output = serde_json::json!({
+            "hypotheses": [
+                {
+                    "id": "H1",
+                    "statement": "bluetooth output not selected",
+                    "probability_updates": [
+                        {"hypothesis_id": "H1", "prior": 0.5, "posterior": 0.7, "evidence_test_id": "T1"},
+                        {"hypothesis_id": "H1", "prior": 0.7, "posterior": 0.95, "evidence_test_id": "T2"}
+                    ]
+                },
+                {"id": "H2", "statement": "driver crashed", "probability_updates": []}
+            ]
+        })
+        .to_string();
+
+        assert_eq!(
+            probability_timelines(&output).expect("parse timelines"),
+            vec![HypothesisTimeline {
+                hypothesis_id: "H1".to_string(),
+                statement: "bluetooth output not selected".to_string(),
+                points: vec![
+                    BeliefPoint {
+                        step: 0,
+                        evidence_test_id: "T1".to_string(),
+                        prior: 0.5,
+                        posterior: 0.7,
+                    },
+                    BeliefPoint {
+                        step: 1,
+                        evidence_test_id: "T2".to_string(),
+                        prior: 0.7,
+                        posterior: 0.95,
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn capture_record_converts_to_covenant_event() {
+        let record = CaptureRecord {
+            status: CaptureStatus::Complete,
+            timed_out_section: None,
+            intent: IntentToken {
+                goal: "route audio".to_string(),
+                constraints: "no reboot".to_string(),
+                success_signal: "sound in headset".to_string(),
+                confidence: 0.8,
+            },
+            event: EventDetails {
+                details: "pressed play".to_string(),
+            },
+            hypotheses: vec![Hypothesis {
+                id: "H1".to_string(),
+                statement: "bluetooth output not selected".to_string(),
+                probability: 0.5,
+                falsifiers: vec!["speaker plays".to_string()],
+                domain_signature: vec![DomainSignatureWeight {
+                    domain: "audio".to_string(),
+                    weight: 1.0,
+                }],
+                test_ids: vec!["T1".to_string()],
+                probability_updates: Vec::new(),
+                depends_on: Vec::new(),
+                mutually_exclusive_with: Vec::new(),
+            }],
+            tests: vec![TestCase {
+                id: "T1".to_string(),
+                description: "switch output".to_string(),
+                procedure: "open sound settings".to_string(),
+                command: None,
+            }],
+            test_results: vec![TestResult {
+                test_id: "T1".to_string(),
+                result: TestStatus::Pass,
+                notes: "audio moved".to_string(),
+                probability_updates: vec![ProbabilityUpdate {
+                    hypothesis_id: "H1".to_string(),
+                    prior: 0.5,
+                    posterior: 0.9,
+                    evidence_test_id: "T1".to_string(),
+                    bayes_factor: Some(9.0),
+                }],
+                artifacts: Vec::new(),
+            }],
+            outcomes: vec![Outcome {
+                summary: "audio routed to bluetooth".to_string(),
+                kind: OutcomeKind::Success,
+                evidence_test_ids: vec!["T1".to_string()],
+                artifacts: Vec::new(),
+            }],
+            patterns: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        let event =
+            covenant_event_from_capture(&record, "evt".to_string(), 42).expect("convert capture");
+
+        assert_eq!(
+            event,
+            CovenantEvent {
+                id: "evt".to_string(),
+                created_at: 42,
+                description: "pressed play".to_string(),
+                domain_signature: r#"[{"domain":"audio","weight":0.5}]"#.to_string(),
+                intent: Some(CovenantIntent {
+                    id: "evt:intent".to_string(),
+                    goal: "route audio".to_string(),
+                    constraints: "no reboot".to_string(),
+                    success_signal: "sound in headset".to_string(),
+                    confidence: 0.8,
+                }),
+                hypotheses: vec![CovenantHypothesis {
+                    id: "evt:H1".to_string(),
+                    model_type: "bluetooth output not selected".to_string(),
+                    probability: 0.5,
+                    falsifiers: vec!["speaker plays".to_string()],
+                    domain_signature: r#"[{"domain":"audio","weight":1.0}]"#.to_string(),
+                }],
+                tests: vec![CovenantTest {
+                    id: "evt:R1:H1".to_string(),
+                    hypothesis_id: "evt:H1".to_string(),
+                    description: "switch output".to_string(),
+                    result: TestStatus::Pass,
+                    evidence_ref: "capture://evt/T1".to_string(),
+                }],
+                outcomes: vec![CovenantOutcome {
+                    id: "evt:O1".to_string(),
+                    summary: "audio routed to bluetooth".to_string(),
+                    outcome: OutcomeKind::Success,
+                    evidence_refs: vec!["capture://evt/T1".to_string()],
+                    evidence: vec![CovenantEvidence::test("capture://evt/T1")],
+                }],
+                tags: Vec::new(),
+                session_id: None,
+                rollout_path: None,
+            }
+        );
+    }
+
+    #[test]
+    fn timed_out_captures_are_saved_as_incomplete() {
+        let record = CaptureRecord {
+            status: CaptureStatus::Incomplete,
+            timed_out_section: Some(CaptureSection::Hypotheses.key().to_string()),
+            intent: IntentToken {
+                goal: "route audio".to_string(),
+                confidence: 0.8,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let event =
+            covenant_event_from_capture(&record, "evt".to_string(), 42).expect("convert capture");
+        let output = serde_json::to_value(&record).expect("serialize capture");
+        assert_eq!(
+            (
+                event.tags,
+                event.status(),
+                &output["status"],
+                &output["timed_out_section"]
+            ),
+            (
+                vec![INCOMPLETE_TAG.to_string()],
+                "open",
+                &serde_json::json!("incomplete"),
+                &serde_json::json!("hypotheses"),
+            )
+        );
+    }
+}
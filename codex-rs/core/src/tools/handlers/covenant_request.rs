@@ -0,0 +1,219 @@
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use codex_protocol::models::FunctionCallOutputBody;
+use codex_protocol::request_user_input::RequestUserInputArgs;
+use codex_protocol::request_user_input::RequestUserInputQuestion;
+use codex_protocol::request_user_input::RequestUserInputQuestionOption;
+use serde::Deserialize;
+
+use crate::covenant::CovenantAction;
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::handlers::parse_arguments;
+use crate::tools::handlers::request_user_input_unavailable_message;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+/// Longest grant the agent may ask for in one request.
+pub(crate) const MAX_GRANT_MINUTES: u64 = 240;
+
+const QUESTION_ID: &str = "covenant_grant";
+const DENY_LABEL: &str = "Deny";
+
+pub struct CovenantRequestHandler;
+
+#[derive(Debug, Deserialize)]
+struct CovenantRequestArgs {
+    capability: String,
+    minutes: u64,
+    reason: String,
+}
+
+pub(crate) fn covenant_request_tool_description() -> String {
+    let capabilities = CovenantAction::ALL
+        .into_iter()
+        .map(CovenantAction::as_capability)
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "Ask the user to grant a covenant capability to this session for a limited time, when the covenant refuses an action you need. Capabilities: {capabilities}. Grants last at most {MAX_GRANT_MINUTES} minutes."
+    )
+}
+
+#[async_trait]
+impl ToolHandler for CovenantRequestHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            payload,
+            ..
+        } = invocation;
+
+        let arguments = match payload {
+            ToolPayload::Function { arguments } => arguments,
+            _ => {
+                return Err(FunctionCallError::RespondToModel(
+                    "covenant_request handler received unsupported payload".to_string(),
+                ));
+            }
+        };
+
+        let mode = session.collaboration_mode().await.mode;
+        if let Some(message) = request_user_input_unavailable_message(mode) {
+            return Err(FunctionCallError::RespondToModel(message));
+        }
+
+        let args: CovenantRequestArgs = parse_arguments(&arguments)?;
+        let action = validate_request(&args)?;
+        let capability = action.as_capability();
+        let grant_label = grant_label(args.minutes);
+        let question = RequestUserInputQuestion {
+            id: QUESTION_ID.to_string(),
+            header: "Covenant".to_string(),
+            question: format!(
+                "Allow {capability} for this session? Reason: {}",
+                args.reason.trim()
+            ),
+            is_other: false,
+            is_secret: false,
+            options: Some(vec![
+                RequestUserInputQuestionOption {
+                    label: grant_label.clone(),
+                    description: format!("Allow {capability} until the grant expires."),
+                },
+                RequestUserInputQuestionOption {
+                    label: DENY_LABEL.to_string(),
+                    description: "Keep the covenant as it is.".to_string(),
+                },
+            ]),
+        };
+        let response = session
+            .request_user_input(
+                turn.as_ref(),
+                call_id.clone(),
+                RequestUserInputArgs {
+                    questions: vec![question],
+                },
+            )
+            .await
+            .ok_or_else(|| {
+                FunctionCallError::RespondToModel(
+                    "covenant_request was cancelled before receiving a response".to_string(),
+                )
+            })?;
+        let granted = response.answers.get(QUESTION_ID).is_some_and(|answer| {
+            answer
+                .answers
+                .iter()
+                .any(|entry| entry.trim() == grant_label)
+        });
+
+        let expires_at = granted.then(|| grant_expiry(Utc::now().timestamp(), args.minutes));
+        session
+            .record_covenant_request(turn.as_ref(), action, call_id.as_str(), expires_at)
+            .await
+            .map_err(|err| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to record the covenant grant for {capability}: {err}"
+                ))
+            })?;
+
+        let content = match expires_at {
+            Some(expires_at) => format!(
+                "The user granted {capability} for {} minutes, until {}.",
+                args.minutes,
+                DateTime::from_timestamp(expires_at, 0)
+                    .map_or_else(|| expires_at.to_string(), |time| time.to_rfc3339())
+            ),
+            None => format!("The user denied {capability}; the covenant still applies."),
+        };
+        Ok(ToolOutput::Function {
+            body: FunctionCallOutputBody::Text(content),
+            success: Some(granted),
+        })
+    }
+}
+
+fn validate_request(args: &CovenantRequestArgs) -> Result<CovenantAction, FunctionCallError> {
+    let action = CovenantAction::from_capability(args.capability.trim()).ok_or_else(|| {
+        FunctionCallError::RespondToModel(format!(
+            "unknown covenant capability '{}'",
+            args.capability
+        ))
+    })?;
+    if !(1..=MAX_GRANT_MINUTES).contains(&args.minutes) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "minutes must be between 1 and {MAX_GRANT_MINUTES}"
+        )));
+    }
+    if args.reason.trim().is_empty() {
+        return Err(FunctionCallError::RespondToModel(
+            "reason must explain why the capability is needed".to_string(),
+        ));
+    }
+    Ok(action)
+}
+
+fn grant_label(minutes: u64) -> String {
+    if minutes == 1 {
+        "Grant for 1 minute".to_string()
+    } else {
+        format!("Grant for {minutes} minutes")
+    }
+}
+
+fn grant_expiry(now: i64, minutes: u64) -> i64 {
+    now.saturating_add(i64::try_from(minutes.saturating_mul(60)).unwrap_or(i64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(capability: &str, minutes: u64, reason: &str) -> CovenantRequestArgs {
+        CovenantRequestArgs {
+            capability: capability.to_string(),
+            minutes,
+            reason: reason.to_string(),
+        }
+    }
+
+    #[test]
+    fn requests_need_a_known_capability_bounded_minutes_and_a_reason() {
+        let results = [
+            args("proposal.exec_command", 30, "run the migration"),
+            args("event.unknown", 30, "run the migration"),
+            args("proposal.exec_command", 0, "run the migration"),
+            args("proposal.exec_command", MAX_GRANT_MINUTES + 1, "run it"),
+            args("proposal.exec_command", 30, " "),
+        ]
+        .iter()
+        .map(|args| match validate_request(args) {
+            Ok(action) => Ok(action.as_capability()),
+            Err(err) => Err(err.to_string()),
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            results,
+            vec![
+                Ok("proposal.exec_command"),
+                Err("unknown covenant capability 'event.unknown'".to_string()),
+                Err(format!("minutes must be between 1 and {MAX_GRANT_MINUTES}")),
+                Err(format!("minutes must be between 1 and {MAX_GRANT_MINUTES}")),
+                Err("reason must explain why the capability is needed".to_string()),
+            ]
+        );
+        assert_eq!(grant_expiry(1_000, 30), 2_800);
+    }
+}
@@ -1,12 +1,14 @@
 pub mod apply_patch;
 mod capture;
 pub(crate) mod collab;
+mod covenant_request;
 mod dynamic;
 mod get_memory;
 mod grep_files;
 mod list_dir;
 mod mcp;
 mod mcp_resource;
+mod pattern_feedback;
 mod plan;
 mod read_file;
 mod request_user_input;
@@ -20,15 +22,22 @@ use serde::Deserialize;
 
 use crate::function_tool::FunctionCallError;
 pub use apply_patch::ApplyPatchHandler;
+pub use capture::BeliefPoint;
 pub use capture::CaptureHandler;
+pub use capture::HypothesisTimeline;
+pub use capture::capture_schema;
 pub(crate) use capture::capture_tool_description;
+pub use capture::probability_timelines;
 pub use collab::CollabHandler;
+pub use covenant_request::CovenantRequestHandler;
+pub(crate) use covenant_request::covenant_request_tool_description;
 pub use dynamic::DynamicToolHandler;
 pub use get_memory::GetMemoryHandler;
 pub use grep_files::GrepFilesHandler;
 pub use list_dir::ListDirHandler;
 pub use mcp::McpHandler;
 pub use mcp_resource::McpResourceHandler;
+pub use pattern_feedback::PatternFeedbackHandler;
 pub use plan::PlanHandler;
 pub use read_file::ReadFileHandler;
 pub use request_user_input::RequestUserInputHandler;
@@ -0,0 +1,216 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use codex_protocol::models::FunctionCallOutputBody;
+use codex_protocol::outcome::Outcome;
+use serde::Deserialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::pattern_usage::PATTERN_USAGE_FILENAME;
+use crate::pattern_usage::PatternUsageRecord;
+use crate::pattern_usage::append_usage;
+use crate::pattern_usage::outcome_supports;
+use crate::pattern_usage::read_usage;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::handlers::parse_arguments;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub struct PatternFeedbackHandler;
+
+#[derive(Debug, Deserialize)]
+struct PatternFeedbackArgs {
+    pattern_id: String,
+    outcome: String,
+    #[serde(default)]
+    match_id: Option<String>,
+}
+
+#[async_trait]
+impl ToolHandler for PatternFeedbackHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session, payload, ..
+        } = invocation;
+
+        let arguments = match payload {
+            ToolPayload::Function { arguments } => arguments,
+            _ => {
+                return Err(FunctionCallError::RespondToModel(
+                    "pattern_feedback handler received unsupported payload".to_string(),
+                ));
+            }
+        };
+
+        let args: PatternFeedbackArgs = parse_arguments(&arguments)?;
+        let pattern_id = args.pattern_id.trim();
+        let outcome = Outcome::from_text(&args.outcome);
+        let Some(state_db) = session.covenant_db() else {
+            return Err(FunctionCallError::RespondToModel(
+                "pattern_feedback is unavailable: the covenant store is not open".to_string(),
+            ));
+        };
+        let pattern = state_db
+            .get_covenant_pattern(pattern_id)
+            .await
+            .map_err(|err| respond(format!("failed to load pattern: {err:#}")))?
+            .ok_or_else(|| respond(format!("no live pattern with id `{pattern_id}`")))?;
+
+        let usage_path = session.codex_home().await.join(PATTERN_USAGE_FILENAME);
+        let cipher = state_db
+            .store_cipher()
+            .map_err(|err| respond(format!("failed to load the store key: {err:#}")))?
+            .cloned();
+        let recorded_id = pattern_id.to_string();
+        let match_id = args.match_id.clone();
+        // The usage log is read and appended with blocking file IO.
+        tokio::task::spawn_blocking(move || {
+            let usage = read_usage(usage_path.as_path(), cipher.as_ref())
+                .map_err(|err| respond(format!("failed to read the pattern usage log: {err:#}")))?;
+            let record = usage_record(
+                &usage,
+                &recorded_id,
+                match_id.as_deref(),
+                outcome,
+                Utc::now().timestamp(),
+            )?;
+            append_usage(usage_path.as_path(), &[record], cipher.as_ref())
+                .map_err(|err| respond(format!("failed to record pattern feedback: {err:#}")))
+        })
+        .await
+        .map_err(|err| respond(format!("failed to record pattern feedback: {err}")))??;
+
+        let counted = match outcome_supports(pattern.anti_pattern, outcome) {
+            Some(supports) => state_db
+                .record_pattern_feedback(pattern_id, supports)
+                .await
+                .map_err(|err| respond(format!("failed to update pattern counts: {err:#}")))?
+                .unwrap_or(pattern),
+            None => pattern,
+        };
+        Ok(ToolOutput::Function {
+            body: FunctionCallOutputBody::Text(format!(
+                "Recorded {outcome} for pattern `{pattern_id}`: {} supporting and {} contradicting reports so far.",
+                counted.supports, counted.contradictions
+            )),
+            success: Some(true),
+        })
+    }
+}
+
+fn respond(message: String) -> FunctionCallError {
+    FunctionCallError::RespondToModel(message)
+}
+
+/// The usage record for a report on `pattern_id`: feedback on `match_id`
+/// when given, which must be a match of that pattern in `usage` without an
+/// outcome reported yet, and otherwise a use applied without a match.
+fn usage_record(
+    usage: &[PatternUsageRecord],
+    pattern_id: &str,
+    match_id: Option<&str>,
+    outcome: Outcome,
+    recorded_at: i64,
+) -> Result<PatternUsageRecord, FunctionCallError> {
+    let Some(match_id) = match_id.map(str::trim) else {
+        return Ok(PatternUsageRecord::new_applied(
+            pattern_id,
+            outcome,
+            recorded_at,
+        ));
+    };
+    let known = usage.iter().any(|record| {
+        matches!(
+            record,
+            PatternUsageRecord::Match {
+                match_id: recorded,
+                pattern_id: matched,
+                ..
+            } if recorded == match_id && matched == pattern_id
+        )
+    });
+    if !known {
+        return Err(respond(format!(
+            "no match `{match_id}` of pattern `{pattern_id}` in the usage log"
+        )));
+    }
+    let reported = usage.iter().any(|record| {
+        matches!(
+            record,
+            PatternUsageRecord::Feedback {
+                match_id: recorded,
+                outcome: Some(_),
+                ..
+            } if recorded == match_id
+        )
+    });
+    if reported {
+        return Err(respond(format!(
+            "an outcome for match `{match_id}` was already recorded"
+        )));
+    }
+    Ok(PatternUsageRecord::Feedback {
+        match_id: match_id.to_string(),
+        accepted: true,
+        outcome: Some(outcome),
+        recorded_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reports_give_feedback_on_known_matches_or_log_an_applied_use() {
+        let mut usage = vec![PatternUsageRecord::Match {
+            match_id: "m1".to_string(),
+            pattern_id: "prune-logs".to_string(),
+            score: 0.8,
+            event_trigger: "disk full".to_string(),
+            recorded_at: 1,
+        }];
+        let applied =
+            usage_record(&usage, "prune-logs", None, Outcome::Failure, 5).expect("applied use");
+
+        assert_eq!(
+            (
+                usage_record(&usage, "prune-logs", Some(" m1 "), Outcome::Success, 5)
+                    .expect("feedback"),
+                applied.clone(),
+                usage_record(&usage, "reboot", Some("m1"), Outcome::Success, 5)
+                    .map_err(|err| err.to_string()),
+            ),
+            (
+                PatternUsageRecord::Feedback {
+                    match_id: "m1".to_string(),
+                    accepted: true,
+                    outcome: Some(Outcome::Success),
+                    recorded_at: 5,
+                },
+                PatternUsageRecord::Applied {
+                    match_id: applied.match_id().to_string(),
+                    pattern_id: "prune-logs".to_string(),
+                    outcome: Outcome::Failure,
+                    recorded_at: 5,
+                },
+                Err("no match `m1` of pattern `reboot` in the usage log".to_string()),
+            )
+        );
+
+        usage.push(
+            usage_record(&usage, "prune-logs", Some("m1"), Outcome::Success, 5).expect("feedback"),
+        );
+        assert_eq!(
+            usage_record(&usage, "prune-logs", Some("m1"), Outcome::Success, 6)
+                .map_err(|err| err.to_string()),
+            Err("an outcome for match `m1` was already recorded".to_string())
+        );
+    }
+}
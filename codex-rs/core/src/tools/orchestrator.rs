@@ -2,9 +2,12 @@
 Module: orchestrator
 
 Central place for approvals + sandbox selection + retry semantics. Drives a
-simple sequence for any ToolRuntime: approval → select sandbox → attempt →
-retry without sandbox on denial (no re‑approval thanks to caching).
+simple sequence for any ToolRuntime: covenant check → approval → select
+sandbox → attempt → retry without sandbox on denial (no re‑approval thanks to
+caching).
 */
+use crate::covenant::CovenantAction;
+use crate::covenant::CovenantEnforcement;
 use crate::error::CodexErr;
 use crate::error::SandboxErr;
 use crate::exec::ExecToolCallOutput;
@@ -20,7 +23,9 @@ use crate::tools::sandboxing::ToolRuntime;
 use crate::tools::sandboxing::default_exec_approval_requirement;
 use codex_otel::ToolDecisionSource;
 use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::protocol::WarningEvent;
 
 pub(crate) struct ToolOrchestrator {
     sandbox: SandboxManager,
@@ -56,6 +61,28 @@ impl ToolOrchestrator {
         let requirement = tool.exec_approval_requirement(req).unwrap_or_else(|| {
             default_exec_approval_requirement(approval_policy, &turn_ctx.sandbox_policy)
         });
+        // Commands that need no approval are only gated where a covenant is
+        // in force, so projects without one run them as before.
+        let skip_covenant = matches!(requirement, ExecApprovalRequirement::Skip { .. })
+            && !tool_ctx.session.covenant_in_force(turn_ctx).await;
+        let requirement = match requirement {
+            ExecApprovalRequirement::Forbidden { .. } => requirement,
+            ExecApprovalRequirement::Skip { .. } if skip_covenant => requirement,
+            ExecApprovalRequirement::Skip { .. }
+            | ExecApprovalRequirement::NeedsApproval { .. } => {
+                match enforce_covenant(tool.covenant_action(), tool_ctx, turn_ctx, approval_policy)
+                    .await?
+                {
+                    Some(reason) => ExecApprovalRequirement::NeedsApproval {
+                        reason: Some(reason),
+                        proposed_execpolicy_amendment: requirement
+                            .proposed_execpolicy_amendment()
+                            .cloned(),
+                    },
+                    None => requirement,
+                }
+            }
+        };
         match requirement {
             ExecApprovalRequirement::Skip { .. } => {
                 otel.tool_decision(otel_tn, otel_ci, &ReviewDecision::Approved, otel_cfg);
@@ -169,6 +196,47 @@ impl ToolOrchestrator {
     }
 }
 
+/// Check the tool's proposal against the covenant. Returns the approval reason
+/// when the proposal must be escalated to the user, and an error when it is
+/// refused outright, either because approvals are disabled or because a
+/// required covenant could not be loaded.
+async fn enforce_covenant(
+    action: Option<CovenantAction>,
+    tool_ctx: &ToolCtx<'_>,
+    turn_ctx: &crate::codex::TurnContext,
+    approval_policy: AskForApproval,
+) -> Result<Option<String>, ToolError> {
+    let Some(action) = action else {
+        return Ok(None);
+    };
+    let capability = action.as_capability();
+    let can_escalate = !matches!(approval_policy, AskForApproval::Never);
+    let message = match tool_ctx
+        .session
+        .enforce_covenant_proposal(turn_ctx, action, &tool_ctx.call_id, can_escalate)
+        .await
+    {
+        Ok(CovenantEnforcement::Allowed) => return Ok(None),
+        Ok(CovenantEnforcement::Escalated) => {
+            return Ok(Some(format!(
+                "covenant scope disallows {capability}; approve to allow it anyway"
+            )));
+        }
+        Ok(CovenantEnforcement::Refused) => format!("covenant scope disallows {capability}"),
+        Err(err) => format!("covenant audit failed for {capability}: {err}"),
+    };
+    tool_ctx
+        .session
+        .send_event(
+            turn_ctx,
+            EventMsg::Warning(WarningEvent {
+                message: message.clone(),
+            }),
+        )
+        .await;
+    Err(ToolError::Rejected(message))
+}
+
 fn build_denial_reason_from_output(_output: &ExecToolCallOutput) -> String {
     // Keep approval reason terse and stable for UX/tests, but accept the
     // output so we can evolve heuristics later without touching call sites.
@@ -5,6 +5,7 @@
 //! `codex --codex-run-as-apply-patch`, and runs under the current
 //! `SandboxAttempt` with a minimal environment.
 use crate::CODEX_APPLY_PATCH_ARG1;
+use crate::covenant::CovenantAction;
 use crate::exec::ExecToolCallOutput;
 use crate::sandboxing::CommandSpec;
 use crate::sandboxing::SandboxPermissions;
@@ -130,6 +131,10 @@ impl Approvable<ApplyPatchRequest> for ApplyPatchRuntime {
         !matches!(policy, AskForApproval::Never)
     }
 
+    fn covenant_action(&self) -> Option<CovenantAction> {
+        Some(CovenantAction::ProposalApplyPatch)
+    }
+
     // apply_patch approvals are decided upstream by assess_patch_safety.
     //
     // This override ensures the orchestrator runs the patch approval flow when required instead
@@ -4,6 +4,7 @@ Runtime: shell
 Executes shell requests under the orchestrator: asks for approval when needed,
 builds a CommandSpec, and runs it under the current SandboxAttempt.
 */
+use crate::covenant::CovenantAction;
 use crate::exec::ExecToolCallOutput;
 use crate::features::Feature;
 use crate::powershell::prefix_powershell_script_with_utf8;
@@ -116,6 +117,10 @@ impl Approvable<ShellRequest> for ShellRuntime {
         })
     }
 
+    fn covenant_action(&self) -> Option<CovenantAction> {
+        Some(CovenantAction::ProposalExecCommand)
+    }
+
     fn exec_approval_requirement(&self, req: &ShellRequest) -> Option<ExecApprovalRequirement> {
         Some(req.exec_approval_requirement.clone())
     }
@@ -4,6 +4,7 @@ Runtime: unified exec
 Handles approval + sandbox orchestration for unified exec requests, delegating to
 the process manager to spawn PTYs once an ExecEnv is prepared.
 */
+use crate::covenant::CovenantAction;
 use crate::error::CodexErr;
 use crate::error::SandboxErr;
 use crate::exec::ExecExpiration;
@@ -139,6 +140,10 @@ impl Approvable<UnifiedExecRequest> for UnifiedExecRuntime<'_> {
         })
     }
 
+    fn covenant_action(&self) -> Option<CovenantAction> {
+        Some(CovenantAction::ProposalExecCommand)
+    }
+
     fn exec_approval_requirement(
         &self,
         req: &UnifiedExecRequest,
@@ -6,6 +6,7 @@
 
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::covenant::CovenantAction;
 use crate::error::CodexErr;
 use crate::protocol::SandboxPolicy;
 use crate::sandboxing::CommandSpec;
@@ -212,6 +213,12 @@ pub(crate) trait Approvable<Req> {
         matches!(policy, AskForApproval::Never)
     }
 
+    /// Covenant capability the tool proposes to exercise. Tools that return
+    /// `Some(_)` are checked against the covenant before any approval flow.
+    fn covenant_action(&self) -> Option<CovenantAction> {
+        None
+    }
+
     /// Return `Some(_)` to specify a custom exec approval requirement, or `None`
     /// to fall back to policy-based default.
     fn exec_approval_requirement(&self, _req: &Req) -> Option<ExecApprovalRequirement> {
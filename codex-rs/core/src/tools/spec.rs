@@ -10,6 +10,7 @@ use crate::tools::handlers::capture_tool_description;
 use crate::tools::handlers::collab::DEFAULT_WAIT_TIMEOUT_MS;
 use crate::tools::handlers::collab::MAX_WAIT_TIMEOUT_MS;
 use crate::tools::handlers::collab::MIN_WAIT_TIMEOUT_MS;
+use crate::tools::handlers::covenant_request_tool_description;
 use crate::tools::handlers::request_user_input_tool_description;
 use crate::tools::registry::ToolRegistryBuilder;
 use codex_protocol::config_types::WebSearchMode;
@@ -32,6 +33,8 @@ pub(crate) struct ToolsConfig {
     pub web_search_mode: Option<WebSearchMode>,
     pub collab_tools: bool,
     pub collaboration_modes_tools: bool,
+    /// Offer the `capture` tool alongside the collaboration modes tools.
+    pub capture_tool: bool,
     pub memory_tools: bool,
     pub request_rule_enabled: bool,
     pub experimental_supported_tools: Vec<String>,
@@ -87,11 +90,19 @@ impl ToolsConfig {
             web_search_mode: *web_search_mode,
             collab_tools: include_collab_tools,
             collaboration_modes_tools: include_collaboration_modes_tools,
+            capture_tool: include_collaboration_modes_tools,
             memory_tools: include_memory_tools,
             request_rule_enabled,
             experimental_supported_tools: model_info.experimental_supported_tools.clone(),
         }
     }
+
+    /// Leave out the `capture` tool unless `enabled`, as
+    /// `tools.capture.enabled = false` asks.
+    pub fn with_capture_tool_enabled(mut self, enabled: bool) -> Self {
+        self.capture_tool &= enabled;
+        self
+    }
 }
 
 /// Generic JSON‑Schema subset needed for our tool definitions
@@ -644,13 +655,100 @@ fn create_capture_tool() -> ToolSpec {
         description: capture_tool_description(),
         strict: false,
         parameters: JsonSchema::Object {
-            properties: BTreeMap::new(),
+            properties: BTreeMap::from([(
+                "template".to_string(),
+                JsonSchema::String {
+                    description: Some(
+                        "Name of a capture template from CODEX_HOME/capture_templates. Omit for the built-in flow."
+                            .to_string(),
+                    ),
+                },
+            )]),
             required: None,
             additional_properties: Some(false.into()),
         },
     })
 }
 
+fn create_covenant_request_tool() -> ToolSpec {
+    let properties = BTreeMap::from([
+        (
+            "capability".to_string(),
+            JsonSchema::String {
+                description: Some("Covenant capability to request.".to_string()),
+            },
+        ),
+        (
+            "minutes".to_string(),
+            JsonSchema::Number {
+                description: Some("How long the grant should last, in minutes.".to_string()),
+            },
+        ),
+        (
+            "reason".to_string(),
+            JsonSchema::String {
+                description: Some("Why the session needs the capability.".to_string()),
+            },
+        ),
+    ]);
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "covenant_request".to_string(),
+        description: covenant_request_tool_description(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec![
+                "capability".to_string(),
+                "minutes".to_string(),
+                "reason".to_string(),
+            ]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_pattern_feedback_tool() -> ToolSpec {
+    let properties = BTreeMap::from([
+        (
+            "pattern_id".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "Id of the live pattern whose best response was applied.".to_string(),
+                ),
+            },
+        ),
+        (
+            "outcome".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "How applying it turned out: success, failure, mixed, or unknown.".to_string(),
+                ),
+            },
+        ),
+        (
+            "match_id".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "Match id the pattern was suggested under, when it came from a recorded match."
+                        .to_string(),
+                ),
+            },
+        ),
+    ]);
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "pattern_feedback".to_string(),
+        description: "Report how applying a covenant pattern's best response turned out. The report is appended to the pattern usage log and counted as supporting or contradicting the pattern.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["pattern_id".to_string(), "outcome".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
 fn create_get_memory_tool() -> ToolSpec {
     let properties = BTreeMap::from([(
         "memory_id".to_string(),
@@ -1267,12 +1365,14 @@ pub(crate) fn build_specs(
     use crate::tools::handlers::ApplyPatchHandler;
     use crate::tools::handlers::CaptureHandler;
     use crate::tools::handlers::CollabHandler;
+    use crate::tools::handlers::CovenantRequestHandler;
     use crate::tools::handlers::DynamicToolHandler;
     use crate::tools::handlers::GetMemoryHandler;
     use crate::tools::handlers::GrepFilesHandler;
     use crate::tools::handlers::ListDirHandler;
     use crate::tools::handlers::McpHandler;
     use crate::tools::handlers::McpResourceHandler;
+    use crate::tools::handlers::PatternFeedbackHandler;
     use crate::tools::handlers::PlanHandler;
     use crate::tools::handlers::ReadFileHandler;
     use crate::tools::handlers::RequestUserInputHandler;
@@ -1297,6 +1397,8 @@ pub(crate) fn build_specs(
     let shell_command_handler = Arc::new(ShellCommandHandler);
     let request_user_input_handler = Arc::new(RequestUserInputHandler);
     let capture_handler = Arc::new(CaptureHandler);
+    let covenant_request_handler = Arc::new(CovenantRequestHandler);
+    let pattern_feedback_handler = Arc::new(PatternFeedbackHandler);
 
     match &config.shell_type {
         ConfigShellToolType::Default => {
@@ -1349,8 +1451,14 @@ pub(crate) fn build_specs(
     if config.collaboration_modes_tools {
         builder.push_spec(create_request_user_input_tool());
         builder.register_handler("request_user_input", request_user_input_handler);
-        builder.push_spec(create_capture_tool());
-        builder.register_handler("capture", capture_handler);
+        if config.capture_tool {
+            builder.push_spec(create_capture_tool());
+            builder.register_handler("capture", capture_handler);
+        }
+        builder.push_spec(create_covenant_request_tool());
+        builder.register_handler("covenant_request", covenant_request_handler);
+        builder.push_spec(create_pattern_feedback_tool());
+        builder.register_handler("pattern_feedback", pattern_feedback_handler);
     }
 
     if config.memory_tools {
@@ -1650,6 +1758,8 @@ mod tests {
             PLAN_TOOL.clone(),
             create_request_user_input_tool(),
             create_capture_tool(),
+            create_covenant_request_tool(),
+            create_pattern_feedback_tool(),
             create_apply_patch_freeform_tool(),
             ToolSpec::WebSearch {
                 external_web_access: Some(true),
@@ -1713,6 +1823,14 @@ mod tests {
             !tools.iter().any(|t| t.spec.name() == "capture"),
             "capture should be disabled when collaboration_modes feature is off"
         );
+        assert!(
+            !tools.iter().any(|t| t.spec.name() == "covenant_request"),
+            "covenant_request should be disabled when collaboration_modes feature is off"
+        );
+        assert!(
+            !tools.iter().any(|t| t.spec.name() == "pattern_feedback"),
+            "pattern_feedback should be disabled when collaboration_modes feature is off"
+        );
 
         features.enable(Feature::CollaborationModes);
         let tools_config = ToolsConfig::new(&ToolsConfigParams {
@@ -1721,7 +1839,28 @@ mod tests {
             web_search_mode: Some(WebSearchMode::Cached),
         });
         let (tools, _) = build_specs(&tools_config, None, &[]).build();
-        assert_contains_tool_names(&tools, &["request_user_input", "capture"]);
+        assert_contains_tool_names(
+            &tools,
+            &[
+                "request_user_input",
+                "capture",
+                "covenant_request",
+                "pattern_feedback",
+            ],
+        );
+
+        let tools_config = ToolsConfig::new(&ToolsConfigParams {
+            model_info: &model_info,
+            features: &features,
+            web_search_mode: Some(WebSearchMode::Cached),
+        })
+        .with_capture_tool_enabled(false);
+        let (tools, _) = build_specs(&tools_config, None, &[]).build();
+        assert!(
+            !tools.iter().any(|t| t.spec.name() == "capture"),
+            "capture should be disabled by tools.capture.enabled = false"
+        );
+        assert_contains_tool_names(&tools, &["request_user_input", "covenant_request"]);
     }
 
     #[test]
@@ -1845,6 +1984,8 @@ mod tests {
                 "update_plan",
                 "request_user_input",
                 "capture",
+                "covenant_request",
+                "pattern_feedback",
                 "apply_patch",
                 "web_search",
                 "view_image",
@@ -1868,6 +2009,8 @@ mod tests {
                 "update_plan",
                 "request_user_input",
                 "capture",
+                "covenant_request",
+                "pattern_feedback",
                 "apply_patch",
                 "web_search",
                 "view_image",
@@ -1893,6 +2036,8 @@ mod tests {
                 "update_plan",
                 "request_user_input",
                 "capture",
+                "covenant_request",
+                "pattern_feedback",
                 "apply_patch",
                 "web_search",
                 "view_image",
@@ -1918,6 +2063,8 @@ mod tests {
                 "update_plan",
                 "request_user_input",
                 "capture",
+                "covenant_request",
+                "pattern_feedback",
                 "apply_patch",
                 "web_search",
                 "view_image",
@@ -1941,6 +2088,8 @@ mod tests {
                 "update_plan",
                 "request_user_input",
                 "capture",
+                "covenant_request",
+                "pattern_feedback",
                 "web_search",
                 "view_image",
             ],
@@ -1963,6 +2112,8 @@ mod tests {
                 "update_plan",
                 "request_user_input",
                 "capture",
+                "covenant_request",
+                "pattern_feedback",
                 "apply_patch",
                 "web_search",
                 "view_image",
@@ -1986,6 +2137,8 @@ mod tests {
                 "update_plan",
                 "request_user_input",
                 "capture",
+                "covenant_request",
+                "pattern_feedback",
                 "web_search",
                 "view_image",
             ],
@@ -2008,6 +2161,8 @@ mod tests {
                 "update_plan",
                 "request_user_input",
                 "capture",
+                "covenant_request",
+                "pattern_feedback",
                 "apply_patch",
                 "web_search",
                 "view_image",
@@ -2032,6 +2187,8 @@ mod tests {
                 "update_plan",
                 "request_user_input",
                 "capture",
+                "covenant_request",
+                "pattern_feedback",
                 "apply_patch",
                 "web_search",
                 "view_image",
@@ -2057,6 +2214,8 @@ mod tests {
                 "update_plan",
                 "request_user_input",
                 "capture",
+                "covenant_request",
+                "pattern_feedback",
                 "web_search",
                 "view_image",
             ],
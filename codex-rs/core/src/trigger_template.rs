@@ -0,0 +1,269 @@
+//! Parameterized pattern triggers.
+//!
+//! Compile replaces the variable spans of a trigger (numbers, paths, and
+//! quoted strings) with placeholders, so "Disk full on /var/log" and "Disk
+//! full on /tmp" compile to the same "Disk full on {path}" pattern. Matching
+//! an event against such a template yields the values the placeholders took.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+const QUOTES: [char; 3] = ['"', '\'', '`'];
+const LEADING_PUNCTUATION: [char; 2] = ['(', '['];
+const TRAILING_PUNCTUATION: [char; 8] = ['.', ',', ';', ':', '!', '?', ')', ']'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum VariableKind {
+    Number,
+    Path,
+    Quoted,
+}
+
+impl VariableKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Number => "number",
+            Self::Path => "path",
+            Self::Quoted => "quoted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PieceKind {
+    Word,
+    Placeholder,
+    Variable(VariableKind),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Piece {
+    kind: PieceKind,
+    range: Range<usize>,
+}
+
+/// Replace the variable spans of `trigger` with placeholders such as `{path}`.
+/// Repeated kinds are numbered from the second one on (`{number}`,
+/// `{number_2}`). Returns `None` when the trigger has no variable spans.
+pub fn templatize_trigger(trigger: &str) -> Option<String> {
+    let mut template = String::new();
+    let mut copied = 0;
+    let mut counts: BTreeMap<VariableKind, usize> = BTreeMap::new();
+    for piece in scan(trigger) {
+        let PieceKind::Variable(kind) = piece.kind else {
+            continue;
+        };
+        let count = counts.entry(kind).or_insert(0);
+        *count += 1;
+        template.push_str(&trigger[copied..piece.range.start]);
+        template.push_str(&placeholder(kind, *count));
+        copied = piece.range.end;
+    }
+    if counts.is_empty() {
+        return None;
+    }
+    template.push_str(&trigger[copied..]);
+    Some(template)
+}
+
+/// Whether `trigger` contains at least one `{name}` placeholder.
+pub fn has_placeholders(trigger: &str) -> bool {
+    scan(trigger)
+        .iter()
+        .any(|piece| piece.kind == PieceKind::Placeholder)
+}
+
+/// Match `trigger` against `template` word for word, ignoring case. Each
+/// placeholder must line up with a variable span of the trigger, whose value
+/// is bound to the placeholder's name. Returns `None` when the shapes differ.
+pub fn match_trigger_template(template: &str, trigger: &str) -> Option<BTreeMap<String, String>> {
+    let expected = scan(template);
+    let actual = scan(trigger);
+    if expected.len() != actual.len() {
+        return None;
+    }
+    let mut bindings = BTreeMap::new();
+    for (expected, actual) in expected.iter().zip(&actual) {
+        let expected_text = &template[expected.range.clone()];
+        let actual_text = &trigger[actual.range.clone()];
+        match (expected.kind, actual.kind) {
+            (PieceKind::Placeholder, PieceKind::Variable(kind)) => {
+                let name = expected_text.trim_start_matches('{').trim_end_matches('}');
+                let value = if kind == VariableKind::Quoted {
+                    &actual_text[1..actual_text.len() - 1]
+                } else {
+                    actual_text
+                };
+                bindings.insert(name.to_string(), value.to_string());
+            }
+            _ if expected_text.to_lowercase() == actual_text.to_lowercase() => {}
+            _ => return None,
+        }
+    }
+    Some(bindings)
+}
+
+fn placeholder(kind: VariableKind, count: usize) -> String {
+    let name = kind.name();
+    if count == 1 {
+        format!("{{{name}}}")
+    } else {
+        format!("{{{name}_{count}}}")
+    }
+}
+
+/// Split `text` into words, placeholders, and variable spans. Leading and
+/// trailing punctuation is split off each word so "/tmp." ends in a path.
+fn scan(text: &str) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut offset = 0;
+    while offset < text.len() {
+        let rest = &text[offset..];
+        let trimmed = rest.trim_start();
+        offset += rest.len() - trimmed.len();
+        let Some(first) = trimmed.chars().next() else {
+            break;
+        };
+        if QUOTES.contains(&first)
+            && let Some(close) = trimmed[1..].find(first)
+        {
+            let end = offset + close + 2;
+            pieces.push(Piece {
+                kind: PieceKind::Variable(VariableKind::Quoted),
+                range: offset..end,
+            });
+            offset = end;
+            continue;
+        }
+        let len = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        scan_word(&mut pieces, &trimmed[..len], offset);
+        offset += len;
+    }
+    pieces
+}
+
+fn scan_word(pieces: &mut Vec<Piece>, word: &str, offset: usize) {
+    let core_start = word.len() - word.trim_start_matches(LEADING_PUNCTUATION).len();
+    let core_end = core_start
+        + word[core_start..]
+            .trim_end_matches(TRAILING_PUNCTUATION)
+            .len();
+    if core_start > 0 {
+        pieces.push(Piece {
+            kind: PieceKind::Word,
+            range: offset..offset + core_start,
+        });
+    }
+    if core_end > core_start {
+        pieces.push(Piece {
+            kind: classify(&word[core_start..core_end]),
+            range: offset + core_start..offset + core_end,
+        });
+    }
+    if core_end < word.len() {
+        pieces.push(Piece {
+            kind: PieceKind::Word,
+            range: offset + core_end..offset + word.len(),
+        });
+    }
+}
+
+fn classify(word: &str) -> PieceKind {
+    if is_placeholder(word) {
+        PieceKind::Placeholder
+    } else if is_number(word) {
+        PieceKind::Variable(VariableKind::Number)
+    } else if is_path(word) {
+        PieceKind::Variable(VariableKind::Path)
+    } else {
+        PieceKind::Word
+    }
+}
+
+fn is_placeholder(word: &str) -> bool {
+    word.strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .is_some_and(|name| {
+            !name.is_empty() && name.chars().all(|ch| ch.is_alphanumeric() || ch == '_')
+        })
+}
+
+/// Integers and decimals, optionally negative or followed by `%`. Digit
+/// groups separated by commas or dots, such as versions, count as numbers.
+fn is_number(word: &str) -> bool {
+    let digits = word.strip_prefix('-').unwrap_or(word);
+    let digits = digits.strip_suffix('%').unwrap_or(digits);
+    digits.starts_with(|ch: char| ch.is_ascii_digit())
+        && digits.ends_with(|ch: char| ch.is_ascii_digit())
+        && digits
+            .chars()
+            .all(|ch| ch.is_ascii_digit() || ch == '.' || ch == ',')
+}
+
+/// Words with a path separator that start like a path (`/`, `~`, `.`, or a
+/// drive letter) or end in a file name with an extension.
+fn is_path(word: &str) -> bool {
+    if !word.contains(['/', '\\']) || !word.chars().any(char::is_alphanumeric) {
+        return false;
+    }
+    word.starts_with(['/', '~', '.'])
+        || word.as_bytes().get(1) == Some(&b':')
+        || word
+            .rsplit(['/', '\\'])
+            .next()
+            .is_some_and(|name| name.contains('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn variable_spans_become_numbered_placeholders() {
+        assert_eq!(
+            [
+                "Disk full on /var/log.",
+                "Retry 3 of 5 failed for \"db migrate\"",
+                "copy src/main.rs to ./out",
+                "Disk full",
+                "read/write error",
+            ]
+            .into_iter()
+            .map(templatize_trigger)
+            .collect::<Vec<_>>(),
+            vec![
+                Some("Disk full on {path}.".to_string()),
+                Some("Retry {number} of {number_2} failed for {quoted}".to_string()),
+                Some("copy {path} to {path_2}".to_string()),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn templates_bind_the_values_of_matching_triggers() {
+        let template = "Disk full on {path} at {number}";
+
+        assert_eq!(
+            match_trigger_template(template, "disk FULL on /tmp/cache at 93%"),
+            Some(BTreeMap::from([
+                ("number".to_string(), "93%".to_string()),
+                ("path".to_string(), "/tmp/cache".to_string()),
+            ]))
+        );
+        assert_eq!(
+            match_trigger_template("Lock held on {quoted}", "Lock held on 'main'"),
+            Some(BTreeMap::from([("quoted".to_string(), "main".to_string())]))
+        );
+        assert_eq!(
+            match_trigger_template(template, "Disk full on /tmp/cache"),
+            None
+        );
+        assert_eq!(
+            match_trigger_template(template, "Disk full on cache at 93%"),
+            None
+        );
+    }
+}
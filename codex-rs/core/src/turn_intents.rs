@@ -0,0 +1,174 @@
+//! Per-turn user intents read back from a rollout.
+//!
+//! Each user message starts a turn. Its intent record carries the message,
+//! when it was sent, the turn context it ran under, and the message's content
+//! tokens, so stats, drift detection, and capture can share one reading of
+//! what the user asked for.
+
+use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use codex_protocol::protocol::SandboxPolicy;
+use codex_protocol::protocol::TurnContextItem;
+use codex_utils_text_normalize::token_set;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// What the user asked for in one turn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnIntent {
+    pub message: String,
+    /// Timestamp of the rollout line that recorded the message.
+    pub timestamp: String,
+    /// The turn context in effect, or `None` when the rollout recorded none
+    /// before the agent replied.
+    pub context: Option<TurnContextSnapshot>,
+    /// Lowercased, stemmed content words of `message`, without stopwords.
+    pub tokens: BTreeSet<String>,
+}
+
+/// The parts of a turn context that describe where and how a turn ran.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnContextSnapshot {
+    pub cwd: PathBuf,
+    pub model: String,
+    pub approval_policy: AskForApproval,
+    pub sandbox_policy: SandboxPolicy,
+}
+
+impl TurnContextSnapshot {
+    /// The sandbox policy's kind, as in `workspace-write`.
+    pub fn sandbox_mode(&self) -> Option<String> {
+        serde_json::to_value(&self.sandbox_policy)
+            .ok()?
+            .get("type")?
+            .as_str()
+            .map(str::to_string)
+    }
+}
+
+impl From<&TurnContextItem> for TurnContextSnapshot {
+    fn from(item: &TurnContextItem) -> Self {
+        Self {
+            cwd: item.cwd.clone(),
+            model: item.model.clone(),
+            approval_policy: item.approval_policy,
+            sandbox_policy: item.sandbox_policy.clone(),
+        }
+    }
+}
+
+/// One intent per user message in `rollout`, in order.
+///
+/// A turn context is recorded once the turn starts, after its user message
+/// but before the agent replies, so a context arriving before the first reply
+/// applies to the turn in progress as well as to later ones.
+pub fn extract_turn_intents(rollout: &[RolloutLine]) -> Vec<TurnIntent> {
+    let mut intents: Vec<TurnIntent> = Vec::new();
+    let mut context = None;
+    let mut replied = false;
+    for line in rollout {
+        match &line.item {
+            RolloutItem::TurnContext(item) => {
+                let snapshot = TurnContextSnapshot::from(item);
+                if !replied && let Some(intent) = intents.last_mut() {
+                    intent.context = Some(snapshot.clone());
+                }
+                context = Some(snapshot);
+            }
+            RolloutItem::EventMsg(EventMsg::UserMessage(event)) => {
+                intents.push(TurnIntent {
+                    message: event.message.clone(),
+                    timestamp: line.timestamp.clone(),
+                    context: context.clone(),
+                    tokens: token_set(&event.message).into_iter().collect(),
+                });
+                replied = false;
+            }
+            RolloutItem::EventMsg(EventMsg::AgentMessage(_)) => replied = true,
+            RolloutItem::EventMsg(_)
+            | RolloutItem::SessionMeta(_)
+            | RolloutItem::ResponseItem(_)
+            | RolloutItem::Compacted(_) => {}
+        }
+    }
+    intents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn line(timestamp: &str, item: serde_json::Value) -> RolloutLine {
+        let mut value = item;
+        value["timestamp"] = serde_json::Value::String(timestamp.to_string());
+        serde_json::from_value(value).expect("rollout line")
+    }
+
+    fn turn_context(model: &str) -> serde_json::Value {
+        serde_json::json!({
+            "type": "turn_context",
+            "payload": {
+                "cwd": "/repo",
+                "approval_policy": "on-request",
+                "sandbox_policy": {"type": "read-only"},
+                "model": model,
+                "summary": "auto"
+            }
+        })
+    }
+
+    fn event(kind: &str, message: &str) -> serde_json::Value {
+        serde_json::json!({
+            "type": "event_msg",
+            "payload": {"type": kind, "message": message}
+        })
+    }
+
+    #[test]
+    fn intents_take_the_context_recorded_before_the_reply() {
+        let rollout = vec![
+            line("t1", event("user_message", "Fix the failing tests")),
+            line("t2", turn_context("gpt-a")),
+            line("t3", event("agent_message", "Fixed.")),
+            line("t4", event("user_message", "now update the docs")),
+            line("t5", event("agent_message", "Updated.")),
+            line("t6", turn_context("gpt-b")),
+        ];
+        let snapshot = |model: &str| TurnContextSnapshot {
+            cwd: PathBuf::from("/repo"),
+            model: model.to_string(),
+            approval_policy: AskForApproval::OnRequest,
+            sandbox_policy: SandboxPolicy::ReadOnly,
+        };
+        let tokens = |words: &[&str]| words.iter().map(ToString::to_string).collect();
+
+        let intents = extract_turn_intents(&rollout);
+        assert_eq!(
+            intents,
+            vec![
+                TurnIntent {
+                    message: "Fix the failing tests".to_string(),
+                    timestamp: "t1".to_string(),
+                    context: Some(snapshot("gpt-a")),
+                    tokens: tokens(&["fail", "fix", "test"]),
+                },
+                TurnIntent {
+                    message: "now update the docs".to_string(),
+                    timestamp: "t4".to_string(),
+                    context: Some(snapshot("gpt-a")),
+                    tokens: tokens(&["doc", "now", "updat"]),
+                },
+            ]
+        );
+        assert_eq!(
+            intents[0]
+                .context
+                .as_ref()
+                .and_then(TurnContextSnapshot::sandbox_mode),
+            Some("read-only".to_string())
+        );
+    }
+}
@@ -0,0 +1,190 @@
+#![allow(clippy::expect_used)]
+
+use anyhow::Result;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use codex_protocol::config_types::ReasoningSummary;
+use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::user_input::UserInput;
+use core_test_support::responses::ResponseMock;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::ev_response_created;
+use core_test_support::responses::mount_sse_sequence;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use wiremock::MockServer;
+
+const CALL_ID: &str = "covenant-shell";
+
+/// Start a session whose project has `covenant`, if any, and queue a model
+/// turn that runs `echo covenant-ok` through `shell_command`. The mock server
+/// is returned so it outlives the turn.
+async fn covenant_session(
+    covenant: Option<serde_json::Value>,
+) -> Result<(MockServer, TestCodex, ResponseMock)> {
+    let server = start_mock_server().await;
+    let test = test_codex().build(&server).await?;
+    if let Some(covenant) = covenant {
+        std::fs::write(
+            test.workspace_path("covenant.json"),
+            serde_json::to_string(&covenant)?,
+        )?;
+    }
+    let arguments = serde_json::to_string(&json!({
+        "command": "echo covenant-ok",
+        "timeout_ms": 2_000,
+    }))?;
+    let responses = mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_response_created("resp-1"),
+                ev_function_call(CALL_ID, "shell_command", &arguments),
+                ev_completed("resp-1"),
+            ]),
+            sse(vec![
+                ev_assistant_message("msg-1", "done"),
+                ev_completed("resp-2"),
+            ]),
+        ],
+    )
+    .await;
+    Ok((server, test, responses))
+}
+
+fn exec_scope_covenant(capabilities: &[&str]) -> serde_json::Value {
+    json!({
+        "version": "2",
+        "scopes": [{ "name": "exec", "capabilities": capabilities }],
+    })
+}
+
+async fn submit_turn(test: &TestCodex, approval_policy: AskForApproval) -> Result<()> {
+    test.codex
+        .submit(Op::UserTurn {
+            items: vec![UserInput::Text {
+                text: "run the echo command".into(),
+                text_elements: Vec::new(),
+            }],
+            final_output_json_schema: None,
+            cwd: test.cwd.path().to_path_buf(),
+            approval_policy,
+            sandbox_policy: SandboxPolicy::DangerFullAccess,
+            model: test.session_configured.model.clone(),
+            effort: None,
+            summary: ReasoningSummary::Auto,
+            collaboration_mode: None,
+            personality: None,
+        })
+        .await?;
+    Ok(())
+}
+
+/// Wait for the turn to finish, failing if it asks for approval first.
+async fn wait_for_completion_without_approval(test: &TestCodex) {
+    let event = wait_for_event(&test.codex, |event| {
+        matches!(
+            event,
+            EventMsg::ExecApprovalRequest(_) | EventMsg::TurnComplete(_)
+        )
+    })
+    .await;
+    if let EventMsg::ExecApprovalRequest(approval) = event {
+        panic!("unexpected approval request: {approval:?}");
+    }
+}
+
+fn shell_output(responses: &ResponseMock) -> String {
+    responses
+        .function_call_output_text(CALL_ID)
+        .expect("shell_command output")
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn commands_run_without_a_covenant_when_approvals_are_disabled() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let (_server, test, responses) = covenant_session(None).await?;
+    submit_turn(&test, AskForApproval::Never).await?;
+    wait_for_completion_without_approval(&test).await;
+
+    assert_eq!(shell_output(&responses).contains("covenant-ok"), true);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn granted_commands_run_without_approval() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let (_server, test, responses) =
+        covenant_session(Some(exec_scope_covenant(&["proposal.exec_command"]))).await?;
+    submit_turn(&test, AskForApproval::Never).await?;
+    wait_for_completion_without_approval(&test).await;
+
+    assert_eq!(shell_output(&responses).contains("covenant-ok"), true);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn ungranted_commands_are_escalated_to_the_user() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let (_server, test, responses) =
+        covenant_session(Some(exec_scope_covenant(&["intervention.exec_approval"]))).await?;
+    submit_turn(&test, AskForApproval::OnRequest).await?;
+
+    let event = wait_for_event(&test.codex, |event| {
+        matches!(
+            event,
+            EventMsg::ExecApprovalRequest(_) | EventMsg::TurnComplete(_)
+        )
+    })
+    .await;
+    let EventMsg::ExecApprovalRequest(approval) = event else {
+        panic!("expected an approval request before completion, got {event:?}");
+    };
+    assert_eq!(
+        approval.reason,
+        Some(
+            "covenant scope disallows proposal.exec_command; approve to allow it anyway"
+                .to_string()
+        )
+    );
+
+    test.codex
+        .submit(Op::ExecApproval {
+            id: approval.turn_id.clone(),
+            decision: ReviewDecision::Approved,
+        })
+        .await?;
+    wait_for_completion_without_approval(&test).await;
+
+    assert_eq!(shell_output(&responses).contains("covenant-ok"), true);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn ungranted_commands_are_refused_when_approvals_are_disabled() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let (_server, test, responses) =
+        covenant_session(Some(exec_scope_covenant(&["event.log"]))).await?;
+    submit_turn(&test, AskForApproval::Never).await?;
+    wait_for_completion_without_approval(&test).await;
+
+    assert_eq!(
+        shell_output(&responses),
+        "covenant scope disallows proposal.exec_command"
+    );
+    Ok(())
+}
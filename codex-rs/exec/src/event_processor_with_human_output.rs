@@ -778,6 +778,9 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             | EventMsg::ListSkillsResponse(_)
             | EventMsg::ListRemoteSkillsResponse(_)
             | EventMsg::RemoteSkillDownloaded(_)
+            | EventMsg::PatternQueryResponse(_)
+            | EventMsg::StalePatterns(_)
+            | EventMsg::ApprovalDecision(_)
             | EventMsg::RawResponseItem(_)
             | EventMsg::UserMessage(_)
             | EventMsg::EnteredReviewMode(_)
@@ -796,6 +799,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             | EventMsg::UndoStarted(_)
             | EventMsg::ThreadRolledBack(_)
             | EventMsg::RequestUserInput(_)
+            | EventMsg::RequestUserInputClosed(_)
             | EventMsg::DynamicToolCallRequest(_) => {}
         }
         CodexStatus::Running
@@ -1,6 +1,16 @@
+use codex_utils_text_normalize::tokenize;
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::fmt;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 pub type RecordId = u64;
 
@@ -27,19 +37,139 @@ pub struct Outcome {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct CompiledPattern {
+    /// The intent's full text, as captured.
     pub intent: String,
     pub outcome: String,
+    /// The intent's tokens, compacted to at most
+    /// [`CompileOptions::max_intent_tokens`] of its most informative ones.
     pub tokens: Vec<String>,
+    /// The outcome's tokens, for finding the pattern by what happened.
+    /// Patterns saved without them are matched on their outcome's text.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outcome_tokens: Vec<String>,
+    /// Labels such as `audio` or `bluetooth`, matched against the query's
+    /// tokens.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Default for [`CompileOptions::max_intent_tokens`].
+pub const DEFAULT_MAX_INTENT_TOKENS: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileOptions {
+    /// Intents with more tokens than this keep only the ones with the highest
+    /// IDF, so a pasted log or stack trace does not swamp matching.
+    pub max_intent_tokens: usize,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            max_intent_tokens: DEFAULT_MAX_INTENT_TOKENS,
+        }
+    }
+}
+
+/// Inverse document frequency of tokens across a set of intents. Tokens the
+/// set has never seen count as the most informative.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IntentIdf {
+    documents: usize,
+    frequencies: HashMap<String, usize>,
+}
+
+impl IntentIdf {
+    pub fn from_intents<'a, I>(intents: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut idf = Self::default();
+        for intent in intents {
+            idf.documents += 1;
+            for token in tokenize(intent).into_iter().collect::<HashSet<_>>() {
+                *idf.frequencies.entry(token).or_default() += 1;
+            }
+        }
+        idf
+    }
+
+    /// Smoothed IDF: `ln((1 + n) / (1 + df)) + 1`.
+    pub fn idf(&self, token: &str) -> f64 {
+        let frequency = self.frequencies.get(token).copied().unwrap_or_default();
+        ((1 + self.documents) as f64 / (1 + frequency) as f64).ln() + 1.0
+    }
+
+    /// `tokens` unchanged when there are at most `cap` distinct ones;
+    /// otherwise the first occurrence of each of the `cap` tokens with the
+    /// highest IDF, in their original order. Ties go to the earlier token.
+    pub fn compact(&self, tokens: Vec<String>, cap: usize) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let distinct: Vec<&str> = tokens
+            .iter()
+            .map(String::as_str)
+            .filter(|token| seen.insert(*token))
+            .collect();
+        if distinct.len() <= cap {
+            return tokens;
+        }
+        let mut ranked: Vec<(usize, f64)> = distinct
+            .iter()
+            .enumerate()
+            .map(|(position, token)| (position, self.idf(token)))
+            .collect();
+        ranked.sort_by(|left, right| right.1.total_cmp(&left.1).then(left.0.cmp(&right.0)));
+        let mut kept: Vec<usize> = ranked
+            .into_iter()
+            .take(cap)
+            .map(|(position, _)| position)
+            .collect();
+        kept.sort_unstable();
+        kept.into_iter()
+            .map(|position| distinct[position].to_string())
+            .collect()
+    }
 }
 
 impl CompiledPattern {
+    /// Compile with the default token cap, weighing tokens by `intent` alone.
     pub fn compile(intent: &IntentToken, outcome: &Outcome) -> Self {
-        let tokens = tokenize(&intent.text);
+        let idf = IntentIdf::from_intents([intent.text.as_str()]);
+        Self::compile_with(intent, outcome, &idf, &CompileOptions::default())
+    }
+
+    /// Compile, keeping at most `options.max_intent_tokens` of the intent's
+    /// tokens as ranked by `idf`.
+    pub fn compile_with(
+        intent: &IntentToken,
+        outcome: &Outcome,
+        idf: &IntentIdf,
+        options: &CompileOptions,
+    ) -> Self {
+        let tokens = idf.compact(tokenize(&intent.text), options.max_intent_tokens);
         Self {
             intent: intent.text.clone(),
             outcome: outcome.summary.clone(),
             tokens,
+            outcome_tokens: tokenize(&outcome.summary),
+            tags: Vec::new(),
+        }
+    }
+
+    /// The pattern with `tags` added, trimmed and lowercased, skipping blank
+    /// and repeated ones.
+    pub fn with_tags<I, T>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        for tag in tags {
+            let tag = tag.as_ref().trim().to_lowercase();
+            if !tag.is_empty() && !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
         }
+        self
     }
 }
 
@@ -52,6 +182,29 @@ pub enum RecordKind {
     CompiledPattern,
 }
 
+impl RecordKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::IntentToken => "intent",
+            Self::Hypothesis => "hypothesis",
+            Self::Test => "test",
+            Self::Outcome => "outcome",
+            Self::CompiledPattern => "pattern",
+        }
+    }
+
+    /// The kind every record of this kind must link to.
+    fn parent(&self) -> Option<RecordKind> {
+        match self {
+            Self::IntentToken => None,
+            Self::Hypothesis => Some(Self::IntentToken),
+            Self::Test => Some(Self::Hypothesis),
+            Self::Outcome => Some(Self::Test),
+            Self::CompiledPattern => Some(Self::Outcome),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", content = "data")]
 pub enum CapturePayload {
@@ -62,6 +215,19 @@ pub enum CapturePayload {
     CompiledPattern(CompiledPattern),
 }
 
+impl CapturePayload {
+    /// The record's main text: what `validate` requires to be non-blank.
+    pub fn text(&self) -> &str {
+        match self {
+            Self::IntentToken(intent) => &intent.text,
+            Self::Hypothesis(hypothesis) => &hypothesis.summary,
+            Self::Test(test) => &test.description,
+            Self::Outcome(outcome) => &outcome.summary,
+            Self::CompiledPattern(pattern) => &pattern.intent,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct CaptureRecord {
     pub id: RecordId,
@@ -70,6 +236,10 @@ pub struct CaptureRecord {
     pub payload: CapturePayload,
 }
 
+/// Records linked into a DAG: each record links to one or more records of its
+/// parent kind, so a flow can hold several intents, a hypothesis can serve
+/// more than one of them, an outcome can settle several tests, and a pattern
+/// can synthesize several outcomes.
 #[derive(Debug, Clone)]
 pub struct CaptureFlow {
     records: Vec<CaptureRecord>,
@@ -84,15 +254,33 @@ impl CaptureFlow {
             next_id: 1,
             intent_id: 0,
         };
-        let intent_id = flow.push_record(RecordKind::IntentToken, Vec::new(), CapturePayload::IntentToken(intent_token));
+        let intent_id = flow.push_record(
+            RecordKind::IntentToken,
+            Vec::new(),
+            CapturePayload::IntentToken(intent_token),
+        );
         flow.intent_id = intent_id;
         flow
     }
 
+    /// Add another intent to the flow. [`Self::add_hypothesis`] keeps linking
+    /// to the flow's first intent; use [`Self::add_hypothesis_for`] for this one.
+    pub fn add_intent(&mut self, intent_token: IntentToken) -> RecordId {
+        self.push_record(
+            RecordKind::IntentToken,
+            Vec::new(),
+            CapturePayload::IntentToken(intent_token),
+        )
+    }
+
     pub fn add_hypothesis(&mut self, hypothesis: Hypothesis) -> RecordId {
+        self.add_hypothesis_for(self.intent_id, hypothesis)
+    }
+
+    pub fn add_hypothesis_for(&mut self, intent_id: RecordId, hypothesis: Hypothesis) -> RecordId {
         self.push_record(
             RecordKind::Hypothesis,
-            vec![self.intent_id],
+            vec![intent_id],
             CapturePayload::Hypothesis(hypothesis),
         )
     }
@@ -106,14 +294,23 @@ impl CaptureFlow {
     }
 
     pub fn add_outcome(&mut self, test_id: RecordId, outcome: Outcome) -> RecordId {
+        self.add_outcome_multi(&[test_id], outcome)
+    }
+
+    /// Add an outcome that settles every test in `test_ids`.
+    pub fn add_outcome_multi(&mut self, test_ids: &[RecordId], outcome: Outcome) -> RecordId {
         self.push_record(
             RecordKind::Outcome,
-            vec![test_id],
+            dedupe_links(test_ids),
             CapturePayload::Outcome(outcome),
         )
     }
 
-    pub fn add_compiled_pattern(&mut self, outcome_id: RecordId, pattern: CompiledPattern) -> RecordId {
+    pub fn add_compiled_pattern(
+        &mut self,
+        outcome_id: RecordId,
+        pattern: CompiledPattern,
+    ) -> RecordId {
         self.push_record(
             RecordKind::CompiledPattern,
             vec![outcome_id],
@@ -121,11 +318,103 @@ impl CaptureFlow {
         )
     }
 
+    /// Also link `record_id` to `parent_id`, merging branches: a pattern to
+    /// another outcome it synthesizes, a hypothesis to another intent it
+    /// explains. Linking twice is a no-op.
+    pub fn link_records(
+        &mut self,
+        record_id: RecordId,
+        parent_id: RecordId,
+    ) -> Result<(), CaptureFlowError> {
+        let parent_kind = self
+            .record(parent_id)
+            .map(|parent| parent.kind.clone())
+            .ok_or(CaptureFlowError::UnknownRecord { id: parent_id })?;
+        let record = self
+            .records
+            .iter_mut()
+            .find(|record| record.id == record_id)
+            .ok_or(CaptureFlowError::UnknownRecord { id: record_id })?;
+        if record.kind.parent() != Some(parent_kind) {
+            return Err(CaptureFlowError::InvalidLink {
+                id: record_id,
+                kind: record.kind.clone(),
+                link: parent_id,
+            });
+        }
+        if !record.links.contains(&parent_id) {
+            record.links.push(parent_id);
+        }
+        Ok(())
+    }
+
     pub fn records(&self) -> &[CaptureRecord] {
         &self.records
     }
 
-    fn push_record(&mut self, kind: RecordKind, links: Vec<RecordId>, payload: CapturePayload) -> RecordId {
+    /// Token IDF across the flow's intents, for [`CompiledPattern::compile_with`].
+    pub fn intent_idf(&self) -> IntentIdf {
+        IntentIdf::from_intents(
+            self.records
+                .iter()
+                .filter_map(|record| match &record.payload {
+                    CapturePayload::IntentToken(intent) => Some(intent.text.as_str()),
+                    CapturePayload::Hypothesis(_)
+                    | CapturePayload::Test(_)
+                    | CapturePayload::Outcome(_)
+                    | CapturePayload::CompiledPattern(_) => None,
+                }),
+        )
+    }
+
+    pub fn record(&self, id: RecordId) -> Option<&CaptureRecord> {
+        self.records.iter().find(|record| record.id == id)
+    }
+
+    /// Check that every record has text and that all of its links, of which
+    /// there must be at least one, point to records of its parent kind:
+    /// hypotheses to intents, tests to hypotheses, outcomes to tests, and
+    /// patterns to outcomes. Links always point one kind up, so the flow
+    /// cannot contain a cycle.
+    pub fn validate(&self) -> Result<(), CaptureFlowError> {
+        let kinds: HashMap<RecordId, &RecordKind> = self
+            .records
+            .iter()
+            .map(|record| (record.id, &record.kind))
+            .collect();
+        for record in &self.records {
+            if record.payload.text().trim().is_empty() {
+                return Err(CaptureFlowError::EmptyRecord {
+                    id: record.id,
+                    kind: record.kind.clone(),
+                });
+            }
+            let linked = match record.kind.parent() {
+                Some(parent) => {
+                    !record.links.is_empty()
+                        && record
+                            .links
+                            .iter()
+                            .all(|link| kinds.get(link) == Some(&&parent))
+                }
+                None => record.links.is_empty(),
+            };
+            if !linked {
+                return Err(CaptureFlowError::Unlinked {
+                    id: record.id,
+                    kind: record.kind.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn push_record(
+        &mut self,
+        kind: RecordKind,
+        links: Vec<RecordId>,
+        payload: CapturePayload,
+    ) -> RecordId {
         let id = self.next_id;
         self.next_id += 1;
         self.records.push(CaptureRecord {
@@ -138,102 +427,704 @@ impl CaptureFlow {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// `ids` in order, without repeats.
+fn dedupe_links(ids: &[RecordId]) -> Vec<RecordId> {
+    let mut seen = HashSet::new();
+    ids.iter().copied().filter(|id| seen.insert(*id)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct PatternMatch {
     pub pattern: CompiledPattern,
-    pub score: usize,
+    /// `fields` combined by the matcher's [`MatchWeights`], from 0 to 1.
+    pub score: f64,
+    pub fields: FieldScores,
     pub rationale: String,
 }
 
+/// The share of the query's distinct tokens found in each field of a
+/// pattern, from 0 to 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FieldScores {
+    pub intent: f64,
+    pub outcome: f64,
+    pub tags: f64,
+}
+
+/// How much each field counts towards a [`PatternMatch`]'s score. Only the
+/// ratios matter; negative weights count as zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchWeights {
+    pub intent: f64,
+    pub outcome: f64,
+    pub tags: f64,
+}
+
+impl Default for MatchWeights {
+    fn default() -> Self {
+        Self {
+            intent: 1.0,
+            outcome: 0.5,
+            tags: 0.25,
+        }
+    }
+}
+
+impl MatchWeights {
+    /// The weighted mean of `fields`, or zero when no weight is positive.
+    pub fn combine(&self, fields: &FieldScores) -> f64 {
+        let weighted = [
+            (self.intent, fields.intent),
+            (self.outcome, fields.outcome),
+            (self.tags, fields.tags),
+        ]
+        .map(|(weight, score)| (weight.max(0.0), score));
+        let total: f64 = weighted.iter().map(|(weight, _)| weight).sum();
+        if total == 0.0 {
+            return 0.0;
+        }
+        weighted
+            .iter()
+            .map(|(weight, score)| weight * score)
+            .sum::<f64>()
+            / total
+    }
+}
+
 #[derive(Debug, Default)]
-pub struct PatternMatcher;
+pub struct PatternMatcher {
+    weights: MatchWeights,
+}
 
 impl PatternMatcher {
+    pub fn with_weights(weights: MatchWeights) -> Self {
+        Self { weights }
+    }
+
+    /// Score every pattern against `query` on its intent, outcome, and tags,
+    /// best first. Patterns with equal scores keep their order.
     pub fn rank(&self, query: &str, patterns: &[CompiledPattern]) -> Vec<PatternMatch> {
+        self.rank_candidates(query, patterns)
+    }
+
+    /// [`Self::rank`] over only the patterns in `index` that share a token
+    /// with `query`. The others would score zero, so they are left out
+    /// instead of being listed last.
+    pub fn rank_indexed(&self, query: &str, index: &PatternIndex) -> Vec<PatternMatch> {
+        self.rank_candidates(query, index.candidates(query))
+    }
+
+    fn rank_candidates<'a>(
+        &self,
+        query: &str,
+        patterns: impl IntoIterator<Item = &'a CompiledPattern>,
+    ) -> Vec<PatternMatch> {
         let query_tokens = tokenize(query);
         let query_set: HashSet<&str> = query_tokens.iter().map(String::as_str).collect();
         let mut matches: Vec<PatternMatch> = patterns
-            .iter()
+            .into_iter()
             .cloned()
             .map(|pattern| {
-                let (score, rationale) = score_pattern(&query_set, &pattern);
+                let (fields, rationale) = score_pattern(&query_set, &pattern);
                 PatternMatch {
                     pattern,
-                    score,
+                    score: self.weights.combine(&fields),
+                    fields,
                     rationale,
                 }
             })
             .collect();
 
-        matches.sort_by(|left, right| right.score.cmp(&left.score));
+        matches.sort_by(|left, right| right.score.total_cmp(&left.score));
         matches
     }
 }
 
+/// Inverted index from the intent, outcome, and tag tokens of compiled
+/// patterns to the patterns, for [`PatternMatcher::rank_indexed`]. Build it
+/// once and [`insert`](Self::insert) patterns as they are compiled.
+#[derive(Debug, Clone, Default)]
+pub struct PatternIndex {
+    patterns: Vec<CompiledPattern>,
+    postings: HashMap<String, Vec<usize>>,
+}
+
+impl PatternIndex {
+    pub fn build(patterns: &[CompiledPattern]) -> Self {
+        let mut index = Self::default();
+        for pattern in patterns {
+            index.insert(pattern.clone());
+        }
+        index
+    }
+
+    pub fn insert(&mut self, pattern: CompiledPattern) {
+        let slot = self.patterns.len();
+        let tokens = pattern
+            .tokens
+            .iter()
+            .cloned()
+            .chain(outcome_tokens(&pattern))
+            .chain(tag_tokens(&pattern))
+            .collect::<HashSet<_>>();
+        for token in tokens {
+            self.postings.entry(token).or_default().push(slot);
+        }
+        self.patterns.push(pattern);
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Patterns sharing at least one token with `query`, in insertion order.
+    pub fn candidates(&self, query: &str) -> Vec<&CompiledPattern> {
+        tokenize(query)
+            .iter()
+            .filter_map(|token| self.postings.get(token))
+            .flatten()
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|slot| &self.patterns[slot])
+            .collect()
+    }
+}
+
+/// Format version written by [`PatternCorpus::save`]. Corpora with a newer
+/// version are refused rather than read partially.
+pub const PATTERN_CORPUS_VERSION: u32 = 1;
+
+/// A set of compiled patterns with where and when it was made, for storing
+/// and sharing patterns between consumers of this crate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct PatternCorpus {
+    pub version: u32,
+    /// Seconds since the Unix epoch.
+    pub created_at: i64,
+    /// Where the patterns came from, such as a project or file name.
+    pub source: String,
+    pub patterns: Vec<CompiledPattern>,
+}
+
+impl PatternCorpus {
+    /// A corpus of `patterns` from `source`, created now.
+    pub fn new(source: impl Into<String>, patterns: Vec<CompiledPattern>) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| i64::try_from(elapsed.as_secs()).unwrap_or(i64::MAX))
+            .unwrap_or_default();
+        Self {
+            version: PATTERN_CORPUS_VERSION,
+            created_at,
+            source: source.into(),
+            patterns,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, PatternCorpusError> {
+        serde_json::to_string_pretty(self).map_err(PatternCorpusError::Parse)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PatternCorpusError> {
+        let corpus: Self = serde_json::from_str(json).map_err(PatternCorpusError::Parse)?;
+        if corpus.version > PATTERN_CORPUS_VERSION {
+            return Err(PatternCorpusError::UnsupportedVersion {
+                version: corpus.version,
+            });
+        }
+        Ok(corpus)
+    }
+
+    /// Write the corpus to `path` as pretty-printed JSON, replacing any
+    /// existing file.
+    pub fn save(&self, path: &Path) -> Result<(), PatternCorpusError> {
+        let mut json = self.to_json()?;
+        json.push('\n');
+        std::fs::write(path, json).map_err(PatternCorpusError::Io)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PatternCorpusError> {
+        let json = std::fs::read_to_string(path).map_err(PatternCorpusError::Io)?;
+        Self::from_json(&json)
+    }
+}
+
+/// Why a [`PatternCorpus`] could not be loaded or saved.
+#[derive(Debug)]
+pub enum PatternCorpusError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    /// The corpus was written by a newer version of this crate.
+    UnsupportedVersion {
+        version: u32,
+    },
+}
+
+impl fmt::Display for PatternCorpusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read or write pattern corpus: {err}"),
+            Self::Parse(err) => write!(f, "invalid pattern corpus: {err}"),
+            Self::UnsupportedVersion { version } => write!(
+                f,
+                "pattern corpus version {version} is newer than the supported version {PATTERN_CORPUS_VERSION}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatternCorpusError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::UnsupportedVersion { .. } => None,
+        }
+    }
+}
+
+/// An allowlist of actions.
+///
+/// Each allowed pattern is one of:
+///
+/// - an exact action, such as `route_audio`;
+/// - a glob where `*` stands for any run of characters, such as
+///   `route_audio:*`, which allows `route_audio:bluetooth`;
+/// - an action with constraints on its named arguments, such as
+///   `read_file:{path within workspace}`, checked by
+///   [`Covenant::enforce_with_args`].
+///
+/// An argument constraint is `{name}`, which only requires the argument,
+/// `{name within workspace}`, which requires a path inside the workspace set
+/// with [`Covenant::with_workspace`], or `{name matching GLOB}`. Several
+/// constraints are separated by commas: `copy_file:{from within workspace},{to within workspace}`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Covenant {
-    allowed_actions: HashSet<String>,
+    allowed_actions: Vec<ActionPattern>,
+    workspace: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ActionPattern {
+    Glob(String),
+    Params {
+        action: String,
+        params: Vec<ParamPattern>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ParamPattern {
+    name: String,
+    constraint: ParamConstraint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ParamConstraint {
+    Present,
+    WithinWorkspace,
+    Matching(String),
 }
 
 impl Covenant {
-    pub fn new<I>(allowed_actions: I) -> Self
+    pub fn new<I>(allowed_actions: I) -> Result<Self, CovenantPatternError>
     where
         I: IntoIterator<Item = String>,
     {
-        let allowed_actions = allowed_actions.into_iter().collect();
-        Self { allowed_actions }
+        let allowed_actions = allowed_actions
+            .into_iter()
+            .map(|pattern| ActionPattern::parse(&pattern))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            allowed_actions,
+            workspace: None,
+        })
+    }
+
+    /// Resolve relative paths against `root` and allow `within workspace`
+    /// arguments below it. Without a workspace those arguments are refused.
+    pub fn with_workspace(mut self, root: impl Into<PathBuf>) -> Self {
+        self.workspace = Some(normalize_path(&root.into()));
+        self
     }
 
     pub fn enforce(&self, action: &str) -> Result<(), CovenantError> {
-        if self.allowed_actions.contains(action) {
-            Ok(())
+        self.enforce_with_args(action, &HashMap::new())
+    }
+
+    /// Allow `action` if some pattern names it and every constraint of that
+    /// pattern holds for `args`. When patterns name the action but refuse its
+    /// arguments, the error carries the first pattern's violation.
+    pub fn enforce_with_args(
+        &self,
+        action: &str,
+        args: &HashMap<String, String>,
+    ) -> Result<(), CovenantError> {
+        let mut violation = None;
+        for pattern in &self.allowed_actions {
+            match pattern {
+                ActionPattern::Glob(glob) => {
+                    if glob_matches(glob, action) {
+                        return Ok(());
+                    }
+                }
+                ActionPattern::Params {
+                    action: name,
+                    params,
+                } => {
+                    if !glob_matches(name, action) {
+                        continue;
+                    }
+                    match params
+                        .iter()
+                        .find_map(|param| self.check_param(param, args).err())
+                    {
+                        None => return Ok(()),
+                        Some(refused) => {
+                            violation.get_or_insert(refused);
+                        }
+                    }
+                }
+            }
+        }
+        Err(CovenantError {
+            action: action.to_string(),
+            violation,
+        })
+    }
+
+    fn check_param(
+        &self,
+        param: &ParamPattern,
+        args: &HashMap<String, String>,
+    ) -> Result<(), ArgViolation> {
+        let Some(value) = args.get(&param.name) else {
+            return Err(ArgViolation::Missing {
+                param: param.name.clone(),
+            });
+        };
+        match &param.constraint {
+            ParamConstraint::Present => Ok(()),
+            ParamConstraint::WithinWorkspace => {
+                let within = self
+                    .workspace
+                    .as_deref()
+                    .is_some_and(|root| normalize_path(&root.join(value)).starts_with(root));
+                if within {
+                    Ok(())
+                } else {
+                    Err(ArgViolation::OutsideWorkspace {
+                        param: param.name.clone(),
+                        value: value.clone(),
+                    })
+                }
+            }
+            ParamConstraint::Matching(glob) => {
+                if glob_matches(glob, value) {
+                    Ok(())
+                } else {
+                    Err(ArgViolation::NoMatch {
+                        param: param.name.clone(),
+                        value: value.clone(),
+                        glob: glob.clone(),
+                    })
+                }
+            }
+        }
+    }
+}
+
+impl ActionPattern {
+    fn parse(pattern: &str) -> Result<Self, CovenantPatternError> {
+        let invalid = |reason: &str| CovenantPatternError {
+            pattern: pattern.to_string(),
+            reason: reason.to_string(),
+        };
+        let Some(brace) = pattern.find('{') else {
+            if pattern.contains('}') {
+                return Err(invalid("`}` without a matching `{`"));
+            }
+            return Ok(Self::Glob(pattern.to_string()));
+        };
+        let Some(action) = pattern[..brace].strip_suffix(':') else {
+            return Err(invalid("argument constraints must follow `action:`"));
+        };
+        if action.is_empty() {
+            return Err(invalid("the action name is empty"));
+        }
+        let mut params = Vec::new();
+        let mut rest = &pattern[brace..];
+        loop {
+            let Some(body) = rest.strip_prefix('{') else {
+                return Err(invalid("expected `{` to start an argument constraint"));
+            };
+            let Some(close) = body.find('}') else {
+                return Err(invalid("`{` without a matching `}`"));
+            };
+            params.push(ParamPattern::parse(&body[..close]).map_err(|reason| invalid(&reason))?);
+            rest = &body[close + 1..];
+            if rest.is_empty() {
+                break;
+            }
+            let Some(next) = rest.strip_prefix(',') else {
+                return Err(invalid("argument constraints must be separated by `,`"));
+            };
+            rest = next;
+        }
+        Ok(Self::Params {
+            action: action.to_string(),
+            params,
+        })
+    }
+}
+
+impl ParamPattern {
+    fn parse(body: &str) -> Result<Self, String> {
+        let (name, constraint) = body.trim().split_once(' ').unwrap_or((body.trim(), ""));
+        if name.is_empty() || name.contains('{') {
+            return Err("an argument constraint needs an argument name".to_string());
+        }
+        let constraint = constraint.trim();
+        let constraint = if constraint.is_empty() {
+            ParamConstraint::Present
+        } else if constraint == "within workspace" {
+            ParamConstraint::WithinWorkspace
+        } else if let Some(glob) = constraint.strip_prefix("matching ") {
+            ParamConstraint::Matching(glob.trim().to_string())
         } else {
-            Err(CovenantError {
-                action: action.to_string(),
-            })
+            return Err(format!(
+                "unknown constraint `{constraint}` on `{name}`; expected `within workspace` or `matching GLOB`"
+            ));
+        };
+        Ok(Self {
+            name: name.to_string(),
+            constraint,
+        })
+    }
+}
+
+/// Whether `text` matches `glob`, where `*` stands for any run of characters.
+fn glob_matches(glob: &str, text: &str) -> bool {
+    let Some((first, rest)) = glob.split_once('*') else {
+        return glob == text;
+    };
+    let Some(mut text) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = rest.split('*').collect::<Vec<_>>();
+    let last = parts.pop().unwrap_or_default();
+    for part in parts {
+        match text.find(part) {
+            Some(index) => text = &text[index + part.len()..],
+            None => return false,
         }
     }
+    text.ends_with(last)
 }
 
+/// `path` with `.` and `..` resolved lexically, without touching the file
+/// system. Symlinks are not followed.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            Component::Prefix(_) | Component::RootDir | Component::Normal(_) => {
+                normalized.push(component)
+            }
+        }
+    }
+    normalized
+}
+
+/// Why [`Covenant::enforce`] or [`Covenant::enforce_with_args`] refused an
+/// action.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CovenantError {
     pub action: String,
+    /// Why the arguments were refused, or `None` when no pattern names the
+    /// action.
+    pub violation: Option<ArgViolation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgViolation {
+    /// The argument is required but was not given.
+    Missing { param: String },
+    /// The argument is a path outside the workspace, or no workspace is set.
+    OutsideWorkspace { param: String, value: String },
+    /// The argument does not match the pattern's glob.
+    NoMatch {
+        param: String,
+        value: String,
+        glob: String,
+    },
+}
+
+impl fmt::Display for CovenantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = &self.action;
+        match &self.violation {
+            None => write!(f, "action `{action}` is not allowed"),
+            Some(ArgViolation::Missing { param }) => {
+                write!(f, "action `{action}` requires the `{param}` argument")
+            }
+            Some(ArgViolation::OutsideWorkspace { param, value }) => write!(
+                f,
+                "action `{action}` only allows `{param}` within the workspace, not `{value}`"
+            ),
+            Some(ArgViolation::NoMatch { param, value, glob }) => write!(
+                f,
+                "action `{action}` only allows `{param}` matching `{glob}`, not `{value}`"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CovenantError {}
+
+/// Why [`Covenant::new`] could not parse an allowed pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CovenantPatternError {
+    pub pattern: String,
+    pub reason: String,
+}
+
+impl fmt::Display for CovenantPatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid action pattern `{}`: {}",
+            self.pattern, self.reason
+        )
+    }
+}
+
+impl std::error::Error for CovenantPatternError {}
+
+/// Why [`CaptureFlow::validate`] rejected a flow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureFlowError {
+    /// The record's text is blank.
+    EmptyRecord { id: RecordId, kind: RecordKind },
+    /// The record is missing its parent link or links to the wrong kind.
+    Unlinked { id: RecordId, kind: RecordKind },
+    /// [`CaptureFlow::link_records`] was given a record of the wrong kind.
+    InvalidLink {
+        id: RecordId,
+        kind: RecordKind,
+        link: RecordId,
+    },
+    /// No record in the flow has this id.
+    UnknownRecord { id: RecordId },
+}
+
+impl fmt::Display for CaptureFlowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyRecord { id, kind } => write!(f, "{} record {id} is empty", kind.label()),
+            Self::Unlinked { id, kind } => match kind.parent() {
+                Some(parent) => write!(
+                    f,
+                    "{} record {id} is not linked to a {} record",
+                    kind.label(),
+                    parent.label()
+                ),
+                None => write!(
+                    f,
+                    "{} record {id} must not link to other records",
+                    kind.label()
+                ),
+            },
+            Self::InvalidLink { id, kind, link } => match kind.parent() {
+                Some(parent) => write!(
+                    f,
+                    "{} record {id} can only link to {} records, not record {link}",
+                    kind.label(),
+                    parent.label()
+                ),
+                None => write!(
+                    f,
+                    "{} record {id} must not link to other records",
+                    kind.label()
+                ),
+            },
+            Self::UnknownRecord { id } => write!(f, "no record {id} in the flow"),
+        }
+    }
 }
 
+impl std::error::Error for CaptureFlowError {}
+
 pub fn capture_schema() -> serde_json::Value {
     let schema = schemars::schema_for!(CaptureRecord);
     serde_json::to_value(schema).expect("schema should serialize")
 }
 
-fn tokenize(text: &str) -> Vec<String> {
-    let mut cleaned = String::with_capacity(text.len());
-    for ch in text.chars() {
-        if ch.is_alphanumeric() {
-            cleaned.push(ch.to_ascii_lowercase());
-        } else {
-            cleaned.push(' ');
-        }
+/// The outcome's tokens, from its text for patterns saved without them.
+fn outcome_tokens(pattern: &CompiledPattern) -> Vec<String> {
+    if pattern.outcome_tokens.is_empty() {
+        tokenize(&pattern.outcome)
+    } else {
+        pattern.outcome_tokens.clone()
     }
-    cleaned
-        .split_whitespace()
-        .map(str::to_string)
-        .collect()
 }
 
-fn score_pattern(query_set: &HashSet<&str>, pattern: &CompiledPattern) -> (usize, String) {
-    let matched: Vec<&str> = pattern
-        .tokens
+fn tag_tokens(pattern: &CompiledPattern) -> Vec<String> {
+    pattern
+        .tags
         .iter()
-        .map(String::as_str)
-        .filter(|token| query_set.contains(*token))
+        .flat_map(|tag| tokenize(tag.as_str()))
+        .collect()
+}
+
+fn score_pattern(query_set: &HashSet<&str>, pattern: &CompiledPattern) -> (FieldScores, String) {
+    let outcome_tokens = outcome_tokens(pattern);
+    let tag_tokens = tag_tokens(pattern);
+    let intent = shared_tokens(query_set, &pattern.tokens);
+    let outcome = shared_tokens(query_set, &outcome_tokens);
+    let tags = shared_tokens(query_set, &tag_tokens);
+    let share = |shared: &[&str]| {
+        if query_set.is_empty() {
+            0.0
+        } else {
+            shared.len() as f64 / query_set.len() as f64
+        }
+    };
+    let fields = FieldScores {
+        intent: share(&intent),
+        outcome: share(&outcome),
+        tags: share(&tags),
+    };
+    let matched: Vec<String> = [("intent", &intent), ("outcome", &outcome), ("tag", &tags)]
+        .into_iter()
+        .filter(|(_, shared)| !shared.is_empty())
+        .map(|(field, shared)| format!("matched {field} tokens: {}", shared.join(", ")))
         .collect();
-    let score = matched.len();
     let rationale = if matched.is_empty() {
-        "no shared intent tokens".to_string()
+        "no shared tokens".to_string()
     } else {
-        format!("matched tokens: {}", matched.join(", "))
+        matched.join("; ")
     };
-    (score, rationale)
+    (fields, rationale)
+}
+
+/// The distinct tokens of `tokens` the query also has, in order.
+fn shared_tokens<'a>(query_set: &HashSet<&str>, tokens: &'a [String]) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    tokens
+        .iter()
+        .map(String::as_str)
+        .filter(|token| query_set.contains(*token) && seen.insert(*token))
+        .collect()
 }
 
 #[cfg(test)]
@@ -280,6 +1171,8 @@ mod tests {
                 intent: "pressed play".to_string(),
                 outcome: "audio routed to bluetooth".to_string(),
                 tokens: vec!["pressed".to_string(), "play".to_string()],
+                outcome_tokens: Vec::new(),
+                tags: Vec::new(),
             },
         );
 
@@ -307,6 +1200,136 @@ mod tests {
         assert_eq!(pattern.links, vec![outcome_id]);
     }
 
+    #[test]
+    fn validate_rejects_blank_and_misplaced_records() {
+        let mut flow = CaptureFlow::new(IntentToken {
+            text: "pressed play".to_string(),
+        });
+        let hypothesis_id = flow.add_hypothesis(Hypothesis {
+            summary: "bluetooth output not selected".to_string(),
+        });
+        flow.add_test(
+            hypothesis_id,
+            CaptureTest {
+                description: "switch output to headset".to_string(),
+            },
+        );
+        assert_eq!(flow.validate(), Ok(()));
+
+        let mut misplaced = flow.clone();
+        let outcome_id = misplaced.add_outcome(
+            hypothesis_id,
+            Outcome {
+                summary: "audio routed".to_string(),
+                success: true,
+            },
+        );
+        let mut blank = flow;
+        let blank_id = blank.add_hypothesis(Hypothesis {
+            summary: "  ".to_string(),
+        });
+
+        assert_eq!(
+            misplaced.validate(),
+            Err(CaptureFlowError::Unlinked {
+                id: outcome_id,
+                kind: RecordKind::Outcome,
+            })
+        );
+        assert_eq!(
+            blank.validate(),
+            Err(CaptureFlowError::EmptyRecord {
+                id: blank_id,
+                kind: RecordKind::Hypothesis,
+            })
+        );
+    }
+
+    #[test]
+    fn flows_branch_and_merge_across_intents() {
+        let mut flow = CaptureFlow::new(IntentToken {
+            text: "pressed play".to_string(),
+        });
+        let second_intent = flow.add_intent(IntentToken {
+            text: "raised volume".to_string(),
+        });
+        let routing = flow.add_hypothesis(Hypothesis {
+            summary: "bluetooth output not selected".to_string(),
+        });
+        let muted = flow.add_hypothesis_for(
+            second_intent,
+            Hypothesis {
+                summary: "player muted".to_string(),
+            },
+        );
+        flow.link_records(routing, second_intent)
+            .expect("link hypothesis");
+        let switch = flow.add_test(
+            routing,
+            CaptureTest {
+                description: "switch output to headset".to_string(),
+            },
+        );
+        let unmute = flow.add_test(
+            muted,
+            CaptureTest {
+                description: "unmute the player".to_string(),
+            },
+        );
+        let both = flow.add_outcome_multi(
+            &[switch, unmute, switch],
+            Outcome {
+                summary: "audio plays on the headset".to_string(),
+                success: true,
+            },
+        );
+        let partial = flow.add_outcome(
+            switch,
+            Outcome {
+                summary: "headset connects but stays quiet".to_string(),
+                success: false,
+            },
+        );
+        let pattern = flow.add_compiled_pattern(
+            both,
+            CompiledPattern {
+                intent: "pressed play".to_string(),
+                outcome: "audio plays on the headset".to_string(),
+                tokens: vec!["pressed".to_string(), "play".to_string()],
+                outcome_tokens: Vec::new(),
+                tags: Vec::new(),
+            },
+        );
+        flow.link_records(pattern, partial).expect("link pattern");
+        flow.link_records(pattern, partial)
+            .expect("link pattern again");
+
+        let links = |id| flow.record(id).map(|record| record.links.clone());
+        assert_eq!(
+            [routing, both, pattern].map(links),
+            [
+                Some(vec![1, second_intent]),
+                Some(vec![switch, unmute]),
+                Some(vec![both, partial]),
+            ]
+        );
+        assert_eq!(flow.validate(), Ok(()));
+        assert_eq!(
+            [
+                flow.link_records(pattern, switch),
+                flow.link_records(99, both)
+            ],
+            [
+                Err(CaptureFlowError::InvalidLink {
+                    id: pattern,
+                    kind: RecordKind::CompiledPattern,
+                    link: switch,
+                }),
+                Err(CaptureFlowError::UnknownRecord { id: 99 }),
+            ]
+        );
+    }
+
     #[test]
     fn pattern_match_ranks_with_rationale() {
         let matcher = PatternMatcher::default();
@@ -315,27 +1338,360 @@ mod tests {
                 intent: "pressed play".to_string(),
                 outcome: "audio routed to bluetooth".to_string(),
                 tokens: vec!["pressed".to_string(), "play".to_string()],
+                outcome_tokens: Vec::new(),
+                tags: Vec::new(),
             },
             CompiledPattern {
                 intent: "paused playback".to_string(),
                 outcome: "audio muted".to_string(),
                 tokens: vec!["paused".to_string(), "playback".to_string()],
+                outcome_tokens: Vec::new(),
+                tags: Vec::new(),
             },
         ];
 
         let results = matcher.rank("hit play on bluetooth", &patterns);
         assert_eq!(results.len(), 2);
         assert_eq!(results[0].pattern.intent, "pressed play");
-        assert_eq!(results[0].score, 1);
-        assert_eq!(results[0].rationale.contains("matched tokens"), true);
+        assert_eq!(
+            results[0].rationale,
+            "matched intent tokens: play; matched outcome tokens: bluetooth"
+        );
+        assert_eq!(results[1].rationale, "no shared tokens");
+    }
+
+    #[test]
+    fn ranking_weighs_intent_outcome_and_tags() {
+        let compile = |intent: &str, outcome: &str| {
+            CompiledPattern::compile(
+                &IntentToken {
+                    text: intent.to_string(),
+                },
+                &Outcome {
+                    summary: outcome.to_string(),
+                    success: true,
+                },
+            )
+        };
+        let patterns = vec![
+            compile("audio bluetooth", "nothing changed"),
+            compile("pressed play", "audio bluetooth headset").with_tags([" Headset ", "headset"]),
+        ];
+        let ranked = |weights: MatchWeights| {
+            PatternMatcher::with_weights(weights)
+                .rank("audio bluetooth headset", &patterns)
+                .into_iter()
+                .map(|result| (result.pattern.intent, result.fields, result.score))
+                .collect::<Vec<_>>()
+        };
+        let intent_only = FieldScores {
+            intent: 2.0 / 3.0,
+            outcome: 0.0,
+            tags: 0.0,
+        };
+        let outcome_and_tags = FieldScores {
+            intent: 0.0,
+            outcome: 1.0,
+            tags: 1.0 / 3.0,
+        };
+        let by_outcome = MatchWeights {
+            intent: 1.0,
+            outcome: 1.0,
+            tags: 1.0,
+        };
+
+        assert_eq!(patterns[1].tags, vec!["headset".to_string()]);
+        assert_eq!(
+            [ranked(MatchWeights::default()), ranked(by_outcome)],
+            [
+                vec![
+                    (
+                        "audio bluetooth".to_string(),
+                        intent_only,
+                        MatchWeights::default().combine(&intent_only),
+                    ),
+                    (
+                        "pressed play".to_string(),
+                        outcome_and_tags,
+                        MatchWeights::default().combine(&outcome_and_tags),
+                    ),
+                ],
+                vec![
+                    (
+                        "pressed play".to_string(),
+                        outcome_and_tags,
+                        by_outcome.combine(&outcome_and_tags),
+                    ),
+                    (
+                        "audio bluetooth".to_string(),
+                        intent_only,
+                        by_outcome.combine(&intent_only),
+                    ),
+                ],
+            ]
+        );
+        assert_eq!(
+            ranked(MatchWeights {
+                outcome: 0.0,
+                tags: 0.0,
+                ..by_outcome
+            })
+            .into_iter()
+            .map(|(intent, _, score)| (intent, score))
+            .collect::<Vec<_>>(),
+            vec![
+                ("audio bluetooth".to_string(), 2.0 / 3.0),
+                ("pressed play".to_string(), 0.0),
+            ]
+        );
+        assert_eq!(
+            by_outcome.combine(&outcome_and_tags),
+            (1.0 + 1.0 / 3.0) / 3.0
+        );
+        assert_eq!(
+            MatchWeights {
+                intent: -1.0,
+                outcome: 0.0,
+                tags: 0.0,
+            }
+            .combine(&intent_only),
+            0.0
+        );
+    }
+
+    #[test]
+    fn the_index_ranks_only_patterns_sharing_a_token() {
+        let compile = |intent: &str, outcome: &str| {
+            CompiledPattern::compile(
+                &IntentToken {
+                    text: intent.to_string(),
+                },
+                &Outcome {
+                    summary: outcome.to_string(),
+                    success: true,
+                },
+            )
+        };
+        let patterns = vec![
+            compile("pressed play", "audio routed to bluetooth"),
+            compile("paused playback", "audio muted"),
+            compile("disk full", "pruned logs").with_tags(["headset"]),
+        ];
+        let mut index = PatternIndex::build(&patterns);
+        let matcher = PatternMatcher::default();
+        let intents = |matches: Vec<PatternMatch>| {
+            matches
+                .into_iter()
+                .map(|result| (result.pattern.intent, result.score))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            intents(matcher.rank_indexed("play on bluetooth headset", &index)),
+            intents(
+                matcher
+                    .rank("play on bluetooth headset", &patterns)
+                    .into_iter()
+                    .filter(|result| result.score > 0.0)
+                    .collect()
+            )
+        );
+
+        index.insert(compile("queue stalled", "restarted the bluetooth daemon"));
+        assert_eq!(
+            (
+                index.len(),
+                index
+                    .candidates("bluetooth")
+                    .into_iter()
+                    .map(|pattern| pattern.intent.as_str())
+                    .collect::<Vec<_>>(),
+            ),
+            (4, vec!["pressed play", "queue stalled"])
+        );
+    }
+
+    #[test]
+    #[ignore = "benchmark; run with --ignored --nocapture"]
+    fn index_ranking_on_100k_patterns() {
+        let patterns = (0..100_000)
+            .map(|id| CompiledPattern {
+                intent: format!("service{} failed region{}", id % 5_000, id % 7_919),
+                outcome: "restarted".to_string(),
+                tokens: vec![
+                    format!("service{}", id % 5_000),
+                    "failed".to_string(),
+                    format!("region{}", id % 7_919),
+                ],
+                outcome_tokens: vec!["restarted".to_string()],
+                tags: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        let matcher = PatternMatcher::default();
+        let query = "service42 region17";
+
+        let started = std::time::Instant::now();
+        let index = PatternIndex::build(&patterns);
+        let build_elapsed = started.elapsed();
+        let started = std::time::Instant::now();
+        let indexed = matcher.rank_indexed(query, &index);
+        let indexed_elapsed = started.elapsed();
+        let started = std::time::Instant::now();
+        let linear = matcher.rank(query, &patterns);
+        let linear_elapsed = started.elapsed();
+
+        assert_eq!(
+            indexed.len(),
+            linear.iter().filter(|result| result.score > 0.0).count()
+        );
+        println!(
+            "index built in {build_elapsed:?}; indexed ranking took {indexed_elapsed:?}, linear took {linear_elapsed:?}"
+        );
+    }
+
+    #[test]
+    fn long_intents_keep_their_most_informative_tokens() {
+        let mut flow = CaptureFlow::new(IntentToken {
+            text: "build failed on ci".to_string(),
+        });
+        flow.add_intent(IntentToken {
+            text: "build failed locally".to_string(),
+        });
+        let pasted = IntentToken {
+            text: "build failed: linker ran out of memory on ci, build failed again".to_string(),
+        };
+        let outcome = Outcome {
+            summary: "raised the runner memory limit".to_string(),
+            success: true,
+        };
+        let idf = flow.intent_idf();
+        let compiled = |max_intent_tokens| {
+            CompiledPattern::compile_with(
+                &pasted,
+                &outcome,
+                &idf,
+                &CompileOptions { max_intent_tokens },
+            )
+            .tokens
+        };
+
+        assert_eq!(
+            [compiled(6), compiled(DEFAULT_MAX_INTENT_TOKENS)],
+            [
+                ["linker", "ran", "out", "memory", "ci", "again"]
+                    .map(String::from)
+                    .to_vec(),
+                tokenize(&pasted.text),
+            ]
+        );
+        assert_eq!(
+            CompiledPattern::compile(&pasted, &outcome).intent,
+            pasted.text
+        );
+    }
+
+    #[test]
+    fn pattern_corpus_round_trips_and_refuses_newer_versions() {
+        let corpus = PatternCorpus {
+            version: PATTERN_CORPUS_VERSION,
+            created_at: 1_767_225_600,
+            source: "audio-troubleshooting".to_string(),
+            patterns: vec![CompiledPattern::compile(
+                &IntentToken {
+                    text: "pressed play".to_string(),
+                },
+                &Outcome {
+                    summary: "audio routed to Bluetooth".to_string(),
+                    success: true,
+                },
+            )],
+        };
+        let path = std::env::temp_dir().join(format!("pattern-corpus-{}.json", std::process::id()));
+
+        corpus.save(&path).expect("save corpus");
+        let loaded = PatternCorpus::load(&path).expect("load corpus");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded, corpus);
+
+        let newer = PatternCorpus {
+            version: PATTERN_CORPUS_VERSION + 1,
+            ..corpus
+        };
+        let err = PatternCorpus::from_json(&newer.to_json().expect("serialize corpus"))
+            .expect_err("newer corpus should be refused");
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "pattern corpus version {} is newer than the supported version {PATTERN_CORPUS_VERSION}",
+                PATTERN_CORPUS_VERSION + 1
+            )
+        );
     }
 
     #[test]
     fn covenant_refuses_out_of_scope_actions() {
-        let covenant = Covenant::new(["route_audio".to_string()]);
+        let covenant = Covenant::new(["route_audio".to_string()]).expect("valid patterns");
         let err = covenant
             .enforce("delete_files")
             .expect_err("should refuse out-of-scope action");
         assert_eq!(err.action, "delete_files");
     }
+
+    #[test]
+    fn covenant_patterns_match_globs_and_constrain_arguments() {
+        let covenant = Covenant::new([
+            "route_audio:*".to_string(),
+            "read_file:{path within workspace}".to_string(),
+            "open_url:{url matching https://*}".to_string(),
+        ])
+        .expect("valid patterns")
+        .with_workspace("/work/app");
+        let args = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+        let refusal = |action: &str, args: HashMap<String, String>| {
+            covenant
+                .enforce_with_args(action, &args)
+                .err()
+                .map(|err| err.to_string())
+        };
+
+        assert_eq!(
+            vec![
+                refusal("route_audio:bluetooth", args(&[])),
+                refusal("route_video:hdmi", args(&[])),
+                refusal("read_file", args(&[("path", "src/lib.rs")])),
+                refusal("read_file", args(&[("path", "/work/app/./docs/../README.md")])),
+                refusal("read_file", args(&[("path", "../secrets.env")])),
+                refusal("read_file", args(&[])),
+                refusal("open_url", args(&[("url", "https://example.com")])),
+                refusal("open_url", args(&[("url", "http://example.com")])),
+            ],
+            vec![
+                None,
+                Some("action `route_video:hdmi` is not allowed".to_string()),
+                None,
+                None,
+                Some(
+                    "action `read_file` only allows `path` within the workspace, not `../secrets.env`"
+                        .to_string()
+                ),
+                Some("action `read_file` requires the `path` argument".to_string()),
+                None,
+                Some(
+                    "action `open_url` only allows `url` matching `https://*`, not `http://example.com`"
+                        .to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            Covenant::new(["read_file:{path inside workspace}".to_string()])
+                .expect_err("unknown constraint")
+                .to_string(),
+            "invalid action pattern `read_file:{path inside workspace}`: unknown constraint `inside workspace` on `path`; expected `within workspace` or `matching GLOB`"
+        );
+    }
 }
@@ -4,6 +4,7 @@ use std::path::PathBuf;
 use crate::mcp::RequestId;
 use crate::parse_command::ParsedCommand;
 use crate::protocol::FileChange;
+use crate::protocol::ReviewDecision;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
@@ -94,3 +95,24 @@ pub struct ApplyPatchApprovalRequestEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub grant_root: Option<PathBuf>,
 }
+
+/// Which approval request an [`ApprovalDecisionEvent`] answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalKind {
+    Exec,
+    Patch,
+}
+
+/// Recorded in the rollout when the user decides an approval request, so the
+/// time turns spend waiting on approvals can be measured afterwards.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema, TS)]
+pub struct ApprovalDecisionEvent {
+    /// Call id of the request that was decided.
+    pub call_id: String,
+    pub turn_id: String,
+    pub kind: ApprovalKind,
+    pub decision: ReviewDecision,
+    /// Milliseconds from the request to the decision.
+    pub wait_ms: u64,
+}
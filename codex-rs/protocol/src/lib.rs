@@ -11,8 +11,10 @@ pub mod message_history;
 pub mod models;
 pub mod num_format;
 pub mod openai_models;
+pub mod outcome;
 pub mod parse_command;
 pub mod plan_tool;
 pub mod protocol;
 pub mod request_user_input;
+pub mod test_status;
 pub mod user_input;
@@ -0,0 +1,189 @@
+//! Shared classification of how an event, test, or capture turned out.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt;
+use ts_rs::TS;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema, TS,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    Failure,
+    Mixed,
+    Unknown,
+    Other,
+}
+
+const SUCCESS_WORDS: &[&str] = &[
+    "pass",
+    "passed",
+    "passes",
+    "passing",
+    "success",
+    "succeeded",
+    "successful",
+    "fixed",
+    "resolved",
+    "solved",
+    "works",
+    "worked",
+    "working",
+    "ok",
+    "green",
+];
+
+const FAILURE_WORDS: &[&str] = &[
+    "fail",
+    "failed",
+    "fails",
+    "failing",
+    "failure",
+    "broken",
+    "broke",
+    "error",
+    "errors",
+    "errored",
+    "crash",
+    "crashed",
+    "regressed",
+    "regression",
+    "worse",
+    "red",
+];
+
+const MIXED_WORDS: &[&str] = &["partial", "partially", "mixed", "flaky", "intermittent"];
+
+const NEGATIONS: &[&str] = &[
+    "not", "no", "never", "didn't", "didnt", "doesn't", "doesnt", "isn't", "isnt",
+];
+
+impl Outcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+            Self::Mixed => "mixed",
+            Self::Unknown => "unknown",
+            Self::Other => "other",
+        }
+    }
+
+    /// Classify free text such as a test result ("pass", "FAILED") or an
+    /// outcome summary ("fixed after restart", "still not working").
+    ///
+    /// Canonical names round-trip through [`Outcome::as_str`]. Empty text is
+    /// [`Outcome::Unknown`]; text without any recognizable signal is
+    /// [`Outcome::Other`].
+    pub fn from_text(text: &str) -> Self {
+        let text = text.trim().to_lowercase();
+        match text.as_str() {
+            "" | "unknown" => return Self::Unknown,
+            "success" => return Self::Success,
+            "failure" => return Self::Failure,
+            "mixed" => return Self::Mixed,
+            "other" => return Self::Other,
+            _ => {}
+        }
+
+        let words = text
+            .split(|ch: char| !(ch.is_alphanumeric() || ch == '\''))
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+        let mut outcomes = Vec::new();
+        for (index, word) in words.iter().enumerate() {
+            let negated = index
+                .checked_sub(1)
+                .and_then(|previous| words.get(previous))
+                .is_some_and(|previous| NEGATIONS.contains(previous));
+            if MIXED_WORDS.contains(word) {
+                outcomes.push(Self::Mixed);
+            } else if SUCCESS_WORDS.contains(word) {
+                outcomes.push(if negated {
+                    Self::Failure
+                } else {
+                    Self::Success
+                });
+            } else if FAILURE_WORDS.contains(word) {
+                outcomes.push(if negated {
+                    Self::Success
+                } else {
+                    Self::Failure
+                });
+            }
+        }
+        if outcomes.is_empty() {
+            return Self::Other;
+        }
+        Self::combine(outcomes)
+    }
+
+    /// Fold several outcomes into one: agreeing outcomes keep their value,
+    /// success alongside failure is [`Outcome::Mixed`], and an empty set is
+    /// [`Outcome::Unknown`].
+    pub fn combine(outcomes: impl IntoIterator<Item = Self>) -> Self {
+        let mut combined: Option<Self> = None;
+        for outcome in outcomes {
+            combined = Some(match (combined, outcome) {
+                (None, outcome) => outcome,
+                (Some(Self::Mixed), _) | (_, Self::Mixed) => Self::Mixed,
+                (Some(Self::Success), Self::Failure) | (Some(Self::Failure), Self::Success) => {
+                    Self::Mixed
+                }
+                (Some(Self::Unknown | Self::Other), outcome) => outcome,
+                (Some(current), _) => current,
+            });
+        }
+        combined.unwrap_or(Self::Unknown)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn free_text_is_classified() {
+        let cases = [
+            ("pass", Outcome::Success),
+            ("FAILED", Outcome::Failure),
+            ("Fixed after restarting the daemon", Outcome::Success),
+            ("still not working", Outcome::Failure),
+            ("no errors in the log", Outcome::Success),
+            ("tests pass but the build fails", Outcome::Mixed),
+            ("flaky on CI", Outcome::Mixed),
+            ("audio routed to bluetooth", Outcome::Other),
+            ("  ", Outcome::Unknown),
+            ("unknown", Outcome::Unknown),
+        ];
+
+        let classified = cases
+            .iter()
+            .map(|(text, _)| (*text, Outcome::from_text(text)))
+            .collect::<Vec<_>>();
+        assert_eq!(classified, cases.to_vec());
+    }
+
+    #[test]
+    fn combine_reports_disagreement_as_mixed() {
+        assert_eq!(
+            Outcome::combine([Outcome::Success, Outcome::Other, Outcome::Success]),
+            Outcome::Success
+        );
+        assert_eq!(
+            Outcome::combine([Outcome::Success, Outcome::Failure]),
+            Outcome::Mixed
+        );
+        assert_eq!(Outcome::combine([]), Outcome::Unknown);
+    }
+}
@@ -50,9 +50,12 @@ use tracing::error;
 use ts_rs::TS;
 
 pub use crate::approvals::ApplyPatchApprovalRequestEvent;
+pub use crate::approvals::ApprovalDecisionEvent;
+pub use crate::approvals::ApprovalKind;
 pub use crate::approvals::ElicitationAction;
 pub use crate::approvals::ExecApprovalRequestEvent;
 pub use crate::approvals::ExecPolicyAmendment;
+pub use crate::request_user_input::RequestUserInputClosedEvent;
 pub use crate::request_user_input::RequestUserInputEvent;
 
 /// Open/close tags for special user-input blocks. Used across crates to avoid
@@ -278,6 +281,18 @@ pub enum Op {
         is_preload: bool,
     },
 
+    /// Rank the stored patterns visible from the session's project against a
+    /// described situation.
+    /// Reply is delivered via `EventMsg::PatternQueryResponse`.
+    QueryPatterns { request: PatternQueryRequest },
+
+    /// Re-confirm or archive stored patterns listed by
+    /// `EventMsg::StalePatterns`. Failures are reported as `EventMsg::Error`.
+    ReviewStalePatterns {
+        pattern_ids: Vec<String>,
+        action: StalePatternAction,
+    },
+
     /// Request the agent to summarize the current conversation context.
     /// The agent will use its existing context (either conversation history or previous response id)
     /// to generate a summary which will be returned as an AgentMessage event.
@@ -790,12 +805,18 @@ pub enum EventMsg {
 
     RequestUserInput(RequestUserInputEvent),
 
+    RequestUserInputClosed(RequestUserInputClosedEvent),
+
     DynamicToolCallRequest(DynamicToolCallRequest),
 
     ElicitationRequest(ElicitationRequestEvent),
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    /// The user's decision on an exec or patch approval request. Only written
+    /// to the rollout; clients are not sent this event.
+    ApprovalDecision(ApprovalDecisionEvent),
+
     /// Notification advising the user that something they are using has been
     /// deprecated and should be phased out.
     DeprecationNotice(DeprecationNoticeEvent),
@@ -837,6 +858,13 @@ pub enum EventMsg {
     /// Remote skill downloaded to local cache.
     RemoteSkillDownloaded(RemoteSkillDownloadedEvent),
 
+    /// Stored patterns that resemble a pattern query.
+    PatternQueryResponse(PatternQueryResponseEvent),
+
+    /// Stored patterns that have gone unconfirmed for a while, listed when a
+    /// session starts.
+    StalePatterns(StalePatternsEvent),
+
     /// Notification that skill data may have been updated and clients may want to reload.
     SkillsUpdateAvailable,
 
@@ -2163,6 +2191,96 @@ pub struct RemoteSkillDownloadedEvent {
     pub path: PathBuf,
 }
 
+/// Request payload for `Op::QueryPatterns`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema, TS)]
+pub struct PatternQueryRequest {
+    /// What happened, matched against pattern triggers.
+    pub trigger: String,
+    /// What is believed to be going on, matched against pattern invariants.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub invariant: String,
+    /// Most matches to return. Defaults to 5.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub limit: Option<usize>,
+    /// Matches to skip, best first, to fetch a later page. Pass the
+    /// `next_offset` of the previous response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub offset: Option<usize>,
+    /// Leave out matches whose score is below this (0-1).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub min_score: Option<f64>,
+    /// Rank patterns from every project, not only the session's.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub global: bool,
+}
+
+/// Response payload for `Op::QueryPatterns`.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, TS)]
+pub struct PatternQueryResponseEvent {
+    /// Best match first.
+    pub matches: Vec<PatternQueryMatch>,
+    /// Patterns that matched the query at all, across every page.
+    pub total_candidates: usize,
+    /// Offset of the next page, absent when these are the last matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub next_offset: Option<usize>,
+}
+
+/// One stored pattern that resembles a pattern query.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema, TS)]
+pub struct PatternQueryMatch {
+    pub pattern_id: String,
+    pub trigger: String,
+    pub invariant: String,
+    /// The response to repeat, or to avoid when `anti_pattern` is set.
+    pub best_response: String,
+    /// An event the pattern did not hold for, when one was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub counterexample: Option<String>,
+    pub anti_pattern: bool,
+    /// Weighted total of the text, domain, and outcome scores (0-1).
+    pub score: f64,
+    /// The component scores behind `score`, as `text=0.91 domain=0.40 ...`.
+    pub rationale: String,
+}
+
+/// Payload for `EventMsg::StalePatterns`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema, TS)]
+pub struct StalePatternsEvent {
+    /// Days without confirmation after which a pattern is listed.
+    pub stale_after_days: u64,
+    /// Least recently confirmed first.
+    pub patterns: Vec<StalePatternSummary>,
+}
+
+/// A stored pattern that has gone unconfirmed for a while.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema, TS)]
+pub struct StalePatternSummary {
+    pub pattern_id: String,
+    pub trigger: String,
+    pub best_response: String,
+    /// When the pattern was last re-confirmed, or created when it never was,
+    /// in seconds since the epoch.
+    #[ts(type = "number")]
+    pub last_confirmed_at: i64,
+}
+
+/// What `Op::ReviewStalePatterns` does with the listed patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(rename_all = "snake_case")]
+pub enum StalePatternAction {
+    /// The patterns still hold; reset their age.
+    Confirm,
+    /// The patterns no longer hold; stop matching them.
+    Archive,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
 #[serde(rename_all = "snake_case")]
 #[ts(rename_all = "snake_case")]
@@ -53,3 +53,13 @@ pub struct RequestUserInputEvent {
     pub turn_id: String,
     pub questions: Vec<RequestUserInputQuestion>,
 }
+
+/// A [`RequestUserInputEvent`] that will no longer be answered, such as a
+/// prompt that timed out. Clients should dismiss it.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct RequestUserInputClosedEvent {
+    /// Responses API call id of the closed request.
+    pub call_id: String,
+    /// Turn ID that the closed request belongs to.
+    pub turn_id: String,
+}
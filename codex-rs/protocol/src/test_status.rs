@@ -0,0 +1,260 @@
+//! How a single test of a hypothesis turned out.
+
+use crate::outcome::Outcome;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use ts_rs::TS;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema, TS,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    Pass,
+    Fail,
+    /// The test ran but did not settle the question.
+    Inconclusive,
+    /// The test was not run.
+    Skipped,
+    /// The test could not run to completion, as when a tool crashed.
+    Error,
+}
+
+/// Each status with the words that parse to it, its canonical name first.
+const STATUS_WORDS: &[(TestStatus, &[&str])] = &[
+    (
+        TestStatus::Pass,
+        &[
+            "pass",
+            "passed",
+            "passes",
+            "passing",
+            "ok",
+            "success",
+            "succeeded",
+            "green",
+        ],
+    ),
+    (
+        TestStatus::Fail,
+        &["fail", "failed", "fails", "failing", "failure", "red"],
+    ),
+    (
+        TestStatus::Inconclusive,
+        &["inconclusive", "unclear", "unknown", "undetermined"],
+    ),
+    (
+        TestStatus::Skipped,
+        &["skipped", "skip", "skips", "not run", "not-run"],
+    ),
+    (
+        TestStatus::Error,
+        &["error", "errored", "errors", "crashed"],
+    ),
+];
+
+/// Edits a word may be away from an accepted one and still be suggested.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+impl TestStatus {
+    pub const ALL: [Self; 5] = [
+        Self::Pass,
+        Self::Fail,
+        Self::Inconclusive,
+        Self::Skipped,
+        Self::Error,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::Fail => "fail",
+            Self::Inconclusive => "inconclusive",
+            Self::Skipped => "skipped",
+            Self::Error => "error",
+        }
+    }
+
+    /// Parse user-entered text: a canonical name or a common synonym such as
+    /// "passed" or "not run", ignoring case and surrounding whitespace.
+    pub fn parse(text: &str) -> Result<Self, UnknownTestStatus> {
+        let word = text.trim().to_lowercase();
+        if let Some((status, _)) = STATUS_WORDS
+            .iter()
+            .find(|(_, words)| words.contains(&word.as_str()))
+        {
+            return Ok(*status);
+        }
+        let suggestions = STATUS_WORDS
+            .iter()
+            .filter(|(_, words)| {
+                !word.is_empty()
+                    && words.iter().any(|candidate| {
+                        candidate.starts_with(word.as_str())
+                            || edit_distance(&word, candidate) <= MAX_SUGGESTION_DISTANCE
+                    })
+            })
+            .map(|(status, _)| *status)
+            .collect();
+        Err(UnknownTestStatus {
+            text: text.trim().to_string(),
+            suggestions,
+        })
+    }
+
+    /// Read a status recorded as free text, as test results were before they
+    /// had a status: text that does not parse is classified as an
+    /// [`Outcome`], and anything without a clear signal is
+    /// [`TestStatus::Inconclusive`].
+    pub fn from_text(text: &str) -> Self {
+        Self::parse(text).unwrap_or_else(|_| match Outcome::from_text(text) {
+            Outcome::Success => Self::Pass,
+            Outcome::Failure => Self::Fail,
+            Outcome::Mixed | Outcome::Unknown | Outcome::Other => Self::Inconclusive,
+        })
+    }
+
+    /// What the result says about the outcome it is evidence for. Tests that
+    /// were skipped or settled nothing carry no signal.
+    pub fn outcome(self) -> Outcome {
+        match self {
+            Self::Pass => Outcome::Success,
+            Self::Fail | Self::Error => Outcome::Failure,
+            Self::Inconclusive | Self::Skipped => Outcome::Unknown,
+        }
+    }
+
+    /// Whether the test ran and gave a pass or fail answer.
+    pub fn is_decisive(self) -> bool {
+        matches!(self, Self::Pass | Self::Fail)
+    }
+}
+
+impl fmt::Display for TestStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TestStatus {
+    type Err = UnknownTestStatus;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse(text)
+    }
+}
+
+/// Text that [`TestStatus::parse`] did not recognize, with the statuses it
+/// resembles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownTestStatus {
+    pub text: String,
+    pub suggestions: Vec<TestStatus>,
+}
+
+impl fmt::Display for UnknownTestStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = |statuses: &[TestStatus]| {
+            statuses
+                .iter()
+                .map(|status| status.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        write!(f, "unknown test status '{}'; ", self.text)?;
+        if self.suggestions.is_empty() {
+            write!(f, "expected one of {}", names(&TestStatus::ALL))
+        } else {
+            write!(f, "did you mean {}?", names(&self.suggestions))
+        }
+    }
+}
+
+impl std::error::Error for UnknownTestStatus {}
+
+/// Levenshtein distance between `left` and `right`, by characters.
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right = right.chars().collect::<Vec<_>>();
+    let mut previous = (0..=right.len()).collect::<Vec<_>>();
+    for (row, left_char) in left.chars().enumerate() {
+        let mut current = vec![row + 1];
+        for (column, right_char) in right.iter().enumerate() {
+            let substitution = previous[column] + usize::from(left_char != *right_char);
+            current.push(
+                substitution
+                    .min(previous[column + 1] + 1)
+                    .min(current[column] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn entered_text_parses_or_suggests_statuses() {
+        let parsed = [
+            "PASS", " failed ", "not run", "errored", "pas", "skiped", "maybe", "",
+        ]
+        .map(TestStatus::parse);
+
+        assert_eq!(
+            parsed,
+            [
+                Ok(TestStatus::Pass),
+                Ok(TestStatus::Fail),
+                Ok(TestStatus::Skipped),
+                Ok(TestStatus::Error),
+                Err(UnknownTestStatus {
+                    text: "pas".to_string(),
+                    suggestions: vec![TestStatus::Pass],
+                }),
+                Err(UnknownTestStatus {
+                    text: "skiped".to_string(),
+                    suggestions: vec![TestStatus::Skipped],
+                }),
+                Err(UnknownTestStatus {
+                    text: "maybe".to_string(),
+                    suggestions: Vec::new(),
+                }),
+                Err(UnknownTestStatus {
+                    text: String::new(),
+                    suggestions: Vec::new(),
+                }),
+            ]
+        );
+        assert_eq!(
+            [&parsed[4], &parsed[6]].map(|result| result.as_ref().err().map(ToString::to_string)),
+            [
+                Some("unknown test status 'pas'; did you mean pass?".to_string()),
+                Some(
+                    "unknown test status 'maybe'; expected one of pass, fail, inconclusive, \
+                     skipped, error"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn free_text_falls_back_to_outcome_classification() {
+        assert_eq!(
+            ["fail", "works after restart", "still broken", "disk at 40%",]
+                .map(TestStatus::from_text),
+            [
+                TestStatus::Fail,
+                TestStatus::Pass,
+                TestStatus::Fail,
+                TestStatus::Inconclusive,
+            ]
+        );
+    }
+}
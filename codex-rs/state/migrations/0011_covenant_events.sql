@@ -0,0 +1,49 @@
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT PRIMARY KEY,
+    created_at INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    domain_signature TEXT NOT NULL,
+    status TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS intent_tokens (
+    id TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL,
+    goal TEXT NOT NULL,
+    constraints TEXT NOT NULL,
+    success_signal TEXT NOT NULL,
+    confidence REAL NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY(event_id) REFERENCES events(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS hypotheses (
+    id TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL,
+    model_type TEXT NOT NULL,
+    probability REAL NOT NULL,
+    falsifiers TEXT NOT NULL,
+    domain_signature TEXT NOT NULL,
+    FOREIGN KEY(event_id) REFERENCES events(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS tests (
+    id TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL,
+    hypothesis_id TEXT NOT NULL,
+    description TEXT NOT NULL,
+    result TEXT NOT NULL,
+    evidence_ref TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY(event_id) REFERENCES events(id) ON DELETE CASCADE,
+    FOREIGN KEY(hypothesis_id) REFERENCES hypotheses(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS outcomes (
+    id TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    evidence_refs TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY(event_id) REFERENCES events(id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE outcomes ADD COLUMN outcome TEXT NOT NULL DEFAULT 'unknown';
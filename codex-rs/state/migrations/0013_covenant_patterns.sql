@@ -0,0 +1,22 @@
+-- Mirrors the table handshakeos-e created on demand, so the runtime can read
+-- and write the live pattern store.
+CREATE TABLE IF NOT EXISTS patterns (
+    id TEXT PRIMARY KEY,
+    trigger TEXT NOT NULL,
+    invariant TEXT NOT NULL,
+    counterexample TEXT NOT NULL,
+    best_response TEXT NOT NULL,
+    domain_signature TEXT NOT NULL,
+    evidence_refs TEXT NOT NULL,
+    namespace TEXT NOT NULL DEFAULT 'global',
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS pattern_reviews (
+    pattern_key TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    decision TEXT NOT NULL,
+    pattern_id TEXT,
+    reviewed_at INTEGER NOT NULL,
+    PRIMARY KEY (pattern_key, version)
+);
@@ -0,0 +1,3 @@
+-- Anti-patterns record responses that made things worse; matches surface them
+-- as warnings instead of recommendations.
+ALTER TABLE patterns ADD COLUMN anti_pattern INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,12 @@
+-- Time-boxed capability grants made during a session on top of the covenant.
+CREATE TABLE IF NOT EXISTS covenant_grants (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    thread_id TEXT NOT NULL,
+    scope TEXT NOT NULL,
+    capability TEXT NOT NULL,
+    granted_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS covenant_grants_thread_idx
+    ON covenant_grants (thread_id, capability);
@@ -0,0 +1,3 @@
+-- Structured form of each outcome's evidence references: a JSON array of
+-- {"kind": "test" | "artifact" | "url" | "unresolved", "ref": "..."} objects.
+ALTER TABLE outcomes ADD COLUMN evidence TEXT NOT NULL DEFAULT '[]';
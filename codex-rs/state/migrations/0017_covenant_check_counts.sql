@@ -0,0 +1,10 @@
+-- How often enforcement checked each capability in each scope, per UTC day.
+-- `day` is the start of the day in seconds since the epoch.
+CREATE TABLE IF NOT EXISTS covenant_check_counts (
+    day INTEGER NOT NULL,
+    scope TEXT NOT NULL,
+    capability TEXT NOT NULL,
+    allowed INTEGER NOT NULL DEFAULT 0,
+    denied INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, scope, capability)
+);
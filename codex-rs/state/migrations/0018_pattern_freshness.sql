@@ -0,0 +1,4 @@
+-- When a pattern was last re-confirmed as still holding, and when it was
+-- archived. Archived patterns are kept for review history but never matched.
+ALTER TABLE patterns ADD COLUMN confirmed_at INTEGER;
+ALTER TABLE patterns ADD COLUMN archived_at INTEGER;
@@ -0,0 +1,4 @@
+-- Free-form tags on covenant events, carried into the patterns compiled from
+-- them, as JSON arrays of strings.
+ALTER TABLE events ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE patterns ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
@@ -0,0 +1,3 @@
+-- The events that contradicted a live pattern's outcome, most informative
+-- first, as a JSON array of {event_id, outcome, resolved_at, evidence_refs}.
+ALTER TABLE patterns ADD COLUMN counterexamples TEXT NOT NULL DEFAULT '[]';
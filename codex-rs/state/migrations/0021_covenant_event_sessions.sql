@@ -0,0 +1,4 @@
+-- The session an event was captured in, when it was captured from inside
+-- one: the thread id and the path of its rollout file.
+ALTER TABLE events ADD COLUMN session_id TEXT;
+ALTER TABLE events ADD COLUMN rollout_path TEXT;
@@ -0,0 +1,5 @@
+-- Reported outcomes of applying a live pattern's best response: how many
+-- agreed with the pattern and how many contradicted it. Recompiling a pattern
+-- keeps both counts.
+ALTER TABLE patterns ADD COLUMN supports INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE patterns ADD COLUMN contradictions INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Covenant scope each live pattern was stored in. Patterns stored before
+-- scopes partitioned the store belong to the default scope.
+ALTER TABLE patterns ADD COLUMN scope TEXT NOT NULL DEFAULT 'default';
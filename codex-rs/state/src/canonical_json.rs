@@ -0,0 +1,150 @@
+//! RFC 8785 (JCS) canonical JSON for persisted records.
+//!
+//! Events, patterns, and log lines are written in canonical form so the same
+//! record serializes to the same bytes on every run, whatever order its
+//! struct fields or maps are in. That keeps files diffable and makes hashes
+//! and deduplication over the serialized bytes reliable.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Serialize `value` as canonical JSON, like [`canonical_json`].
+pub fn to_canonical_json<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    Ok(canonical_json(&serde_json::to_value(value)?))
+}
+
+/// Serialize `value` as RFC 8785 (JCS) canonical JSON: no whitespace, object
+/// keys sorted by UTF-16 code units, and numbers in ECMAScript form.
+pub fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+        Value::Number(number) => out.push_str(&canonical_number(number)),
+        // serde_json escapes strings exactly as JCS requires.
+        Value::String(_) => out.push_str(&value.to_string()),
+        Value::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            let mut entries = fields.iter().collect::<Vec<_>>();
+            entries.sort_by(|(left, _), (right, _)| left.encode_utf16().cmp(right.encode_utf16()));
+            out.push('{');
+            for (index, (key, item)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(item, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// ECMAScript `Number.prototype.toString` for finite numbers.
+fn canonical_number(number: &serde_json::Number) -> String {
+    if number.is_i64() || number.is_u64() {
+        return number.to_string();
+    }
+    let Some(value) = number.as_f64() else {
+        return number.to_string();
+    };
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    // `{:e}` gives the shortest round-trip digits, as in `1.25e-7`.
+    let formatted = format!("{:e}", value.abs());
+    let Some((mantissa, exponent)) = formatted.split_once('e') else {
+        return number.to_string();
+    };
+    let digits = mantissa.replace('.', "");
+    let Ok(exponent) = exponent.parse::<i32>() else {
+        return number.to_string();
+    };
+    let length = digits.len() as i32;
+    let point = exponent + 1;
+    let body = if length <= point && point <= 21 {
+        format!("{digits}{}", "0".repeat((point - length) as usize))
+    } else if 0 < point && point <= 21 {
+        let (whole, fraction) = digits.split_at(point as usize);
+        format!("{whole}.{fraction}")
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{digits}", "0".repeat((-point) as usize))
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let fraction = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{rest}")
+        };
+        let sign = if point - 1 < 0 { "-" } else { "+" };
+        format!("{first}{fraction}e{sign}{}", (point - 1).abs())
+    };
+    if value < 0.0 {
+        format!("-{body}")
+    } else {
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    #[test]
+    fn canonical_json_sorts_keys_and_formats_numbers() {
+        let value: Value = serde_json::from_str(
+            r#"{"b": [1.5, 1e21, 0.000001, 1e-7, -0.0, 100], "a": {"z": "\u0007é", "y": null}}"#,
+        )
+        .expect("parse value");
+
+        assert_eq!(
+            canonical_json(&value),
+            r#"{"a":{"y":null,"z":"\u0007é"},"b":[1.5,1e+21,0.000001,1e-7,0,100]}"#
+        );
+    }
+
+    #[test]
+    fn records_serialize_the_same_whatever_their_field_and_map_order() {
+        #[derive(Serialize)]
+        struct Record {
+            weight: f64,
+            id: &'static str,
+            labels: HashMap<&'static str, u32>,
+        }
+        let record = |pairs: &[(&'static str, u32)]| Record {
+            weight: 2.0,
+            id: "e1",
+            labels: pairs.iter().copied().collect(),
+        };
+
+        let forward =
+            to_canonical_json(&record(&[("b", 2), ("a", 1), ("c", 3)])).expect("serialize record");
+        let backward =
+            to_canonical_json(&record(&[("c", 3), ("a", 1), ("b", 2)])).expect("serialize record");
+
+        assert_eq!(
+            (forward.as_str(), backward.as_str()),
+            (
+                r#"{"id":"e1","labels":{"a":1,"b":2,"c":3},"weight":2}"#,
+                r#"{"id":"e1","labels":{"a":1,"b":2,"c":3},"weight":2}"#,
+            )
+        );
+    }
+}
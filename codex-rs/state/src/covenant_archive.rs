@@ -0,0 +1,392 @@
+//! Compressed monthly archives of resolved covenant events.
+//!
+//! Resolved events moved out of the state database are appended to
+//! `CODEX_HOME/covenant/archive/events-YYYY-MM.jsonl.zst`, one JSON event per
+//! line, grouped by the month the event was created. Every append writes a new
+//! zstd frame, so existing frames are never rewritten. Artifact files that no
+//! remaining event is attached to move to `covenant/archive/artifacts/`.
+//! With a [`StoreCipher`], each line is sealed before it is compressed.
+
+use crate::CovenantEvent;
+use crate::canonical_json::to_canonical_json;
+use crate::store_cipher::StoreCipher;
+use crate::store_cipher::open_line;
+use anyhow::Context;
+use chrono::DateTime;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+const ARCHIVE_FILE_PREFIX: &str = "events-";
+const ARCHIVE_FILE_SUFFIX: &str = ".jsonl.zst";
+const ARCHIVE_COMPRESSION_LEVEL: i32 = 3;
+
+/// Directory holding content-addressed evidence files for active events.
+pub fn covenant_artifacts_dir(codex_home: &Path) -> PathBuf {
+    codex_home.join("covenant").join("artifacts")
+}
+
+/// Directory holding archived events and the artifacts they carried.
+pub fn covenant_archive_dir(codex_home: &Path) -> PathBuf {
+    codex_home.join("covenant").join("archive")
+}
+
+/// Limits that decide which resolved events leave the active store.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CovenantRetention {
+    /// Resolved events created more than this many seconds ago are archived.
+    pub max_event_age_secs: Option<i64>,
+    /// Oldest resolved events are archived until the store is estimated to
+    /// fit in this many bytes.
+    pub max_store_bytes: Option<u64>,
+}
+
+impl CovenantRetention {
+    /// How many of the oldest resolved events to archive.
+    ///
+    /// `closed_created_at` holds the creation time of every resolved event,
+    /// oldest first. The store currently holds `store_events` events of any
+    /// status in `store_bytes` bytes; the size limit assumes each event takes
+    /// an equal share of that.
+    pub fn events_to_archive(
+        &self,
+        closed_created_at: &[i64],
+        now: i64,
+        store_events: usize,
+        store_bytes: u64,
+    ) -> usize {
+        let expired = self.max_event_age_secs.map_or(0, |max_age| {
+            closed_created_at
+                .iter()
+                .take_while(|created_at| now.saturating_sub(**created_at) > max_age)
+                .count()
+        });
+        let oversized = match self.max_store_bytes {
+            Some(max_bytes) if store_bytes > max_bytes && store_events > 0 => {
+                let bytes_per_event = store_bytes.div_ceil(store_events as u64).max(1);
+                let excess = store_bytes - max_bytes;
+                usize::try_from(excess.div_ceil(bytes_per_event)).unwrap_or(usize::MAX)
+            }
+            _ => 0,
+        };
+        expired.max(oversized).min(closed_created_at.len())
+    }
+}
+
+/// Append `events` to their monthly archive files, returning the files written.
+/// The files, and the directory entries of new ones, are synced to disk
+/// before this returns.
+pub fn archive_covenant_events(
+    archive_dir: &Path,
+    events: &[CovenantEvent],
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut by_month: BTreeMap<String, Vec<&CovenantEvent>> = BTreeMap::new();
+    for event in events {
+        by_month
+            .entry(archive_month(event.created_at))
+            .or_default()
+            .push(event);
+    }
+    if by_month.is_empty() {
+        return Ok(Vec::new());
+    }
+    let new_dir = !archive_dir.exists();
+    std::fs::create_dir_all(archive_dir)
+        .with_context(|| format!("failed to create {}", archive_dir.display()))?;
+
+    let mut written = Vec::with_capacity(by_month.len());
+    let mut new_files = false;
+    for (month, events) in by_month {
+        let mut lines = String::new();
+        for event in events {
+            let line = to_canonical_json(event)?;
+            match cipher {
+                Some(cipher) => lines.push_str(&cipher.seal_line(line.as_str())?),
+                None => lines.push_str(&line),
+            }
+            lines.push('\n');
+        }
+        let compressed = zstd::encode_all(lines.as_bytes(), ARCHIVE_COMPRESSION_LEVEL)?;
+        let path = archive_dir.join(format!("{ARCHIVE_FILE_PREFIX}{month}{ARCHIVE_FILE_SUFFIX}"));
+        new_files |= !path.exists();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| {
+                file.write_all(&compressed)?;
+                file.sync_all()
+            })
+            .with_context(|| format!("failed to append to {}", path.display()))?;
+        written.push(path);
+    }
+    // The caller deletes the events from the store next, so new files must
+    // survive a crash as well as their contents.
+    if new_files {
+        sync_dir(archive_dir)?;
+    }
+    if new_dir && let Some(parent) = archive_dir.parent() {
+        sync_dir(parent)?;
+    }
+    Ok(written)
+}
+
+/// Flush the entries of `dir` to disk. Directories cannot be synced on
+/// Windows, where this does nothing.
+fn sync_dir(dir: &Path) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        std::fs::File::open(dir)
+            .and_then(|dir| dir.sync_all())
+            .with_context(|| format!("failed to sync {}", dir.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// An archived event and the line of the archive file it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedCovenantEvent {
+    pub path: PathBuf,
+    /// 1-based line within the decompressed archive.
+    pub line: usize,
+    pub event: CovenantEvent,
+}
+
+/// Every archived event, ordered like the active store lists them. A missing
+/// archive directory is an empty archive. Sealed lines need `cipher`.
+pub fn read_archived_covenant_events(
+    archive_dir: &Path,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<Vec<CovenantEvent>> {
+    Ok(read_archived_covenant_event_entries(archive_dir, cipher)?
+        .into_iter()
+        .map(|entry| entry.event)
+        .collect())
+}
+
+/// Like [`read_archived_covenant_events`], but keeps where each event was read
+/// from.
+pub fn read_archived_covenant_event_entries(
+    archive_dir: &Path,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<Vec<ArchivedCovenantEvent>> {
+    let entries = match std::fs::read_dir(archive_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", archive_dir.display()));
+        }
+    };
+    let mut paths = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(ARCHIVE_FILE_PREFIX) && name.ends_with(ARCHIVE_FILE_SUFFIX)
+                })
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut events: Vec<ArchivedCovenantEvent> = Vec::new();
+    for path in paths {
+        let compressed =
+            std::fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let contents = zstd::decode_all(compressed.as_slice())
+            .with_context(|| format!("failed to decompress {}", path.display()))?;
+        let contents = String::from_utf8(contents)
+            .with_context(|| format!("{} is not valid UTF-8", path.display()))?;
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = open_line(line, cipher)
+                .and_then(|line| Ok(serde_json::from_str(&line)?))
+                .with_context(|| format!("failed to parse {}:{}", path.display(), index + 1))?;
+            events.push(ArchivedCovenantEvent {
+                path: path.clone(),
+                line: index + 1,
+                event,
+            });
+        }
+    }
+    events.sort_by(|left, right| {
+        left.event
+            .created_at
+            .cmp(&right.event.created_at)
+            .then_with(|| left.event.id.cmp(&right.event.id))
+    });
+    Ok(events)
+}
+
+/// Move artifact files whose hash is not in `attached` into the archive,
+/// returning how many moved. Partially written files are left alone.
+pub fn archive_unattached_artifacts(
+    artifacts_dir: &Path,
+    archive_dir: &Path,
+    attached: &HashSet<String>,
+) -> anyhow::Result<usize> {
+    let entries = match std::fs::read_dir(artifacts_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", artifacts_dir.display()));
+        }
+    };
+    let destination_dir = archive_dir.join("artifacts");
+    let mut moved = 0;
+    for entry in entries.filter_map(Result::ok) {
+        let file_name = entry.file_name();
+        let Some(sha256) = file_name.to_str() else {
+            continue;
+        };
+        if sha256.ends_with(".tmp") || attached.contains(sha256) {
+            continue;
+        }
+        std::fs::create_dir_all(&destination_dir)
+            .with_context(|| format!("failed to create {}", destination_dir.display()))?;
+        let destination = destination_dir.join(sha256);
+        std::fs::rename(entry.path(), &destination)
+            .with_context(|| format!("failed to move artifact to {}", destination.display()))?;
+        moved += 1;
+    }
+    Ok(moved)
+}
+
+/// Total size of the files under `dir`, or zero when it does not exist.
+pub fn directory_size(dir: &Path) -> anyhow::Result<u64> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", dir.display())),
+    };
+    let mut total = 0;
+    for entry in entries.filter_map(Result::ok) {
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            total += metadata.len();
+        }
+    }
+    Ok(total)
+}
+
+fn archive_month(created_at: i64) -> String {
+    DateTime::from_timestamp(created_at, 0).map_or_else(
+        || "unknown".to_string(),
+        |time| time.format("%Y-%m").to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn event(id: &str, created_at: i64) -> CovenantEvent {
+        CovenantEvent {
+            id: id.to_string(),
+            created_at,
+            description: format!("event {id}"),
+            domain_signature: "[]".to_string(),
+            intent: None,
+            hypotheses: Vec::new(),
+            tests: Vec::new(),
+            outcomes: Vec::new(),
+            tags: Vec::new(),
+            session_id: None,
+            rollout_path: None,
+        }
+    }
+
+    #[test]
+    fn retention_archives_the_larger_of_the_age_and_size_cuts() {
+        let closed = [100, 200, 300, 400];
+        let by_age = CovenantRetention {
+            max_event_age_secs: Some(250),
+            max_store_bytes: None,
+        };
+        let by_size = CovenantRetention {
+            max_event_age_secs: None,
+            max_store_bytes: Some(700),
+        };
+        let both = CovenantRetention {
+            max_event_age_secs: Some(250),
+            max_store_bytes: Some(100),
+        };
+
+        assert_eq!(
+            [
+                by_age.events_to_archive(&closed, 500, 5, 1_000),
+                by_size.events_to_archive(&closed, 500, 5, 1_000),
+                both.events_to_archive(&closed, 500, 5, 1_000),
+                CovenantRetention::default().events_to_archive(&closed, 500, 5, 1_000),
+            ],
+            [2, 2, 4, 0]
+        );
+    }
+
+    #[test]
+    fn archived_events_are_read_back_across_appends() {
+        let dir = std::env::temp_dir().join(format!("covenant-archive-{}", uuid::Uuid::new_v4()));
+        // 2026-01-02 and 2026-02-03 UTC.
+        let january = event("jan", 1_767_312_000);
+        let february = event("feb", 1_770_076_800);
+        let late_january = event("jan-2", 1_767_398_400);
+
+        let cipher = StoreCipher::generate();
+
+        let written = archive_covenant_events(&dir, &[january.clone(), february.clone()], None)
+            .expect("archive events");
+        // Encryption turned on later only seals what is appended from then on.
+        archive_covenant_events(&dir, std::slice::from_ref(&late_january), Some(&cipher))
+            .expect("append events");
+
+        assert_eq!(
+            written,
+            vec![
+                dir.join("events-2026-01.jsonl.zst"),
+                dir.join("events-2026-02.jsonl.zst"),
+            ]
+        );
+        assert_eq!(
+            read_archived_covenant_events(&dir, Some(&cipher)).expect("read archive"),
+            vec![january, late_january.clone(), february]
+        );
+        assert_eq!(
+            read_archived_covenant_events(&dir, None)
+                .map_err(|err| err.to_string())
+                .map(|events| events.len()),
+            Err(format!(
+                "failed to parse {}:2",
+                dir.join("events-2026-01.jsonl.zst").display()
+            ))
+        );
+        let january_archive = dir.join("events-2026-01.jsonl.zst");
+        assert_eq!(
+            read_archived_covenant_event_entries(&dir, Some(&cipher))
+                .expect("read archive")
+                .into_iter()
+                .map(|entry| (entry.path, entry.line, entry.event.id))
+                .collect::<Vec<_>>(),
+            vec![
+                (january_archive.clone(), 1, "jan".to_string()),
+                (january_archive, 2, late_january.id),
+                (dir.join("events-2026-02.jsonl.zst"), 1, "feb".to_string()),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
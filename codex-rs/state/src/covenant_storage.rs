@@ -0,0 +1,131 @@
+//! Where covenant events, patterns, and the audit log are stored.
+//!
+//! By default they live in the state database under `CODEX_HOME`, next to
+//! thread metadata. A workspace store keeps them in the repository instead,
+//! under `<repo>/.codex/covenant/`, so they travel with the project: the state
+//! database sits in that directory, with `artifacts/` and `archive/` beside it.
+
+use crate::covenant_archive::covenant_archive_dir;
+use crate::covenant_archive::covenant_artifacts_dir;
+use crate::pattern_namespace::git_output;
+use crate::runtime::state_db_path;
+use serde::Deserialize;
+use serde::Serialize;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Directory under the repository root that holds a workspace store.
+pub const WORKSPACE_STORE_DIR: &str = ".codex";
+
+/// Which covenant store a command or session uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum CovenantStorage {
+    /// The state database in `CODEX_HOME`.
+    #[default]
+    Home,
+    /// `<repo>/.codex/covenant/` in the repository containing the working
+    /// directory.
+    Workspace,
+}
+
+impl CovenantStorage {
+    pub fn is_home(&self) -> bool {
+        *self == Self::Home
+    }
+}
+
+/// Resolved locations of one covenant store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CovenantStorePaths {
+    /// `CODEX_HOME`, which the redaction config is read from for either store.
+    pub codex_home: PathBuf,
+    /// Root that `covenant/artifacts` and `covenant/archive` are resolved
+    /// against: `CODEX_HOME`, or `<repo>/.codex` for a workspace store.
+    pub root: PathBuf,
+    /// Directory holding the state database and the compiled pattern log.
+    pub db_dir: PathBuf,
+}
+
+impl CovenantStorePaths {
+    /// Resolve `storage` for a command run from `cwd`. A workspace store
+    /// outside of a git repository is rooted at `cwd` itself.
+    pub fn resolve(storage: CovenantStorage, codex_home: &Path, cwd: &Path) -> Self {
+        match storage {
+            CovenantStorage::Home => Self {
+                codex_home: codex_home.to_path_buf(),
+                root: codex_home.to_path_buf(),
+                db_dir: codex_home.to_path_buf(),
+            },
+            CovenantStorage::Workspace => {
+                let root = workspace_root(cwd).join(WORKSPACE_STORE_DIR);
+                Self {
+                    codex_home: codex_home.to_path_buf(),
+                    db_dir: root.join("covenant"),
+                    root,
+                }
+            }
+        }
+    }
+
+    pub fn db_path(&self) -> PathBuf {
+        state_db_path(&self.db_dir)
+    }
+
+    pub fn artifacts_dir(&self) -> PathBuf {
+        covenant_artifacts_dir(&self.root)
+    }
+
+    pub fn archive_dir(&self) -> PathBuf {
+        covenant_archive_dir(&self.root)
+    }
+}
+
+/// Top level of the git repository containing `cwd`, or `cwd` outside of one.
+pub fn workspace_root(cwd: &Path) -> PathBuf {
+    git_output(cwd, &["rev-parse", "--show-toplevel"])
+        .map_or_else(|| cwd.to_path_buf(), PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn workspace_stores_live_under_the_workspace_codex_dir() {
+        let codex_home = Path::new("/home/dev/.codex");
+        let cwd = std::env::temp_dir();
+        let cwd = cwd.as_path();
+
+        let home = CovenantStorePaths::resolve(CovenantStorage::Home, codex_home, cwd);
+        let workspace = CovenantStorePaths::resolve(CovenantStorage::Workspace, codex_home, cwd);
+
+        assert_eq!(
+            (
+                home.clone(),
+                home.artifacts_dir(),
+                workspace.clone(),
+                workspace.db_path(),
+                workspace.artifacts_dir(),
+                workspace.archive_dir(),
+            ),
+            (
+                CovenantStorePaths {
+                    codex_home: codex_home.to_path_buf(),
+                    root: codex_home.to_path_buf(),
+                    db_dir: codex_home.to_path_buf(),
+                },
+                codex_home.join("covenant").join("artifacts"),
+                CovenantStorePaths {
+                    codex_home: codex_home.to_path_buf(),
+                    root: cwd.join(".codex"),
+                    db_dir: cwd.join(".codex").join("covenant"),
+                },
+                state_db_path(&cwd.join(".codex").join("covenant")),
+                cwd.join(".codex").join("covenant").join("artifacts"),
+                cwd.join(".codex").join("covenant").join("archive"),
+            )
+        );
+    }
+}
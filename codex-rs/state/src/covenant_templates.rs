@@ -0,0 +1,89 @@
+//! Named capability lists for covenant scopes.
+//!
+//! A scope in `covenant.json` can name a template with `"template"` instead
+//! of spelling out every capability. Capabilities it lists as well are added
+//! after the template's.
+
+/// Each template and the capabilities it grants, narrowest first.
+pub const CAPABILITY_TEMPLATES: &[(&str, &[&str])] = &[
+    ("read-only", &["event.log"]),
+    (
+        "standard-dev",
+        &[
+            "proposal.exec_command",
+            "proposal.apply_patch",
+            "event.log",
+            "event.predict",
+            "event.test",
+            "event.resolve",
+            "event.attach",
+        ],
+    ),
+    (
+        "full-intervention",
+        &[
+            "proposal.exec_command",
+            "proposal.apply_patch",
+            "intervention.exec_approval",
+            "intervention.patch_approval",
+            "intervention.user_shell",
+            "event.log",
+            "event.predict",
+            "event.test",
+            "event.resolve",
+            "event.attach",
+            "patterns.add",
+        ],
+    ),
+];
+
+/// The template's capabilities followed by those in `extra` it does not
+/// already grant, or `None` for an unknown template.
+pub fn expand_capability_template(template: &str, extra: &[String]) -> Option<Vec<String>> {
+    let (_, capabilities) = CAPABILITY_TEMPLATES
+        .iter()
+        .find(|(name, _)| *name == template)?;
+    let mut expanded = capabilities
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    for capability in extra {
+        if !expanded.contains(capability) {
+            expanded.push(capability.clone());
+        }
+    }
+    Some(expanded)
+}
+
+/// The error for a scope naming a template that does not exist.
+pub fn unknown_template_message(scope: &str, template: &str) -> String {
+    let known = CAPABILITY_TEMPLATES
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("covenant scope '{scope}' uses unknown template '{template}' (known: {known})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn templates_expand_with_extra_capabilities_appended_once() {
+        assert_eq!(
+            (
+                expand_capability_template(
+                    "read-only",
+                    &["event.log".to_string(), "event.test".to_string()]
+                ),
+                expand_capability_template("admin", &[]),
+            ),
+            (
+                Some(vec!["event.log".to_string(), "event.test".to_string()]),
+                None
+            )
+        );
+    }
+}
@@ -0,0 +1,1819 @@
+//! `handshakeos-e`: record and manage HandshakeOS event knowledge in the
+//! covenant store. The binary parses its arguments into a command and hands it
+//! to [`run_main`]; `codex covenant batch` runs batch files through
+//! [`run_covenant_batch`].
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::CovenantEvidence;
+use crate::CovenantStorage;
+use crate::CovenantStorePaths;
+use crate::EvidenceKind;
+use crate::GLOBAL_PATTERN_NAMESPACE;
+use crate::Redactor;
+use crate::StoreCipher;
+use crate::covenant_artifacts_dir;
+use crate::expand_capability_template;
+use crate::normalize_event_tags;
+use crate::open_line;
+use crate::project_pattern_namespace;
+use crate::seal_text;
+use crate::to_canonical_json;
+use crate::unknown_template_message;
+use anyhow::Context;
+use chrono::Utc;
+use clap::Args;
+use clap::Parser;
+use clap::Subcommand;
+use codex_protocol::outcome::Outcome;
+use codex_protocol::test_status::TestStatus;
+use dirs::home_dir;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use sha2::Digest;
+use sha2::Sha256;
+use sqlx::Row;
+use sqlx::SqliteConnection;
+use sqlx::SqlitePool;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::sqlite::SqliteJournalMode;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::sqlite::SqliteSynchronous;
+use uuid::Uuid;
+
+#[derive(Debug, Parser)]
+#[command(name = "handshakeos-e")]
+#[command(about = "Record and manage HandshakeOS event knowledge")]
+struct Cli {
+    /// Covenant scope used to authorize command execution.
+    #[arg(long, default_value = "default")]
+    scope: String,
+
+    /// Actor identity written to the audit trail.
+    #[arg(long, default_value = "cli")]
+    actor: String,
+
+    /// Path to the SQLite database. Defaults to the database of the covenant
+    /// store chosen by `--store`.
+    #[arg(long)]
+    db: Option<PathBuf>,
+
+    /// Covenant store to use: `home` ($CODEX_HOME) or `workspace`
+    /// (`<repo>/.codex/covenant/`). Defaults to covenant.json's `storage`.
+    #[arg(long, value_enum)]
+    store: Option<CovenantStorage>,
+
+    /// Report failures on stderr as one JSON object with a stable `code`.
+    #[arg(long, global = true)]
+    json_errors: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// A command, or one line of a batch file: an object naming the command in
+/// `op` (`log`, `predict`, `test`, `resolve`, `attach`, or `patterns-add`)
+/// alongside its arguments, as in
+/// `{"op": "test", "event_id": "...", "hypothesis_id": "...", ...}`.
+#[derive(Debug, PartialEq, Subcommand, Deserialize)]
+#[serde(tag = "op", rename_all = "kebab-case")]
+enum Command {
+    /// Initialize covenant and domain tables.
+    #[serde(skip)]
+    Init(InitArgs),
+    /// Insert an event and optional intent.
+    Log(LogArgs),
+    /// Attach hypotheses to an existing event.
+    Predict(PredictArgs),
+    /// Attach test results to an existing hypothesis.
+    Test(TestArgs),
+    /// Resolve an event using evidence references.
+    Resolve(ResolveArgs),
+    /// Store a file as evidence for an event.
+    Attach(AttachArgs),
+    /// Create or update a reusable pattern definition.
+    #[command(name = "patterns-add")]
+    PatternsAdd(PatternsAddArgs),
+    /// Apply a JSONL file of commands in one transaction, each checked and
+    /// audited as if it were run on its own.
+    #[serde(skip)]
+    Batch(BatchArgs),
+}
+
+#[derive(Debug, PartialEq, Args)]
+struct InitArgs {
+    /// Covenant version to seed if missing.
+    #[arg(long, default_value = "1")]
+    covenant_version: String,
+}
+
+#[derive(Debug, PartialEq, Args, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LogArgs {
+    #[arg(long)]
+    event_id: Option<String>,
+    #[arg(long)]
+    description: String,
+    #[arg(long)]
+    domain_signature: String,
+    #[arg(long)]
+    intent_goal: Option<String>,
+    #[arg(long)]
+    intent_constraints: Option<String>,
+    #[arg(long)]
+    intent_success_signal: Option<String>,
+    #[arg(long)]
+    intent_confidence: Option<f64>,
+    /// Label the event, as in `--tag infra --tag flaky-test`. Tags use
+    /// letters, digits, `-` and `_`, and are stored lowercase.
+    #[arg(long = "tag", value_name = "TAG")]
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Args, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PredictArgs {
+    /// Event id, or a prefix of one that no other event shares.
+    #[arg(long, required_unless_present = "event_match")]
+    event_id: Option<String>,
+    /// Words that all appear in the description of exactly one event, such
+    /// as `"disk full"`, instead of `--event-id`.
+    #[arg(long, conflicts_with = "event_id")]
+    event_match: Option<String>,
+    #[arg(long)]
+    model_type: String,
+    #[arg(long)]
+    probability: f64,
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default)]
+    falsifiers: Vec<String>,
+    #[arg(long)]
+    domain_signature: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Args, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestArgs {
+    /// Event id, or a prefix of one that no other event shares.
+    #[arg(long, required_unless_present = "event_match")]
+    event_id: Option<String>,
+    /// Words that all appear in the description of exactly one event, such
+    /// as `"disk full"`, instead of `--event-id`.
+    #[arg(long, conflicts_with = "event_id")]
+    event_match: Option<String>,
+    #[arg(long)]
+    hypothesis_id: String,
+    #[arg(long)]
+    description: String,
+    /// `pass`, `fail`, `inconclusive`, `skipped`, or `error`, or a synonym such
+    /// as `passed` or `not run`.
+    #[arg(long)]
+    #[serde(deserialize_with = "deserialize_test_status")]
+    result: TestStatus,
+    #[arg(long)]
+    evidence_ref: String,
+}
+
+/// Batch lines take the same spellings as `--result`.
+fn deserialize_test_status<'de, D>(deserializer: D) -> Result<TestStatus, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    TestStatus::parse(&text).map_err(serde::de::Error::custom)
+}
+
+#[derive(Debug, PartialEq, Args, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResolveArgs {
+    /// Event id, or a prefix of one that no other event shares.
+    #[arg(long, required_unless_present = "event_match")]
+    event_id: Option<String>,
+    /// Words that all appear in the description of exactly one event, such
+    /// as `"disk full"`, instead of `--event-id`.
+    #[arg(long, conflicts_with = "event_id")]
+    event_match: Option<String>,
+    #[arg(long)]
+    summary: String,
+    /// Evidence references: test ids or descriptions of the event, artifact
+    /// ids (`sha256:<hex>`) attached to the event first, or URLs.
+    #[arg(long, visible_alias = "evidence", value_delimiter = ',')]
+    #[serde(default)]
+    evidence_refs: Vec<String>,
+    /// Fail instead of warning when a reference matches none of those.
+    #[arg(long)]
+    #[serde(default)]
+    strict: bool,
+    /// How the event turned out (success, failure, mixed). Inferred from the
+    /// summary when omitted.
+    #[arg(long)]
+    outcome: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Args, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AttachArgs {
+    /// Event id, or a prefix of one that no other event shares.
+    #[arg(long, required_unless_present = "event_match")]
+    event_id: Option<String>,
+    /// Words that all appear in the description of exactly one event, such
+    /// as `"disk full"`, instead of `--event-id`.
+    #[arg(long, conflicts_with = "event_id")]
+    event_match: Option<String>,
+    /// File to store under `$CODEX_HOME/covenant/artifacts/`, keyed by its SHA-256.
+    #[arg(long)]
+    file: PathBuf,
+}
+
+#[derive(Debug, PartialEq, Args, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatternsAddArgs {
+    #[arg(long)]
+    pattern_id: Option<String>,
+    #[arg(long)]
+    trigger: String,
+    #[arg(long)]
+    invariant: String,
+    #[arg(long)]
+    counterexample: String,
+    #[arg(long)]
+    best_response: String,
+    #[arg(long)]
+    domain_signature: String,
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default)]
+    evidence_refs: Vec<String>,
+    /// Store the pattern in the global namespace instead of the current project.
+    #[arg(long)]
+    #[serde(default)]
+    global: bool,
+    /// Mark the best response as one that made things worse, so matches warn
+    /// against it.
+    #[arg(long)]
+    #[serde(default)]
+    anti_pattern: bool,
+    /// Update the pattern with this id even when it belongs to another
+    /// covenant scope, leaving it in that scope. New patterns are always
+    /// stored in `--scope`.
+    #[arg(long)]
+    #[serde(default)]
+    all_scopes: bool,
+}
+
+#[derive(Debug, PartialEq, Args)]
+struct BatchArgs {
+    /// JSONL file with one command object per line.
+    #[arg(long)]
+    file: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct Covenant {
+    version: String,
+    #[serde(default)]
+    storage: CovenantStorage,
+    scopes: Vec<CovenantScope>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CovenantScope {
+    name: String,
+    #[serde(default)]
+    template: Option<String>,
+    #[serde(default)]
+    capabilities: Vec<String>,
+}
+
+/// Stable failure codes reported by `--json-errors`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ErrorCode {
+    /// The arguments did not parse.
+    Usage,
+    /// The covenant scope does not grant the command's capability.
+    ScopeDenied,
+    /// No `covenant.json` was found from the working directory up.
+    CovenantNotFound,
+    EventNotFound,
+    /// An event id prefix or `--event-match` fits more than one event.
+    AmbiguousEvent,
+    HypothesisNotFound,
+    /// `patterns-add` names a pattern of another scope without `--all-scopes`.
+    PatternInOtherScope,
+    /// An evidence reference names an artifact the event does not have.
+    ArtifactNotAttached,
+    /// The artifact is recorded but its file is gone.
+    ArtifactMissing,
+    MissingEvidence,
+    /// With `resolve --strict`, an evidence reference matched nothing.
+    UnresolvedEvidence,
+    /// A file or value could not be parsed.
+    ParseError,
+    Io,
+    Database,
+    Internal,
+}
+
+/// A failure the caller can branch on, with the field and id it concerns.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct CliError {
+    code: ErrorCode,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+}
+
+impl CliError {
+    fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            field: None,
+            id: None,
+        }
+    }
+
+    fn for_field(mut self, field: &'static str, id: impl Into<String>) -> Self {
+        self.field = Some(field);
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Describe any failure, keeping the code of a [`CliError`] anywhere in
+    /// the chain and classifying the rest by their source.
+    fn from_anyhow(err: &anyhow::Error) -> Self {
+        let message = format!("{err:#}");
+        if let Some(cli_error) = err.downcast_ref::<CliError>() {
+            return Self {
+                message,
+                ..cli_error.clone()
+            };
+        }
+        let code = err
+            .chain()
+            .find_map(|cause| {
+                if cause.is::<serde_json::Error>() {
+                    Some(ErrorCode::ParseError)
+                } else if cause.is::<sqlx::Error>() || cause.is::<sqlx::migrate::MigrateError>() {
+                    Some(ErrorCode::Database)
+                } else if cause.is::<std::io::Error>() {
+                    Some(ErrorCode::Io)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(ErrorCode::Internal);
+        Self::new(code, message)
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl Covenant {
+    fn allows(&self, scope: &str, capability: &str) -> bool {
+        self.scopes.iter().any(|entry| {
+            entry.name == scope
+                && entry
+                    .capabilities
+                    .iter()
+                    .any(|capability_entry| capability_entry == capability)
+        })
+    }
+}
+
+/// Run `handshakeos-e` with the process arguments, exiting on usage errors.
+pub async fn run_main() -> anyhow::Result<()> {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(err) if err.use_stderr() && std::env::args().any(|arg| arg == "--json-errors") => {
+            let error = CliError::new(ErrorCode::Usage, err.to_string().trim());
+            eprintln!("{}", serde_json::to_string(&error)?);
+            std::process::exit(err.exit_code());
+        }
+        Err(err) => err.exit(),
+    };
+    let json_errors = cli.json_errors;
+    match run(cli).await {
+        Err(err) if json_errors => {
+            eprintln!("{}", serde_json::to_string(&CliError::from_anyhow(&err))?);
+            std::process::exit(1);
+        }
+        result => result,
+    }
+}
+
+/// Where and as whom [`run_covenant_batch`] applies a batch file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CovenantBatchOptions {
+    /// JSONL file with one command object per line.
+    pub file: PathBuf,
+    /// Covenant scope used to authorize each command.
+    pub scope: String,
+    /// Actor identity written to the audit trail.
+    pub actor: String,
+    /// Covenant store to use; `None` follows covenant.json.
+    pub store: Option<CovenantStorage>,
+}
+
+/// Apply a batch file exactly as `handshakeos-e batch --file` does: in one
+/// transaction, each command checked and audited as if it were run alone.
+pub async fn run_covenant_batch(options: CovenantBatchOptions) -> anyhow::Result<()> {
+    run(Cli {
+        scope: options.scope,
+        actor: options.actor,
+        db: None,
+        store: options.store,
+        json_errors: false,
+        command: Command::Batch(BatchArgs { file: options.file }),
+    })
+    .await
+}
+
+/// Where, as whom, and to which event [`run_covenant_attach`] stores a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CovenantAttachOptions {
+    /// Event id, or a prefix of one that no other event shares.
+    pub event_id: Option<String>,
+    /// Words that all appear in the description of exactly one event.
+    pub event_match: Option<String>,
+    /// File to store as evidence for the event.
+    pub file: PathBuf,
+    /// Covenant scope used to authorize the attachment.
+    pub scope: String,
+    /// Actor identity written to the audit trail.
+    pub actor: String,
+    /// Covenant store to use; `None` follows covenant.json.
+    pub store: Option<CovenantStorage>,
+}
+
+/// Store a file as evidence for an event exactly as `handshakeos-e attach`
+/// does.
+pub async fn run_covenant_attach(options: CovenantAttachOptions) -> anyhow::Result<()> {
+    run(Cli {
+        scope: options.scope,
+        actor: options.actor,
+        db: None,
+        store: options.store,
+        json_errors: false,
+        command: Command::Attach(AttachArgs {
+            event_id: options.event_id,
+            event_match: options.event_match,
+            file: options.file,
+        }),
+    })
+    .await
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    let codex_home = default_codex_home();
+    let cwd = std::env::current_dir()?;
+    let covenant = load_covenant(cwd.as_path()).await?;
+    let store = CovenantStorePaths::resolve(
+        cli.store.unwrap_or(covenant.storage),
+        codex_home.as_path(),
+        cwd.as_path(),
+    );
+    let db_path = cli.db.clone().unwrap_or_else(|| store.db_path());
+    let redactor = Redactor::load(codex_home.as_path()).unwrap_or_else(|err| {
+        eprintln!("warning: failed to load redaction config, using defaults: {err}");
+        Redactor::default()
+    });
+    let cipher = StoreCipher::load()?;
+    let text = StoredText {
+        redactor: &redactor,
+        cipher: cipher.as_ref(),
+    };
+    let pool = open_sqlite_pool(db_path.as_path()).await?;
+    // Apply the runtime migrations first so the covenant tables match what the
+    // capture tool writes, then add the tables only this binary uses.
+    sqlx::migrate!("./migrations").run(&pool).await?;
+    ensure_schema(&pool).await?;
+
+    let (commands, batch_file) = match cli.command {
+        Command::Batch(args) => (read_batch(args.file.as_path())?, Some(args.file)),
+        command => (vec![(1, command)], None),
+    };
+
+    let actor = text.seal(&cli.actor)?;
+    let covenant_version = covenant.version.clone();
+    // Every command's audit entry and writes commit together, so a failure
+    // part way through leaves none of them behind. A refusal commits its
+    // audit entry alone.
+    let mut tx = pool.begin().await?;
+    let mut messages = Vec::with_capacity(commands.len());
+    for (line, mut command) in commands {
+        let resolved = resolve_event_selector(&mut tx, &mut command, text.cipher).await;
+        match &batch_file {
+            Some(file) => resolved
+                .with_context(|| format!("{}:{line}: nothing was applied", file.display()))?,
+            None => resolved?,
+        }
+        let (capability, event_ref) = command_capability(&command);
+        let allowed = covenant.allows(cli.scope.as_str(), capability);
+        let action_type = if allowed {
+            capability.to_string()
+        } else {
+            format!("{capability}:denied")
+        };
+        if !allowed {
+            drop(tx);
+            tx = pool.begin().await?;
+        }
+        insert_audit_action(
+            &mut tx,
+            actor.as_str(),
+            action_type.as_str(),
+            cli.scope.as_str(),
+            covenant_version.as_str(),
+            event_ref,
+            None,
+        )
+        .await?;
+        record_covenant_check(&mut tx, cli.scope.as_str(), capability, allowed).await?;
+        if !allowed {
+            tx.commit().await?;
+            let error = CliError::new(
+                ErrorCode::ScopeDenied,
+                format!(
+                    "covenant scope '{}' disallows capability '{capability}'",
+                    cli.scope
+                ),
+            )
+            .for_field("scope", cli.scope);
+            return Err(match &batch_file {
+                Some(file) => anyhow::Error::new(error)
+                    .context(format!("{}:{line}: nothing was applied", file.display())),
+                None => error.into(),
+            });
+        }
+
+        let message = run_command(
+            &mut tx,
+            command,
+            cli.scope.as_str(),
+            &text,
+            store.root.as_path(),
+            cwd.as_path(),
+        )
+        .await;
+        let message = match &batch_file {
+            Some(file) => message
+                .with_context(|| format!("{}:{line}: nothing was applied", file.display()))?,
+            None => message?,
+        };
+        messages.push(message);
+    }
+    tx.commit().await?;
+    for message in messages {
+        println!("{message}");
+    }
+    Ok(())
+}
+
+/// The capability `command` needs and the event it touches, if any.
+fn command_capability(command: &Command) -> (&'static str, Option<&str>) {
+    match command {
+        Command::Init(_) => ("system.init", None),
+        Command::Log(_) => ("event.log", None),
+        Command::Predict(args) => ("event.predict", args.event_id.as_deref()),
+        Command::Test(args) => ("event.test", args.event_id.as_deref()),
+        Command::Resolve(args) => ("event.resolve", args.event_id.as_deref()),
+        Command::Attach(args) => ("event.attach", args.event_id.as_deref()),
+        Command::PatternsAdd(_) => ("patterns.add", None),
+        Command::Batch(_) => ("system.batch", None),
+    }
+}
+
+/// Parse a batch file into its commands, each with its line number. Blank
+/// lines are skipped.
+fn read_batch(path: &Path) -> anyhow::Result<Vec<(usize, Command)>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    parse_batch(&contents).with_context(|| format!("invalid batch file {}", path.display()))
+}
+
+fn parse_batch(contents: &str) -> anyhow::Result<Vec<(usize, Command)>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let command =
+                serde_json::from_str(line).with_context(|| format!("line {}", index + 1))?;
+            Ok((index + 1, command))
+        })
+        .collect()
+}
+
+/// Text on its way into or out of the covenant tables. Event and pattern text
+/// is redacted and, when there is a store key, sealed as the runtime seals it.
+struct StoredText<'a> {
+    redactor: &'a Redactor,
+    cipher: Option<&'a StoreCipher>,
+}
+
+impl StoredText<'_> {
+    fn seal(&self, text: &str) -> anyhow::Result<String> {
+        seal_text(&self.redactor.redact(text), self.cipher)
+    }
+}
+
+/// `rows` of `(id, text)` with each text decrypted when it was sealed.
+fn open_texts(
+    rows: Vec<(String, String)>,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<Vec<(String, String)>> {
+    rows.into_iter()
+        .map(|(id, text)| Ok((id, open_line(text.as_str(), cipher)?.into_owned())))
+        .collect()
+}
+
+/// Apply `command`'s writes on `conn` and describe what was done.
+async fn run_command(
+    conn: &mut SqliteConnection,
+    command: Command,
+    scope: &str,
+    text: &StoredText<'_>,
+    store_root: &Path,
+    cwd: &Path,
+) -> anyhow::Result<String> {
+    let message = match command {
+        Command::Batch(_) => anyhow::bail!("batch files cannot contain batch commands"),
+        Command::Init(args) => {
+            ensure_covenant_version(&mut *conn, args.covenant_version.as_str()).await?;
+            format!(
+                "initialized schema and covenant version {}",
+                args.covenant_version
+            )
+        }
+        Command::Log(args) => {
+            let tags = normalize_event_tags(&args.tags)
+                .map_err(|message| CliError::new(ErrorCode::Usage, message))?;
+            let event_id = args.event_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+            let created_at = Utc::now().timestamp();
+            sqlx::query(
+                r#"
+INSERT INTO events (id, created_at, description, domain_signature, status, tags)
+VALUES (?, ?, ?, ?, 'open', ?)
+                "#,
+            )
+            .bind(event_id.as_str())
+            .bind(created_at)
+            .bind(text.seal(&args.description)?)
+            .bind(args.domain_signature)
+            .bind(to_canonical_json(&tags)?)
+            .execute(&mut *conn)
+            .await?;
+
+            if let Some(goal) = args.intent_goal {
+                let intent_id = Uuid::new_v4().to_string();
+                sqlx::query(
+                    r#"
+INSERT INTO intent_tokens (id, event_id, goal, constraints, success_signal, confidence, created_at)
+VALUES (?, ?, ?, ?, ?, ?, ?)
+                    "#,
+                )
+                .bind(intent_id.as_str())
+                .bind(event_id.as_str())
+                .bind(text.seal(&goal)?)
+                .bind(text.seal(&args.intent_constraints.unwrap_or_default())?)
+                .bind(text.seal(&args.intent_success_signal.unwrap_or_default())?)
+                .bind(args.intent_confidence.unwrap_or(0.5))
+                .bind(created_at)
+                .execute(&mut *conn)
+                .await?;
+            }
+
+            format!("logged event {event_id}")
+        }
+        Command::Predict(args) => {
+            let event_id = resolved_event_id(args.event_id)?;
+            ensure_event_exists(&mut *conn, event_id.as_str()).await?;
+            let hypothesis_id = Uuid::new_v4().to_string();
+            let domain_signature = match args.domain_signature {
+                Some(signature) => signature,
+                None => event_domain_signature(&mut *conn, event_id.as_str()).await?,
+            };
+            let falsifiers = args
+                .falsifiers
+                .iter()
+                .map(|falsifier| text.redactor.redact(falsifier))
+                .collect::<Vec<_>>();
+            let falsifiers = seal_text(&to_canonical_json(&falsifiers)?, text.cipher)?;
+            sqlx::query(
+                r#"
+INSERT INTO hypotheses (id, event_id, model_type, probability, falsifiers, domain_signature)
+VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(hypothesis_id.as_str())
+            .bind(event_id)
+            .bind(text.seal(&args.model_type)?)
+            .bind(args.probability)
+            .bind(falsifiers)
+            .bind(domain_signature)
+            .execute(&mut *conn)
+            .await?;
+            format!("added hypothesis {hypothesis_id}")
+        }
+        Command::Test(args) => {
+            let event_id = resolved_event_id(args.event_id)?;
+            ensure_event_exists(&mut *conn, event_id.as_str()).await?;
+            ensure_hypothesis_exists(&mut *conn, event_id.as_str(), args.hypothesis_id.as_str())
+                .await?;
+            let test_id = Uuid::new_v4().to_string();
+            sqlx::query(
+                r#"
+INSERT INTO tests (id, event_id, hypothesis_id, description, result, evidence_ref, created_at)
+VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(test_id.as_str())
+            .bind(event_id)
+            .bind(args.hypothesis_id)
+            .bind(text.seal(&args.description)?)
+            .bind(args.result.as_str())
+            .bind(args.evidence_ref)
+            .bind(Utc::now().timestamp())
+            .execute(&mut *conn)
+            .await?;
+            format!("attached test {test_id}")
+        }
+        Command::Resolve(args) => {
+            let event_id = resolved_event_id(args.event_id)?;
+            ensure_event_exists(&mut *conn, event_id.as_str()).await?;
+            if args.evidence_refs.is_empty() {
+                return Err(CliError::new(
+                    ErrorCode::MissingEvidence,
+                    "at least one evidence reference is required",
+                )
+                .for_field("evidence_refs", event_id)
+                .into());
+            }
+            let tests = event_tests(&mut *conn, event_id.as_str(), text.cipher).await?;
+            let mut evidence = Vec::with_capacity(args.evidence_refs.len());
+            for evidence_ref in &args.evidence_refs {
+                let item = classify_evidence(evidence_ref, &tests);
+                match item.kind {
+                    EvidenceKind::Artifact => {
+                        ensure_artifact_exists(
+                            &mut *conn,
+                            covenant_artifacts_dir(store_root).as_path(),
+                            event_id.as_str(),
+                            evidence_ref,
+                        )
+                        .await?;
+                    }
+                    EvidenceKind::Unresolved => {
+                        let message = format!(
+                            "evidence reference '{evidence_ref}' matches no test, artifact, or URL of event {event_id}"
+                        );
+                        if args.strict {
+                            return Err(CliError::new(ErrorCode::UnresolvedEvidence, message)
+                                .for_field("evidence_refs", evidence_ref.as_str())
+                                .into());
+                        }
+                        eprintln!("warning: {message}");
+                    }
+                    EvidenceKind::Test | EvidenceKind::Url => {}
+                }
+                evidence.push(item);
+            }
+            let outcome_id = Uuid::new_v4().to_string();
+            // Tests named by description are stored by id.
+            let evidence_refs = to_canonical_json(
+                &evidence
+                    .iter()
+                    .map(|item| item.reference.as_str())
+                    .collect::<Vec<_>>(),
+            )?;
+            let outcome = Outcome::from_text(args.outcome.as_deref().unwrap_or(&args.summary));
+            sqlx::query(
+                r#"
+INSERT INTO outcomes (id, event_id, summary, outcome, evidence_refs, evidence, created_at)
+VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(outcome_id.as_str())
+            .bind(event_id.as_str())
+            .bind(text.seal(&args.summary)?)
+            .bind(outcome.as_str())
+            .bind(evidence_refs)
+            .bind(to_canonical_json(&evidence)?)
+            .bind(Utc::now().timestamp())
+            .execute(&mut *conn)
+            .await?;
+
+            sqlx::query("UPDATE events SET status = 'closed' WHERE id = ?")
+                .bind(event_id.as_str())
+                .execute(&mut *conn)
+                .await?;
+            format!("resolved event {event_id} as {outcome}")
+        }
+        Command::Attach(args) => {
+            let event_id = resolved_event_id(args.event_id)?;
+            ensure_event_exists(&mut *conn, event_id.as_str()).await?;
+            let artifact =
+                store_artifact(covenant_artifacts_dir(store_root).as_path(), &args.file).await?;
+            let file_name = args
+                .file
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            sqlx::query(
+                r#"
+INSERT INTO artifacts (id, event_id, sha256, file_name, size_bytes, created_at)
+VALUES (?, ?, ?, ?, ?, ?)
+ON CONFLICT(id, event_id) DO NOTHING
+                "#,
+            )
+            .bind(artifact.id.as_str())
+            .bind(event_id.as_str())
+            .bind(artifact.sha256.as_str())
+            .bind(file_name)
+            .bind(artifact.size_bytes)
+            .bind(Utc::now().timestamp())
+            .execute(&mut *conn)
+            .await?;
+            format!("attached artifact {} to event {event_id}", artifact.id)
+        }
+        Command::PatternsAdd(args) => {
+            let pattern_id = args
+                .pattern_id
+                .unwrap_or_else(|| Uuid::new_v4().to_string());
+            let evidence_refs = to_canonical_json(&args.evidence_refs)?;
+            let namespace = if args.global {
+                GLOBAL_PATTERN_NAMESPACE.to_string()
+            } else {
+                project_pattern_namespace(cwd)
+            };
+            let row = sqlx::query(
+                r#"
+INSERT INTO patterns (
+    id,
+    trigger,
+    invariant,
+    counterexample,
+    best_response,
+    domain_signature,
+    evidence_refs,
+    namespace,
+    scope,
+    created_at,
+    anti_pattern
+)
+VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+ON CONFLICT(id) DO UPDATE SET
+    trigger = excluded.trigger,
+    invariant = excluded.invariant,
+    counterexample = excluded.counterexample,
+    best_response = excluded.best_response,
+    domain_signature = excluded.domain_signature,
+    evidence_refs = excluded.evidence_refs,
+    namespace = excluded.namespace,
+    anti_pattern = excluded.anti_pattern,
+    archived_at = NULL
+WHERE patterns.scope = excluded.scope OR ?
+RETURNING scope
+                "#,
+            )
+            .bind(pattern_id.as_str())
+            .bind(text.seal(&args.trigger)?)
+            .bind(text.seal(&args.invariant)?)
+            .bind(text.seal(&args.counterexample)?)
+            .bind(text.seal(&args.best_response)?)
+            .bind(args.domain_signature)
+            .bind(evidence_refs)
+            .bind(namespace.as_str())
+            .bind(scope)
+            .bind(Utc::now().timestamp())
+            .bind(args.anti_pattern)
+            .bind(args.all_scopes)
+            .fetch_optional(&mut *conn)
+            .await?;
+            // The update is skipped, and nothing returned, when the pattern
+            // belongs to another scope.
+            let Some(row) = row else {
+                return Err(CliError::new(
+                    ErrorCode::PatternInOtherScope,
+                    format!(
+                        "pattern {pattern_id} belongs to another covenant scope; pass --all-scopes to update it there"
+                    ),
+                )
+                .for_field("pattern_id", pattern_id)
+                .into());
+            };
+            let stored_scope: String = row.try_get("scope")?;
+            format!("upserted pattern {pattern_id} in namespace {namespace}, scope {stored_scope}")
+        }
+    };
+    Ok(message)
+}
+
+/// Resolve the Codex home so this CLI shares the state database and
+/// redaction config the runtime uses, making events recorded by the capture
+/// tool visible here.
+fn default_codex_home() -> PathBuf {
+    if let Ok(codex_home) = std::env::var("CODEX_HOME") {
+        PathBuf::from(codex_home)
+    } else if let Some(home) = home_dir() {
+        home.join(".codex")
+    } else {
+        PathBuf::from(".codex")
+    }
+}
+
+async fn open_sqlite_pool(path: &Path) -> anyhow::Result<SqlitePool> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .foreign_keys(true);
+
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .with_context(|| format!("open sqlite database at {}", path.display()))
+}
+
+async fn ensure_schema(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+CREATE TABLE IF NOT EXISTS covenants (
+    version TEXT PRIMARY KEY,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS audit_actions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    actor TEXT NOT NULL,
+    action_type TEXT NOT NULL,
+    scope TEXT NOT NULL,
+    covenant_version TEXT NOT NULL,
+    event_id TEXT,
+    intent_id TEXT,
+    FOREIGN KEY(covenant_version) REFERENCES covenants(version)
+);
+
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT PRIMARY KEY,
+    created_at INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    domain_signature TEXT NOT NULL,
+    status TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS intent_tokens (
+    id TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL,
+    goal TEXT NOT NULL,
+    constraints TEXT NOT NULL,
+    success_signal TEXT NOT NULL,
+    confidence REAL NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY(event_id) REFERENCES events(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS hypotheses (
+    id TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL,
+    model_type TEXT NOT NULL,
+    probability REAL NOT NULL,
+    falsifiers TEXT NOT NULL,
+    domain_signature TEXT NOT NULL,
+    FOREIGN KEY(event_id) REFERENCES events(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS tests (
+    id TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL,
+    hypothesis_id TEXT NOT NULL,
+    description TEXT NOT NULL,
+    result TEXT NOT NULL,
+    evidence_ref TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY(event_id) REFERENCES events(id) ON DELETE CASCADE,
+    FOREIGN KEY(hypothesis_id) REFERENCES hypotheses(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS outcomes (
+    id TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    outcome TEXT NOT NULL DEFAULT 'unknown',
+    evidence_refs TEXT NOT NULL,
+    evidence TEXT NOT NULL DEFAULT '[]',
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY(event_id) REFERENCES events(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS patterns (
+    id TEXT PRIMARY KEY,
+    trigger TEXT NOT NULL,
+    invariant TEXT NOT NULL,
+    counterexample TEXT NOT NULL,
+    best_response TEXT NOT NULL,
+    domain_signature TEXT NOT NULL,
+    evidence_refs TEXT NOT NULL,
+    namespace TEXT NOT NULL DEFAULT 'global',
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS artifacts (
+    id TEXT NOT NULL,
+    event_id TEXT NOT NULL,
+    sha256 TEXT NOT NULL,
+    file_name TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (id, event_id),
+    FOREIGN KEY(event_id) REFERENCES events(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS covenant_check_counts (
+    day INTEGER NOT NULL,
+    scope TEXT NOT NULL,
+    capability TEXT NOT NULL,
+    allowed INTEGER NOT NULL DEFAULT 0,
+    denied INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, scope, capability)
+);
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    ensure_column(
+        pool,
+        "patterns",
+        "namespace",
+        "TEXT NOT NULL DEFAULT 'global'",
+    )
+    .await?;
+    ensure_column(
+        pool,
+        "patterns",
+        "anti_pattern",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
+    ensure_column(pool, "patterns", "confirmed_at", "INTEGER").await?;
+    ensure_column(pool, "patterns", "archived_at", "INTEGER").await?;
+    ensure_column(pool, "events", "tags", "TEXT NOT NULL DEFAULT '[]'").await?;
+    ensure_column(pool, "patterns", "tags", "TEXT NOT NULL DEFAULT '[]'").await?;
+    ensure_column(pool, "patterns", "scope", "TEXT NOT NULL DEFAULT 'default'").await?;
+
+    Ok(())
+}
+
+/// Add `column` to `table` when a database created by an older binary lacks it.
+async fn ensure_column(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> anyhow::Result<()> {
+    let exists = sqlx::query(&format!("PRAGMA table_info({table})"))
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .any(|row| row.get::<String, _>("name") == column);
+    if !exists {
+        sqlx::query(&format!(
+            "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+        ))
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn ensure_covenant_version(
+    conn: &mut SqliteConnection,
+    covenant_version: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO covenants (version, created_at) VALUES (?, ?)")
+        .bind(covenant_version)
+        .bind(Utc::now().timestamp())
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Count a covenant check towards today's totals for `codex covenant usage`.
+async fn record_covenant_check(
+    conn: &mut SqliteConnection,
+    scope: &str,
+    capability: &str,
+    allowed: bool,
+) -> anyhow::Result<()> {
+    let now = Utc::now().timestamp();
+    sqlx::query(
+        r#"
+INSERT INTO covenant_check_counts (day, scope, capability, allowed, denied)
+VALUES (?, ?, ?, ?, ?)
+ON CONFLICT(day, scope, capability) DO UPDATE SET
+    allowed = allowed + excluded.allowed,
+    denied = denied + excluded.denied
+        "#,
+    )
+    .bind(now - now.rem_euclid(SECONDS_PER_DAY))
+    .bind(scope)
+    .bind(capability)
+    .bind(i64::from(allowed))
+    .bind(i64::from(!allowed))
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn insert_audit_action(
+    conn: &mut SqliteConnection,
+    actor: &str,
+    action_type: &str,
+    scope: &str,
+    covenant_version: &str,
+    event_id: Option<&str>,
+    intent_id: Option<&str>,
+) -> anyhow::Result<()> {
+    ensure_covenant_version(&mut *conn, covenant_version).await?;
+    sqlx::query(
+        r#"
+INSERT INTO audit_actions (timestamp, actor, action_type, scope, covenant_version, event_id, intent_id)
+VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(Utc::now().timestamp())
+    .bind(actor)
+    .bind(action_type)
+    .bind(scope)
+    .bind(covenant_version)
+    .bind(event_id)
+    .bind(intent_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn load_covenant(cwd: &Path) -> anyhow::Result<Covenant> {
+    let covenant_path = find_covenant_path(cwd).await.ok_or_else(|| {
+        CliError::new(
+            ErrorCode::CovenantNotFound,
+            format!("covenant.json not found from {}", cwd.display()),
+        )
+    })?;
+    let contents = tokio::fs::read_to_string(&covenant_path).await?;
+    let mut covenant = serde_json::from_str::<Covenant>(&contents)
+        .with_context(|| format!("failed to parse {}", covenant_path.display()))?;
+    for scope in &mut covenant.scopes {
+        if let Some(template) = &scope.template {
+            scope.capabilities = expand_capability_template(template, &scope.capabilities)
+                .ok_or_else(|| anyhow::anyhow!(unknown_template_message(&scope.name, template)))
+                .with_context(|| format!("failed to parse {}", covenant_path.display()))?;
+        }
+    }
+    Ok(covenant)
+}
+
+async fn find_covenant_path(cwd: &Path) -> Option<PathBuf> {
+    let mut current = Some(cwd);
+    while let Some(path) = current {
+        let candidate = path.join("covenant.json");
+        if tokio::fs::try_exists(&candidate).await.unwrap_or(false) {
+            return Some(candidate);
+        }
+        current = path.parent();
+    }
+    None
+}
+
+/// Events listed when a selector fits more than one.
+const MAX_LISTED_CANDIDATES: usize = 10;
+
+/// Replace the event a command names by id prefix or `event_match` with the
+/// full id of the one event it fits.
+async fn resolve_event_selector(
+    conn: &mut SqliteConnection,
+    command: &mut Command,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<()> {
+    let (event_id, event_match) = match command {
+        Command::Predict(PredictArgs {
+            event_id,
+            event_match,
+            ..
+        })
+        | Command::Test(TestArgs {
+            event_id,
+            event_match,
+            ..
+        })
+        | Command::Resolve(ResolveArgs {
+            event_id,
+            event_match,
+            ..
+        })
+        | Command::Attach(AttachArgs {
+            event_id,
+            event_match,
+            ..
+        }) => (event_id, event_match),
+        Command::Init(_) | Command::Log(_) | Command::PatternsAdd(_) | Command::Batch(_) => {
+            return Ok(());
+        }
+    };
+    let resolved = match (event_id.as_deref(), event_match.take()) {
+        (Some(_), Some(_)) => {
+            return Err(CliError::new(
+                ErrorCode::Usage,
+                "pass either event_id or event_match, not both",
+            )
+            .into());
+        }
+        (None, None) => {
+            return Err(CliError::new(ErrorCode::Usage, "pass event_id or event_match").into());
+        }
+        (Some(prefix), None) => {
+            let candidates = sqlx::query_as::<_, (String, String)>(
+                r#"
+SELECT id, description
+FROM events
+WHERE substr(id, 1, length(?)) = ?
+ORDER BY created_at DESC, id ASC
+                "#,
+            )
+            .bind(prefix)
+            .bind(prefix)
+            .fetch_all(&mut *conn)
+            .await?;
+            let candidates = open_texts(candidates, cipher)?;
+            match candidates.iter().find(|(id, _)| id == prefix) {
+                Some((id, _)) => id.clone(),
+                None => single_event(
+                    "event_id",
+                    prefix,
+                    candidates,
+                    format!("event {prefix} does not exist"),
+                )?,
+            }
+        }
+        (None, Some(query)) => {
+            let events = sqlx::query_as::<_, (String, String)>(
+                "SELECT id, description FROM events ORDER BY created_at DESC, id ASC",
+            )
+            .fetch_all(&mut *conn)
+            .await?;
+            let events = open_texts(events, cipher)?;
+            single_event(
+                "event_match",
+                query.as_str(),
+                events_matching(query.as_str(), events),
+                format!("no event description matches '{query}'"),
+            )?
+        }
+    };
+    *event_id = Some(resolved);
+    Ok(())
+}
+
+/// Lowercased alphanumeric words of `text`.
+fn words(text: &str) -> Vec<String> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The `(id, description)` events whose description contains every word of
+/// `query`.
+fn events_matching(query: &str, events: Vec<(String, String)>) -> Vec<(String, String)> {
+    let query = words(query);
+    events
+        .into_iter()
+        .filter(|(_, description)| {
+            let description = words(description);
+            !query.is_empty() && query.iter().all(|word| description.contains(word))
+        })
+        .collect()
+}
+
+/// The id of the only candidate, or an error naming `field`: `not_found`
+/// when there are none, and a list to choose from when there are several.
+fn single_event(
+    field: &'static str,
+    query: &str,
+    mut candidates: Vec<(String, String)>,
+    not_found: String,
+) -> Result<String, CliError> {
+    if candidates.len() == 1 {
+        let (id, _) = candidates.remove(0);
+        return Ok(id);
+    }
+    if candidates.is_empty() {
+        return Err(CliError::new(ErrorCode::EventNotFound, not_found).for_field(field, query));
+    }
+    let mut message = format!(
+        "'{query}' matches {} events; pass one of these as --event-id:",
+        candidates.len()
+    );
+    for (id, description) in candidates.iter().take(MAX_LISTED_CANDIDATES) {
+        message.push_str(&format!("\n  {id}  {description}"));
+    }
+    if candidates.len() > MAX_LISTED_CANDIDATES {
+        message.push_str(&format!(
+            "\n  ... and {} more",
+            candidates.len() - MAX_LISTED_CANDIDATES
+        ));
+    }
+    Err(CliError::new(ErrorCode::AmbiguousEvent, message).for_field(field, query))
+}
+
+/// The id a command's event was resolved to before it ran.
+fn resolved_event_id(event_id: Option<String>) -> anyhow::Result<String> {
+    event_id.context("the command's event was not resolved")
+}
+
+async fn ensure_event_exists(conn: &mut SqliteConnection, event_id: &str) -> anyhow::Result<()> {
+    let exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(1) FROM events WHERE id = ?")
+        .bind(event_id)
+        .fetch_one(conn)
+        .await?;
+    if exists == 0 {
+        return Err(CliError::new(
+            ErrorCode::EventNotFound,
+            format!("event {event_id} does not exist"),
+        )
+        .for_field("event_id", event_id)
+        .into());
+    }
+    Ok(())
+}
+
+async fn ensure_hypothesis_exists(
+    conn: &mut SqliteConnection,
+    event_id: &str,
+    hypothesis_id: &str,
+) -> anyhow::Result<()> {
+    let exists = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(1) FROM hypotheses WHERE id = ? AND event_id = ?",
+    )
+    .bind(hypothesis_id)
+    .bind(event_id)
+    .fetch_one(conn)
+    .await?;
+    if exists == 0 {
+        return Err(CliError::new(
+            ErrorCode::HypothesisNotFound,
+            format!("hypothesis {hypothesis_id} does not exist for event {event_id}"),
+        )
+        .for_field("hypothesis_id", hypothesis_id)
+        .into());
+    }
+    Ok(())
+}
+
+/// The `(id, description)` of every test recorded for `event_id`.
+async fn event_tests(
+    conn: &mut SqliteConnection,
+    event_id: &str,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let tests = sqlx::query_as::<_, (String, String)>(
+        "SELECT id, description FROM tests WHERE event_id = ? ORDER BY created_at, id",
+    )
+    .bind(event_id)
+    .fetch_all(conn)
+    .await?;
+    open_texts(tests, cipher)
+}
+
+/// What an evidence reference points at. Tests match by id or, ignoring case,
+/// by description, and are referenced by id either way.
+fn classify_evidence(reference: &str, tests: &[(String, String)]) -> CovenantEvidence {
+    let reference = reference.trim();
+    let (kind, reference) = if reference.starts_with(ARTIFACT_ID_PREFIX) {
+        (EvidenceKind::Artifact, reference)
+    } else if reference.starts_with("https://") || reference.starts_with("http://") {
+        (EvidenceKind::Url, reference)
+    } else if let Some((id, _)) = tests.iter().find(|(id, description)| {
+        id == reference || description.trim().eq_ignore_ascii_case(reference)
+    }) {
+        (EvidenceKind::Test, id.as_str())
+    } else {
+        (EvidenceKind::Unresolved, reference)
+    };
+    CovenantEvidence {
+        kind,
+        reference: reference.to_string(),
+    }
+}
+
+/// Artifact ids are the SHA-256 of the stored content, so attaching the same
+/// file twice reuses the existing copy.
+const ARTIFACT_ID_PREFIX: &str = "sha256:";
+
+#[derive(Debug, PartialEq)]
+struct StoredArtifact {
+    id: String,
+    sha256: String,
+    size_bytes: i64,
+}
+
+async fn store_artifact(artifacts_dir: &Path, file: &Path) -> anyhow::Result<StoredArtifact> {
+    let contents = tokio::fs::read(file)
+        .await
+        .with_context(|| format!("failed to read {}", file.display()))?;
+    let sha256 = Sha256::digest(&contents)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    tokio::fs::create_dir_all(artifacts_dir).await?;
+    let destination = artifacts_dir.join(sha256.as_str());
+    if !tokio::fs::try_exists(&destination).await.unwrap_or(false) {
+        let staging = artifacts_dir.join(format!("{sha256}.tmp"));
+        tokio::fs::write(&staging, &contents).await?;
+        tokio::fs::rename(&staging, &destination).await?;
+    }
+    Ok(StoredArtifact {
+        id: format!("{ARTIFACT_ID_PREFIX}{sha256}"),
+        size_bytes: i64::try_from(contents.len()).unwrap_or(i64::MAX),
+        sha256,
+    })
+}
+
+async fn ensure_artifact_exists(
+    conn: &mut SqliteConnection,
+    artifacts_dir: &Path,
+    event_id: &str,
+    artifact_id: &str,
+) -> anyhow::Result<()> {
+    let sha256 = sqlx::query_scalar::<_, String>(
+        "SELECT sha256 FROM artifacts WHERE id = ? AND event_id = ?",
+    )
+    .bind(artifact_id)
+    .bind(event_id)
+    .fetch_optional(conn)
+    .await?
+    .ok_or_else(|| {
+        CliError::new(
+            ErrorCode::ArtifactNotAttached,
+            format!("artifact {artifact_id} is not attached to event {event_id}"),
+        )
+        .for_field("evidence_refs", artifact_id)
+    })?;
+    let path = artifacts_dir.join(sha256.as_str());
+    if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        return Err(CliError::new(
+            ErrorCode::ArtifactMissing,
+            format!(
+                "artifact {artifact_id} is missing from {}",
+                artifacts_dir.display()
+            ),
+        )
+        .for_field("evidence_refs", artifact_id)
+        .into());
+    }
+    Ok(())
+}
+
+async fn event_domain_signature(
+    conn: &mut SqliteConnection,
+    event_id: &str,
+) -> anyhow::Result<String> {
+    let row = sqlx::query("SELECT domain_signature FROM events WHERE id = ?")
+        .bind(event_id)
+        .fetch_one(conn)
+        .await?;
+    row.try_get::<String, _>("domain_signature")
+        .context("event missing domain_signature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn covenant_check_works() {
+        let covenant = Covenant {
+            version: "1".to_string(),
+            scopes: vec![CovenantScope {
+                name: "default".to_string(),
+                template: None,
+                capabilities: vec!["event.log".to_string()],
+            }],
+            storage: CovenantStorage::Home,
+        };
+
+        assert_eq!(covenant.allows("default", "event.log"), true);
+        assert_eq!(covenant.allows("default", "event.test"), false);
+        assert_eq!(covenant.allows("missing", "event.log"), false);
+    }
+
+    #[test]
+    fn batch_lines_parse_into_commands() {
+        let contents = r#"{"op": "log", "event_id": "evt", "description": "Deploy failed", "domain_signature": "[]"}
+
+{"op": "predict", "event_id": "evt", "model_type": "Migration locks the table", "probability": 0.6}
+{"op": "test", "event_id": "evt", "hypothesis_id": "h", "description": "retry the deploy", "result": "Passed", "evidence_ref": "https://ci/2"}
+{"op": "resolve", "event_match": "deploy failed", "summary": "fixed", "evidence_refs": ["https://ci/1"]}
+"#;
+        let expected = vec![
+            (
+                1,
+                Command::Log(LogArgs {
+                    event_id: Some("evt".to_string()),
+                    description: "Deploy failed".to_string(),
+                    domain_signature: "[]".to_string(),
+                    intent_goal: None,
+                    intent_constraints: None,
+                    intent_success_signal: None,
+                    intent_confidence: None,
+                    tags: Vec::new(),
+                }),
+            ),
+            (
+                3,
+                Command::Predict(PredictArgs {
+                    event_id: Some("evt".to_string()),
+                    event_match: None,
+                    model_type: "Migration locks the table".to_string(),
+                    probability: 0.6,
+                    falsifiers: Vec::new(),
+                    domain_signature: None,
+                }),
+            ),
+            (
+                4,
+                Command::Test(TestArgs {
+                    event_id: Some("evt".to_string()),
+                    event_match: None,
+                    hypothesis_id: "h".to_string(),
+                    description: "retry the deploy".to_string(),
+                    result: TestStatus::Pass,
+                    evidence_ref: "https://ci/2".to_string(),
+                }),
+            ),
+            (
+                5,
+                Command::Resolve(ResolveArgs {
+                    event_id: None,
+                    event_match: Some("deploy failed".to_string()),
+                    summary: "fixed".to_string(),
+                    evidence_refs: vec!["https://ci/1".to_string()],
+                    strict: false,
+                    outcome: None,
+                }),
+            ),
+        ];
+        assert_eq!(parse_batch(contents).expect("parse batch"), expected);
+
+        // Commands that only make sense on their own, and unknown
+        // arguments, are rejected with the line they are on.
+        let errors = [
+            r#"{"op": "init", "covenant_version": "1"}"#,
+            r#"
+{"op": "log", "description": "x", "domain_signature": "[]", "scope": "cli"}"#,
+            r#"{"op": "test", "event_id": "evt", "hypothesis_id": "h", "description": "x", "result": "pased", "evidence_ref": "log"}"#,
+        ]
+        .into_iter()
+        .map(|contents| parse_batch(contents).err().map(|err| err.to_string()))
+        .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                Some("line 1".to_string()),
+                Some("line 2".to_string()),
+                Some("line 1".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn artifacts_are_content_addressed() {
+        let root = std::env::temp_dir().join(format!("handshakeos-e-{}", Uuid::new_v4()));
+        let source = root.join("build.log");
+        tokio::fs::create_dir_all(&root)
+            .await
+            .expect("create temp dir");
+        tokio::fs::write(&source, "error: disk full\n")
+            .await
+            .expect("write source");
+        let artifacts = covenant_artifacts_dir(root.as_path());
+
+        let first = store_artifact(artifacts.as_path(), &source)
+            .await
+            .expect("store artifact");
+        let second = store_artifact(artifacts.as_path(), &source)
+            .await
+            .expect("store artifact again");
+
+        assert_eq!(first, second);
+        assert_eq!(first.id, format!("sha256:{}", first.sha256));
+        assert_eq!(first.size_bytes, 17);
+        let stored = tokio::fs::read_to_string(artifacts.join(first.sha256.as_str()))
+            .await
+            .expect("read stored artifact");
+        assert_eq!(stored, "error: disk full\n".to_string());
+
+        let _ = tokio::fs::remove_dir_all(root).await;
+    }
+
+    #[tokio::test]
+    async fn patterns_add_stays_within_its_scope() {
+        let root = std::env::temp_dir().join(format!("handshakeos-e-{}", Uuid::new_v4()));
+        tokio::fs::create_dir_all(&root)
+            .await
+            .expect("create temp dir");
+        let pool = open_sqlite_pool(root.join("state.sqlite").as_path())
+            .await
+            .expect("open pool");
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("migrate");
+        ensure_schema(&pool).await.expect("ensure schema");
+        async fn add(
+            conn: &mut SqliteConnection,
+            root: &Path,
+            scope: &str,
+            best_response: &str,
+            all_scopes: bool,
+        ) -> Result<String, ErrorCode> {
+            let command = Command::PatternsAdd(PatternsAddArgs {
+                pattern_id: Some("disk-full".to_string()),
+                trigger: "disk full".to_string(),
+                invariant: "logs grow".to_string(),
+                counterexample: String::new(),
+                best_response: best_response.to_string(),
+                domain_signature: "[]".to_string(),
+                evidence_refs: Vec::new(),
+                global: true,
+                anti_pattern: false,
+                all_scopes,
+            });
+            let text = StoredText {
+                redactor: &Redactor::default(),
+                cipher: None,
+            };
+            run_command(conn, command, scope, &text, root, root)
+                .await
+                .map_err(|err| CliError::from_anyhow(&err).code)
+        }
+        let mut conn = pool.acquire().await.expect("acquire connection");
+
+        assert_eq!(
+            [
+                add(&mut conn, &root, "cli", "prune logs", false).await,
+                add(&mut conn, &root, "exec", "reboot", false).await,
+                add(&mut conn, &root, "exec", "rotate logs", true).await,
+            ],
+            [
+                Ok("upserted pattern disk-full in namespace global, scope cli".to_string()),
+                Err(ErrorCode::PatternInOtherScope),
+                Ok("upserted pattern disk-full in namespace global, scope cli".to_string()),
+            ]
+        );
+        drop(conn);
+        let stored: (String, String) =
+            sqlx::query_as("SELECT scope, best_response FROM patterns WHERE id = 'disk-full'")
+                .fetch_one(&pool)
+                .await
+                .expect("stored pattern");
+        assert_eq!(stored, ("cli".to_string(), "rotate logs".to_string()));
+
+        let _ = tokio::fs::remove_dir_all(root).await;
+    }
+
+    #[test]
+    fn json_errors_keep_codes_through_context() {
+        let not_found: anyhow::Error =
+            CliError::new(ErrorCode::EventNotFound, "event e1 does not exist")
+                .for_field("event_id", "e1")
+                .into();
+        let not_found = not_found.context("failed to resolve");
+        let parse_error = serde_json::from_str::<Covenant>("{")
+            .context("failed to parse covenant.json")
+            .expect_err("invalid covenant");
+
+        assert_eq!(
+            serde_json::to_value(CliError::from_anyhow(&not_found)).expect("serialize error"),
+            json!({
+                "code": "event_not_found",
+                "message": "failed to resolve: event e1 does not exist",
+                "field": "event_id",
+                "id": "e1",
+            })
+        );
+        assert_eq!(
+            [
+                CliError::from_anyhow(&parse_error).code,
+                CliError::from_anyhow(&anyhow::anyhow!("boom")).code,
+            ],
+            [ErrorCode::ParseError, ErrorCode::Internal]
+        );
+    }
+
+    #[test]
+    fn evidence_resolves_to_tests_artifacts_and_urls() {
+        let tests = vec![
+            ("t-1".to_string(), "Disk usage check".to_string()),
+            ("t-2".to_string(), "Log rotation".to_string()),
+        ];
+
+        assert_eq!(
+            [
+                "t-2",
+                "disk usage check",
+                "sha256:abc",
+                "https://ci.example.com/run/1",
+                "ran it by hand",
+            ]
+            .into_iter()
+            .map(|reference| classify_evidence(reference, &tests))
+            .collect::<Vec<_>>(),
+            vec![
+                CovenantEvidence::test("t-2"),
+                CovenantEvidence::test("t-1"),
+                CovenantEvidence {
+                    kind: EvidenceKind::Artifact,
+                    reference: "sha256:abc".to_string(),
+                },
+                CovenantEvidence {
+                    kind: EvidenceKind::Url,
+                    reference: "https://ci.example.com/run/1".to_string(),
+                },
+                CovenantEvidence {
+                    kind: EvidenceKind::Unresolved,
+                    reference: "ran it by hand".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn event_selectors_need_exactly_one_match() {
+        let events = vec![
+            ("e-3".to_string(), "Disk full on build host".to_string()),
+            ("e-2".to_string(), "Certificate expired on api".to_string()),
+            ("e-1".to_string(), "Disk full, again".to_string()),
+        ];
+        let select = |query: &str| {
+            single_event(
+                "event_match",
+                query,
+                events_matching(query, events.clone()),
+                format!("no event description matches '{query}'"),
+            )
+            .map_err(|err| (err.code, err.message))
+        };
+
+        assert_eq!(
+            [
+                select("certificate API"),
+                select("disk full"),
+                select("memory"),
+            ],
+            [
+                Ok("e-2".to_string()),
+                Err((
+                    ErrorCode::AmbiguousEvent,
+                    "'disk full' matches 2 events; pass one of these as --event-id:\n  \
+                     e-3  Disk full on build host\n  e-1  Disk full, again"
+                        .to_string(),
+                )),
+                Err((
+                    ErrorCode::EventNotFound,
+                    "no event description matches 'memory'".to_string(),
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn evidence_refs_are_serialized() {
+        let evidence_refs = vec!["test-1".to_string(), "test-2".to_string()];
+        let serialized = to_canonical_json(&evidence_refs).expect("serialize evidence refs");
+        assert_eq!(serialized, json!(["test-1", "test-2"]).to_string());
+    }
+}
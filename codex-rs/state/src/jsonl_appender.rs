@@ -0,0 +1,315 @@
+//! Crash-tolerant appends to JSONL logs.
+//!
+//! Each record is written as one `write` of the serialized line and its
+//! newline, so a crash can at worst leave a partial last line. Opening a log
+//! repairs that tail before anything new is appended: an unterminated line
+//! that still parses is completed with a newline, anything else is cut off.
+
+use crate::canonical_json::to_canonical_json;
+use crate::store_cipher::StoreCipher;
+use anyhow::Context;
+use serde::Serialize;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+const TAIL_CHUNK_BYTES: u64 = 4096;
+
+/// When appended lines are flushed to disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Leave it to the operating system.
+    Never,
+    /// Once, when [`JsonlAppender::finish`] is called.
+    #[default]
+    OnFinish,
+    /// After every appended line.
+    EveryLine,
+}
+
+/// Size-based rotation. Before a line would grow the log past `max_bytes`,
+/// the log is renamed to `<name>.1`, shifting older rotations up to
+/// `<name>.<keep>` and dropping the oldest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotationPolicy {
+    pub max_bytes: u64,
+    pub keep: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonlAppenderOptions {
+    pub fsync: FsyncPolicy,
+    /// `None` never rotates.
+    pub rotation: Option<RotationPolicy>,
+}
+
+/// An open JSONL log that appends whole lines.
+#[derive(Debug)]
+pub struct JsonlAppender {
+    path: PathBuf,
+    file: File,
+    len: u64,
+    options: JsonlAppenderOptions,
+    repaired_bytes: u64,
+    unsynced: bool,
+    cipher: Option<StoreCipher>,
+}
+
+impl JsonlAppender {
+    /// Open `path` for appending, creating it and its parent directory if
+    /// needed and repairing a partial last line.
+    pub fn open(path: &Path, options: JsonlAppenderOptions) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let mut file = open_log(path)?;
+        let (len, repaired_bytes) = repair_tail(&mut file)
+            .with_context(|| format!("failed to repair {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            len,
+            options,
+            repaired_bytes,
+            unsynced: repaired_bytes > 0,
+            cipher: None,
+        })
+    }
+
+    /// Seal every line appended from now on with `cipher`.
+    pub fn with_cipher(mut self, cipher: Option<StoreCipher>) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Bytes of a partial last line that were cut off or completed on open.
+    pub fn repaired_bytes(&self) -> u64 {
+        self.repaired_bytes
+    }
+
+    /// Serialize `value` as one canonical JSON line and append it.
+    pub fn append<T: Serialize>(&mut self, value: &T) -> anyhow::Result<()> {
+        let line = to_canonical_json(value)?;
+        self.append_line(&line)
+    }
+
+    /// Append an already serialized line. `line` must not contain newlines.
+    pub fn append_line(&mut self, line: &str) -> anyhow::Result<()> {
+        anyhow::ensure!(!line.contains('\n'), "JSONL lines cannot contain newlines");
+        let sealed = self
+            .cipher
+            .as_ref()
+            .map(|cipher| cipher.seal_line(line))
+            .transpose()?;
+        let line = sealed.as_deref().unwrap_or(line);
+        let mut bytes = Vec::with_capacity(line.len() + 1);
+        bytes.extend_from_slice(line.as_bytes());
+        bytes.push(b'\n');
+        if let Some(rotation) = self.options.rotation
+            && self.len > 0
+            && self.len + bytes.len() as u64 > rotation.max_bytes
+        {
+            self.rotate(rotation)?;
+        }
+        self.file
+            .write_all(&bytes)
+            .with_context(|| format!("failed to append to {}", self.path.display()))?;
+        self.len += bytes.len() as u64;
+        self.unsynced = true;
+        if self.options.fsync == FsyncPolicy::EveryLine {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Flush appended lines to disk when the policy asks for it.
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        if self.options.fsync != FsyncPolicy::Never {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> anyhow::Result<()> {
+        if self.unsynced {
+            self.file
+                .sync_data()
+                .with_context(|| format!("failed to sync {}", self.path.display()))?;
+            self.unsynced = false;
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self, rotation: RotationPolicy) -> anyhow::Result<()> {
+        if self.options.fsync != FsyncPolicy::Never {
+            self.sync()?;
+        }
+        if rotation.keep == 0 {
+            self.file.set_len(0)?;
+            self.len = 0;
+            return Ok(());
+        }
+        for index in (1..rotation.keep).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                std::fs::rename(&from, rotated_path(&self.path, index + 1))
+                    .with_context(|| format!("failed to rotate {}", from.display()))?;
+            }
+        }
+        std::fs::rename(&self.path, rotated_path(&self.path, 1))
+            .with_context(|| format!("failed to rotate {}", self.path.display()))?;
+        self.file = open_log(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+/// `<path>.<index>`, where rotated copies of a log are kept.
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+fn open_log(path: &Path) -> anyhow::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))
+}
+
+/// Complete or cut off an unterminated last line. Returns the resulting
+/// length and how many bytes were changed.
+fn repair_tail(file: &mut File) -> std::io::Result<(u64, u64)> {
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return Ok((0, 0));
+    }
+    let mut last = [0; 1];
+    file.seek(SeekFrom::Start(len - 1))?;
+    file.read_exact(&mut last)?;
+    if last[0] == b'\n' {
+        return Ok((len, 0));
+    }
+
+    // Walk back in chunks to the newline ending the last complete line.
+    let mut line_start = 0;
+    let mut offset = len;
+    while offset > 0 {
+        let chunk = offset.min(TAIL_CHUNK_BYTES);
+        offset -= chunk;
+        let mut buffer = vec![0; chunk as usize];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut buffer)?;
+        if let Some(newline) = buffer.iter().rposition(|byte| *byte == b'\n') {
+            line_start = offset + newline as u64 + 1;
+            break;
+        }
+    }
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(line_start))?;
+    file.read_to_end(&mut tail)?;
+    if serde_json::from_slice::<serde_json::Value>(&tail).is_ok() {
+        file.write_all(b"\n")?;
+        return Ok((len + 1, 1));
+    }
+    file.set_len(line_start)?;
+    Ok((line_start, len - line_start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::time::SystemTime;
+    use std::time::UNIX_EPOCH;
+    use uuid::Uuid;
+
+    fn unique_temp_dir() -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos());
+        std::env::temp_dir().join(format!("codex-state-jsonl-test-{nanos}-{}", Uuid::new_v4()))
+    }
+
+    fn reopen(path: &Path) -> (u64, String) {
+        let appender =
+            JsonlAppender::open(path, JsonlAppenderOptions::default()).expect("open log");
+        let repaired = appender.repaired_bytes();
+        appender.finish().expect("finish");
+        (repaired, std::fs::read_to_string(path).expect("read log"))
+    }
+
+    #[test]
+    fn open_repairs_a_partial_last_line() {
+        let dir = unique_temp_dir();
+        let path = dir.join("logs").join("usage.jsonl");
+        let mut appender =
+            JsonlAppender::open(&path, JsonlAppenderOptions::default()).expect("open log");
+        appender
+            .append(&serde_json::json!({"id": 1}))
+            .expect("append");
+        appender.finish().expect("finish");
+
+        let mut file = OpenOptions::new().append(true).open(&path).expect("open");
+        file.write_all(b"{\"id\": 2").expect("write partial line");
+        assert_eq!(reopen(&path), (8, "{\"id\":1}\n".to_string()));
+
+        file.write_all(b"{\"id\":3}")
+            .expect("write unterminated line");
+        assert_eq!(reopen(&path), (1, "{\"id\":1}\n{\"id\":3}\n".to_string()));
+        assert_eq!(reopen(&path), (0, "{\"id\":1}\n{\"id\":3}\n".to_string()));
+
+        std::fs::remove_dir_all(dir).expect("cleanup");
+    }
+
+    #[test]
+    fn rotation_keeps_the_newest_logs() {
+        let dir = unique_temp_dir();
+        let path = dir.join("patterns.jsonl");
+        let options = JsonlAppenderOptions {
+            fsync: FsyncPolicy::EveryLine,
+            rotation: Some(RotationPolicy {
+                max_bytes: 10,
+                keep: 2,
+            }),
+        };
+        let mut appender = JsonlAppender::open(&path, options).expect("open log");
+        for line in ["\"one\"", "\"two\"", "\"three\"", "\"four\""] {
+            appender.append_line(line).expect("append");
+        }
+        appender.finish().expect("finish");
+
+        let read = |path: PathBuf| std::fs::read_to_string(path).unwrap_or_default();
+        assert_eq!(
+            (
+                read(path.clone()),
+                read(rotated_path(&path, 1)),
+                read(rotated_path(&path, 2)),
+                rotated_path(&path, 3).exists(),
+            ),
+            (
+                "\"four\"\n".to_string(),
+                "\"three\"\n".to_string(),
+                "\"two\"\n".to_string(),
+                false,
+            )
+        );
+
+        std::fs::remove_dir_all(dir).expect("cleanup");
+    }
+}
@@ -4,12 +4,21 @@
 //! from JSONL rollouts and mirrors it into a local SQLite database. Backfill
 //! orchestration and rollout scanning live in `codex-core`.
 
+mod canonical_json;
+mod covenant_archive;
+mod covenant_storage;
+mod covenant_templates;
 mod extract;
+pub mod handshake_cli;
+mod jsonl_appender;
 pub mod log_db;
 mod migrations;
 mod model;
 mod paths;
+mod pattern_namespace;
+mod redact;
 mod runtime;
+mod store_cipher;
 
 pub use model::LogEntry;
 pub use model::LogQuery;
@@ -20,22 +29,75 @@ pub use runtime::StateRuntime;
 /// Low-level storage engine: useful for focused tests.
 ///
 /// Most consumers should prefer [`StateRuntime`].
+pub use canonical_json::canonical_json;
+pub use canonical_json::to_canonical_json;
+pub use covenant_archive::ArchivedCovenantEvent;
+pub use covenant_archive::CovenantRetention;
+pub use covenant_archive::archive_covenant_events;
+pub use covenant_archive::archive_unattached_artifacts;
+pub use covenant_archive::covenant_archive_dir;
+pub use covenant_archive::covenant_artifacts_dir;
+pub use covenant_archive::directory_size;
+pub use covenant_archive::read_archived_covenant_event_entries;
+pub use covenant_archive::read_archived_covenant_events;
+pub use covenant_storage::CovenantStorage;
+pub use covenant_storage::CovenantStorePaths;
+pub use covenant_storage::WORKSPACE_STORE_DIR;
+pub use covenant_storage::workspace_root;
+pub use covenant_templates::CAPABILITY_TEMPLATES;
+pub use covenant_templates::expand_capability_template;
+pub use covenant_templates::unknown_template_message;
 pub use extract::apply_rollout_item;
+pub use jsonl_appender::FsyncPolicy;
+pub use jsonl_appender::JsonlAppender;
+pub use jsonl_appender::JsonlAppenderOptions;
+pub use jsonl_appender::RotationPolicy;
+pub use jsonl_appender::rotated_path;
 pub use model::Anchor;
 pub use model::AuditAction;
 pub use model::BackfillState;
 pub use model::BackfillStats;
 pub use model::BackfillStatus;
+pub use model::CovenantCheck;
+pub use model::CovenantCheckCount;
+pub use model::CovenantEvent;
+pub use model::CovenantEvidence;
+pub use model::CovenantGrant;
+pub use model::CovenantHypothesis;
+pub use model::CovenantIntent;
+pub use model::CovenantOutcome;
+pub use model::CovenantPattern;
+pub use model::CovenantTest;
+pub use model::EvidenceKind;
 pub use model::ExtractionOutcome;
+pub use model::PatternCounterexample;
+pub use model::PatternReview;
+pub use model::PatternReviewDecision;
 pub use model::SortKey;
+pub use model::StalePattern;
 pub use model::ThreadMemory;
 pub use model::ThreadMetadata;
 pub use model::ThreadMetadataBuilder;
 pub use model::ThreadsPage;
+pub use model::normalize_event_tags;
+pub use pattern_namespace::DEFAULT_PATTERN_SCOPE;
+pub use pattern_namespace::GLOBAL_PATTERN_NAMESPACE;
+pub use pattern_namespace::PatternNamespaces;
+pub use pattern_namespace::pattern_namespace_visible;
+pub use pattern_namespace::project_pattern_namespace;
+pub use redact::REDACTION_CONFIG_FILENAME;
+pub use redact::RedactionConfig;
+pub use redact::Redactor;
+pub use runtime::CovenantTransaction;
 pub use runtime::STATE_DB_FILENAME;
 pub use runtime::STATE_DB_VERSION;
 pub use runtime::state_db_filename;
 pub use runtime::state_db_path;
+pub use store_cipher::LazyStoreCipher;
+pub use store_cipher::STORE_KEY_ENV_VAR;
+pub use store_cipher::StoreCipher;
+pub use store_cipher::open_line;
+pub use store_cipher::seal_text;
 
 /// Errors encountered during DB operations. Tags: [stage]
 pub const DB_ERROR_METRIC: &str = "codex.db.error";
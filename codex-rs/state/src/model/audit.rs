@@ -1,4 +1,4 @@
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AuditAction {
     pub timestamp: i64,
     pub actor: String,
@@ -8,3 +8,34 @@ pub struct AuditAction {
     pub event_id: Option<String>,
     pub intent_id: Option<String>,
 }
+
+/// A capability the user granted one session for a limited time, beyond what
+/// the covenant allows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CovenantGrant {
+    pub thread_id: String,
+    pub scope: String,
+    pub capability: String,
+    pub granted_at: i64,
+    pub expires_at: i64,
+}
+
+/// One covenant check of a capability, and whether enforcement allowed it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CovenantCheck {
+    pub timestamp: i64,
+    pub scope: String,
+    pub capability: String,
+    pub allowed: bool,
+}
+
+/// How often a capability was checked in one scope during one UTC day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CovenantCheckCount {
+    /// Start of the day, in seconds since the epoch.
+    pub day: i64,
+    pub scope: String,
+    pub capability: String,
+    pub allowed: i64,
+    pub denied: i64,
+}
@@ -0,0 +1,150 @@
+use codex_protocol::outcome::Outcome;
+use codex_protocol::test_status::TestStatus;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// An event persisted into the covenant store together with the hypotheses,
+/// tests, and outcomes recorded for it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CovenantEvent {
+    pub id: String,
+    pub created_at: i64,
+    pub description: String,
+    pub domain_signature: String,
+    pub intent: Option<CovenantIntent>,
+    pub hypotheses: Vec<CovenantHypothesis>,
+    pub tests: Vec<CovenantTest>,
+    pub outcomes: Vec<CovenantOutcome>,
+    /// Free-form labels such as `infra` or `flaky-test`, lowercase and
+    /// without duplicates.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The session the event was captured in, when it was captured from
+    /// inside one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// The rollout file of `session_id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollout_path: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CovenantIntent {
+    pub id: String,
+    pub goal: String,
+    pub constraints: String,
+    pub success_signal: String,
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CovenantHypothesis {
+    pub id: String,
+    pub model_type: String,
+    pub probability: f64,
+    pub falsifiers: Vec<String>,
+    pub domain_signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CovenantTest {
+    pub id: String,
+    pub hypothesis_id: String,
+    pub description: String,
+    /// Tests recorded before statuses existed hold free text, which is
+    /// classified with [`TestStatus::from_text`] when read.
+    #[serde(deserialize_with = "deserialize_test_status")]
+    pub result: TestStatus,
+    pub evidence_ref: String,
+}
+
+fn deserialize_test_status<'de, D>(deserializer: D) -> Result<TestStatus, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let text = String::deserialize(deserializer)?;
+    Ok(TestStatus::from_text(&text))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CovenantOutcome {
+    pub id: String,
+    pub summary: String,
+    pub outcome: Outcome,
+    pub evidence_refs: Vec<String>,
+    /// What each evidence reference points at. Outcomes recorded before
+    /// evidence was classified have none.
+    #[serde(default)]
+    pub evidence: Vec<CovenantEvidence>,
+}
+
+/// One evidence reference of an outcome, with what it resolved to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CovenantEvidence {
+    pub kind: EvidenceKind,
+    #[serde(rename = "ref")]
+    pub reference: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceKind {
+    /// A test recorded for the event.
+    Test,
+    /// A file attached to the event, referenced as `sha256:<hex>`.
+    Artifact,
+    Url,
+    /// A reference that matched no test, artifact, or URL.
+    Unresolved,
+}
+
+impl CovenantEvidence {
+    pub fn test(reference: impl Into<String>) -> Self {
+        Self {
+            kind: EvidenceKind::Test,
+            reference: reference.into(),
+        }
+    }
+}
+
+/// Check and normalize event tags: a leading `#` is dropped, letters are
+/// lowercased, and repeats are removed, keeping the first occurrence. Tags
+/// use letters, digits, `-` and `_`.
+pub fn normalize_event_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for value in tags {
+        let tag = value.trim();
+        let tag = tag.strip_prefix('#').unwrap_or(tag).to_lowercase();
+        if tag.is_empty()
+            || !tag
+                .chars()
+                .all(|ch| ch.is_alphanumeric() || ch == '-' || ch == '_')
+        {
+            return Err(format!(
+                "'{value}' is not a tag; tags use letters, digits, '-' and '_'"
+            ));
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+impl CovenantEvent {
+    /// Whether the event carries every one of `tags`, which should already be
+    /// normalized.
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter().all(|tag| self.tags.contains(tag))
+    }
+
+    /// Events with at least one outcome are stored as resolved.
+    pub fn status(&self) -> &'static str {
+        if self.outcomes.is_empty() {
+            "open"
+        } else {
+            "closed"
+        }
+    }
+}
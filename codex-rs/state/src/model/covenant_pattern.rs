@@ -0,0 +1,107 @@
+use codex_protocol::outcome::Outcome;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::DEFAULT_PATTERN_SCOPE;
+
+/// A pattern in the live store, visible to `codex patterns-match`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CovenantPattern {
+    pub id: String,
+    pub trigger: String,
+    pub invariant: String,
+    pub counterexample: String,
+    pub best_response: String,
+    pub domain_signature: String,
+    pub evidence_refs: Vec<String>,
+    pub namespace: String,
+    /// Covenant scope the pattern was stored in. Pattern commands only see
+    /// the patterns of one scope unless asked for all of them.
+    #[serde(default = "default_scope")]
+    pub scope: String,
+    pub created_at: i64,
+    /// The best response made things worse; matches warn against it.
+    #[serde(default)]
+    pub anti_pattern: bool,
+    /// Tags of the events the pattern was compiled from.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Events whose outcome contradicted the pattern's, most informative
+    /// first. `counterexample` describes the first in words when it is set.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub counterexamples: Vec<PatternCounterexample>,
+    /// Reported outcomes of applying the best response that agreed with the
+    /// pattern.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub supports: u64,
+    /// Reported outcomes of applying the best response that contradicted the
+    /// pattern.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub contradictions: u64,
+}
+
+fn default_scope() -> String {
+    DEFAULT_PATTERN_SCOPE.to_string()
+}
+
+fn is_zero(count: &u64) -> bool {
+    *count == 0
+}
+
+/// An event whose outcome contradicted the outcome a pattern was compiled
+/// with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PatternCounterexample {
+    pub event_id: String,
+    pub outcome: Outcome,
+    pub resolved_at: i64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence_refs: Vec<String>,
+}
+
+/// A live pattern that has gone without confirmation for a while.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct StalePattern {
+    pub pattern: CovenantPattern,
+    /// When the pattern was last re-confirmed, or created when it never was.
+    pub last_confirmed_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternReviewDecision {
+    Accepted,
+    Edited,
+    Rejected,
+}
+
+impl PatternReviewDecision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Accepted => "accepted",
+            Self::Edited => "edited",
+            Self::Rejected => "rejected",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "accepted" => Some(Self::Accepted),
+            "edited" => Some(Self::Edited),
+            "rejected" => Some(Self::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// The decision made on one version of a suggested pattern.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PatternReview {
+    pub pattern_key: String,
+    pub version: u64,
+    pub decision: PatternReviewDecision,
+    /// Id of the live pattern written for accepted or edited suggestions.
+    pub pattern_id: Option<String>,
+    pub reviewed_at: i64,
+}
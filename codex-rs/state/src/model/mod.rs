@@ -1,12 +1,30 @@
 mod audit;
 mod backfill_state;
+mod covenant_event;
+mod covenant_pattern;
 mod log;
 mod thread_memory;
 mod thread_metadata;
 
 pub use audit::AuditAction;
+pub use audit::CovenantCheck;
+pub use audit::CovenantCheckCount;
+pub use audit::CovenantGrant;
 pub use backfill_state::BackfillState;
 pub use backfill_state::BackfillStatus;
+pub use covenant_event::CovenantEvent;
+pub use covenant_event::CovenantEvidence;
+pub use covenant_event::CovenantHypothesis;
+pub use covenant_event::CovenantIntent;
+pub use covenant_event::CovenantOutcome;
+pub use covenant_event::CovenantTest;
+pub use covenant_event::EvidenceKind;
+pub use covenant_event::normalize_event_tags;
+pub use covenant_pattern::CovenantPattern;
+pub use covenant_pattern::PatternCounterexample;
+pub use covenant_pattern::PatternReview;
+pub use covenant_pattern::PatternReviewDecision;
+pub use covenant_pattern::StalePattern;
 pub use log::LogEntry;
 pub use log::LogQuery;
 pub use log::LogRow;
@@ -0,0 +1,127 @@
+use sha2::Digest;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use tokio::sync::Mutex;
+
+/// Namespace for patterns that should be visible from every project.
+pub const GLOBAL_PATTERN_NAMESPACE: &str = "global";
+
+/// Covenant scope of patterns stored without one, and of every pattern
+/// stored before patterns were partitioned by scope.
+pub const DEFAULT_PATTERN_SCOPE: &str = "default";
+
+/// Derive the pattern namespace for the project containing `cwd`.
+///
+/// The namespace is keyed by the `origin` remote URL when one is configured so
+/// separate clones of the same repository share patterns. Outside of a repo with
+/// a remote it falls back to the git root, then to `cwd` itself.
+pub fn project_pattern_namespace(cwd: &Path) -> String {
+    let key = git_output(cwd, &["config", "--get", "remote.origin.url"])
+        .or_else(|| git_output(cwd, &["rev-parse", "--show-toplevel"]))
+        .unwrap_or_else(|| {
+            std::fs::canonicalize(cwd)
+                .unwrap_or_else(|_| cwd.to_path_buf())
+                .display()
+                .to_string()
+        });
+    let digest = Sha256::digest(key.as_bytes());
+    let hash = digest
+        .iter()
+        .take(8)
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    format!("project:{hash}")
+}
+
+/// Pattern namespaces already resolved by [`project_pattern_namespace`],
+/// keyed by the directory they were resolved from.
+#[derive(Debug, Default)]
+pub struct PatternNamespaces {
+    resolved: Mutex<HashMap<PathBuf, String>>,
+}
+
+impl PatternNamespaces {
+    /// The pattern namespace for the project containing `cwd`, resolved on
+    /// a blocking thread the first time, since that shells out to git.
+    pub async fn resolve(&self, cwd: &Path) -> anyhow::Result<String> {
+        if let Some(namespace) = self.resolved.lock().await.get(cwd) {
+            return Ok(namespace.clone());
+        }
+        let key = cwd.to_path_buf();
+        let namespace = tokio::task::spawn_blocking({
+            let cwd = key.clone();
+            move || project_pattern_namespace(&cwd)
+        })
+        .await?;
+        self.resolved.lock().await.insert(key, namespace.clone());
+        Ok(namespace)
+    }
+}
+
+/// Returns true when a pattern stored under `pattern_namespace` should be
+/// offered to callers working in `namespace`.
+pub fn pattern_namespace_visible(pattern_namespace: &str, namespace: &str) -> bool {
+    pattern_namespace == GLOBAL_PATTERN_NAMESPACE || pattern_namespace == namespace
+}
+
+pub(crate) fn git_output(cwd: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(cwd)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn project_namespace_is_stable_for_the_same_directory() {
+        let cwd = std::env::temp_dir();
+        let first = project_pattern_namespace(cwd.as_path());
+        let second = project_pattern_namespace(cwd.as_path());
+
+        assert_eq!(first, second);
+        assert_eq!(first.starts_with("project:"), true);
+    }
+
+    #[tokio::test]
+    async fn resolved_namespaces_match_and_are_cached() {
+        let cwd = std::env::temp_dir();
+        let namespaces = PatternNamespaces::default();
+        let resolved = namespaces.resolve(cwd.as_path()).await.expect("resolve");
+
+        assert_eq!(resolved, project_pattern_namespace(cwd.as_path()));
+        assert_eq!(
+            namespaces.resolved.lock().await.clone(),
+            HashMap::from([(cwd, resolved)])
+        );
+    }
+
+    #[test]
+    fn global_patterns_are_visible_from_every_namespace() {
+        assert_eq!(
+            pattern_namespace_visible(GLOBAL_PATTERN_NAMESPACE, "project:abc"),
+            true
+        );
+        assert_eq!(
+            pattern_namespace_visible("project:abc", "project:abc"),
+            true
+        );
+        assert_eq!(
+            pattern_namespace_visible("project:def", "project:abc"),
+            false
+        );
+    }
+}
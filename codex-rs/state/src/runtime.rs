@@ -1,37 +1,61 @@
 use crate::AuditAction;
+use crate::CovenantCheck;
+use crate::CovenantCheckCount;
+use crate::CovenantEvent;
+use crate::CovenantGrant;
+use crate::CovenantHypothesis;
+use crate::CovenantIntent;
+use crate::CovenantOutcome;
+use crate::CovenantPattern;
+use crate::CovenantStorePaths;
+use crate::CovenantTest;
 use crate::DB_ERROR_METRIC;
+use crate::LazyStoreCipher;
 use crate::LogEntry;
 use crate::LogQuery;
 use crate::LogRow;
+use crate::PatternReview;
+use crate::PatternReviewDecision;
+use crate::Redactor;
 use crate::SortKey;
+use crate::StalePattern;
+use crate::StoreCipher;
 use crate::ThreadMemory;
 use crate::ThreadMetadata;
 use crate::ThreadMetadataBuilder;
 use crate::ThreadsPage;
 use crate::apply_rollout_item;
+use crate::canonical_json::to_canonical_json;
 use crate::migrations::MIGRATOR;
 use crate::model::ThreadMemoryRow;
 use crate::model::ThreadRow;
 use crate::model::anchor_from_item;
 use crate::model::datetime_to_epoch_seconds;
 use crate::paths::file_modified_time_utc;
+use crate::store_cipher::open_line;
+use crate::store_cipher::seal_text;
 use chrono::DateTime;
 use chrono::Utc;
 use codex_otel::OtelManager;
 use codex_protocol::ThreadId;
 use codex_protocol::dynamic_tools::DynamicToolSpec;
+use codex_protocol::outcome::Outcome;
 use codex_protocol::protocol::RolloutItem;
+use codex_protocol::test_status::TestStatus;
 use log::LevelFilter;
 use serde_json::Value;
 use sqlx::ConnectOptions;
 use sqlx::QueryBuilder;
 use sqlx::Row;
 use sqlx::Sqlite;
+use sqlx::SqliteConnection;
 use sqlx::SqlitePool;
 use sqlx::sqlite::SqliteConnectOptions;
 use sqlx::sqlite::SqliteJournalMode;
 use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::sqlite::SqliteRow;
 use sqlx::sqlite::SqliteSynchronous;
+use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -43,11 +67,15 @@ pub const STATE_DB_VERSION: u32 = 3;
 
 const METRIC_DB_INIT: &str = "codex.db.init";
 
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
 #[derive(Clone)]
 pub struct StateRuntime {
     codex_home: PathBuf,
     default_provider: String,
     pool: Arc<sqlx::SqlitePool>,
+    redactor: Redactor,
+    cipher: Arc<LazyStoreCipher>,
 }
 
 impl StateRuntime {
@@ -59,9 +87,45 @@ impl StateRuntime {
         default_provider: String,
         otel: Option<OtelManager>,
     ) -> anyhow::Result<Arc<Self>> {
-        tokio::fs::create_dir_all(&codex_home).await?;
-        remove_legacy_state_files(&codex_home).await;
-        let state_path = state_db_path(codex_home.as_path());
+        let db_dir = codex_home.clone();
+        Self::open(
+            db_dir,
+            codex_home,
+            default_provider,
+            LazyStoreCipher::default(),
+            otel,
+        )
+        .await
+    }
+
+    /// Open the covenant store at `paths`, which for a workspace store is a
+    /// separate database from the one in `CODEX_HOME`. The redaction config is
+    /// still read from `CODEX_HOME`, and the store key is the same one.
+    pub async fn init_covenant_store(
+        paths: &CovenantStorePaths,
+        default_provider: String,
+        otel: Option<OtelManager>,
+    ) -> anyhow::Result<Arc<Self>> {
+        Self::open(
+            paths.db_dir.clone(),
+            paths.codex_home.clone(),
+            default_provider,
+            LazyStoreCipher::default(),
+            otel,
+        )
+        .await
+    }
+
+    async fn open(
+        db_dir: PathBuf,
+        codex_home: PathBuf,
+        default_provider: String,
+        cipher: LazyStoreCipher,
+        otel: Option<OtelManager>,
+    ) -> anyhow::Result<Arc<Self>> {
+        tokio::fs::create_dir_all(&db_dir).await?;
+        remove_legacy_state_files(&db_dir).await;
+        let state_path = state_db_path(db_dir.as_path());
         let existed = tokio::fs::try_exists(&state_path).await.unwrap_or(false);
         let pool = match open_sqlite(&state_path).await {
             Ok(db) => Arc::new(db),
@@ -76,10 +140,16 @@ impl StateRuntime {
         if let Some(otel) = otel.as_ref() {
             otel.counter(METRIC_DB_INIT, 1, &[("status", "opened")]);
         }
+        let redactor = Redactor::load(codex_home.as_path()).unwrap_or_else(|err| {
+            warn!("failed to load redaction config, using defaults: {err}");
+            Redactor::default()
+        });
         let runtime = Arc::new(Self {
             pool,
             codex_home,
             default_provider,
+            redactor,
+            cipher: Arc::new(cipher),
         });
         if !existed && let Some(otel) = otel.as_ref() {
             otel.counter(METRIC_DB_INIT, 1, &[("status", "created")]);
@@ -92,6 +162,17 @@ impl StateRuntime {
         self.codex_home.as_path()
     }
 
+    /// Redactor applied to covenant events and audit entries before they are stored.
+    pub fn redactor(&self) -> &Redactor {
+        &self.redactor
+    }
+
+    /// Key the covenant text columns are sealed with, when there is one. It
+    /// is loaded on first use, which is where a bad key surfaces.
+    pub fn store_cipher(&self) -> anyhow::Result<Option<&StoreCipher>> {
+        self.cipher.get()
+    }
+
     /// Get persisted rollout metadata backfill state.
     pub async fn get_backfill_state(&self) -> anyhow::Result<crate::BackfillState> {
         self.ensure_backfill_state_row().await?;
@@ -108,48 +189,558 @@ WHERE id = 1
     }
 
     pub async fn ensure_covenant_version(&self, version: &str) -> anyhow::Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        insert_covenant_version(&mut conn, version).await
+    }
+
+    /// Start a transaction over the covenant tables. Writes made through it
+    /// land together on [`CovenantTransaction::commit`]; dropping it without
+    /// committing discards them.
+    pub async fn begin_covenant_transaction(&self) -> anyhow::Result<CovenantTransaction<'_>> {
+        Ok(CovenantTransaction {
+            tx: self.pool.begin().await?,
+            redactor: &self.redactor,
+            cipher: self.cipher.get()?,
+        })
+    }
+
+    pub async fn insert_audit_action(&self, action: &AuditAction) -> anyhow::Result<()> {
+        let mut tx = self.begin_covenant_transaction().await?;
+        tx.insert_audit_action(action).await?;
+        tx.commit().await
+    }
+
+    pub async fn record_covenant_check(&self, check: &CovenantCheck) -> anyhow::Result<()> {
+        let mut tx = self.begin_covenant_transaction().await?;
+        tx.record_covenant_check(check).await?;
+        tx.commit().await
+    }
+
+    /// Daily check counts from the day containing `since` on, or all of them
+    /// when `since` is `None`, oldest day first.
+    pub async fn list_covenant_check_counts(
+        &self,
+        since: Option<i64>,
+    ) -> anyhow::Result<Vec<CovenantCheckCount>> {
         sqlx::query(
             r#"
-INSERT OR IGNORE INTO covenants (version, created_at)
-VALUES (?, ?)
+SELECT day, scope, capability, allowed, denied
+FROM covenant_check_counts
+WHERE day >= ?
+ORDER BY day ASC, scope ASC, capability ASC
             "#,
         )
-        .bind(version)
-        .bind(Utc::now().timestamp())
-        .execute(self.pool.as_ref())
-        .await?;
-        Ok(())
+        .bind(since.map_or(i64::MIN, start_of_day))
+        .fetch_all(self.pool.as_ref())
+        .await?
+        .into_iter()
+        .map(|row| -> anyhow::Result<CovenantCheckCount> {
+            Ok(CovenantCheckCount {
+                day: row.try_get("day")?,
+                scope: row.try_get("scope")?,
+                capability: row.try_get("capability")?,
+                allowed: row.try_get("allowed")?,
+                denied: row.try_get("denied")?,
+            })
+        })
+        .collect()
     }
 
-    pub async fn insert_audit_action(&self, action: &AuditAction) -> anyhow::Result<()> {
-        self.ensure_covenant_version(action.covenant_version.as_str())
-            .await?;
+    pub async fn insert_covenant_grant(&self, grant: &CovenantGrant) -> anyhow::Result<()> {
         sqlx::query(
             r#"
-INSERT INTO audit_actions (
-    timestamp,
-    actor,
-    action_type,
+INSERT INTO covenant_grants (
+    thread_id,
     scope,
-    covenant_version,
-    event_id,
-    intent_id
+    capability,
+    granted_at,
+    expires_at
 )
-VALUES (?, ?, ?, ?, ?, ?, ?)
+VALUES (?, ?, ?, ?, ?)
             "#,
         )
-        .bind(action.timestamp)
-        .bind(action.actor.as_str())
-        .bind(action.action_type.as_str())
-        .bind(action.scope.as_str())
-        .bind(action.covenant_version.as_str())
-        .bind(action.event_id.as_deref())
-        .bind(action.intent_id.as_deref())
+        .bind(grant.thread_id.as_str())
+        .bind(grant.scope.as_str())
+        .bind(grant.capability.as_str())
+        .bind(grant.granted_at)
+        .bind(grant.expires_at)
         .execute(self.pool.as_ref())
         .await?;
         Ok(())
     }
 
+    /// When the latest grant of `capability` to the thread in `scope` expires,
+    /// or `None` when no grant is active at `now`.
+    pub async fn covenant_grant_expiry(
+        &self,
+        thread_id: &str,
+        scope: &str,
+        capability: &str,
+        now: i64,
+    ) -> anyhow::Result<Option<i64>> {
+        let expires_at = sqlx::query_scalar::<_, Option<i64>>(
+            r#"
+SELECT MAX(expires_at)
+FROM covenant_grants
+WHERE thread_id = ? AND scope = ? AND capability = ? AND expires_at > ?
+            "#,
+        )
+        .bind(thread_id)
+        .bind(scope)
+        .bind(capability)
+        .bind(now)
+        .fetch_one(self.pool.as_ref())
+        .await?;
+        Ok(expires_at)
+    }
+
+    /// Persist an event with its intent, hypotheses, tests, and outcomes in a
+    /// single transaction.
+    pub async fn insert_covenant_event(&self, event: &CovenantEvent) -> anyhow::Result<()> {
+        let mut tx = self.begin_covenant_transaction().await?;
+        tx.insert_covenant_event(event).await?;
+        tx.commit().await
+    }
+
+    /// Load every closed covenant event with its linked records, oldest first.
+    pub async fn list_closed_covenant_events(&self) -> anyhow::Result<Vec<CovenantEvent>> {
+        self.list_covenant_events_with_status(Some("closed")).await
+    }
+
+    /// Load every covenant event, open or closed, with its linked records,
+    /// oldest first.
+    pub async fn list_covenant_events(&self) -> anyhow::Result<Vec<CovenantEvent>> {
+        self.list_covenant_events_with_status(None).await
+    }
+
+    async fn list_covenant_events_with_status(
+        &self,
+        status: Option<&str>,
+    ) -> anyhow::Result<Vec<CovenantEvent>> {
+        let rows = sqlx::query(
+            r#"
+SELECT id, created_at, description, domain_signature, tags, session_id, rollout_path
+FROM events
+WHERE ? IS NULL OR status = ?
+ORDER BY created_at ASC, id ASC
+            "#,
+        )
+        .bind(status)
+        .bind(status)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        let mut events = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id: String = row.try_get("id")?;
+            let intent = sqlx::query(
+                r#"
+SELECT id, goal, constraints, success_signal, confidence
+FROM intent_tokens
+WHERE event_id = ?
+ORDER BY created_at ASC, id ASC
+LIMIT 1
+                "#,
+            )
+            .bind(id.as_str())
+            .fetch_optional(self.pool.as_ref())
+            .await?
+            .map(|row| -> anyhow::Result<CovenantIntent> {
+                Ok(CovenantIntent {
+                    id: row.try_get("id")?,
+                    goal: self.open_text(&row, "goal")?,
+                    constraints: self.open_text(&row, "constraints")?,
+                    success_signal: self.open_text(&row, "success_signal")?,
+                    confidence: row.try_get("confidence")?,
+                })
+            })
+            .transpose()?;
+
+            let hypotheses = sqlx::query(
+                r#"
+SELECT id, model_type, probability, falsifiers, domain_signature
+FROM hypotheses
+WHERE event_id = ?
+ORDER BY id ASC
+                "#,
+            )
+            .bind(id.as_str())
+            .fetch_all(self.pool.as_ref())
+            .await?
+            .into_iter()
+            .map(|row| -> anyhow::Result<CovenantHypothesis> {
+                let falsifiers = self.open_text(&row, "falsifiers")?;
+                Ok(CovenantHypothesis {
+                    id: row.try_get("id")?,
+                    model_type: self.open_text(&row, "model_type")?,
+                    probability: row.try_get("probability")?,
+                    falsifiers: serde_json::from_str(falsifiers.as_str())?,
+                    domain_signature: row.try_get("domain_signature")?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let tests = sqlx::query(
+                r#"
+SELECT id, hypothesis_id, description, result, evidence_ref
+FROM tests
+WHERE event_id = ?
+ORDER BY created_at ASC, id ASC
+                "#,
+            )
+            .bind(id.as_str())
+            .fetch_all(self.pool.as_ref())
+            .await?
+            .into_iter()
+            .map(|row| -> anyhow::Result<CovenantTest> {
+                let result: String = row.try_get("result")?;
+                Ok(CovenantTest {
+                    id: row.try_get("id")?,
+                    hypothesis_id: row.try_get("hypothesis_id")?,
+                    description: self.open_text(&row, "description")?,
+                    result: TestStatus::from_text(result.as_str()),
+                    evidence_ref: row.try_get("evidence_ref")?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let outcomes = sqlx::query(
+                r#"
+SELECT id, summary, outcome, evidence_refs, evidence
+FROM outcomes
+WHERE event_id = ?
+ORDER BY created_at ASC, id ASC
+                "#,
+            )
+            .bind(id.as_str())
+            .fetch_all(self.pool.as_ref())
+            .await?
+            .into_iter()
+            .map(|row| -> anyhow::Result<CovenantOutcome> {
+                let outcome: String = row.try_get("outcome")?;
+                let evidence_refs: String = row.try_get("evidence_refs")?;
+                let evidence: String = row.try_get("evidence")?;
+                Ok(CovenantOutcome {
+                    id: row.try_get("id")?,
+                    summary: self.open_text(&row, "summary")?,
+                    outcome: Outcome::from_text(outcome.as_str()),
+                    evidence_refs: serde_json::from_str(evidence_refs.as_str())?,
+                    evidence: serde_json::from_str(evidence.as_str())?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let tags: String = row.try_get("tags")?;
+            events.push(CovenantEvent {
+                created_at: row.try_get("created_at")?,
+                description: self.open_text(&row, "description")?,
+                domain_signature: row.try_get("domain_signature")?,
+                intent,
+                hypotheses,
+                tests,
+                outcomes,
+                tags: serde_json::from_str(tags.as_str())?,
+                session_id: row.try_get("session_id")?,
+                rollout_path: row.try_get("rollout_path")?,
+                id,
+            });
+        }
+        Ok(events)
+    }
+
+    /// The text column `column` of `row`, decrypted when it was sealed.
+    fn open_text(&self, row: &SqliteRow, column: &str) -> anyhow::Result<String> {
+        let text: String = row.try_get(column)?;
+        Ok(open_line(text.as_str(), self.cipher.get()?)?.into_owned())
+    }
+
+    /// Number of covenant events in the store, open or closed.
+    pub async fn covenant_event_count(&self) -> anyhow::Result<usize> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(1) FROM events")
+            .fetch_one(self.pool.as_ref())
+            .await?;
+        Ok(usize::try_from(count).unwrap_or_default())
+    }
+
+    /// Remove events and, through cascading deletes, every record linked to
+    /// them, in a single transaction.
+    pub async fn delete_covenant_events(&self, ids: &[String]) -> anyhow::Result<()> {
+        let mut tx = self.begin_covenant_transaction().await?;
+        tx.delete_covenant_events(ids).await?;
+        tx.commit().await
+    }
+
+    /// Hashes of the artifact files attached to events still in the store, or
+    /// `None` when no artifact has ever been attached.
+    pub async fn list_attached_artifact_hashes(&self) -> anyhow::Result<Option<HashSet<String>>> {
+        // The artifacts table is created by `handshakeos-e` on first use.
+        let has_table = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(1) FROM sqlite_master WHERE type = 'table' AND name = 'artifacts'",
+        )
+        .fetch_one(self.pool.as_ref())
+        .await?
+            > 0;
+        if !has_table {
+            return Ok(None);
+        }
+        let hashes = sqlx::query_scalar::<_, String>("SELECT DISTINCT sha256 FROM artifacts")
+            .fetch_all(self.pool.as_ref())
+            .await?;
+        Ok(Some(hashes.into_iter().collect()))
+    }
+
+    /// Rebuild the database file so space freed by deletes is returned to the
+    /// filesystem.
+    pub async fn vacuum(&self) -> anyhow::Result<()> {
+        sqlx::query("VACUUM").execute(self.pool.as_ref()).await?;
+        Ok(())
+    }
+
+    /// Insert a pattern into the live store, replacing any pattern with the same id.
+    pub async fn upsert_covenant_pattern(&self, pattern: &CovenantPattern) -> anyhow::Result<()> {
+        let mut tx = self.begin_covenant_transaction().await?;
+        tx.upsert_covenant_pattern(pattern).await?;
+        tx.commit().await
+    }
+
+    /// Load every pattern in the live store that is not archived, oldest
+    /// first. With `scope`, only the patterns of that covenant scope are
+    /// loaded.
+    pub async fn list_covenant_patterns(
+        &self,
+        scope: Option<&str>,
+    ) -> anyhow::Result<Vec<CovenantPattern>> {
+        let cipher = self.cipher.get()?;
+        sqlx::query(
+            r#"
+SELECT
+    id,
+    trigger,
+    invariant,
+    counterexample,
+    best_response,
+    domain_signature,
+    evidence_refs,
+    namespace,
+    scope,
+    created_at,
+    anti_pattern,
+    tags,
+    counterexamples,
+    supports,
+    contradictions
+FROM patterns
+WHERE archived_at IS NULL AND (? IS NULL OR scope = ?)
+ORDER BY created_at ASC, id ASC
+            "#,
+        )
+        .bind(scope)
+        .bind(scope)
+        .fetch_all(self.pool.as_ref())
+        .await?
+        .iter()
+        .map(|row| covenant_pattern_from_row(row, cipher))
+        .collect()
+    }
+
+    /// The live pattern with `id`, or `None` when there is none or it is
+    /// archived.
+    pub async fn get_covenant_pattern(&self, id: &str) -> anyhow::Result<Option<CovenantPattern>> {
+        let cipher = self.cipher.get()?;
+        sqlx::query(
+            r#"
+SELECT
+    id,
+    trigger,
+    invariant,
+    counterexample,
+    best_response,
+    domain_signature,
+    evidence_refs,
+    namespace,
+    scope,
+    created_at,
+    anti_pattern,
+    tags,
+    counterexamples,
+    supports,
+    contradictions
+FROM patterns
+WHERE archived_at IS NULL AND id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(self.pool.as_ref())
+        .await?
+        .as_ref()
+        .map(|row| covenant_pattern_from_row(row, cipher))
+        .transpose()
+    }
+
+    /// Live patterns last confirmed, or created when never confirmed, before
+    /// `cutoff`, least recently confirmed first. With `scope`, only the
+    /// patterns of that covenant scope are loaded.
+    pub async fn list_stale_covenant_patterns(
+        &self,
+        cutoff: i64,
+        scope: Option<&str>,
+    ) -> anyhow::Result<Vec<StalePattern>> {
+        let cipher = self.cipher.get()?;
+        sqlx::query(
+            r#"
+SELECT
+    id,
+    trigger,
+    invariant,
+    counterexample,
+    best_response,
+    domain_signature,
+    evidence_refs,
+    namespace,
+    scope,
+    created_at,
+    anti_pattern,
+    tags,
+    counterexamples,
+    supports,
+    contradictions,
+    COALESCE(confirmed_at, created_at) AS last_confirmed_at
+FROM patterns
+WHERE archived_at IS NULL
+    AND COALESCE(confirmed_at, created_at) < ?
+    AND (? IS NULL OR scope = ?)
+ORDER BY last_confirmed_at ASC, id ASC
+            "#,
+        )
+        .bind(cutoff)
+        .bind(scope)
+        .bind(scope)
+        .fetch_all(self.pool.as_ref())
+        .await?
+        .iter()
+        .map(|row| -> anyhow::Result<StalePattern> {
+            Ok(StalePattern {
+                pattern: covenant_pattern_from_row(row, cipher)?,
+                last_confirmed_at: row.try_get("last_confirmed_at")?,
+            })
+        })
+        .collect()
+    }
+
+    /// Mark live patterns as confirmed still to hold at `at`. Returns how
+    /// many were updated.
+    pub async fn confirm_covenant_patterns(&self, ids: &[String], at: i64) -> anyhow::Result<u64> {
+        self.touch_live_patterns("confirmed_at", ids, at).await
+    }
+
+    /// Archive live patterns at `at` so they are no longer matched. Returns
+    /// how many were archived.
+    pub async fn archive_covenant_patterns(&self, ids: &[String], at: i64) -> anyhow::Result<u64> {
+        self.touch_live_patterns("archived_at", ids, at).await
+    }
+
+    /// Set `column` to `at` on the live patterns in `ids`.
+    async fn touch_live_patterns(
+        &self,
+        column: &'static str,
+        ids: &[String],
+        at: i64,
+    ) -> anyhow::Result<u64> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let mut builder = QueryBuilder::<Sqlite>::new(format!("UPDATE patterns SET {column} = "));
+        builder.push_bind(at);
+        builder.push(" WHERE archived_at IS NULL AND id IN (");
+        let mut separated = builder.separated(", ");
+        for id in ids {
+            separated.push_bind(id);
+        }
+        separated.push_unseparated(")");
+        Ok(builder
+            .build()
+            .execute(self.pool.as_ref())
+            .await?
+            .rows_affected())
+    }
+
+    /// Count one reported outcome of applying a live pattern's best response
+    /// as supporting or contradicting it. Returns the updated pattern, or
+    /// `None` when no live pattern has the id.
+    pub async fn record_pattern_feedback(
+        &self,
+        id: &str,
+        supports: bool,
+    ) -> anyhow::Result<Option<CovenantPattern>> {
+        let cipher = self.cipher.get()?;
+        sqlx::query(
+            r#"
+UPDATE patterns
+SET supports = supports + ?, contradictions = contradictions + ?
+WHERE archived_at IS NULL AND id = ?
+RETURNING
+    id,
+    trigger,
+    invariant,
+    counterexample,
+    best_response,
+    domain_signature,
+    evidence_refs,
+    namespace,
+    scope,
+    created_at,
+    anti_pattern,
+    tags,
+    counterexamples,
+    supports,
+    contradictions
+            "#,
+        )
+        .bind(i64::from(supports))
+        .bind(i64::from(!supports))
+        .bind(id)
+        .fetch_optional(self.pool.as_ref())
+        .await?
+        .as_ref()
+        .map(|row| covenant_pattern_from_row(row, cipher))
+        .transpose()
+    }
+
+    /// Record the review decision for one version of a suggested pattern.
+    pub async fn insert_pattern_review(&self, review: &PatternReview) -> anyhow::Result<()> {
+        let mut tx = self.begin_covenant_transaction().await?;
+        tx.insert_pattern_review(review).await?;
+        tx.commit().await
+    }
+
+    /// Load every recorded review decision, oldest first.
+    pub async fn list_pattern_reviews(&self) -> anyhow::Result<Vec<PatternReview>> {
+        sqlx::query(
+            r#"
+SELECT pattern_key, version, decision, pattern_id, reviewed_at
+FROM pattern_reviews
+ORDER BY reviewed_at ASC, pattern_key ASC, version ASC
+            "#,
+        )
+        .fetch_all(self.pool.as_ref())
+        .await?
+        .into_iter()
+        .map(|row| -> anyhow::Result<PatternReview> {
+            let version: i64 = row.try_get("version")?;
+            let decision: String = row.try_get("decision")?;
+            Ok(PatternReview {
+                pattern_key: row.try_get("pattern_key")?,
+                version: u64::try_from(version)?,
+                decision: PatternReviewDecision::parse(decision.as_str()).ok_or_else(|| {
+                    anyhow::anyhow!("unknown pattern review decision '{decision}'")
+                })?,
+                pattern_id: row.try_get("pattern_id")?,
+                reviewed_at: row.try_get("reviewed_at")?,
+            })
+        })
+        .collect()
+    }
+
     /// Mark rollout metadata backfill as running.
     pub async fn mark_backfill_running(&self) -> anyhow::Result<()> {
         self.ensure_backfill_state_row().await?;
@@ -778,53 +1369,385 @@ ON CONFLICT(id) DO NOTHING
     }
 }
 
-fn push_log_filters<'a>(builder: &mut QueryBuilder<'a, Sqlite>, query: &'a LogQuery) {
-    if let Some(level_upper) = query.level_upper.as_ref() {
-        builder
-            .push(" AND UPPER(level) = ")
-            .push_bind(level_upper.as_str());
-    }
-    if let Some(from_ts) = query.from_ts {
-        builder.push(" AND ts >= ").push_bind(from_ts);
-    }
-    if let Some(to_ts) = query.to_ts {
-        builder.push(" AND ts <= ").push_bind(to_ts);
-    }
-    push_like_filters(builder, "module_path", &query.module_like);
-    push_like_filters(builder, "file", &query.file_like);
-    let has_thread_filter = !query.thread_ids.is_empty() || query.include_threadless;
-    if has_thread_filter {
-        builder.push(" AND (");
-        let mut needs_or = false;
-        for thread_id in &query.thread_ids {
-            if needs_or {
-                builder.push(" OR ");
-            }
-            builder.push("thread_id = ").push_bind(thread_id.as_str());
-            needs_or = true;
-        }
-        if query.include_threadless {
-            if needs_or {
-                builder.push(" OR ");
-            }
-            builder.push("thread_id IS NULL");
-        }
-        builder.push(")");
-    }
-    if let Some(after_id) = query.after_id {
-        builder.push(" AND id > ").push_bind(after_id);
-    }
+/// Covenant store writes that commit together or not at all, so an event
+/// mutation and the audit action recording it cannot be split by a crash.
+pub struct CovenantTransaction<'a> {
+    tx: sqlx::Transaction<'static, Sqlite>,
+    redactor: &'a Redactor,
+    cipher: Option<&'a StoreCipher>,
 }
 
-fn push_like_filters<'a>(
-    builder: &mut QueryBuilder<'a, Sqlite>,
-    column: &str,
-    filters: &'a [String],
-) {
-    if filters.is_empty() {
-        return;
+impl CovenantTransaction<'_> {
+    /// `text` as its column stores it, sealed when there is a store key.
+    fn seal(&self, text: &str) -> anyhow::Result<String> {
+        seal_text(text, self.cipher)
     }
-    builder.push(" AND (");
+
+    pub async fn insert_audit_action(&mut self, action: &AuditAction) -> anyhow::Result<()> {
+        let action = self.redactor.redact_audit_action(action);
+        insert_covenant_version(&mut self.tx, action.covenant_version.as_str()).await?;
+        sqlx::query(
+            r#"
+INSERT INTO audit_actions (
+    timestamp,
+    actor,
+    action_type,
+    scope,
+    covenant_version,
+    event_id,
+    intent_id
+)
+VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(action.timestamp)
+        .bind(self.seal(action.actor.as_str())?)
+        .bind(action.action_type.as_str())
+        .bind(action.scope.as_str())
+        .bind(action.covenant_version.as_str())
+        .bind(action.event_id.as_deref())
+        .bind(action.intent_id.as_deref())
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Persist an event with its intent, hypotheses, tests, and outcomes.
+    pub async fn insert_covenant_event(&mut self, event: &CovenantEvent) -> anyhow::Result<()> {
+        let event = self.redactor.redact_covenant_event(event);
+        sqlx::query(
+            r#"
+INSERT INTO events (
+    id,
+    created_at,
+    description,
+    domain_signature,
+    status,
+    tags,
+    session_id,
+    rollout_path
+)
+VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(event.id.as_str())
+        .bind(event.created_at)
+        .bind(self.seal(event.description.as_str())?)
+        .bind(event.domain_signature.as_str())
+        .bind(event.status())
+        .bind(to_canonical_json(&event.tags)?)
+        .bind(event.session_id.as_deref())
+        .bind(event.rollout_path.as_deref())
+        .execute(&mut *self.tx)
+        .await?;
+
+        if let Some(intent) = event.intent.as_ref() {
+            sqlx::query(
+                r#"
+INSERT INTO intent_tokens (id, event_id, goal, constraints, success_signal, confidence, created_at)
+VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(intent.id.as_str())
+            .bind(event.id.as_str())
+            .bind(self.seal(intent.goal.as_str())?)
+            .bind(self.seal(intent.constraints.as_str())?)
+            .bind(self.seal(intent.success_signal.as_str())?)
+            .bind(intent.confidence)
+            .bind(event.created_at)
+            .execute(&mut *self.tx)
+            .await?;
+        }
+
+        for hypothesis in &event.hypotheses {
+            sqlx::query(
+                r#"
+INSERT INTO hypotheses (id, event_id, model_type, probability, falsifiers, domain_signature)
+VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(hypothesis.id.as_str())
+            .bind(event.id.as_str())
+            .bind(self.seal(hypothesis.model_type.as_str())?)
+            .bind(hypothesis.probability)
+            .bind(self.seal(&to_canonical_json(&hypothesis.falsifiers)?)?)
+            .bind(hypothesis.domain_signature.as_str())
+            .execute(&mut *self.tx)
+            .await?;
+        }
+
+        for test in &event.tests {
+            sqlx::query(
+                r#"
+INSERT INTO tests (id, event_id, hypothesis_id, description, result, evidence_ref, created_at)
+VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(test.id.as_str())
+            .bind(event.id.as_str())
+            .bind(test.hypothesis_id.as_str())
+            .bind(self.seal(test.description.as_str())?)
+            .bind(test.result.as_str())
+            .bind(test.evidence_ref.as_str())
+            .bind(event.created_at)
+            .execute(&mut *self.tx)
+            .await?;
+        }
+
+        for outcome in &event.outcomes {
+            sqlx::query(
+                r#"
+INSERT INTO outcomes (id, event_id, summary, outcome, evidence_refs, evidence, created_at)
+VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(outcome.id.as_str())
+            .bind(event.id.as_str())
+            .bind(self.seal(outcome.summary.as_str())?)
+            .bind(outcome.outcome.as_str())
+            .bind(to_canonical_json(&outcome.evidence_refs)?)
+            .bind(to_canonical_json(&outcome.evidence)?)
+            .bind(event.created_at)
+            .execute(&mut *self.tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove events and, through cascading deletes, every record linked to
+    /// them.
+    pub async fn delete_covenant_events(&mut self, ids: &[String]) -> anyhow::Result<()> {
+        for id in ids {
+            sqlx::query("DELETE FROM events WHERE id = ?")
+                .bind(id.as_str())
+                .execute(&mut *self.tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Insert a pattern into the live store, replacing any pattern with the
+    /// same id in the same scope and bringing it back if it was archived. A
+    /// pattern with the id in another scope is left alone and the upsert
+    /// fails.
+    pub async fn upsert_covenant_pattern(
+        &mut self,
+        pattern: &CovenantPattern,
+    ) -> anyhow::Result<()> {
+        let pattern = self.redactor.redact_covenant_pattern(pattern);
+        let row = sqlx::query(
+            r#"
+INSERT INTO patterns (
+    id,
+    trigger,
+    invariant,
+    counterexample,
+    best_response,
+    domain_signature,
+    evidence_refs,
+    namespace,
+    scope,
+    created_at,
+    anti_pattern,
+    tags,
+    counterexamples,
+    supports,
+    contradictions
+)
+VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+ON CONFLICT(id) DO UPDATE SET
+    trigger = excluded.trigger,
+    invariant = excluded.invariant,
+    counterexample = excluded.counterexample,
+    best_response = excluded.best_response,
+    domain_signature = excluded.domain_signature,
+    evidence_refs = excluded.evidence_refs,
+    namespace = excluded.namespace,
+    scope = excluded.scope,
+    anti_pattern = excluded.anti_pattern,
+    tags = excluded.tags,
+    counterexamples = excluded.counterexamples,
+    archived_at = NULL
+WHERE patterns.scope = excluded.scope
+RETURNING id
+            "#,
+        )
+        .bind(pattern.id.as_str())
+        .bind(self.seal(&pattern.trigger)?)
+        .bind(self.seal(&pattern.invariant)?)
+        .bind(self.seal(&pattern.counterexample)?)
+        .bind(self.seal(&pattern.best_response)?)
+        .bind(pattern.domain_signature.as_str())
+        .bind(to_canonical_json(&pattern.evidence_refs)?)
+        .bind(pattern.namespace.as_str())
+        .bind(pattern.scope.as_str())
+        .bind(pattern.created_at)
+        .bind(pattern.anti_pattern)
+        .bind(to_canonical_json(&pattern.tags)?)
+        .bind(to_canonical_json(&pattern.counterexamples)?)
+        .bind(i64::try_from(pattern.supports)?)
+        .bind(i64::try_from(pattern.contradictions)?)
+        .fetch_optional(&mut *self.tx)
+        .await?;
+        // The update is skipped, and nothing returned, when the pattern
+        // belongs to another scope.
+        if row.is_none() {
+            anyhow::bail!(
+                "pattern {} belongs to another covenant scope than {}",
+                pattern.id,
+                pattern.scope
+            );
+        }
+        Ok(())
+    }
+
+    /// Record the review decision for one version of a suggested pattern.
+    pub async fn insert_pattern_review(&mut self, review: &PatternReview) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+INSERT INTO pattern_reviews (pattern_key, version, decision, pattern_id, reviewed_at)
+VALUES (?, ?, ?, ?, ?)
+ON CONFLICT(pattern_key, version) DO UPDATE SET
+    decision = excluded.decision,
+    pattern_id = excluded.pattern_id,
+    reviewed_at = excluded.reviewed_at
+            "#,
+        )
+        .bind(review.pattern_key.as_str())
+        .bind(i64::try_from(review.version)?)
+        .bind(review.decision.as_str())
+        .bind(review.pattern_id.as_deref())
+        .bind(review.reviewed_at)
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Count `check` towards its scope and capability's total for the day.
+    pub async fn record_covenant_check(&mut self, check: &CovenantCheck) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+INSERT INTO covenant_check_counts (day, scope, capability, allowed, denied)
+VALUES (?, ?, ?, ?, ?)
+ON CONFLICT(day, scope, capability) DO UPDATE SET
+    allowed = allowed + excluded.allowed,
+    denied = denied + excluded.denied
+            "#,
+        )
+        .bind(start_of_day(check.timestamp))
+        .bind(check.scope.as_str())
+        .bind(check.capability.as_str())
+        .bind(i64::from(check.allowed))
+        .bind(i64::from(!check.allowed))
+        .execute(&mut *self.tx)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn commit(self) -> anyhow::Result<()> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// The pattern in `row`, with its text columns opened with `cipher`.
+fn covenant_pattern_from_row(
+    row: &SqliteRow,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<CovenantPattern> {
+    let evidence_refs: String = row.try_get("evidence_refs")?;
+    let tags: String = row.try_get("tags")?;
+    let counterexamples: String = row.try_get("counterexamples")?;
+    let open_text = |column: &str| -> anyhow::Result<String> {
+        let text: String = row.try_get(column)?;
+        Ok(open_line(text.as_str(), cipher)?.into_owned())
+    };
+    Ok(CovenantPattern {
+        id: row.try_get("id")?,
+        trigger: open_text("trigger")?,
+        invariant: open_text("invariant")?,
+        counterexample: open_text("counterexample")?,
+        best_response: open_text("best_response")?,
+        domain_signature: row.try_get("domain_signature")?,
+        evidence_refs: serde_json::from_str(evidence_refs.as_str())?,
+        namespace: row.try_get("namespace")?,
+        scope: row.try_get("scope")?,
+        created_at: row.try_get("created_at")?,
+        anti_pattern: row.try_get("anti_pattern")?,
+        tags: serde_json::from_str(tags.as_str())?,
+        counterexamples: serde_json::from_str(counterexamples.as_str())?,
+        supports: u64::try_from(row.try_get::<i64, _>("supports")?)?,
+        contradictions: u64::try_from(row.try_get::<i64, _>("contradictions")?)?,
+    })
+}
+
+/// Start of the UTC day containing `timestamp`.
+fn start_of_day(timestamp: i64) -> i64 {
+    timestamp - timestamp.rem_euclid(SECONDS_PER_DAY)
+}
+
+async fn insert_covenant_version(conn: &mut SqliteConnection, version: &str) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+INSERT OR IGNORE INTO covenants (version, created_at)
+VALUES (?, ?)
+        "#,
+    )
+    .bind(version)
+    .bind(Utc::now().timestamp())
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+fn push_log_filters<'a>(builder: &mut QueryBuilder<'a, Sqlite>, query: &'a LogQuery) {
+    if let Some(level_upper) = query.level_upper.as_ref() {
+        builder
+            .push(" AND UPPER(level) = ")
+            .push_bind(level_upper.as_str());
+    }
+    if let Some(from_ts) = query.from_ts {
+        builder.push(" AND ts >= ").push_bind(from_ts);
+    }
+    if let Some(to_ts) = query.to_ts {
+        builder.push(" AND ts <= ").push_bind(to_ts);
+    }
+    push_like_filters(builder, "module_path", &query.module_like);
+    push_like_filters(builder, "file", &query.file_like);
+    let has_thread_filter = !query.thread_ids.is_empty() || query.include_threadless;
+    if has_thread_filter {
+        builder.push(" AND (");
+        let mut needs_or = false;
+        for thread_id in &query.thread_ids {
+            if needs_or {
+                builder.push(" OR ");
+            }
+            builder.push("thread_id = ").push_bind(thread_id.as_str());
+            needs_or = true;
+        }
+        if query.include_threadless {
+            if needs_or {
+                builder.push(" OR ");
+            }
+            builder.push("thread_id IS NULL");
+        }
+        builder.push(")");
+    }
+    if let Some(after_id) = query.after_id {
+        builder.push(" AND id > ").push_bind(after_id);
+    }
+}
+
+fn push_like_filters<'a>(
+    builder: &mut QueryBuilder<'a, Sqlite>,
+    column: &str,
+    filters: &'a [String],
+) {
+    if filters.is_empty() {
+        return;
+    }
+    builder.push(" AND (");
     for (idx, filter) in filters.iter().enumerate() {
         if idx > 0 {
             builder.push(" OR ");
@@ -1006,16 +1929,27 @@ fn push_thread_order_and_limit(
 
 #[cfg(test)]
 mod tests {
+    use super::CovenantCheck;
+    use super::CovenantCheckCount;
+    use super::CovenantGrant;
+    use super::CovenantPattern;
+    use super::PatternReview;
+    use super::PatternReviewDecision;
+    use super::SECONDS_PER_DAY;
     use super::STATE_DB_FILENAME;
     use super::STATE_DB_VERSION;
+    use super::StalePattern;
     use super::StateRuntime;
     use super::ThreadMetadata;
     use super::state_db_filename;
+    use crate::PatternCounterexample;
     use chrono::DateTime;
     use chrono::Utc;
     use codex_protocol::ThreadId;
+    use codex_protocol::outcome::Outcome;
     use codex_protocol::protocol::AskForApproval;
     use codex_protocol::protocol::SandboxPolicy;
+    use codex_protocol::test_status::TestStatus;
     use pretty_assertions::assert_eq;
     use sqlx::Row;
     use std::path::Path;
@@ -1192,6 +2126,724 @@ ORDER BY name
         let _ = tokio::fs::remove_dir_all(codex_home).await;
     }
 
+    #[tokio::test]
+    async fn insert_covenant_event_persists_linked_records() {
+        let codex_home = unique_temp_dir();
+        let runtime = StateRuntime::init(codex_home.clone(), "test-provider".to_string(), None)
+            .await
+            .expect("initialize runtime");
+
+        let event = crate::CovenantEvent {
+            id: "evt-1".to_string(),
+            created_at: 1_735_000_002,
+            description: "pressed play".to_string(),
+            domain_signature: r#"[{"domain":"audio","weight":1.0}]"#.to_string(),
+            intent: Some(crate::CovenantIntent {
+                id: "evt-1:intent".to_string(),
+                goal: "route audio".to_string(),
+                constraints: "none".to_string(),
+                success_signal: "sound in headset".to_string(),
+                confidence: 0.8,
+            }),
+            hypotheses: vec![crate::CovenantHypothesis {
+                id: "evt-1:H1".to_string(),
+                model_type: "bluetooth output not selected".to_string(),
+                probability: 0.9,
+                falsifiers: vec!["speaker plays".to_string()],
+                domain_signature: r#"[{"domain":"audio","weight":1.0}]"#.to_string(),
+            }],
+            tests: vec![crate::CovenantTest {
+                id: "evt-1:R1:H1".to_string(),
+                hypothesis_id: "evt-1:H1".to_string(),
+                description: "switch output".to_string(),
+                result: TestStatus::Pass,
+                evidence_ref: "capture://evt-1/T1".to_string(),
+            }],
+            outcomes: vec![crate::CovenantOutcome {
+                id: "evt-1:O1".to_string(),
+                summary: "audio routed to bluetooth".to_string(),
+                outcome: codex_protocol::outcome::Outcome::Success,
+                evidence_refs: vec!["capture://evt-1/T1".to_string()],
+                evidence: vec![crate::CovenantEvidence::test("capture://evt-1/T1")],
+            }],
+            tags: vec!["audio".to_string(), "flaky-test".to_string()],
+            session_id: Some("thread-1".to_string()),
+            rollout_path: Some("/sessions/rollout-thread-1.jsonl".to_string()),
+        };
+        runtime
+            .insert_covenant_event(&event)
+            .await
+            .expect("insert covenant event");
+
+        let status = sqlx::query_scalar::<_, String>("SELECT status FROM events WHERE id = ?")
+            .bind("evt-1")
+            .fetch_one(runtime.pool.as_ref())
+            .await
+            .expect("query event status");
+        assert_eq!(status, "closed".to_string());
+
+        let counts = sqlx::query(
+            r#"
+SELECT
+    (SELECT COUNT(1) FROM intent_tokens WHERE event_id = 'evt-1') AS intents,
+    (SELECT COUNT(1) FROM hypotheses WHERE event_id = 'evt-1') AS hypotheses,
+    (SELECT COUNT(1) FROM tests WHERE event_id = 'evt-1') AS tests,
+    (SELECT COUNT(1) FROM outcomes WHERE event_id = 'evt-1') AS outcomes
+            "#,
+        )
+        .fetch_one(runtime.pool.as_ref())
+        .await
+        .map(|row| {
+            (
+                row.get::<i64, _>("intents"),
+                row.get::<i64, _>("hypotheses"),
+                row.get::<i64, _>("tests"),
+                row.get::<i64, _>("outcomes"),
+            )
+        })
+        .expect("query linked counts");
+        assert_eq!(counts, (1, 1, 1, 1));
+
+        let listed = runtime
+            .list_closed_covenant_events()
+            .await
+            .expect("list closed covenant events");
+        assert_eq!(listed, vec![event.clone()]);
+
+        let open = crate::CovenantEvent {
+            id: "evt-2".to_string(),
+            created_at: 1_735_000_003,
+            intent: None,
+            hypotheses: Vec::new(),
+            tests: Vec::new(),
+            outcomes: Vec::new(),
+            ..event.clone()
+        };
+        runtime
+            .insert_covenant_event(&open)
+            .await
+            .expect("insert open covenant event");
+        assert_eq!(
+            (
+                runtime
+                    .list_closed_covenant_events()
+                    .await
+                    .expect("list closed covenant events"),
+                runtime
+                    .list_covenant_events()
+                    .await
+                    .expect("list covenant events"),
+            ),
+            (vec![event.clone()], vec![event, open])
+        );
+
+        let _ = tokio::fs::remove_dir_all(codex_home).await;
+    }
+
+    #[tokio::test]
+    async fn sealed_text_columns_open_with_the_store_key() {
+        let codex_home = unique_temp_dir();
+        let runtime = StateRuntime::open(
+            codex_home.clone(),
+            codex_home.clone(),
+            "test-provider".to_string(),
+            crate::LazyStoreCipher::loaded(Some(crate::StoreCipher::generate())),
+            None,
+        )
+        .await
+        .expect("initialize runtime");
+
+        let event = crate::CovenantEvent {
+            id: "evt-1".to_string(),
+            created_at: 1_735_000_002,
+            description: "vpn drops on wifi".to_string(),
+            domain_signature: String::new(),
+            intent: Some(crate::CovenantIntent {
+                id: "evt-1:intent".to_string(),
+                goal: "stay connected".to_string(),
+                constraints: "no reboot".to_string(),
+                success_signal: "ping holds".to_string(),
+                confidence: 0.5,
+            }),
+            hypotheses: vec![crate::CovenantHypothesis {
+                id: "evt-1:H1".to_string(),
+                model_type: "mtu too large".to_string(),
+                probability: 0.6,
+                falsifiers: vec!["small packets drop".to_string()],
+                domain_signature: String::new(),
+            }],
+            tests: vec![crate::CovenantTest {
+                id: "evt-1:R1:H1".to_string(),
+                hypothesis_id: "evt-1:H1".to_string(),
+                description: "lower mtu".to_string(),
+                result: TestStatus::Pass,
+                evidence_ref: "capture://evt-1/T1".to_string(),
+            }],
+            outcomes: vec![crate::CovenantOutcome {
+                id: "evt-1:O1".to_string(),
+                summary: "mtu lowered".to_string(),
+                outcome: codex_protocol::outcome::Outcome::Success,
+                evidence_refs: vec!["capture://evt-1/T1".to_string()],
+                evidence: vec![crate::CovenantEvidence::test("capture://evt-1/T1")],
+            }],
+            tags: Vec::new(),
+            session_id: None,
+            rollout_path: None,
+        };
+        let pattern = CovenantPattern {
+            id: "vpn drops|mtu".to_string(),
+            trigger: "vpn drops on wifi".to_string(),
+            invariant: "mtu too large".to_string(),
+            counterexample: "evt-0".to_string(),
+            best_response: "lower mtu".to_string(),
+            domain_signature: String::new(),
+            evidence_refs: vec!["capture://evt-1/T1".to_string()],
+            namespace: "global".to_string(),
+            scope: "default".to_string(),
+            created_at: 1_735_000_002,
+            anti_pattern: false,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+            supports: 0,
+            contradictions: 0,
+        };
+        let mut tx = runtime
+            .begin_covenant_transaction()
+            .await
+            .expect("begin transaction");
+        tx.insert_covenant_event(&event)
+            .await
+            .expect("insert covenant event");
+        tx.upsert_covenant_pattern(&pattern)
+            .await
+            .expect("upsert pattern");
+        tx.insert_audit_action(&crate::AuditAction {
+            timestamp: 1_735_000_002,
+            actor: "alice@example.com".to_string(),
+            action_type: "event.log".to_string(),
+            scope: "capture".to_string(),
+            covenant_version: "2026-02-01".to_string(),
+            event_id: Some("evt-1".to_string()),
+            intent_id: None,
+        })
+        .await
+        .expect("insert audit action");
+        tx.commit().await.expect("commit transaction");
+
+        let stored = sqlx::query_scalar::<_, String>(
+            r#"
+SELECT description FROM events
+UNION ALL SELECT goal FROM intent_tokens
+UNION ALL SELECT constraints FROM intent_tokens
+UNION ALL SELECT success_signal FROM intent_tokens
+UNION ALL SELECT model_type FROM hypotheses
+UNION ALL SELECT falsifiers FROM hypotheses
+UNION ALL SELECT description FROM tests
+UNION ALL SELECT summary FROM outcomes
+UNION ALL SELECT actor FROM audit_actions
+UNION ALL SELECT trigger FROM patterns
+UNION ALL SELECT invariant FROM patterns
+UNION ALL SELECT counterexample FROM patterns
+UNION ALL SELECT best_response FROM patterns
+            "#,
+        )
+        .fetch_all(runtime.pool.as_ref())
+        .await
+        .expect("query stored text");
+        assert_eq!(
+            stored
+                .iter()
+                .filter(|text| !text.starts_with(r#"{"age":""#))
+                .collect::<Vec<_>>(),
+            Vec::<&String>::new()
+        );
+        assert_eq!(
+            runtime
+                .list_covenant_events()
+                .await
+                .expect("list covenant events"),
+            vec![event]
+        );
+        assert_eq!(
+            runtime
+                .list_covenant_patterns(None)
+                .await
+                .expect("list covenant patterns"),
+            vec![pattern]
+        );
+
+        let _ = tokio::fs::remove_dir_all(codex_home).await;
+    }
+
+    #[tokio::test]
+    async fn deleting_covenant_events_removes_linked_records() {
+        let codex_home = unique_temp_dir();
+        let runtime = StateRuntime::init(codex_home.clone(), "test-provider".to_string(), None)
+            .await
+            .expect("initialize runtime");
+        let event = |id: &str| crate::CovenantEvent {
+            id: id.to_string(),
+            created_at: 1_735_000_002,
+            description: "disk full".to_string(),
+            domain_signature: "[]".to_string(),
+            intent: None,
+            hypotheses: vec![crate::CovenantHypothesis {
+                id: format!("{id}:H1"),
+                model_type: "logs filled the disk".to_string(),
+                probability: 0.7,
+                falsifiers: Vec::new(),
+                domain_signature: "[]".to_string(),
+            }],
+            tests: Vec::new(),
+            outcomes: vec![crate::CovenantOutcome {
+                id: format!("{id}:O1"),
+                summary: "pruned logs".to_string(),
+                outcome: codex_protocol::outcome::Outcome::Success,
+                evidence_refs: Vec::new(),
+                evidence: Vec::new(),
+            }],
+            tags: Vec::new(),
+            session_id: None,
+            rollout_path: None,
+        };
+        for id in ["evt-old", "evt-new"] {
+            runtime
+                .insert_covenant_event(&event(id))
+                .await
+                .expect("insert covenant event");
+        }
+
+        runtime
+            .delete_covenant_events(&["evt-old".to_string()])
+            .await
+            .expect("delete covenant events");
+
+        let orphans = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(1) FROM hypotheses WHERE event_id = 'evt-old'",
+        )
+        .fetch_one(runtime.pool.as_ref())
+        .await
+        .expect("count orphaned hypotheses");
+        assert_eq!(
+            (
+                runtime
+                    .covenant_event_count()
+                    .await
+                    .expect("count covenant events"),
+                orphans,
+                runtime
+                    .list_attached_artifact_hashes()
+                    .await
+                    .expect("list artifact hashes"),
+            ),
+            (1, 0, None)
+        );
+
+        let _ = tokio::fs::remove_dir_all(codex_home).await;
+    }
+
+    #[tokio::test]
+    async fn covenant_transactions_write_events_and_audits_together() {
+        let codex_home = unique_temp_dir();
+        let runtime = StateRuntime::init(codex_home.clone(), "test-provider".to_string(), None)
+            .await
+            .expect("initialize runtime");
+        let event = |id: &str| crate::CovenantEvent {
+            id: id.to_string(),
+            created_at: 1_735_000_003,
+            description: "disk full".to_string(),
+            domain_signature: "[]".to_string(),
+            intent: None,
+            hypotheses: Vec::new(),
+            tests: Vec::new(),
+            outcomes: Vec::new(),
+            tags: Vec::new(),
+            session_id: None,
+            rollout_path: None,
+        };
+        let audit = |id: &str| crate::AuditAction {
+            timestamp: 1_735_000_003,
+            actor: "user".to_string(),
+            action_type: "capture.import".to_string(),
+            scope: "cli".to_string(),
+            covenant_version: "missing".to_string(),
+            event_id: Some(id.to_string()),
+            intent_id: None,
+        };
+
+        let mut abandoned = runtime
+            .begin_covenant_transaction()
+            .await
+            .expect("begin abandoned transaction");
+        abandoned
+            .insert_covenant_event(&event("evt-abandoned"))
+            .await
+            .expect("insert abandoned event");
+        abandoned
+            .insert_audit_action(&audit("evt-abandoned"))
+            .await
+            .expect("insert abandoned audit");
+        drop(abandoned);
+
+        let mut committed = runtime
+            .begin_covenant_transaction()
+            .await
+            .expect("begin committed transaction");
+        committed
+            .insert_covenant_event(&event("evt-committed"))
+            .await
+            .expect("insert committed event");
+        committed
+            .insert_audit_action(&audit("evt-committed"))
+            .await
+            .expect("insert committed audit");
+        committed.commit().await.expect("commit transaction");
+
+        let audited = sqlx::query_scalar::<_, String>("SELECT event_id FROM audit_actions")
+            .fetch_all(runtime.pool.as_ref())
+            .await
+            .expect("list audited events");
+        assert_eq!(
+            (
+                runtime
+                    .covenant_event_count()
+                    .await
+                    .expect("count covenant events"),
+                audited,
+            ),
+            (1, vec!["evt-committed".to_string()])
+        );
+
+        let _ = tokio::fs::remove_dir_all(codex_home).await;
+    }
+
+    #[tokio::test]
+    async fn reviewed_patterns_round_trip_through_the_live_store() {
+        let codex_home = unique_temp_dir();
+        let runtime = StateRuntime::init(codex_home.clone(), "test-provider".to_string(), None)
+            .await
+            .expect("initialize runtime");
+
+        let pattern = CovenantPattern {
+            id: "disk full|disk pressure".to_string(),
+            trigger: "Disk full".to_string(),
+            invariant: "disk pressure".to_string(),
+            counterexample: "evt-3".to_string(),
+            best_response: "prune logs".to_string(),
+            domain_signature: "[1.0,0.0]".to_string(),
+            evidence_refs: vec!["evidence://evt-1".to_string()],
+            namespace: "global".to_string(),
+            scope: "default".to_string(),
+            created_at: 1_735_000_002,
+            anti_pattern: false,
+            tags: vec!["infra".to_string()],
+            counterexamples: vec![PatternCounterexample {
+                event_id: "evt-3".to_string(),
+                outcome: Outcome::Failure,
+                resolved_at: 1_735_000_001,
+                evidence_refs: vec!["evidence://evt-3".to_string()],
+            }],
+            supports: 0,
+            contradictions: 0,
+        };
+        runtime
+            .upsert_covenant_pattern(&pattern)
+            .await
+            .expect("upsert pattern");
+        let edited = CovenantPattern {
+            best_response: "rotate logs".to_string(),
+            anti_pattern: true,
+            tags: vec!["infra".to_string(), "disk".to_string()],
+            ..pattern.clone()
+        };
+        runtime
+            .upsert_covenant_pattern(&edited)
+            .await
+            .expect("update pattern");
+        let in_other_scope = CovenantPattern {
+            best_response: "reboot".to_string(),
+            scope: "ops".to_string(),
+            ..pattern.clone()
+        };
+        assert_eq!(
+            runtime
+                .upsert_covenant_pattern(&in_other_scope)
+                .await
+                .map_err(|err| err.to_string()),
+            Err(
+                "pattern disk full|disk pressure belongs to another covenant scope than ops"
+                    .to_string()
+            )
+        );
+        let review = PatternReview {
+            pattern_key: pattern.id.clone(),
+            version: 2,
+            decision: PatternReviewDecision::Edited,
+            pattern_id: Some(pattern.id.clone()),
+            reviewed_at: 1_735_000_003,
+        };
+        runtime
+            .insert_pattern_review(&review)
+            .await
+            .expect("insert review");
+
+        assert_eq!(
+            (
+                runtime
+                    .list_covenant_patterns(None)
+                    .await
+                    .expect("list patterns"),
+                runtime
+                    .list_covenant_patterns(Some("default"))
+                    .await
+                    .expect("list default patterns"),
+                runtime
+                    .list_covenant_patterns(Some("ops"))
+                    .await
+                    .expect("list ops patterns"),
+            ),
+            (vec![edited.clone()], vec![edited], Vec::new())
+        );
+        assert_eq!(
+            runtime.list_pattern_reviews().await.expect("list reviews"),
+            vec![review]
+        );
+
+        let _ = tokio::fs::remove_dir_all(codex_home).await;
+    }
+
+    #[tokio::test]
+    async fn live_patterns_are_confirmed_archived_and_counted() {
+        let codex_home = unique_temp_dir();
+        let runtime = StateRuntime::init(codex_home.clone(), "test-provider".to_string(), None)
+            .await
+            .expect("initialize runtime");
+        let pattern = |id: &str, created_at: i64| CovenantPattern {
+            id: id.to_string(),
+            trigger: format!("{id} trigger"),
+            invariant: "disk pressure".to_string(),
+            counterexample: String::new(),
+            best_response: "prune logs".to_string(),
+            domain_signature: "[]".to_string(),
+            evidence_refs: Vec::new(),
+            namespace: "global".to_string(),
+            scope: "default".to_string(),
+            created_at,
+            anti_pattern: false,
+            tags: Vec::new(),
+            counterexamples: Vec::new(),
+            supports: 0,
+            contradictions: 0,
+        };
+        let patterns = [
+            pattern("old", 100),
+            pattern("older", 50),
+            pattern("new", 900),
+        ];
+        for pattern in &patterns {
+            runtime
+                .upsert_covenant_pattern(pattern)
+                .await
+                .expect("upsert pattern");
+        }
+        let stale = |pattern: &CovenantPattern, last_confirmed_at| StalePattern {
+            pattern: pattern.clone(),
+            last_confirmed_at,
+        };
+        assert_eq!(
+            runtime
+                .list_stale_covenant_patterns(500, None)
+                .await
+                .expect("list stale patterns"),
+            vec![stale(&patterns[1], 50), stale(&patterns[0], 100)]
+        );
+
+        let confirmed = runtime
+            .confirm_covenant_patterns(&["old".to_string()], 600)
+            .await
+            .expect("confirm pattern");
+        let archived = runtime
+            .archive_covenant_patterns(&["older".to_string(), "missing".to_string()], 600)
+            .await
+            .expect("archive pattern");
+        assert_eq!(
+            (
+                confirmed,
+                archived,
+                runtime
+                    .list_stale_covenant_patterns(700, Some("default"))
+                    .await
+                    .expect("list stale patterns"),
+                runtime
+                    .list_covenant_patterns(None)
+                    .await
+                    .expect("list patterns"),
+                runtime
+                    .get_covenant_pattern("old")
+                    .await
+                    .expect("get live pattern"),
+                runtime
+                    .get_covenant_pattern("older")
+                    .await
+                    .expect("get archived pattern"),
+            ),
+            (
+                1,
+                1,
+                vec![stale(&patterns[0], 600)],
+                vec![patterns[0].clone(), patterns[2].clone()],
+                Some(patterns[0].clone()),
+                None,
+            )
+        );
+
+        let supported = runtime
+            .record_pattern_feedback("old", true)
+            .await
+            .expect("record support");
+        let contradicted = runtime
+            .record_pattern_feedback("old", false)
+            .await
+            .expect("record contradiction");
+        let archived = runtime
+            .record_pattern_feedback("older", true)
+            .await
+            .expect("record feedback on archived pattern");
+        runtime
+            .upsert_covenant_pattern(&patterns[0])
+            .await
+            .expect("recompile pattern");
+        let counted = CovenantPattern {
+            supports: 1,
+            contradictions: 1,
+            ..patterns[0].clone()
+        };
+        assert_eq!(
+            (
+                supported.map(|pattern| (pattern.supports, pattern.contradictions)),
+                contradicted,
+                archived,
+                runtime
+                    .list_covenant_patterns(None)
+                    .await
+                    .expect("list patterns"),
+            ),
+            (
+                Some((1, 0)),
+                Some(counted.clone()),
+                None,
+                vec![counted.clone(), patterns[2].clone()],
+            )
+        );
+
+        // Adding an archived pattern again brings it back.
+        runtime
+            .upsert_covenant_pattern(&patterns[1])
+            .await
+            .expect("re-add archived pattern");
+        assert_eq!(
+            runtime
+                .list_covenant_patterns(None)
+                .await
+                .expect("list patterns"),
+            vec![patterns[1].clone(), counted, patterns[2].clone()]
+        );
+
+        let _ = tokio::fs::remove_dir_all(codex_home).await;
+    }
+
+    #[tokio::test]
+    async fn covenant_grants_expire_per_thread() {
+        let codex_home = unique_temp_dir();
+        let runtime = StateRuntime::init(codex_home.clone(), "test-provider".to_string(), None)
+            .await
+            .expect("initialize runtime");
+        let grant = CovenantGrant {
+            thread_id: "thread-1".to_string(),
+            scope: "cli".to_string(),
+            capability: "proposal.exec_command".to_string(),
+            granted_at: 1_000,
+            expires_at: 1_600,
+        };
+        runtime
+            .insert_covenant_grant(&grant)
+            .await
+            .expect("insert grant");
+
+        let mut expiries = Vec::new();
+        for (thread_id, now) in [
+            ("thread-1", 1_200),
+            ("thread-1", 1_600),
+            ("thread-2", 1_200),
+        ] {
+            expiries.push(
+                runtime
+                    .covenant_grant_expiry(thread_id, "cli", "proposal.exec_command", now)
+                    .await
+                    .expect("query grant"),
+            );
+        }
+        assert_eq!(expiries, vec![Some(1_600), None, None]);
+
+        let _ = tokio::fs::remove_dir_all(codex_home).await;
+    }
+
+    #[tokio::test]
+    async fn covenant_checks_are_counted_per_day() {
+        let codex_home = unique_temp_dir();
+        let runtime = StateRuntime::init(codex_home.clone(), "test-provider".to_string(), None)
+            .await
+            .expect("initialize runtime");
+        let check = |timestamp, capability: &str, allowed| CovenantCheck {
+            timestamp,
+            scope: "cli".to_string(),
+            capability: capability.to_string(),
+            allowed,
+        };
+        for recorded in [
+            check(10, "proposal.exec_command", true),
+            check(20, "proposal.exec_command", false),
+            check(30, "proposal.exec_command", true),
+            check(40, "event.log", true),
+            check(SECONDS_PER_DAY + 5, "proposal.exec_command", false),
+        ] {
+            runtime
+                .record_covenant_check(&recorded)
+                .await
+                .expect("record check");
+        }
+
+        let count = |day, capability: &str, allowed, denied| CovenantCheckCount {
+            day,
+            scope: "cli".to_string(),
+            capability: capability.to_string(),
+            allowed,
+            denied,
+        };
+        assert_eq!(
+            (
+                runtime
+                    .list_covenant_check_counts(None)
+                    .await
+                    .expect("list counts"),
+                runtime
+                    .list_covenant_check_counts(Some(SECONDS_PER_DAY + 100))
+                    .await
+                    .expect("list counts"),
+            ),
+            (
+                vec![
+                    count(0, "event.log", 1, 0),
+                    count(0, "proposal.exec_command", 2, 1),
+                    count(SECONDS_PER_DAY, "proposal.exec_command", 0, 1),
+                ],
+                vec![count(SECONDS_PER_DAY, "proposal.exec_command", 0, 1)],
+            )
+        );
+
+        let _ = tokio::fs::remove_dir_all(codex_home).await;
+    }
+
     #[tokio::test]
     async fn insert_audit_action_creates_linked_covenant_record() {
         let codex_home = unique_temp_dir();
@@ -0,0 +1,305 @@
+//! Encryption at rest for the covenant store's JSONL files and the text
+//! columns of its database.
+//!
+//! Each line is sealed on its own with [age], so logs stay appendable and a
+//! crash still loses at most the last line. Text columns are sealed the same
+//! way, one value at a time, with [`seal_text`]. A sealed line is itself a JSON
+//! object, `{"age":"<base64 ciphertext>"}`, which the tail repair in
+//! [`crate::JsonlAppender`] keeps like any other line. Readers pass every
+//! line through [`open_line`]: plaintext lines come back unchanged, so logs
+//! written before encryption was turned on keep working.
+//!
+//! The key is an age X25519 identity (`AGE-SECRET-KEY-1...`) read from
+//! `CODEX_STORE_KEY`, or else from the OS keychain. Without a key, lines are
+//! written in plaintext and sealed lines cannot be read.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+use age::secrecy::ExposeSecret;
+use age::secrecy::SecretString;
+use age::x25519::Identity;
+use age::x25519::Recipient;
+use anyhow::Context;
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use codex_keyring_store::DefaultKeyringStore;
+use codex_keyring_store::KeyringStore;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::canonical_json::to_canonical_json;
+
+/// Environment variable holding the store key. It takes precedence over the
+/// keychain.
+pub const STORE_KEY_ENV_VAR: &str = "CODEX_STORE_KEY";
+
+/// Keychain service and account the store key is saved under.
+const STORE_KEY_KEYRING_SERVICE: &str = "codex";
+const STORE_KEY_KEYRING_ACCOUNT: &str = "covenant-store-key";
+
+const SEALED_LINE_PREFIX: &str = "{\"age\":\"";
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SealedLine {
+    age: String,
+}
+
+/// Seals and opens store lines with one age identity.
+#[derive(Clone)]
+pub struct StoreCipher {
+    identity: Arc<Identity>,
+    recipient: Recipient,
+}
+
+impl fmt::Debug for StoreCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoreCipher")
+            .field("recipient", &self.recipient.to_string())
+            .finish_non_exhaustive()
+    }
+}
+
+impl StoreCipher {
+    /// A cipher with a freshly generated key.
+    pub fn generate() -> Self {
+        Self::from_identity(Identity::generate())
+    }
+
+    /// Parse an `AGE-SECRET-KEY-1...` identity.
+    pub fn from_key(key: &str) -> anyhow::Result<Self> {
+        let identity = Identity::from_str(key.trim())
+            .map_err(|err| anyhow::anyhow!("invalid store key: {err}"))?;
+        Ok(Self::from_identity(identity))
+    }
+
+    fn from_identity(identity: Identity) -> Self {
+        let recipient = identity.to_public();
+        Self {
+            identity: Arc::new(identity),
+            recipient,
+        }
+    }
+
+    /// The key from `CODEX_STORE_KEY`, or else the OS keychain. `None` when
+    /// neither has one.
+    pub fn load() -> anyhow::Result<Option<Self>> {
+        let from_env = std::env::var(STORE_KEY_ENV_VAR).ok();
+        Self::load_with(from_env.as_deref(), &DefaultKeyringStore)
+    }
+
+    /// The key in the OS keychain, leaving `CODEX_STORE_KEY` aside.
+    pub fn load_from_keychain() -> anyhow::Result<Option<Self>> {
+        Self::load_with(None, &DefaultKeyringStore)
+    }
+
+    fn load_with(
+        from_env: Option<&str>,
+        keyring: &dyn KeyringStore,
+    ) -> anyhow::Result<Option<Self>> {
+        if let Some(key) = from_env.filter(|key| !key.trim().is_empty()) {
+            return Self::from_key(key)
+                .with_context(|| format!("failed to read {STORE_KEY_ENV_VAR}"))
+                .map(Some);
+        }
+        // An unavailable keychain is treated like one without a key, so the
+        // store keeps working on machines that have none.
+        match keyring.load(STORE_KEY_KEYRING_SERVICE, STORE_KEY_KEYRING_ACCOUNT) {
+            Ok(Some(key)) => Self::from_key(key.as_str())
+                .context("failed to read the store key from the keychain")
+                .map(Some),
+            Ok(None) => Ok(None),
+            Err(err) => {
+                warn!("failed to load the store key from the keychain: {err}");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Save this key in the OS keychain, where [`StoreCipher::load`] finds it.
+    pub fn save_to_keychain(&self) -> anyhow::Result<()> {
+        self.save_with(&DefaultKeyringStore)
+    }
+
+    fn save_with(&self, keyring: &dyn KeyringStore) -> anyhow::Result<()> {
+        keyring
+            .save(
+                STORE_KEY_KEYRING_SERVICE,
+                STORE_KEY_KEYRING_ACCOUNT,
+                self.key().expose_secret(),
+            )
+            .map_err(|err| anyhow::anyhow!(err.message()))
+            .context("failed to save the store key in the keychain")
+    }
+
+    /// The `AGE-SECRET-KEY-1...` form of the key, as `CODEX_STORE_KEY` takes
+    /// it.
+    pub fn export_key(&self) -> String {
+        self.key().expose_secret().to_string()
+    }
+
+    fn key(&self) -> SecretString {
+        self.identity.to_string()
+    }
+
+    /// Encrypt one line. Every line is sealed, including one that already
+    /// looks sealed, so [`open_line`] returns exactly what was passed in.
+    pub fn seal_line(&self, line: &str) -> anyhow::Result<String> {
+        let ciphertext =
+            age::encrypt(&self.recipient, line.as_bytes()).context("failed to encrypt line")?;
+        to_canonical_json(&SealedLine {
+            age: BASE64_STANDARD.encode(ciphertext),
+        })
+    }
+
+    fn open_sealed(&self, sealed: &SealedLine) -> anyhow::Result<String> {
+        let ciphertext = BASE64_STANDARD
+            .decode(sealed.age.as_bytes())
+            .context("sealed line is not valid base64")?;
+        let plaintext = age::decrypt(self.identity.as_ref(), &ciphertext)
+            .context("failed to decrypt line; it was sealed with a different store key")?;
+        String::from_utf8(plaintext).context("decrypted line is not valid UTF-8")
+    }
+}
+
+/// The store key, loaded by [`StoreCipher::load`] the first time it is
+/// needed. Opening a database therefore never reads the keychain, and a
+/// malformed `CODEX_STORE_KEY` fails covenant reads and writes, not startup.
+#[derive(Debug, Default)]
+pub struct LazyStoreCipher {
+    loaded: OnceLock<Result<Option<StoreCipher>, String>>,
+}
+
+impl LazyStoreCipher {
+    /// A cipher that is already loaded, without consulting the environment
+    /// or the keychain.
+    pub fn loaded(cipher: Option<StoreCipher>) -> Self {
+        Self {
+            loaded: OnceLock::from(Ok(cipher)),
+        }
+    }
+
+    /// The store key, loading it on first use. A failed load is remembered,
+    /// so the keychain is only asked once.
+    pub fn get(&self) -> anyhow::Result<Option<&StoreCipher>> {
+        match self
+            .loaded
+            .get_or_init(|| StoreCipher::load().map_err(|err| format!("{err:#}")))
+        {
+            Ok(cipher) => Ok(cipher.as_ref()),
+            Err(err) => Err(anyhow::anyhow!("{err}")),
+        }
+    }
+}
+
+/// The plaintext of `line`: decrypted when it is sealed, unchanged when not.
+pub fn open_line<'a>(line: &'a str, cipher: Option<&StoreCipher>) -> anyhow::Result<Cow<'a, str>> {
+    let Some(sealed) = parse_sealed(line) else {
+        return Ok(Cow::Borrowed(line));
+    };
+    let cipher = cipher.with_context(|| {
+        format!("line is encrypted; set {STORE_KEY_ENV_VAR} or add the store key to the keychain")
+    })?;
+    cipher.open_sealed(&sealed).map(Cow::Owned)
+}
+
+/// `text` as a text column stores it: sealed with `cipher`, or unchanged
+/// without one. Read it back with [`open_line`].
+pub fn seal_text(text: &str, cipher: Option<&StoreCipher>) -> anyhow::Result<String> {
+    match cipher {
+        Some(cipher) => cipher.seal_line(text),
+        None => Ok(text.to_string()),
+    }
+}
+
+fn parse_sealed(line: &str) -> Option<SealedLine> {
+    let line = line.trim();
+    if !line.starts_with(SEALED_LINE_PREFIX) {
+        return None;
+    }
+    serde_json::from_str(line).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_keyring_store::tests::MockKeyringStore;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sealed_lines_open_only_with_their_key() {
+        let cipher = StoreCipher::generate();
+        let line = r#"{"id":"disk-full","best_response":"prune logs"}"#;
+        let sealed = cipher.seal_line(line).expect("seal line");
+        let resealed = cipher.seal_line(sealed.as_str()).expect("reseal line");
+
+        assert_eq!(
+            (
+                parse_sealed(sealed.as_str()).is_some(),
+                sealed.contains("prune"),
+                open_line(sealed.as_str(), Some(&cipher))
+                    .expect("open line")
+                    .into_owned(),
+                open_line(resealed.as_str(), Some(&cipher))
+                    .expect("open resealed line")
+                    .into_owned(),
+                open_line(line, None).expect("open plaintext").into_owned(),
+            ),
+            (
+                true,
+                false,
+                line.to_string(),
+                sealed.clone(),
+                line.to_string()
+            )
+        );
+        assert_eq!(
+            [
+                open_line(sealed.as_str(), None),
+                open_line(sealed.as_str(), Some(&StoreCipher::generate())),
+            ]
+            .map(|result| result.map_err(|err| err.to_string())),
+            [
+                Err(
+                    "line is encrypted; set CODEX_STORE_KEY or add the store key to the keychain"
+                        .to_string()
+                ),
+                Err("failed to decrypt line; it was sealed with a different store key".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_environment_key_wins_over_the_keychain() {
+        let keyring = MockKeyringStore::default();
+        let recipient =
+            |cipher: Option<StoreCipher>| cipher.map(|cipher| cipher.recipient.to_string());
+        let without_key = recipient(StoreCipher::load_with(None, &keyring).expect("load no key"));
+
+        let saved = StoreCipher::generate();
+        saved.save_with(&keyring).expect("save key");
+        let from_env = StoreCipher::generate();
+        let env_key = from_env.key();
+
+        assert_eq!(
+            (
+                without_key,
+                recipient(StoreCipher::load_with(None, &keyring).expect("load keychain key")),
+                recipient(
+                    StoreCipher::load_with(Some(env_key.expose_secret()), &keyring)
+                        .expect("load env key")
+                ),
+            ),
+            (
+                None,
+                Some(saved.recipient.to_string()),
+                Some(from_env.recipient.to_string())
+            )
+        );
+    }
+}
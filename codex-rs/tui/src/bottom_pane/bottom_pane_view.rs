@@ -66,4 +66,11 @@ pub(crate) trait BottomPaneView: Renderable {
     ) -> Option<RequestUserInputEvent> {
         Some(request)
     }
+
+    /// Drop the request_user_input for `call_id` in `turn_id`, shown or
+    /// queued, after the agent stopped waiting for it; return true if this
+    /// view held it.
+    fn dismiss_user_input_request(&mut self, _turn_id: &str, _call_id: &str) -> bool {
+        false
+    }
 }
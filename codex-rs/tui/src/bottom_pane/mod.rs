@@ -742,6 +742,24 @@ impl BottomPane {
         self.push_view(Box::new(modal));
     }
 
+    /// Called when the agent stops waiting for a request_user_input.
+    pub fn close_user_input_request(&mut self, turn_id: &str, call_id: &str) {
+        let Some(idx) = self
+            .view_stack
+            .iter_mut()
+            .rposition(|view| view.dismiss_user_input_request(turn_id, call_id))
+        else {
+            return;
+        };
+        if self.view_stack[idx].is_complete() {
+            self.view_stack.remove(idx);
+            if idx == self.view_stack.len() {
+                self.on_active_view_complete();
+            }
+        }
+        self.request_redraw();
+    }
+
     fn on_active_view_complete(&mut self) {
         self.resume_status_timer_after_modal();
         self.set_composer_input_enabled(true, None);
@@ -753,6 +753,11 @@ impl RequestUserInputOverlay {
                 interrupted: false,
             },
         )));
+        self.advance_to_next_request();
+    }
+
+    /// Show the next queued request, or finish when none is left.
+    fn advance_to_next_request(&mut self) {
         if let Some(next) = self.queue.pop_front() {
             self.request = next;
             self.reset_for_request();
@@ -1251,6 +1256,19 @@ impl BottomPaneView for RequestUserInputOverlay {
         self.queue.push_back(request);
         None
     }
+
+    fn dismiss_user_input_request(&mut self, turn_id: &str, call_id: &str) -> bool {
+        let is_closed = |request: &RequestUserInputEvent| {
+            request.turn_id == turn_id && request.call_id == call_id
+        };
+        if is_closed(&self.request) {
+            self.advance_to_next_request();
+            return true;
+        }
+        let queued = self.queue.len();
+        self.queue.retain(|request| !is_closed(request));
+        self.queue.len() != queued
+    }
 }
 
 #[cfg(test)]
@@ -1432,6 +1450,55 @@ mod tests {
         assert_eq!(overlay.request.turn_id, "turn-3");
     }
 
+    #[test]
+    fn closed_requests_are_dismissed_shown_or_queued() {
+        let (tx, mut rx) = test_sender();
+        let mut overlay = RequestUserInputOverlay::new(
+            request_event("turn-1", vec![question_with_options("q1", "First")]),
+            tx,
+            true,
+            false,
+            false,
+        );
+        overlay.try_consume_user_input_request(request_event(
+            "turn-2",
+            vec![question_with_options("q2", "Second")],
+        ));
+        overlay.try_consume_user_input_request(request_event(
+            "turn-3",
+            vec![question_with_options("q3", "Third")],
+        ));
+
+        let dismissed = [
+            overlay.dismiss_user_input_request("turn-2", "call-1"),
+            overlay.dismiss_user_input_request("turn-2", "call-9"),
+        ];
+        let shown_after_queued = overlay.request.turn_id.clone();
+        let dismissed_shown = overlay.dismiss_user_input_request("turn-1", "call-1");
+        let shown_after_shown = overlay.request.turn_id.clone();
+        let dismissed_last = overlay.dismiss_user_input_request("turn-3", "call-1");
+
+        assert_eq!(
+            (
+                dismissed,
+                shown_after_queued,
+                dismissed_shown,
+                shown_after_shown,
+                dismissed_last,
+                overlay.done,
+            ),
+            (
+                [true, false],
+                "turn-1".to_string(),
+                true,
+                "turn-3".to_string(),
+                true,
+                true,
+            )
+        );
+        assert!(rx.try_recv().is_err(), "closing a request sends no answer");
+    }
+
     #[test]
     fn interrupt_discards_queued_requests_and_emits_interrupt() {
         let (tx, mut rx) = test_sender();
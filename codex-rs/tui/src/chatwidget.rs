@@ -87,6 +87,8 @@ use codex_core::protocol::RateLimitSnapshot;
 use codex_core::protocol::ReviewRequest;
 use codex_core::protocol::ReviewTarget;
 use codex_core::protocol::SkillMetadata as ProtocolSkillMetadata;
+use codex_core::protocol::StalePatternAction;
+use codex_core::protocol::StalePatternsEvent;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TerminalInteractionEvent;
 use codex_core::protocol::TokenUsage;
@@ -118,6 +120,7 @@ use codex_protocol::config_types::Settings;
 use codex_protocol::config_types::WindowsSandboxLevel;
 use codex_protocol::models::local_image_label_text;
 use codex_protocol::parse_command::ParsedCommand;
+use codex_protocol::request_user_input::RequestUserInputClosedEvent;
 use codex_protocol::request_user_input::RequestUserInputEvent;
 use codex_protocol::user_input::TextElement;
 use codex_protocol::user_input::UserInput;
@@ -1754,6 +1757,14 @@ impl ChatWidget {
         );
     }
 
+    fn on_request_user_input_closed(&mut self, ev: RequestUserInputClosedEvent) {
+        let ev2 = ev.clone();
+        self.defer_or_handle(
+            |q| q.push_user_input_closed(ev),
+            |s| s.handle_request_user_input_closed_now(ev2),
+        );
+    }
+
     fn on_exec_command_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.flush_answer_stream_with_separator();
         if is_unified_exec_source(ev.source) {
@@ -2344,6 +2355,12 @@ impl ChatWidget {
         self.request_redraw();
     }
 
+    pub(crate) fn handle_request_user_input_closed_now(&mut self, ev: RequestUserInputClosedEvent) {
+        self.bottom_pane
+            .close_user_input_request(&ev.turn_id, &ev.call_id);
+        self.request_redraw();
+    }
+
     pub(crate) fn handle_exec_begin_now(&mut self, ev: ExecCommandBeginEvent) {
         // Ensure the status indicator is visible while the command runs.
         self.bottom_pane.ensure_status_indicator();
@@ -3835,6 +3852,9 @@ impl ChatWidget {
             EventMsg::RequestUserInput(ev) => {
                 self.on_request_user_input(ev);
             }
+            EventMsg::RequestUserInputClosed(ev) => {
+                self.on_request_user_input_closed(ev);
+            }
             EventMsg::ExecCommandBegin(ev) => self.on_exec_command_begin(ev),
             EventMsg::TerminalInteraction(delta) => self.on_terminal_interaction(delta),
             EventMsg::ExecCommandOutputDelta(delta) => self.on_exec_command_output_delta(delta),
@@ -3850,7 +3870,11 @@ impl ChatWidget {
             EventMsg::McpListToolsResponse(ev) => self.on_list_mcp_tools(ev),
             EventMsg::ListCustomPromptsResponse(ev) => self.on_list_custom_prompts(ev),
             EventMsg::ListSkillsResponse(ev) => self.on_list_skills(ev),
-            EventMsg::ListRemoteSkillsResponse(_) | EventMsg::RemoteSkillDownloaded(_) => {}
+            EventMsg::ListRemoteSkillsResponse(_)
+            | EventMsg::RemoteSkillDownloaded(_)
+            | EventMsg::PatternQueryResponse(_)
+            | EventMsg::ApprovalDecision(_) => {}
+            EventMsg::StalePatterns(ev) => self.on_stale_patterns(ev),
             EventMsg::SkillsUpdateAvailable => {
                 self.submit_op(Op::ListSkills {
                     cwds: Vec::new(),
@@ -6490,6 +6514,101 @@ impl ChatWidget {
         self.set_skills_from_response(&ev);
     }
 
+    /// List the stale patterns and offer to re-confirm or archive them all
+    /// with one key.
+    fn on_stale_patterns(&mut self, ev: StalePatternsEvent) {
+        let StalePatternsEvent {
+            stale_after_days,
+            patterns,
+        } = ev;
+        let count = patterns.len();
+        let (noun, verb) = if count == 1 {
+            ("pattern", "has")
+        } else {
+            ("patterns", "have")
+        };
+        let mut lines: Vec<Line<'static>> = vec![
+            vec![
+                "• ".dim(),
+                format!(
+                    "{count} stored {noun} {verb} not been confirmed in {stale_after_days} days:"
+                )
+                .into(),
+            ]
+            .into(),
+        ];
+        for pattern in &patterns {
+            let confirmed = chrono::DateTime::from_timestamp(pattern.last_confirmed_at, 0)
+                .map(|time| time.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            lines.push(
+                vec![
+                    "  ".into(),
+                    pattern.trigger.clone().into(),
+                    " → ".dim(),
+                    pattern.best_response.clone().into(),
+                    format!(" (last confirmed {confirmed})").dim(),
+                ]
+                .into(),
+            );
+        }
+        self.add_plain_history_lines(lines);
+
+        let pattern_ids = patterns
+            .into_iter()
+            .map(|pattern| pattern.pattern_id)
+            .collect::<Vec<_>>();
+        let review = |action: StalePatternAction, done: String| -> Vec<SelectionAction> {
+            let pattern_ids = pattern_ids.clone();
+            vec![Box::new(move |tx| {
+                tx.send(AppEvent::InsertHistoryCell(Box::new(
+                    history_cell::new_info_event(done.clone(), None),
+                )));
+                tx.send(AppEvent::CodexOp(Op::ReviewStalePatterns {
+                    pattern_ids: pattern_ids.clone(),
+                    action,
+                }));
+            })]
+        };
+        let items = vec![
+            SelectionItem {
+                name: "Re-confirm all".to_string(),
+                description: Some("They still hold; reset their age.".to_string()),
+                actions: review(
+                    StalePatternAction::Confirm,
+                    format!("Re-confirmed {count} stale {noun}."),
+                ),
+                dismiss_on_select: true,
+                ..Default::default()
+            },
+            SelectionItem {
+                name: "Archive all".to_string(),
+                description: Some("They no longer hold; stop matching them.".to_string()),
+                actions: review(
+                    StalePatternAction::Archive,
+                    format!("Archived {count} stale {noun}."),
+                ),
+                dismiss_on_select: true,
+                ..Default::default()
+            },
+            SelectionItem {
+                name: "Decide later".to_string(),
+                description: Some("Ask again next session.".to_string()),
+                dismiss_on_select: true,
+                ..Default::default()
+            },
+        ];
+        self.bottom_pane.show_selection_view(SelectionViewParams {
+            title: Some("Stale patterns".to_string()),
+            subtitle: Some(format!(
+                "Not confirmed in {stale_after_days} days. Press 1 to re-confirm or 2 to archive."
+            )),
+            footer_hint: Some(standard_popup_hint_line()),
+            items,
+            ..Default::default()
+        });
+    }
+
     pub(crate) fn on_connectors_loaded(&mut self, result: Result<ConnectorsSnapshot, String>) {
         self.connectors_cache = match result {
             Ok(connectors) => ConnectorsCacheState::Ready(connectors),
@@ -50,6 +50,9 @@ use codex_core::protocol::RateLimitWindow;
 use codex_core::protocol::ReviewRequest;
 use codex_core::protocol::ReviewTarget;
 use codex_core::protocol::SessionSource;
+use codex_core::protocol::StalePatternAction;
+use codex_core::protocol::StalePatternSummary;
+use codex_core::protocol::StalePatternsEvent;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TerminalInteractionEvent;
 use codex_core::protocol::TokenCountEvent;
@@ -3873,6 +3876,59 @@ async fn approvals_popup_navigation_skips_disabled() {
     );
 }
 
+#[tokio::test]
+async fn stale_patterns_are_archived_with_one_key() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual(None).await;
+
+    chat.handle_codex_event(Event {
+        id: "stale-1".into(),
+        msg: EventMsg::StalePatterns(StalePatternsEvent {
+            stale_after_days: 90,
+            patterns: vec![StalePatternSummary {
+                pattern_id: "disk".to_string(),
+                trigger: "disk full on build host".to_string(),
+                best_response: "prune old logs".to_string(),
+                last_confirmed_at: 1_735_689_600,
+            }],
+        }),
+    });
+    let notice = drain_insert_history(&mut rx)
+        .iter()
+        .map(|lines| lines_to_single_string(lines))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        notice,
+        vec![
+            "• 1 stored pattern has not been confirmed in 90 days:\n  disk full on build host → \
+             prune old logs (last confirmed 2025-01-01)\n"
+                .to_string()
+        ]
+    );
+
+    chat.handle_key_event(KeyEvent::from(KeyCode::Char('2')));
+    let mut reviews = Vec::new();
+    let mut history = Vec::new();
+    while let Ok(ev) = rx.try_recv() {
+        match ev {
+            AppEvent::CodexOp(Op::ReviewStalePatterns {
+                pattern_ids,
+                action,
+            }) => reviews.push((pattern_ids, action)),
+            AppEvent::InsertHistoryCell(cell) => {
+                history.push(lines_to_single_string(&cell.display_lines(80)));
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(
+        (reviews, history),
+        (
+            vec![(vec!["disk".to_string()], StalePatternAction::Archive)],
+            vec!["• Archived 1 stale pattern.\n".to_string()],
+        )
+    );
+}
+
 //
 // Snapshot test: command approval modal
 //
@@ -0,0 +1,323 @@
+//! Shared tokenization for bag-of-words similarity scores.
+//!
+//! Pattern ranking, session fidelity, and intent matching all compare token
+//! sets. Normalizing text the same way everywhere keeps those scores
+//! consistent: text is split on non-alphanumeric characters, lowercased,
+//! filtered against a stopword list, and reduced to a light stem.
+//!
+//! The stopwords and stemming can be changed per installation. A binary that
+//! reads such settings installs them once with
+//! [`set_configured_normalizer`], and every caller of [`tokenize`],
+//! [`token_set`], or [`TextNormalizer::configured`] in that process uses them.
+
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+use std::sync::OnceLock;
+
+/// Common English function words dropped by the default normalizer.
+/// Negations are kept because they change what a request means.
+pub const ENGLISH_STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be",
+    "been", "before", "but", "by", "can", "could", "did", "do", "does", "for", "from", "had",
+    "has", "have", "he", "her", "here", "him", "his", "how", "i", "if", "in", "into", "is", "it",
+    "its", "just", "me", "my", "of", "on", "or", "our", "please", "she", "should", "so", "some",
+    "than", "that", "the", "their", "them", "then", "there", "these", "they", "this", "those",
+    "to", "too", "up", "us", "was", "we", "were", "what", "when", "where", "which", "while", "who",
+    "will", "with", "would", "you", "your",
+];
+
+static CONFIGURED: OnceLock<TextNormalizer> = OnceLock::new();
+
+static DEFAULT: LazyLock<TextNormalizer> = LazyLock::new(TextNormalizer::default);
+
+/// Stems never shrink below this many characters.
+const MIN_STEM_LEN: usize = 3;
+
+const VOWELS: [char; 6] = ['a', 'e', 'i', 'o', 'u', 'y'];
+
+/// How text is turned into tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextNormalizer {
+    /// Reduce tokens with [`stem`] so inflections compare equal.
+    pub stem: bool,
+    /// Drop [`ENGLISH_STOPWORDS`].
+    pub english_stopwords: bool,
+    /// Additional words to drop, compared case-insensitively.
+    pub extra_stopwords: Vec<String>,
+    /// Words from [`ENGLISH_STOPWORDS`] to keep, compared case-insensitively.
+    pub kept_stopwords: Vec<String>,
+    /// Tokens with fewer characters than this are dropped.
+    pub min_token_len: usize,
+}
+
+impl Default for TextNormalizer {
+    fn default() -> Self {
+        Self {
+            stem: true,
+            english_stopwords: true,
+            extra_stopwords: Vec::new(),
+            kept_stopwords: Vec::new(),
+            min_token_len: 2,
+        }
+    }
+}
+
+impl TextNormalizer {
+    /// The normalizer installed with [`set_configured_normalizer`], or the
+    /// default when none was installed.
+    pub fn configured() -> &'static TextNormalizer {
+        CONFIGURED.get().unwrap_or(&DEFAULT)
+    }
+
+    /// Normalized tokens in text order, duplicates included.
+    pub fn tokens(&self, text: &str) -> Vec<String> {
+        text.split(|ch: char| !ch.is_alphanumeric())
+            .filter(|token| !token.is_empty() && token.chars().count() >= self.min_token_len)
+            .map(str::to_lowercase)
+            .filter(|token| !self.is_stopword(token))
+            .map(|token| if self.stem { stem(&token) } else { token })
+            .collect()
+    }
+
+    pub fn token_set(&self, text: &str) -> HashSet<String> {
+        self.tokens(text).into_iter().collect()
+    }
+
+    /// Space-joined runs of `n` consecutive tokens. Text with fewer than `n`
+    /// tokens has no n-grams.
+    pub fn ngrams(&self, text: &str, n: usize) -> Vec<String> {
+        if n == 0 {
+            return Vec::new();
+        }
+        self.tokens(text)
+            .windows(n)
+            .map(|window| window.join(" "))
+            .collect()
+    }
+
+    /// Hashes of the text's n-grams. The hash is stable across runs and
+    /// platforms, so fingerprints can be stored and compared later.
+    pub fn fingerprint(&self, text: &str, n: usize) -> BTreeSet<u64> {
+        self.ngrams(text, n)
+            .iter()
+            .map(|ngram| fnv1a(ngram.as_bytes()))
+            .collect()
+    }
+
+    fn is_stopword(&self, token: &str) -> bool {
+        (self.english_stopwords
+            && ENGLISH_STOPWORDS.contains(&token)
+            && !self
+                .kept_stopwords
+                .iter()
+                .any(|word| word.to_lowercase() == token))
+            || self
+                .extra_stopwords
+                .iter()
+                .any(|word| word.to_lowercase() == token)
+    }
+}
+
+/// Install the normalizer used by [`tokenize`], [`token_set`], and
+/// [`TextNormalizer::configured`] for the rest of the process. Only the first
+/// call takes effect; later calls return their normalizer back.
+pub fn set_configured_normalizer(normalizer: TextNormalizer) -> Result<(), TextNormalizer> {
+    CONFIGURED.set(normalizer)
+}
+
+/// Tokens of `text` under the configured normalizer.
+pub fn tokenize(text: &str) -> Vec<String> {
+    TextNormalizer::configured().tokens(text)
+}
+
+/// Token set of `text` under the configured normalizer.
+pub fn token_set(text: &str) -> HashSet<String> {
+    TextNormalizer::configured().token_set(text)
+}
+
+/// Dice coefficient of the character trigrams of `left` and `right`, from 0
+/// to 1. Words are lowercased and padded with a space on each side, so short
+/// or run-together words such as "OOM" and "OOMKilled" still share trigrams
+/// when they share no token.
+pub fn char_trigram_similarity(left: &str, right: &str) -> f64 {
+    let left = char_trigrams(left);
+    let right = char_trigrams(right);
+    if left.is_empty() || right.is_empty() {
+        return 0.0;
+    }
+    let shared = left.intersection(&right).count() as f64;
+    2.0 * shared / (left.len() + right.len()) as f64
+}
+
+fn char_trigrams(text: &str) -> HashSet<[char; 3]> {
+    let words = text
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    if words.is_empty() {
+        return HashSet::new();
+    }
+    let chars = format!(" {} ", words.join(" ")).chars().collect::<Vec<_>>();
+    chars
+        .windows(3)
+        .map(|window| [window[0], window[1], window[2]])
+        .collect()
+}
+
+/// A light suffix stripper for lowercase tokens. Plural, past-tense, gerund,
+/// and adverb endings are removed, then a trailing `e`, so "rename",
+/// "renamed", and "renames" all become "renam".
+pub fn stem(token: &str) -> String {
+    let mut stem = strip_inflection(token);
+    if stem.ends_with('e') && stem.chars().count() > MIN_STEM_LEN {
+        stem.pop();
+    }
+    stem
+}
+
+fn strip_inflection(token: &str) -> String {
+    if let Some(base) = token.strip_suffix("ies")
+        && long_enough(base)
+    {
+        return format!("{base}y");
+    }
+    for suffix in ["ing", "ed"] {
+        if let Some(base) = token.strip_suffix(suffix)
+            && long_enough(base)
+            && base.contains(VOWELS)
+        {
+            return undouble(base).to_string();
+        }
+    }
+    for suffix in ["ly", "es"] {
+        if let Some(base) = token.strip_suffix(suffix)
+            && long_enough(base)
+        {
+            return base.to_string();
+        }
+    }
+    if let Some(base) = token.strip_suffix('s')
+        && long_enough(base)
+        && !base.ends_with(['s', 'u', 'i'])
+    {
+        return base.to_string();
+    }
+    token.to_string()
+}
+
+fn long_enough(stem: &str) -> bool {
+    stem.chars().count() >= MIN_STEM_LEN
+}
+
+/// Drop one of a doubled final consonant left behind by `-ing`/`-ed`
+/// ("running" -> "runn" -> "run"). Doubled `l`, `s`, and `z` are kept.
+fn undouble(base: &str) -> &str {
+    let mut chars = base.chars().rev();
+    match (chars.next(), chars.next()) {
+        (Some(last), Some(previous))
+            if last == previous
+                && last.is_alphabetic()
+                && !VOWELS.contains(&last)
+                && !matches!(last, 'l' | 's' | 'z') =>
+        {
+            &base[..base.len() - last.len_utf8()]
+        }
+        _ => base,
+    }
+}
+
+/// 64-bit FNV-1a.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn default_tokens_are_lowercased_filtered_and_stemmed() {
+        assert_eq!(
+            tokenize("Renamed the parser modules, then RUNNING tests"),
+            vec!["renam", "parser", "modul", "run", "test"]
+        );
+        assert_eq!(
+            [
+                "rename", "renamed", "renames", "queries", "query", "passed", "status"
+            ]
+            .into_iter()
+            .map(stem)
+            .collect::<Vec<_>>(),
+            vec![
+                "renam", "renam", "renam", "query", "query", "pass", "status"
+            ]
+        );
+    }
+
+    #[test]
+    fn stopwords_and_stemming_are_configurable() {
+        let normalizer = TextNormalizer {
+            stem: false,
+            english_stopwords: false,
+            extra_stopwords: vec!["Parser".to_string()],
+            kept_stopwords: Vec::new(),
+            min_token_len: 1,
+        };
+        let keeping = TextNormalizer {
+            extra_stopwords: vec!["make".to_string()],
+            kept_stopwords: vec!["How".to_string()],
+            ..TextNormalizer::default()
+        };
+
+        assert_eq!(
+            (
+                normalizer.tokens("the Parser, a modules"),
+                keeping.tokens("how to make the modules"),
+            ),
+            (
+                vec!["the".to_string(), "a".to_string(), "modules".to_string()],
+                vec!["how".to_string(), "modul".to_string()],
+            )
+        );
+    }
+
+    #[test]
+    fn fingerprints_ignore_case_punctuation_and_stopwords() {
+        let normalizer = TextNormalizer::default();
+
+        assert_eq!(
+            normalizer.ngrams("disk is full again", 2),
+            vec!["disk full", "full again"]
+        );
+        assert_eq!(
+            normalizer.fingerprint("Disk is FULL again", 2),
+            normalizer.fingerprint("disk full, again", 2)
+        );
+        assert_eq!(normalizer.fingerprint("disk", 2), BTreeSet::new());
+    }
+
+    #[test]
+    fn char_trigrams_match_short_and_run_together_words() {
+        let rounded = |left: &str, right: &str| {
+            (char_trigram_similarity(left, right) * 100.0).round() / 100.0
+        };
+
+        assert_eq!(
+            [
+                rounded("OOM", "oom"),
+                rounded("OOM", "OOM kill"),
+                rounded("OOM", "OOMKilled"),
+                rounded("OOM", "disk full"),
+                rounded("OOM", "  "),
+            ],
+            [1.0, 0.55, 0.33, 0.0, 0.0]
+        );
+    }
+}